@@ -94,12 +94,11 @@ impl FilterOptions {
         let omitted_set: HashSet<&str> = self.omit_edges_into.iter().map(|s| s.as_str()).collect();
         let omitted_package_ids: HashSet<_> = names_to_ids(pkg_graph, &omitted_set).collect();
 
-        let platform = if let Some(ref target) = self.target {
-            // The features are unknown.
-            Some(Platform::new(target, TargetFeatures::Unknown).unwrap())
-        } else {
-            None
-        };
+        // The features are unknown.
+        let platform = self
+            .target
+            .as_ref()
+            .map(|target| Platform::new_heuristic(target, TargetFeatures::Unknown));
 
         move |_, link| {
             let (from, to) = link.endpoints();
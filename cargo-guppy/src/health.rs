@@ -0,0 +1,148 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Dependency freshness reports.
+//!
+//! `cargo guppy health` doesn't access the network. Information from the registry index and
+//! other sources is read from a JSON file passed in with `--signals`, mapping package names to
+//! objects with these optional fields:
+//!
+//! ```json
+//! {
+//!     "serde": {
+//!         "latest-version": "1.0.130",
+//!         "yanked": false,
+//!         "rust-version": "1.31",
+//!         "archived": false,
+//!         "release-dates": [1632096000]
+//!     }
+//! }
+//! ```
+//!
+//! Release dates are in seconds since the Unix epoch. Without a signals file, only duplicate
+//! packages are reported.
+
+use anyhow::{anyhow, bail};
+use guppy::graph::health::FreshnessPolicy;
+use guppy::graph::maintenance::{MaintenanceEnricher, MaintenanceSignals};
+use guppy::Version;
+use guppy_cmdlib::{CargoMetadataOptions, ReportOptions};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use structopt::StructOpt;
+
+const DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, StructOpt)]
+pub struct HealthOptions {
+    #[structopt(long)]
+    /// Read registry and maintenance information from this JSON file
+    signals: Option<PathBuf>,
+
+    #[structopt(long, default_value = "1095")]
+    /// Consider packages unreleased for more than this many days unmaintained
+    max_age_days: u64,
+
+    #[structopt(long)]
+    /// Exit with an error if any workspace member scores lower than this
+    min_score: Option<u32>,
+
+    #[structopt(flatten)]
+    report_opts: ReportOptions,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_health(opts: &HealthOptions) -> Result<(), anyhow::Error> {
+    let mut command = opts.metadata_opts.make_command();
+    let mut pkg_graph = command.build_graph()?;
+
+    if let Some(path) = &opts.signals {
+        let signals = read_signals(path)
+            .map_err(|err| anyhow!("error reading signals from {}: {}", path.display(), err))?;
+        pkg_graph.enrich_maintenance(&MaintenanceEnricher::new().add_source(&signals))?;
+    }
+
+    let policy = FreshnessPolicy::new(
+        Duration::from_secs(opts.max_age_days * DAY),
+        SystemTime::now(),
+    );
+    let report = pkg_graph.health_report(&policy);
+    print!("{}", opts.report_opts.render(&report));
+
+    if let Some(min_score) = opts.min_score {
+        let failing: Vec<_> = report
+            .members()
+            .iter()
+            .filter(|member| member.score() < min_score)
+            .map(|member| member.member().name())
+            .collect();
+        if !failing.is_empty() {
+            bail!(
+                "workspace members scored lower than {}: {}",
+                min_score,
+                failing.join(", ")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct SignalsEntry {
+    latest_version: Option<String>,
+    yanked: Option<bool>,
+    rust_version: Option<String>,
+    archived: Option<bool>,
+    #[serde(default)]
+    release_dates: Vec<u64>,
+}
+
+fn read_signals(path: &Path) -> Result<BTreeMap<String, MaintenanceSignals>, anyhow::Error> {
+    let contents = fs::read_to_string(path)?;
+    let entries: BTreeMap<String, SignalsEntry> = serde_json::from_str(&contents)?;
+
+    entries
+        .into_iter()
+        .map(|(name, entry)| {
+            let mut signals = MaintenanceSignals::new().with_release_dates(
+                entry
+                    .release_dates
+                    .iter()
+                    .map(|secs| UNIX_EPOCH + Duration::from_secs(*secs)),
+            );
+            if let Some(version) = &entry.latest_version {
+                signals = signals.with_latest_version(parse_version(&name, version)?);
+            }
+            if let Some(yanked) = entry.yanked {
+                signals = signals.with_yanked(yanked);
+            }
+            if let Some(version) = &entry.rust_version {
+                signals = signals.with_rust_version(parse_version(&name, version)?);
+            }
+            if let Some(archived) = entry.archived {
+                signals = signals.with_archived(archived);
+            }
+            Ok((name, signals))
+        })
+        .collect()
+}
+
+/// Parses a version, allowing the patch (and minor) components to be omitted as in
+/// `rust-version`.
+fn parse_version(name: &str, version: &str) -> Result<Version, anyhow::Error> {
+    let components = version.split('.').count();
+    let padded = match components {
+        1 => format!("{}.0.0", version),
+        2 => format!("{}.0", version),
+        _ => version.to_string(),
+    };
+    Version::parse(&padded)
+        .map_err(|err| anyhow!("{}: invalid version '{}': {}", name, version, err))
+}
@@ -0,0 +1,146 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Dependency graph metrics over a range of commits.
+//!
+//! `cargo guppy history v1.0..main` builds a graph for each commit in the range without checking
+//! it out, and prints a time series of metrics as JSON or CSV. The workspace is the one containing
+//! the current directory, or `--manifest-path`.
+//!
+//! In CSV output, closure sizes get a `closure:<member>` column per workspace member and license
+//! counts a `license:<expression>` column per license expression, across every commit in the
+//! range. Cells are empty for commits where a member or license doesn't appear.
+
+use anyhow::anyhow;
+use clap::arg_enum;
+use guppy::graph::history::HistoryEntry;
+use guppy::graph::PackageGraph;
+use guppy_cmdlib::CargoMetadataOptions;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+arg_enum! {
+    #[derive(Debug)]
+    pub enum HistoryFormat {
+        Json,
+        Csv,
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct HistoryOptions {
+    /// Range of commits to analyze, as understood by `git log` (e.g. `v1.0..main`)
+    range: String,
+
+    #[structopt(long, possible_values = &HistoryFormat::variants(), case_insensitive = true, default_value = "json")]
+    /// Output format
+    format: HistoryFormat,
+
+    #[structopt(long, short = "o")]
+    /// Write the output to this file instead of standard output
+    output: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_history(opts: &HistoryOptions) -> Result<(), anyhow::Error> {
+    let manifest_path = opts.metadata_opts.abs_manifest_path()?;
+    let workspace_dir = manifest_path
+        .parent()
+        .ok_or_else(|| anyhow!("manifest path {} has no parent", manifest_path.display()))?;
+    let mut command = opts.metadata_opts.make_command();
+    let entries = PackageGraph::history(workspace_dir, &opts.range, &mut command)?;
+
+    for entry in &entries {
+        if let Some(error) = entry.error() {
+            eprintln!("warning: skipping {}: {}", entry.commit(), error);
+        }
+    }
+
+    let output = match opts.format {
+        HistoryFormat::Json => serde_json::to_string_pretty(&entries)? + "\n",
+        HistoryFormat::Csv => to_csv(&entries),
+    };
+    match &opts.output {
+        Some(path) => fs::write(path, output)
+            .map_err(|err| anyhow!("error writing history to {}: {}", path.display(), err))?,
+        None => print!("{}", output),
+    }
+
+    Ok(())
+}
+
+fn to_csv(entries: &[HistoryEntry]) -> String {
+    let metrics = entries.iter().filter_map(|entry| entry.metrics());
+    let members: BTreeSet<_> = metrics
+        .clone()
+        .flat_map(|metrics| metrics.closure_sizes().keys())
+        .collect();
+    let licenses: BTreeSet<_> = metrics
+        .flat_map(|metrics| metrics.licenses().keys())
+        .collect();
+
+    let mut header = vec![
+        "commit".to_string(),
+        "time".to_string(),
+        "packages".to_string(),
+        "workspace-members".to_string(),
+        "duplicates".to_string(),
+    ];
+    header.extend(members.iter().map(|member| format!("closure:{}", member)));
+    header.extend(
+        licenses
+            .iter()
+            .map(|license| format!("license:{}", license)),
+    );
+    header.push("error".to_string());
+
+    let mut rows = vec![header];
+    for entry in entries {
+        let mut row = vec![entry.commit().to_string(), entry.time().to_string()];
+        match entry.metrics() {
+            Some(metrics) => {
+                row.push(metrics.package_count().to_string());
+                row.push(metrics.workspace_member_count().to_string());
+                row.push(metrics.duplicate_count().to_string());
+                row.extend(members.iter().map(|member| {
+                    metrics
+                        .closure_sizes()
+                        .get(*member)
+                        .map_or_else(String::new, |size| size.to_string())
+                }));
+                row.extend(licenses.iter().map(|license| {
+                    metrics
+                        .licenses()
+                        .get(*license)
+                        .map_or_else(String::new, |count| count.to_string())
+                }));
+            }
+            None => row.resize(
+                row.len() + 3 + members.len() + licenses.len(),
+                String::new(),
+            ),
+        }
+        row.push(entry.error().unwrap_or_default().to_string());
+        rows.push(row);
+    }
+
+    rows.iter()
+        .map(|row| {
+            let fields: Vec<_> = row.iter().map(|field| csv_field(field)).collect();
+            fields.join(",") + "\n"
+        })
+        .collect()
+}
+
+/// Quotes a CSV field if necessary.
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -3,22 +3,38 @@
 
 mod core;
 mod diff;
+mod health;
+mod history;
+mod minimal_versions;
+mod sarif;
+mod serve;
 
 pub use crate::core::*;
-
-use guppy::graph::cargo::CargoOptions;
-use guppy::graph::DependencyDirection;
+pub use crate::health::*;
+pub use crate::history::*;
+pub use crate::minimal_versions::*;
+pub use crate::sarif::*;
+pub use crate::serve::*;
+
+use guppy::graph::cargo::{CargoOptions, ResolverMigration};
+use guppy::graph::ownership::Ownership;
+use guppy::graph::{DependencyDirection, PackageQuery, PackageResolver, VersionComparison};
 use guppy::{
-    graph::{DotWrite, PackageDotVisitor, PackageGraph, PackageLink, PackageMetadata},
+    graph::{
+        DotWrite, NdjsonWriter, PackageDotVisitor, PackageGraph, PackageLink, PackageMetadata,
+    },
     PackageId,
 };
-use guppy_cmdlib::{triple_to_platform, CargoMetadataOptions, PackagesAndFeatures};
+use guppy_cmdlib::{
+    triple_to_platform, CargoMetadataOptions, PackagesAndFeatures, ReportOptions, TeamOptions,
+};
 use std::cmp;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fs;
-use std::io::Write;
+use std::io::{self, Write};
 use std::iter;
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 pub fn cmd_diff(json: bool, old: &str, new: &str) -> Result<(), anyhow::Error> {
@@ -42,11 +58,159 @@ pub fn cmd_diff(json: bool, old: &str, new: &str) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[derive(Debug, StructOpt)]
+pub struct AnonymizeOptions {
+    #[structopt(long, default_value = "0")]
+    /// Seed for generated names; the same seed always produces the same output
+    seed: u64,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_anonymize(opts: &AnonymizeOptions) -> Result<(), anyhow::Error> {
+    let mut command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+
+    let metadata = pkg_graph.anonymize(opts.seed)?;
+    // Go through a JSON value so that keys are sorted and the output is stable.
+    let value = serde_json::to_value(&metadata)?;
+    println!("{}", serde_json::to_string_pretty(&value)?);
+
+    Ok(())
+}
+
+#[derive(Debug, StructOpt)]
+pub struct BuildScriptsOptions {
+    #[structopt(flatten)]
+    report_opts: ReportOptions,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_build_scripts(opts: &BuildScriptsOptions) -> Result<(), anyhow::Error> {
+    let mut command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+
+    print!(
+        "{}",
+        opts.report_opts.render(&pkg_graph.build_script_report()?)
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, StructOpt)]
+pub struct CheckBudgetsOptions {
+    #[structopt(flatten)]
+    report_opts: ReportOptions,
+
+    #[structopt(flatten)]
+    team_opts: TeamOptions,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_check_budgets(opts: &CheckBudgetsOptions) -> Result<(), anyhow::Error> {
+    let mut command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+
+    let report = pkg_graph.check_budgets()?;
+    let success = match opts.team_opts.load(&pkg_graph)? {
+        Some(teams) => {
+            let violations = report
+                .violations()
+                .iter()
+                .map(|violation| (violation.package(), violation));
+            let breakdown = opts
+                .team_opts
+                .breakdown(&teams, "Budget violations", violations)?;
+            print!("{}", opts.report_opts.render(&breakdown));
+            breakdown.item_count() == 0
+        }
+        None => {
+            print!("{}", opts.report_opts.render(&report));
+            report.is_success()
+        }
+    };
+    if !success {
+        anyhow::bail!("dependency budgets exceeded");
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, StructOpt)]
+pub struct CompareVersionsOptions {
+    #[structopt(long)]
+    /// Exit with an error if any dependency resolves to different versions
+    check: bool,
+
+    #[structopt(required = true, min_values = 2)]
+    /// Files containing `cargo metadata` output, one for each build to compare
+    metadata: Vec<String>,
+
+    #[structopt(flatten)]
+    report_opts: ReportOptions,
+}
+
+pub fn cmd_compare_versions(opts: &CompareVersionsOptions) -> Result<(), anyhow::Error> {
+    let graphs = opts
+        .metadata
+        .iter()
+        .map(|path| {
+            let json = fs::read_to_string(path)
+                .map_err(|err| anyhow::anyhow!("error reading {}: {}", path, err))?;
+            PackageGraph::from_json(&json)
+                .map_err(|err| anyhow::anyhow!("error parsing {}: {}", path, err))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let comparison =
+        VersionComparison::new(opts.metadata.iter().map(String::as_str).zip(graphs.iter()));
+    print!("{}", opts.report_opts.render(&comparison));
+
+    if opts.check && !comparison.is_consistent() {
+        anyhow::bail!("dependency versions are forked");
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DevWeightOptions {
+    #[structopt(flatten)]
+    report_opts: ReportOptions,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_dev_weight(opts: &DevWeightOptions) -> Result<(), anyhow::Error> {
+    let mut command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+
+    print!(
+        "{}",
+        opts.report_opts.render(&pkg_graph.dev_weight_report())
+    );
+
+    Ok(())
+}
+
 #[derive(Debug, StructOpt)]
 pub struct DupsOptions {
     #[structopt(flatten)]
     filter_opts: FilterOptions,
 
+    #[structopt(flatten)]
+    team_opts: TeamOptions,
+
+    #[structopt(flatten)]
+    report_opts: ReportOptions,
+
     #[structopt(flatten)]
     metadata_opts: CargoMetadataOptions,
 }
@@ -66,6 +230,32 @@ pub fn cmd_dups(opts: &DupsOptions) -> Result<(), anyhow::Error> {
         dupe_map.entry(package.name()).or_default().push(package);
     }
 
+    if let Some(teams) = opts.team_opts.load(&pkg_graph)? {
+        // Attribute each duplicated package to the workspace members that depend on it.
+        let mut items = vec![];
+        for (_, member) in pkg_graph.workspace().members() {
+            let member_query = pkg_graph.query_forward(iter::once(member.id()))?;
+            let resolver = opts.filter_opts.make_resolver(&pkg_graph);
+            for package in member_query
+                .resolve_with_fn(resolver)
+                .packages(DependencyDirection::Forward)
+            {
+                let is_dup = match dupe_map.get(package.name()) {
+                    Some(dupes) => dupes.len() > 1,
+                    None => false,
+                };
+                if is_dup {
+                    items.push((member, format!("{} {}", package.name(), package.version())));
+                }
+            }
+        }
+        let breakdown = opts
+            .team_opts
+            .breakdown(&teams, "Duplicate packages", items)?;
+        print!("{}", opts.report_opts.render(&breakdown));
+        return Ok(());
+    }
+
     for (name, dupes) in dupe_map {
         if dupes.len() <= 1 {
             continue;
@@ -79,6 +269,61 @@ pub fn cmd_dups(opts: &DupsOptions) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+#[derive(Debug, StructOpt)]
+pub struct PlatformCoverageOptions {
+    #[structopt(long = "platform", short = "p", number_of_values = 1, required = true)]
+    /// Platforms to check coverage for, as target triples or "current"
+    platforms: Vec<String>,
+
+    #[structopt(flatten)]
+    report_opts: ReportOptions,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_platform_coverage(opts: &PlatformCoverageOptions) -> Result<(), anyhow::Error> {
+    let platforms = opts
+        .platforms
+        .iter()
+        .map(|triple| {
+            triple_to_platform(Some(triple), || None)?
+                .ok_or_else(|| anyhow::anyhow!("platform '{}' is not supported here", triple))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+    print!(
+        "{}",
+        opts.report_opts
+            .render(&pkg_graph.platform_coverage(&platforms))
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, StructOpt)]
+pub struct RequirementsOptions {
+    #[structopt(flatten)]
+    report_opts: ReportOptions,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_requirements(opts: &RequirementsOptions) -> Result<(), anyhow::Error> {
+    let mut command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+
+    print!(
+        "{}",
+        opts.report_opts.render(&pkg_graph.requirement_report())
+    );
+
+    Ok(())
+}
+
 #[derive(Debug, StructOpt)]
 pub struct ResolveCargoOptions {
     #[structopt(flatten)]
@@ -145,6 +390,129 @@ pub fn cmd_resolve_cargo(opts: &ResolveCargoOptions) -> Result<(), anyhow::Error
     Ok(())
 }
 
+#[derive(Debug, StructOpt)]
+pub struct ResolverMigrationOptions {
+    #[structopt(flatten)]
+    pf: PackagesAndFeatures,
+
+    #[structopt(long = "include-dev")]
+    /// Include dev-dependencies of initial packages (default: false)
+    include_dev: bool,
+
+    #[structopt(long = "target-platform")]
+    /// Evaluate against target platform, "current" or "any" (default: any)
+    target_platform: Option<String>,
+
+    #[structopt(long = "host-platform")]
+    /// Evaluate against host platform, "current" or "any" (default: any)
+    host_platform: Option<String>,
+
+    #[structopt(flatten)]
+    report_opts: ReportOptions,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct FeatureImpactOptions {
+    #[structopt(long = "include-dev")]
+    /// Include dev-dependencies of workspace members (default: false)
+    include_dev: bool,
+
+    #[structopt(long = "target-platform")]
+    /// Evaluate against target platform, "current" or "any" (default: any)
+    target_platform: Option<String>,
+
+    #[structopt(long = "host-platform")]
+    /// Evaluate against host platform, "current" or "any" (default: any)
+    host_platform: Option<String>,
+
+    #[structopt(flatten)]
+    report_opts: ReportOptions,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_feature_impact(opts: &FeatureImpactOptions) -> Result<(), anyhow::Error> {
+    let target_platform = triple_to_platform(opts.target_platform.as_deref(), || None)?;
+    let host_platform = triple_to_platform(opts.host_platform.as_deref(), || None)?;
+    let cargo_opts = CargoOptions::new()
+        .with_dev_deps(opts.include_dev)
+        .with_target_platform(target_platform.as_ref())
+        .with_host_platform(host_platform.as_ref());
+
+    let mut command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+    let matrix = pkg_graph.feature_impact_matrix(&cargo_opts)?;
+    print!("{}", opts.report_opts.render(&matrix));
+
+    Ok(())
+}
+
+pub fn cmd_resolver_migration(opts: &ResolverMigrationOptions) -> Result<(), anyhow::Error> {
+    let target_platform = triple_to_platform(opts.target_platform.as_deref(), || None)?;
+    let host_platform = triple_to_platform(opts.host_platform.as_deref(), || None)?;
+    let cargo_opts = CargoOptions::new()
+        .with_dev_deps(opts.include_dev)
+        .with_target_platform(target_platform.as_ref())
+        .with_host_platform(host_platform.as_ref());
+
+    let mut command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+    let query = opts.pf.make_feature_query(&pkg_graph)?;
+    let migration = ResolverMigration::new(query, &cargo_opts)?;
+    print!("{}", opts.report_opts.render(&migration));
+
+    Ok(())
+}
+
+#[derive(Debug, StructOpt)]
+pub struct RestructureOptions {
+    #[structopt(flatten)]
+    report_opts: ReportOptions,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_restructure(opts: &RestructureOptions) -> Result<(), anyhow::Error> {
+    let mut command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+
+    print!(
+        "{}",
+        opts.report_opts.render(&pkg_graph.restructure_report())
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, StructOpt)]
+pub struct TeamsOptions {
+    /// Ownership file mapping workspace members to teams (TOML or CODEOWNERS format)
+    #[structopt(long = "owners", value_name = "PATH")]
+    owners: PathBuf,
+
+    #[structopt(flatten)]
+    report_opts: ReportOptions,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_teams(opts: &TeamsOptions) -> Result<(), anyhow::Error> {
+    let mut command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+
+    let ownership = Ownership::from_path(&opts.owners)?;
+    let teams = pkg_graph.team_ownership(&ownership)?;
+    print!("{}", opts.report_opts.render(&teams));
+
+    Ok(())
+}
+
 struct NameVisitor;
 
 impl PackageDotVisitor for NameVisitor {
@@ -170,6 +538,18 @@ pub struct CmdSelectOptions {
     /// Save selection graph in .dot format
     output_dot: Option<String>,
 
+    #[structopt(long)]
+    /// Output selected packages as newline-delimited JSON rather than package IDs
+    ndjson: bool,
+
+    #[structopt(long)]
+    /// Report dependency cycles followed during the selection to stderr
+    audit_cycles: bool,
+
+    #[structopt(long)]
+    /// Save the provenance of the selection, in JSON format
+    provenance: Option<String>,
+
     #[structopt(flatten)]
     query_opts: QueryOptions,
 
@@ -183,8 +563,40 @@ pub fn cmd_select(options: &CmdSelectOptions) -> Result<(), anyhow::Error> {
 
     let query = options.query_opts.apply(&pkg_graph)?;
     let resolver = options.filter_opts.make_resolver(&pkg_graph);
-    let package_set = query.resolve_with_fn(resolver);
+    let package_set = if options.audit_cycles {
+        let audit = query.resolve_with_cycle_audit(FnResolver(resolver));
+        for cycle in audit.cycles() {
+            let members: Vec<_> = cycle
+                .members()
+                .iter()
+                .map(|package| package.name())
+                .collect();
+            let dev_links: Vec<_> = cycle
+                .dev_links()
+                .map(|link| format!("{} -> {}", link.from().name(), link.to().name()))
+                .collect();
+            eprintln!(
+                "cycle: {} (dev links: {}){}",
+                members.join(", "),
+                dev_links.join(", "),
+                if cycle.includes_initial() {
+                    " [includes query roots]"
+                } else {
+                    ""
+                }
+            );
+        }
+        audit.into_set()
+    } else {
+        query.resolve_with_fn(resolver)
+    };
 
+    let stdout = io::stdout();
+    let mut ndjson = if options.ndjson {
+        Some(NdjsonWriter::new(io::BufWriter::new(stdout.lock())))
+    } else {
+        None
+    };
     for package_id in package_set.package_ids(options.output_direction) {
         let package = pkg_graph.metadata(package_id).unwrap();
         let in_workspace = package.in_workspace();
@@ -197,10 +609,25 @@ pub fn cmd_select(options: &CmdSelectOptions) -> Result<(), anyhow::Error> {
             Kind::DirectThirdParty => direct_dep,
             Kind::ThirdParty => !in_workspace,
         };
-        if show_package {
-            println!("{}", package_id);
+        if !show_package {
+            continue;
+        }
+        match &mut ndjson {
+            Some(writer) => writer.write_package(package)?,
+            None => println!("{}", package_id),
         }
     }
+    if let Some(writer) = ndjson {
+        writer.finish()?;
+    }
+
+    if let Some(ref output_file) = options.provenance {
+        let provenance = package_set
+            .provenance()
+            .with_tool(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        let f = fs::File::create(output_file)?;
+        serde_json::to_writer_pretty(f, &provenance)?;
+    }
 
     if let Some(ref output_file) = options.output_dot {
         let dot = package_set.display_dot(NameVisitor);
@@ -211,6 +638,18 @@ pub fn cmd_select(options: &CmdSelectOptions) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Adapts a resolver function into a `PackageResolver`.
+struct FnResolver<F>(F);
+
+impl<'g, F> PackageResolver<'g> for FnResolver<F>
+where
+    F: FnMut(&PackageQuery<'g>, PackageLink<'g>) -> bool,
+{
+    fn accept(&mut self, query: &PackageQuery<'g>, link: PackageLink<'g>) -> bool {
+        (self.0)(query, link)
+    }
+}
+
 #[derive(Debug, StructOpt)]
 pub struct SubtreeSizeOptions {
     #[structopt(flatten)]
@@ -1,7 +1,13 @@
 // Copyright (c) The cargo-guppy Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use cargo_guppy::{CmdSelectOptions, DupsOptions, ResolveCargoOptions, SubtreeSizeOptions};
+use cargo_guppy::{
+    AnonymizeOptions, BuildScriptsOptions, CheckBudgetsOptions, CmdSelectOptions,
+    CompareVersionsOptions, DevWeightOptions, DupsOptions, FeatureImpactOptions, HealthOptions,
+    HistoryOptions, MinimalVersionsOptions, PlatformCoverageOptions, RequirementsOptions,
+    ResolveCargoOptions, ResolverMigrationOptions, RestructureOptions, SarifOptions, ServeOptions,
+    SubtreeSizeOptions, TeamsOptions,
+};
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -13,6 +19,21 @@ struct Args {
 
 #[derive(Debug, StructOpt)]
 enum Command {
+    #[structopt(name = "anonymize")]
+    /// Print cargo metadata output with workspace names, paths and private sources scrubbed
+    Anonymize(AnonymizeOptions),
+    #[structopt(name = "build-scripts")]
+    /// Report build scripts, their declared inputs and the packages they can affect
+    BuildScripts(BuildScriptsOptions),
+    #[structopt(name = "check-budgets")]
+    /// Check workspace members against the dependency budgets they declare
+    CheckBudgets(CheckBudgetsOptions),
+    #[structopt(name = "compare-versions")]
+    /// Compare resolved dependency versions across several cargo metadata outputs
+    CompareVersions(CompareVersionsOptions),
+    #[structopt(name = "dev-weight")]
+    /// Report how much of the graph exists only for dev-dependencies
+    DevWeight(DevWeightOptions),
     #[structopt(name = "diff")]
     /// Perform a diff of two Cargo.lock files
     Diff {
@@ -24,15 +45,48 @@ enum Command {
     #[structopt(name = "dups")]
     /// Print the number of duplicate packages
     Duplicates(DupsOptions),
+    #[structopt(name = "feature-impact")]
+    /// Report the third-party packages and features each workspace feature adds when enabled
+    FeatureImpact(FeatureImpactOptions),
+    #[structopt(name = "health")]
+    /// Report a freshness score for each workspace member's dependencies
+    Health(HealthOptions),
+    #[structopt(name = "history")]
+    /// Print dependency graph metrics for each commit in a range, as JSON or CSV
+    History(HistoryOptions),
+    #[structopt(name = "minimal-versions")]
+    /// Check that workspace requirements still work when resolved to their minimal versions
+    MinimalVersions(MinimalVersionsOptions),
+    #[structopt(name = "platform-coverage")]
+    /// Report which platforms each platform-specific dependency applies to
+    PlatformCoverage(PlatformCoverageOptions),
+    #[structopt(name = "requirements")]
+    /// Report exact pins, narrow ranges and requirements that prevent unifying duplicates
+    Requirements(RequirementsOptions),
     #[structopt(name = "resolve-cargo")]
     /// Return packages and features that would be built by Cargo
     ResolveCargo(ResolveCargoOptions),
+    #[structopt(name = "resolver-migration")]
+    /// Report features that change when switching to version 2 of the feature resolver
+    ResolverMigration(ResolverMigrationOptions),
+    #[structopt(name = "restructure")]
+    /// Suggest workspace members to merge or split, based on how they're used
+    Restructure(RestructureOptions),
+    #[structopt(name = "sarif")]
+    /// Report budget, duplicate version and MSRV findings in SARIF format
+    Sarif(SarifOptions),
     #[structopt(name = "select")]
     /// Select packages and their transitive dependencies
     Select(CmdSelectOptions),
+    #[structopt(name = "serve")]
+    /// Keep the graph in memory and answer JSON-RPC queries
+    Serve(ServeOptions),
     #[structopt(name = "subtree-size")]
     /// Print a list of dependencies along with their unique subtree size
     SubtreeSize(SubtreeSizeOptions),
+    #[structopt(name = "teams")]
+    /// List the workspace members owned by each team in an ownership file
+    Teams(TeamsOptions),
 }
 
 // When invoked as a cargo subcommand, cargo passes too many arguments so we need to filter out
@@ -51,11 +105,27 @@ fn main() {
     let args = Args::from_iter(args());
 
     let result = match args.cmd {
+        Command::Anonymize(ref options) => cargo_guppy::cmd_anonymize(options),
+        Command::BuildScripts(ref options) => cargo_guppy::cmd_build_scripts(options),
+        Command::CheckBudgets(ref options) => cargo_guppy::cmd_check_budgets(options),
+        Command::CompareVersions(ref options) => cargo_guppy::cmd_compare_versions(options),
+        Command::DevWeight(ref options) => cargo_guppy::cmd_dev_weight(options),
         Command::Diff { json, old, new } => cargo_guppy::cmd_diff(json, &old, &new),
         Command::Duplicates(ref options) => cargo_guppy::cmd_dups(options),
+        Command::FeatureImpact(ref options) => cargo_guppy::cmd_feature_impact(options),
+        Command::Health(ref options) => cargo_guppy::cmd_health(options),
+        Command::History(ref options) => cargo_guppy::cmd_history(options),
+        Command::MinimalVersions(ref options) => cargo_guppy::cmd_minimal_versions(options),
+        Command::PlatformCoverage(ref options) => cargo_guppy::cmd_platform_coverage(options),
+        Command::Requirements(ref options) => cargo_guppy::cmd_requirements(options),
         Command::ResolveCargo(ref options) => cargo_guppy::cmd_resolve_cargo(options),
+        Command::ResolverMigration(ref options) => cargo_guppy::cmd_resolver_migration(options),
+        Command::Restructure(ref options) => cargo_guppy::cmd_restructure(options),
+        Command::Sarif(ref options) => cargo_guppy::cmd_sarif(options),
         Command::Select(ref options) => cargo_guppy::cmd_select(options),
+        Command::Serve(ref options) => cargo_guppy::cmd_serve(options),
         Command::SubtreeSize(ref options) => cargo_guppy::cmd_subtree_size(options),
+        Command::Teams(ref options) => cargo_guppy::cmd_teams(options),
     };
 
     match result {
@@ -0,0 +1,94 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Minimal-versions checks.
+//!
+//! `cargo guppy minimal-versions` doesn't access the network. Registry index data is read from a
+//! JSON file passed in with `--index`, mapping crate names to lists of published versions:
+//!
+//! ```json
+//! {
+//!     "serde": [
+//!         { "version": "1.0.0", "features": ["std"] },
+//!         { "version": "1.0.10", "features": ["std", "derive"], "yanked": false }
+//!     ]
+//! }
+//! ```
+//!
+//! Features should include optional dependencies. Crates missing from the file aren't checked.
+
+use anyhow::{anyhow, bail};
+use guppy::graph::minimal_versions::IndexEntry;
+use guppy::Version;
+use guppy_cmdlib::{CargoMetadataOptions, ReportOptions};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct MinimalVersionsOptions {
+    #[structopt(long)]
+    /// Read registry index data from this JSON file
+    index: PathBuf,
+
+    #[structopt(flatten)]
+    report_opts: ReportOptions,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_minimal_versions(opts: &MinimalVersionsOptions) -> Result<(), anyhow::Error> {
+    let mut command = opts.metadata_opts.make_command();
+    let pkg_graph = command.build_graph()?;
+
+    let index = read_index(&opts.index).map_err(|err| {
+        anyhow!(
+            "error reading index data from {}: {}",
+            opts.index.display(),
+            err
+        )
+    })?;
+    let report = pkg_graph.minimal_versions_check(&index)?;
+    print!("{}", opts.report_opts.render(&report));
+
+    if !report.is_ok() {
+        bail!("requirements would break with minimal versions");
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IndexFileEntry {
+    version: String,
+    #[serde(default)]
+    features: Vec<String>,
+    #[serde(default)]
+    yanked: bool,
+}
+
+fn read_index(path: &Path) -> Result<BTreeMap<String, Vec<IndexEntry>>, anyhow::Error> {
+    let contents = fs::read_to_string(path)?;
+    let crates: BTreeMap<String, Vec<IndexFileEntry>> = serde_json::from_str(&contents)?;
+
+    crates
+        .into_iter()
+        .map(|(name, entries)| {
+            let entries = entries
+                .into_iter()
+                .map(|entry| {
+                    let version = Version::parse(&entry.version).map_err(|err| {
+                        anyhow!("{}: invalid version '{}': {}", name, entry.version, err)
+                    })?;
+                    Ok(IndexEntry::new(version)
+                        .with_features(entry.features)
+                        .with_yanked(entry.yanked))
+                })
+                .collect::<Result<_, anyhow::Error>>()?;
+            Ok((name, entries))
+        })
+        .collect()
+}
@@ -0,0 +1,346 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! SARIF output for findings.
+//!
+//! `cargo guppy sarif` collects dependency budget violations, duplicate versions, MSRV
+//! violations and platform-specific sections that never or always apply, or that have likely
+//! misspelled values, into a [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+//! log, which GitHub code scanning and other tools can use to annotate the relevant `Cargo.toml`
+//! files. Paths are relative to the workspace root. Where possible, results point at the line in
+//! the manifest that declares the offending dependency or key.
+
+use anyhow::anyhow;
+use guppy::graph::budget::BudgetViolation;
+use guppy::graph::{DependencyDirection, ManifestSpan, PackageGraph, PackageMetadata};
+use guppy::{SpecCoverage, TargetSpec};
+use guppy_cmdlib::CargoMetadataOptions;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/facebookincubator/cargo-guppy";
+
+/// The rules findings are reported under, along with their descriptions.
+const RULES: &[(&str, &str)] = &[
+    (
+        "budget/direct-deps",
+        "A workspace member has more direct dependencies than its budget allows",
+    ),
+    (
+        "budget/transitive-deps",
+        "A workspace member has more transitive dependencies than its budget allows",
+    ),
+    (
+        "budget/banned-category",
+        "A workspace member depends on a package in a banned category",
+    ),
+    (
+        "duplicate-versions",
+        "Several versions of a package are in the dependency graph",
+    ),
+    (
+        "msrv",
+        "A workspace member's rust-version is newer than its pinned toolchain",
+    ),
+    (
+        "target-spec/never",
+        "A platform-specific dependency section doesn't apply to any known platform",
+    ),
+    (
+        "target-spec/always",
+        "A platform-specific dependency section applies to every known platform",
+    ),
+    (
+        "target-spec/unknown-value",
+        "A platform-specific dependency section has a value no known platform has",
+    ),
+];
+
+#[derive(Debug, StructOpt)]
+pub struct SarifOptions {
+    #[structopt(long, short = "o")]
+    /// Write the SARIF log to this file instead of standard output
+    output: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_sarif(opts: &SarifOptions) -> Result<(), anyhow::Error> {
+    let mut command = opts.metadata_opts.make_command();
+    // MSRV violations are checked against pinned toolchains.
+    let pkg_graph = command.toolchain(true).build_graph()?;
+
+    let log = sarif_log(&pkg_graph)?;
+    let output = serde_json::to_string_pretty(&log)?;
+    match &opts.output {
+        Some(path) => fs::write(path, output + "\n")
+            .map_err(|err| anyhow!("error writing SARIF log to {}: {}", path.display(), err))?,
+        None => println!("{}", output),
+    }
+
+    Ok(())
+}
+
+fn sarif_log(pkg_graph: &PackageGraph) -> Result<Value, anyhow::Error> {
+    let root = pkg_graph.workspace().root();
+    let mut results = vec![];
+
+    for violation in pkg_graph.check_budgets()?.violations() {
+        let package = violation.package();
+        let (rule_id, locations) = match violation {
+            BudgetViolation::DirectDeps { .. } => {
+                ("budget/direct-deps", vec![manifest_location(package, root)])
+            }
+            BudgetViolation::TransitiveDeps { .. } => (
+                "budget/transitive-deps",
+                vec![manifest_location(package, root)],
+            ),
+            BudgetViolation::BannedCategory { path, .. } => {
+                // The second package in the path is the direct dependency that leads to the
+                // banned category.
+                let direct_dep = path.get(1).map(|dep| dep.name());
+                let mut locations =
+                    declaration_locations(package, root, |name| Some(name) == direct_dep);
+                if locations.is_empty() {
+                    locations.push(manifest_location(package, root));
+                }
+                ("budget/banned-category", locations)
+            }
+        };
+        results.push(result(rule_id, "error", violation.to_string(), locations));
+    }
+
+    for (name, versions) in duplicates(pkg_graph) {
+        let versions: Vec<_> = versions
+            .iter()
+            .map(|package| package.version().to_string())
+            .collect();
+        // Point at the declarations in workspace members that depend on any of the versions
+        // directly, falling back to the root manifest if they're all pulled in transitively.
+        let mut members: Vec<_> = pkg_graph
+            .workspace()
+            .members()
+            .map(|(_, member)| member)
+            .collect();
+        members.sort_by_key(|member| member.manifest_path());
+        let mut locations: Vec<_> = members
+            .into_iter()
+            .flat_map(|member| declaration_locations(member, root, |dep_name| dep_name == name))
+            .collect();
+        if locations.is_empty() {
+            locations.push(location(&root.join("Cargo.toml"), root, None));
+        }
+        let message = format!(
+            "{} has {} versions: {}",
+            name,
+            versions.len(),
+            versions.join(", ")
+        );
+        results.push(result("duplicate-versions", "warning", message, locations));
+    }
+
+    for violation in pkg_graph.toolchain_msrv_violations() {
+        let package = violation.package();
+        let message = format!(
+            "{}: rust-version {} is newer than pinned toolchain {} ({})",
+            package.name(),
+            violation.rust_version(),
+            violation.toolchain().channel(),
+            violation.toolchain().path().display(),
+        );
+        let span = package
+            .manifest_spans()
+            .and_then(|spans| spans.package_key("rust-version"));
+        results.push(result(
+            "msrv",
+            "error",
+            message,
+            vec![location(package.manifest_path(), root, span)],
+        ));
+    }
+
+    for (package, target, spec, spans) in cfg_targets(pkg_graph) {
+        let locations: Vec<_> = spans
+            .into_iter()
+            .map(|span| location(package.manifest_path(), root, Some(span)))
+            .collect();
+        let coverage = match spec.coverage() {
+            SpecCoverage::Never => Some((
+                "target-spec/never",
+                format!(
+                    "{}: [target.'{}'] doesn't apply to any known platform",
+                    package.name(),
+                    target
+                ),
+            )),
+            SpecCoverage::Always => Some((
+                "target-spec/always",
+                format!(
+                    "{}: [target.'{}'] applies to every known platform",
+                    package.name(),
+                    target
+                ),
+            )),
+            _ => None,
+        };
+        if let Some((rule_id, message)) = coverage {
+            results.push(result(rule_id, "warning", message, locations.clone()));
+        }
+        for warning in spec.warnings() {
+            let message = format!("{}: [target.'{}']: {}", package.name(), target, warning);
+            results.push(result(
+                "target-spec/unknown-value",
+                "warning",
+                message,
+                locations.clone(),
+            ));
+        }
+    }
+
+    let rules: Vec<_> = RULES
+        .iter()
+        .map(|(id, description)| {
+            json!({
+                "id": id,
+                "shortDescription": { "text": description },
+            })
+        })
+        .collect();
+    Ok(json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": INFORMATION_URI,
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    }))
+}
+
+/// Returns packages with more than one version in the graph, ordered by name and version.
+fn duplicates(pkg_graph: &PackageGraph) -> BTreeMap<&str, Vec<PackageMetadata<'_>>> {
+    let mut versions: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for package in pkg_graph
+        .query_workspace()
+        .resolve()
+        .packages(DependencyDirection::Forward)
+    {
+        versions.entry(package.name()).or_default().push(package);
+    }
+    versions.retain(|_, packages| packages.len() > 1);
+    for packages in versions.values_mut() {
+        packages.sort_by(|a, b| a.version().cmp(b.version()));
+    }
+    versions
+}
+
+/// Returns the `cfg()` expressions in workspace members' `[target]` sections, along with the
+/// dependency declarations under them.
+///
+/// Triples aren't returned, since they may be for custom targets or targets newer than the ones
+/// `guppy` knows about.
+#[allow(clippy::type_complexity)]
+fn cfg_targets(
+    pkg_graph: &PackageGraph,
+) -> Vec<(PackageMetadata<'_>, &str, TargetSpec, Vec<ManifestSpan>)> {
+    let mut members: Vec<_> = pkg_graph
+        .workspace()
+        .members()
+        .map(|(_, member)| member)
+        .collect();
+    members.sort_by_key(|member| member.manifest_path());
+
+    let mut cfg_targets = vec![];
+    for member in members {
+        let spans = match member.manifest_spans() {
+            Some(spans) => spans,
+            None => continue,
+        };
+        let mut targets: BTreeMap<&str, Vec<ManifestSpan>> = BTreeMap::new();
+        for dependency in spans.dependencies() {
+            if let Some(target) = dependency.target() {
+                targets.entry(target).or_default().push(dependency.span());
+            }
+        }
+        for (target, spans) in targets {
+            if !target.starts_with("cfg(") {
+                continue;
+            }
+            if let Ok(spec) = target.parse::<TargetSpec>() {
+                cfg_targets.push((member, target, spec, spans));
+            }
+        }
+    }
+    cfg_targets
+}
+
+fn result(rule_id: &str, level: &str, message: String, locations: Vec<Value>) -> Value {
+    json!({
+        "ruleId": rule_id,
+        "level": level,
+        "message": { "text": message },
+        "locations": locations,
+    })
+}
+
+fn manifest_location(package: PackageMetadata<'_>, root: &Path) -> Value {
+    location(package.manifest_path(), root, None)
+}
+
+/// Returns locations for the declarations in this package of direct dependencies on packages
+/// whose names match.
+fn declaration_locations(
+    package: PackageMetadata<'_>,
+    root: &Path,
+    mut matches: impl FnMut(&str) -> bool,
+) -> Vec<Value> {
+    let mut links: Vec<_> = package
+        .direct_links()
+        .filter(|link| matches(link.to().name()))
+        .collect();
+    links.sort_by_key(|link| link.to().id());
+    let mut spans: Vec<_> = links
+        .iter()
+        .flat_map(|link| link.declaration_spans())
+        .map(|declaration| declaration.span())
+        .collect();
+    spans.sort_unstable();
+    spans.dedup();
+    spans
+        .into_iter()
+        .map(|span| location(package.manifest_path(), root, Some(span)))
+        .collect()
+}
+
+fn location(path: &Path, root: &Path, span: Option<ManifestSpan>) -> Value {
+    let path = path.strip_prefix(root).unwrap_or(path);
+    // SARIF URIs always use forward slashes.
+    let uri = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let mut physical_location = json!({
+        "artifactLocation": { "uri": uri },
+    });
+    if let Some(span) = span {
+        physical_location["region"] = json!({
+            "startLine": span.start_line(),
+            "startColumn": span.start_column(),
+            "endLine": span.end_line(),
+            "endColumn": span.end_column(),
+        });
+    }
+    json!({ "physicalLocation": physical_location })
+}
@@ -0,0 +1,573 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A long-running server that answers queries over JSON-RPC.
+//!
+//! Constructing a `PackageGraph` means running `cargo metadata`, which can take a while for large
+//! workspaces. `cargo guppy serve` builds the graph once and answers any number of queries
+//! against it, rebuilding it whenever a workspace manifest or `Cargo.lock` changes.
+//!
+//! The protocol is JSON-RPC 2.0 with one message per line. The supported methods are:
+//!
+//! * `select`: `{"packages": [names], "reverse": bool}` returns the transitive dependencies (or
+//!   dependents) of the given packages, or of the whole workspace if none are specified.
+//! * `why`: `{"package": name}` returns, for each version of the package, a shortest path to it
+//!   from a workspace member.
+//! * `features`: `{"packages": [names], "features": [names], "all_features": bool,
+//!   "no_default_features": bool}` returns the features enabled for each package.
+//! * `affected`: `{"paths": [paths]}` returns the workspace members that own the given paths,
+//!   and the workspace members that depend on them.
+//! * `reload` rebuilds the graph, and `shutdown` stops the server.
+
+use anyhow::{anyhow, bail};
+use guppy::graph::{DependencyDirection, PackageGraph, PackageMetadata};
+use guppy::PackageId;
+use guppy_cmdlib::{CargoMetadataOptions, PackagesAndFeatures};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use structopt::StructOpt;
+
+// Error codes defined by the JSON-RPC 2.0 specification.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// Server-defined error code for queries that fail.
+const QUERY_ERROR: i64 = -32000;
+
+#[derive(Debug, StructOpt)]
+pub struct ServeOptions {
+    #[structopt(long)]
+    /// Listen on this unix socket instead of stdin and stdout
+    socket: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    metadata_opts: CargoMetadataOptions,
+}
+
+pub fn cmd_serve(options: &ServeOptions) -> Result<(), anyhow::Error> {
+    let mut server = Server::new(options.metadata_opts.clone())?;
+
+    match &options.socket {
+        Some(socket) => serve_socket(&mut server, socket),
+        None => {
+            let stdin = io::stdin();
+            let stdout = io::stdout();
+            server.serve(stdin.lock(), stdout.lock())?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(unix)]
+fn serve_socket(server: &mut Server, socket: &Path) -> Result<(), anyhow::Error> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    // A socket left behind by a server that didn't exit cleanly can be replaced, but one that's
+    // still accepting connections belongs to another server.
+    if let Ok(metadata) = fs::symlink_metadata(socket) {
+        if !metadata.file_type().is_socket() {
+            bail!("{} exists and isn't a socket", socket.display());
+        }
+        if UnixStream::connect(socket).is_ok() {
+            bail!("another server is listening on {}", socket.display());
+        }
+        fs::remove_file(socket)?;
+    }
+
+    let listener = UnixListener::bind(socket)?;
+    let _guard = RemoveOnDrop(socket);
+    // Connections are handled one at a time, so every query sees an up-to-date graph.
+    for stream in listener.incoming() {
+        let result = stream.and_then(|stream| {
+            let reader = BufReader::new(stream.try_clone()?);
+            server.serve(reader, stream)
+        });
+        match result {
+            Ok(Shutdown::Requested) => break,
+            Ok(Shutdown::NotRequested) => {}
+            // A client going away shouldn't take the server down with it.
+            Err(err) => eprintln!("warning: error serving connection: {}", err),
+        }
+    }
+    Ok(())
+}
+
+/// Removes the socket file when the server exits, however it does so.
+#[cfg(unix)]
+struct RemoveOnDrop<'a>(&'a Path);
+
+#[cfg(unix)]
+impl Drop for RemoveOnDrop<'_> {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(self.0);
+    }
+}
+
+#[cfg(not(unix))]
+fn serve_socket(_server: &mut Server, _socket: &Path) -> Result<(), anyhow::Error> {
+    bail!("--socket is only supported on unix platforms")
+}
+
+/// Whether a client asked the server to shut down.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Shutdown {
+    Requested,
+    NotRequested,
+}
+
+/// Holds a package graph in memory and answers JSON-RPC requests against it.
+pub struct Server {
+    metadata_opts: CargoMetadataOptions,
+    graph: PackageGraph,
+    watched: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl Server {
+    /// Creates a new server, building the graph for the workspace described by `metadata_opts`.
+    pub fn new(metadata_opts: CargoMetadataOptions) -> Result<Self, anyhow::Error> {
+        let graph = metadata_opts.make_command().build_graph()?;
+        Ok(Self::from_graph(metadata_opts, graph))
+    }
+
+    fn from_graph(metadata_opts: CargoMetadataOptions, graph: PackageGraph) -> Self {
+        let watched = watched_files(&graph);
+        Self {
+            metadata_opts,
+            graph,
+            watched,
+        }
+    }
+
+    /// Reads requests from `reader` until it's closed or a `shutdown` request is received,
+    /// writing responses out to `writer`.
+    pub fn serve(
+        &mut self,
+        reader: impl BufRead,
+        mut writer: impl Write,
+    ) -> Result<Shutdown, io::Error> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (response, shutdown) = self.handle(&line);
+            if let Some(response) = response {
+                writeln!(writer, "{}", response)?;
+                writer.flush()?;
+            }
+            if shutdown == Shutdown::Requested {
+                return Ok(shutdown);
+            }
+        }
+        Ok(Shutdown::NotRequested)
+    }
+
+    /// Handles a single request, returning the response to send back. Notifications (requests
+    /// without an ID) don't get a response.
+    pub fn handle(&mut self, line: &str) -> (Option<Value>, Shutdown) {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(err) => {
+                let response = error_response(Value::Null, PARSE_ERROR, err.to_string());
+                return (Some(response), Shutdown::NotRequested);
+            }
+        };
+        let id = request.get("id").cloned();
+        let method = match request.get("method").and_then(Value::as_str) {
+            Some(method) => method,
+            None => {
+                let response = error_response(
+                    id.unwrap_or(Value::Null),
+                    INVALID_REQUEST,
+                    "request doesn't have a method",
+                );
+                return (Some(response), Shutdown::NotRequested);
+            }
+        };
+        let params = request.get("params").cloned().unwrap_or_else(|| json!({}));
+
+        let mut shutdown = Shutdown::NotRequested;
+        let result = match method {
+            "shutdown" => {
+                shutdown = Shutdown::Requested;
+                Ok(Value::Null)
+            }
+            "reload" => self.reload().map(|()| Value::Null),
+            _ => self.refresh().and_then(|()| self.query(method, params)),
+        };
+
+        let id = match id {
+            Some(id) => id,
+            None => return (None, shutdown),
+        };
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(RequestError { code, message }) => error_response(id, code, message),
+        };
+        (Some(response), shutdown)
+    }
+
+    /// Rebuilds the graph if any watched files have changed since it was last built.
+    fn refresh(&mut self) -> Result<(), RequestError> {
+        let changed = self
+            .watched
+            .iter()
+            .any(|(path, modified)| modified_time(path) != *modified);
+        if changed {
+            self.reload()?;
+        }
+        Ok(())
+    }
+
+    fn reload(&mut self) -> Result<(), RequestError> {
+        let graph = self
+            .metadata_opts
+            .make_command()
+            .build_graph()
+            .map_err(|err| RequestError::query(anyhow!(err)))?;
+        self.watched = watched_files(&graph);
+        self.graph = graph;
+        Ok(())
+    }
+
+    fn query(&self, method: &str, params: Value) -> Result<Value, RequestError> {
+        let graph = &self.graph;
+        let result = match method {
+            "select" => select(graph, parse_params(params)?),
+            "why" => why(graph, parse_params(params)?),
+            "features" => features(graph, parse_params(params)?),
+            "affected" => affected(graph, parse_params(params)?),
+            _ => {
+                return Err(RequestError {
+                    code: METHOD_NOT_FOUND,
+                    message: format!("unknown method '{}'", method),
+                })
+            }
+        };
+        result.map_err(RequestError::query)
+    }
+}
+
+// ---
+// Queries
+// ---
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SelectParams {
+    #[serde(default)]
+    packages: Vec<String>,
+    #[serde(default)]
+    reverse: bool,
+}
+
+fn select(graph: &PackageGraph, params: SelectParams) -> Result<Value, anyhow::Error> {
+    let direction = if params.reverse {
+        DependencyDirection::Reverse
+    } else {
+        DependencyDirection::Forward
+    };
+    let query = if params.packages.is_empty() {
+        if params.reverse {
+            bail!("reverse queries require packages to be specified");
+        }
+        graph.query_workspace()
+    } else {
+        let ids = names_to_ids(graph, &params.packages)?;
+        graph.query_directed(ids.iter().copied(), direction)?
+    };
+
+    let packages: Vec<_> = query
+        .resolve()
+        .packages(direction)
+        .map(package_json)
+        .collect();
+    Ok(Value::Array(packages))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WhyParams {
+    package: String,
+}
+
+fn why(graph: &PackageGraph, params: WhyParams) -> Result<Value, anyhow::Error> {
+    let ids = names_to_ids(graph, &[params.package])?;
+    let paths: Vec<_> = ids
+        .into_iter()
+        .map(|id| {
+            let path = match shortest_path_from_workspace(graph, id) {
+                Some(path) => Value::Array(
+                    path.into_iter()
+                        .map(|id| Value::String(id.repr().to_string()))
+                        .collect(),
+                ),
+                None => Value::Null,
+            };
+            json!({ "id": id.repr(), "path": path })
+        })
+        .collect();
+    Ok(Value::Array(paths))
+}
+
+/// Returns the shortest path from a workspace member to `target`, inclusive of both ends.
+fn shortest_path_from_workspace<'g>(
+    graph: &'g PackageGraph,
+    target: &'g PackageId,
+) -> Option<Vec<&'g PackageId>> {
+    // Do a breadth-first search over reverse links, so that the first workspace member found is
+    // the closest one.
+    let mut next: HashMap<&PackageId, &PackageId> = HashMap::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(target);
+    let mut found = None;
+
+    while let Some(id) = queue.pop_front() {
+        let package = graph.metadata(id).expect("valid package ID");
+        if package.in_workspace() {
+            found = Some(id);
+            break;
+        }
+        for link in package.reverse_direct_links() {
+            let from = link.from().id();
+            if from != target && !next.contains_key(from) {
+                next.insert(from, id);
+                queue.push_back(from);
+            }
+        }
+    }
+
+    let mut path = vec![found?];
+    while let Some(id) = next.get(path[path.len() - 1]) {
+        path.push(id);
+    }
+    Some(path)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FeaturesParams {
+    packages: Vec<String>,
+    features: Vec<String>,
+    all_features: bool,
+    no_default_features: bool,
+}
+
+fn features(graph: &PackageGraph, params: FeaturesParams) -> Result<Value, anyhow::Error> {
+    let pf = PackagesAndFeatures {
+        packages: params.packages,
+        features: params.features,
+        all_features: params.all_features,
+        no_default_features: params.no_default_features,
+    };
+    let feature_set = pf.make_feature_query(graph)?.resolve();
+    let packages: Vec<_> = feature_set
+        .packages_with_features(DependencyDirection::Forward)
+        .map(|feature_list| {
+            let mut value = package_json(*feature_list.package());
+            value["features"] = json!(feature_list.features());
+            value
+        })
+        .collect();
+    Ok(Value::Array(packages))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AffectedParams {
+    paths: Vec<PathBuf>,
+}
+
+fn affected(graph: &PackageGraph, params: AffectedParams) -> Result<Value, anyhow::Error> {
+    let path_owners = graph.path_owners();
+    let mut owners = BTreeSet::new();
+    let mut unowned = vec![];
+    for path in &params.paths {
+        match path_owners.owner(path) {
+            Some(owner) => {
+                owners.insert(owner.package().id());
+            }
+            None => unowned.push(path.display().to_string()),
+        }
+    }
+
+    let affected: Vec<_> = graph
+        .query_reverse(owners.iter().copied())?
+        .resolve()
+        .packages(DependencyDirection::Reverse)
+        .filter(|package| package.in_workspace())
+        .map(package_json)
+        .collect();
+    let owners: Vec<_> = owners
+        .into_iter()
+        .map(|id| package_json(graph.metadata(id).expect("valid package ID")))
+        .collect();
+    Ok(json!({
+        "owners": owners,
+        "affected": affected,
+        "unowned": unowned,
+    }))
+}
+
+// ---
+// Helper methods
+// ---
+
+struct RequestError {
+    code: i64,
+    message: String,
+}
+
+impl RequestError {
+    fn query(err: anyhow::Error) -> Self {
+        Self {
+            code: QUERY_ERROR,
+            message: err.to_string(),
+        }
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RequestError> {
+    serde_json::from_value(params).map_err(|err| RequestError {
+        code: INVALID_PARAMS,
+        message: err.to_string(),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.into() },
+    })
+}
+
+fn names_to_ids<'g>(
+    graph: &'g PackageGraph,
+    names: &[String],
+) -> Result<Vec<&'g PackageId>, anyhow::Error> {
+    let names: HashSet<&str> = names.iter().map(|name| name.as_str()).collect();
+    let ids: Vec<_> = crate::core::names_to_ids(graph, &names).collect();
+    let found: HashSet<&str> = ids
+        .iter()
+        .map(|id| graph.metadata(id).expect("valid package ID").name())
+        .collect();
+    let mut missing: Vec<_> = names.difference(&found).collect();
+    if !missing.is_empty() {
+        missing.sort();
+        bail!("unknown packages: {}", itertools::join(missing, ", "));
+    }
+    Ok(ids)
+}
+
+fn package_json(package: PackageMetadata) -> Value {
+    json!({
+        "id": package.id().repr(),
+        "name": package.name(),
+        "version": package.version().to_string(),
+    })
+}
+
+/// Returns the files that cause the graph to be rebuilt when they change.
+fn watched_files(graph: &PackageGraph) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let root = graph.workspace().root();
+    let mut paths: BTreeSet<PathBuf> = graph
+        .workspace()
+        .members()
+        .map(|(_, package)| package.manifest_path().to_path_buf())
+        .collect();
+    paths.insert(root.join("Cargo.toml"));
+    paths.insert(root.join("Cargo.lock"));
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let modified = modified_time(&path);
+            (path, modified)
+        })
+        .collect()
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::{env, process, thread};
+
+    static METADATA1: &str = include_str!("../../fixtures/small/metadata1.json");
+
+    fn connect(socket: &Path) -> UnixStream {
+        loop {
+            match UnixStream::connect(socket) {
+                Ok(stream) => return stream,
+                Err(_) => thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        }
+    }
+
+    #[test]
+    fn serve_socket_survives_disconnects() {
+        let socket = env::temp_dir().join(format!("cargo-guppy-serve-{}.sock", process::id()));
+        let _ = fs::remove_file(&socket);
+        // Leave a stale socket behind, as a server that crashed would.
+        drop(UnixListener::bind(&socket).unwrap());
+
+        let graph = PackageGraph::from_json(METADATA1).unwrap();
+        let metadata_opts = CargoMetadataOptions {
+            manifest_path: None,
+            offline: true,
+            registry_mirror: None,
+        };
+        let mut server = Server::from_graph(metadata_opts, graph);
+        let handle = {
+            let socket = socket.clone();
+            thread::spawn(move || serve_socket(&mut server, &socket))
+        };
+
+        // Clients that go away partway through a request, or before reading the response.
+        let mut stream = connect(&socket);
+        stream
+            .write_all(b"{\"jsonrpc\": \"2.0\", \"id\": 1, \"met\xff")
+            .unwrap();
+        drop(stream);
+        let mut stream = connect(&socket);
+        writeln!(
+            stream,
+            r#"{{"jsonrpc": "2.0", "id": 1, "method": "select"}}"#
+        )
+        .unwrap();
+        drop(stream);
+
+        let stream = connect(&socket);
+        let mut writer = stream.try_clone().unwrap();
+        let mut reader = BufReader::new(stream);
+        writeln!(
+            writer,
+            r#"{{"jsonrpc": "2.0", "id": 2, "method": "select"}}"#
+        )
+        .unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let response: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(response["id"], 2);
+        assert!(response["result"].is_array(), "response: {}", response);
+        writeln!(writer, r#"{{"jsonrpc": "2.0", "method": "shutdown"}}"#).unwrap();
+
+        handle.join().unwrap().unwrap();
+        assert!(!socket.exists(), "socket is removed on shutdown");
+    }
+}
@@ -0,0 +1,44 @@
+#!/bin/sh
+# Copyright (c) The cargo-guppy Contributors
+# SPDX-License-Identifier: MIT OR Apache-2.0
+
+# Seeds the corpora from the fixtures in this repository, then runs each fuzz target for a fixed
+# amount of time. Inputs that take longer than a few seconds are reported as hangs.
+#
+# Usage: ./fuzz-all.sh [SECONDS_PER_TARGET]
+
+set -eu
+
+cd "$(dirname "$0")"
+seconds="${1:-60}"
+fixtures=../fixtures
+
+# Writes each line on standard input to its own file in the given directory.
+write_lines() {
+    while IFS= read -r line; do
+        name=$(printf '%s' "$line" | cksum | cut -d ' ' -f 1)
+        printf '%s' "$line" > "$1/$name"
+    done
+}
+
+mkdir -p corpus/metadata_json corpus/package_spec corpus/target_spec
+cp "$fixtures"/small/*.json "$fixtures"/invalid/*.json corpus/metadata_json/
+
+# Platform-specific dependencies, e.g. "target": "cfg(target_os = \"windows\")".
+grep -ho '"target": *"\([^"\\]\|\\.\)*"' "$fixtures"/small/*.json "$fixtures"/large/*.json \
+    | sed -e 's/^"target": *"//' -e 's/"$//' -e 's/\\"/"/g' \
+    | sort -u \
+    | write_lines corpus/target_spec
+
+# Package IDs, e.g. "serde 1.0.101 (registry+https://...)", as serde@1.0.101 and as full specs
+# with the source URL.
+grep -ho '"id": *"[^ "]* [^ "]* ([^"]*)"' "$fixtures"/small/*.json \
+    | sed -e 's/^"id": *"//' -e 's/"$//' \
+    | sort -u \
+    | sed -e 's/^\([^ ]*\) \([^ ]*\) (\(.*\))$/\1@\2\
+\3#\1@\2/' \
+    | write_lines corpus/package_spec
+
+for target in metadata_json package_spec target_spec; do
+    cargo +nightly fuzz run "$target" -- -max_total_time="$seconds" -timeout=5
+done
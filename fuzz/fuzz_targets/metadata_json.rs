@@ -0,0 +1,37 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! `cargo metadata` output, which may come from manifests in untrusted pull requests.
+
+#![no_main]
+
+use guppy::graph::cargo::CargoOptions;
+use guppy::graph::feature::all_filter;
+use guppy::graph::{DependencyDirection, PackageGraph};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let json = match std::str::from_utf8(data) {
+        Ok(json) => json,
+        Err(_) => return,
+    };
+    let graph = match PackageGraph::from_json(json) {
+        Ok(graph) => graph,
+        Err(_) => return,
+    };
+
+    // Graphs that build successfully are internally consistent and can be queried.
+    graph.verify().expect("package graph is consistent");
+    let feature_graph = graph.feature_graph();
+    feature_graph.verify().expect("feature graph is consistent");
+
+    graph
+        .query_workspace()
+        .resolve()
+        .packages(DependencyDirection::Forward)
+        .count();
+    feature_graph
+        .query_workspace(all_filter())
+        .resolve_cargo(&CargoOptions::new())
+        .expect("forward queries resolve");
+});
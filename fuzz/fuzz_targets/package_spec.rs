@@ -0,0 +1,31 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Package specifications, the `-p` syntax used to select packages for queries.
+
+#![no_main]
+
+use guppy::graph::PackageGraph;
+use guppy::PackageSpec;
+use libfuzzer_sys::fuzz_target;
+use once_cell::sync::Lazy;
+
+static GRAPH: Lazy<PackageGraph> = Lazy::new(|| {
+    PackageGraph::from_json(include_str!("../../fixtures/small/metadata1.json"))
+        .expect("fixture is valid")
+});
+
+fuzz_target!(|data: &[u8]| {
+    let input = match std::str::from_utf8(data) {
+        Ok(input) => input,
+        Err(_) => return,
+    };
+    let spec = match PackageSpec::parse(input) {
+        Ok(spec) => spec,
+        Err(_) => return,
+    };
+
+    for package in GRAPH.packages() {
+        spec.matches(&package);
+    }
+});
@@ -0,0 +1,39 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Target specifications, as found in `[target.'cfg(...)'.dependencies]` sections.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use target_spec::{Platform, TargetFeatures, TargetSpec};
+
+fuzz_target!(|data: &[u8]| {
+    let input = match std::str::from_utf8(data) {
+        Ok(input) => input,
+        Err(_) => return,
+    };
+    let spec: TargetSpec = match input.parse() {
+        Ok(spec) => spec,
+        Err(_) => return,
+    };
+
+    // Any spec that parses can be evaluated against any platform, including ones whose properties
+    // are only partly known.
+    for triple in &[
+        "x86_64-unknown-linux-gnu",
+        "x86_64-pc-windows-msvc",
+        "thumbv7em-none-eabihf",
+    ] {
+        let platform = Platform::new(triple, TargetFeatures::Unknown).expect("known triple");
+        spec.eval_result(&platform);
+    }
+    let platform = Platform::new_heuristic("riscv64gc-unknown-freebsd", TargetFeatures::none());
+    spec.eval_result(&platform);
+
+    // The canonical form of a spec is itself a valid spec.
+    let canonical = spec.display_canonical().to_string();
+    if let Err(err) = canonical.parse::<TargetSpec>() {
+        panic!("canonical form {:?} doesn't parse: {}", canonical, err);
+    }
+});
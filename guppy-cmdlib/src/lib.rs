@@ -12,9 +12,12 @@ use anyhow::Result;
 use guppy::graph::feature::{
     all_filter, default_filter, feature_filter, none_filter, FeatureFilter, FeatureQuery,
 };
-use guppy::graph::PackageGraph;
+use guppy::graph::ownership::{Ownership, TeamBreakdown, TeamOwnership};
+use guppy::graph::{PackageGraph, PackageMetadata};
+use guppy::report::{Report, ReportFormat};
 use guppy::{MetadataCommand, Platform, TargetFeatures};
 use std::env;
+use std::fmt;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -66,6 +69,9 @@ impl PackagesAndFeatures {
     }
 }
 
+/// The name of the source `--registry-mirror` replaces crates.io with.
+const MIRROR_SOURCE_NAME: &str = "guppy-registry-mirror";
+
 /// Context for invoking the `cargo metadata` command.
 ///
 /// The options mirror Cargo's.
@@ -74,6 +80,14 @@ pub struct CargoMetadataOptions {
     /// Path to Cargo.toml
     #[structopt(long = "manifest-path")]
     pub manifest_path: Option<PathBuf>,
+
+    /// Run without accessing the network
+    #[structopt(long = "offline")]
+    pub offline: bool,
+
+    /// Use this registry index instead of crates.io
+    #[structopt(long = "registry-mirror", value_name = "URL")]
+    pub registry_mirror: Option<String>,
 }
 
 impl CargoMetadataOptions {
@@ -98,10 +112,92 @@ impl CargoMetadataOptions {
         if let Some(manifest_path) = &self.manifest_path {
             command.manifest_path(manifest_path);
         }
+
+        command.offline(self.offline);
+
+        let mut other_options = vec![];
+        if let Some(mirror) = &self.registry_mirror {
+            // Configure a replacement source, in the same way as a `[source]` table in
+            // `.cargo/config.toml`.
+            other_options.push("--config".to_string());
+            other_options.push(format!(
+                "source.crates-io.replace-with=\"{}\"",
+                MIRROR_SOURCE_NAME
+            ));
+            other_options.push("--config".to_string());
+            other_options.push(format!(
+                "source.{}.registry=\"{}\"",
+                MIRROR_SOURCE_NAME, mirror
+            ));
+        }
+        if !other_options.is_empty() {
+            command.other_options(other_options);
+        }
         command
     }
 }
 
+/// Options for printing analysis reports.
+#[derive(Clone, Debug, StructOpt)]
+pub struct ReportOptions {
+    /// Output format: text, markdown, json or html (default: a compact summary)
+    #[structopt(long = "format")]
+    pub format: Option<ReportFormat>,
+}
+
+impl ReportOptions {
+    /// Renders the report in the chosen format, or through its `Display` implementation if no
+    /// format was chosen.
+    pub fn render(&self, report: &(impl Report + fmt::Display)) -> String {
+        match self.format {
+            Some(format) => report.render(format.renderer()),
+            None => report.to_string(),
+        }
+    }
+}
+
+/// Options for breaking reports down by the teams owning workspace members.
+#[derive(Clone, Debug, StructOpt)]
+pub struct TeamOptions {
+    /// Ownership file mapping workspace members to teams (TOML or CODEOWNERS format)
+    #[structopt(long = "owners", value_name = "PATH")]
+    pub owners: Option<PathBuf>,
+
+    /// Only report items belonging to members owned by this team
+    #[structopt(long = "team", requires = "owners")]
+    pub team: Option<String>,
+}
+
+impl TeamOptions {
+    /// Reads the ownership file and resolves it against this graph, or returns `None` if no
+    /// ownership file was passed in.
+    pub fn load<'g>(&self, graph: &'g PackageGraph) -> Result<Option<TeamOwnership<'g>>> {
+        match &self.owners {
+            Some(owners) => {
+                let ownership = Ownership::from_path(owners)?;
+                Ok(Some(graph.team_ownership(&ownership)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Groups these report items by team, and filters them down to the team passed in, if any.
+    pub fn breakdown<'g, T: fmt::Display>(
+        &self,
+        teams: &TeamOwnership<'g>,
+        title: &str,
+        items: impl IntoIterator<Item = (PackageMetadata<'g>, T)>,
+    ) -> Result<TeamBreakdown> {
+        let breakdown = teams.breakdown(title, items);
+        match &self.team {
+            Some(team) => breakdown
+                .filter_team(team)
+                .ok_or_else(|| anyhow::anyhow!("team '{}' not found in ownership file", team)),
+            None => Ok(breakdown),
+        }
+    }
+}
+
 /// Parse a given triple, the string "current", or "any", into a platform.
 ///
 /// TODO: This should eventually support JSON specs as well, probably.
@@ -112,10 +208,11 @@ pub fn triple_to_platform<'a>(
     match triple {
         Some("current") => Ok(Platform::current()),
         Some("any") => Ok(None),
-        Some(triple) => match Platform::new(triple, TargetFeatures::Unknown) {
-            Some(platform) => Ok(Some(platform)),
-            None => anyhow::bail!("unrecognized triple '{}'", triple),
-        },
+        // Properties of triples that aren't builtin are guessed from their components.
+        Some(triple) => Ok(Some(Platform::new_heuristic(
+            triple,
+            TargetFeatures::Unknown,
+        ))),
         None => Ok(default_fn()),
     }
 }
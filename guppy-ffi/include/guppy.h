@@ -0,0 +1,32 @@
+/*
+ * Copyright (c) The cargo-guppy Contributors
+ * SPDX-License-Identifier: MIT OR Apache-2.0
+ *
+ * C API for guppy. Every function returning `char *` returns a JSON string of the form
+ * {"result": ...} or {"error": "message"}, which must be freed with guppy_string_free.
+ */
+
+#ifndef GUPPY_H
+#define GUPPY_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct GuppyGraph GuppyGraph;
+
+char *guppy_eval_target_spec(const char *spec, const char *platform);
+
+GuppyGraph *guppy_graph_load(const char *json, char **error);
+void guppy_graph_free(GuppyGraph *graph);
+
+char *guppy_graph_reverse_deps(const GuppyGraph *graph, const char *name);
+char *guppy_graph_licenses(const GuppyGraph *graph);
+
+void guppy_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GUPPY_H */
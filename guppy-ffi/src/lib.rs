@@ -0,0 +1,344 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A minimal C API for `guppy`.
+//!
+//! Every query returns a newly allocated, NUL-terminated JSON string, which must be freed with
+//! `guppy_string_free`. Successful results are of the form `{"result": ...}`, and failures are of
+//! the form `{"error": "message"}`, so callers only need a JSON parser to use this API.
+//!
+//! Graphs are loaded from `cargo metadata` JSON, such as the output of
+//! `PackageGraph::to_cargo_metadata`, and are represented by the opaque `GuppyGraph` type. A
+//! loaded graph is immutable, so it can be queried from several threads at once. Loading a graph
+//! doesn't read any files, so the result only depends on the JSON: information that `guppy`
+//! otherwise reads from manifests and toolchain files, like `rust-version`, isn't available.
+//!
+//! Panics don't unwind into the caller. They're reported like other failures, as
+//! `{"error": "message"}`.
+//!
+//! The C declarations for this API are in `include/guppy.h`.
+
+use guppy::graph::{DependencyDirection, PackageGraph, PackageMetadata};
+use guppy::{CargoMetadata, MemoryFileSource};
+use serde_json::{json, Value};
+use std::any::Any;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// A package graph loaded through the C API.
+pub struct GuppyGraph {
+    graph: PackageGraph,
+}
+
+/// Evaluates a target spec, such as `cfg(unix)` or a target triple, against a platform triple.
+///
+/// The result is `true` or `false`, or `null` if the answer can't be determined because the spec
+/// depends on target features.
+///
+/// # Safety
+///
+/// `spec` and `platform` must be valid, NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn guppy_eval_target_spec(
+    spec: *const c_char,
+    platform: *const c_char,
+) -> *mut c_char {
+    respond(|| {
+        let spec = to_str(spec, "spec")?;
+        let platform = to_str(platform, "platform")?;
+        let result = target_spec::eval(spec, platform).map_err(|err| err.to_string())?;
+        Ok(json!(result))
+    })
+}
+
+/// Loads a package graph from `cargo metadata` JSON.
+///
+/// Returns null on failure. If `error` is not null, it is set to a JSON error string on failure
+/// and to null on success.
+///
+/// # Safety
+///
+/// `json` must be a valid, NUL-terminated string, and `error` must either be null or point to
+/// writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn guppy_graph_load(
+    json: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut GuppyGraph {
+    let result = catch_panic(|| {
+        let json = to_str(json, "json")?;
+        let metadata = CargoMetadata::parse_json(json).map_err(|err| err.to_string())?;
+        // Don't read manifests or other files from this machine.
+        PackageGraph::from_metadata_with_files(metadata, &MemoryFileSource::new())
+            .map_err(|err| err.to_string())
+    });
+    match result {
+        Ok(graph) => {
+            if !error.is_null() {
+                *error = ptr::null_mut();
+            }
+            Box::into_raw(Box::new(GuppyGraph { graph }))
+        }
+        Err(msg) => {
+            if !error.is_null() {
+                *error = into_c_string(json!({ "error": msg }));
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Frees a graph returned by `guppy_graph_load`. Passing in null is a no-op.
+///
+/// # Safety
+///
+/// `graph` must be null or a graph returned by `guppy_graph_load` that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn guppy_graph_free(graph: *mut GuppyGraph) {
+    if !graph.is_null() {
+        // There's nothing to report a panic to here, so ignore it.
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(graph))));
+    }
+}
+
+/// Returns the packages that depend, directly or indirectly, on any package named `name`.
+///
+/// The result is an array of `{"id", "name", "version"}` objects in reverse topological order, not
+/// including the packages named `name`. It is an error if no packages are named `name`.
+///
+/// # Safety
+///
+/// `graph` must be a valid graph and `name` must be a valid, NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn guppy_graph_reverse_deps(
+    graph: *const GuppyGraph,
+    name: *const c_char,
+) -> *mut c_char {
+    respond(|| {
+        let graph = &to_graph(graph)?.graph;
+        let name = to_str(name, "name")?;
+        let ids: Vec<_> = graph
+            .packages()
+            .filter(|package| package.name() == name)
+            .map(|package| package.id())
+            .collect();
+        if ids.is_empty() {
+            return Err(format!("no packages named '{}'", name));
+        }
+
+        let query = graph
+            .query_reverse(ids.iter().copied())
+            .map_err(|err| err.to_string())?;
+        let packages: Vec<_> = query
+            .resolve()
+            .packages(DependencyDirection::Reverse)
+            .filter(|package| package.name() != name)
+            .map(package_json)
+            .collect();
+        Ok(Value::Array(packages))
+    })
+}
+
+/// Returns the license for every package in the graph.
+///
+/// The result is an array of `{"id", "name", "version", "license"}` objects sorted by package
+/// ID. `license` is `null` for packages that don't specify one.
+///
+/// # Safety
+///
+/// `graph` must be a valid graph.
+#[no_mangle]
+pub unsafe extern "C" fn guppy_graph_licenses(graph: *const GuppyGraph) -> *mut c_char {
+    respond(|| {
+        let graph = &to_graph(graph)?.graph;
+        let mut packages: Vec<_> = graph.packages().collect();
+        packages.sort_by_key(|package| package.id());
+        let licenses = packages
+            .into_iter()
+            .map(|package| {
+                let mut value = package_json(package);
+                value["license"] = json!(package.license());
+                value
+            })
+            .collect();
+        Ok(Value::Array(licenses))
+    })
+}
+
+/// Frees a string returned by this API. Passing in null is a no-op.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this API that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn guppy_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(CString::from_raw(s))));
+    }
+}
+
+// ---
+// Helper methods
+// ---
+
+fn respond(f: impl FnOnce() -> Result<Value, String>) -> *mut c_char {
+    let value = match catch_panic(f) {
+        Ok(result) => json!({ "result": result }),
+        Err(msg) => json!({ "error": msg }),
+    };
+    into_c_string(value)
+}
+
+/// Runs `f`, turning a panic into an error so that it doesn't unwind across the FFI boundary.
+fn catch_panic<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    // Results are only returned through the C API, so no broken invariants are observable after a
+    // panic.
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(format!("panicked: {}", panic_message(&*payload))))
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg
+    } else {
+        "unknown panic"
+    }
+}
+
+fn into_c_string(value: Value) -> *mut c_char {
+    // serde_json escapes control characters, so the output never contains a NUL byte.
+    CString::new(value.to_string())
+        .expect("JSON output doesn't contain NUL bytes")
+        .into_raw()
+}
+
+unsafe fn to_str<'a>(s: *const c_char, name: &str) -> Result<&'a str, String> {
+    if s.is_null() {
+        return Err(format!("{} is null", name));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|err| format!("{} is not valid UTF-8: {}", name, err))
+}
+
+unsafe fn to_graph<'a>(graph: *const GuppyGraph) -> Result<&'a GuppyGraph, String> {
+    graph.as_ref().ok_or_else(|| "graph is null".to_string())
+}
+
+fn package_json(package: PackageMetadata) -> Value {
+    json!({
+        "id": package.id().repr(),
+        "name": package.name(),
+        "version": package.version().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(s: *mut c_char) -> Value {
+        assert!(!s.is_null(), "API returned a string");
+        let value = unsafe {
+            let value = serde_json::from_str(CStr::from_ptr(s).to_str().unwrap()).unwrap();
+            guppy_string_free(s);
+            value
+        };
+        value
+    }
+
+    fn c_string(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    fn load_metadata1() -> *mut GuppyGraph {
+        let json = c_string(include_str!("../../fixtures/small/metadata1.json"));
+        let mut error = ptr::null_mut();
+        let graph = unsafe { guppy_graph_load(json.as_ptr(), &mut error) };
+        assert!(!graph.is_null());
+        assert!(error.is_null());
+        graph
+    }
+
+    #[test]
+    fn eval_target_spec() {
+        let platform = c_string("x86_64-unknown-linux-gnu");
+        for (spec, expected) in &[
+            ("cfg(unix)", json!({ "result": true })),
+            ("cfg(windows)", json!({ "result": false })),
+            ("cfg(target_feature = \"sse2\")", json!({ "result": null })),
+        ] {
+            let spec = c_string(spec);
+            let actual = call(unsafe { guppy_eval_target_spec(spec.as_ptr(), platform.as_ptr()) });
+            assert_eq!(&actual, expected);
+        }
+
+        let spec = c_string("cfg(");
+        let actual = call(unsafe { guppy_eval_target_spec(spec.as_ptr(), platform.as_ptr()) });
+        assert!(actual["error"].is_string(), "invalid spec: {}", actual);
+        let actual = call(unsafe { guppy_eval_target_spec(ptr::null(), platform.as_ptr()) });
+        assert_eq!(actual, json!({ "error": "spec is null" }));
+    }
+
+    #[test]
+    fn graph_queries() {
+        let graph = load_metadata1();
+
+        let name = c_string("lazy_static");
+        let actual = call(unsafe { guppy_graph_reverse_deps(graph, name.as_ptr()) });
+        let names: Vec<_> = actual["result"]
+            .as_array()
+            .expect("result is an array")
+            .iter()
+            .map(|package| package["name"].as_str().unwrap())
+            .collect();
+        assert!(names.contains(&"testcrate"), "reverse deps: {:?}", names);
+        assert!(!names.contains(&"lazy_static"), "reverse deps: {:?}", names);
+
+        let name = c_string("no-such-package");
+        let actual = call(unsafe { guppy_graph_reverse_deps(graph, name.as_ptr()) });
+        assert_eq!(
+            actual,
+            json!({ "error": "no packages named 'no-such-package'" })
+        );
+
+        let actual = call(unsafe { guppy_graph_licenses(graph) });
+        let licenses = actual["result"].as_array().expect("result is an array");
+        let testcrate = licenses
+            .iter()
+            .find(|package| package["name"] == "testcrate")
+            .expect("testcrate is in the graph");
+        assert_eq!(testcrate["license"], Value::Null);
+        assert!(licenses
+            .iter()
+            .any(|package| package["license"] == "MIT/Apache-2.0"));
+
+        unsafe { guppy_graph_free(graph) };
+    }
+
+    #[test]
+    fn load_errors() {
+        let json = c_string("{}");
+        let mut error = ptr::null_mut();
+        let graph = unsafe { guppy_graph_load(json.as_ptr(), &mut error) };
+        assert!(graph.is_null());
+        assert!(call(error)["error"].is_string());
+
+        // A null error pointer is allowed.
+        let graph = unsafe { guppy_graph_load(json.as_ptr(), ptr::null_mut()) };
+        assert!(graph.is_null());
+        unsafe { guppy_graph_free(graph) };
+    }
+
+    #[test]
+    fn panics() {
+        let actual = call(respond(|| panic!("oh no")));
+        assert_eq!(actual, json!({ "error": "panicked: oh no" }));
+        let actual = call(respond(|| panic!("formatted {}", 42)));
+        assert_eq!(actual, json!({ "error": "panicked: formatted 42" }));
+    }
+}
@@ -0,0 +1,734 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Information from Cargo configuration files.
+//!
+//! Cargo reads configuration from `.cargo/config.toml` files in the directory it's invoked from
+//! and its ancestors, and from `$CARGO_HOME/config.toml`. Several of these settings change what a
+//! build in that repository looks like:
+//!
+//! * `[source]` tables replace the sources packages are fetched from, for example with vendored
+//!   copies.
+//! * `[target]` tables set flags like `--cfg` for specific platforms.
+//! * `build.target` sets the platform builds are for by default.
+//! * `[patch]` tables patch dependencies, the same way `[patch]` in `Cargo.toml` does.
+//!
+//! If `MetadataCommand::cargo_config` is set, `guppy` looks for these files, starting from the
+//! workspace root, while constructing a `PackageGraph`. The result is available through
+//! `Workspace::cargo_config`. Environment variables like `RUSTFLAGS` or `CARGO_BUILD_TARGET`
+//! aren't taken into account.
+//!
+//! `guppy` uses these settings in the following ways:
+//!
+//! * `[source]` tables are reflected in `PackageMetadata::replaced_source`.
+//! * `build.target` and `[target]` tables are applied to the platforms Cargo builds are simulated
+//!   for, through `CargoOptions::with_cargo_config`.
+//! * `[patch]` tables are already applied by `cargo metadata`, so the packages they patch in are
+//!   part of the graph. `CargoConfig::patch_for` finds the table a package came from.
+
+use crate::graph::{PackageMetadata, PackageSource};
+use crate::{Error, FileSource, RealFileSource, TargetSpec};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use target_spec::{Platform, TargetFeatures};
+
+/// The names of Cargo configuration files within `.cargo` directories, in the order Cargo checks
+/// them.
+pub const CARGO_CONFIG_FILE_NAMES: &[&str] = &["config", "config.toml"];
+
+/// The name Cargo uses for the crates.io source in `[source]` and `[patch]` tables.
+const CRATES_IO_NAME: &str = "crates-io";
+
+/// Cargo configuration, merged from all the configuration files that apply to a directory.
+#[derive(Clone, Debug, Default)]
+pub struct CargoConfig {
+    files: Vec<PathBuf>,
+    build_targets: Vec<String>,
+    build_rustflags: Vec<String>,
+    sources: BTreeMap<String, SourceConfig>,
+    target_overrides: Vec<TargetOverride>,
+    patches: Vec<ConfigPatch>,
+}
+
+impl CargoConfig {
+    /// Parses the contents of Cargo configuration files, in order of increasing precedence.
+    ///
+    /// Files are merged the way Cargo merges them: values in later files override values in
+    /// earlier ones, and arrays are concatenated. Relative paths are resolved relative to the
+    /// parent of the directory containing each file, as with the `.cargo` directory.
+    pub fn parse<'a>(files: impl IntoIterator<Item = (PathBuf, &'a str)>) -> Result<Self, Error> {
+        let mut paths = vec![];
+        let mut merged = toml::Value::Table(toml::value::Table::new());
+        for (path, contents) in files {
+            let mut value: toml::Value = toml::from_str(contents)
+                .map_err(|err| Error::CargoConfigError(path.clone(), Box::new(err)))?;
+            // Check each file separately, so that errors point to the right file.
+            let raw: RawConfig = value
+                .clone()
+                .try_into()
+                .map_err(|err| Error::CargoConfigError(path.clone(), Box::new(err)))?;
+            for key in raw.target.keys() {
+                parse_target_key(key).map_err(|err| {
+                    Error::CargoConfigError(
+                        path.clone(),
+                        format!("invalid [target] key '{}': {}", key, err).into(),
+                    )
+                })?;
+            }
+
+            let base_dir = path.parent().and_then(Path::parent).unwrap_or(&path);
+            resolve_paths(&mut value, base_dir);
+            split_rustflags(&mut value);
+            merge(&mut merged, value);
+            paths.push(path);
+        }
+        // Paths are reported in order of decreasing precedence, the same as discovery.
+        paths.reverse();
+
+        let raw: RawConfig = merged.try_into().map_err(|err| {
+            Error::CargoConfigError(paths.first().cloned().unwrap_or_default(), Box::new(err))
+        })?;
+        Ok(Self::from_raw(paths, raw))
+    }
+
+    /// Looks for Cargo configuration files in `dir`, its ancestors and `$CARGO_HOME`, the way
+    /// Cargo does when invoked from `dir`.
+    pub fn discover(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::discover_with_files(dir, &RealFileSource)
+    }
+
+    /// Looks for Cargo configuration files in `dir`, its ancestors and `$CARGO_HOME`, reading files
+    /// through this `FileSource`.
+    ///
+    /// Returns an empty configuration if no files are found.
+    pub fn discover_with_files(
+        dir: impl AsRef<Path>,
+        files: &dyn FileSource,
+    ) -> Result<Self, Error> {
+        let mut config_dirs: Vec<PathBuf> = dir
+            .as_ref()
+            .ancestors()
+            .map(|dir| dir.join(".cargo"))
+            .collect();
+        if let Some(cargo_home) = cargo_home() {
+            if !config_dirs.contains(&cargo_home) {
+                config_dirs.push(cargo_home);
+            }
+        }
+
+        let mut found = vec![];
+        for config_dir in config_dirs {
+            for file_name in CARGO_CONFIG_FILE_NAMES {
+                let path = config_dir.join(file_name);
+                match files.read_to_string(&path) {
+                    Ok(contents) => {
+                        found.push((path, contents));
+                        // If both files are present, Cargo only reads the first one.
+                        break;
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                    Err(err) => return Err(Error::CargoConfigError(path, Box::new(err))),
+                }
+            }
+        }
+
+        // Files closer to dir take precedence.
+        Self::parse(
+            found
+                .iter()
+                .rev()
+                .map(|(path, contents)| (path.clone(), contents.as_str())),
+        )
+    }
+
+    fn from_raw(files: Vec<PathBuf>, raw: RawConfig) -> Self {
+        let sources = raw
+            .source
+            .into_iter()
+            .map(|(name, source)| {
+                let location = if let Some(directory) = source.directory {
+                    Some(SourceLocation::Directory(directory))
+                } else if let Some(local_registry) = source.local_registry {
+                    Some(SourceLocation::LocalRegistry(local_registry))
+                } else if let Some(registry) = source.registry {
+                    Some(SourceLocation::Registry(registry))
+                } else {
+                    source.git.map(SourceLocation::Git)
+                };
+                let config = SourceConfig {
+                    name: name.clone(),
+                    replace_with: source.replace_with,
+                    location,
+                };
+                (name, config)
+            })
+            .collect();
+
+        let target_overrides = raw
+            .target
+            .into_iter()
+            .map(|(key, target)| TargetOverride {
+                spec: parse_target_key(&key).expect("target keys were checked while parsing"),
+                key,
+                rustflags: target
+                    .rustflags
+                    .map(StringOrVec::into_vec)
+                    .unwrap_or_default(),
+                linker: target.linker,
+            })
+            .collect();
+
+        let patches = raw
+            .patch
+            .into_iter()
+            .flat_map(|(source, patches)| {
+                patches.into_iter().map(move |(name, patch)| ConfigPatch {
+                    source: source.clone(),
+                    name,
+                    package: patch.package,
+                    path: patch.path,
+                    git: patch.git,
+                    version: patch.version,
+                })
+            })
+            .collect();
+
+        Self {
+            files,
+            build_targets: raw
+                .build
+                .target
+                .map(StringOrVec::into_vec)
+                .unwrap_or_default(),
+            build_rustflags: raw
+                .build
+                .rustflags
+                .map(StringOrVec::into_vec)
+                .unwrap_or_default(),
+            sources,
+            target_overrides,
+            patches,
+        }
+    }
+
+    /// Returns the paths of the configuration files this was read from, in order of decreasing
+    /// precedence.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Returns true if no configuration files were found.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns the targets builds are for by default, as set by `build.target`.
+    ///
+    /// This is empty if `build.target` isn't set, in which case builds are for the host platform.
+    /// Entries may be paths to custom target specifications rather than triples.
+    pub fn build_targets(&self) -> &[String] {
+        &self.build_targets
+    }
+
+    /// Returns the platform builds are for by default, with the target features and flags set in
+    /// rustflags applied.
+    ///
+    /// Returns `None` if `build.target` isn't set, or if the first target isn't a triple known to
+    /// `target-spec`. `CargoOptions::with_cargo_config` uses this platform as the default target
+    /// platform.
+    pub fn target_platform<'a>(
+        &'a self,
+        target_features: TargetFeatures<'a>,
+    ) -> Option<Platform<'a>> {
+        let platform = Platform::new(self.build_targets.first()?, target_features)?;
+        Some(self.apply_rustflags(&platform))
+    }
+
+    /// Returns a copy of this platform with the `--cfg` flags and target features set by its
+    /// rustflags applied.
+    ///
+    /// Key-value cfgs like `--cfg foo="bar"` can't be represented by a `Platform`, so they're
+    /// skipped. Target features are only changed if the platform's features are known.
+    pub fn apply_rustflags<'a>(&'a self, platform: &Platform<'a>) -> Platform<'a> {
+        let rustflags = self.rustflags(platform);
+        let mut flags = vec![];
+        let mut target_features = platform.target_features().clone();
+        for (name, value) in rustflag_pairs(&rustflags) {
+            match name {
+                "--cfg" if !value.contains('=') => flags.push(value),
+                "-C" | "--codegen" if value.starts_with("target-feature=") => {
+                    if let TargetFeatures::Features(features) = &mut target_features {
+                        for feature in value["target-feature=".len()..].split(',') {
+                            if let Some(feature) = feature.strip_prefix('+') {
+                                features.insert(feature);
+                            } else if let Some(feature) = feature.strip_prefix('-') {
+                                features.remove(feature);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut platform = platform.clone();
+        platform.set_target_features(target_features);
+        platform.add_flags(&flags);
+        platform
+    }
+
+    /// Returns the rustflags Cargo passes to `rustc` when building for this platform.
+    ///
+    /// As with Cargo, these are the flags from every `[target]` table that matches the platform,
+    /// or if there are none, the flags from `build.rustflags`.
+    pub fn rustflags<'a>(&'a self, platform: &Platform<'_>) -> Vec<&'a str> {
+        let target_flags: Vec<&str> = self
+            .matching_overrides(platform)
+            .into_iter()
+            .flat_map(|target| target.rustflags.iter().map(|flag| flag.as_str()))
+            .collect();
+        if target_flags.is_empty() {
+            self.build_rustflags
+                .iter()
+                .map(|flag| flag.as_str())
+                .collect()
+        } else {
+            target_flags
+        }
+    }
+
+    /// Returns the `[target]` tables in this configuration, sorted by key.
+    pub fn target_overrides(&self) -> &[TargetOverride] {
+        &self.target_overrides
+    }
+
+    /// Returns the `[target]` tables that apply to this platform.
+    ///
+    /// The table for the platform's triple, if any, is returned first, followed by matching
+    /// `cfg()` tables sorted by key.
+    pub fn matching_overrides(&self, platform: &Platform<'_>) -> Vec<&TargetOverride> {
+        let (mut matching, by_cfg): (Vec<_>, Vec<_>) = self
+            .target_overrides
+            .iter()
+            .filter(|target| target.spec.eval(platform) == Some(true))
+            .partition(|target| !target.is_cfg());
+        matching.extend(by_cfg);
+        matching
+    }
+
+    /// Returns the source defined by the `[source]` table with this name, if any.
+    pub fn source(&self, name: impl AsRef<str>) -> Option<&SourceConfig> {
+        self.sources.get(name.as_ref())
+    }
+
+    /// Returns the sources defined in this configuration, sorted by name.
+    pub fn sources(&self) -> impl Iterator<Item = &SourceConfig> + ExactSizeIterator {
+        self.sources.values()
+    }
+
+    /// Returns the source that packages from this external source are actually fetched from, or
+    /// `None` if the source isn't replaced.
+    ///
+    /// `source` is in the format `cargo` uses, e.g.
+    /// `registry+https://github.com/rust-lang/crates.io-index`. Chains of `replace-with` keys are
+    /// followed to the end.
+    pub fn replaced_source(&self, source: &str) -> Option<&SourceConfig> {
+        let name = self.source_name(source)?;
+        let mut current = self.sources.get(name)?;
+        let mut steps = 0;
+        while let Some(replace_with) = &current.replace_with {
+            // Cargo rejects cycles: don't loop forever over them.
+            steps += 1;
+            if steps > self.sources.len() {
+                return None;
+            }
+            current = self.sources.get(replace_with)?;
+        }
+        if steps == 0 {
+            None
+        } else {
+            Some(current)
+        }
+    }
+
+    /// Returns the `[patch]` entries in this configuration, sorted by source and name.
+    pub fn patches(&self) -> &[ConfigPatch] {
+        &self.patches
+    }
+
+    /// Returns the `[patch]` entry in this configuration that this package was patched in by, if
+    /// any.
+    ///
+    /// Recent versions of Cargo apply patches from configuration files, so `cargo metadata`
+    /// reports the patched packages. This matches packages to the entries by path or git URL.
+    pub fn patch_for(&self, package: &PackageMetadata<'_>) -> Option<&ConfigPatch> {
+        let package_dir = package.manifest_path().parent()?;
+        self.patches.iter().find(|patch| {
+            if patch.package_name() != package.name() {
+                return false;
+            }
+            match (&patch.path, &patch.git, package.source()) {
+                (Some(path), _, source) if !source.is_external() => {
+                    normalize(path) == normalize(package_dir)
+                }
+                (None, Some(git), PackageSource::External(source)) => {
+                    source.starts_with("git+")
+                        && strip_url(&source["git+".len()..]) == strip_url(git)
+                }
+                _ => false,
+            }
+        })
+    }
+
+    /// Returns the name of the `[source]` table for this source string, if any.
+    fn source_name(&self, source: &str) -> Option<&str> {
+        if source == PackageSource::CRATES_IO_REGISTRY {
+            return Some(CRATES_IO_NAME);
+        }
+        let git_url = source.strip_prefix("git+").map(strip_url);
+        self.sources
+            .values()
+            .find(|config| match (&config.location, git_url) {
+                (Some(SourceLocation::Registry(url)), _) => {
+                    source.strip_prefix("registry+") == Some(url) || source == url
+                }
+                (Some(SourceLocation::Git(url)), Some(git_url)) => strip_url(url) == git_url,
+                _ => false,
+            })
+            .map(|config| config.name.as_str())
+    }
+}
+
+/// A source defined in a `[source]` table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SourceConfig {
+    name: String,
+    replace_with: Option<String>,
+    location: Option<SourceLocation>,
+}
+
+impl SourceConfig {
+    /// Returns the name of this source, e.g. `crates-io` or `vendored-sources`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the name of the source this source is replaced with, if any.
+    pub fn replace_with(&self) -> Option<&str> {
+        self.replace_with.as_deref()
+    }
+
+    /// Returns where packages from this source are found, if specified.
+    ///
+    /// This isn't specified for sources like `crates-io` that are only replaced.
+    pub fn location(&self) -> Option<&SourceLocation> {
+        self.location.as_ref()
+    }
+}
+
+/// Where packages from a source defined in a `[source]` table are found.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SourceLocation {
+    /// A directory of vendored packages, as created by `cargo vendor`.
+    Directory(PathBuf),
+    /// A local registry, as created by `cargo local-registry`.
+    LocalRegistry(PathBuf),
+    /// A remote registry with this index URL.
+    Registry(String),
+    /// A git repository with this URL.
+    Git(String),
+}
+
+/// A `[target]` table in a Cargo configuration file, keyed by a triple or a `cfg()` expression.
+#[derive(Clone, Debug)]
+pub struct TargetOverride {
+    key: String,
+    spec: TargetSpec,
+    rustflags: Vec<String>,
+    linker: Option<PathBuf>,
+}
+
+impl TargetOverride {
+    /// Returns the key of this table, e.g. `x86_64-unknown-linux-gnu` or `cfg(unix)`.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns the platforms this table applies to.
+    pub fn spec(&self) -> &TargetSpec {
+        &self.spec
+    }
+
+    /// Returns true if this table is keyed by a `cfg()` expression rather than a triple.
+    pub fn is_cfg(&self) -> bool {
+        self.key.starts_with("cfg(")
+    }
+
+    /// Returns the flags passed to `rustc` for the platforms this table applies to.
+    pub fn rustflags(&self) -> &[String] {
+        &self.rustflags
+    }
+
+    /// Returns the linker set for the platforms this table applies to, if any.
+    pub fn linker(&self) -> Option<&Path> {
+        self.linker.as_deref()
+    }
+}
+
+/// An entry in a `[patch]` table in a Cargo configuration file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigPatch {
+    source: String,
+    name: String,
+    package: Option<String>,
+    path: Option<PathBuf>,
+    git: Option<String>,
+    version: Option<String>,
+}
+
+impl ConfigPatch {
+    /// Returns the source being patched: either `crates-io` or a URL.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the name this patch is listed under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the name of the package this patch replaces dependencies with.
+    ///
+    /// This is the `package` key if specified, or the name it's listed under otherwise.
+    pub fn package_name(&self) -> &str {
+        self.package.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Returns the path the patched package is at, if it's a path dependency.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns the git repository the patched package is from, if it's a git dependency.
+    pub fn git(&self) -> Option<&str> {
+        self.git.as_deref()
+    }
+
+    /// Returns the version requirement for the patched package, if specified.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+}
+
+// ---
+// Helper functions
+// ---
+
+fn cargo_home() -> Option<PathBuf> {
+    if let Some(cargo_home) = env::var_os("CARGO_HOME") {
+        return Some(PathBuf::from(cargo_home));
+    }
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+    Some(Path::new(&home).join(".cargo"))
+}
+
+fn parse_target_key(key: &str) -> Result<TargetSpec, target_spec::ParseError> {
+    if key.starts_with("cfg(") {
+        key.parse()
+    } else {
+        // Custom targets are keyed by the name of their target specification.
+        Ok(key
+            .parse()
+            .unwrap_or_else(|_| TargetSpec::custom(key.to_string())))
+    }
+}
+
+/// Makes relative paths in this configuration file absolute.
+fn resolve_paths(value: &mut toml::Value, base_dir: &Path) {
+    let resolve = |value: Option<&mut toml::Value>| {
+        if let Some(toml::Value::String(path)) = value {
+            if Path::new(path.as_str()).is_relative() {
+                *path = base_dir.join(&path).to_string_lossy().into_owned();
+            }
+        }
+    };
+
+    if let Some(toml::Value::Table(sources)) = value.get_mut("source") {
+        for source in sources.iter_mut().map(|(_, value)| value) {
+            if let toml::Value::Table(source) = source {
+                resolve(source.get_mut("directory"));
+                resolve(source.get_mut("local-registry"));
+            }
+        }
+    }
+    if let Some(toml::Value::Table(patch_sources)) = value.get_mut("patch") {
+        for patches in patch_sources.iter_mut().map(|(_, value)| value) {
+            if let toml::Value::Table(patches) = patches {
+                for patch in patches.iter_mut().map(|(_, value)| value) {
+                    if let toml::Value::Table(patch) = patch {
+                        resolve(patch.get_mut("path"));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Turns rustflags specified as strings into arrays, so that they're concatenated with flags
+/// specified in other files.
+fn split_rustflags(value: &mut toml::Value) {
+    let split = |value: Option<&mut toml::Value>| {
+        if let Some(toml::Value::String(flags)) = value {
+            let flags = flags
+                .split_whitespace()
+                .map(|flag| toml::Value::String(flag.to_string()))
+                .collect();
+            *value.expect("value is Some") = toml::Value::Array(flags);
+        }
+    };
+
+    split(
+        value
+            .get_mut("build")
+            .and_then(|build| build.get_mut("rustflags")),
+    );
+    if let Some(toml::Value::Table(targets)) = value.get_mut("target") {
+        for (_, target) in targets.iter_mut() {
+            split(target.get_mut("rustflags"));
+        }
+    }
+}
+
+/// Merges `overlay` into `base`: tables are merged recursively, arrays are concatenated, and
+/// other values in `overlay` replace those in `base`.
+fn merge(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (toml::Value::Array(base), toml::Value::Array(overlay)) => base.extend(overlay),
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Splits rustflags into pairs of flag names and values, for flags like `--cfg foo`, `--cfg=foo`
+/// and `-Ctarget-feature=+avx`.
+fn rustflag_pairs<'a>(rustflags: &[&'a str]) -> Vec<(&'a str, &'a str)> {
+    let mut pairs = vec![];
+    let mut iter = rustflags.iter();
+    while let Some(flag) = iter.next() {
+        for name in &["--cfg", "--codegen", "-C"] {
+            if *flag == *name {
+                if let Some(value) = iter.next() {
+                    pairs.push((*name, *value));
+                }
+            } else if let Some(value) = flag.strip_prefix(name) {
+                // -Cfoo=bar, --cfg=foo and --codegen=foo=bar.
+                let value = if *name == "-C" {
+                    value
+                } else if let Some(value) = value.strip_prefix('=') {
+                    value
+                } else {
+                    continue;
+                };
+                pairs.push((*name, value));
+            } else {
+                continue;
+            }
+            break;
+        }
+    }
+    pairs
+}
+
+/// Strips the query and fragment from a URL, along with any trailing `/`.
+fn strip_url(url: &str) -> &str {
+    let end = url.find(&['?', '#'][..]).unwrap_or(url.len());
+    url[..end].trim_end_matches('/')
+}
+
+/// Lexically removes `.` and `..` components from a path.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawConfig {
+    #[serde(default)]
+    build: RawBuild,
+    #[serde(default)]
+    source: BTreeMap<String, RawSource>,
+    #[serde(default)]
+    target: BTreeMap<String, RawTarget>,
+    #[serde(default)]
+    patch: BTreeMap<String, BTreeMap<String, RawPatch>>,
+}
+
+#[derive(Default, Deserialize)]
+struct RawBuild {
+    target: Option<StringOrVec>,
+    rustflags: Option<StringOrVec>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawSource {
+    replace_with: Option<String>,
+    directory: Option<PathBuf>,
+    local_registry: Option<PathBuf>,
+    registry: Option<String>,
+    git: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawTarget {
+    rustflags: Option<StringOrVec>,
+    linker: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct RawPatch {
+    package: Option<String>,
+    path: Option<PathBuf>,
+    git: Option<String>,
+    version: Option<String>,
+}
+
+/// A value that's either a space-separated string or an array of strings.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrVec {
+    String(String),
+    Vec(Vec<String>),
+}
+
+impl StringOrVec {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            StringOrVec::String(s) => s.split_whitespace().map(String::from).collect(),
+            StringOrVec::Vec(v) => v,
+        }
+    }
+}
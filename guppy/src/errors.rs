@@ -6,6 +6,8 @@
 use crate::PackageId;
 use std::error;
 use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
 
 use Error::*;
 
@@ -27,8 +29,53 @@ pub enum Error {
     UnknownFeatureId(PackageId, Option<String>),
     /// A package specified by name was unknown to this workspace.
     UnknownWorkspaceName(String),
+    /// A package specification couldn't be parsed.
+    PackageSpecParseError(String, String),
+    /// A package name didn't follow the crates.io naming rules.
+    InvalidPackageName(String, String),
+    /// A feature name didn't follow the crates.io naming rules.
+    InvalidFeatureName(String, String),
     /// An error occured while computing a `CargoSet`.
     CargoSetError(String),
+    /// An error occurred while reading or interpreting a workspace member's `Cargo.toml`.
+    ManifestError {
+        /// The workspace member being processed.
+        package_id: PackageId,
+        /// The path to the manifest. This may be the workspace root's manifest if a field is
+        /// inherited from it.
+        manifest_path: PathBuf,
+        /// The underlying error.
+        source: Box<dyn error::Error + Send + Sync>,
+    },
+    /// An error occurred while reading or parsing a toolchain file.
+    ToolchainFileError(PathBuf, Box<dyn error::Error + Send + Sync>),
+    /// An error occurred while reading or parsing a Cargo configuration file.
+    CargoConfigError(PathBuf, Box<dyn error::Error + Send + Sync>),
+    /// An error occurred while reading or parsing an ownership file.
+    OwnershipFileError(PathBuf, Box<dyn error::Error + Send + Sync>),
+    /// An error occurred while reading a revision from the git repository at this path.
+    GitError(PathBuf, Box<dyn error::Error + Send + Sync>),
+    /// An error occurred while reading a license file for this package.
+    LicenseFileError(PackageId, PathBuf, io::Error),
+    /// A source of maintenance signals returned an error for this package.
+    ///
+    /// Only returned by the maintenance signal enrichment pass, which requires the `unstable`
+    /// feature.
+    MaintenanceSignalError(PackageId, Box<dyn error::Error + Send + Sync>),
+    /// An error occurred while reading this file to scan this package for `unsafe` code.
+    ///
+    /// Only returned by the unsafe usage scan, which requires the `unstable` feature.
+    UnsafeScanError(PackageId, PathBuf, io::Error),
+    /// A registry index source returned an error for the crate with this name.
+    ///
+    /// Only returned by the minimal-versions check, which requires the `unstable` feature.
+    IndexSourceError(String, Box<dyn error::Error + Send + Sync>),
+    /// The dependency budget declared by this package couldn't be parsed.
+    DependencyBudgetError(PackageId, serde_json::Error),
+    /// The build script inputs declared by this package couldn't be parsed.
+    BuildScriptMetadataError(PackageId, serde_json::Error),
+    /// A metadata extension couldn't parse this package's table under the given namespace.
+    MetadataExtensionError(PackageId, &'static str, Box<dyn error::Error + Send + Sync>),
     /// An internal error occurred within this `PackageGraph`.
     PackageGraphInternalError(String),
     /// An internal error occurred within this `FeatureGraph`.
@@ -39,6 +86,63 @@ impl Error {
     pub(crate) fn command_error(err: cargo_metadata::Error) -> Self {
         Error::CommandError(Box::new(err))
     }
+
+    pub(crate) fn manifest_error(
+        package_id: &PackageId,
+        manifest_path: impl Into<PathBuf>,
+        source: impl Into<Box<dyn error::Error + Send + Sync>>,
+    ) -> Self {
+        Error::ManifestError {
+            package_id: package_id.clone(),
+            manifest_path: manifest_path.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Returns the package this error is about, if any.
+    pub fn package_id(&self) -> Option<&PackageId> {
+        match self {
+            UnknownPackageId(package_id)
+            | UnknownFeatureId(package_id, _)
+            | ManifestError { package_id, .. }
+            | DependencyBudgetError(package_id, _)
+            | BuildScriptMetadataError(package_id, _)
+            | MetadataExtensionError(package_id, _, _)
+            | LicenseFileError(package_id, _, _)
+            | MaintenanceSignalError(package_id, _)
+            | UnsafeScanError(package_id, _, _) => Some(package_id),
+            CommandError(_)
+            | MetadataParseError(_)
+            | MetadataSerializeError(_)
+            | PackageGraphConstructError(_)
+            | UnknownWorkspaceName(_)
+            | PackageSpecParseError(_, _)
+            | InvalidPackageName(_, _)
+            | InvalidFeatureName(_, _)
+            | CargoSetError(_)
+            | ToolchainFileError(_, _)
+            | CargoConfigError(_, _)
+            | OwnershipFileError(_, _)
+            | GitError(_, _)
+            | PackageGraphInternalError(_)
+            | IndexSourceError(_, _)
+            | FeatureGraphInternalError(_) => None,
+        }
+    }
+
+    /// Returns the file this error is about, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ManifestError { manifest_path, .. } => Some(manifest_path),
+            ToolchainFileError(path, _) => Some(path),
+            CargoConfigError(path, _) => Some(path),
+            OwnershipFileError(path, _) => Some(path),
+            GitError(path, _) => Some(path),
+            LicenseFileError(_, path, _) => Some(path),
+            UnsafeScanError(_, path, _) => Some(path),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -64,7 +168,72 @@ impl fmt::Display for Error {
                 None => write!(f, "Unknown feature ID: '{}' (base)", package_id),
             },
             UnknownWorkspaceName(name) => write!(f, "Unknown workspace package name: {}", name),
+            PackageSpecParseError(spec, msg) => {
+                write!(f, "Error while parsing package spec '{}': {}", spec, msg)
+            }
+            InvalidPackageName(name, msg) => write!(f, "Invalid package name '{}': {}", name, msg),
+            InvalidFeatureName(name, msg) => write!(f, "Invalid feature name '{}': {}", name, msg),
             CargoSetError(msg) => write!(f, "Error while computing Cargo set: {}", msg),
+            ManifestError {
+                package_id,
+                manifest_path,
+                source,
+            } => write!(
+                f,
+                "Error while reading manifest {:?} for '{}': {}",
+                manifest_path, package_id, source
+            ),
+            ToolchainFileError(path, err) => {
+                write!(f, "Error while reading toolchain file {:?}: {}", path, err)
+            }
+            CargoConfigError(path, err) => write!(
+                f,
+                "Error while reading Cargo configuration file {:?}: {}",
+                path, err
+            ),
+            OwnershipFileError(path, err) => {
+                write!(f, "Error while reading ownership file {:?}: {}", path, err)
+            }
+            GitError(path, err) => {
+                write!(f, "Error while reading git repository {:?}: {}", path, err)
+            }
+            LicenseFileError(package_id, path, err) => write!(
+                f,
+                "Error while reading license file {:?} for '{}': {}",
+                path, package_id, err
+            ),
+            MaintenanceSignalError(package_id, err) => write!(
+                f,
+                "Error while fetching maintenance signals for '{}': {}",
+                package_id, err
+            ),
+            UnsafeScanError(package_id, path, err) => write!(
+                f,
+                "Error while scanning {:?} for unsafe code in '{}': {}",
+                path, package_id, err
+            ),
+            IndexSourceError(name, err) => {
+                write!(
+                    f,
+                    "Error while fetching index entries for '{}': {}",
+                    name, err
+                )
+            }
+            DependencyBudgetError(package_id, err) => write!(
+                f,
+                "Error while parsing dependency budget for '{}': {}",
+                package_id, err
+            ),
+            BuildScriptMetadataError(package_id, err) => write!(
+                f,
+                "Error while parsing build script inputs for '{}': {}",
+                package_id, err
+            ),
+            MetadataExtensionError(package_id, namespace, err) => write!(
+                f,
+                "Error while parsing [package.metadata.{}] for '{}': {}",
+                namespace, package_id, err
+            ),
             PackageGraphInternalError(msg) => write!(f, "Internal error in package graph: {}", msg),
             FeatureGraphInternalError(msg) => write!(f, "Internal error in feature graph: {}", msg),
         }
@@ -81,7 +250,22 @@ impl error::Error for Error {
             UnknownPackageId(_) => None,
             UnknownFeatureId(_, _) => None,
             UnknownWorkspaceName(_) => None,
+            PackageSpecParseError(_, _) => None,
+            InvalidPackageName(_, _) => None,
+            InvalidFeatureName(_, _) => None,
             CargoSetError(_) => None,
+            ManifestError { source, .. } => Some(source.as_ref()),
+            ToolchainFileError(_, err) => Some(err.as_ref()),
+            CargoConfigError(_, err) => Some(err.as_ref()),
+            OwnershipFileError(_, err) => Some(err.as_ref()),
+            GitError(_, err) => Some(err.as_ref()),
+            LicenseFileError(_, _, err) => Some(err),
+            MaintenanceSignalError(_, err) => Some(err.as_ref()),
+            UnsafeScanError(_, _, err) => Some(err),
+            IndexSourceError(_, err) => Some(err.as_ref()),
+            DependencyBudgetError(_, err) => Some(err),
+            BuildScriptMetadataError(_, err) => Some(err),
+            MetadataExtensionError(_, _, err) => Some(err.as_ref()),
             PackageGraphInternalError(_) => None,
             FeatureGraphInternalError(_) => None,
         }
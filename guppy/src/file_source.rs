@@ -0,0 +1,83 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A source of files read while constructing a `PackageGraph`.
+///
+/// Apart from the output of `cargo metadata`, `guppy` reads workspace members' `Cargo.toml` files
+/// while building a graph, along with toolchain and Cargo configuration files if requested. By
+/// default these are read from disk through `RealFileSource`, but another source can be passed
+/// into `PackageGraph::from_metadata_with_files`: for example, a `MemoryFileSource` populated
+/// from git objects or a tarball.
+///
+/// Paths are the absolute paths reported by `cargo metadata`.
+pub trait FileSource {
+    /// Reads the file at this path into a string.
+    ///
+    /// Returns an error of kind `io::ErrorKind::NotFound` if the file doesn't exist. Missing
+    /// files are expected: `guppy` checks for several optional files.
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+}
+
+impl<S: FileSource + ?Sized> FileSource for &S {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        (**self).read_to_string(path)
+    }
+}
+
+impl<S: FileSource + ?Sized> FileSource for Box<S> {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        (**self).read_to_string(path)
+    }
+}
+
+/// A `FileSource` that reads files from disk.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RealFileSource;
+
+impl FileSource for RealFileSource {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+}
+
+/// A `FileSource` backed by an in-memory map from paths to contents.
+///
+/// Paths not in the map are reported as missing.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MemoryFileSource {
+    files: BTreeMap<PathBuf, String>,
+}
+
+impl MemoryFileSource {
+    /// Creates a new, empty `MemoryFileSource`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file to this source, replacing any previous contents at this path.
+    pub fn insert(&mut self, path: impl Into<PathBuf>, contents: impl Into<String>) -> &mut Self {
+        self.files.insert(path.into(), contents.into());
+        self
+    }
+
+    /// Iterates over the paths in this source, in sorted order.
+    pub fn paths<'a>(&'a self) -> impl Iterator<Item = &'a Path> + 'a {
+        self.files.keys().map(|path| path.as_path())
+    }
+}
+
+impl FileSource for MemoryFileSource {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.files.get(path).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} not found in file source", path.display()),
+            )
+        })
+    }
+}
@@ -0,0 +1,325 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::PackageGraph;
+use crate::{CargoMetadata, Error, MemoryFileSource, MetadataCommand};
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+impl PackageGraph {
+    /// Constructs a `PackageGraph` for the workspace at `repo_path`, as it was at a git revision.
+    ///
+    /// `repo_path` is the root of the workspace, which may be a subdirectory of a git repository,
+    /// and `git_ref` is anything `git rev-parse` understands, e.g. `main`, `HEAD~3` or a commit
+    /// hash. The working tree is neither read nor changed.
+    ///
+    /// `cargo metadata` needs files on disk, so the manifests and lockfile at that revision are
+    /// written out to a temporary directory, along with empty placeholders for Rust source files
+    /// so that Cargo can discover targets. Paths in the resulting graph are rewritten to be under
+    /// `repo_path`, and manifests are read from git through a `MemoryFileSource` while building
+    /// the graph. Path dependencies outside `repo_path` aren't supported.
+    ///
+    /// This invokes the `git` and `cargo` binaries.
+    pub fn from_git_ref(
+        repo_path: impl AsRef<Path>,
+        git_ref: impl AsRef<str>,
+    ) -> Result<Self, Error> {
+        Self::from_git_ref_with_command(repo_path, git_ref, &mut MetadataCommand::new())
+    }
+
+    /// Constructs a `PackageGraph` for the workspace at `repo_path` as it was at a git revision,
+    /// running this `MetadataCommand`.
+    ///
+    /// This is useful to pass additional options to `cargo metadata`. The manifest path is
+    /// overridden to point at the revision's files. See `from_git_ref` for more.
+    pub fn from_git_ref_with_command(
+        repo_path: impl AsRef<Path>,
+        git_ref: impl AsRef<str>,
+        command: &mut MetadataCommand,
+    ) -> Result<Self, Error> {
+        let git = Git::open(repo_path.as_ref())?;
+        let commit = git.rev_parse(git_ref.as_ref())?;
+        let tree = git.tree(&commit)?;
+        git.build_graph(&tree, command)
+    }
+}
+
+/// Wraps invocations of a `git` binary in a repository containing a Cargo workspace.
+#[derive(Debug)]
+pub(crate) struct Git {
+    repo_path: PathBuf,
+}
+
+impl Git {
+    pub(crate) fn open(repo_path: &Path) -> Result<Self, Error> {
+        let canonical = repo_path
+            .canonicalize()
+            .map_err(|err| Error::GitError(repo_path.to_path_buf(), err.into()))?;
+        Ok(Self {
+            repo_path: canonical,
+        })
+    }
+
+    fn error(&self, err: impl Into<GitError>) -> Error {
+        Error::GitError(self.repo_path.clone(), err.into())
+    }
+
+    fn command(&self, args: &[impl AsRef<OsStr>]) -> Command {
+        let mut command = Command::new("git");
+        command.arg("-C").arg(&self.repo_path).args(args);
+        command
+    }
+
+    /// Resolves this ref to a commit hash.
+    pub(crate) fn rev_parse(&self, git_ref: &str) -> Result<String, Error> {
+        let spec = format!("{}^{{commit}}", git_ref);
+        let output = run(self.command(&["rev-parse", "--verify", "--quiet", &spec]))
+            .map_err(|_| self.error(format!("unknown revision '{}'", git_ref)))?;
+        Ok(String::from_utf8_lossy(&output).trim().to_string())
+    }
+
+    /// Lists the commits in this range, as understood by `git log`, oldest first.
+    ///
+    /// Only first parents are followed, so merged branches appear as single commits.
+    #[cfg(feature = "unstable")]
+    pub(crate) fn log(&self, range: &str) -> Result<Vec<GitCommit>, Error> {
+        let output = run(self.command(&[
+            "log",
+            "--reverse",
+            "--first-parent",
+            "--format=%H %ct %s",
+            range,
+            "--",
+        ]))
+        .map_err(|err| self.error(err))?;
+        String::from_utf8_lossy(&output)
+            .lines()
+            .map(|line| {
+                let mut parts = line.splitn(3, ' ');
+                let hash = parts.next().unwrap_or_default();
+                let time = parts.next().and_then(|time| time.parse().ok());
+                match time {
+                    Some(time) => Ok(GitCommit {
+                        hash: hash.to_string(),
+                        time,
+                        summary: parts.next().unwrap_or_default().to_string(),
+                    }),
+                    None => Err(self.error(format!("unexpected git log output: {}", line))),
+                }
+            })
+            .collect()
+    }
+
+    /// Lists the files in this commit that are needed to build a `PackageGraph`.
+    pub(crate) fn tree(&self, commit: &str) -> Result<GitTree, Error> {
+        // Without --full-tree, paths are limited to and relative to the current directory.
+        let output =
+            run(self.command(&["ls-tree", "-r", "-z", commit])).map_err(|err| self.error(err))?;
+        let mut read = vec![];
+        let mut placeholders = vec![];
+        for line in output.split(|b| *b == 0).filter(|line| !line.is_empty()) {
+            let entry = match TreeEntry::parse(&String::from_utf8_lossy(line)) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            if entry.is_read() {
+                read.push(entry);
+            } else if entry.is_placeholder() {
+                placeholders.push(entry);
+            }
+        }
+        Ok(GitTree { read, placeholders })
+    }
+
+    /// Runs `cargo metadata` against the files in this tree, and builds a graph from it.
+    pub(crate) fn build_graph(
+        &self,
+        tree: &GitTree,
+        command: &mut MetadataCommand,
+    ) -> Result<PackageGraph, Error> {
+        let contents = self.cat_blobs(&tree.read).map_err(|err| self.error(err))?;
+
+        let temp_dir = tempfile::Builder::new()
+            .prefix("guppy-git-")
+            .tempdir()
+            .map_err(|err| self.error(err))?;
+        let temp_path = temp_dir
+            .path()
+            .canonicalize()
+            .map_err(|err| self.error(err))?;
+
+        let mut files = MemoryFileSource::new();
+        let write_file = |path: &Path, contents: &str| -> io::Result<()> {
+            let temp_file = temp_path.join(path);
+            if let Some(parent) = temp_file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(temp_file, contents)
+        };
+        for (entry, contents) in tree.read.iter().zip(contents) {
+            write_file(&entry.path, &contents).map_err(|err| self.error(err))?;
+            files.insert(self.repo_path.join(&entry.path), contents);
+        }
+        for entry in &tree.placeholders {
+            write_file(&entry.path, "").map_err(|err| self.error(err))?;
+        }
+
+        let metadata = command.manifest_path(temp_path.join("Cargo.toml")).exec()?;
+        let metadata = rewrite_paths(&metadata, &temp_path, &self.repo_path)?;
+        PackageGraph::from_metadata_with_files(metadata, &files)
+    }
+
+    /// Reads the contents of these blobs, in order.
+    fn cat_blobs(&self, entries: &[TreeEntry]) -> Result<Vec<String>, GitError> {
+        let mut child = self
+            .command(&["cat-file", "--batch"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let input: String = entries
+            .iter()
+            .map(|entry| format!("{}\n", entry.object))
+            .collect();
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        // Write from another thread so that a full stdout pipe can't deadlock us.
+        let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let output = child.wait_with_output()?;
+        writer.join().expect("writer thread doesn't panic")?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().into());
+        }
+
+        // Each blob is output as "<object> blob <size>\n<contents>\n".
+        let mut remaining = &output.stdout[..];
+        let mut contents = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let header_end = remaining
+                .iter()
+                .position(|b| *b == b'\n')
+                .ok_or("truncated cat-file output")?;
+            let header = String::from_utf8_lossy(&remaining[..header_end]);
+            let size: usize = match header.split(' ').collect::<Vec<_>>().as_slice() {
+                [_, "blob", size] => size.parse()?,
+                _ => return Err(format!("unexpected cat-file output: {}", header).into()),
+            };
+            let start = header_end + 1;
+            let blob = remaining
+                .get(start..start + size)
+                .ok_or("truncated cat-file output")?;
+            let blob = String::from_utf8(blob.to_vec())
+                .map_err(|_| format!("{} is not valid UTF-8", entry.path.display()))?;
+            contents.push(blob);
+            remaining = remaining.get(start + size + 1..).unwrap_or(&[]);
+        }
+        Ok(contents)
+    }
+}
+
+/// A commit listed by `Git::log`.
+#[cfg(feature = "unstable")]
+#[derive(Clone, Debug)]
+pub(crate) struct GitCommit {
+    pub(crate) hash: String,
+    /// The committer time, in seconds since the Unix epoch.
+    pub(crate) time: i64,
+    pub(crate) summary: String,
+}
+
+/// The files in a commit that are needed to build a `PackageGraph`.
+#[derive(Clone, Debug)]
+pub(crate) struct GitTree {
+    // Manifests, the lockfile and other files whose contents are read.
+    read: Vec<TreeEntry>,
+    // Rust source files, written out empty so that Cargo can discover targets.
+    placeholders: Vec<TreeEntry>,
+}
+
+#[cfg(feature = "unstable")]
+impl GitTree {
+    /// Returns a key made up of the paths and object IDs of the files whose contents are read.
+    ///
+    /// Trees with the same manifest key have the same dependency graph, though build targets may
+    /// differ.
+    pub(crate) fn manifest_key(&self) -> String {
+        self.read
+            .iter()
+            .map(|entry| format!("{} {}\n", entry.object, entry.path.display()))
+            .collect()
+    }
+}
+
+/// Rewrites every occurrence of `from` in this metadata to `to`.
+fn rewrite_paths(metadata: &CargoMetadata, from: &Path, to: &Path) -> Result<CargoMetadata, Error> {
+    let mut json = vec![];
+    metadata.serialize(&mut json)?;
+    let json = String::from_utf8(json).expect("serde_json produces valid UTF-8");
+
+    // Paths must be escaped the same way they are in the JSON. Package IDs for path dependencies
+    // have URLs like "path+file:///tmp/...", which contain the path as well.
+    let escape = |path: &Path| {
+        let escaped = serde_json::to_string(&path.to_string_lossy()).expect("strings serialize");
+        escaped[1..escaped.len() - 1].to_string()
+    };
+    CargoMetadata::parse_json(json.replace(&escape(from), &escape(to)))
+}
+
+type GitError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Runs a command, returning its standard output if it succeeded.
+fn run(mut command: Command) -> Result<Vec<u8>, GitError> {
+    let output = command.stderr(Stdio::piped()).output()?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().into())
+    }
+}
+
+/// A file in a git tree.
+#[derive(Clone, Debug)]
+struct TreeEntry {
+    object: String,
+    path: PathBuf,
+}
+
+impl TreeEntry {
+    /// Parses a line of the form "<mode> <type> <object>\t<path>". Only regular files are
+    /// returned: symlinks and submodules are skipped.
+    fn parse(line: &str) -> Option<Self> {
+        let tab = line.find('\t')?;
+        let (meta, path) = (&line[..tab], &line[tab + 1..]);
+        match meta.split(' ').collect::<Vec<_>>().as_slice() {
+            ["100644", "blob", object] | ["100755", "blob", object] => Some(Self {
+                object: object.to_string(),
+                path: path.into(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns true if this file is read for `cargo metadata` or while building the graph.
+    fn is_read(&self) -> bool {
+        let file_name = self.path.file_name().and_then(|name| name.to_str());
+        match file_name {
+            Some("Cargo.toml") | Some("rust-toolchain") | Some("rust-toolchain.toml") => true,
+            // Root lockfile and Cargo configuration.
+            Some("Cargo.lock") => self.path == Path::new("Cargo.lock"),
+            Some("config") | Some("config.toml") => {
+                self.path.parent().and_then(|parent| parent.file_name())
+                    == Some(OsStr::new(".cargo"))
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if an empty file is written out for target discovery.
+    fn is_placeholder(&self) -> bool {
+        self.path.extension() == Some(OsStr::new("rs"))
+    }
+}
@@ -0,0 +1,466 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Anonymizing graphs so they can be shared.
+
+use crate::graph::{PackageGraph, PackageSource};
+use crate::{CargoMetadata, Error};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+impl PackageGraph {
+    /// Returns `cargo metadata` output for this graph with identifying information scrubbed out.
+    ///
+    /// This is meant for sharing the structure of a private workspace, for example in bug reports.
+    /// Packages are considered *private* unless they come from crates.io. Anonymization:
+    /// * renames private packages, along with any crates.io packages that happen to share a name
+    ///   with them,
+    /// * replaces the workspace root, manifest paths and source paths with placeholders,
+    /// * replaces sources other than crates.io, such as private registries and git repositories,
+    ///   with placeholder URLs, and
+    /// * removes authors, descriptions, repository URLs, readmes, license files, keywords, `links`
+    ///   values and metadata tables from private packages.
+    ///
+    /// The topology of the graph, versions, features, dependency kinds and platform specs are
+    /// preserved. The output is deterministic given the graph and the seed, and different seeds
+    /// produce different names.
+    pub fn anonymize(&self, seed: u64) -> Result<CargoMetadata, Error> {
+        let metadata = self.to_cargo_metadata()?;
+        let mut value = serde_json::to_value(&metadata.0).map_err(Error::MetadataSerializeError)?;
+        Scrubber::new(seed, &value).scrub(&mut value);
+        serde_json::from_value(value)
+            .map(CargoMetadata)
+            .map_err(Error::MetadataSerializeError)
+    }
+}
+
+const WORKSPACE_ROOT: &str = "/workspace";
+
+struct Scrubber {
+    seed: u64,
+    workspace_root: String,
+    // Package names that need to be renamed.
+    private_names: BTreeSet<String>,
+    names: HashMap<String, String>,
+    used_names: HashSet<String>,
+    sources: HashMap<String, String>,
+    // Maps old package IDs to new ones.
+    ids: HashMap<String, String>,
+}
+
+impl Scrubber {
+    fn new(seed: u64, value: &Value) -> Self {
+        let packages = packages(value);
+        let private_names = packages
+            .iter()
+            .filter(|package| !is_public(package))
+            .map(|package| str_field(package, "name").to_string())
+            .collect();
+        // Never produce a name that's already taken by a public package.
+        let used_names = packages
+            .iter()
+            .map(|package| str_field(package, "name").to_string())
+            .collect();
+
+        let mut scrubber = Self {
+            seed,
+            workspace_root: str_field(value, "workspace_root").to_string(),
+            private_names,
+            names: HashMap::new(),
+            used_names,
+            sources: HashMap::new(),
+            ids: HashMap::new(),
+        };
+
+        // Assign names in sorted order, so that the result doesn't depend on hash map ordering.
+        for name in scrubber.private_names.clone() {
+            scrubber.name(&name);
+        }
+        let workspace_members: HashSet<_> = value["workspace_members"]
+            .as_array()
+            .expect("workspace_members is an array")
+            .iter()
+            .map(|id| id.as_str().expect("package IDs are strings").to_string())
+            .collect();
+        for package in packages {
+            let old_id = str_field(package, "id").to_string();
+            let in_workspace = workspace_members.contains(&old_id);
+            let new_id = format!(
+                "{} {} ({})",
+                scrubber.name(str_field(package, "name")),
+                str_field(package, "version"),
+                scrubber.source_repr(package, in_workspace)
+            );
+            scrubber.ids.insert(old_id, new_id);
+        }
+        scrubber
+    }
+
+    fn scrub(&mut self, value: &mut Value) {
+        let workspace_members: HashSet<_> = value["workspace_members"]
+            .as_array()
+            .expect("workspace_members is an array")
+            .iter()
+            .map(|id| id.as_str().expect("package IDs are strings").to_string())
+            .collect();
+
+        // Local dependency names for each package, mapped to their new names.
+        let mut aliases = HashMap::new();
+        let mut lib_names = HashMap::new();
+        for package in value["packages"]
+            .as_array_mut()
+            .expect("packages is an array")
+        {
+            let old_id = str_field(package, "id").to_string();
+            let in_workspace = workspace_members.contains(&old_id);
+            let (package_aliases, lib_name) = self.scrub_package(package, in_workspace);
+            aliases.insert(old_id.clone(), package_aliases);
+            lib_names.insert(old_id, lib_name);
+        }
+
+        for node in value["resolve"]["nodes"]
+            .as_array_mut()
+            .expect("nodes is an array")
+        {
+            let old_id = str_field(node, "id").to_string();
+            let package_aliases = &aliases[&old_id];
+            node["id"] = json!(self.ids[&old_id]);
+            for dep in node["deps"].as_array_mut().expect("deps is an array") {
+                let pkg = str_field(dep, "pkg").to_string();
+                let resolved_name = str_field(dep, "name");
+                let new_name = package_aliases
+                    .get(resolved_name)
+                    .map(|alias| alias.replace('-', "_"))
+                    .or_else(|| lib_names[&pkg].clone())
+                    .unwrap_or_else(|| resolved_name.to_string());
+                dep["name"] = json!(new_name);
+                dep["pkg"] = json!(self.ids[&pkg]);
+            }
+            let dependencies: Vec<_> = node["dependencies"]
+                .as_array()
+                .expect("dependencies is an array")
+                .iter()
+                .map(|id| json!(self.ids[id.as_str().expect("package IDs are strings")]))
+                .collect();
+            node["dependencies"] = json!(dependencies);
+            let features: Vec<_> = node["features"]
+                .as_array()
+                .expect("features is an array")
+                .iter()
+                .map(|feature| {
+                    let feature = feature.as_str().expect("features are strings");
+                    json!(package_aliases
+                        .get(feature)
+                        .map(String::as_str)
+                        .unwrap_or(feature))
+                })
+                .collect();
+            node["features"] = json!(features);
+        }
+
+        let members: Vec<_> = workspace_members
+            .iter()
+            .map(|id| self.ids[id].clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        value["workspace_members"] = json!(members);
+        value["workspace_root"] = json!(WORKSPACE_ROOT);
+        value["target_directory"] = json!(format!("{}/target", WORKSPACE_ROOT));
+    }
+
+    /// Scrubs this package, returning a map of its local dependency names to their new names, and
+    /// the new name of its library target if it was renamed.
+    fn scrub_package(
+        &mut self,
+        package: &mut Value,
+        in_workspace: bool,
+    ) -> (HashMap<String, String>, Option<String>) {
+        let public = is_public(package);
+        let old_name = str_field(package, "name").to_string();
+        let new_name = self.name(&old_name);
+        let old_dir = Path::new(str_field(package, "manifest_path"))
+            .parent()
+            .expect("manifest paths have a parent")
+            .to_path_buf();
+        let new_dir = self.package_dir(package, in_workspace);
+
+        let mut aliases = HashMap::new();
+        for dep in package["dependencies"]
+            .as_array_mut()
+            .expect("dependencies is an array")
+        {
+            let dep_name = str_field(dep, "name").to_string();
+            let new_dep_name = self.name(&dep_name);
+            let (old_local, new_local) = match dep["rename"].as_str() {
+                Some(rename) if rename != dep_name => {
+                    let new_rename = if public {
+                        rename.to_string()
+                    } else {
+                        self.hashed("rename", rename, "dep")
+                    };
+                    (rename.to_string(), new_rename)
+                }
+                _ => (dep_name.clone(), new_dep_name.clone()),
+            };
+            if dep["rename"].is_string() {
+                dep["rename"] = json!(new_local);
+            }
+            dep["name"] = json!(new_dep_name);
+            if let Some(source) = dep["source"].as_str() {
+                dep["source"] = json!(self.source(source));
+            }
+            if let Some(registry) = dep["registry"].as_str() {
+                dep["registry"] = json!(self.registry(registry));
+            }
+            if old_local != new_local {
+                aliases.insert(old_local.replace('-', "_"), new_local.clone());
+                aliases.insert(old_local, new_local);
+            }
+        }
+
+        let features = package["features"]
+            .as_object()
+            .expect("features is an object")
+            .iter()
+            .map(|(feature, values)| {
+                let values: Vec<_> = values
+                    .as_array()
+                    .expect("feature values are an array")
+                    .iter()
+                    .map(|value| {
+                        json!(rename_feature_value(
+                            value.as_str().expect("feature values are strings"),
+                            &aliases
+                        ))
+                    })
+                    .collect();
+                (feature.clone(), json!(values))
+            })
+            .collect::<Map<_, _>>();
+        package["features"] = Value::Object(features);
+
+        let renamed = old_name != new_name;
+        let mut lib_name = None;
+        for (idx, target) in package["targets"]
+            .as_array_mut()
+            .expect("targets is an array")
+            .iter_mut()
+            .enumerate()
+        {
+            let kinds: Vec<_> = target["kind"]
+                .as_array()
+                .expect("kind is an array")
+                .iter()
+                .map(|kind| kind.as_str().expect("kinds are strings").to_string())
+                .collect();
+            let is_lib = !kinds.iter().any(|kind| {
+                matches!(
+                    kind.as_str(),
+                    "custom-build" | "bin" | "example" | "test" | "bench"
+                )
+            });
+            if is_lib && renamed {
+                let name = new_name.replace('-', "_");
+                target["name"] = json!(name);
+                lib_name = Some(name);
+            } else if !public && kinds.iter().any(|kind| kind == "custom-build") {
+                target["name"] = json!("build-script-build");
+            } else if !public && !is_lib {
+                let name = format!("{}-{}", kinds.join("-"), idx);
+                target["name"] = json!(name);
+            }
+
+            let old_path = str_field(target, "src_path").to_string();
+            let relative = if public {
+                Path::new(&old_path)
+                    .strip_prefix(&old_dir)
+                    .ok()
+                    .map(|path| path.display().to_string())
+            } else {
+                None
+            };
+            let relative = relative.unwrap_or_else(|| {
+                format!("src/{}.rs", str_field(target, "name").replace('-', "_"))
+            });
+            target["src_path"] = json!(format!("{}/{}", new_dir, relative));
+        }
+
+        package["name"] = json!(new_name);
+        package["id"] = json!(self.ids[str_field(package, "id")]);
+        package["manifest_path"] = json!(format!("{}/Cargo.toml", new_dir));
+        if let Some(source) = package["source"].as_str() {
+            package["source"] = json!(self.source(source));
+        }
+        if !public {
+            package["authors"] = json!([]);
+            package["keywords"] = json!([]);
+            for field in &[
+                "description",
+                "repository",
+                "readme",
+                "license_file",
+                "metadata",
+            ] {
+                package[*field] = Value::Null;
+            }
+            if let Some(links) = package["links"].as_str() {
+                package["links"] = json!(self.hashed("links", links, "native"));
+            }
+        }
+
+        (aliases, lib_name)
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn name(&mut self, name: &str) -> String {
+        if !self.private_names.contains(name) {
+            return name.to_string();
+        }
+        if let Some(new_name) = self.names.get(name) {
+            return new_name.clone();
+        }
+        let mut attempt = 0;
+        let new_name = loop {
+            let candidate = format!(
+                "crate-{:08x}",
+                self.hash(&[b"name", &[attempt], name.as_bytes()]) as u32
+            );
+            if self.used_names.insert(candidate.clone()) {
+                break candidate;
+            }
+            attempt += 1;
+        };
+        self.names.insert(name.to_string(), new_name.clone());
+        new_name
+    }
+
+    fn source(&mut self, source: &str) -> String {
+        if source == PackageSource::CRATES_IO_REGISTRY {
+            return source.to_string();
+        }
+        if let Some(new_source) = self.sources.get(source) {
+            return new_source.clone();
+        }
+        let new_source = if source.starts_with("registry+") {
+            let hash = self.hash(&[b"source", source.as_bytes()]) as u32;
+            format!("registry+https://registry-{:08x}.invalid/index", hash)
+        } else if source.starts_with("git+") {
+            // Declarations refer to the repository, while packages also include the locked
+            // commit, so hash them separately. Queries like `?branch=` are dropped.
+            let (repo, rev) = match source.find('#') {
+                Some(idx) => (&source[..idx], Some(&source[idx + 1..])),
+                None => (source, None),
+            };
+            let repo = repo.split('?').next().unwrap_or(repo);
+            let hash = self.hash(&[b"source", repo.as_bytes()]) as u32;
+            match rev {
+                Some(rev) => format!(
+                    "git+https://git-{:08x}.invalid/repo#{:016x}",
+                    hash,
+                    self.hash(&[b"rev", rev.as_bytes()])
+                ),
+                None => format!("git+https://git-{:08x}.invalid/repo", hash),
+            }
+        } else {
+            let hash = self.hash(&[b"source", source.as_bytes()]) as u32;
+            format!("unknown+https://source-{:08x}.invalid", hash)
+        };
+        self.sources.insert(source.to_string(), new_source.clone());
+        new_source
+    }
+
+    fn registry(&mut self, registry: &str) -> String {
+        let source = self.source(&format!("registry+{}", registry));
+        source
+            .strip_prefix("registry+")
+            .unwrap_or(&source)
+            .to_string()
+    }
+
+    /// Returns the source string for this package as it appears in its package ID.
+    fn source_repr(&mut self, package: &Value, in_workspace: bool) -> String {
+        match package["source"].as_str() {
+            Some(source) => self.source(source),
+            None => format!("path+file://{}", self.package_dir(package, in_workspace)),
+        }
+    }
+
+    fn package_dir(&mut self, package: &Value, in_workspace: bool) -> String {
+        let name = self.name(str_field(package, "name"));
+        let version = str_field(package, "version");
+        if in_workspace {
+            let manifest_dir = Path::new(str_field(package, "manifest_path")).parent();
+            if manifest_dir == Some(Path::new(&self.workspace_root)) {
+                WORKSPACE_ROOT.to_string()
+            } else {
+                format!("{}/{}", WORKSPACE_ROOT, name)
+            }
+        } else if package["source"].is_null() {
+            format!("/path/{}-{}", name, version)
+        } else {
+            format!("/registry/{}-{}", name, version)
+        }
+    }
+
+    fn hashed(&self, tag: &str, input: &str, prefix: &str) -> String {
+        format!(
+            "{}-{:08x}",
+            prefix,
+            self.hash(&[tag.as_bytes(), input.as_bytes()]) as u32
+        )
+    }
+
+    /// A seeded FNV-1a hash, which unlike the standard library's hashers is stable across Rust
+    /// versions and platforms.
+    fn hash(&self, parts: &[&[u8]]) -> u64 {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+        let mut hash = OFFSET;
+        for byte in self.seed.to_le_bytes().iter() {
+            hash = (hash ^ u64::from(*byte)).wrapping_mul(PRIME);
+        }
+        for part in parts {
+            // Separate parts so that ("ab", "c") and ("a", "bc") hash differently.
+            for byte in part.iter().chain(&[0xff]) {
+                hash = (hash ^ u64::from(*byte)).wrapping_mul(PRIME);
+            }
+        }
+        hash
+    }
+}
+
+fn packages(value: &Value) -> &[Value] {
+    value["packages"].as_array().expect("packages is an array")
+}
+
+fn str_field<'a>(value: &'a Value, field: &str) -> &'a str {
+    value[field]
+        .as_str()
+        .unwrap_or_else(|| panic!("field '{}' is a string", field))
+}
+
+fn is_public(package: &Value) -> bool {
+    package["source"].as_str() == Some(PackageSource::CRATES_IO_REGISTRY)
+}
+
+/// Renames the dependency referred to by a feature value such as `dep`, `dep/feature`,
+/// `dep?/feature` or `dep:dep`.
+fn rename_feature_value(value: &str, aliases: &HashMap<String, String>) -> String {
+    let (prefix, rest) = match value.strip_prefix("dep:") {
+        Some(rest) => ("dep:", rest),
+        None => ("", value),
+    };
+    let (dep, suffix) = match rest.find(&['/', '?'][..]) {
+        Some(idx) => rest.split_at(idx),
+        None => (rest, ""),
+    };
+    match aliases.get(dep) {
+        Some(new_dep) => format!("{}{}{}", prefix, new_dep, suffix),
+        None => value.to_string(),
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Run an analysis over many package graphs in parallel.
+//!
+//! Tools like dependency dashboards need to look at many workspaces at once: every repository in
+//! an organization, or a workspace at several revisions. A `BatchRunner` loads the graph for each
+//! `BatchSource` on a pool of worker threads, runs an analysis over it, and collects the results
+//! in the order the sources were added.
+//!
+//! Each worker loads one graph at a time and drops it once the analysis has run, so at most
+//! `jobs` graphs are in memory at once. Only the analysis results are kept.
+//!
+//! This module is experimental, and requires the `unstable` feature to be enabled.
+
+use crate::graph::PackageGraph;
+use crate::{Error, MetadataCommand};
+use std::fmt;
+use std::panic;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+/// A workspace to build a package graph for.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum BatchSource {
+    /// A workspace on disk, identified by the path to its `Cargo.toml` or that of any of its
+    /// members.
+    Manifest(PathBuf),
+
+    /// A workspace at a git revision, built with `PackageGraph::from_git_ref_with_command`.
+    GitRef {
+        /// The path to the repository.
+        repo_path: PathBuf,
+        /// The revision to build a graph for.
+        git_ref: String,
+    },
+}
+
+impl BatchSource {
+    /// Creates a new source for the workspace at this manifest path.
+    pub fn manifest(path: impl Into<PathBuf>) -> Self {
+        BatchSource::Manifest(path.into())
+    }
+
+    /// Creates a new source for the workspace in this repository, at this revision.
+    pub fn git_ref(repo_path: impl Into<PathBuf>, git_ref: impl Into<String>) -> Self {
+        BatchSource::GitRef {
+            repo_path: repo_path.into(),
+            git_ref: git_ref.into(),
+        }
+    }
+
+    fn load(&self, command: &MetadataCommand) -> Result<PackageGraph, Error> {
+        let mut command = command.clone();
+        match self {
+            BatchSource::Manifest(path) => command.manifest_path(path).build_graph(),
+            BatchSource::GitRef { repo_path, git_ref } => {
+                PackageGraph::from_git_ref_with_command(repo_path, git_ref, &mut command)
+            }
+        }
+    }
+}
+
+impl fmt::Display for BatchSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchSource::Manifest(path) => write!(f, "{}", path.display()),
+            BatchSource::GitRef { repo_path, git_ref } => {
+                write!(f, "{}@{}", repo_path.display(), git_ref)
+            }
+        }
+    }
+}
+
+/// Loads package graphs for a set of sources and runs an analysis over each one in parallel.
+///
+/// ## Examples
+///
+/// Count the packages in several workspaces, two at a time:
+///
+/// ```rust,no_run
+/// use guppy::graph::batch::{BatchRunner, BatchSource};
+/// use guppy::MetadataCommand;
+///
+/// let mut runner = BatchRunner::new(MetadataCommand::new());
+/// runner
+///     .jobs(2)
+///     .add_source(BatchSource::manifest("/src/foo/Cargo.toml"))
+///     .add_source(BatchSource::git_ref("/src/bar", "v1.0"));
+///
+/// for result in runner.run(|_source, graph| graph.package_count()) {
+///     match result.result() {
+///         Ok(count) => println!("{}: {} packages", result.source(), count),
+///         Err(err) => eprintln!("{}: error: {}", result.source(), err),
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct BatchRunner {
+    command: MetadataCommand,
+    jobs: usize,
+    sources: Vec<BatchSource>,
+}
+
+impl BatchRunner {
+    /// Creates a new runner with no sources.
+    ///
+    /// `command` is used as a template for every source: its manifest path is overridden for each
+    /// one, but other options like the path to `cargo` are kept.
+    ///
+    /// By default, one job is run per CPU.
+    pub fn new(command: MetadataCommand) -> Self {
+        Self {
+            command,
+            jobs: num_cpus::get(),
+            sources: vec![],
+        }
+    }
+
+    /// Sets the number of graphs to load and analyze at once.
+    ///
+    /// This also bounds the number of graphs in memory at any time. A value of 0 is treated as 1.
+    pub fn jobs(&mut self, jobs: usize) -> &mut Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// Adds a source to this runner.
+    pub fn add_source(&mut self, source: BatchSource) -> &mut Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Adds several sources to this runner.
+    pub fn add_sources(&mut self, sources: impl IntoIterator<Item = BatchSource>) -> &mut Self {
+        self.sources.extend(sources);
+        self
+    }
+
+    /// Returns the sources added to this runner, in order.
+    pub fn sources(&self) -> &[BatchSource] {
+        &self.sources
+    }
+
+    /// Loads the graph for every source and runs `analysis` over it.
+    ///
+    /// Returns one result per source, in the order the sources were added. If a graph couldn't be
+    /// loaded, its result is the error and the other sources are still processed.
+    ///
+    /// `analysis` is called from worker threads, so it must be `Send`, `Sync` and `'static`. To
+    /// share data with it, wrap the data in an `Arc`.
+    ///
+    /// If `analysis` panics, the panic is propagated to the caller once the workers have stopped.
+    pub fn run<T, F>(&self, analysis: F) -> Vec<BatchResult<T>>
+    where
+        F: Fn(&BatchSource, &PackageGraph) -> T + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        let sources = Arc::new(self.sources.clone());
+        let next = Arc::new(AtomicUsize::new(0));
+        let analysis = Arc::new(analysis);
+        let (sender, receiver) = mpsc::channel();
+
+        let jobs = self.jobs.min(sources.len());
+        let handles: Vec<_> = (0..jobs)
+            .map(|_| {
+                let sources = sources.clone();
+                let next = next.clone();
+                let analysis = analysis.clone();
+                let command = self.command.clone();
+                let sender = sender.clone();
+                thread::spawn(move || loop {
+                    let idx = next.fetch_add(1, Ordering::SeqCst);
+                    let source = match sources.get(idx) {
+                        Some(source) => source,
+                        None => break,
+                    };
+                    // The graph is dropped at the end of this statement, before the next source
+                    // is loaded.
+                    let result = source.load(&command).map(|graph| analysis(source, &graph));
+                    if sender.send((idx, result)).is_err() {
+                        break;
+                    }
+                })
+            })
+            .collect();
+        // Drop the original sender so that the receiver stops once every worker is done.
+        drop(sender);
+
+        let mut results: Vec<_> = sources.iter().map(|_| None).collect();
+        for (idx, result) in receiver {
+            results[idx] = Some(result);
+        }
+        for handle in handles {
+            if let Err(payload) = handle.join() {
+                panic::resume_unwind(payload);
+            }
+        }
+
+        sources
+            .iter()
+            .zip(results)
+            .map(|(source, result)| BatchResult {
+                source: source.clone(),
+                result: result.expect("every source was processed"),
+            })
+            .collect()
+    }
+}
+
+/// The result of running an analysis over a single source, returned by `BatchRunner::run`.
+#[derive(Debug)]
+pub struct BatchResult<T> {
+    source: BatchSource,
+    result: Result<T, Error>,
+}
+
+impl<T> BatchResult<T> {
+    /// Returns the source this result is for.
+    pub fn source(&self) -> &BatchSource {
+        &self.source
+    }
+
+    /// Returns the result of the analysis, or the error that occurred while loading the graph.
+    pub fn result(&self) -> Result<&T, &Error> {
+        self.result.as_ref()
+    }
+
+    /// Consumes this result, returning the result of the analysis or the error that occurred
+    /// while loading the graph.
+    pub fn into_result(self) -> Result<T, Error> {
+        self.result
+    }
+}
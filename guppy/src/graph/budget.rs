@@ -0,0 +1,402 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Dependency budgets for workspace members.
+//!
+//! A workspace member can declare limits on its dependencies in its `Cargo.toml`:
+//!
+//! ```toml
+//! [package.metadata.guppy.budget]
+//! # The maximum number of direct dependencies.
+//! max-direct-deps = 10
+//! # The maximum number of packages this package transitively depends on.
+//! max-transitive-deps = 100
+//! # Packages in these crates.io categories may not be depended on, directly or indirectly.
+//! banned-categories = ["network-programming"]
+//! ```
+//!
+//! `PackageGraph::check_budgets` checks every workspace member against its budget. Dev-only
+//! dependencies are not counted, and dependencies are counted across all platforms.
+
+use crate::graph::{DependencyDirection, PackageGraph, PackageLink, PackageMetadata};
+use crate::report::{Report, ReportDocument};
+use crate::{Error, PackageId};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/// The dependency budget declared by a workspace member.
+///
+/// Budgets are parsed out of the `[package.metadata.guppy.budget]` table. All limits are
+/// optional.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct DependencyBudget {
+    max_direct_deps: Option<usize>,
+    max_transitive_deps: Option<usize>,
+    banned_categories: Vec<String>,
+}
+
+impl DependencyBudget {
+    /// Creates a new budget with no limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of direct dependencies.
+    pub fn with_max_direct_deps(mut self, max: usize) -> Self {
+        self.max_direct_deps = Some(max);
+        self
+    }
+
+    /// Sets the maximum number of packages this package transitively depends on, not including
+    /// itself.
+    pub fn with_max_transitive_deps(mut self, max: usize) -> Self {
+        self.max_transitive_deps = Some(max);
+        self
+    }
+
+    /// Adds crates.io categories that packages this package depends on may not be in.
+    pub fn with_banned_categories(
+        mut self,
+        categories: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.banned_categories
+            .extend(categories.into_iter().map(|category| category.into()));
+        self
+    }
+
+    /// Returns the maximum number of direct dependencies, if specified.
+    pub fn max_direct_deps(&self) -> Option<usize> {
+        self.max_direct_deps
+    }
+
+    /// Returns the maximum number of transitive dependencies, if specified.
+    pub fn max_transitive_deps(&self) -> Option<usize> {
+        self.max_transitive_deps
+    }
+
+    /// Returns the banned categories.
+    pub fn banned_categories(&self) -> &[String] {
+        &self.banned_categories
+    }
+
+    /// Checks this package against this budget, returning any violations found.
+    pub fn check<'g>(&self, package: PackageMetadata<'g>) -> Vec<BudgetViolation<'g>> {
+        let mut violations = vec![];
+        let graph = package.graph;
+
+        if let Some(limit) = self.max_direct_deps {
+            let mut seen = HashSet::new();
+            let deps: Vec<_> = package
+                .direct_links()
+                .filter(|link| counts(*link))
+                .map(|link| link.to())
+                .filter(|dep| seen.insert(dep.id()))
+                .collect();
+            if deps.len() > limit {
+                violations.push(BudgetViolation::DirectDeps {
+                    package,
+                    limit,
+                    deps,
+                });
+            }
+        }
+
+        let needs_closure =
+            self.max_transitive_deps.is_some() || !self.banned_categories.is_empty();
+        if !needs_closure {
+            return violations;
+        }
+
+        let paths = ShortestPaths::new(package);
+        if let Some(limit) = self.max_transitive_deps {
+            // Don't count the package itself.
+            let count = paths.len() - 1;
+            if count > limit {
+                violations.push(BudgetViolation::TransitiveDeps {
+                    package,
+                    limit,
+                    count,
+                });
+            }
+        }
+
+        // Iterate over dependencies in topological order so that violations are reported in a
+        // stable order.
+        let deps = graph
+            .query_forward(std::iter::once(package.id()))
+            .expect("valid package ID")
+            .resolve_with_fn(|_, link| counts(link));
+        for dep in deps.packages(DependencyDirection::Forward) {
+            if dep.id() == package.id() {
+                continue;
+            }
+            for category in dep.categories() {
+                if self.banned_categories.contains(category) {
+                    violations.push(BudgetViolation::BannedCategory {
+                        package,
+                        category,
+                        path: paths.path_to(dep.id()),
+                    });
+                }
+            }
+        }
+
+        violations
+    }
+}
+
+/// A dependency budget that was exceeded.
+#[derive(Clone, Debug)]
+pub enum BudgetViolation<'g> {
+    /// The package has too many direct dependencies.
+    DirectDeps {
+        /// The package whose budget was exceeded.
+        package: PackageMetadata<'g>,
+        /// The maximum number of direct dependencies.
+        limit: usize,
+        /// The direct dependencies of the package.
+        deps: Vec<PackageMetadata<'g>>,
+    },
+    /// The package has too many transitive dependencies.
+    TransitiveDeps {
+        /// The package whose budget was exceeded.
+        package: PackageMetadata<'g>,
+        /// The maximum number of transitive dependencies.
+        limit: usize,
+        /// The actual number of transitive dependencies.
+        count: usize,
+    },
+    /// The package depends on a package in a banned category.
+    BannedCategory {
+        /// The package whose budget was exceeded.
+        package: PackageMetadata<'g>,
+        /// The banned category.
+        category: &'g str,
+        /// A shortest path from the package to the dependency in the banned category, inclusive
+        /// of both ends.
+        path: Vec<PackageMetadata<'g>>,
+    },
+}
+
+impl<'g> BudgetViolation<'g> {
+    /// Returns the package whose budget was exceeded.
+    pub fn package(&self) -> PackageMetadata<'g> {
+        match self {
+            BudgetViolation::DirectDeps { package, .. }
+            | BudgetViolation::TransitiveDeps { package, .. }
+            | BudgetViolation::BannedCategory { package, .. } => *package,
+        }
+    }
+}
+
+impl<'g> fmt::Display for BudgetViolation<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetViolation::DirectDeps {
+                package,
+                limit,
+                deps,
+            } => {
+                write!(
+                    f,
+                    "{}: {} direct dependencies, budget is {} (",
+                    package.name(),
+                    deps.len(),
+                    limit
+                )?;
+                write_names(f, deps, ", ")?;
+                write!(f, ")")
+            }
+            BudgetViolation::TransitiveDeps {
+                package,
+                limit,
+                count,
+            } => write!(
+                f,
+                "{}: {} transitive dependencies, budget is {}",
+                package.name(),
+                count,
+                limit
+            ),
+            BudgetViolation::BannedCategory {
+                package,
+                category,
+                path,
+            } => {
+                write!(
+                    f,
+                    "{}: depends on banned category '{}' via ",
+                    package.name(),
+                    category
+                )?;
+                write_names(f, path, " -> ")
+            }
+        }
+    }
+}
+
+/// The result of checking every workspace member against its dependency budget.
+///
+/// Returned by `PackageGraph::check_budgets`.
+#[derive(Clone, Debug)]
+pub struct BudgetReport<'g> {
+    checked: usize,
+    violations: Vec<BudgetViolation<'g>>,
+}
+
+impl<'g> BudgetReport<'g> {
+    /// Returns the number of workspace members that declared a budget.
+    pub fn checked(&self) -> usize {
+        self.checked
+    }
+
+    /// Returns the violations found, ordered by workspace path.
+    pub fn violations(&self) -> &[BudgetViolation<'g>] {
+        &self.violations
+    }
+
+    /// Returns true if no budgets were exceeded.
+    pub fn is_success(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl<'g> fmt::Display for BudgetReport<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for violation in &self.violations {
+            writeln!(f, "{}", violation)?;
+        }
+        writeln!(
+            f,
+            "checked {} packages with budgets, {} violations found",
+            self.checked,
+            self.violations.len()
+        )
+    }
+}
+
+impl<'g> Report for BudgetReport<'g> {
+    fn to_document(&self) -> ReportDocument {
+        let mut document = ReportDocument::new("Dependency budgets");
+        let section = document.add_section(None);
+        for violation in &self.violations {
+            section.add_item(violation.to_string());
+        }
+        document.set_summary(format!(
+            "checked {} packages with budgets, {} violations found",
+            self.checked,
+            self.violations.len()
+        ));
+        document
+    }
+}
+
+impl<'g> PackageMetadata<'g> {
+    /// Returns the dependency budget declared in this package's
+    /// `[package.metadata.guppy.budget]` table, or `None` if it doesn't declare one.
+    ///
+    /// Returns an error if the table is malformed.
+    pub fn dependency_budget(&self) -> Result<Option<DependencyBudget>, Error> {
+        match self.metadata_table().pointer("/guppy/budget") {
+            Some(table) => serde_json::from_value(table.clone())
+                .map(Some)
+                .map_err(|err| Error::DependencyBudgetError(self.id().clone(), err)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl PackageGraph {
+    /// Checks every workspace member against the dependency budget it declares.
+    ///
+    /// Returns an error if any budgets are malformed.
+    pub fn check_budgets(&self) -> Result<BudgetReport<'_>, Error> {
+        let mut checked = 0;
+        let mut violations = vec![];
+        for (_, package) in self.workspace().members() {
+            if let Some(budget) = package.dependency_budget()? {
+                checked += 1;
+                violations.extend(budget.check(package));
+            }
+        }
+        Ok(BudgetReport {
+            checked,
+            violations,
+        })
+    }
+}
+
+// ---
+// Helper methods
+// ---
+
+fn counts(link: PackageLink<'_>) -> bool {
+    !link.dev_only()
+}
+
+fn write_names(
+    f: &mut fmt::Formatter<'_>,
+    packages: &[PackageMetadata<'_>],
+    sep: &str,
+) -> fmt::Result {
+    for (idx, package) in packages.iter().enumerate() {
+        if idx > 0 {
+            write!(f, "{}", sep)?;
+        }
+        write!(f, "{} {}", package.name(), package.version())?;
+    }
+    Ok(())
+}
+
+/// Shortest paths from a package to each of its transitive dependencies.
+struct ShortestPaths<'g> {
+    graph: &'g PackageGraph,
+    root: &'g PackageId,
+    // Maps each package reached to the package it was first reached from.
+    parents: HashMap<&'g PackageId, &'g PackageId>,
+}
+
+impl<'g> ShortestPaths<'g> {
+    fn new(root: PackageMetadata<'g>) -> Self {
+        let mut parents = HashMap::new();
+        let mut queue = VecDeque::new();
+        parents.insert(root.id(), root.id());
+        queue.push_back(root);
+
+        while let Some(package) = queue.pop_front() {
+            for link in package.direct_links().filter(|link| counts(*link)) {
+                let to = link.to();
+                if !parents.contains_key(to.id()) {
+                    parents.insert(to.id(), package.id());
+                    queue.push_back(to);
+                }
+            }
+        }
+
+        Self {
+            graph: root.graph,
+            root: root.id(),
+            parents,
+        }
+    }
+
+    /// Returns the number of packages reached, including the root.
+    fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    fn path_to(&self, target: &'g PackageId) -> Vec<PackageMetadata<'g>> {
+        let mut path = vec![target];
+        let mut current = target;
+        while current != self.root {
+            current = self.parents[current];
+            path.push(current);
+        }
+        path.reverse();
+        path.into_iter()
+            .map(|id| self.graph.metadata(id).expect("valid package ID"))
+            .collect()
+    }
+}
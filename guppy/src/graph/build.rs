@@ -1,24 +1,36 @@
 // Copyright (c) The cargo-guppy Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::cargo_config::CargoConfig;
 use crate::graph::{
     cargo_version_matches, BuildTargetImpl, BuildTargetKindImpl, DepRequiredOrOptional,
-    DependencyReqImpl, OwnedBuildTargetId, PackageGraph, PackageGraphData, PackageIx,
-    PackageLinkImpl, PackageMetadataImpl, PlatformStatusImpl, WorkspaceImpl,
+    DependencyReqImpl, ManifestSpans, OwnedBuildTargetId, PackageGraph, PackageGraphData,
+    PackageIx, PackageLinkImpl, PackageMetadataImpl, PlatformStatusImpl, WorkspaceImpl,
+    WorkspacePath,
 };
 use crate::sorted_set::SortedSet;
-use crate::{Error, PackageId};
+use crate::toolchain::{parse_version, Toolchain};
+use crate::{Error, FileSource, PackageId};
 use cargo_metadata::{Dependency, DependencyKind, Metadata, NodeDep, Package, Resolve, Target};
 use once_cell::sync::OnceCell;
 use petgraph::prelude::*;
 use semver::Version;
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
 use std::path::{Path, PathBuf};
-use target_spec::TargetSpec;
+use target_spec::{TargetSpec, TargetSpecCache};
 
 impl PackageGraph {
     /// Constructs a new `PackageGraph` instances from the given metadata.
-    pub(crate) fn build(metadata: Metadata) -> Result<Self, Error> {
+    ///
+    /// Cargo configuration files are only read if `discover_cargo_config` is true, and toolchain
+    /// files only if `discover_toolchains` is true.
+    pub(crate) fn build(
+        metadata: Metadata,
+        files: &dyn FileSource,
+        discover_cargo_config: bool,
+        discover_toolchains: bool,
+    ) -> Result<Self, Error> {
         let resolve = metadata.resolve.ok_or_else(|| {
             Error::PackageGraphConstructError(
                 "no 'resolve' entries found: ensure you don't have no_deps set".into(),
@@ -31,11 +43,29 @@ impl PackageGraph {
             .map(PackageId::from_metadata)
             .collect();
 
+        // A toolchain file that can't be read or parsed is treated as missing: it shouldn't stop
+        // the graph from being built.
+        let workspace_toolchain = if discover_toolchains {
+            Toolchain::from_dir_with_files(&metadata.workspace_root, files)
+                .ok()
+                .flatten()
+        } else {
+            None
+        };
+        let cargo_config = if discover_cargo_config {
+            CargoConfig::discover_with_files(&metadata.workspace_root, files)?
+        } else {
+            CargoConfig::default()
+        };
+
         let mut build_state = GraphBuildState::new(
             &metadata.packages,
             resolve,
             &metadata.workspace_root,
             &workspace_members,
+            discover_toolchains,
+            workspace_toolchain.as_ref(),
+            files,
         );
 
         let packages: HashMap<_, _> = metadata
@@ -46,7 +76,14 @@ impl PackageGraph {
 
         let dep_graph = build_state.finish();
 
-        let workspace = WorkspaceImpl::new(metadata.workspace_root, &packages, workspace_members)?;
+        let workspace = WorkspaceImpl::new(
+            metadata.workspace_root,
+            metadata.target_directory,
+            workspace_toolchain,
+            cargo_config,
+            &packages,
+            workspace_members,
+        )?;
 
         Ok(Self {
             dep_graph,
@@ -55,6 +92,11 @@ impl PackageGraph {
             data: PackageGraphData {
                 packages,
                 workspace,
+                #[cfg(feature = "unstable")]
+                maintenance: HashMap::new(),
+                #[cfg(feature = "unstable")]
+                unsafe_stats: HashMap::new(),
+                extensions: HashMap::new(),
             },
         })
     }
@@ -64,6 +106,9 @@ impl WorkspaceImpl {
     /// Indexes and creates a new workspace.
     fn new(
         workspace_root: impl Into<PathBuf>,
+        target_directory: impl Into<PathBuf>,
+        toolchain: Option<Toolchain>,
+        cargo_config: CargoConfig,
         packages: &HashMap<PackageId, PackageMetadataImpl>,
         members: impl IntoIterator<Item = PackageId>,
     ) -> Result<Self, Error> {
@@ -86,7 +131,7 @@ impl WorkspaceImpl {
                     id, package_metadata.manifest_path,
                 ))
             })?;
-            members_by_path.insert(workspace_path.to_path_buf(), id.clone());
+            members_by_path.insert(workspace_path.to_native_path(), id.clone());
 
             match members_by_name.entry(package_metadata.name.clone().into_boxed_str()) {
                 Entry::Vacant(vacant) => {
@@ -105,6 +150,9 @@ impl WorkspaceImpl {
 
         Ok(Self {
             root: workspace_root,
+            target_directory: target_directory.into(),
+            toolchain,
+            cargo_config,
             members_by_path,
             members_by_name,
             #[cfg(feature = "proptest09")]
@@ -121,6 +169,11 @@ struct GraphBuildState<'a> {
     resolve_data: HashMap<PackageId, (Vec<NodeDep>, Vec<String>)>,
     workspace_root: &'a Path,
     workspace_members: &'a HashSet<PackageId>,
+    discover_toolchains: bool,
+    workspace_toolchain: Option<&'a Toolchain>,
+    files: &'a dyn FileSource,
+    // Manifests tend to repeat the same target specs, so each one is only parsed once.
+    target_specs: TargetSpecCache,
 }
 
 impl<'a> GraphBuildState<'a> {
@@ -129,6 +182,9 @@ impl<'a> GraphBuildState<'a> {
         resolve: Resolve,
         workspace_root: &'a Path,
         workspace_members: &'a HashSet<PackageId>,
+        discover_toolchains: bool,
+        workspace_toolchain: Option<&'a Toolchain>,
+        files: &'a dyn FileSource,
     ) -> Self {
         // No idea how many edges there are going to be, so use packages.len() as a reasonable lower
         // bound.
@@ -162,6 +218,10 @@ impl<'a> GraphBuildState<'a> {
             resolve_data,
             workspace_root,
             workspace_members,
+            discover_toolchains,
+            workspace_toolchain,
+            files,
+            target_specs: TargetSpecCache::new(),
         }
     }
 
@@ -172,13 +232,33 @@ impl<'a> GraphBuildState<'a> {
         let package_id = PackageId::from_metadata(package.id);
         let (package_ix, _, _) = self.package_data(&package_id)?;
 
-        let workspace_path = if self.workspace_members.contains(&package_id) {
-            Some(self.workspace_path(&package_id, &package.manifest_path)?)
-        } else {
-            None
-        };
+        let (workspace_path, rust_version, toolchain, include, exclude, manifest_spans) =
+            if self.workspace_members.contains(&package_id) {
+                let manifest_path = &package.manifest_path;
+                let source = read_manifest_source(self.files, &package_id, manifest_path)?;
+                // Cargo has already parsed the manifest, so if it doesn't parse here, it uses
+                // syntax this version of toml doesn't support. Treat it like a missing manifest
+                // rather than failing.
+                let manifest = source
+                    .as_deref()
+                    .and_then(|source| parse_manifest(&package_id, manifest_path, source).ok());
+                let manifest_spans = match manifest {
+                    Some(_) => source.as_deref().map(ManifestSpans::parse),
+                    None => None,
+                };
+                (
+                    Some(self.workspace_path(&package_id, manifest_path)?),
+                    self.rust_version(&package_id, manifest_path, manifest.as_ref())?,
+                    self.member_toolchain(manifest_path),
+                    package_patterns(&package_id, manifest_path, manifest.as_ref(), "include")?,
+                    package_patterns(&package_id, manifest_path, manifest.as_ref(), "exclude")?,
+                    manifest_spans,
+                )
+            } else {
+                (None, None, None, vec![], vec![], None)
+            };
 
-        let mut build_targets = BuildTargets::new(&package_id);
+        let mut build_targets = BuildTargets::new(&package_id, self.workspace_root);
         for build_target in package.targets {
             build_targets.add(build_target)?;
         }
@@ -204,7 +284,13 @@ impl<'a> GraphBuildState<'a> {
             let dep_id = PackageId::from_metadata(pkg.clone());
             let (name, deps) = dep_resolver.resolve(resolved_name, &dep_id)?;
             let (dep_idx, _, _) = self.package_data(&dep_id)?;
-            let edge = PackageLinkImpl::new(&package_id, name, resolved_name, deps)?;
+            let edge = PackageLinkImpl::new(
+                &package_id,
+                name,
+                resolved_name,
+                deps,
+                &mut self.target_specs,
+            )?;
             // Use update_edge instead of add_edge to prevent multiple edges from being added
             // between these two nodes.
             // XXX maybe check for an existing edge?
@@ -224,14 +310,14 @@ impl<'a> GraphBuildState<'a> {
         // one). Some of them might be optional, some might not be. List a dependency here if *any*
         // of those specifications are optional, since that's how Cargo features work. But also
         // dedup them.
-        let optional_deps = package
-            .dependencies
-            .into_iter()
+        let dependencies = package.dependencies;
+        let optional_deps = dependencies
+            .iter()
             .filter_map(|dep| {
                 if dep.optional {
-                    match dep.rename {
-                        Some(rename) => Some(rename.into_boxed_str()),
-                        None => Some(dep.name.into_boxed_str()),
+                    match &dep.rename {
+                        Some(rename) => Some(rename.as_str().into()),
+                        None => Some(dep.name.as_str().into()),
                     }
                 } else {
                     None
@@ -256,12 +342,17 @@ impl<'a> GraphBuildState<'a> {
                 description: package.description.map(|s| s.into()),
                 license: package.license.map(|s| s.into()),
                 license_file: package.license_file.map(|s| s.into()),
+                normalized_manifest_path: WorkspacePath::from_absolute(
+                    self.workspace_root,
+                    &package.manifest_path,
+                ),
                 manifest_path: package.manifest_path.into(),
                 categories: package.categories,
                 keywords: package.keywords,
                 readme: package.readme.map(|s| s.into()),
                 repository: package.repository.map(|s| s.into()),
                 edition: package.edition.into(),
+                source: package.source.map(|s| s.to_string().into_boxed_str()),
                 metadata_table: package.metadata,
                 links: package.links.map(|s| s.into()),
                 publish: package.publish,
@@ -269,8 +360,14 @@ impl<'a> GraphBuildState<'a> {
 
                 package_ix,
                 workspace_path,
+                rust_version,
+                toolchain,
+                include,
+                exclude,
+                manifest_spans,
                 build_targets,
                 has_default_feature,
+                dependencies,
                 resolved_deps,
                 resolved_features,
             },
@@ -289,7 +386,7 @@ impl<'a> GraphBuildState<'a> {
 
     /// Computes the workspace path for this package. Errors if this package is not in the
     /// workspace.
-    fn workspace_path(&self, id: &PackageId, manifest_path: &Path) -> Result<Box<Path>, Error> {
+    fn workspace_path(&self, id: &PackageId, manifest_path: &Path) -> Result<WorkspacePath, Error> {
         // Strip off the workspace path from the manifest path.
         let workspace_path = manifest_path
             .strip_prefix(self.workspace_root)
@@ -305,7 +402,81 @@ impl<'a> GraphBuildState<'a> {
                 id, manifest_path
             ))
         })?;
-        Ok(workspace_path.to_path_buf().into_boxed_path())
+        WorkspacePath::new(workspace_path).ok_or_else(|| {
+            Error::PackageGraphConstructError(format!(
+                "workspace member '{}' has invalid manifest path {:?}",
+                id, manifest_path
+            ))
+        })
+    }
+
+    /// Reads the `rust-version` declared in a workspace member's manifest, if any.
+    ///
+    /// `cargo metadata` doesn't report this field, so it is read from the manifest directly. A
+    /// missing manifest is treated as not declaring a version.
+    fn rust_version(
+        &self,
+        id: &PackageId,
+        manifest_path: &Path,
+        manifest: Option<&toml::Value>,
+    ) -> Result<Option<Version>, Error> {
+        let manifest = match manifest {
+            Some(manifest) => manifest,
+            None => return Ok(None),
+        };
+        let (rust_version, manifest_path) =
+            match manifest.get("package").and_then(|p| p.get("rust-version")) {
+                Some(toml::Value::String(rust_version)) => {
+                    (rust_version.clone(), manifest_path.to_path_buf())
+                }
+                Some(toml::Value::Table(table))
+                    if table.get("workspace") == Some(&toml::Value::Boolean(true)) =>
+                {
+                    // Inherited from the workspace root.
+                    let root_manifest_path = self.workspace_root.join("Cargo.toml");
+                    let root_manifest = read_manifest(self.files, id, &root_manifest_path)?;
+                    let rust_version = match root_manifest
+                        .as_ref()
+                        .and_then(|m| m.get("workspace"))
+                        .and_then(|w| w.get("package"))
+                        .and_then(|p| p.get("rust-version"))
+                        .and_then(|v| v.as_str())
+                    {
+                        Some(rust_version) => rust_version.to_string(),
+                        None => return Ok(None),
+                    };
+                    (rust_version, root_manifest_path)
+                }
+                _ => return Ok(None),
+            };
+        let version = parse_version(&rust_version).ok_or_else(|| {
+            Error::manifest_error(
+                id,
+                manifest_path,
+                format!("invalid rust-version '{}'", rust_version),
+            )
+        })?;
+        Ok(Some(version))
+    }
+
+    /// Finds the toolchain for a workspace member: the closest toolchain file between the member's
+    /// directory and the workspace root, or the workspace toolchain.
+    ///
+    /// As with the workspace toolchain, files that can't be read or parsed are skipped.
+    fn member_toolchain(&self, manifest_path: &Path) -> Option<Toolchain> {
+        if !self.discover_toolchains {
+            return None;
+        }
+        if let Some(member_dir) = manifest_path.parent() {
+            for dir in member_dir.ancestors().take_while(|dir| {
+                *dir != self.workspace_root && dir.starts_with(self.workspace_root)
+            }) {
+                if let Ok(Some(toolchain)) = Toolchain::from_dir_with_files(dir, self.files) {
+                    return Some(toolchain);
+                }
+            }
+        }
+        self.workspace_toolchain.cloned()
     }
 
     fn finish(self) -> Graph<PackageId, PackageLinkImpl, Directed, PackageIx> {
@@ -315,13 +486,15 @@ impl<'a> GraphBuildState<'a> {
 
 struct BuildTargets<'a> {
     package_id: &'a PackageId,
+    workspace_root: &'a Path,
     targets: BTreeMap<OwnedBuildTargetId, BuildTargetImpl>,
 }
 
 impl<'a> BuildTargets<'a> {
-    fn new(package_id: &'a PackageId) -> Self {
+    fn new(package_id: &'a PackageId, workspace_root: &'a Path) -> Self {
         Self {
             package_id,
+            workspace_root,
             targets: BTreeMap::new(),
         }
     }
@@ -412,6 +585,10 @@ impl<'a> BuildTargets<'a> {
                     kind,
                     lib_name,
                     required_features: target.required_features,
+                    normalized_path: WorkspacePath::from_absolute(
+                        self.workspace_root,
+                        &target.src_path,
+                    ),
                     path: target.src_path.into_boxed_path(),
                     edition: target.edition.into_boxed_str(),
                     doc_tests: target.doctest,
@@ -593,6 +770,7 @@ impl PackageLinkImpl {
         name: &str,
         resolved_name: &str,
         deps: impl IntoIterator<Item = &'a Dependency>,
+        target_specs: &mut TargetSpecCache,
     ) -> Result<Self, Error> {
         let mut version_req = None;
         let mut normal = DependencyReqImpl::default();
@@ -613,9 +791,9 @@ impl PackageLinkImpl {
             }
 
             match dep.kind {
-                DependencyKind::Normal => normal.add_instance(from_id, dep)?,
-                DependencyKind::Build => build.add_instance(from_id, dep)?,
-                DependencyKind::Development => dev.add_instance(from_id, dep)?,
+                DependencyKind::Normal => normal.add_instance(from_id, dep, target_specs)?,
+                DependencyKind::Build => build.add_instance(from_id, dep, target_specs)?,
+                DependencyKind::Development => dev.add_instance(from_id, dep, target_specs)?,
                 _ => {
                     // unknown dependency kind -- can't do much with this!
                     continue;
@@ -661,23 +839,33 @@ impl PackageLinkImpl {
 /// causes this union-ing to *not* happen, so that's why we store all the features enabled by
 /// each target separately.
 impl DependencyReqImpl {
-    fn add_instance(&mut self, from_id: &PackageId, dep: &Dependency) -> Result<(), Error> {
+    fn add_instance(
+        &mut self,
+        from_id: &PackageId,
+        dep: &Dependency,
+        target_specs: &mut TargetSpecCache,
+    ) -> Result<(), Error> {
         if dep.optional {
-            self.optional.add_instance(from_id, dep)
+            self.optional.add_instance(from_id, dep, target_specs)
         } else {
-            self.required.add_instance(from_id, dep)
+            self.required.add_instance(from_id, dep, target_specs)
         }
     }
 }
 
 impl DepRequiredOrOptional {
-    fn add_instance(&mut self, from_id: &PackageId, dep: &Dependency) -> Result<(), Error> {
+    fn add_instance(
+        &mut self,
+        from_id: &PackageId,
+        dep: &Dependency,
+        target_specs: &mut TargetSpecCache,
+    ) -> Result<(), Error> {
         // target_spec is None if this is not a platform-specific dependency.
         let target_spec = match dep.target.as_ref() {
             Some(spec_or_triple) => {
                 // This is a platform-specific dependency, so add it to the list of specs.
                 let spec_or_triple = format!("{}", spec_or_triple);
-                let target_spec: TargetSpec = spec_or_triple.parse().map_err(|err| {
+                let target_spec = target_specs.parse(&spec_or_triple).map_err(|err| {
                     Error::PackageGraphConstructError(format!(
                         "for package '{}': for dependency '{}', parsing target '{}' failed: {}",
                         from_id, dep.name, spec_or_triple, err
@@ -735,3 +923,63 @@ impl PlatformStatusImpl {
         }
     }
 }
+
+/// Reads the list of patterns for the `include` or `exclude` key in a workspace member's manifest.
+///
+/// `cargo metadata` doesn't report these fields, so they are read from the manifest directly.
+fn package_patterns(
+    id: &PackageId,
+    manifest_path: &Path,
+    manifest: Option<&toml::Value>,
+    key: &str,
+) -> Result<Vec<String>, Error> {
+    let value = match manifest
+        .and_then(|m| m.get("package"))
+        .and_then(|p| p.get(key))
+    {
+        Some(value) => value,
+        None => return Ok(vec![]),
+    };
+    let invalid = || {
+        Error::manifest_error(
+            id,
+            manifest_path,
+            format!("'{}' must be an array of strings", key),
+        )
+    };
+    value
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|pattern| pattern.as_str().map(|s| s.to_string()).ok_or_else(invalid))
+        .collect()
+}
+
+/// Reads and parses a manifest file, returning `None` if it doesn't exist.
+fn read_manifest(
+    files: &dyn FileSource,
+    id: &PackageId,
+    path: &Path,
+) -> Result<Option<toml::Value>, Error> {
+    read_manifest_source(files, id, path)?
+        .map(|source| parse_manifest(id, path, &source))
+        .transpose()
+}
+
+fn read_manifest_source(
+    files: &dyn FileSource,
+    id: &PackageId,
+    path: &Path,
+) -> Result<Option<String>, Error> {
+    match files.read_to_string(path) {
+        Ok(source) => Ok(Some(source)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(Error::manifest_error(id, path, err)),
+    }
+}
+
+fn parse_manifest(id: &PackageId, path: &Path, source: &str) -> Result<toml::Value, Error> {
+    source
+        .parse()
+        .map_err(|err: toml::de::Error| Error::manifest_error(id, path, err))
+}
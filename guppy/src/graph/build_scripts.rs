@@ -0,0 +1,303 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Build script inputs and their influence on other packages.
+//!
+//! Build scripts run arbitrary code at build time, so they're worth auditing separately from the
+//! rest of a package. `cargo metadata` doesn't say what a build script reads, since that's only
+//! known once it runs and prints `cargo:rerun-if-changed` or `cargo:rerun-if-env-changed`.
+//! Packages can declare these inputs up front in their `Cargo.toml`:
+//!
+//! ```toml
+//! [package.metadata.guppy.build-script]
+//! rerun-if-changed = ["build.rs", "include/foo.h"]
+//! rerun-if-env-changed = ["FOO_LIB_DIR"]
+//! ```
+//!
+//! Beyond its own package, a build script can affect:
+//! * the build scripts of packages that directly depend on it, if it declares a native library
+//!   through the `links` key. Cargo passes anything it prints as `cargo:KEY=VALUE` to these build
+//!   scripts, as `DEP_<LINKS>_<KEY>` environment variables.
+//! * every package that depends on it, directly or transitively, since they're rebuilt whenever
+//!   it reruns.
+
+use crate::graph::{DependencyDirection, PackageGraph, PackageMetadata};
+use crate::report::{Report, ReportDocument};
+use crate::Error;
+use serde::Deserialize;
+use std::fmt;
+
+/// The inputs to a package's build script, as far as they're known without running it.
+#[derive(Clone, Debug)]
+pub struct BuildScriptInputs<'g> {
+    package: PackageMetadata<'g>,
+    declared: DeclaredInputs,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct DeclaredInputs {
+    rerun_if_changed: Vec<String>,
+    rerun_if_env_changed: Vec<String>,
+}
+
+impl<'g> BuildScriptInputs<'g> {
+    /// Returns the package this build script is for.
+    pub fn package(&self) -> PackageMetadata<'g> {
+        self.package
+    }
+
+    /// Returns the name of the native library this build script links to, if specified through
+    /// the `links` key.
+    pub fn links(&self) -> Option<&'g str> {
+        self.package.links()
+    }
+
+    /// Returns the paths, relative to the package directory, that the build script declares it
+    /// reads.
+    pub fn rerun_if_changed(&self) -> &[String] {
+        &self.declared.rerun_if_changed
+    }
+
+    /// Returns the environment variables the build script declares it reads.
+    pub fn rerun_if_env_changed(&self) -> &[String] {
+        &self.declared.rerun_if_env_changed
+    }
+
+    /// Returns true if the package declares its build script's inputs.
+    pub fn is_declared(&self) -> bool {
+        self.package
+            .metadata_table()
+            .pointer("/guppy/build-script")
+            .is_some()
+    }
+}
+
+impl<'g> PackageMetadata<'g> {
+    /// Returns the inputs to this package's build script, or `None` if it doesn't have one.
+    ///
+    /// Inputs are read from the `[package.metadata.guppy.build-script]` table. Returns an error if
+    /// the table is malformed.
+    pub fn build_script_inputs(&self) -> Result<Option<BuildScriptInputs<'g>>, Error> {
+        if !self.has_build_script() {
+            return Ok(None);
+        }
+        let declared = match self.metadata_table().pointer("/guppy/build-script") {
+            Some(table) => serde_json::from_value(table.clone())
+                .map_err(|err| Error::BuildScriptMetadataError(self.id().clone(), err))?,
+            None => DeclaredInputs::default(),
+        };
+        Ok(Some(BuildScriptInputs {
+            package: *self,
+            declared,
+        }))
+    }
+}
+
+/// The packages a build script can affect.
+#[derive(Clone, Debug)]
+pub struct BuildScriptInfluence<'g> {
+    inputs: BuildScriptInputs<'g>,
+    links_dependents: Vec<PackageMetadata<'g>>,
+    affected_members: Vec<PackageMetadata<'g>>,
+}
+
+impl<'g> BuildScriptInfluence<'g> {
+    /// Returns the inputs to this build script.
+    pub fn inputs(&self) -> &BuildScriptInputs<'g> {
+        &self.inputs
+    }
+
+    /// Returns the packages whose build scripts receive metadata from this build script through
+    /// `DEP_<LINKS>_<KEY>` environment variables.
+    ///
+    /// This is empty if the package doesn't specify `links`.
+    pub fn links_dependents(&self) -> &[PackageMetadata<'g>] {
+        &self.links_dependents
+    }
+
+    /// Returns the workspace members that are rebuilt when this build script reruns, in reverse
+    /// topological order.
+    ///
+    /// This includes the package itself if it's a workspace member.
+    pub fn affected_members(&self) -> &[PackageMetadata<'g>] {
+        &self.affected_members
+    }
+}
+
+/// A report of every build script in a graph and the packages it can affect.
+///
+/// Returned by `PackageGraph::build_script_report`. The `Display` implementation renders a
+/// summary of which build scripts can affect the build environment of workspace members.
+#[derive(Clone, Debug)]
+pub struct BuildScriptReport<'g> {
+    scripts: Vec<BuildScriptInfluence<'g>>,
+}
+
+impl<'g> BuildScriptReport<'g> {
+    /// Returns every build script in the graph, sorted by package ID.
+    pub fn scripts(&self) -> &[BuildScriptInfluence<'g>] {
+        &self.scripts
+    }
+
+    /// Iterates over build scripts that don't declare their inputs.
+    pub fn undeclared<'a>(&'a self) -> impl Iterator<Item = &'a BuildScriptInfluence<'g>> + 'a {
+        self.scripts
+            .iter()
+            .filter(|script| !script.inputs.is_declared())
+    }
+}
+
+impl<'g> fmt::Display for BuildScriptReport<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for script in &self.scripts {
+            let package = script.inputs.package;
+            write!(f, "{} {}", package.name(), package.version())?;
+            if let Some(links) = script.inputs.links() {
+                write!(f, " (links = \"{}\")", links)?;
+            }
+            writeln!(f)?;
+
+            if script.inputs.is_declared() {
+                writeln!(
+                    f,
+                    "    rerun-if-changed: [{}]",
+                    script.inputs.rerun_if_changed().join(", ")
+                )?;
+                writeln!(
+                    f,
+                    "    rerun-if-env-changed: [{}]",
+                    script.inputs.rerun_if_env_changed().join(", ")
+                )?;
+            } else {
+                writeln!(f, "    inputs: undeclared")?;
+            }
+            if !script.links_dependents.is_empty() {
+                writeln!(
+                    f,
+                    "    passes metadata to: {}",
+                    join_names(&script.links_dependents)
+                )?;
+            }
+            writeln!(
+                f,
+                "    affects {} workspace members: {}",
+                script.affected_members.len(),
+                join_names(&script.affected_members)
+            )?;
+        }
+        writeln!(
+            f,
+            "{} build scripts, {} with undeclared inputs",
+            self.scripts.len(),
+            self.undeclared().count()
+        )
+    }
+}
+
+impl<'g> Report for BuildScriptReport<'g> {
+    fn to_document(&self) -> ReportDocument {
+        let mut document = ReportDocument::new("Build scripts");
+        let section = document.add_section(None);
+        for script in &self.scripts {
+            let package = script.inputs.package;
+            let item = match script.inputs.links() {
+                Some(links) => section.add_item(format!(
+                    "{} {} (links = \"{}\")",
+                    package.name(),
+                    package.version(),
+                    links
+                )),
+                None => section.add_item(format!("{} {}", package.name(), package.version())),
+            };
+            if script.inputs.is_declared() {
+                item.add_detail(format!(
+                    "rerun-if-changed: [{}]",
+                    script.inputs.rerun_if_changed().join(", ")
+                ));
+                item.add_detail(format!(
+                    "rerun-if-env-changed: [{}]",
+                    script.inputs.rerun_if_env_changed().join(", ")
+                ));
+            } else {
+                item.add_detail("inputs: undeclared");
+            }
+            if !script.links_dependents.is_empty() {
+                item.add_detail(format!(
+                    "passes metadata to: {}",
+                    join_names(&script.links_dependents)
+                ));
+            }
+            item.add_detail(format!(
+                "affects {} workspace members: {}",
+                script.affected_members.len(),
+                join_names(&script.affected_members)
+            ));
+        }
+        document.set_summary(format!(
+            "{} build scripts, {} with undeclared inputs",
+            self.scripts.len(),
+            self.undeclared().count()
+        ));
+        document
+    }
+}
+
+impl PackageGraph {
+    /// Returns every build script in this graph, along with the packages it can affect.
+    ///
+    /// Returns an error if any package's `[package.metadata.guppy.build-script]` table is
+    /// malformed.
+    pub fn build_script_report(&self) -> Result<BuildScriptReport<'_>, Error> {
+        let mut packages: Vec<_> = self.packages().collect();
+        packages.sort_by_key(|package| package.id());
+
+        let mut scripts = vec![];
+        for package in packages {
+            let inputs = match package.build_script_inputs()? {
+                Some(inputs) => inputs,
+                None => continue,
+            };
+
+            // Cargo only passes `links` metadata to build scripts of direct dependents. Dev
+            // dependencies don't get it.
+            let links_dependents = if package.links().is_some() {
+                let mut dependents: Vec<_> = package
+                    .reverse_direct_links()
+                    .filter(|link| !link.dev_only() && link.from().has_build_script())
+                    .map(|link| link.from())
+                    .collect();
+                dependents.sort_by_key(|dependent| dependent.id());
+                dependents.dedup_by_key(|dependent| dependent.id());
+                dependents
+            } else {
+                vec![]
+            };
+
+            // Cargo doesn't build dev-dependencies of non-workspace packages.
+            let affected_members = self
+                .query_reverse(std::iter::once(package.id()))
+                .expect("valid package ID")
+                .resolve_with_fn(|_, link| !link.dev_only() || link.from().in_workspace())
+                .packages(DependencyDirection::Forward)
+                .filter(|package| package.in_workspace())
+                .collect();
+
+            scripts.push(BuildScriptInfluence {
+                inputs,
+                links_dependents,
+                affected_members,
+            });
+        }
+
+        Ok(BuildScriptReport { scripts })
+    }
+}
+
+fn join_names(packages: &[PackageMetadata<'_>]) -> String {
+    packages
+        .iter()
+        .map(|package| package.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -1,6 +1,7 @@
 // Copyright (c) The cargo-guppy Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::graph::WorkspacePath;
 use crate::sorted_set::SortedSet;
 use std::borrow::Borrow;
 use std::cmp::Ordering;
@@ -65,6 +66,12 @@ impl<'g> BuildTarget<'g> {
         &self.inner.path
     }
 
+    /// Returns the normalized path of the source for this build target relative to the workspace
+    /// root, or `None` if it is outside the workspace root.
+    pub fn normalized_path(&self) -> Option<&'g WorkspacePath> {
+        self.inner.normalized_path.as_ref()
+    }
+
     /// Returns the Rust edition for this build target.
     pub fn edition(&self) -> &'g str {
         &self.inner.edition
@@ -194,6 +201,8 @@ pub(super) struct BuildTargetImpl {
     pub(super) lib_name: Option<Box<str>>,
     pub(super) required_features: Vec<String>,
     pub(super) path: Box<Path>,
+    // Set if the path is within the workspace root.
+    pub(super) normalized_path: Option<WorkspacePath>,
     pub(super) edition: Box<str>,
     pub(super) doc_tests: bool,
 }
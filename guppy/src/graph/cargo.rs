@@ -6,13 +6,22 @@
 //! Cargo comes with a set of algorithms to figure out what packages or features are built. This
 //! module reimplements those algorithms using `guppy`'s data structures.
 
-use crate::graph::feature::{all_filter, CrossLink, FeatureQuery, FeatureSet};
-use crate::graph::{DependencyDirection, EnabledTernary, PackageIx, PackageLink};
+use crate::cargo_config::CargoConfig;
+use crate::graph::feature::{
+    all_filter, default_filter, none_filter, CrossLink, FeatureGraph, FeatureId, FeatureQuery,
+    FeatureSet,
+};
+use crate::graph::{DependencyDirection, EnabledTernary, PackageIx, PackageLink, PackageQuery};
+use crate::report::{Report, ReportDocument};
 use crate::sorted_set::SortedSet;
+use crate::toolchain::Toolchain;
 use crate::{DependencyKind, Error, PackageId};
 use petgraph::prelude::*;
-use std::collections::HashSet;
-use target_spec::Platform;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use target_spec::{Platform, PlatformSpec, RustcVersion, TargetFeatures};
 
 /// Options for queries which simulate what Cargo does.
 ///
@@ -21,9 +30,10 @@ use target_spec::Platform;
 pub struct CargoOptions<'a> {
     version: CargoResolverVersion,
     include_dev: bool,
-    host_platform: Option<&'a Platform<'a>>,
-    target_platform: Option<&'a Platform<'a>>,
+    host_platform: PlatformSpec<'a>,
+    target_platform: PlatformSpec<'a>,
     omitted_packages: HashSet<&'a PackageId>,
+    cargo_config: Option<&'a CargoConfig>,
 }
 
 impl<'a> CargoOptions<'a> {
@@ -33,15 +43,16 @@ impl<'a> CargoOptions<'a> {
     ///
     /// * use version 1 of the Cargo resolver
     /// * exclude dev-dependencies
-    /// * resolve dependencies assuming any possible host or target platform
+    /// * resolve dependencies assuming any possible host or target platform (`PlatformSpec::Any`)
     /// * do not omit any packages.
     pub fn new() -> Self {
         Self {
             version: CargoResolverVersion::V1,
             include_dev: false,
-            host_platform: None,
-            target_platform: None,
+            host_platform: PlatformSpec::Any,
+            target_platform: PlatformSpec::Any,
             omitted_packages: HashSet::new(),
+            cargo_config: None,
         }
     }
 
@@ -64,23 +75,26 @@ impl<'a> CargoOptions<'a> {
         self
     }
 
-    /// Sets both the target and host platforms to the provided one, or to evaluate against any
-    /// platform if `None`.
-    pub fn with_platform(mut self, platform: Option<&'a Platform<'a>>) -> Self {
+    /// Sets both the target and host platforms to the provided ones.
+    ///
+    /// This accepts either a `PlatformSpec`, or an `Option<&Platform>` where `None` means to
+    /// evaluate against any platform (`PlatformSpec::Any`).
+    pub fn with_platform(mut self, platform: impl Into<PlatformSpec<'a>>) -> Self {
+        let platform = platform.into();
         self.target_platform = platform;
         self.host_platform = platform;
         self
     }
 
-    /// Sets the target platform to the provided one, or to evaluate against any platform if `None`.
-    pub fn with_target_platform(mut self, target_platform: Option<&'a Platform<'a>>) -> Self {
-        self.target_platform = target_platform;
+    /// Sets the target platform to the provided one. See `with_platform` for the accepted values.
+    pub fn with_target_platform(mut self, target_platform: impl Into<PlatformSpec<'a>>) -> Self {
+        self.target_platform = target_platform.into();
         self
     }
 
-    /// Sets the host platform to the provided one, or to evaluate against any platform if `None`.
-    pub fn with_host_platform(mut self, host_platform: Option<&'a Platform<'a>>) -> Self {
-        self.host_platform = host_platform;
+    /// Sets the host platform to the provided one. See `with_platform` for the accepted values.
+    pub fn with_host_platform(mut self, host_platform: impl Into<PlatformSpec<'a>>) -> Self {
+        self.host_platform = host_platform.into();
         self
     }
 
@@ -97,6 +111,23 @@ impl<'a> CargoOptions<'a> {
         self.omitted_packages.extend(package_ids);
         self
     }
+
+    /// Applies the settings in this Cargo configuration, the way a build in its repository would.
+    ///
+    /// * If the target platform is `PlatformSpec::Any`, the platform from `build.target` is used
+    ///   instead. See `CargoConfig::target_platform`.
+    /// * The `--cfg` flags and target features set by rustflags are applied to a single target
+    ///   platform. As with Cargo, they're only applied to the host platform if `build.target`
+    ///   isn't set.
+    ///
+    /// `[patch]` tables don't need to be applied here, since `cargo metadata` already resolves
+    /// dependencies with them.
+    ///
+    /// The configuration for a workspace is available through `Workspace::cargo_config`.
+    pub fn with_cargo_config(mut self, cargo_config: &'a CargoConfig) -> Self {
+        self.cargo_config = Some(cargo_config);
+        self
+    }
 }
 
 impl<'a> Default for CargoOptions<'a> {
@@ -120,8 +151,41 @@ impl<'g> CargoSet<'g> {
     ///
     /// This is also accessible through `FeatureQuery::resolve_cargo()`, and it may be more
     /// convenient to use that if the code is written in a "fluent" style.
+    ///
+    /// If the workspace's toolchain is pinned to a specific release, `version()` predicates are
+    /// evaluated against that release on host and target platforms without a rustc version.
     pub fn new(query: FeatureQuery<'g>, opts: &CargoOptions<'_>) -> Result<Self, Error> {
-        let build_state = CargoSetBuildState::new(&query, opts)?;
+        let rustc_version = query
+            .graph()
+            .package_graph
+            .workspace()
+            .toolchain()
+            .and_then(Toolchain::rustc_version);
+        let (config_host, config_target) = match opts.cargo_config {
+            Some(cargo_config) => {
+                with_cargo_config(cargo_config, opts.host_platform, opts.target_platform)
+            }
+            None => (None, None),
+        };
+
+        let mut opts: CargoOptions<'_> = opts.clone();
+        if let Some(platform) = &config_host {
+            opts.host_platform = PlatformSpec::Platform(platform);
+        }
+        if let Some(platform) = &config_target {
+            opts.target_platform = PlatformSpec::Platform(platform);
+        }
+
+        let host_platform = with_rustc_version(opts.host_platform, rustc_version);
+        let target_platform = with_rustc_version(opts.target_platform, rustc_version);
+        if let Some(platform) = &host_platform {
+            opts.host_platform = PlatformSpec::Platform(platform);
+        }
+        if let Some(platform) = &target_platform {
+            opts.target_platform = PlatformSpec::Platform(platform);
+        }
+
+        let build_state = CargoSetBuildState::new(&query, &opts)?;
         Ok(build_state.build(query))
     }
 
@@ -163,6 +227,52 @@ impl<'g> CargoSet<'g> {
     }
 }
 
+/// Returns copies of the host and target platforms with the settings in this Cargo configuration
+/// applied, for the ones that change. See `CargoOptions::with_cargo_config`.
+fn with_cargo_config<'a>(
+    cargo_config: &'a CargoConfig,
+    host_platform: PlatformSpec<'a>,
+    target_platform: PlatformSpec<'a>,
+) -> (Option<Platform<'a>>, Option<Platform<'a>>) {
+    let host_platform = if cargo_config.build_targets().is_empty() {
+        host_platform
+            .platform()
+            .map(|platform| cargo_config.apply_rustflags(platform))
+    } else {
+        None
+    };
+    let target_platform = match target_platform {
+        PlatformSpec::Any => {
+            let target_features = cargo_config
+                .build_targets()
+                .first()
+                .and_then(TargetFeatures::known)
+                .unwrap_or(TargetFeatures::Unknown);
+            cargo_config.target_platform(target_features)
+        }
+        spec => spec
+            .platform()
+            .map(|platform| cargo_config.apply_rustflags(platform)),
+    };
+    (host_platform, target_platform)
+}
+
+/// Returns a copy of this platform with the given rustc version, if it's a single platform that
+/// doesn't have one yet.
+fn with_rustc_version<'a>(
+    platform_spec: PlatformSpec<'a>,
+    rustc_version: Option<RustcVersion>,
+) -> Option<Platform<'a>> {
+    let rustc_version = rustc_version?;
+    let platform = platform_spec.platform()?;
+    if platform.rustc_version().is_some() {
+        return None;
+    }
+    let mut platform = platform.clone();
+    platform.set_rustc_version(Some(rustc_version));
+    Some(platform)
+}
+
 struct CargoSetBuildState<'a> {
     opts: &'a CargoOptions<'a>,
     omitted_packages: SortedSet<NodeIndex<PackageIx>>,
@@ -237,7 +347,7 @@ impl<'a> CargoSetBuildState<'a> {
         let mut proc_macro_edge_ixs = Vec::new();
 
         let is_enabled =
-            |link: PackageLink<'_>, kind: DependencyKind, platform: Option<&Platform<'_>>| {
+            |link: PackageLink<'_>, kind: DependencyKind, platform: PlatformSpec<'_>| {
                 let (from, to) = link.endpoints();
                 let req_status = link.req_for_kind(kind).status();
                 // Check the complete set to figure out whether we look at required_on or
@@ -257,16 +367,12 @@ impl<'a> CargoSetBuildState<'a> {
                         false
                     });
 
-                match (consider_optional, platform) {
-                    (true, Some(platform)) => {
-                        req_status.enabled_on(platform) != EnabledTernary::Disabled
-                    }
-                    (true, None) => req_status.enabled_on_any(),
-                    (false, Some(platform)) => {
-                        req_status.required_on(platform) != EnabledTernary::Disabled
-                    }
-                    (false, None) => req_status.required_on_any(),
-                }
+                let status = if consider_optional {
+                    req_status.enabled_on_spec(&platform)
+                } else {
+                    req_status.required_on_spec(&platform)
+                };
+                status != EnabledTernary::Disabled
             };
 
         // 2. Figure out what packages will be included on the target platform, i.e. normal + dev
@@ -350,15 +456,9 @@ impl<'a> CargoSetBuildState<'a> {
     fn new_v2(self, query: FeatureQuery<'_>) -> CargoSet {
         let graph = *query.graph();
 
-        let is_enabled = |link: CrossLink<'_>,
-                          kind: DependencyKind,
-                          platform: Option<&Platform<'_>>| {
+        let is_enabled = |link: CrossLink<'_>, kind: DependencyKind, platform: PlatformSpec<'_>| {
             let platform_status = link.status_for_kind(kind);
-
-            match platform {
-                Some(platform) => platform_status.enabled_on(platform) != EnabledTernary::Disabled,
-                None => !platform_status.is_never(),
-            }
+            platform_status.enabled_on_spec(&platform) != EnabledTernary::Disabled
         };
 
         // State to maintain between steps 1 and 2.
@@ -477,3 +577,547 @@ pub enum CargoResolverVersion {
     /// Cargo reference.
     V2,
 }
+
+/// A comparison of feature resolution under version 1 and version 2 of Cargo's feature resolver.
+///
+/// Switching a workspace to `resolver = "2"` can stop features from being enabled, since the V2
+/// resolver no longer unifies features across inactive platforms, build dependencies and
+/// dev-dependencies of non-initial packages. A `ResolverMigration` lists every feature whose
+/// status changes on the target or host platform, along with an activation chain explaining why
+/// it was enabled in the first place.
+pub struct ResolverMigration<'g> {
+    v1: CargoSet<'g>,
+    v2: CargoSet<'g>,
+    changes: Vec<FeatureChange<'g>>,
+}
+
+impl<'g> ResolverMigration<'g> {
+    /// Resolves this query with both resolver versions and compares the results.
+    ///
+    /// The resolver version in `opts` is ignored; all other options are used for both
+    /// resolutions.
+    pub fn new(query: FeatureQuery<'g>, opts: &CargoOptions<'_>) -> Result<Self, Error> {
+        let v1_opts = opts.clone().with_version(CargoResolverVersion::V1);
+        let v2_opts = opts.clone().with_version(CargoResolverVersion::V2);
+        let v1 = CargoSet::new(query.clone(), &v1_opts)?;
+        let v2 = CargoSet::new(query.clone(), &v2_opts)?;
+
+        // The V1 resolver unifies features across everything it considers, even packages that end
+        // up not being built. Activation chains for features it enables are found in this set.
+        let build_state = CargoSetBuildState::new(&query, &v1_opts)?;
+        let v1_unified = query.clone().resolve_with_fn(|query, link| {
+            if build_state.is_omitted(link.to().package_ix()) {
+                false
+            } else if query
+                .starts_from(link.from().feature_id())
+                .expect("valid ID")
+            {
+                true
+            } else {
+                !link.dev_only()
+            }
+        });
+        let v1_chains = ActivationChains::new(&query, &v1_unified);
+        let v2_chains =
+            ActivationChains::new(&query, &v2.target_features().union(v2.host_features()));
+
+        let mut changes = vec![];
+        for &platform in BuildPlatform::VALUES {
+            let (v1_set, v2_set) = match platform {
+                BuildPlatform::Target => (v1.target_features(), v2.target_features()),
+                BuildPlatform::Host => (v1.host_features(), v2.host_features()),
+            };
+            for feature_id in v1_set
+                .difference(v2_set)
+                .feature_ids(DependencyDirection::Forward)
+            {
+                changes.push(FeatureChange {
+                    feature_id,
+                    platform,
+                    kind: FeatureChangeKind::Disabled,
+                    chain: v1_chains.chain_to(feature_id),
+                });
+            }
+            for feature_id in v2_set
+                .difference(v1_set)
+                .feature_ids(DependencyDirection::Forward)
+            {
+                changes.push(FeatureChange {
+                    feature_id,
+                    platform,
+                    kind: FeatureChangeKind::Enabled,
+                    chain: v2_chains.chain_to(feature_id),
+                });
+            }
+        }
+        changes.sort_by_key(|change| (change.feature_id, change.platform));
+
+        Ok(Self { v1, v2, changes })
+    }
+
+    /// Returns the packages and features built with version 1 of the resolver.
+    pub fn v1_set(&self) -> &CargoSet<'g> {
+        &self.v1
+    }
+
+    /// Returns the packages and features built with version 2 of the resolver.
+    pub fn v2_set(&self) -> &CargoSet<'g> {
+        &self.v2
+    }
+
+    /// Returns every feature whose status changes, sorted by feature ID and platform.
+    pub fn changes(&self) -> &[FeatureChange<'g>] {
+        &self.changes
+    }
+
+    /// Iterates over features that are enabled with version 1 of the resolver, but not with
+    /// version 2.
+    pub fn disabled<'a>(&'a self) -> impl Iterator<Item = &'a FeatureChange<'g>> + 'a {
+        self.changes
+            .iter()
+            .filter(|change| change.kind == FeatureChangeKind::Disabled)
+    }
+
+    /// Returns true if both resolver versions produce the same results.
+    pub fn is_unchanged(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl<'g> fmt::Display for ResolverMigration<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let package_graph = self.v1.target_features().graph().package_graph;
+        let display_feature = |feature_id: FeatureId<'_>| {
+            let package = package_graph
+                .metadata(feature_id.package_id())
+                .expect("valid package ID");
+            format!(
+                "{} {}/{}",
+                package.name(),
+                package.version(),
+                feature_id.feature().unwrap_or("[base]")
+            )
+        };
+
+        for change in &self.changes {
+            let kind = match change.kind {
+                FeatureChangeKind::Disabled => "disabled",
+                FeatureChangeKind::Enabled => "enabled",
+            };
+            writeln!(
+                f,
+                "{} ({}): {}",
+                display_feature(change.feature_id),
+                change.platform,
+                kind
+            )?;
+            let chain: Vec<_> = change
+                .chain
+                .iter()
+                .map(|feature_id| display_feature(*feature_id))
+                .collect();
+            writeln!(f, "    via {}", chain.join(" -> "))?;
+        }
+        writeln!(
+            f,
+            "{} features change, {} no longer enabled",
+            self.changes.len(),
+            self.disabled().count()
+        )
+    }
+}
+
+impl<'g> Report for ResolverMigration<'g> {
+    fn to_document(&self) -> ReportDocument {
+        let package_graph = self.v1.target_features().graph().package_graph;
+        let display_feature = |feature_id: FeatureId<'_>| {
+            let package = package_graph
+                .metadata(feature_id.package_id())
+                .expect("valid package ID");
+            format!(
+                "{} {}/{}",
+                package.name(),
+                package.version(),
+                feature_id.feature().unwrap_or("[base]")
+            )
+        };
+
+        let mut document = ReportDocument::new("Resolver migration");
+        let section = document.add_section(None);
+        for change in &self.changes {
+            let kind = match change.kind {
+                FeatureChangeKind::Disabled => "disabled",
+                FeatureChangeKind::Enabled => "enabled",
+            };
+            let chain: Vec<_> = change
+                .chain
+                .iter()
+                .map(|feature_id| display_feature(*feature_id))
+                .collect();
+            section
+                .add_item(format!(
+                    "{} ({}): {}",
+                    display_feature(change.feature_id),
+                    change.platform,
+                    kind
+                ))
+                .add_detail(format!("via {}", chain.join(" -> ")));
+        }
+        document.set_summary(format!(
+            "{} features change, {} no longer enabled",
+            self.changes.len(),
+            self.disabled().count()
+        ));
+        document
+    }
+}
+
+/// A feature whose status changes between resolver versions, as part of a `ResolverMigration`.
+#[derive(Clone, Debug)]
+pub struct FeatureChange<'g> {
+    feature_id: FeatureId<'g>,
+    platform: BuildPlatform,
+    kind: FeatureChangeKind,
+    chain: Vec<FeatureId<'g>>,
+}
+
+impl<'g> FeatureChange<'g> {
+    /// Returns the feature whose status changes.
+    pub fn feature_id(&self) -> FeatureId<'g> {
+        self.feature_id
+    }
+
+    /// Returns the platform on which the status changes.
+    pub fn platform(&self) -> BuildPlatform {
+        self.platform
+    }
+
+    /// Returns the kind of change.
+    pub fn kind(&self) -> FeatureChangeKind {
+        self.kind
+    }
+
+    /// Returns a shortest activation chain for this feature, starting from an initial feature and
+    /// ending at this feature.
+    ///
+    /// For disabled features, this is how the V1 resolver enabled it; for enabled features, this
+    /// is how the V2 resolver enables it. Either way, the chain may pass through packages that
+    /// are not built on this platform.
+    pub fn chain(&self) -> &[FeatureId<'g>] {
+        &self.chain
+    }
+}
+
+/// The kind of a `FeatureChange`.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum FeatureChangeKind {
+    /// The feature is enabled with version 1 of the resolver, but not with version 2.
+    Disabled,
+    /// The feature is enabled with version 2 of the resolver, but not with version 1.
+    Enabled,
+}
+
+/// A platform that packages are built for: either the target or the host.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum BuildPlatform {
+    /// The target platform, for code that's included in the final build artifacts.
+    Target,
+    /// The host platform, for build scripts, build dependencies and procedural macros.
+    Host,
+}
+
+impl BuildPlatform {
+    /// A list of all `BuildPlatform` values.
+    pub const VALUES: &'static [Self] = &[BuildPlatform::Target, BuildPlatform::Host];
+}
+
+impl fmt::Display for BuildPlatform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildPlatform::Target => write!(f, "target"),
+            BuildPlatform::Host => write!(f, "host"),
+        }
+    }
+}
+
+/// Shortest paths from the initials of a query to every feature in a set.
+struct ActivationChains<'g> {
+    // Maps each feature reached to the feature it was first reached from. Initials map to
+    // themselves.
+    parents: HashMap<FeatureId<'g>, FeatureId<'g>>,
+}
+
+impl<'g> ActivationChains<'g> {
+    fn new(query: &FeatureQuery<'g>, set: &FeatureSet<'g>) -> Self {
+        let mut edges: HashMap<FeatureId<'g>, Vec<FeatureId<'g>>> = HashMap::new();
+        for (from, to, _) in set.links(DependencyDirection::Forward) {
+            edges.entry(from).or_default().push(to);
+        }
+
+        let mut parents = HashMap::new();
+        let mut queue = VecDeque::new();
+        for feature_id in set.feature_ids(DependencyDirection::Forward) {
+            if query.starts_from(feature_id) == Some(true) {
+                parents.insert(feature_id, feature_id);
+                queue.push_back(feature_id);
+            }
+        }
+        while let Some(feature_id) = queue.pop_front() {
+            for &to in edges.get(&feature_id).into_iter().flatten() {
+                if let Entry::Vacant(entry) = parents.entry(to) {
+                    entry.insert(feature_id);
+                    queue.push_back(to);
+                }
+            }
+        }
+
+        Self { parents }
+    }
+
+    fn chain_to(&self, feature_id: FeatureId<'g>) -> Vec<FeatureId<'g>> {
+        let mut chain = vec![feature_id];
+        let mut current = feature_id;
+        while let Some(&parent) = self.parents.get(&current) {
+            if parent == current {
+                break;
+            }
+            chain.push(parent);
+            current = parent;
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+/// A matrix of builds to simulate: every combination of a platform, a set of features for the
+/// initial packages, and whether dev-dependencies are included.
+///
+/// This is the shared definition of "the builds a workspace cares about". It can be serialized
+/// into and deserialized from configuration files, where platforms are represented by their
+/// triples:
+///
+/// ```toml
+/// platforms = ["x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc"]
+/// features = ["none", "default", "all"]
+/// include-dev = [false, true]
+/// ```
+///
+/// Fields that aren't specified take their default values, as described in `BuildMatrix::new`.
+/// Duplicate values are ignored.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct BuildMatrix<'a> {
+    #[serde(with = "platform_triples")]
+    platforms: Vec<Platform<'a>>,
+    #[serde(deserialize_with = "deserialize_dedup")]
+    features: Vec<StandardFeatures>,
+    #[serde(deserialize_with = "deserialize_dedup")]
+    include_dev: Vec<bool>,
+}
+
+impl<'a> BuildMatrix<'a> {
+    /// Creates a new `BuildMatrix` with the default settings:
+    ///
+    /// * evaluate dependencies against any possible platform
+    /// * build with no features, default features and all features
+    /// * build both with and without dev-dependencies.
+    pub fn new() -> Self {
+        Self {
+            platforms: vec![],
+            features: StandardFeatures::VALUES.to_vec(),
+            include_dev: vec![false, true],
+        }
+    }
+
+    /// Sets the platforms to simulate builds on.
+    ///
+    /// If no platforms are provided, dependencies are evaluated against any possible platform.
+    pub fn with_platforms(mut self, platforms: impl IntoIterator<Item = Platform<'a>>) -> Self {
+        self.platforms = platforms.into_iter().collect();
+        self
+    }
+
+    /// Sets the features to build initial packages with.
+    ///
+    /// Duplicate values are ignored.
+    pub fn with_features(mut self, features: impl IntoIterator<Item = StandardFeatures>) -> Self {
+        self.features = dedup(features);
+        self
+    }
+
+    /// Sets whether builds are simulated with dev-dependencies, without them, or both.
+    ///
+    /// Duplicate values are ignored.
+    pub fn with_include_dev(mut self, include_dev: impl IntoIterator<Item = bool>) -> Self {
+        self.include_dev = dedup(include_dev);
+        self
+    }
+
+    /// Returns the platforms builds are simulated on.
+    pub fn platforms(&self) -> &[Platform<'a>] {
+        &self.platforms
+    }
+
+    /// Returns the features initial packages are built with.
+    pub fn features(&self) -> &[StandardFeatures] {
+        &self.features
+    }
+
+    /// Returns whether builds are simulated with dev-dependencies, without them, or both.
+    pub fn include_dev(&self) -> &[bool] {
+        &self.include_dev
+    }
+
+    /// Returns the number of builds simulated on each platform.
+    pub fn builds_per_platform(&self) -> usize {
+        self.features.len() * self.include_dev.len()
+    }
+
+    /// Returns the platforms to simulate builds on, as indexes into `platforms()`. `None` stands
+    /// for "any platform", and is returned if no platforms were specified.
+    pub fn platform_idxs(&self) -> Vec<Option<usize>> {
+        if self.platforms.is_empty() {
+            vec![None]
+        } else {
+            (0..self.platforms.len()).map(Some).collect()
+        }
+    }
+
+    /// Iterates over every build in this matrix, grouped by platform and then by whether
+    /// dev-dependencies are included.
+    pub fn builds<'m>(&'m self) -> impl Iterator<Item = MatrixBuild<'m, 'a>> + 'm {
+        self.platform_idxs()
+            .into_iter()
+            .flat_map(move |platform_idx| {
+                let platform = platform_idx.map(|idx| &self.platforms[idx]);
+                self.include_dev.iter().flat_map(move |&include_dev| {
+                    self.features.iter().map(move |&features| MatrixBuild {
+                        platform_idx,
+                        platform,
+                        features,
+                        include_dev,
+                    })
+                })
+            })
+    }
+}
+
+impl<'a> Default for BuildMatrix<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A single build in a `BuildMatrix`.
+#[derive(Copy, Clone, Debug)]
+pub struct MatrixBuild<'m, 'a> {
+    /// The index of the platform in `BuildMatrix::platforms`, or `None` if dependencies are
+    /// evaluated against any platform.
+    pub platform_idx: Option<usize>,
+
+    /// The platform this build is simulated on, or `None` for any platform.
+    pub platform: Option<&'m Platform<'a>>,
+
+    /// The features initial packages are built with.
+    pub features: StandardFeatures,
+
+    /// Whether dev-dependencies of initial packages are included.
+    pub include_dev: bool,
+}
+
+impl<'m, 'a> MatrixBuild<'m, 'a> {
+    /// Returns `CargoOptions` for this build, with all other options set to their defaults.
+    pub fn cargo_options(&self) -> CargoOptions<'m> {
+        CargoOptions::new()
+            .with_dev_deps(self.include_dev)
+            .with_platform(self.platform)
+    }
+
+    /// Returns a `FeatureQuery` for these packages, built with the features for this build.
+    pub fn feature_query<'g>(
+        &self,
+        feature_graph: &FeatureGraph<'g>,
+        packages: &PackageQuery<'g>,
+    ) -> FeatureQuery<'g> {
+        self.features.feature_query(feature_graph, packages)
+    }
+}
+
+/// A standard set of features to build initial packages with.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StandardFeatures {
+    /// No features, equivalent to `--no-default-features`.
+    None,
+    /// The default features.
+    Default,
+    /// All features, equivalent to `--all-features`.
+    All,
+}
+
+impl StandardFeatures {
+    /// A list of all `StandardFeatures` values.
+    pub const VALUES: &'static [Self] = &[
+        StandardFeatures::None,
+        StandardFeatures::Default,
+        StandardFeatures::All,
+    ];
+
+    /// Returns a `FeatureQuery` for these packages, built with this set of features.
+    pub fn feature_query<'g>(
+        self,
+        feature_graph: &FeatureGraph<'g>,
+        packages: &PackageQuery<'g>,
+    ) -> FeatureQuery<'g> {
+        match self {
+            StandardFeatures::None => feature_graph.query_packages(packages, none_filter()),
+            StandardFeatures::Default => feature_graph.query_packages(packages, default_filter()),
+            StandardFeatures::All => feature_graph.query_packages(packages, all_filter()),
+        }
+    }
+}
+
+fn dedup<T: Copy + PartialEq>(values: impl IntoIterator<Item = T>) -> Vec<T> {
+    let mut out = Vec::new();
+    for value in values {
+        if !out.contains(&value) {
+            out.push(value);
+        }
+    }
+    out
+}
+
+fn deserialize_dedup<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de> + Copy + PartialEq,
+{
+    Ok(dedup(Vec::<T>::deserialize(deserializer)?))
+}
+
+/// Serializes platforms as their triples.
+mod platform_triples {
+    use super::*;
+    use serde::de::Error;
+    use serde::{Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        platforms: &[Platform<'_>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(platforms.iter().map(|platform| platform.triple()))
+    }
+
+    pub(super) fn deserialize<'de, 'a, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Platform<'a>>, D::Error> {
+        let triples = Vec::<String>::deserialize(deserializer)?;
+        triples
+            .into_iter()
+            .map(|triple| {
+                Platform::new(&triple, TargetFeatures::Unknown)
+                    .ok_or_else(|| D::Error::custom(format!("unknown platform '{}'", triple)))
+            })
+            .collect()
+    }
+}
@@ -3,10 +3,16 @@
 
 //! Code for handling cycles in dependency graphs.
 
-use crate::graph::{PackageGraph, PackageIx};
+use crate::graph::{
+    PackageGraph, PackageIx, PackageLink, PackageMetadata, PackageQuery, PackageResolver,
+    PackageSet, ResolverFn,
+};
 use crate::petgraph_support::scc::Sccs;
 use crate::Error;
 use crate::PackageId;
+use petgraph::algo::kosaraju_scc;
+use petgraph::prelude::*;
+use std::collections::HashMap;
 
 /// Contains information about dependency cycles.
 ///
@@ -44,3 +50,187 @@ impl<'g> Cycles<'g> {
             .map(move |scc| scc.iter().map(move |ix| &dep_graph[*ix]).collect())
     }
 }
+
+impl<'g> PackageQuery<'g> {
+    /// Resolves this query, following every link found along the way, and reports the
+    /// dependency cycles that were followed.
+    ///
+    /// This is the same as `resolve`, except that cycles are reported rather than silently
+    /// traversed. See `resolve_with_cycle_audit` for more.
+    pub fn resolve_cycle_audit(self) -> CycleAudit<'g> {
+        self.resolve_with_cycle_audit(ResolverFn(|_: &PackageQuery<'g>, _| true))
+    }
+
+    /// Resolves this query using the provided resolver, and reports the dependency cycles that
+    /// were followed.
+    ///
+    /// Cargo permits cycles if at least one of the links is dev-only. A resolve that follows such
+    /// a link can end up including the packages it started from as their own transitive
+    /// dependencies, which can be surprising. The audit explains every such result through the
+    /// cycles it came from.
+    pub fn resolve_with_cycle_audit(
+        self,
+        mut resolver: impl PackageResolver<'g>,
+    ) -> CycleAudit<'g> {
+        let graph = self.graph;
+        let initials: Vec<_> = graph
+            .package_ids()
+            .filter(|id| self.starts_from(id) == Some(true))
+            .collect();
+        let mut followed = vec![];
+        let set = self.resolve_with(ResolverFn(|query: &PackageQuery<'g>, link| {
+            let accepted = resolver.accept(query, link);
+            if accepted {
+                followed.push(link);
+            }
+            accepted
+        }));
+
+        let mut cycles: Vec<_> = graph
+            .sccs()
+            .multi_sccs()
+            .flat_map(|scc| {
+                let members: HashMap<_, _> =
+                    scc.iter().enumerate().map(|(idx, ix)| (*ix, idx)).collect();
+                let links: Vec<_> = followed
+                    .iter()
+                    .filter(|link| {
+                        members.contains_key(&link.from().package_ix())
+                            && members.contains_key(&link.to().package_ix())
+                    })
+                    .copied()
+                    .collect();
+                FollowedCycle::from_links(graph, &members, links, &initials)
+            })
+            .collect();
+        cycles.sort_by(|a, b| a.members[0].id().cmp(b.members[0].id()));
+
+        CycleAudit { set, cycles }
+    }
+}
+
+/// The result of resolving a query while auditing the dependency cycles it follows.
+///
+/// Returned by `PackageQuery::resolve_with_cycle_audit`.
+#[derive(Clone, Debug)]
+pub struct CycleAudit<'g> {
+    set: PackageSet<'g>,
+    cycles: Vec<FollowedCycle<'g>>,
+}
+
+impl<'g> CycleAudit<'g> {
+    /// Returns the resolved set of packages.
+    pub fn set(&self) -> &PackageSet<'g> {
+        &self.set
+    }
+
+    /// Consumes the audit, returning the resolved set of packages.
+    pub fn into_set(self) -> PackageSet<'g> {
+        self.set
+    }
+
+    /// Returns the cycles followed during resolution, sorted by the ID of their first member.
+    pub fn cycles(&self) -> &[FollowedCycle<'g>] {
+        &self.cycles
+    }
+
+    /// Returns true if the packages the query started from were reached again through a cycle.
+    pub fn reaches_initials(&self) -> bool {
+        self.cycles.iter().any(|cycle| cycle.includes_initial)
+    }
+}
+
+/// A dependency cycle followed while resolving a query.
+#[derive(Clone, Debug)]
+pub struct FollowedCycle<'g> {
+    members: Vec<PackageMetadata<'g>>,
+    links: Vec<PackageLink<'g>>,
+    includes_initial: bool,
+}
+
+impl<'g> FollowedCycle<'g> {
+    /// Returns the packages in this cycle, sorted by package ID.
+    pub fn members(&self) -> &[PackageMetadata<'g>] {
+        &self.members
+    }
+
+    /// Returns the links between members of this cycle that were followed.
+    pub fn links(&self) -> &[PackageLink<'g>] {
+        &self.links
+    }
+
+    /// Iterates over the dev-only links in this cycle.
+    ///
+    /// Cargo only permits cycles with at least one dev-only link, so this is normally non-empty.
+    pub fn dev_links<'a>(&'a self) -> impl Iterator<Item = PackageLink<'g>> + 'a {
+        self.links.iter().filter(|link| link.dev_only()).copied()
+    }
+
+    /// Returns true if this cycle includes a package the query started from.
+    ///
+    /// Such packages are their own transitive dependencies (or dependents, for reverse queries)
+    /// through this cycle.
+    pub fn includes_initial(&self) -> bool {
+        self.includes_initial
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    /// Returns the cycles among the followed links between members of one SCC. Not every link
+    /// in the SCC may have been followed, so this may return zero or several cycles.
+    fn from_links(
+        graph: &'g PackageGraph,
+        members: &HashMap<NodeIndex<PackageIx>, usize>,
+        links: Vec<PackageLink<'g>>,
+        initials: &[&'g PackageId],
+    ) -> Vec<Self> {
+        let mut followed: Graph<NodeIndex<PackageIx>, ()> = Graph::new();
+        let mut nodes = HashMap::new();
+        for ix in members.keys() {
+            nodes.insert(*ix, followed.add_node(*ix));
+        }
+        for link in &links {
+            followed.add_edge(
+                nodes[&link.from().package_ix()],
+                nodes[&link.to().package_ix()],
+                (),
+            );
+        }
+
+        kosaraju_scc(&followed)
+            .into_iter()
+            .filter(|scc| scc.len() > 1)
+            .map(|scc| {
+                let mut cycle_members: Vec<_> = scc
+                    .iter()
+                    .map(|node| {
+                        graph
+                            .metadata(&graph.dep_graph()[followed[*node]])
+                            .expect("valid package ID")
+                    })
+                    .collect();
+                cycle_members.sort_by_key(|package| package.id());
+                let in_cycle = |package: PackageMetadata<'g>| {
+                    cycle_members
+                        .iter()
+                        .any(|member| member.id() == package.id())
+                };
+                let cycle_links: Vec<_> = links
+                    .iter()
+                    .filter(|link| in_cycle(link.from()) && in_cycle(link.to()))
+                    .copied()
+                    .collect();
+                let includes_initial = cycle_members
+                    .iter()
+                    .any(|member| initials.contains(&member.id()));
+                Self {
+                    members: cycle_members,
+                    links: cycle_links,
+                    includes_initial,
+                }
+            })
+            .collect()
+    }
+}
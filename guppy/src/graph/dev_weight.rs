@@ -0,0 +1,285 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{DependencyDirection, PackageGraph, PackageLink, PackageMetadata, PackageSet};
+use crate::report::{Report, ReportDocument};
+use crate::PackageId;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+
+impl PackageGraph {
+    /// Reports how much of the graph exists only because of dev-dependencies of workspace
+    /// members.
+    ///
+    /// For each workspace member, packages that are built for its tests but not for the member
+    /// itself are counted, and its direct dev-dependencies are ranked by how many packages would
+    /// no longer be built if they were removed. Dependencies are evaluated across all platforms.
+    pub fn dev_weight_report(&self) -> DevWeightReport<'_> {
+        let members: Vec<_> = self
+            .workspace()
+            .members()
+            .map(|(_, member)| MemberDevWeight::new(member))
+            .collect();
+
+        let normal = self
+            .query_workspace()
+            .resolve_with_fn(|_, link| !link.dev_only());
+        let full = self.query_workspace().resolve_with_fn(|query, link| {
+            let is_initial = query
+                .starts_from(link.from().id())
+                .expect("valid package ID");
+            follow_dev(is_initial, link)
+        });
+        let dev_only = packages_in(&full.difference(&normal));
+
+        DevWeightReport { members, dev_only }
+    }
+}
+
+/// A report of how much of a package graph exists only because of dev-dependencies.
+///
+/// Returned by `PackageGraph::dev_weight_report`. The `Display` implementation renders the report
+/// in a human-readable format.
+#[derive(Clone, Debug)]
+pub struct DevWeightReport<'g> {
+    members: Vec<MemberDevWeight<'g>>,
+    dev_only: Vec<PackageMetadata<'g>>,
+}
+
+impl<'g> DevWeightReport<'g> {
+    /// Returns the dev-dependency weight of each workspace member, ordered by workspace path.
+    pub fn members(&self) -> &[MemberDevWeight<'g>] {
+        &self.members
+    }
+
+    /// Returns packages that are only built for the tests of workspace members, across the
+    /// whole workspace, in topological order.
+    ///
+    /// This is smaller than the union of `MemberDevWeight::dev_only` across members, since a
+    /// package only needed for one member's tests may be a normal dependency of another member.
+    pub fn dev_only(&self) -> &[PackageMetadata<'g>] {
+        &self.dev_only
+    }
+
+    /// Returns the estimated number of units that are only built for the tests of workspace
+    /// members.
+    ///
+    /// See `MemberDevWeight::dev_only_units` for how units are estimated.
+    pub fn dev_only_units(&self) -> usize {
+        estimated_units(&self.dev_only)
+    }
+}
+
+impl<'g> fmt::Display for DevWeightReport<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for member in &self.members {
+            writeln!(
+                f,
+                "{}: {} packages, {} only for dev ({} unique crates, ~{} units)",
+                member.member.name(),
+                member.normal_count + member.dev_only.len(),
+                member.dev_only.len(),
+                member.dev_only_crates(),
+                member.dev_only_units()
+            )?;
+            for suggestion in &member.suggestions {
+                writeln!(
+                    f,
+                    "    removing dev-dependency {} would drop {} packages",
+                    suggestion.link.to().name(),
+                    suggestion.dropped.len()
+                )?;
+            }
+        }
+        writeln!(
+            f,
+            "workspace: {} packages only for dev (~{} units)",
+            self.dev_only.len(),
+            self.dev_only_units()
+        )
+    }
+}
+
+impl<'g> Report for DevWeightReport<'g> {
+    fn to_document(&self) -> ReportDocument {
+        let mut document = ReportDocument::new("Dev-dependency weight");
+        let section = document.add_section(None);
+        for member in &self.members {
+            let item = section.add_item(format!(
+                "{}: {} packages, {} only for dev ({} unique crates, ~{} units)",
+                member.member.name(),
+                member.normal_count + member.dev_only.len(),
+                member.dev_only.len(),
+                member.dev_only_crates(),
+                member.dev_only_units()
+            ));
+            for suggestion in &member.suggestions {
+                item.add_detail(format!(
+                    "removing dev-dependency {} would drop {} packages",
+                    suggestion.link.to().name(),
+                    suggestion.dropped.len()
+                ));
+            }
+        }
+        document.set_summary(format!(
+            "workspace: {} packages only for dev (~{} units)",
+            self.dev_only.len(),
+            self.dev_only_units()
+        ));
+        document
+    }
+}
+
+/// The dev-dependency weight of a single workspace member.
+#[derive(Clone, Debug)]
+pub struct MemberDevWeight<'g> {
+    member: PackageMetadata<'g>,
+    normal_count: usize,
+    dev_only: Vec<PackageMetadata<'g>>,
+    suggestions: Vec<DevDepSuggestion<'g>>,
+}
+
+impl<'g> MemberDevWeight<'g> {
+    /// Computes the dev-dependency weight of this package.
+    pub fn new(member: PackageMetadata<'g>) -> Self {
+        let graph = member.graph;
+        let normal = graph
+            .query_forward(std::iter::once(member.id()))
+            .expect("valid package ID")
+            .resolve_with_fn(|_, link| !link.dev_only());
+        let full = Self::full_set(member, None);
+        let dev_only = packages_in(&full.difference(&normal));
+
+        let mut seen = HashSet::new();
+        let mut suggestions: Vec<_> = member
+            .direct_links()
+            .filter(|link| link.dev_only() && seen.insert(link.to().id()))
+            .filter_map(|link| {
+                let without = Self::full_set(member, Some(link.to().id()));
+                let dropped = packages_in(&full.difference(&without));
+                if dropped.is_empty() {
+                    None
+                } else {
+                    Some(DevDepSuggestion { link, dropped })
+                }
+            })
+            .collect();
+        suggestions.sort_by(|a, b| {
+            b.dropped
+                .len()
+                .cmp(&a.dropped.len())
+                .then_with(|| a.link.to().id().cmp(b.link.to().id()))
+        });
+
+        Self {
+            member,
+            normal_count: normal.len(),
+            dev_only,
+            suggestions,
+        }
+    }
+
+    /// Returns the workspace member this is for.
+    pub fn member(&self) -> PackageMetadata<'g> {
+        self.member
+    }
+
+    /// Returns the number of packages built for the member itself, including the member.
+    pub fn normal_count(&self) -> usize {
+        self.normal_count
+    }
+
+    /// Returns the packages built for the member's tests but not for the member itself, in
+    /// topological order.
+    pub fn dev_only(&self) -> &[PackageMetadata<'g>] {
+        &self.dev_only
+    }
+
+    /// Returns the number of distinct crate names among `dev_only`.
+    pub fn dev_only_crates(&self) -> usize {
+        self.dev_only
+            .iter()
+            .map(|package| package.name())
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+
+    /// Returns the estimated number of units Cargo builds for `dev_only`.
+    ///
+    /// Each package is counted as one unit for its library, and two more if it has a build
+    /// script: one to compile the build script and one to run it.
+    pub fn dev_only_units(&self) -> usize {
+        estimated_units(&self.dev_only)
+    }
+
+    /// Returns the member's direct dev-dependencies whose removal would stop packages from being
+    /// built, ordered by the number of packages that would no longer be built, largest first.
+    pub fn suggestions(&self) -> &[DevDepSuggestion<'g>] {
+        &self.suggestions
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    /// Returns the packages built for the member's tests, optionally pretending that the member
+    /// doesn't dev-depend on `skip`.
+    fn full_set(member: PackageMetadata<'g>, skip: Option<&PackageId>) -> PackageSet<'g> {
+        member
+            .graph
+            .query_forward(std::iter::once(member.id()))
+            .expect("valid package ID")
+            .resolve_with_fn(|query, link| {
+                let is_initial = query
+                    .starts_from(link.from().id())
+                    .expect("valid package ID");
+                if is_initial && link.dev_only() && Some(link.to().id()) == skip {
+                    return false;
+                }
+                follow_dev(is_initial, link)
+            })
+    }
+}
+
+/// A direct dev-dependency whose removal would stop packages from being built, as part of a
+/// `MemberDevWeight`.
+#[derive(Clone, Debug)]
+pub struct DevDepSuggestion<'g> {
+    link: PackageLink<'g>,
+    dropped: Vec<PackageMetadata<'g>>,
+}
+
+impl<'g> DevDepSuggestion<'g> {
+    /// Returns the link from the workspace member to the dev-dependency.
+    pub fn link(&self) -> PackageLink<'g> {
+        self.link
+    }
+
+    /// Returns the packages that would no longer be built for the member's tests, in
+    /// topological order.
+    ///
+    /// This includes the dev-dependency itself.
+    pub fn dropped(&self) -> &[PackageMetadata<'g>] {
+        &self.dropped
+    }
+}
+
+// ---
+// Helper methods
+// ---
+
+/// Dev-dependencies are only followed from initials, matching what `cargo test` builds.
+fn follow_dev(is_initial: bool, link: PackageLink<'_>) -> bool {
+    is_initial || !link.dev_only()
+}
+
+fn packages_in<'g>(set: &PackageSet<'g>) -> Vec<PackageMetadata<'g>> {
+    set.packages(DependencyDirection::Forward).collect()
+}
+
+fn estimated_units(packages: &[PackageMetadata<'_>]) -> usize {
+    packages
+        .iter()
+        .map(|package| if package.has_build_script() { 3 } else { 1 })
+        .sum()
+}
@@ -0,0 +1,237 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Re-emitting graphs as `cargo metadata` output.
+
+use crate::graph::{
+    BuildTarget, BuildTargetId, BuildTargetKind, DependencyDirection, PackageGraph, PackageLink,
+    PackageMetadata, PackageSet,
+};
+use crate::{CargoMetadata, Error, PackageId};
+use serde_json::{json, Map};
+use std::io;
+
+impl PackageGraph {
+    /// Re-emits this graph in the format returned by `cargo metadata`.
+    ///
+    /// The result can be serialized with `CargoMetadata::serialize` and fed into existing tools
+    /// that consume `cargo metadata` JSON.
+    pub fn to_cargo_metadata(&self) -> Result<CargoMetadata, Error> {
+        self.resolve_all().to_cargo_metadata()
+    }
+}
+
+impl<'g> PackageSet<'g> {
+    /// Re-emits the packages in this set in the format returned by `cargo metadata`.
+    ///
+    /// Packages outside this set are not included, and neither are resolved dependency edges
+    /// pointing to them. Dependency declarations in package manifests are preserved as-is.
+    pub fn to_cargo_metadata(&self) -> Result<CargoMetadata, Error> {
+        self.to_cargo_metadata_filtered(|_| true)
+    }
+
+    /// Re-emits the packages in this set in the format returned by `cargo metadata`, only keeping
+    /// resolved dependency edges for which `link_filter` returns true.
+    ///
+    /// This can be used to produce a platform-specific view of the graph, by filtering out links
+    /// that aren't enabled on a particular platform.
+    pub fn to_cargo_metadata_filtered(
+        &self,
+        mut link_filter: impl FnMut(PackageLink<'g>) -> bool,
+    ) -> Result<CargoMetadata, Error> {
+        let graph = self.graph;
+        let mut packages: Vec<_> = self.packages(DependencyDirection::Forward).collect();
+        // cargo metadata returns packages sorted by ID.
+        packages.sort_by_key(|package| package.id());
+
+        let mut package_values = Vec::with_capacity(packages.len());
+        let mut node_values = Vec::with_capacity(packages.len());
+        for package in &packages {
+            package_values.push(package_json(*package)?);
+
+            let inner = package.inner;
+            let mut deps = vec![];
+            let mut dependencies = vec![];
+            for node_dep in &inner.resolved_deps {
+                let to = match graph.metadata(&PackageId::from_metadata(node_dep.pkg.clone())) {
+                    Some(to) => to,
+                    None => continue,
+                };
+                if self.contains(to.id()) != Some(true) {
+                    continue;
+                }
+                let link = match graph.link_between(package.package_ix(), to.package_ix()) {
+                    Some(link) => link,
+                    None => continue,
+                };
+                if !link_filter(link) {
+                    continue;
+                }
+                deps.push(serde_json::to_value(node_dep).map_err(Error::MetadataSerializeError)?);
+                dependencies.push(json!(to.id().repr()));
+            }
+
+            node_values.push(json!({
+                "id": package.id().repr(),
+                "deps": deps,
+                "dependencies": dependencies,
+                "features": inner.resolved_features,
+            }));
+        }
+
+        let workspace = graph.workspace();
+        let workspace_members: Vec<_> = packages
+            .iter()
+            .filter(|package| package.in_workspace())
+            .map(|package| json!(package.id().repr()))
+            .collect();
+
+        let value = json!({
+            "packages": package_values,
+            "workspace_members": workspace_members,
+            "resolve": {
+                "nodes": node_values,
+                "root": null,
+            },
+            "workspace_root": workspace.root(),
+            "target_directory": workspace.target_directory(),
+            "version": 1,
+        });
+
+        let metadata = serde_json::from_value(value).map_err(Error::MetadataSerializeError)?;
+        Ok(CargoMetadata(metadata))
+    }
+}
+
+impl<'g> PackageSet<'g> {
+    /// Writes the packages in this set to `writer` as newline-delimited JSON, in the given
+    /// direction.
+    ///
+    /// See `NdjsonWriter` for the format.
+    pub fn write_ndjson(
+        &self,
+        direction: DependencyDirection,
+        writer: impl io::Write,
+    ) -> Result<(), Error> {
+        let mut writer = NdjsonWriter::new(writer);
+        for package in self.packages(direction) {
+            writer.write_package(package)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Writes packages as newline-delimited JSON, one package per line.
+///
+/// Each line is a package in the same format as the `packages` entries in `cargo metadata` output.
+/// Unlike `to_cargo_metadata`, packages are written out as they're visited rather than collected
+/// into a single document first, so very large selections can be piped into tools like `jq`
+/// without buffering them in memory.
+///
+/// Output isn't buffered: wrap `writer` in an `io::BufWriter` if it makes a system call for each
+/// write.
+pub struct NdjsonWriter<W> {
+    writer: W,
+    count: usize,
+}
+
+impl<W: io::Write> NdjsonWriter<W> {
+    /// Creates a new writer that writes packages to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer, count: 0 }
+    }
+
+    /// Writes this package as a single line.
+    pub fn write_package(&mut self, package: PackageMetadata<'_>) -> Result<(), Error> {
+        let value = package_json(package)?;
+        serde_json::to_writer(&mut self.writer, &value).map_err(Error::MetadataSerializeError)?;
+        self.writer
+            .write_all(b"\n")
+            .map_err(|err| Error::MetadataSerializeError(serde_json::Error::io(err)))?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Returns the number of packages written so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Flushes the underlying writer and returns it.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.writer
+            .flush()
+            .map_err(|err| Error::MetadataSerializeError(serde_json::Error::io(err)))?;
+        Ok(self.writer)
+    }
+}
+
+fn package_json(package: PackageMetadata<'_>) -> Result<serde_json::Value, Error> {
+    let inner = package.inner;
+
+    let features: Map<_, _> = inner
+        .features
+        .iter()
+        .filter_map(|(name, deps)| {
+            // Optional dependencies are not part of the features map in cargo metadata.
+            deps.as_ref().map(|deps| (name.to_string(), json!(deps)))
+        })
+        .collect();
+
+    let targets: Vec<_> = package.build_targets().map(target_json).collect();
+
+    let dependencies =
+        serde_json::to_value(&inner.dependencies).map_err(Error::MetadataSerializeError)?;
+
+    Ok(json!({
+        "name": inner.name,
+        "version": inner.version.to_string(),
+        "authors": inner.authors,
+        "id": package.id().repr(),
+        "source": inner.source,
+        "description": inner.description,
+        "dependencies": dependencies,
+        "license": inner.license,
+        "license_file": inner.license_file,
+        "targets": targets,
+        "features": features,
+        "manifest_path": inner.manifest_path,
+        "categories": inner.categories,
+        "keywords": inner.keywords,
+        "readme": inner.readme,
+        "repository": inner.repository,
+        "edition": inner.edition,
+        "metadata": inner.metadata_table,
+        "links": inner.links,
+        "publish": inner.publish,
+    }))
+}
+
+fn target_json(build_target: BuildTarget<'_>) -> serde_json::Value {
+    let crate_types: Vec<&str> = match build_target.kind() {
+        BuildTargetKind::LibraryOrExample(crate_types) => {
+            crate_types.iter().map(|s| s.as_str()).collect()
+        }
+        BuildTargetKind::ProcMacro => vec!["proc-macro"],
+        BuildTargetKind::Binary => vec!["bin"],
+    };
+    let kind = match build_target.id() {
+        BuildTargetId::Library => crate_types.clone(),
+        BuildTargetId::BuildScript => vec!["custom-build"],
+        BuildTargetId::Binary(_) => vec!["bin"],
+        BuildTargetId::Example(_) => vec!["example"],
+        BuildTargetId::Test(_) => vec!["test"],
+        BuildTargetId::Benchmark(_) => vec!["bench"],
+    };
+
+    json!({
+        "name": build_target.name(),
+        "kind": kind,
+        "crate_types": crate_types,
+        "required-features": build_target.required_features(),
+        "src_path": build_target.path(),
+        "edition": build_target.edition(),
+        "doctest": build_target.doc_tests(),
+    })
+}
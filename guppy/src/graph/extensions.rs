@@ -0,0 +1,105 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Typed parsers for custom metadata tables.
+//!
+//! Tools built on guppy commonly store their own configuration in `Cargo.toml`, under
+//! `[package.metadata.<namespace>]`. Instead of having each tool re-parse `metadata_table`, a
+//! `MetadataExtension` can be registered with a `PackageGraph` once, after which parsed values are
+//! available through `PackageMetadata::extension`.
+
+use crate::graph::{PackageGraph, PackageMetadata};
+use crate::{Error, PackageId};
+use serde_json::Value as JsonValue;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::error;
+use std::sync::Arc;
+
+/// A typed parser for a `[package.metadata.<namespace>]` table.
+///
+/// With serde, an implementation is typically a couple of lines:
+///
+/// ```
+/// use guppy::graph::MetadataExtension;
+/// use serde::Deserialize;
+/// use serde_json::Value;
+/// use std::error;
+///
+/// #[derive(Deserialize)]
+/// struct Lints {
+///     deny: Vec<String>,
+/// }
+///
+/// impl MetadataExtension for Lints {
+///     const NAMESPACE: &'static str = "lints";
+///
+///     fn parse(table: &Value) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+///         Ok(serde_json::from_value(table.clone())?)
+///     }
+/// }
+/// ```
+pub trait MetadataExtension: Any + Send + Sync + Sized {
+    /// The namespace this extension reads from. Values are read from
+    /// `[package.metadata.<NAMESPACE>]`.
+    const NAMESPACE: &'static str;
+
+    /// Parses the table for a package.
+    ///
+    /// This is only called for packages that have a table under `NAMESPACE`.
+    fn parse(table: &JsonValue) -> Result<Self, Box<dyn error::Error + Send + Sync>>;
+}
+
+pub(super) type ExtensionValues = HashMap<PackageId, Arc<dyn Any + Send + Sync>>;
+
+impl PackageGraph {
+    /// Parses the tables for this extension across every package in the graph, and attaches the
+    /// results to each package.
+    ///
+    /// Values from a previous registration of the same extension are replaced. Returns the number
+    /// of packages with a table under the extension's namespace, or an error if any of them failed
+    /// to parse. Nothing is attached if an error occurs.
+    pub fn register_extension<T: MetadataExtension>(&mut self) -> Result<usize, Error> {
+        let mut values = ExtensionValues::new();
+        for package in self.packages() {
+            let table = match package.metadata_table().get(T::NAMESPACE) {
+                Some(table) => table,
+                None => continue,
+            };
+            let value = T::parse(table).map_err(|err| {
+                Error::MetadataExtensionError(package.id().clone(), T::NAMESPACE, err)
+            })?;
+            values.insert(package.id().clone(), Arc::new(value));
+        }
+
+        let count = values.len();
+        self.data.extensions.insert(TypeId::of::<T>(), values);
+        Ok(count)
+    }
+
+    /// Returns true if this extension was registered through `register_extension`.
+    pub fn has_extension<T: MetadataExtension>(&self) -> bool {
+        self.data.extensions.contains_key(&TypeId::of::<T>())
+    }
+}
+
+impl<'g> PackageMetadata<'g> {
+    /// Returns the value parsed by this extension for this package.
+    ///
+    /// Returns `None` if the extension wasn't registered through
+    /// `PackageGraph::register_extension`, or if this package doesn't have a table under its
+    /// namespace.
+    pub fn extension<T: MetadataExtension>(&self) -> Option<&'g T> {
+        let value = self
+            .graph
+            .data
+            .extensions
+            .get(&TypeId::of::<T>())?
+            .get(self.id())?;
+        Some(
+            value
+                .downcast_ref::<T>()
+                .expect("extension values are keyed by their type"),
+        )
+    }
+}
@@ -9,7 +9,7 @@ use crate::graph::{
     PlatformStatus, PlatformStatusImpl,
 };
 use crate::petgraph_support::scc::Sccs;
-use crate::{DependencyKind, Error, PackageId};
+use crate::{DependencyKind, Error, FeatureName, PackageId};
 use once_cell::sync::OnceCell;
 use petgraph::algo::has_path_connecting;
 use petgraph::prelude::*;
@@ -322,8 +322,8 @@ impl<'g> FeatureGraph<'g> {
 ///
 /// Returned by various methods on `FeatureGraph` and `FeatureQuery`.
 ///
-/// `From` impls are available for `(&'g PackageId, &'g str)` and `(&'g PackageId, Option<&'g str>)`
-/// tuples.
+/// `From` impls are available for `(&'g PackageId, &'g str)`, `(&'g PackageId, &'g FeatureName)`
+/// and `(&'g PackageId, Option<&'g str>)` tuples.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct FeatureId<'g> {
     package_id: &'g PackageId,
@@ -389,6 +389,12 @@ impl<'g> From<(&'g PackageId, &'g str)> for FeatureId<'g> {
     }
 }
 
+impl<'g> From<(&'g PackageId, &'g FeatureName)> for FeatureId<'g> {
+    fn from((package_id, feature): (&'g PackageId, &'g FeatureName)) -> Self {
+        FeatureId::new(package_id, feature.as_str())
+    }
+}
+
 impl<'g> From<(&'g PackageId, Option<&'g str>)> for FeatureId<'g> {
     fn from((package_id, feature): (&'g PackageId, Option<&'g str>)) -> Self {
         FeatureId {
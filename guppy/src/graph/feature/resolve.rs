@@ -7,7 +7,7 @@ use crate::graph::feature::{
     FeatureQuery, FeatureResolver,
 };
 use crate::graph::resolve_core::ResolveCore;
-use crate::graph::{DependencyDirection, PackageMetadata, PackageSet};
+use crate::graph::{DependencyDirection, PackageMetadata, PackageSet, SetOrigin};
 use crate::petgraph_support::IxBitSet;
 use crate::PackageId;
 use fixedbitset::FixedBitSet;
@@ -213,7 +213,7 @@ impl<'g> FeatureSet<'g> {
                     .package_ix_for_feature_ix(NodeIndex::new(feature_ix))
             })
             .collect();
-        PackageSet::from_included(self.graph.package_graph, included.0)
+        PackageSet::from_included(self.graph.package_graph, included.0, SetOrigin::FeatureSet)
     }
 
     // ---
@@ -380,8 +380,7 @@ impl<'g> FeatureSet<'g> {
         }
     }
 
-    // Currently a helper for debugging -- will be made public in the future.
-    #[allow(dead_code)]
+    // Used for activation chains -- will be made public in the future.
     pub(crate) fn links<'a>(
         &'a self,
         direction: DependencyDirection,
@@ -0,0 +1,291 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The cost of the features exposed by workspace members.
+
+use crate::graph::cargo::{CargoOptions, CargoSet};
+use crate::graph::feature::{default_filter, feature_id_filter, FeatureId, FeatureSet};
+use crate::graph::{DependencyDirection, PackageGraph, PackageMetadata};
+use crate::report::{Report, ReportDocument};
+use crate::Error;
+use std::fmt;
+use std::iter;
+
+impl PackageGraph {
+    /// Returns a matrix of workspace members and the features they expose, with the third-party
+    /// packages and features that enabling each feature adds to the member's default build.
+    ///
+    /// Each named feature and optional dependency of a member is enabled on its own, on top of the
+    /// member's default features. Builds are simulated with these options, with the member as the
+    /// only initial package, and packages built for either the target or the host are counted.
+    pub fn feature_impact_matrix(
+        &self,
+        opts: &CargoOptions<'_>,
+    ) -> Result<FeatureImpactMatrix<'_>, Error> {
+        let members = self
+            .workspace()
+            .members()
+            .map(|(_, member)| MemberFeatureImpact::new(member, opts))
+            .collect::<Result<_, _>>()?;
+        Ok(FeatureImpactMatrix { members })
+    }
+}
+
+/// A matrix of workspace members and the features they expose, with the cost of enabling each
+/// feature.
+///
+/// Returned by `PackageGraph::feature_impact_matrix`. The `Display` implementation renders the
+/// matrix in a human-readable format, with one row per feature followed by the packages it adds.
+#[derive(Clone, Debug)]
+pub struct FeatureImpactMatrix<'g> {
+    members: Vec<MemberFeatureImpact<'g>>,
+}
+
+impl<'g> FeatureImpactMatrix<'g> {
+    /// Returns the features of each workspace member, ordered by workspace path.
+    pub fn members(&self) -> &[MemberFeatureImpact<'g>] {
+        &self.members
+    }
+
+    /// Returns the cost of enabling this feature of the workspace member with this name.
+    ///
+    /// Returns `None` if there's no such member, or if it doesn't expose the feature.
+    pub fn impact(&self, member: &str, feature: &str) -> Option<&FeatureImpact<'g>> {
+        self.members
+            .iter()
+            .find(|impact| impact.member.name() == member)?
+            .feature(feature)
+    }
+
+    /// Returns the number of features, across all workspace members, that add third-party
+    /// packages to the default build.
+    pub fn costly_features(&self) -> usize {
+        self.members
+            .iter()
+            .flat_map(|member| &member.features)
+            .filter(|impact| !impact.added_packages.is_empty())
+            .count()
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn summary(&self) -> String {
+        format!(
+            "{} features add third-party packages, across {} members",
+            self.costly_features(),
+            self.members.len()
+        )
+    }
+}
+
+impl<'g> fmt::Display for FeatureImpactMatrix<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for member in &self.members {
+            writeln!(f, "{}", member.summary())?;
+            for impact in &member.features {
+                writeln!(f, "    {}", impact)?;
+                for package in &impact.added_packages {
+                    writeln!(f, "        {} {}", package.name(), package.version())?;
+                }
+            }
+        }
+        writeln!(f, "{}", self.summary())
+    }
+}
+
+impl<'g> Report for FeatureImpactMatrix<'g> {
+    fn to_document(&self) -> ReportDocument {
+        let mut document = ReportDocument::new("Feature impact");
+        for member in &self.members {
+            let section = document.add_section(Some(member.summary()));
+            for impact in &member.features {
+                let item = section.add_item(impact.to_string());
+                for package in &impact.added_packages {
+                    item.add_detail(format!("{} {}", package.name(), package.version()));
+                }
+            }
+        }
+        document.set_summary(self.summary());
+        document
+    }
+}
+
+/// The features exposed by a single workspace member, as part of a `FeatureImpactMatrix`.
+#[derive(Clone, Debug)]
+pub struct MemberFeatureImpact<'g> {
+    member: PackageMetadata<'g>,
+    default_count: usize,
+    features: Vec<FeatureImpact<'g>>,
+}
+
+impl<'g> MemberFeatureImpact<'g> {
+    /// Computes the cost of enabling each feature of this package, with these options.
+    pub fn new(member: PackageMetadata<'g>, opts: &CargoOptions<'_>) -> Result<Self, Error> {
+        let default_build = Self::build(member, None, opts)?;
+        let default_packages = default_build.to_package_set();
+
+        let mut names: Vec<_> = member
+            .named_features()
+            .filter(|feature| *feature != "default")
+            .chain(member.optional_deps())
+            .collect();
+        names.sort_unstable();
+
+        let mut features = Vec::with_capacity(names.len());
+        for feature in names {
+            if default_build.contains(FeatureId::new(member.id(), feature)) == Some(true) {
+                features.push(FeatureImpact {
+                    feature,
+                    default: true,
+                    added_packages: vec![],
+                    added_features: vec![],
+                });
+                continue;
+            }
+
+            let build = Self::build(member, Some(feature), opts)?;
+            let added_packages = build
+                .to_package_set()
+                .difference(&default_packages)
+                .packages(DependencyDirection::Forward)
+                .filter(|package| !package.in_workspace())
+                .collect();
+            let package_graph = member.graph;
+            let added_features = build
+                .difference(&default_build)
+                .feature_ids(DependencyDirection::Forward)
+                .filter(|feature_id| {
+                    let package = package_graph
+                        .metadata(feature_id.package_id())
+                        .expect("valid package ID");
+                    !feature_id.is_base() && !package.in_workspace()
+                })
+                .collect();
+            features.push(FeatureImpact {
+                feature,
+                default: false,
+                added_packages,
+                added_features,
+            });
+        }
+
+        Ok(Self {
+            member,
+            default_count: default_packages
+                .packages(DependencyDirection::Forward)
+                .filter(|package| !package.in_workspace())
+                .count(),
+            features,
+        })
+    }
+
+    /// Returns the workspace member this is for.
+    pub fn member(&self) -> PackageMetadata<'g> {
+        self.member
+    }
+
+    /// Returns the number of third-party packages in the member's default build.
+    pub fn default_count(&self) -> usize {
+        self.default_count
+    }
+
+    /// Returns the named features and optional dependencies of the member, sorted by name.
+    ///
+    /// The `default` feature itself is skipped.
+    pub fn features(&self) -> &[FeatureImpact<'g>] {
+        &self.features
+    }
+
+    /// Returns the cost of enabling this feature, or `None` if the member doesn't expose it.
+    pub fn feature(&self, feature: &str) -> Option<&FeatureImpact<'g>> {
+        self.features
+            .iter()
+            .find(|impact| impact.feature == feature)
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    /// Returns the features built for the member with its default features, and optionally one
+    /// more feature.
+    fn build(
+        member: PackageMetadata<'g>,
+        feature: Option<&'g str>,
+        opts: &CargoOptions<'_>,
+    ) -> Result<FeatureSet<'g>, Error> {
+        let package_graph = member.graph;
+        let packages = package_graph.query_forward(iter::once(member.id()))?;
+        let filter = feature_id_filter(
+            default_filter(),
+            feature.map(|feature| FeatureId::new(member.id(), feature)),
+        );
+        let query = package_graph
+            .feature_graph()
+            .query_packages(&packages, filter);
+        let cargo_set = CargoSet::new(query, opts)?;
+        Ok(cargo_set.target_features().union(cargo_set.host_features()))
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{}: {} third-party packages in default build",
+            self.member.name(),
+            self.default_count
+        )
+    }
+}
+
+/// The cost of enabling one feature of a workspace member, as part of a `MemberFeatureImpact`.
+#[derive(Clone, Debug)]
+pub struct FeatureImpact<'g> {
+    feature: &'g str,
+    default: bool,
+    added_packages: Vec<PackageMetadata<'g>>,
+    added_features: Vec<FeatureId<'g>>,
+}
+
+impl<'g> FeatureImpact<'g> {
+    /// Returns the name of the feature or optional dependency.
+    pub fn feature(&self) -> &'g str {
+        self.feature
+    }
+
+    /// Returns true if the feature is already enabled in the member's default build, in which
+    /// case enabling it adds nothing.
+    pub fn is_default(&self) -> bool {
+        self.default
+    }
+
+    /// Returns the third-party packages that enabling the feature adds to the default build, in
+    /// topological order.
+    pub fn added_packages(&self) -> &[PackageMetadata<'g>] {
+        &self.added_packages
+    }
+
+    /// Returns the features of third-party packages that enabling the feature adds to the
+    /// default build, in topological order.
+    ///
+    /// This includes features of packages in `added_packages`, as well as new features of
+    /// packages that were already built.
+    pub fn added_features(&self) -> &[FeatureId<'g>] {
+        &self.added_features
+    }
+}
+
+impl<'g> fmt::Display for FeatureImpact<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.default {
+            return write!(f, "{}: enabled by default", self.feature);
+        }
+        write!(
+            f,
+            "{}: +{} packages, +{} features",
+            self.feature,
+            self.added_packages.len(),
+            self.added_features.len()
+        )
+    }
+}
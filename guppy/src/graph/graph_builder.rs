@@ -0,0 +1,393 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::PackageGraph;
+use crate::{
+    CargoMetadata, DependencyKind, Error, FeatureName, PackageId, PackageName, RealFileSource,
+};
+use semver::Version;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+static REGISTRY_SOURCE: &str = "registry+https://github.com/rust-lang/crates.io-index";
+
+/// Constructs small `PackageGraph` instances programmatically, without `cargo metadata` JSON.
+///
+/// This is meant for tests that need a simple topology: add packages, features and dependencies,
+/// then call `build`. The result behaves as if `cargo metadata --all-features` had been run on a
+/// workspace with these packages.
+///
+/// * Workspace members are placed in `<workspace root>/<name>`.
+/// * Third-party packages come from crates.io, and are placed in
+///   `<workspace root>/.cargo/registry/<name>-<version>`.
+/// * Every package has a library target, which is a procedural macro if requested.
+/// * Packages with build dependencies also get a build script.
+/// * If the workspace root exists on disk, toolchain files in it are read.
+///
+/// # Examples
+///
+/// ```
+/// use guppy::graph::{BuilderDependency, PackageGraphBuilder};
+/// use guppy::{DependencyKind, FeatureName, PackageName, Version};
+///
+/// let derive = FeatureName::new("derive").unwrap();
+/// let mut builder = PackageGraphBuilder::new("/fakeworkspace");
+/// let app = builder.add_workspace_member(PackageName::new("app").unwrap(), Version::new(0, 1, 0));
+/// let serde = builder.add_third_party(PackageName::new("serde").unwrap(), Version::new(1, 0, 100));
+/// builder.add_feature(&serde, derive.clone(), Vec::<String>::new()).unwrap();
+/// builder
+///     .add_dependency(
+///         &app,
+///         &serde,
+///         BuilderDependency::new(DependencyKind::Normal).with_features(vec![derive]),
+///     )
+///     .unwrap();
+///
+/// let graph = builder.build().expect("graph is valid");
+/// assert_eq!(graph.workspace().member_count(), 1);
+/// assert!(graph.depends_on(&app, &serde).unwrap());
+/// ```
+#[derive(Clone, Debug)]
+pub struct PackageGraphBuilder {
+    workspace_root: PathBuf,
+    packages: Vec<BuilderPackage>,
+}
+
+#[derive(Clone, Debug)]
+struct BuilderPackage {
+    id: PackageId,
+    name: PackageName,
+    version: Version,
+    in_workspace: bool,
+    proc_macro: bool,
+    features: BTreeMap<String, Vec<String>>,
+    deps: Vec<(PackageId, BuilderDependency)>,
+}
+
+impl PackageGraphBuilder {
+    /// Creates a new builder for a workspace rooted at the given path. The path doesn't need to
+    /// exist.
+    pub fn new(workspace_root: impl Into<PathBuf>) -> Self {
+        Self {
+            workspace_root: workspace_root.into(),
+            packages: vec![],
+        }
+    }
+
+    /// Adds a workspace member, returning its package ID.
+    pub fn add_workspace_member(&mut self, name: PackageName, version: Version) -> PackageId {
+        let id = PackageId::new(format!(
+            "{} {} (path+file://{})",
+            name,
+            version,
+            self.workspace_root.join(name.as_str()).display()
+        ));
+        self.push_package(id, name, version, true)
+    }
+
+    /// Adds a third-party package from crates.io, returning its package ID.
+    pub fn add_third_party(&mut self, name: PackageName, version: Version) -> PackageId {
+        let id = PackageId::new(format!("{} {} ({})", name, version, REGISTRY_SOURCE));
+        self.push_package(id, name, version, false)
+    }
+
+    /// Sets whether the library target of this package is a procedural macro.
+    ///
+    /// Returns an error if the package ID wasn't added to this builder.
+    pub fn set_proc_macro(
+        &mut self,
+        package_id: &PackageId,
+        proc_macro: bool,
+    ) -> Result<&mut Self, Error> {
+        self.package_mut(package_id)?.proc_macro = proc_macro;
+        Ok(self)
+    }
+
+    /// Adds a named feature to this package. The values are in the same format as in the
+    /// `[features]` section of a manifest, e.g. `"other-feature"`, `"optional-dep"` or
+    /// `"dep/feature"`.
+    ///
+    /// Returns an error if the package ID wasn't added to this builder.
+    pub fn add_feature(
+        &mut self,
+        package_id: &PackageId,
+        name: FeatureName,
+        values: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<&mut Self, Error> {
+        let values = values.into_iter().map(|value| value.into()).collect();
+        self.package_mut(package_id)?
+            .features
+            .insert(name.into(), values);
+        Ok(self)
+    }
+
+    /// Adds a dependency from one package to another.
+    ///
+    /// Unless set through `BuilderDependency::with_version_req`, the dependency is declared with
+    /// an exact version requirement on the package it points to.
+    /// Adding several dependencies between the same two packages with different kinds or
+    /// platforms is allowed, just like in a manifest.
+    ///
+    /// Returns an error if either package ID wasn't added to this builder.
+    pub fn add_dependency(
+        &mut self,
+        from: &PackageId,
+        to: &PackageId,
+        dependency: BuilderDependency,
+    ) -> Result<&mut Self, Error> {
+        self.package_index(to)?;
+        self.package_mut(from)?.deps.push((to.clone(), dependency));
+        Ok(self)
+    }
+
+    /// Builds a `PackageGraph` out of the packages added so far.
+    ///
+    /// Returns an error if the same package was added twice, or if the graph is otherwise
+    /// invalid, e.g. because a workspace member has a dependency on itself.
+    pub fn build(&self) -> Result<PackageGraph, Error> {
+        let mut seen = BTreeSet::new();
+        for package in &self.packages {
+            if !seen.insert(&package.id) {
+                return Err(Error::PackageGraphConstructError(format!(
+                    "package '{}' was added more than once",
+                    package.id
+                )));
+            }
+        }
+
+        let mut package_values = vec![];
+        let mut node_values = vec![];
+        for package in &self.packages {
+            let package_dir = self.package_dir(package);
+
+            let mut dependencies = vec![];
+            let mut resolved: BTreeMap<&PackageId, String> = BTreeMap::new();
+            let mut has_build_deps = false;
+            for (to_id, dep) in &package.deps {
+                let to = &self.packages[self.package_index(to_id)?];
+                dependencies.push(json!({
+                    "name": to.name.as_str(),
+                    "source": if to.in_workspace { None } else { Some(REGISTRY_SOURCE) },
+                    "req": match &dep.version_req {
+                        Some(req) => req.clone(),
+                        None => format!("={}", to.version),
+                    },
+                    "kind": match dep.kind {
+                        DependencyKind::Normal => serde_json::Value::Null,
+                        kind => json!(kind.to_str()),
+                    },
+                    "rename": dep.rename.as_ref().map(|rename| rename.as_str()),
+                    "optional": dep.optional,
+                    "uses_default_features": dep.default_features,
+                    "features": dep.features.iter().map(|feature| feature.as_str()).collect::<Vec<_>>(),
+                    "target": dep.target,
+                    "registry": null,
+                }));
+                has_build_deps |= dep.kind == DependencyKind::Build;
+
+                // Cargo doesn't resolve dev-dependencies of non-workspace packages.
+                if dep.kind != DependencyKind::Development || package.in_workspace {
+                    let dep_name = dep.rename.as_ref().unwrap_or(&to.name);
+                    resolved.insert(&to.id, dep_name.lib_name());
+                }
+            }
+
+            let lib_kind = if package.proc_macro {
+                "proc-macro"
+            } else {
+                "lib"
+            };
+            let mut targets = vec![json!({
+                "kind": [lib_kind],
+                "crate_types": [lib_kind],
+                "name": package.name.as_str(),
+                "src_path": package_dir.join("src/lib.rs"),
+                "edition": "2018",
+                "doctest": !package.proc_macro,
+            })];
+            // Cargo only follows build dependencies if there's a build script.
+            if has_build_deps {
+                targets.push(json!({
+                    "kind": ["custom-build"],
+                    "crate_types": ["bin"],
+                    "name": "build-script-build",
+                    "src_path": package_dir.join("build.rs"),
+                    "edition": "2018",
+                    "doctest": false,
+                }));
+            }
+
+            package_values.push(json!({
+                "name": package.name.as_str(),
+                "version": package.version.to_string(),
+                "authors": [],
+                "id": package.id.repr(),
+                "source": if package.in_workspace { None } else { Some(REGISTRY_SOURCE) },
+                "description": null,
+                "dependencies": dependencies,
+                "license": null,
+                "license_file": null,
+                "targets": targets,
+                "features": package.features,
+                "manifest_path": package_dir.join("Cargo.toml"),
+                "categories": [],
+                "keywords": [],
+                "readme": null,
+                "repository": null,
+                "edition": "2018",
+                "metadata": null,
+                "links": null,
+                "publish": null,
+            }));
+
+            let deps: Vec<_> = resolved
+                .iter()
+                .map(|(id, dep_name)| json!({ "name": dep_name, "pkg": id.repr() }))
+                .collect();
+            let dependency_ids: Vec<_> = resolved.keys().map(|id| id.repr()).collect();
+            node_values.push(json!({
+                "id": package.id.repr(),
+                "deps": deps,
+                "dependencies": dependency_ids,
+                "features": [],
+            }));
+        }
+
+        let workspace_members: Vec<_> = self
+            .packages
+            .iter()
+            .filter(|package| package.in_workspace)
+            .map(|package| package.id.repr())
+            .collect();
+        let metadata = json!({
+            "packages": package_values,
+            "workspace_members": workspace_members,
+            "resolve": {
+                "nodes": node_values,
+                "root": null,
+            },
+            "workspace_root": self.workspace_root,
+            "target_directory": self.workspace_root.join("target"),
+            "version": 1,
+        });
+
+        let metadata = serde_json::from_value(metadata).map_err(Error::MetadataParseError)?;
+        PackageGraph::from_metadata_with_files(CargoMetadata(metadata), &RealFileSource)
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn push_package(
+        &mut self,
+        id: PackageId,
+        name: PackageName,
+        version: Version,
+        in_workspace: bool,
+    ) -> PackageId {
+        self.packages.push(BuilderPackage {
+            id: id.clone(),
+            name,
+            version,
+            in_workspace,
+            proc_macro: false,
+            features: BTreeMap::new(),
+            deps: vec![],
+        });
+        id
+    }
+
+    fn package_index(&self, package_id: &PackageId) -> Result<usize, Error> {
+        self.packages
+            .iter()
+            .position(|package| package.id == *package_id)
+            .ok_or_else(|| Error::UnknownPackageId(package_id.clone()))
+    }
+
+    fn package_mut(&mut self, package_id: &PackageId) -> Result<&mut BuilderPackage, Error> {
+        let idx = self.package_index(package_id)?;
+        Ok(&mut self.packages[idx])
+    }
+
+    fn package_dir(&self, package: &BuilderPackage) -> PathBuf {
+        if package.in_workspace {
+            self.workspace_root.join(package.name.as_str())
+        } else {
+            self.workspace_root
+                .join(".cargo/registry")
+                .join(format!("{}-{}", package.name, package.version))
+        }
+    }
+}
+
+/// A dependency to add through `PackageGraphBuilder::add_dependency`.
+#[derive(Clone, Debug)]
+pub struct BuilderDependency {
+    kind: DependencyKind,
+    optional: bool,
+    default_features: bool,
+    features: Vec<FeatureName>,
+    target: Option<String>,
+    rename: Option<PackageName>,
+    version_req: Option<String>,
+}
+
+impl BuilderDependency {
+    /// Creates a new dependency of the given kind. By default, the dependency is non-optional,
+    /// uses default features, enables no other features and applies to all platforms.
+    pub fn new(kind: DependencyKind) -> Self {
+        Self {
+            kind,
+            optional: false,
+            default_features: true,
+            features: vec![],
+            target: None,
+            rename: None,
+            version_req: None,
+        }
+    }
+
+    /// Sets whether this dependency is optional.
+    ///
+    /// An optional dependency is enabled by a feature of the same name, or by a feature listing it
+    /// through `add_feature`.
+    pub fn with_optional(mut self, optional: bool) -> Self {
+        self.optional = optional;
+        self
+    }
+
+    /// Sets whether default features are enabled on this dependency.
+    pub fn with_default_features(mut self, default_features: bool) -> Self {
+        self.default_features = default_features;
+        self
+    }
+
+    /// Sets the features enabled on this dependency.
+    pub fn with_features(mut self, features: impl IntoIterator<Item = FeatureName>) -> Self {
+        self.features = features.into_iter().collect();
+        self
+    }
+
+    /// Makes this dependency platform-specific, e.g. `cfg(unix)` or `x86_64-pc-windows-msvc`.
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Renames this dependency, as with `name = { package = "..." }` in a manifest.
+    pub fn with_rename(mut self, rename: PackageName) -> Self {
+        self.rename = Some(rename);
+        self
+    }
+
+    /// Sets the version requirement for this dependency, e.g. `"1.2"` or `">= 0.3, < 0.5"`.
+    ///
+    /// The requirement isn't checked against the version of the package the dependency points
+    /// to. An unparseable requirement makes `PackageGraphBuilder::build` fail.
+    pub fn with_version_req(mut self, version_req: impl Into<String>) -> Self {
+        self.version_req = Some(version_req.into());
+        self
+    }
+}
@@ -1,14 +1,22 @@
 // Copyright (c) The cargo-guppy Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::cargo_config::{CargoConfig, SourceConfig};
+use crate::graph::extensions::ExtensionValues;
 use crate::graph::feature::{FeatureGraphImpl, FeatureId, FeatureNode};
+#[cfg(feature = "unstable")]
+use crate::graph::maintenance::MaintenanceSignals;
+#[cfg(feature = "unstable")]
+use crate::graph::unsafe_usage::UnsafeStats;
 use crate::graph::{
     cargo_version_matches, BuildTarget, BuildTargetId, BuildTargetImpl, BuildTargetKind, Cycles,
-    DependencyDirection, OwnedBuildTargetId, PackageIx,
+    DependencyDirection, ManifestSpans, OwnedBuildTargetId, PackageIx, WorkspacePath,
 };
 use crate::petgraph_support::scc::Sccs;
+use crate::toolchain::Toolchain;
 use crate::{
-    CargoMetadata, DependencyKind, Error, JsonValue, MetadataCommand, PackageId, Platform,
+    CargoMetadata, DependencyKind, Error, FileSource, JsonValue, MetadataCommand, PackageId,
+    PackageSpec, Platform, PlatformSpec, RealFileSource,
 };
 use cargo_metadata::NodeDep;
 use fixedbitset::FixedBitSet;
@@ -18,7 +26,9 @@ use petgraph::algo::{has_path_connecting, DfsSpace};
 use petgraph::graph::EdgeReference;
 use petgraph::prelude::*;
 use semver::{Version, VersionReq};
+use std::any::TypeId;
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::iter;
 use std::path::{Path, PathBuf};
 use target_spec::TargetSpec;
@@ -47,6 +57,14 @@ pub struct PackageGraph {
 pub(super) struct PackageGraphData {
     pub(super) packages: HashMap<PackageId, PackageMetadataImpl>,
     pub(super) workspace: WorkspaceImpl,
+    // Maintenance signals, attached through an optional enrichment pass.
+    #[cfg(feature = "unstable")]
+    pub(super) maintenance: HashMap<PackageId, MaintenanceSignals>,
+    // Unsafe usage stats, attached through an optional scanning pass.
+    #[cfg(feature = "unstable")]
+    pub(super) unsafe_stats: HashMap<PackageId, UnsafeStats>,
+    // Values parsed by metadata extensions, keyed by the extension's type.
+    pub(super) extensions: HashMap<TypeId, ExtensionValues>,
 }
 
 impl PackageGraph {
@@ -56,8 +74,23 @@ impl PackageGraph {
     }
 
     /// Parses the given `Metadata` and constructs a `PackageGraph` from it.
+    ///
+    /// Toolchain and Cargo configuration files aren't read. To read toolchain files, use
+    /// `from_metadata_with_files` with a `RealFileSource`.
     pub fn from_metadata(metadata: CargoMetadata) -> Result<Self, Error> {
-        Self::build(metadata.0)
+        Self::build(metadata.0, &RealFileSource, false, false)
+    }
+
+    /// Parses the given `Metadata` and constructs a `PackageGraph` from it, reading manifests and
+    /// toolchain files through this `FileSource` rather than from disk.
+    ///
+    /// This is useful for analyzing a workspace without a checkout, for example from git objects.
+    /// Cargo configuration files aren't read.
+    pub fn from_metadata_with_files(
+        metadata: CargoMetadata,
+        files: &dyn FileSource,
+    ) -> Result<Self, Error> {
+        Self::build(metadata.0, files, false, true)
     }
 
     /// Constructs a package graph from the given JSON output of `cargo metadata`.
@@ -243,6 +276,17 @@ impl PackageGraph {
             .map(move |inner| PackageMetadata::new(self, inner))
     }
 
+    /// Returns the packages matching the given specification.
+    ///
+    /// This is the same as the set of packages Cargo's `-p` option would match, except that no
+    /// error is returned if more than one package matches.
+    pub fn packages_matching<'a>(
+        &'a self,
+        spec: &'a PackageSpec,
+    ) -> impl Iterator<Item = PackageMetadata<'a>> + 'a {
+        self.packages().filter(move |package| spec.matches(package))
+    }
+
     /// Returns the number of packages in this graph.
     pub fn package_count(&self) -> usize {
         // This can be obtained in two different ways: self.dep_graph.node_count() or
@@ -378,6 +422,16 @@ impl PackageGraph {
             .collect()
     }
 
+    /// Returns the link between these two packages, if any.
+    pub(super) fn link_between(
+        &self,
+        from_ix: NodeIndex<PackageIx>,
+        to_ix: NodeIndex<PackageIx>,
+    ) -> Option<PackageLink<'_>> {
+        let edge_ix = self.dep_graph.find_edge(from_ix, to_ix)?;
+        Some(self.edge_ix_to_link(edge_ix))
+    }
+
     /// Maps a package ID to its internal graph node index.
     pub(super) fn package_ix(&self, package_id: &PackageId) -> Option<NodeIndex<PackageIx>> {
         self.metadata(package_id)
@@ -469,6 +523,30 @@ impl<'g> Workspace<'g> {
         &self.inner.root
     }
 
+    /// Returns the target directory in which output artifacts are stored.
+    pub fn target_directory(&self) -> &'g Path {
+        &self.inner.target_directory
+    }
+
+    /// Returns the toolchain pinned for the workspace root, if any.
+    ///
+    /// This is looked up from a `rust-toolchain.toml` or `rust-toolchain` file in the workspace
+    /// root, if the graph was built by a `MetadataCommand` with `toolchain` set or by
+    /// `PackageGraph::from_metadata_with_files`. Unlike `rustup`, directories above the workspace
+    /// root aren't searched.
+    pub fn toolchain(&self) -> Option<&'g Toolchain> {
+        self.inner.toolchain.as_ref()
+    }
+
+    /// Returns the Cargo configuration that applies to builds in this workspace.
+    ///
+    /// This is only read if the graph was built by a `MetadataCommand` with `cargo_config` set,
+    /// from `.cargo/config.toml` files in the workspace root and its ancestors, and from
+    /// `$CARGO_HOME`. It's empty otherwise, or if no configuration files were found.
+    pub fn cargo_config(&self) -> &'g CargoConfig {
+        &self.inner.cargo_config
+    }
+
     /// Returns the number of packages in this workspace.
     pub fn member_count(&self) -> usize {
         self.inner.members_by_path.len()
@@ -521,6 +599,9 @@ impl<'g> Workspace<'g> {
 #[derive(Clone, Debug)]
 pub(super) struct WorkspaceImpl {
     pub(super) root: PathBuf,
+    pub(super) target_directory: PathBuf,
+    pub(super) toolchain: Option<Toolchain>,
+    pub(super) cargo_config: CargoConfig,
     // This is a BTreeMap to allow presenting data in sorted order.
     pub(super) members_by_path: BTreeMap<PathBuf, PackageId>,
     pub(super) members_by_name: BTreeMap<Box<str>, PackageId>,
@@ -536,8 +617,8 @@ pub(super) struct WorkspaceImpl {
 /// details.
 #[derive(Copy, Clone, Debug)]
 pub struct PackageMetadata<'g> {
-    graph: &'g PackageGraph,
-    inner: &'g PackageMetadataImpl,
+    pub(super) graph: &'g PackageGraph,
+    pub(super) inner: &'g PackageMetadataImpl,
 }
 
 impl<'g> PackageMetadata<'g> {
@@ -693,6 +774,23 @@ impl<'g> PackageMetadata<'g> {
         self.inner.publish.as_deref()
     }
 
+    /// Returns the source of this package: the workspace, a path on disk, or an external source
+    /// like a registry.
+    pub fn source(&self) -> PackageSource<'g> {
+        if let Some(workspace_path) = &self.inner.workspace_path {
+            return PackageSource::Workspace(workspace_path.as_ref());
+        }
+        match &self.inner.source {
+            Some(source) => PackageSource::External(source),
+            None => PackageSource::Path(
+                self.inner
+                    .manifest_path
+                    .parent()
+                    .expect("manifest path has a parent directory"),
+            ),
+        }
+    }
+
     /// Returns true if this package is in the workspace.
     pub fn in_workspace(&self) -> bool {
         self.inner.workspace_path.is_some()
@@ -700,10 +798,28 @@ impl<'g> PackageMetadata<'g> {
 
     /// Returns the relative path to this package in the workspace, or `None` if this package is
     /// not in the workspace.
+    ///
+    /// The path uses `/` as the separator on all platforms. For a `WorkspacePath`, use
+    /// `normalized_workspace_path`.
     pub fn workspace_path(&self) -> Option<&'g Path> {
         self.inner.workspace_path.as_ref().map(|path| path.as_ref())
     }
 
+    /// Returns the normalized path to this package relative to the workspace root, or `None` if
+    /// this package is not in the workspace.
+    pub fn normalized_workspace_path(&self) -> Option<&'g WorkspacePath> {
+        self.inner.workspace_path.as_ref()
+    }
+
+    /// Returns the normalized path to this package's `Cargo.toml` relative to the workspace root,
+    /// or `None` if it is outside the workspace root.
+    ///
+    /// This is set for workspace members, and for other packages within the workspace root such
+    /// as path dependencies that are excluded from the workspace.
+    pub fn normalized_manifest_path(&self) -> Option<&'g WorkspacePath> {
+        self.inner.normalized_manifest_path.as_ref()
+    }
+
     /// Returns all the build targets for this package.
     ///
     /// For more, see [Cargo
@@ -721,6 +837,64 @@ impl<'g> PackageMetadata<'g> {
             .map(BuildTarget::new)
     }
 
+    /// Returns the minimum supported Rust version declared through the `rust-version` key, if any.
+    ///
+    /// This is only available for workspace members.
+    pub fn rust_version(&self) -> Option<&'g Version> {
+        self.inner.rust_version.as_ref()
+    }
+
+    /// Returns the toolchain pinned for this package, if any.
+    ///
+    /// This is only available for workspace members. It is the toolchain file closest to the
+    /// package's directory, falling back to the one for the workspace.
+    pub fn toolchain(&self) -> Option<&'g Toolchain> {
+        self.inner.toolchain.as_ref()
+    }
+
+    /// Returns the source this package is actually fetched from, if the workspace's Cargo
+    /// configuration replaces the source reported by `source`.
+    ///
+    /// For example, with vendored dependencies, crates.io packages are fetched from the vendor
+    /// directory instead.
+    pub fn replaced_source(&self) -> Option<&'g SourceConfig> {
+        let source = self.source().external_source()?;
+        self.graph
+            .workspace()
+            .cargo_config()
+            .replaced_source(source)
+    }
+
+    /// Returns the patterns listed in the `include` key of this package's manifest.
+    ///
+    /// If this isn't empty, only files matching these patterns are part of the package when it's
+    /// published, and `exclude` is ignored. This is only available for workspace members.
+    ///
+    /// For more, see [The `exclude` and `include`
+    /// fields](https://doc.rust-lang.org/cargo/reference/manifest.html#the-exclude-and-include-fields)
+    /// in the Cargo reference.
+    pub fn include(&self) -> &'g [String] {
+        &self.inner.include
+    }
+
+    /// Returns the patterns listed in the `exclude` key of this package's manifest.
+    ///
+    /// Files matching these patterns aren't part of the package when it's published. This is
+    /// only available for workspace members.
+    pub fn exclude(&self) -> &'g [String] {
+        &self.inner.exclude
+    }
+
+    /// Returns the maintenance signals attached to this package, if any.
+    ///
+    /// Signals are only available after `PackageGraph::enrich_maintenance` has been called.
+    ///
+    /// Requires the `unstable` feature to be enabled.
+    #[cfg(feature = "unstable")]
+    pub fn maintenance_signals(&self) -> Option<&'g MaintenanceSignals> {
+        self.graph.data.maintenance.get(self.id())
+    }
+
     /// Returns true if this package is a procedural macro.
     ///
     /// For more about procedural macros, see [Procedural
@@ -774,6 +948,28 @@ impl<'g> PackageMetadata<'g> {
             .map(|(_, named_feature, _)| named_feature)
     }
 
+    /// Returns the list of named features available for this package, along with the features
+    /// and optional dependencies each one enables, as listed in `Cargo.toml`.
+    pub fn named_features_with_deps(&self) -> impl Iterator<Item = (&'g str, &'g [String])> + 'g {
+        self.named_features_full()
+            .map(|(_, named_feature, deps)| (named_feature, deps))
+    }
+
+    /// Returns the names of the optional dependencies declared by this package. Each optional
+    /// dependency is also available as a feature.
+    ///
+    /// An optional dependency that was never enabled while resolving the graph doesn't have a
+    /// corresponding `PackageLink`.
+    pub fn optional_deps(&self) -> impl Iterator<Item = &'g str> + 'g {
+        self.inner
+            .features
+            .iter()
+            .filter_map(|(feature, deps)| match deps {
+                None => Some(feature.as_ref()),
+                Some(_) => None,
+            })
+    }
+
     // ---
     // Helper methods
     // --
@@ -871,6 +1067,7 @@ pub(crate) struct PackageMetadataImpl {
     pub(super) readme: Option<Box<Path>>,
     pub(super) repository: Option<Box<str>>,
     pub(super) edition: Box<str>,
+    pub(super) source: Option<Box<str>>,
     pub(super) metadata_table: JsonValue,
     pub(super) links: Option<Box<str>>,
     pub(super) publish: Option<Vec<String>>,
@@ -880,13 +1077,84 @@ pub(crate) struct PackageMetadataImpl {
 
     // Other information.
     pub(super) package_ix: NodeIndex<PackageIx>,
-    pub(super) workspace_path: Option<Box<Path>>,
+    pub(super) workspace_path: Option<WorkspacePath>,
+    // Set if the manifest is within the workspace root.
+    pub(super) normalized_manifest_path: Option<WorkspacePath>,
+    // These are only set for workspace members.
+    pub(super) rust_version: Option<Version>,
+    pub(super) toolchain: Option<Toolchain>,
+    pub(super) include: Vec<String>,
+    pub(super) exclude: Vec<String>,
+    pub(super) manifest_spans: Option<ManifestSpans>,
     pub(super) build_targets: BTreeMap<OwnedBuildTargetId, BuildTargetImpl>,
     pub(super) has_default_feature: bool,
+    // The dependencies as declared in the manifest, retained for re-emitting metadata.
+    pub(super) dependencies: Vec<cargo_metadata::Dependency>,
     pub(super) resolved_deps: Vec<NodeDep>,
     pub(super) resolved_features: Vec<String>,
 }
 
+/// The source of a package.
+///
+/// Returned by `PackageMetadata::source`.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum PackageSource<'g> {
+    /// This package is a workspace member. The path is relative to the workspace root.
+    Workspace(&'g Path),
+
+    /// This package is a path dependency outside the workspace. The path is the directory
+    /// containing the package's manifest.
+    Path(&'g Path),
+
+    /// This package is from an external source such as a registry or a git repository, in the
+    /// format cargo uses (e.g. `registry+https://github.com/rust-lang/crates.io-index`).
+    External(&'g str),
+}
+
+impl<'g> PackageSource<'g> {
+    /// The source string for the crates.io registry.
+    pub const CRATES_IO_REGISTRY: &'static str =
+        "registry+https://github.com/rust-lang/crates.io-index";
+
+    /// Returns true if this package is a workspace member.
+    pub fn is_workspace(&self) -> bool {
+        matches!(self, PackageSource::Workspace(_))
+    }
+
+    /// Returns true if this package is a path dependency outside the workspace.
+    pub fn is_path(&self) -> bool {
+        matches!(self, PackageSource::Path(_))
+    }
+
+    /// Returns true if this package is from an external source.
+    pub fn is_external(&self) -> bool {
+        matches!(self, PackageSource::External(_))
+    }
+
+    /// Returns true if this package is from the crates.io registry.
+    pub fn is_crates_io(&self) -> bool {
+        *self == PackageSource::External(Self::CRATES_IO_REGISTRY)
+    }
+
+    /// Returns the external source string, if this package is from an external source.
+    pub fn external_source(&self) -> Option<&'g str> {
+        match self {
+            PackageSource::External(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl<'g> fmt::Display for PackageSource<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackageSource::Workspace(path) => write!(f, "workspace:{}", path.display()),
+            PackageSource::Path(path) => write!(f, "path:{}", path.display()),
+            PackageSource::External(source) => write!(f, "{}", source),
+        }
+    }
+}
+
 /// Represents a dependency from one package to another.
 ///
 /// This struct contains information about:
@@ -1163,6 +1431,13 @@ impl<'g> EnabledStatus<'g> {
         self.required.enabled_on(platform)
     }
 
+    /// Evaluates whether this dependency is required on the given platform or set of platforms.
+    ///
+    /// For a single platform, this is the same as `required_on`.
+    pub fn required_on_spec(&self, platform_spec: &PlatformSpec<'_>) -> EnabledTernary {
+        self.required.enabled_on_spec(platform_spec)
+    }
+
     /// Returns true if there are any platforms on which this dependency is required.
     pub fn required_on_any(&self) -> bool {
         !self.required.is_never()
@@ -1179,6 +1454,17 @@ impl<'g> EnabledStatus<'g> {
         required.or(optional)
     }
 
+    /// Evaluates whether this dependency is enabled (required or optional) on the given platform
+    /// or set of platforms.
+    ///
+    /// For a single platform, this is the same as `enabled_on`.
+    pub fn enabled_on_spec(&self, platform_spec: &PlatformSpec<'_>) -> EnabledTernary {
+        let required = self.required.enabled_on_spec(platform_spec);
+        let optional = self.optional.enabled_on_spec(platform_spec);
+
+        required.or(optional)
+    }
+
     /// Returns true if there are any platforms on which this dependency is enabled (required or
     /// optional).
     pub fn enabled_on_any(&self) -> bool {
@@ -1258,6 +1544,19 @@ impl<'g> PlatformStatus<'g> {
             PlatformStatus::PlatformDependent { eval } => eval.eval(platform),
         }
     }
+
+    /// Evaluates whether this dependency is enabled on the given platform or set of platforms.
+    ///
+    /// With `PlatformSpec::Always`, platform-dependent statuses are enabled only if they're known
+    /// to be enabled on every platform. With `PlatformSpec::Any`, they're enabled unless they
+    /// can't be enabled on any platform.
+    pub fn enabled_on_spec(&self, platform_spec: &PlatformSpec<'_>) -> EnabledTernary {
+        match self {
+            PlatformStatus::Never => EnabledTernary::Disabled,
+            PlatformStatus::Always => EnabledTernary::Enabled,
+            PlatformStatus::PlatformDependent { eval } => eval.eval_spec(platform_spec),
+        }
+    }
 }
 
 /// Whether a dependency or feature is enabled on a specific platform.
@@ -1322,6 +1621,12 @@ pub struct PlatformEval<'g> {
 }
 
 impl<'g> PlatformEval<'g> {
+    /// Returns the target specs this evaluator checks. The dependency or feature is enabled on a
+    /// platform if any of them match.
+    pub fn specs(&self) -> &'g [TargetSpec] {
+        self.specs
+    }
+
     /// Runs this evaluator against the given platform.
     pub fn eval(&self, platform: &Platform<'_>) -> EnabledTernary {
         let mut res = EnabledTernary::Disabled;
@@ -1335,6 +1640,22 @@ impl<'g> PlatformEval<'g> {
         }
         res
     }
+
+    /// Runs this evaluator against the given platform or set of platforms.
+    ///
+    /// For a single platform, this is the same as `eval`.
+    pub fn eval_spec(&self, platform_spec: &PlatformSpec<'_>) -> EnabledTernary {
+        let mut res = EnabledTernary::Disabled;
+        for spec in self.specs.iter() {
+            let matches = platform_spec.eval(spec);
+            // Short-circuit evaluation if possible.
+            if matches == Some(true) {
+                return EnabledTernary::Enabled;
+            }
+            res = res.or(EnabledTernary::new(matches));
+        }
+        res
+    }
 }
 
 /// Information about dependency requirements.
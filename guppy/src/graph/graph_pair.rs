@@ -0,0 +1,299 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{PackageGraph, PackageMetadata, PackageSource};
+use crate::PackageId;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Two package graphs, typically of the same workspace before and after a change, with packages
+/// correlated across them.
+///
+/// Package IDs are not stable across graphs: they change whenever a package's version changes,
+/// and workspace members get new IDs if the workspace is checked out somewhere else. Instead,
+/// packages are correlated by their name and a `CorrelationKey` derived from their source:
+/// * workspace members by their path relative to the workspace root,
+/// * path dependencies by their path, and
+/// * external packages by their source, ignoring the revision that git sources are locked to.
+///
+/// If several packages with the same name and source exist in a graph (for example, two
+/// versions of a crate from crates.io), packages with the same version are matched first. The
+/// rest are matched in version order.
+///
+/// The correlation forms the basis of before/after analyses like diffs.
+#[derive(Clone, Debug)]
+pub struct GraphPair<'a> {
+    old: &'a PackageGraph,
+    new: &'a PackageGraph,
+    changes: Vec<PackageChange<'a>>,
+    old_index: HashMap<&'a PackageId, usize>,
+    new_index: HashMap<&'a PackageId, usize>,
+}
+
+impl<'a> GraphPair<'a> {
+    /// Correlates the packages in these two graphs.
+    pub fn new(old: &'a PackageGraph, new: &'a PackageGraph) -> Self {
+        let mut groups: BTreeMap<CorrelationKey, (Vec<_>, Vec<_>)> = BTreeMap::new();
+        for package in old.packages() {
+            groups
+                .entry(CorrelationKey::new(package))
+                .or_default()
+                .0
+                .push(package);
+        }
+        for package in new.packages() {
+            groups
+                .entry(CorrelationKey::new(package))
+                .or_default()
+                .1
+                .push(package);
+        }
+
+        let mut changes = vec![];
+        for (key, (mut old_packages, mut new_packages)) in groups {
+            old_packages.sort_by(|a, b| a.version().cmp(b.version()));
+            new_packages.sort_by(|a, b| a.version().cmp(b.version()));
+
+            // Match up identical versions first.
+            let mut old_rest = vec![];
+            for old_package in old_packages {
+                match new_packages
+                    .iter()
+                    .position(|new_package| new_package.version() == old_package.version())
+                {
+                    Some(idx) => {
+                        let new_package = new_packages.remove(idx);
+                        changes.push(PackageChange {
+                            key: key.clone(),
+                            old: Some(old_package),
+                            new: Some(new_package),
+                        });
+                    }
+                    None => old_rest.push(old_package),
+                }
+            }
+
+            // Then match the rest in version order.
+            let mut new_rest = new_packages.into_iter();
+            for old_package in old_rest {
+                changes.push(PackageChange {
+                    key: key.clone(),
+                    old: Some(old_package),
+                    new: new_rest.next(),
+                });
+            }
+            changes.extend(new_rest.map(|new_package| PackageChange {
+                key: key.clone(),
+                old: None,
+                new: Some(new_package),
+            }));
+        }
+        changes.sort_by(|a, b| {
+            (
+                &a.key,
+                a.old.map(|p| p.version()),
+                a.new.map(|p| p.version()),
+            )
+                .cmp(&(
+                    &b.key,
+                    b.old.map(|p| p.version()),
+                    b.new.map(|p| p.version()),
+                ))
+        });
+
+        let mut old_index = HashMap::new();
+        let mut new_index = HashMap::new();
+        for (idx, change) in changes.iter().enumerate() {
+            if let Some(package) = change.old {
+                old_index.insert(package.id(), idx);
+            }
+            if let Some(package) = change.new {
+                new_index.insert(package.id(), idx);
+            }
+        }
+
+        Self {
+            old,
+            new,
+            changes,
+            old_index,
+            new_index,
+        }
+    }
+
+    /// Returns the old graph.
+    pub fn old_graph(&self) -> &'a PackageGraph {
+        self.old
+    }
+
+    /// Returns the new graph.
+    pub fn new_graph(&self) -> &'a PackageGraph {
+        self.new
+    }
+
+    /// Iterates over every correlated package, ordered by name and source.
+    ///
+    /// Packages that are the same in both graphs are included.
+    pub fn changes<'b>(&'b self) -> impl Iterator<Item = &'b PackageChange<'a>> + 'b {
+        self.changes.iter()
+    }
+
+    /// Iterates over packages added in the new graph.
+    pub fn added<'b>(&'b self) -> impl Iterator<Item = PackageMetadata<'a>> + 'b {
+        self.changes
+            .iter()
+            .filter_map(|change| match change.kind() {
+                PackageChangeKind::Added => change.new,
+                _ => None,
+            })
+    }
+
+    /// Iterates over packages removed in the new graph.
+    pub fn removed<'b>(&'b self) -> impl Iterator<Item = PackageMetadata<'a>> + 'b {
+        self.changes
+            .iter()
+            .filter_map(|change| match change.kind() {
+                PackageChangeKind::Removed => change.old,
+                _ => None,
+            })
+    }
+
+    /// Iterates over packages whose versions changed.
+    pub fn version_changes<'b>(&'b self) -> impl Iterator<Item = &'b PackageChange<'a>> + 'b {
+        self.changes
+            .iter()
+            .filter(|change| change.kind() == PackageChangeKind::VersionChanged)
+    }
+
+    /// Returns the package in the new graph correlated with this package in the old graph.
+    ///
+    /// Returns `None` if the package ID is unknown or if the package was removed.
+    pub fn old_to_new(&self, old_id: &PackageId) -> Option<PackageMetadata<'a>> {
+        self.changes[*self.old_index.get(old_id)?].new
+    }
+
+    /// Returns the package in the old graph correlated with this package in the new graph.
+    ///
+    /// Returns `None` if the package ID is unknown or if the package was added.
+    pub fn new_to_old(&self, new_id: &PackageId) -> Option<PackageMetadata<'a>> {
+        self.changes[*self.new_index.get(new_id)?].old
+    }
+}
+
+/// A package correlated across the two graphs in a `GraphPair`.
+///
+/// At least one of `old` and `new` is present.
+#[derive(Clone, Debug)]
+pub struct PackageChange<'a> {
+    key: CorrelationKey,
+    old: Option<PackageMetadata<'a>>,
+    new: Option<PackageMetadata<'a>>,
+}
+
+impl<'a> PackageChange<'a> {
+    /// Returns the key this package was correlated by.
+    pub fn key(&self) -> &CorrelationKey {
+        &self.key
+    }
+
+    /// Returns the package in the old graph, or `None` if it was added.
+    pub fn old_package(&self) -> Option<PackageMetadata<'a>> {
+        self.old
+    }
+
+    /// Returns the package in the new graph, or `None` if it was removed.
+    pub fn new_package(&self) -> Option<PackageMetadata<'a>> {
+        self.new
+    }
+
+    /// Returns the kind of change this is.
+    pub fn kind(&self) -> PackageChangeKind {
+        match (self.old, self.new) {
+            (Some(old), Some(new)) if old.version() == new.version() => {
+                PackageChangeKind::Unchanged
+            }
+            (Some(_), Some(_)) => PackageChangeKind::VersionChanged,
+            (None, Some(_)) => PackageChangeKind::Added,
+            (Some(_), None) => PackageChangeKind::Removed,
+            (None, None) => unreachable!("at least one of old and new is present"),
+        }
+    }
+}
+
+/// The kind of a `PackageChange`.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum PackageChangeKind {
+    /// The package has the same version in both graphs.
+    ///
+    /// Other details about the package, such as its dependencies, may still have changed.
+    Unchanged,
+    /// The package's version changed.
+    VersionChanged,
+    /// The package was added in the new graph.
+    Added,
+    /// The package was removed in the new graph.
+    Removed,
+}
+
+/// The key by which packages are correlated across graphs.
+///
+/// Returned by `PackageChange::key`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct CorrelationKey {
+    name: Box<str>,
+    source: CorrelatedSource,
+}
+
+impl CorrelationKey {
+    /// Returns the correlation key for this package.
+    pub fn new(package: PackageMetadata<'_>) -> Self {
+        let source = match package.source() {
+            PackageSource::Workspace(_) => CorrelatedSource::Workspace(
+                package
+                    .normalized_workspace_path()
+                    .expect("workspace members have a workspace path")
+                    .as_str()
+                    .into(),
+            ),
+            PackageSource::Path(path) => {
+                CorrelatedSource::Path(path.to_string_lossy().replace('\\', "/").into())
+            }
+            PackageSource::External(source) => {
+                // Git sources are of the form "git+https://...?branch=master#<commit>". Ignore the
+                // commit so that packages are still correlated when it changes.
+                let source = match source.find('#') {
+                    Some(idx) if source.starts_with("git+") => &source[..idx],
+                    _ => source,
+                };
+                CorrelatedSource::External(source.into())
+            }
+        };
+        Self {
+            name: package.name().into(),
+            source,
+        }
+    }
+
+    /// Returns the package name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for CorrelationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source {
+            CorrelatedSource::Workspace(path) => write!(f, "{} (workspace:{})", self.name, path),
+            CorrelatedSource::Path(path) => write!(f, "{} (path:{})", self.name, path),
+            CorrelatedSource::External(source) => write!(f, "{} ({})", self.name, source),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum CorrelatedSource {
+    // The normalized path relative to the workspace root.
+    Workspace(Box<str>),
+    Path(Box<str>),
+    External(Box<str>),
+}
@@ -0,0 +1,446 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Dependency freshness scores and health reports.
+//!
+//! This module combines several signals into a per-package freshness score:
+//! * how far behind the latest published version the package is, as reported through
+//!   `MaintenanceSignals::latest_version`,
+//! * whether the version in use was yanked,
+//! * whether the package looks unmaintained, as determined by `MaintenanceSignals::is_stale`, and
+//! * whether several versions of the package are present in the graph.
+//!
+//! Scores range from 0 to 100, where 100 means no problems were found. Penalties are subtracted
+//! for each problem:
+//!
+//! | Problem                      | Penalty                 |
+//! |------------------------------|-------------------------|
+//! | each major version behind    | 15, up to 45            |
+//! | each minor version behind    | 3, up to 15             |
+//! | yanked                       | 50                      |
+//! | unmaintained                 | 30                      |
+//! | duplicated in the graph      | 5                       |
+//!
+//! Scores are aggregated per workspace member in a `HealthReport`, which also lists
+//! dependencies that require a newer Rust version than the member declares, as reported through
+//! `MaintenanceSignals::rust_version`.
+//!
+//! Signals other than duplicates are only available after `PackageGraph::enrich_maintenance` has
+//! been called. This module is experimental, and requires the `unstable` feature to be enabled.
+
+use crate::graph::{DependencyDirection, PackageGraph, PackageMetadata};
+use crate::report::{Report, ReportDocument};
+use crate::{PackageId, Version};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+const MAJOR_PENALTY: u32 = 15;
+const MAX_MAJOR_PENALTY: u32 = 45;
+const MINOR_PENALTY: u32 = 3;
+const MAX_MINOR_PENALTY: u32 = 15;
+const YANKED_PENALTY: u32 = 50;
+const UNMAINTAINED_PENALTY: u32 = 30;
+const DUPLICATE_PENALTY: u32 = 5;
+const MSRV_PENALTY: u32 = 10;
+
+/// Options for computing freshness scores.
+#[derive(Clone, Debug)]
+pub struct FreshnessPolicy {
+    max_age: Duration,
+    now: SystemTime,
+}
+
+impl FreshnessPolicy {
+    /// Creates a new policy that considers packages unmaintained if they haven't been released for
+    /// more than `max_age` as of `now`.
+    pub fn new(max_age: Duration, now: SystemTime) -> Self {
+        Self { max_age, now }
+    }
+
+    /// Returns the maximum time since the latest release before a package is considered
+    /// unmaintained.
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Returns the time against which release dates are compared.
+    pub fn now(&self) -> SystemTime {
+        self.now
+    }
+}
+
+impl Default for FreshnessPolicy {
+    /// Returns a policy that considers packages unreleased for more than 3 years as of now to be
+    /// unmaintained.
+    fn default() -> Self {
+        Self::new(
+            Duration::from_secs(3 * 365 * 24 * 60 * 60),
+            SystemTime::now(),
+        )
+    }
+}
+
+/// The freshness of a single package.
+#[derive(Clone, Debug)]
+pub struct Freshness<'g> {
+    package: PackageMetadata<'g>,
+    majors_behind: u64,
+    minors_behind: u64,
+    yanked: bool,
+    unmaintained: bool,
+    duplicates: Vec<PackageMetadata<'g>>,
+}
+
+impl<'g> Freshness<'g> {
+    /// Computes the freshness of this package.
+    pub fn new(package: PackageMetadata<'g>, policy: &FreshnessPolicy) -> Self {
+        let signals = package.maintenance_signals();
+
+        let (mut majors_behind, mut minors_behind) = (0, 0);
+        if let Some(latest) = signals.and_then(|signals| signals.latest_version()) {
+            let current = package.version();
+            if latest.major > current.major {
+                majors_behind = latest.major - current.major;
+            } else if latest.major == current.major && latest.minor > current.minor {
+                minors_behind = latest.minor - current.minor;
+            }
+        }
+        let yanked = signals.and_then(|signals| signals.yanked()) == Some(true);
+        let unmaintained = match signals {
+            Some(signals) => signals.is_stale(policy.max_age, policy.now),
+            None => false,
+        };
+
+        let mut duplicates: Vec<_> = package
+            .graph
+            .packages()
+            .filter(|other| other.name() == package.name() && other.id() != package.id())
+            .collect();
+        duplicates.sort_by(|a, b| a.version().cmp(b.version()));
+
+        Self {
+            package,
+            majors_behind,
+            minors_behind,
+            yanked,
+            unmaintained,
+            duplicates,
+        }
+    }
+
+    /// Returns the package this is for.
+    pub fn package(&self) -> PackageMetadata<'g> {
+        self.package
+    }
+
+    /// Returns the number of major versions this package is behind the latest published one.
+    pub fn majors_behind(&self) -> u64 {
+        self.majors_behind
+    }
+
+    /// Returns the number of minor versions this package is behind the latest published one.
+    ///
+    /// This is 0 if the package is behind by a major version.
+    pub fn minors_behind(&self) -> u64 {
+        self.minors_behind
+    }
+
+    /// Returns true if the version in use was yanked.
+    pub fn is_yanked(&self) -> bool {
+        self.yanked
+    }
+
+    /// Returns true if this package looks unmaintained.
+    pub fn is_unmaintained(&self) -> bool {
+        self.unmaintained
+    }
+
+    /// Returns other packages with the same name present in the graph, in ascending version order.
+    ///
+    /// These are typically other versions of this package, but may also be the same version from
+    /// a different source.
+    pub fn duplicates(&self) -> &[PackageMetadata<'g>] {
+        &self.duplicates
+    }
+
+    /// Returns the freshness score for this package, from 0 to 100.
+    pub fn score(&self) -> u32 {
+        100u32.saturating_sub(self.penalty())
+    }
+
+    /// Returns true if no problems were found with this package.
+    pub fn is_fresh(&self) -> bool {
+        self.penalty() == 0
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn penalty(&self) -> u32 {
+        let mut penalty = scaled_penalty(self.majors_behind, MAJOR_PENALTY, MAX_MAJOR_PENALTY)
+            + scaled_penalty(self.minors_behind, MINOR_PENALTY, MAX_MINOR_PENALTY);
+        if self.yanked {
+            penalty += YANKED_PENALTY;
+        }
+        if self.unmaintained {
+            penalty += UNMAINTAINED_PENALTY;
+        }
+        if !self.duplicates.is_empty() {
+            penalty += DUPLICATE_PENALTY;
+        }
+        penalty
+    }
+}
+
+impl<'g> fmt::Display for Freshness<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: score {}",
+            self.package.name(),
+            self.package.version(),
+            self.score()
+        )?;
+        let mut problems = vec![];
+        if self.majors_behind > 0 {
+            problems.push(format!("{} major versions behind", self.majors_behind));
+        }
+        if self.minors_behind > 0 {
+            problems.push(format!("{} minor versions behind", self.minors_behind));
+        }
+        if self.yanked {
+            problems.push("yanked".to_string());
+        }
+        if self.unmaintained {
+            problems.push("unmaintained".to_string());
+        }
+        if !self.duplicates.is_empty() {
+            let versions: Vec<_> = self
+                .duplicates
+                .iter()
+                .map(|other| other.version().to_string())
+                .collect();
+            problems.push(format!("duplicated as {}", versions.join(", ")));
+        }
+        if !problems.is_empty() {
+            write!(f, " ({})", problems.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The health of a workspace member's dependencies.
+#[derive(Clone, Debug)]
+pub struct MemberHealth<'g> {
+    member: PackageMetadata<'g>,
+    deps: Vec<Freshness<'g>>,
+    msrv_violations: Vec<PackageMetadata<'g>>,
+}
+
+impl<'g> MemberHealth<'g> {
+    /// Computes the health of this workspace member's dependencies.
+    ///
+    /// Dependencies outside the workspace are considered, including the member's own
+    /// dev-dependencies but not those of its dependencies.
+    pub fn new(member: PackageMetadata<'g>, policy: &FreshnessPolicy) -> Self {
+        let mut cache = HashMap::new();
+        Self::new_impl(member, policy, &mut cache)
+    }
+
+    /// Returns the workspace member this is for.
+    pub fn member(&self) -> PackageMetadata<'g> {
+        self.member
+    }
+
+    /// Returns the freshness of each dependency, in topological order.
+    pub fn deps(&self) -> &[Freshness<'g>] {
+        &self.deps
+    }
+
+    /// Returns dependencies that declare a `rust-version` newer than the member's.
+    ///
+    /// A dependency's `rust-version` is read from its maintenance signals. This is empty if the
+    /// member doesn't declare a `rust-version`.
+    pub fn msrv_violations(&self) -> &[PackageMetadata<'g>] {
+        &self.msrv_violations
+    }
+
+    /// Returns the mean freshness score of this member's dependencies, minus 10 for each MSRV
+    /// violation.
+    ///
+    /// The score is 100 if the member has no dependencies outside the workspace.
+    pub fn score(&self) -> u32 {
+        let mean = if self.deps.is_empty() {
+            100
+        } else {
+            let total: u32 = self.deps.iter().map(|dep| dep.score()).sum();
+            total / self.deps.len() as u32
+        };
+        mean.saturating_sub(MSRV_PENALTY * self.msrv_violations.len() as u32)
+    }
+
+    /// Returns the dependency with the lowest freshness score, if any.
+    pub fn worst(&self) -> Option<&Freshness<'g>> {
+        self.deps.iter().min_by_key(|dep| dep.score())
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn new_impl(
+        member: PackageMetadata<'g>,
+        policy: &FreshnessPolicy,
+        cache: &mut HashMap<&'g PackageId, Freshness<'g>>,
+    ) -> Self {
+        let deps: Vec<_> = member
+            .graph
+            .query_forward(std::iter::once(member.id()))
+            .expect("valid package ID")
+            .resolve_with_fn(|_, link| !link.dev_only() || link.from().id() == member.id())
+            .packages(DependencyDirection::Forward)
+            .filter(|dep| !dep.in_workspace())
+            .collect();
+
+        let msrv_violations = match member.rust_version() {
+            Some(member_version) => deps
+                .iter()
+                .filter(|dep| match dep_rust_version(**dep) {
+                    Some(dep_version) => dep_version > member_version,
+                    None => false,
+                })
+                .copied()
+                .collect(),
+            None => vec![],
+        };
+
+        let deps = deps
+            .into_iter()
+            .map(|dep| {
+                cache
+                    .entry(dep.id())
+                    .or_insert_with(|| Freshness::new(dep, policy))
+                    .clone()
+            })
+            .collect();
+
+        Self {
+            member,
+            deps,
+            msrv_violations,
+        }
+    }
+}
+
+/// A freshness report for every workspace member.
+///
+/// Returned by `PackageGraph::health_report`. The `Display` implementation lists, for each
+/// member, its score and every dependency with problems.
+#[derive(Clone, Debug)]
+pub struct HealthReport<'g> {
+    members: Vec<MemberHealth<'g>>,
+}
+
+impl<'g> HealthReport<'g> {
+    /// Returns the health of each workspace member, ordered by workspace path.
+    pub fn members(&self) -> &[MemberHealth<'g>] {
+        &self.members
+    }
+
+    /// Returns true if no problems were found with any workspace member's dependencies.
+    pub fn is_healthy(&self) -> bool {
+        self.members.iter().all(|member| member.score() == 100)
+    }
+}
+
+impl<'g> fmt::Display for HealthReport<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for member in &self.members {
+            writeln!(
+                f,
+                "{}: score {} ({} dependencies)",
+                member.member.name(),
+                member.score(),
+                member.deps.len()
+            )?;
+            for dep in member.deps.iter().filter(|dep| !dep.is_fresh()) {
+                writeln!(f, "    {}", dep)?;
+            }
+            for dep in &member.msrv_violations {
+                writeln!(
+                    f,
+                    "    {} {}: requires Rust {}, newer than {}",
+                    dep.name(),
+                    dep.version(),
+                    dep_rust_version(*dep).expect("MSRV violations have a rust-version"),
+                    member
+                        .member
+                        .rust_version()
+                        .expect("MSRV violations are only computed with a rust-version"),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'g> Report for HealthReport<'g> {
+    fn to_document(&self) -> ReportDocument {
+        let mut document = ReportDocument::new("Dependency health");
+        let section = document.add_section(None);
+        for member in &self.members {
+            let item = section.add_item(format!(
+                "{}: score {} ({} dependencies)",
+                member.member.name(),
+                member.score(),
+                member.deps.len()
+            ));
+            for dep in member.deps.iter().filter(|dep| !dep.is_fresh()) {
+                item.add_detail(dep.to_string());
+            }
+            for dep in &member.msrv_violations {
+                item.add_detail(format!(
+                    "{} {}: requires Rust {}, newer than {}",
+                    dep.name(),
+                    dep.version(),
+                    dep_rust_version(*dep).expect("MSRV violations have a rust-version"),
+                    member
+                        .member
+                        .rust_version()
+                        .expect("MSRV violations are only computed with a rust-version"),
+                ));
+            }
+        }
+        document
+    }
+}
+
+impl PackageGraph {
+    /// Computes a freshness report for every workspace member.
+    pub fn health_report(&self, policy: &FreshnessPolicy) -> HealthReport<'_> {
+        let mut cache = HashMap::new();
+        let members = self
+            .workspace()
+            .members()
+            .map(|(_, member)| MemberHealth::new_impl(member, policy, &mut cache))
+            .collect();
+        HealthReport { members }
+    }
+}
+
+fn dep_rust_version<'g>(dep: PackageMetadata<'g>) -> Option<&'g Version> {
+    dep.rust_version().or_else(|| {
+        dep.maintenance_signals()
+            .and_then(|signals| signals.rust_version())
+    })
+}
+
+fn scaled_penalty(count: u64, per: u32, max: u32) -> u32 {
+    if count >= u64::from(max / per) {
+        max
+    } else {
+        count as u32 * per
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Metrics for a workspace's dependency graph over a range of git commits.
+//!
+//! `PackageGraph::history` builds a graph for each commit in a range with
+//! `PackageGraph::from_git_ref`, and summarizes each one as a `GraphMetrics`. The result is a time
+//! series suitable for dashboards. Graphs are only built for commits that change a manifest or the
+//! lockfile: other commits reuse the metrics for the previous commit with the same files.
+//!
+//! This module is experimental, and requires the `unstable` feature to be enabled.
+
+use crate::git::Git;
+use crate::graph::{DependencyDirection, PackageGraph};
+use crate::{Error, MetadataCommand};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::iter;
+use std::path::Path;
+
+/// Summary metrics for a `PackageGraph`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GraphMetrics {
+    package_count: usize,
+    workspace_member_count: usize,
+    duplicate_count: usize,
+    closure_sizes: BTreeMap<String, usize>,
+    licenses: BTreeMap<String, usize>,
+}
+
+impl GraphMetrics {
+    /// The name `licenses` uses for packages that don't declare a license.
+    pub const NO_LICENSE: &'static str = "(none)";
+
+    /// Computes metrics for this graph.
+    pub fn new(graph: &PackageGraph) -> Self {
+        let resolved = graph.query_workspace().resolve();
+        let mut versions: BTreeMap<_, usize> = BTreeMap::new();
+        let mut licenses: BTreeMap<String, usize> = BTreeMap::new();
+        for package in resolved.packages(DependencyDirection::Forward) {
+            *versions.entry(package.name()).or_default() += 1;
+            *licenses
+                .entry(package.license().unwrap_or(Self::NO_LICENSE).to_string())
+                .or_default() += 1;
+        }
+
+        let closure_sizes = graph
+            .workspace()
+            .members()
+            .map(|(_, member)| {
+                let closure = graph
+                    .query_forward(iter::once(member.id()))
+                    .expect("workspace members are known to the graph")
+                    .resolve();
+                // Don't count the member itself.
+                (member.name().to_string(), closure.len() - 1)
+            })
+            .collect();
+
+        Self {
+            package_count: graph.package_count(),
+            workspace_member_count: graph.workspace().member_count(),
+            duplicate_count: versions.values().filter(|count| **count > 1).count(),
+            closure_sizes,
+            licenses,
+        }
+    }
+
+    /// Returns the number of packages in the graph.
+    pub fn package_count(&self) -> usize {
+        self.package_count
+    }
+
+    /// Returns the number of workspace members.
+    pub fn workspace_member_count(&self) -> usize {
+        self.workspace_member_count
+    }
+
+    /// Returns the number of package names that resolve to more than one version.
+    pub fn duplicate_count(&self) -> usize {
+        self.duplicate_count
+    }
+
+    /// Returns the number of transitive dependencies of each workspace member, by name.
+    ///
+    /// Dependencies of every kind are counted, on all platforms.
+    pub fn closure_sizes(&self) -> &BTreeMap<String, usize> {
+        &self.closure_sizes
+    }
+
+    /// Returns the number of packages depended on by the workspace with each license
+    /// expression, including workspace members.
+    ///
+    /// Packages that don't declare a license are counted under `GraphMetrics::NO_LICENSE`.
+    pub fn licenses(&self) -> &BTreeMap<String, usize> {
+        &self.licenses
+    }
+}
+
+/// Metrics for a single commit, returned by `PackageGraph::history`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HistoryEntry {
+    commit: String,
+    time: i64,
+    summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metrics: Option<GraphMetrics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Returns the commit hash.
+    pub fn commit(&self) -> &str {
+        &self.commit
+    }
+
+    /// Returns the committer time, in seconds since the Unix epoch.
+    pub fn time(&self) -> i64 {
+        self.time
+    }
+
+    /// Returns the first line of the commit message.
+    pub fn summary(&self) -> &str {
+        &self.summary
+    }
+
+    /// Returns the metrics for this commit, or `None` if a graph couldn't be built for it.
+    pub fn metrics(&self) -> Option<&GraphMetrics> {
+        self.metrics.as_ref()
+    }
+
+    /// Returns the error that occurred while building a graph for this commit, if any.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+impl PackageGraph {
+    /// Computes metrics for the workspace at `repo_path` at each commit in `range`, oldest first.
+    ///
+    /// `range` is anything `git log` understands, such as `v1.0..main`. Only first parents are
+    /// followed, so commits on merged branches are represented by their merge commit.
+    ///
+    /// See `from_git_ref_with_command` for how graphs are built. If a graph can't be built for a
+    /// commit, for example because a manifest was invalid then, the error is recorded in its entry
+    /// and the remaining commits are still processed. Errors that affect every commit, such as an
+    /// invalid repository or range, are returned.
+    ///
+    /// Requires the `unstable` feature to be enabled.
+    pub fn history(
+        repo_path: impl AsRef<Path>,
+        range: impl AsRef<str>,
+        command: &mut MetadataCommand,
+    ) -> Result<Vec<HistoryEntry>, Error> {
+        let git = Git::open(repo_path.as_ref())?;
+        let mut cache: HashMap<String, Result<GraphMetrics, String>> = HashMap::new();
+        let mut entries = vec![];
+
+        for commit in git.log(range.as_ref())? {
+            let tree = git.tree(&commit.hash)?;
+            let result = cache
+                .entry(tree.manifest_key())
+                .or_insert_with(|| {
+                    git.build_graph(&tree, command)
+                        .map(|graph| GraphMetrics::new(&graph))
+                        .map_err(|err| err.to_string())
+                })
+                .clone();
+            let (metrics, error) = match result {
+                Ok(metrics) => (Some(metrics), None),
+                Err(error) => (None, Some(error)),
+            };
+            entries.push(HistoryEntry {
+                commit: commit.hash,
+                time: commit.time,
+                summary: commit.summary,
+                metrics,
+                error,
+            });
+        }
+
+        Ok(entries)
+    }
+}
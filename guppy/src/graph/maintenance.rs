@@ -0,0 +1,304 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Upstream maintenance signals for packages.
+//!
+//! `cargo metadata` says nothing about whether a dependency is still maintained. This module
+//! provides an optional enrichment pass that asks one or more `SignalSource` instances for
+//! per-package signals (release dates, the latest version in the registry index, whether the
+//! version in use is yanked, its minimum supported Rust version, whether the upstream repository
+//! is archived), and
+//! attaches the results to the `PackageGraph`. The signals can then be retrieved through
+//! `PackageMetadata::maintenance_signals` and used in policy rules like "no dependencies
+//! unreleased for more than 3 years".
+//!
+//! `guppy` does not perform any network access itself: sources backed by a registry index, the
+//! crates.io API or a code forge are provided by the caller.
+//!
+//! This module is experimental, and requires the `unstable` feature to be enabled.
+
+use crate::graph::{PackageGraph, PackageMetadata};
+use crate::{Error, PackageId, Version};
+use std::collections::{BTreeMap, HashMap};
+use std::error;
+use std::time::{Duration, SystemTime};
+
+/// Maintenance signals for a single package.
+///
+/// All signals are optional, since different sources may know about different things.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MaintenanceSignals {
+    // Sorted in ascending order.
+    release_dates: Vec<SystemTime>,
+    latest_version: Option<Version>,
+    yanked: Option<bool>,
+    rust_version: Option<Version>,
+    archived: Option<bool>,
+}
+
+impl MaintenanceSignals {
+    /// Creates a new, empty set of signals.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the dates at which versions of this package were released.
+    ///
+    /// The dates do not need to be in any particular order, and duplicates are removed.
+    pub fn with_release_dates(mut self, dates: impl IntoIterator<Item = SystemTime>) -> Self {
+        self.release_dates = dates.into_iter().collect();
+        self.release_dates.sort();
+        self.release_dates.dedup();
+        self
+    }
+
+    /// Sets the latest version of this package published to its registry.
+    pub fn with_latest_version(mut self, version: Version) -> Self {
+        self.latest_version = Some(version);
+        self
+    }
+
+    /// Sets whether the version of this package in use has been yanked from its registry.
+    pub fn with_yanked(mut self, yanked: bool) -> Self {
+        self.yanked = Some(yanked);
+        self
+    }
+
+    /// Sets the minimum supported Rust version that the version in use declares.
+    ///
+    /// `cargo metadata` only reports this for workspace members, so for other packages it is
+    /// typically read from the registry index.
+    pub fn with_rust_version(mut self, version: Version) -> Self {
+        self.rust_version = Some(version);
+        self
+    }
+
+    /// Sets whether the upstream repository for this package is archived.
+    pub fn with_archived(mut self, archived: bool) -> Self {
+        self.archived = Some(archived);
+        self
+    }
+
+    /// Returns the release dates known for this package, in ascending order.
+    pub fn release_dates(&self) -> &[SystemTime] {
+        &self.release_dates
+    }
+
+    /// Returns the date of the latest known release, if any.
+    pub fn latest_release(&self) -> Option<SystemTime> {
+        self.release_dates.last().copied()
+    }
+
+    /// Returns the latest version published to the registry, or `None` if this isn't known.
+    pub fn latest_version(&self) -> Option<&Version> {
+        self.latest_version.as_ref()
+    }
+
+    /// Returns whether the version in use has been yanked, or `None` if this isn't known.
+    pub fn yanked(&self) -> Option<bool> {
+        self.yanked
+    }
+
+    /// Returns the minimum supported Rust version of the version in use, or `None` if this isn't
+    /// known.
+    pub fn rust_version(&self) -> Option<&Version> {
+        self.rust_version.as_ref()
+    }
+
+    /// Returns whether the upstream repository is archived, or `None` if this isn't known.
+    pub fn archived(&self) -> Option<bool> {
+        self.archived
+    }
+
+    /// Returns the average time between consecutive releases.
+    ///
+    /// Returns `None` if fewer than two release dates are known.
+    pub fn release_cadence(&self) -> Option<Duration> {
+        let first = self.release_dates.first()?;
+        let last = self.release_dates.last()?;
+        let intervals = self.release_dates.len() - 1;
+        if intervals == 0 {
+            return None;
+        }
+        let total = last.duration_since(*first).unwrap_or_default();
+        Some(total / intervals as u32)
+    }
+
+    /// Returns how long it has been since the latest release, as of `now`.
+    ///
+    /// Returns `None` if no release dates are known.
+    pub fn unreleased_for(&self, now: SystemTime) -> Option<Duration> {
+        let latest = self.latest_release()?;
+        Some(now.duration_since(latest).unwrap_or_default())
+    }
+
+    /// Returns true if the latest release is older than `max_age` as of `now`, or if the upstream
+    /// repository is known to be archived.
+    ///
+    /// Packages without any release information are not considered stale.
+    pub fn is_stale(&self, max_age: Duration, now: SystemTime) -> bool {
+        if self.archived == Some(true) {
+            return true;
+        }
+        match self.unreleased_for(now) {
+            Some(age) => age > max_age,
+            None => false,
+        }
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    /// Merges signals from `other` into `self`. Release dates are unioned, and signals already
+    /// present in `self` take precedence over ones in `other`.
+    fn merge(&mut self, other: MaintenanceSignals) {
+        self.release_dates.extend(other.release_dates);
+        self.release_dates.sort();
+        self.release_dates.dedup();
+        if self.latest_version.is_none() {
+            self.latest_version = other.latest_version;
+        }
+        if self.yanked.is_none() {
+            self.yanked = other.yanked;
+        }
+        if self.rust_version.is_none() {
+            self.rust_version = other.rust_version;
+        }
+        if self.archived.is_none() {
+            self.archived = other.archived;
+        }
+    }
+}
+
+/// A source of maintenance signals, for example a registry index or an HTTP API.
+pub trait SignalSource {
+    /// Returns the signals known for this package, or `None` if this source has no information
+    /// about it.
+    fn signals(
+        &self,
+        package: PackageMetadata<'_>,
+    ) -> Result<Option<MaintenanceSignals>, Box<dyn error::Error + Send + Sync>>;
+}
+
+impl<S: SignalSource + ?Sized> SignalSource for &S {
+    fn signals(
+        &self,
+        package: PackageMetadata<'_>,
+    ) -> Result<Option<MaintenanceSignals>, Box<dyn error::Error + Send + Sync>> {
+        (**self).signals(package)
+    }
+}
+
+/// A static map from package names to signals, useful for pre-seeded or offline data.
+impl SignalSource for BTreeMap<String, MaintenanceSignals> {
+    fn signals(
+        &self,
+        package: PackageMetadata<'_>,
+    ) -> Result<Option<MaintenanceSignals>, Box<dyn error::Error + Send + Sync>> {
+        Ok(self.get(package.name()).cloned())
+    }
+}
+
+/// Options for the maintenance signal enrichment pass.
+pub struct MaintenanceEnricher<'a> {
+    sources: Vec<&'a dyn SignalSource>,
+    include_workspace: bool,
+}
+
+impl<'a> MaintenanceEnricher<'a> {
+    /// Creates a new `MaintenanceEnricher` with no sources.
+    ///
+    /// By default, workspace members are skipped since they have no upstream.
+    pub fn new() -> Self {
+        Self {
+            sources: vec![],
+            include_workspace: false,
+        }
+    }
+
+    /// Adds a source of signals.
+    ///
+    /// Sources are queried in the order they were added. If several sources report the same
+    /// signal, the one added first wins.
+    pub fn add_source(mut self, source: &'a dyn SignalSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// If set to true, workspace members are also queried.
+    pub fn with_workspace(mut self, include_workspace: bool) -> Self {
+        self.include_workspace = include_workspace;
+        self
+    }
+
+    /// Queries all sources for every package in this graph, returning the collected signals.
+    ///
+    /// Packages that no source knows about are not included in the result.
+    pub fn collect(
+        &self,
+        graph: &PackageGraph,
+    ) -> Result<HashMap<PackageId, MaintenanceSignals>, Error> {
+        let mut result = HashMap::new();
+        for package in graph.packages() {
+            if package.in_workspace() && !self.include_workspace {
+                continue;
+            }
+            let mut combined: Option<MaintenanceSignals> = None;
+            for source in &self.sources {
+                let signals = source
+                    .signals(package)
+                    .map_err(|err| Error::MaintenanceSignalError(package.id().clone(), err))?;
+                if let Some(signals) = signals {
+                    match &mut combined {
+                        Some(combined) => combined.merge(signals),
+                        None => combined = Some(signals),
+                    }
+                }
+            }
+            if let Some(combined) = combined {
+                result.insert(package.id().clone(), combined);
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl<'a> Default for MaintenanceEnricher<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PackageGraph {
+    /// Runs the maintenance signal enrichment pass over this graph, attaching the results to each
+    /// package.
+    ///
+    /// Signals from a previous run are replaced. Returns the number of packages for which signals
+    /// were found.
+    pub fn enrich_maintenance(
+        &mut self,
+        enricher: &MaintenanceEnricher<'_>,
+    ) -> Result<usize, Error> {
+        let signals = enricher.collect(self)?;
+        let count = signals.len();
+        self.data.maintenance = signals;
+        Ok(count)
+    }
+
+    /// Returns the packages whose maintenance signals mark them as stale, as determined by
+    /// `MaintenanceSignals::is_stale`.
+    ///
+    /// Only packages for which signals were attached through `enrich_maintenance` are considered.
+    pub fn stale_packages<'g>(
+        &'g self,
+        max_age: Duration,
+        now: SystemTime,
+    ) -> impl Iterator<Item = PackageMetadata<'g>> + 'g {
+        self.packages()
+            .filter(move |package| match package.maintenance_signals() {
+                Some(signals) => signals.is_stale(max_age, now),
+                None => false,
+            })
+    }
+}
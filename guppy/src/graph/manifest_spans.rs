@@ -0,0 +1,572 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Source locations for declarations in `Cargo.toml` files.
+//!
+//! `cargo metadata` doesn't say where in a manifest anything was declared. For workspace members,
+//! `guppy` scans the manifest during graph construction and records where dependencies, features,
+//! build targets and `[package]` keys are declared, so that diagnostics can point at them.
+
+use crate::graph::feature::FeatureMetadata;
+use crate::graph::{BuildTargetId, PackageLink, PackageMetadata};
+use crate::DependencyKind;
+use std::collections::BTreeMap;
+
+/// A range of text in a `Cargo.toml` file.
+///
+/// Lines and columns start at 1, and columns count characters rather than bytes. The end column is
+/// one past the last character in the range.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ManifestSpan {
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+impl ManifestSpan {
+    /// Returns the line the span starts on.
+    pub fn start_line(&self) -> usize {
+        self.start_line
+    }
+
+    /// Returns the column the span starts at.
+    pub fn start_column(&self) -> usize {
+        self.start_column
+    }
+
+    /// Returns the line the span ends on.
+    pub fn end_line(&self) -> usize {
+        self.end_line
+    }
+
+    /// Returns the column one past the end of the span.
+    pub fn end_column(&self) -> usize {
+        self.end_column
+    }
+
+    fn new(start: Pos, end: Pos) -> Self {
+        Self {
+            start_line: start.line + 1,
+            start_column: start.col + 1,
+            end_line: end.line + 1,
+            end_column: end.col + 1,
+        }
+    }
+}
+
+/// A dependency declaration in a `Cargo.toml` file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DependencySpan {
+    dep_name: String,
+    kind: DependencyKind,
+    target: Option<String>,
+    span: ManifestSpan,
+}
+
+impl DependencySpan {
+    /// Returns the name of the dependency, as written in the manifest.
+    ///
+    /// This matches `PackageLink::dep_name`.
+    pub fn dep_name(&self) -> &str {
+        &self.dep_name
+    }
+
+    /// Returns the kind of dependency this declaration is for.
+    pub fn kind(&self) -> DependencyKind {
+        self.kind
+    }
+
+    /// Returns the platform this declaration is for, as the triple or `cfg()` expression in a
+    /// `[target.<platform>.dependencies]` section, or `None` if it is for all platforms.
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    /// Returns the location of the declaration. This covers the key and value for a declaration
+    /// inside a dependencies section, or the entire table for a `[dependencies.<name>]` section.
+    pub fn span(&self) -> ManifestSpan {
+        self.span
+    }
+}
+
+/// Locations of declarations in a workspace member's `Cargo.toml`.
+///
+/// Returned by `PackageMetadata::manifest_spans`.
+#[derive(Clone, Debug, Default)]
+pub struct ManifestSpans {
+    package_keys: BTreeMap<String, ManifestSpan>,
+    dependencies: Vec<DependencySpan>,
+    features: BTreeMap<String, ManifestSpan>,
+    targets: Vec<(TargetSection, Option<String>, ManifestSpan)>,
+}
+
+impl ManifestSpans {
+    /// Returns the location of a key in the `[package]` section, such as `rust-version`.
+    pub fn package_key(&self, key: &str) -> Option<ManifestSpan> {
+        self.package_keys.get(key).copied()
+    }
+
+    /// Iterates over every dependency declaration, in the order they appear in the manifest.
+    pub fn dependencies<'a>(&'a self) -> impl Iterator<Item = &'a DependencySpan> + 'a {
+        self.dependencies.iter()
+    }
+
+    /// Iterates over declarations for the dependency with this name, in the order they appear in
+    /// the manifest.
+    ///
+    /// A dependency may be declared several times, for example as both a normal and a dev
+    /// dependency, or for different platforms.
+    pub fn dependency<'a>(&'a self, dep_name: &'a str) -> impl Iterator<Item = &'a DependencySpan> {
+        self.dependencies
+            .iter()
+            .filter(move |span| span.dep_name == dep_name)
+    }
+
+    /// Returns the location of a named feature in the `[features]` section.
+    ///
+    /// Optional dependencies are only declared as dependencies, so this returns `None` for them.
+    pub fn feature(&self, name: &str) -> Option<ManifestSpan> {
+        self.features.get(name).copied()
+    }
+
+    /// Returns the location of a build target's section, such as `[lib]` or `[[bin]]`.
+    ///
+    /// For build scripts, this is the `build` key in the `[package]` section. Returns `None` for
+    /// targets that Cargo discovered automatically without a section.
+    pub fn target(&self, id: BuildTargetId<'_>) -> Option<ManifestSpan> {
+        let (section, name) = match id {
+            BuildTargetId::Library => return self.section_span(TargetSection::Lib, None),
+            BuildTargetId::BuildScript => return self.package_key("build"),
+            BuildTargetId::Binary(name) => (TargetSection::Bin, name),
+            BuildTargetId::Example(name) => (TargetSection::Example, name),
+            BuildTargetId::Test(name) => (TargetSection::Test, name),
+            BuildTargetId::Benchmark(name) => (TargetSection::Bench, name),
+        };
+        self.section_span(section, Some(name))
+    }
+
+    fn section_span(&self, section: TargetSection, name: Option<&str>) -> Option<ManifestSpan> {
+        self.targets
+            .iter()
+            .find(|(s, n, _)| {
+                // A [lib] section may leave out the name, and still refers to the library.
+                *s == section && (section == TargetSection::Lib || n.as_deref() == name)
+            })
+            .map(|(_, _, span)| *span)
+    }
+
+    /// Scans the source of a manifest. This doesn't validate the manifest: Cargo has already
+    /// parsed it by the time `guppy` sees it.
+    pub(super) fn parse(source: &str) -> Self {
+        Scanner::new(source).scan()
+    }
+}
+
+impl<'g> PackageMetadata<'g> {
+    /// Returns the locations of declarations in this package's `Cargo.toml`.
+    ///
+    /// This is only available for workspace members, and is `None` for other packages or if the
+    /// manifest couldn't be found or parsed during graph construction.
+    pub fn manifest_spans(&self) -> Option<&'g ManifestSpans> {
+        self.inner.manifest_spans.as_ref()
+    }
+}
+
+impl<'g> PackageLink<'g> {
+    /// Iterates over the declarations of this dependency in the `from` package's `Cargo.toml`.
+    ///
+    /// This is empty unless the `from` package is a workspace member. See
+    /// `PackageMetadata::manifest_spans` for more.
+    pub fn declaration_spans(&self) -> impl Iterator<Item = &'g DependencySpan> + 'g {
+        let dep_name = self.dep_name();
+        self.from()
+            .manifest_spans()
+            .into_iter()
+            .flat_map(move |spans| spans.dependency(dep_name))
+    }
+}
+
+impl<'g> FeatureMetadata<'g> {
+    /// Returns the location of this feature in its package's `[features]` section.
+    ///
+    /// This is `None` for base features, optional dependencies, and features of packages outside
+    /// the workspace. See `PackageMetadata::manifest_spans` for more.
+    pub fn manifest_span(&self) -> Option<ManifestSpan> {
+        let feature = self.feature_id().feature()?;
+        self.package().manifest_spans()?.feature(feature)
+    }
+}
+
+// ---
+// Helper methods
+// ---
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum TargetSection {
+    Lib,
+    Bin,
+    Example,
+    Test,
+    Bench,
+}
+
+/// The section of the manifest the scanner is in.
+enum Section {
+    Package,
+    Dependencies {
+        kind: DependencyKind,
+        target: Option<String>,
+    },
+    Dependency {
+        dep_name: String,
+        kind: DependencyKind,
+        target: Option<String>,
+        start: Pos,
+    },
+    Features,
+    Target {
+        section: TargetSection,
+        name: Option<String>,
+        start: Pos,
+    },
+    Other,
+}
+
+/// A position in the source, with lines and columns starting at 0.
+#[derive(Copy, Clone, Debug)]
+struct Pos {
+    line: usize,
+    col: usize,
+}
+
+struct Scanner {
+    lines: Vec<Vec<char>>,
+    spans: ManifestSpans,
+    section: Section,
+    // The end of the last header or key-value pair, which is where a table section ends.
+    last_end: Pos,
+}
+
+impl Scanner {
+    fn new(source: &str) -> Self {
+        Self {
+            lines: source.lines().map(|line| line.chars().collect()).collect(),
+            spans: ManifestSpans::default(),
+            section: Section::Other,
+            last_end: Pos { line: 0, col: 0 },
+        }
+    }
+
+    fn scan(mut self) -> ManifestSpans {
+        let mut line = 0;
+        while line < self.lines.len() {
+            let col = match self.lines[line].iter().position(|c| !c.is_whitespace()) {
+                Some(col) => col,
+                None => {
+                    line += 1;
+                    continue;
+                }
+            };
+            let start = Pos { line, col };
+            line = match self.lines[line][col] {
+                '#' => line + 1,
+                '[' => self.header(start),
+                _ => self.key_value(start),
+            };
+        }
+        self.close_section();
+        self.spans
+    }
+
+    /// Processes a table header, returning the next line to scan.
+    fn header(&mut self, start: Pos) -> usize {
+        self.close_section();
+        let chars = &self.lines[start.line];
+        let is_array = chars.get(start.col + 1) == Some(&'[');
+        let key_col = start.col + if is_array { 2 } else { 1 };
+        let (segments, key_end) = parse_key(chars, key_col);
+        let segments: Vec<_> = segments.iter().map(|s| s.as_str()).collect();
+        let close_col = chars[key_end..]
+            .iter()
+            .position(|c| *c == ']')
+            .map_or(chars.len(), |len| key_end + len);
+        self.last_end = Pos {
+            line: start.line,
+            col: (close_col + if is_array { 2 } else { 1 }).min(chars.len()),
+        };
+
+        self.section = match (is_array, segments.as_slice()) {
+            (false, ["package"]) => Section::Package,
+            (false, ["features"]) => Section::Features,
+            (false, ["lib"]) => Section::Target {
+                section: TargetSection::Lib,
+                name: None,
+                start,
+            },
+            (true, [name]) => match target_section(name) {
+                Some(section) => Section::Target {
+                    section,
+                    name: None,
+                    start,
+                },
+                None => Section::Other,
+            },
+            (false, [deps]) => match dependency_kind(deps) {
+                Some(kind) => Section::Dependencies { kind, target: None },
+                None => Section::Other,
+            },
+            (false, [deps, dep_name]) => match dependency_kind(deps) {
+                Some(kind) => Section::Dependency {
+                    dep_name: dep_name.to_string(),
+                    kind,
+                    target: None,
+                    start,
+                },
+                None => Section::Other,
+            },
+            (false, ["target", target, deps]) => match dependency_kind(deps) {
+                Some(kind) => Section::Dependencies {
+                    kind,
+                    target: Some(target.to_string()),
+                },
+                None => Section::Other,
+            },
+            (false, ["target", target, deps, dep_name]) => match dependency_kind(deps) {
+                Some(kind) => Section::Dependency {
+                    dep_name: dep_name.to_string(),
+                    kind,
+                    target: Some(target.to_string()),
+                    start,
+                },
+                None => Section::Other,
+            },
+            _ => Section::Other,
+        };
+        start.line + 1
+    }
+
+    /// Processes a key-value pair, returning the next line to scan.
+    fn key_value(&mut self, start: Pos) -> usize {
+        let chars = &self.lines[start.line];
+        let (segments, key_end) = parse_key(chars, start.col);
+        let eq_col = skip_whitespace(chars, key_end);
+        if segments.is_empty() || chars.get(eq_col) != Some(&'=') {
+            // Not something this scanner understands.
+            return start.line + 1;
+        }
+        let value_start = Pos {
+            line: start.line,
+            col: skip_whitespace(chars, eq_col + 1),
+        };
+        let end = self.value_end(value_start);
+        let span = ManifestSpan::new(start, end);
+        self.last_end = end;
+
+        let key = segments[0].clone();
+        match &mut self.section {
+            Section::Package => {
+                self.spans.package_keys.entry(key).or_insert(span);
+            }
+            Section::Dependencies { kind, target } => {
+                self.spans.dependencies.push(DependencySpan {
+                    dep_name: key,
+                    kind: *kind,
+                    target: target.clone(),
+                    span,
+                });
+            }
+            Section::Features => {
+                self.spans.features.insert(key, span);
+            }
+            Section::Target { name, .. } => {
+                if key == "name" && segments.len() == 1 {
+                    *name = string_value(&self.lines, value_start, end);
+                }
+            }
+            Section::Dependency { .. } | Section::Other => {}
+        }
+        end.line + 1
+    }
+
+    /// Records spans for the table section that was being scanned, if any.
+    fn close_section(&mut self) {
+        let end = self.last_end;
+        match std::mem::replace(&mut self.section, Section::Other) {
+            Section::Dependency {
+                dep_name,
+                kind,
+                target,
+                start,
+            } => self.spans.dependencies.push(DependencySpan {
+                dep_name,
+                kind,
+                target,
+                span: ManifestSpan::new(start, end),
+            }),
+            Section::Target {
+                section,
+                name,
+                start,
+            } => self
+                .spans
+                .targets
+                .push((section, name, ManifestSpan::new(start, end))),
+            _ => {}
+        }
+    }
+
+    /// Returns the position one past the end of the value starting at this position. Values may
+    /// span several lines.
+    fn value_end(&self, start: Pos) -> Pos {
+        let mut depth = 0_usize;
+        let mut end = start;
+        let (mut line, mut col) = (start.line, start.col);
+        while line < self.lines.len() {
+            let chars = &self.lines[line];
+            if col >= chars.len() {
+                if depth == 0 {
+                    break;
+                }
+                line += 1;
+                col = 0;
+                continue;
+            }
+            match chars[col] {
+                '#' => col = chars.len(),
+                quote @ '"' | quote @ '\'' => {
+                    let pos = self.string_end(Pos { line, col }, quote);
+                    line = pos.line;
+                    col = pos.col;
+                    end = pos;
+                }
+                '[' | '{' => {
+                    depth += 1;
+                    col += 1;
+                    end = Pos { line, col };
+                }
+                ']' | '}' => {
+                    depth = depth.saturating_sub(1);
+                    col += 1;
+                    end = Pos { line, col };
+                }
+                c if c.is_whitespace() => col += 1,
+                _ => {
+                    col += 1;
+                    end = Pos { line, col };
+                }
+            }
+        }
+        end
+    }
+
+    /// Returns the position one past the end of the string starting at this position.
+    fn string_end(&self, start: Pos, quote: char) -> Pos {
+        let chars = &self.lines[start.line];
+        let is_multiline = chars[start.col..].starts_with(&[quote, quote, quote]);
+        let (mut line, mut col) = (start.line, start.col + if is_multiline { 3 } else { 1 });
+        while line < self.lines.len() {
+            let chars = &self.lines[line];
+            if col >= chars.len() {
+                if !is_multiline {
+                    // Unterminated string.
+                    return Pos { line, col };
+                }
+                line += 1;
+                col = 0;
+                continue;
+            }
+            if quote == '"' && chars[col] == '\\' {
+                col += 2;
+            } else if is_multiline && chars[col..].starts_with(&[quote, quote, quote]) {
+                return Pos { line, col: col + 3 };
+            } else if !is_multiline && chars[col] == quote {
+                return Pos { line, col: col + 1 };
+            } else {
+                col += 1;
+            }
+        }
+        Pos { line, col }
+    }
+}
+
+/// Returns the contents of a single-line string value without escapes.
+fn string_value(lines: &[Vec<char>], start: Pos, end: Pos) -> Option<String> {
+    if start.line != end.line {
+        return None;
+    }
+    let value = &lines[start.line][start.col..end.col];
+    match value {
+        ['"', inner @ .., '"'] if !inner.contains(&'\\') => Some(inner.iter().collect()),
+        ['\'', inner @ .., '\''] => Some(inner.iter().collect()),
+        _ => None,
+    }
+}
+
+/// Parses a dotted key starting at this column, returning its segments and the column one past
+/// its end.
+fn parse_key(chars: &[char], col: usize) -> (Vec<String>, usize) {
+    let mut segments = vec![];
+    let mut col = skip_whitespace(chars, col);
+    loop {
+        let segment_end = match chars.get(col) {
+            Some(&quote) if quote == '"' || quote == '\'' => {
+                let mut end = col + 1;
+                let mut segment = String::new();
+                while end < chars.len() && chars[end] != quote {
+                    if quote == '"' && chars[end] == '\\' && end + 1 < chars.len() {
+                        end += 1;
+                    }
+                    segment.push(chars[end]);
+                    end += 1;
+                }
+                segments.push(segment);
+                (end + 1).min(chars.len())
+            }
+            _ => {
+                let end = chars[col.min(chars.len())..]
+                    .iter()
+                    .position(|c| !(c.is_ascii_alphanumeric() || *c == '-' || *c == '_'))
+                    .map_or(chars.len(), |len| col + len);
+                if end == col {
+                    return (segments, col);
+                }
+                segments.push(chars[col..end].iter().collect());
+                end
+            }
+        };
+        let next = skip_whitespace(chars, segment_end);
+        if chars.get(next) == Some(&'.') {
+            col = skip_whitespace(chars, next + 1);
+        } else {
+            return (segments, segment_end);
+        }
+    }
+}
+
+fn skip_whitespace(chars: &[char], col: usize) -> usize {
+    let mut col = col;
+    while col < chars.len() && chars[col].is_whitespace() {
+        col += 1;
+    }
+    col
+}
+
+fn dependency_kind(section: &str) -> Option<DependencyKind> {
+    match section {
+        "dependencies" => Some(DependencyKind::Normal),
+        "dev-dependencies" | "dev_dependencies" => Some(DependencyKind::Development),
+        "build-dependencies" | "build_dependencies" => Some(DependencyKind::Build),
+        _ => None,
+    }
+}
+
+fn target_section(section: &str) -> Option<TargetSection> {
+    match section {
+        "bin" => Some(TargetSection::Bin),
+        "example" => Some(TargetSection::Example),
+        "test" => Some(TargetSection::Test),
+        "bench" => Some(TargetSection::Bench),
+        _ => None,
+    }
+}
@@ -0,0 +1,359 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Checks that version requirements still work with their minimal versions.
+//!
+//! With `-Z minimal-versions`, Cargo resolves every requirement to the lowest version it allows
+//! rather than the highest. A workspace member that asks for a feature its dependency only added
+//! in a later version will then fail to build, even though normal resolution works. This module
+//! simulates that resolution for the direct dependencies of workspace members, using registry
+//! index data provided by an `IndexSource`.
+//!
+//! `guppy` does not perform any network access itself: sources backed by a registry index are
+//! provided by the caller.
+//!
+//! This module is experimental, and requires the `unstable` feature to be enabled.
+
+use crate::graph::{PackageGraph, PackageLink, PackageSource};
+use crate::report::{Report, ReportDocument};
+use crate::{Error, Version};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error;
+use std::fmt;
+
+/// A published version of a crate, as recorded in a registry index.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IndexEntry {
+    version: Version,
+    features: BTreeSet<String>,
+    yanked: bool,
+}
+
+impl IndexEntry {
+    /// Creates a new entry for this version, with no features.
+    pub fn new(version: Version) -> Self {
+        Self {
+            version,
+            features: BTreeSet::new(),
+            yanked: false,
+        }
+    }
+
+    /// Sets the features this version provides.
+    ///
+    /// Optional dependencies should be included, since they can be enabled as features.
+    pub fn with_features(mut self, features: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.features = features.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets whether this version has been yanked. Yanked versions are never selected.
+    pub fn with_yanked(mut self, yanked: bool) -> Self {
+        self.yanked = yanked;
+        self
+    }
+
+    /// Returns the version.
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// Returns the features this version provides, including optional dependencies.
+    pub fn features(&self) -> &BTreeSet<String> {
+        &self.features
+    }
+
+    /// Returns true if this version has been yanked.
+    pub fn is_yanked(&self) -> bool {
+        self.yanked
+    }
+}
+
+/// A source of registry index data.
+pub trait IndexSource {
+    /// Returns every published version of the crate with this name, in any order, or `None` if
+    /// this source has no information about it.
+    fn versions(
+        &self,
+        name: &str,
+    ) -> Result<Option<Vec<IndexEntry>>, Box<dyn error::Error + Send + Sync>>;
+}
+
+impl<S: IndexSource + ?Sized> IndexSource for &S {
+    fn versions(
+        &self,
+        name: &str,
+    ) -> Result<Option<Vec<IndexEntry>>, Box<dyn error::Error + Send + Sync>> {
+        (**self).versions(name)
+    }
+}
+
+/// A static map from crate names to versions, useful for pre-seeded or offline data.
+impl IndexSource for BTreeMap<String, Vec<IndexEntry>> {
+    fn versions(
+        &self,
+        name: &str,
+    ) -> Result<Option<Vec<IndexEntry>>, Box<dyn error::Error + Send + Sync>> {
+        Ok(self.get(name).cloned())
+    }
+}
+
+impl PackageGraph {
+    /// Simulates resolving the direct registry dependencies of workspace members to the minimal
+    /// versions their requirements allow, and reports requirements that would break.
+    ///
+    /// A requirement breaks if no published, non-yanked version matches it, or if its minimal
+    /// version doesn't provide every feature the workspace member enables on it, either in the
+    /// dependency declaration or through its own `[features]` table. Dependencies the index
+    /// source doesn't know about aren't checked.
+    ///
+    /// The dependencies and features of the minimal versions themselves aren't checked, since
+    /// they're outside the workspace's control.
+    ///
+    /// Requires the `unstable` feature to be enabled.
+    pub fn minimal_versions_check(
+        &self,
+        index: &dyn IndexSource,
+    ) -> Result<MinimalVersionsReport<'_>, Error> {
+        let mut checked = 0;
+        let mut issues = vec![];
+        let mut members: Vec<_> = self
+            .workspace()
+            .members()
+            .map(|(_, member)| member)
+            .collect();
+        members.sort_by_key(|member| member.id());
+
+        for member in members {
+            let mut links: Vec<_> = member
+                .direct_links()
+                .filter(|link| is_registry(link.to().source()))
+                .collect();
+            links.sort_by_key(|link| link.to().id());
+            for link in links {
+                let name = link.to().name();
+                let mut entries = match index
+                    .versions(name)
+                    .map_err(|err| Error::IndexSourceError(name.to_string(), err))?
+                {
+                    Some(entries) => entries,
+                    None => continue,
+                };
+                checked += 1;
+                entries.retain(|entry| !entry.yanked && link.version_req().matches(&entry.version));
+                entries.sort_by(|a, b| a.version.cmp(&b.version));
+
+                let required = required_features(link);
+                let minimal = match entries.first() {
+                    Some(minimal) => minimal,
+                    None => {
+                        issues.push(MinimalVersionIssue {
+                            link,
+                            minimal_version: None,
+                            missing_features: vec![],
+                            suggested_minimum: None,
+                        });
+                        continue;
+                    }
+                };
+                let missing_features: Vec<_> = required
+                    .iter()
+                    .filter(|feature| !minimal.features.contains(**feature))
+                    .map(|feature| feature.to_string())
+                    .collect();
+                if missing_features.is_empty() {
+                    continue;
+                }
+
+                let suggested_minimum = entries
+                    .iter()
+                    .find(|entry| {
+                        required
+                            .iter()
+                            .all(|feature| entry.features.contains(*feature))
+                    })
+                    .map(|entry| entry.version.clone());
+                issues.push(MinimalVersionIssue {
+                    link,
+                    minimal_version: Some(minimal.version.clone()),
+                    missing_features,
+                    suggested_minimum,
+                });
+            }
+        }
+
+        Ok(MinimalVersionsReport { checked, issues })
+    }
+}
+
+/// The result of `PackageGraph::minimal_versions_check`.
+///
+/// The `Display` implementation renders the issues in a human-readable format. Requires the
+/// `unstable` feature to be enabled.
+#[derive(Clone, Debug)]
+pub struct MinimalVersionsReport<'g> {
+    checked: usize,
+    issues: Vec<MinimalVersionIssue<'g>>,
+}
+
+impl<'g> MinimalVersionsReport<'g> {
+    /// Returns the number of requirements that were checked.
+    pub fn checked(&self) -> usize {
+        self.checked
+    }
+
+    /// Returns the requirements that would break, sorted by workspace member and dependency.
+    pub fn issues(&self) -> &[MinimalVersionIssue<'g>] {
+        &self.issues
+    }
+
+    /// Returns true if no requirements would break.
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl<'g> fmt::Display for MinimalVersionsReport<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            write!(
+                f,
+                "{} -> {} {}: ",
+                issue.link.from().name(),
+                issue.link.to().name(),
+                issue.link.version_req()
+            )?;
+            match &issue.minimal_version {
+                None => writeln!(f, "no published version matches")?,
+                Some(minimal) => {
+                    write!(
+                        f,
+                        "minimal version {} lacks features: {}",
+                        minimal,
+                        issue.missing_features.join(", ")
+                    )?;
+                    match &issue.suggested_minimum {
+                        Some(suggested) => writeln!(f, " (require at least {})", suggested)?,
+                        None => writeln!(f, " (no matching version has them)")?,
+                    }
+                }
+            }
+        }
+        writeln!(
+            f,
+            "{} requirements checked, {} would break with minimal versions",
+            self.checked,
+            self.issues.len()
+        )
+    }
+}
+
+impl<'g> Report for MinimalVersionsReport<'g> {
+    fn to_document(&self) -> ReportDocument {
+        let mut document = ReportDocument::new("Minimal versions");
+        let section = document.add_section(None);
+        for issue in &self.issues {
+            let problem = match &issue.minimal_version {
+                None => "no published version matches".to_string(),
+                Some(minimal) => {
+                    let suggestion = match &issue.suggested_minimum {
+                        Some(suggested) => format!("require at least {}", suggested),
+                        None => "no matching version has them".to_string(),
+                    };
+                    format!(
+                        "minimal version {} lacks features: {} ({})",
+                        minimal,
+                        issue.missing_features.join(", "),
+                        suggestion
+                    )
+                }
+            };
+            section.add_item(format!(
+                "{} -> {} {}: {}",
+                issue.link.from().name(),
+                issue.link.to().name(),
+                issue.link.version_req(),
+                problem
+            ));
+        }
+        document.set_summary(format!(
+            "{} requirements checked, {} would break with minimal versions",
+            self.checked,
+            self.issues.len()
+        ));
+        document
+    }
+}
+
+/// A requirement that would break when resolved to its minimal version.
+#[derive(Clone, Debug)]
+pub struct MinimalVersionIssue<'g> {
+    link: PackageLink<'g>,
+    minimal_version: Option<Version>,
+    missing_features: Vec<String>,
+    suggested_minimum: Option<Version>,
+}
+
+impl<'g> MinimalVersionIssue<'g> {
+    /// Returns the link from the workspace member to the dependency.
+    pub fn link(&self) -> PackageLink<'g> {
+        self.link
+    }
+
+    /// Returns the minimal version the requirement would resolve to, or `None` if no published,
+    /// non-yanked version matches it.
+    pub fn minimal_version(&self) -> Option<&Version> {
+        self.minimal_version.as_ref()
+    }
+
+    /// Returns the features the workspace member enables that the minimal version lacks, sorted.
+    pub fn missing_features(&self) -> &[String] {
+        &self.missing_features
+    }
+
+    /// Returns the lowest version matching the requirement that provides every feature the
+    /// workspace member enables, if any.
+    ///
+    /// Raising the lower bound of the requirement to this version fixes the issue.
+    pub fn suggested_minimum(&self) -> Option<&Version> {
+        self.suggested_minimum.as_ref()
+    }
+}
+
+// ---
+// Helper methods
+// ---
+
+fn is_registry(source: PackageSource<'_>) -> bool {
+    match source {
+        PackageSource::External(source) => source.starts_with("registry+"),
+        _ => false,
+    }
+}
+
+/// Returns the features the package on the `from` side of this link enables on the `to` side.
+fn required_features<'g>(link: PackageLink<'g>) -> BTreeSet<&'g str> {
+    let mut required: BTreeSet<_> = link
+        .normal()
+        .features()
+        .chain(link.build().features())
+        .chain(link.dev().features())
+        .collect();
+
+    // Features like "dep/feature" or "dep?/feature" in the package's own feature table.
+    for (_, values) in link.from().named_features_with_deps() {
+        for value in values {
+            let mut parts = value.splitn(2, '/');
+            let dep = parts.next().expect("splitn returns at least one part");
+            let feature = match parts.next() {
+                Some(feature) => feature,
+                None => continue,
+            };
+            let dep = dep.strip_suffix('?').unwrap_or(dep);
+            if dep == link.dep_name() {
+                required.insert(feature);
+            }
+        }
+    }
+    required
+}
@@ -10,35 +10,98 @@ use crate::PackageId;
 use petgraph::prelude::*;
 use std::fmt;
 
+mod anonymize;
+#[cfg(feature = "unstable")]
+pub mod batch;
+pub mod budget;
 mod build;
+mod build_scripts;
 mod build_targets;
 pub mod cargo;
 mod cycles;
+mod dev_weight;
+mod export;
+mod extensions;
 pub mod feature;
+mod feature_impact;
+mod graph_builder;
 mod graph_impl;
+mod graph_pair;
+#[cfg(feature = "unstable")]
+pub mod health;
+#[cfg(feature = "unstable")]
+pub mod history;
+#[cfg(feature = "unstable")]
+pub mod maintenance;
+mod manifest_spans;
+#[cfg(feature = "unstable")]
+pub mod minimal_versions;
+mod notice;
+pub mod ownership;
+mod path_owners;
+mod platform_coverage;
 #[cfg(feature = "proptest09")]
 mod proptest09;
+mod provenance;
 mod query;
 mod query_core;
+mod release_notes;
 mod resolve;
 mod resolve_core;
+mod restructure;
+mod target_closure;
+#[cfg(feature = "unstable")]
+pub mod taxonomy;
+mod transform;
+#[cfg(feature = "unstable")]
+pub mod unsafe_usage;
+#[cfg(feature = "unstable")]
+mod upgrade;
+mod version_comparison;
+mod version_reqs;
+mod visit;
+mod workspace_path;
 
 pub use crate::petgraph_support::dot::DotWrite;
+pub use build_scripts::*;
 pub use build_targets::*;
 pub use cycles::*;
+pub use dev_weight::*;
+pub use export::*;
+pub use extensions::*;
+pub use feature_impact::*;
+pub use graph_builder::*;
 pub use graph_impl::*;
+pub use graph_pair::*;
+pub use manifest_spans::*;
+pub use notice::*;
 use once_cell::sync::Lazy;
+pub use path_owners::*;
 use petgraph::graph::IndexType;
+pub use platform_coverage::*;
 #[cfg(feature = "proptest09")]
 pub use proptest09::*;
+pub use provenance::*;
 pub use query::*;
+pub use release_notes::*;
 pub use resolve::*;
+pub use restructure::*;
 use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+pub use target_closure::*;
+pub use transform::*;
+#[cfg(feature = "unstable")]
+pub use upgrade::*;
+pub use version_comparison::*;
+pub use version_reqs::*;
+pub use visit::*;
+pub use workspace_path::WorkspacePath;
 
 /// The direction in which to follow dependencies.
 ///
 /// Used by the `_directed` methods.
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
 #[cfg_attr(feature = "proptest09", derive(proptest_derive::Arbitrary))]
 pub enum DependencyDirection {
     /// Dependencies from this package to other packages.
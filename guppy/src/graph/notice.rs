@@ -0,0 +1,212 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Attribution notices for the packages linked into a binary.
+
+use crate::graph::{BuildTargetId, DependencyDirection, PackageMetadata, TargetClosure};
+use crate::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Prefixes of file names that are treated as license texts, compared case-insensitively.
+static LICENSE_FILE_PREFIXES: &[&str] = &["copying", "copyright", "licence", "license", "notice"];
+
+impl<'g> TargetClosure<'g> {
+    /// Generates an attribution notice for the third-party packages linked into these targets.
+    ///
+    /// Only packages built for the target platform are included: procedural macros and build
+    /// dependencies run on the host, and aren't distributed with the binary. Workspace members
+    /// are skipped as well.
+    ///
+    /// The dependencies of every binary in a package are the same, so to generate a notice for a
+    /// specific binary, use the closure for `TargetKind::Binaries` with the features the binary
+    /// is built with. Returns `None` if every target was skipped.
+    ///
+    /// License texts are read from each package's directory, which for registry dependencies is
+    /// in the local registry cache or the vendored sources. Packages whose sources aren't on disk
+    /// are listed without license texts.
+    pub fn notice(&self) -> Result<Option<Notice<'g>>, Error> {
+        let cargo_set = match self.cargo_set() {
+            Some(cargo_set) => cargo_set,
+            None => return Ok(None),
+        };
+
+        let mut entries = cargo_set
+            .target_features()
+            .to_package_set()
+            .packages(DependencyDirection::Forward)
+            .filter(|package| !package.in_workspace())
+            .map(NoticeEntry::new)
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by(|a, b| {
+            (a.package.name(), a.package.version()).cmp(&(b.package.name(), b.package.version()))
+        });
+
+        Ok(Some(Notice {
+            package: self.package(),
+            targets: self.targets().to_vec(),
+            entries,
+        }))
+    }
+}
+
+/// An attribution notice for the third-party packages linked into a set of build targets.
+///
+/// Returned by `TargetClosure::notice`. The `Display` implementation renders the notice as plain
+/// text, suitable for a `NOTICE` file to ship alongside the binary.
+#[derive(Clone, Debug)]
+pub struct Notice<'g> {
+    package: PackageMetadata<'g>,
+    targets: Vec<BuildTargetId<'g>>,
+    entries: Vec<NoticeEntry<'g>>,
+}
+
+impl<'g> Notice<'g> {
+    /// Returns the package containing the targets this notice is for.
+    pub fn package(&self) -> PackageMetadata<'g> {
+        self.package
+    }
+
+    /// Returns the targets this notice is for, sorted.
+    pub fn targets(&self) -> &[BuildTargetId<'g>] {
+        &self.targets
+    }
+
+    /// Returns an entry for each third-party package, sorted by name and version.
+    pub fn entries(&self) -> &[NoticeEntry<'g>] {
+        &self.entries
+    }
+
+    /// Returns the entries for packages that no license texts were found for.
+    ///
+    /// These may need to be attributed manually.
+    pub fn missing_license_texts<'a>(&'a self) -> impl Iterator<Item = &'a NoticeEntry<'g>> + 'a {
+        self.entries
+            .iter()
+            .filter(|entry| entry.license_texts.is_empty())
+    }
+}
+
+impl<'g> fmt::Display for Notice<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {} includes the following third-party packages.",
+            self.package.name(),
+            self.package.version()
+        )?;
+        for entry in &self.entries {
+            let package = entry.package;
+            writeln!(f)?;
+            writeln!(f, "{}", "=".repeat(80))?;
+            writeln!(f, "{} {}", package.name(), package.version())?;
+            if !package.authors().is_empty() {
+                writeln!(f, "Authors: {}", package.authors().join(", "))?;
+            }
+            writeln!(f, "License: {}", package.license().unwrap_or("unknown"))?;
+            if let Some(repository) = package.repository() {
+                writeln!(f, "Repository: {}", repository)?;
+            }
+            for license_text in &entry.license_texts {
+                writeln!(f)?;
+                writeln!(f, "--- {} ---", license_text.path.display())?;
+                write!(f, "{}", license_text.text)?;
+                if !license_text.text.ends_with('\n') {
+                    writeln!(f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A third-party package in a `Notice`, along with the license texts found for it.
+#[derive(Clone, Debug)]
+pub struct NoticeEntry<'g> {
+    package: PackageMetadata<'g>,
+    license_texts: Vec<LicenseText>,
+}
+
+impl<'g> NoticeEntry<'g> {
+    fn new(package: PackageMetadata<'g>) -> Result<Self, Error> {
+        let dir = match package.manifest_path().parent() {
+            Some(dir) if dir.is_dir() => dir,
+            _ => {
+                return Ok(Self {
+                    package,
+                    license_texts: vec![],
+                })
+            }
+        };
+
+        let paths = match package.license_file() {
+            Some(license_file) => vec![license_file.to_path_buf()],
+            None => find_license_files(dir)
+                .map_err(|err| Error::LicenseFileError(package.id().clone(), dir.into(), err))?,
+        };
+        let license_texts = paths
+            .into_iter()
+            .map(|path| {
+                let full_path = dir.join(&path);
+                let text = fs::read_to_string(&full_path)
+                    .map_err(|err| Error::LicenseFileError(package.id().clone(), full_path, err))?;
+                Ok(LicenseText { path, text })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self {
+            package,
+            license_texts,
+        })
+    }
+
+    /// Returns the package this entry is for.
+    pub fn package(&self) -> PackageMetadata<'g> {
+        self.package
+    }
+
+    /// Returns the license texts found for this package.
+    ///
+    /// If the package specifies `license-file`, this is that file. Otherwise, it's every file in
+    /// the package's root directory with a name like `LICENSE`, `LICENSE-MIT`, `COPYING` or
+    /// `NOTICE`, sorted by name.
+    pub fn license_texts(&self) -> &[LicenseText] {
+        &self.license_texts
+    }
+}
+
+/// The contents of a license file in a package.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LicenseText {
+    path: PathBuf,
+    text: String,
+}
+
+impl LicenseText {
+    /// Returns the path to the license file, relative to the package's directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the contents of the license file.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+fn find_license_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_lowercase();
+        if LICENSE_FILE_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+            && entry.file_type()?.is_file()
+        {
+            paths.push(PathBuf::from(entry.file_name()));
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
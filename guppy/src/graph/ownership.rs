@@ -0,0 +1,533 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Team ownership of workspace members.
+//!
+//! Large workspaces are often maintained by several teams. An ownership file maps workspace
+//! members to the teams that own them, so that reports can be broken down or filtered by team.
+//! Two formats are supported.
+//!
+//! TOML files, with the extension `.toml`, list the members and workspace paths each team owns:
+//!
+//! ```toml
+//! [teams.platform]
+//! members = ["guppy", "target-spec"]
+//!
+//! [teams.tools]
+//! paths = ["tools", "cargo-guppy"]
+//! ```
+//!
+//! A path owns every workspace member at or under it. Members owned through more than one entry
+//! are owned by all the teams that list them.
+//!
+//! Any other file is read in the format of GitHub's `CODEOWNERS` files: each line is a workspace
+//! path followed by the teams that own it. As with `CODEOWNERS`, the last matching line wins, and
+//! a path followed by no teams makes the members under it unowned.
+//!
+//! ```text
+//! # Blank lines and comments are ignored.
+//! *           @org/platform
+//! /tools/     @org/tools @org/platform
+//! ```
+//!
+//! `*` matches every member, and leading and trailing slashes are ignored. Other glob patterns
+//! aren't supported.
+//!
+//! `PackageGraph::team_ownership` resolves an `Ownership` against the members of a workspace.
+//! Reports whose items belong to workspace members can then be broken down by team through
+//! `TeamOwnership::breakdown`.
+
+use crate::graph::{PackageGraph, PackageMetadata};
+use crate::report::{Report, ReportDocument};
+use crate::{Error, FileSource, PackageId, RealFileSource};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A parsed ownership file, mapping workspace members to teams.
+///
+/// See the module-level documentation for the supported formats.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Ownership {
+    path: PathBuf,
+    teams: BTreeSet<String>,
+    rules: Vec<OwnershipRule>,
+    // True for CODEOWNERS files, where only the last matching rule applies. Otherwise every
+    // matching rule applies.
+    last_match_wins: bool,
+}
+
+impl Ownership {
+    /// Parses the contents of an ownership file found at `path`.
+    ///
+    /// The format is chosen based on the extension of `path`: TOML for `.toml` files, and the
+    /// `CODEOWNERS` format otherwise.
+    pub fn parse(path: impl Into<PathBuf>, contents: &str) -> Result<Self, Error> {
+        let path = path.into();
+        if path.extension().and_then(|extension| extension.to_str()) == Some("toml") {
+            Self::parse_toml(path, contents)
+        } else {
+            Self::parse_codeowners(path, contents)
+        }
+    }
+
+    /// Reads and parses the ownership file at `path`.
+    pub fn from_path(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        Self::from_path_with_files(path, &RealFileSource)
+    }
+
+    /// Reads and parses the ownership file at `path` through this `FileSource`.
+    pub fn from_path_with_files(
+        path: impl Into<PathBuf>,
+        files: &dyn FileSource,
+    ) -> Result<Self, Error> {
+        let path = path.into();
+        let contents = files
+            .read_to_string(&path)
+            .map_err(|err| Error::OwnershipFileError(path.clone(), Box::new(err)))?;
+        Self::parse(path, &contents)
+    }
+
+    /// Returns the path this file was read from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the names of every team mentioned in this file, sorted by name.
+    pub fn teams(&self) -> impl Iterator<Item = &str> {
+        self.teams.iter().map(|team| team.as_str())
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn parse_toml(path: PathBuf, contents: &str) -> Result<Self, Error> {
+        let file: OwnershipToml = toml::from_str(contents)
+            .map_err(|err| Error::OwnershipFileError(path.clone(), Box::new(err)))?;
+        let teams = file.teams.keys().cloned().collect();
+        let mut rules = vec![];
+        for (team, entry) in file.teams {
+            for name in entry.members {
+                rules.push(OwnershipRule {
+                    matcher: Matcher::Name(name),
+                    teams: vec![team.clone()],
+                });
+            }
+            for member_path in entry.paths {
+                let matcher = Matcher::path(&member_path.to_string_lossy());
+                rules.push(OwnershipRule {
+                    matcher,
+                    teams: vec![team.clone()],
+                });
+            }
+        }
+        Ok(Self {
+            path,
+            teams,
+            rules,
+            last_match_wins: false,
+        })
+    }
+
+    fn parse_codeowners(path: PathBuf, contents: &str) -> Result<Self, Error> {
+        let mut rules = vec![];
+        for (line_number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let pattern = parts.next().expect("line is non-empty");
+            if pattern != "*" && pattern.contains(&['*', '?', '['][..]) {
+                return Err(Error::OwnershipFileError(
+                    path,
+                    format!(
+                        "line {}: unsupported pattern '{}' (only paths and '*' are supported)",
+                        line_number + 1,
+                        pattern
+                    )
+                    .into(),
+                ));
+            }
+            rules.push(OwnershipRule {
+                matcher: Matcher::path(pattern),
+                teams: parts.map(|team| team.to_string()).collect(),
+            });
+        }
+        let teams = rules
+            .iter()
+            .flat_map(|rule: &OwnershipRule| &rule.teams)
+            .cloned()
+            .collect();
+        Ok(Self {
+            path,
+            teams,
+            rules,
+            last_match_wins: true,
+        })
+    }
+}
+
+impl PackageGraph {
+    /// Resolves this ownership file against the members of this workspace.
+    ///
+    /// Returns an error if the file lists a member by a name that isn't in the workspace.
+    pub fn team_ownership(&self, ownership: &Ownership) -> Result<TeamOwnership<'_>, Error> {
+        TeamOwnership::new(self, ownership)
+    }
+}
+
+/// The teams owning each workspace member, as described by an `Ownership` file.
+///
+/// Constructed through `PackageGraph::team_ownership`. The `Display` implementation lists each
+/// team along with the members it owns.
+#[derive(Clone, Debug)]
+pub struct TeamOwnership<'g> {
+    // Every team mentioned in the ownership file, including teams that don't own any members.
+    teams: BTreeMap<String, Vec<PackageMetadata<'g>>>,
+    member_teams: HashMap<&'g PackageId, Vec<String>>,
+    unowned: Vec<PackageMetadata<'g>>,
+}
+
+impl<'g> TeamOwnership<'g> {
+    /// Resolves this ownership file against the members of this workspace.
+    pub fn new(graph: &'g PackageGraph, ownership: &Ownership) -> Result<Self, Error> {
+        let workspace = graph.workspace();
+        for rule in &ownership.rules {
+            if let Matcher::Name(name) = &rule.matcher {
+                if workspace.member_by_name(name).is_none() {
+                    return Err(Error::UnknownWorkspaceName(name.clone()));
+                }
+            }
+        }
+
+        let mut teams: BTreeMap<_, Vec<_>> = ownership
+            .teams()
+            .map(|team| (team.to_string(), vec![]))
+            .collect();
+        let mut member_teams = HashMap::new();
+        let mut unowned = vec![];
+        for (_, member) in workspace.members() {
+            let mut matching = ownership
+                .rules
+                .iter()
+                .filter(|rule| rule.matcher.matches(member));
+            let owners: BTreeSet<&String> = if ownership.last_match_wins {
+                matching
+                    .next_back()
+                    .map(|rule| rule.teams.iter().collect())
+                    .unwrap_or_default()
+            } else {
+                matching.flat_map(|rule| &rule.teams).collect()
+            };
+
+            if owners.is_empty() {
+                unowned.push(member);
+                continue;
+            }
+            for team in &owners {
+                teams
+                    .get_mut(*team)
+                    .expect("teams contains every team in the file")
+                    .push(member);
+            }
+            member_teams.insert(member.id(), owners.into_iter().cloned().collect());
+        }
+
+        Ok(Self {
+            teams,
+            member_teams,
+            unowned,
+        })
+    }
+
+    /// Returns the names of every team in the ownership file, sorted by name.
+    pub fn teams<'a>(&'a self) -> impl Iterator<Item = &'a str> + 'a {
+        self.teams.keys().map(|team| team.as_str())
+    }
+
+    /// Returns the workspace members owned by this team, ordered by workspace path.
+    ///
+    /// Returns `None` if the ownership file doesn't mention this team.
+    pub fn members(&self, team: &str) -> Option<&[PackageMetadata<'g>]> {
+        self.teams.get(team).map(|members| members.as_slice())
+    }
+
+    /// Returns the teams owning this package, sorted by name.
+    ///
+    /// This is empty if the package is unowned or isn't a workspace member.
+    pub fn teams_for(&self, package_id: &PackageId) -> &[String] {
+        self.member_teams
+            .get(package_id)
+            .map_or(&[], |teams| teams.as_slice())
+    }
+
+    /// Returns true if this team owns this package.
+    pub fn owns(&self, team: &str, package_id: &PackageId) -> bool {
+        self.teams_for(package_id).iter().any(|owner| owner == team)
+    }
+
+    /// Returns the workspace members that no team owns, ordered by workspace path.
+    pub fn unowned(&self) -> &[PackageMetadata<'g>] {
+        &self.unowned
+    }
+
+    /// Groups the items of a report by the teams owning the workspace members they belong to.
+    ///
+    /// Each item is listed under every team that owns its member, or as unowned. Items that are
+    /// listed more than once for the same team, for example because several members of the team
+    /// depend on the same package, are only included once.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use guppy::CargoMetadata;
+    /// use guppy::graph::ownership::Ownership;
+    ///
+    /// let metadata = CargoMetadata::parse_json(include_str!("../../../fixtures/small/metadata1.json")).unwrap();
+    /// let package_graph = metadata.build_graph().unwrap();
+    ///
+    /// let ownership = Ownership::parse("CODEOWNERS", "* @org/core\n").unwrap();
+    /// let teams = package_graph.team_ownership(&ownership).unwrap();
+    ///
+    /// let items = package_graph
+    ///     .workspace()
+    ///     .members()
+    ///     .map(|(_, member)| (member, format!("{} {}", member.name(), member.version())));
+    /// let breakdown = teams.breakdown("Versions", items);
+    /// assert_eq!(breakdown.items("@org/core"), Some(&["testcrate 0.1.0".to_string()][..]));
+    /// ```
+    pub fn breakdown<T: fmt::Display>(
+        &self,
+        title: impl Into<String>,
+        items: impl IntoIterator<Item = (PackageMetadata<'g>, T)>,
+    ) -> TeamBreakdown {
+        let mut teams: BTreeMap<String, Vec<String>> = self
+            .teams
+            .keys()
+            .map(|team| (team.clone(), vec![]))
+            .collect();
+        let mut unowned = vec![];
+        for (member, item) in items {
+            let item = item.to_string();
+            match self.member_teams.get(member.id()) {
+                Some(owners) => {
+                    for team in owners {
+                        let team_items = teams.get_mut(team).expect("team is known");
+                        if !team_items.contains(&item) {
+                            team_items.push(item.clone());
+                        }
+                    }
+                }
+                None => {
+                    if !unowned.contains(&item) {
+                        unowned.push(item);
+                    }
+                }
+            }
+        }
+        TeamBreakdown {
+            title: title.into(),
+            teams,
+            unowned: Some(unowned),
+        }
+    }
+}
+
+impl<'g> fmt::Display for TeamOwnership<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (team, members) in &self.teams {
+            writeln!(f, "{}: {} members", team, members.len())?;
+            for member in members {
+                writeln!(f, "    {}", member.name())?;
+            }
+        }
+        writeln!(f, "unowned: {} members", self.unowned.len())?;
+        for member in &self.unowned {
+            writeln!(f, "    {}", member.name())?;
+        }
+        Ok(())
+    }
+}
+
+impl<'g> Report for TeamOwnership<'g> {
+    fn to_document(&self) -> ReportDocument {
+        let mut document = ReportDocument::new("Team ownership");
+        for (team, members) in &self.teams {
+            let section =
+                document.add_section(Some(format!("{}: {} members", team, members.len())));
+            for member in members {
+                section.add_item(member.name());
+            }
+        }
+        let section =
+            document.add_section(Some(format!("unowned: {} members", self.unowned.len())));
+        for member in &self.unowned {
+            section.add_item(member.name());
+        }
+        document.set_summary(format!(
+            "{} teams, {} unowned members",
+            self.teams.len(),
+            self.unowned.len()
+        ));
+        document
+    }
+}
+
+/// The items of a report, grouped by the teams owning the workspace members they belong to.
+///
+/// Returned by `TeamOwnership::breakdown`. The `Display` implementation lists the items under each
+/// team, including teams without any items.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TeamBreakdown {
+    title: String,
+    teams: BTreeMap<String, Vec<String>>,
+    // None once the breakdown has been filtered to a single team.
+    unowned: Option<Vec<String>>,
+}
+
+impl TeamBreakdown {
+    /// Returns the title of the report this is a breakdown of.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Returns the teams in this breakdown, sorted by name.
+    pub fn teams<'a>(&'a self) -> impl Iterator<Item = &'a str> + 'a {
+        self.teams.keys().map(|team| team.as_str())
+    }
+
+    /// Returns the items for this team, in the order they were passed in.
+    ///
+    /// Returns `None` if this team isn't part of the breakdown.
+    pub fn items(&self, team: &str) -> Option<&[String]> {
+        self.teams.get(team).map(|items| items.as_slice())
+    }
+
+    /// Returns the items belonging to unowned members, in the order they were passed in.
+    ///
+    /// This is empty if the breakdown was filtered to a single team.
+    pub fn unowned(&self) -> &[String] {
+        self.unowned.as_deref().unwrap_or(&[])
+    }
+
+    /// Returns the total number of items across all teams and unowned members.
+    ///
+    /// Items listed under more than one team are counted once for each.
+    pub fn item_count(&self) -> usize {
+        self.teams.values().map(|items| items.len()).sum::<usize>() + self.unowned().len()
+    }
+
+    /// Filters this breakdown down to the items for a single team.
+    ///
+    /// Returns `None` if this team isn't part of the breakdown.
+    pub fn filter_team(mut self, team: &str) -> Option<Self> {
+        let items = self.teams.remove(team)?;
+        self.teams = BTreeMap::new();
+        self.teams.insert(team.to_string(), items);
+        self.unowned = None;
+        Some(self)
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn sections(&self) -> impl Iterator<Item = (&str, &[String])> {
+        let unowned = self
+            .unowned
+            .as_ref()
+            .map(|items| ("unowned", items.as_slice()));
+        self.teams
+            .iter()
+            .map(|(team, items)| (team.as_str(), items.as_slice()))
+            .chain(unowned)
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{}: {} items across {} teams",
+            self.title,
+            self.item_count(),
+            self.teams.len()
+        )
+    }
+}
+
+impl fmt::Display for TeamBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (team, items) in self.sections() {
+            writeln!(f, "{}: {} items", team, items.len())?;
+            for item in items {
+                writeln!(f, "    {}", item)?;
+            }
+        }
+        writeln!(f, "{}", self.summary())
+    }
+}
+
+impl Report for TeamBreakdown {
+    fn to_document(&self) -> ReportDocument {
+        let mut document = ReportDocument::new(format!("{} by team", self.title));
+        for (team, items) in self.sections() {
+            let section = document.add_section(Some(format!("{}: {} items", team, items.len())));
+            for item in items {
+                section.add_item(item.as_str());
+            }
+        }
+        document.set_summary(self.summary());
+        document
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct OwnershipRule {
+    matcher: Matcher,
+    teams: Vec<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Matcher {
+    All,
+    Name(String),
+    // Relative to the workspace root.
+    Path(PathBuf),
+}
+
+impl Matcher {
+    fn path(pattern: &str) -> Self {
+        let pattern = pattern.trim_matches('/');
+        if pattern.is_empty() || pattern == "*" {
+            Matcher::All
+        } else {
+            Matcher::Path(PathBuf::from(pattern))
+        }
+    }
+
+    fn matches(&self, member: PackageMetadata<'_>) -> bool {
+        match self {
+            Matcher::All => true,
+            Matcher::Name(name) => member.name() == name,
+            Matcher::Path(path) => match member.workspace_path() {
+                Some(member_path) => member_path.starts_with(path),
+                None => false,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OwnershipToml {
+    #[serde(default)]
+    teams: BTreeMap<String, TeamToml>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TeamToml {
+    members: Vec<String>,
+    paths: Vec<PathBuf>,
+}
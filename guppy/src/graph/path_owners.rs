@@ -0,0 +1,373 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::workspace_path::normalize;
+use crate::graph::{BuildTargetId, PackageGraph, PackageMetadata};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+impl PackageGraph {
+    /// Builds a map from file paths to the workspace members that own them.
+    pub fn path_owners(&self) -> PathOwners<'_> {
+        PathOwners::new(self)
+    }
+}
+
+/// A map from file paths to the workspace members that own them.
+///
+/// A path is owned by a workspace member if:
+/// * it is the source file of one of the member's build targets,
+/// * it is within the member's directory, or
+/// * it is within the directory of a build target whose source file is outside the member's
+///   directory, e.g. one specified with `path = "../common/lib.rs"`.
+///
+/// If directories are nested, the innermost one wins, similar to how Cargo doesn't include nested
+/// packages in the outer one.
+///
+/// Whether a path is part of the package as published, as determined by the `include` and
+/// `exclude` keys in the member's manifest, is reported through `PathOwner::is_packaged`.
+///
+/// Constructed through `PackageGraph::path_owners`.
+#[derive(Clone, Debug)]
+pub struct PathOwners<'g> {
+    graph: &'g PackageGraph,
+    // These paths are absolute and normalized.
+    target_files: BTreeMap<PathBuf, (PackageMetadata<'g>, BuildTargetId<'g>)>,
+    dirs: BTreeMap<PathBuf, DirOwner<'g>>,
+    // Packaging rules for each member, keyed by its package directory.
+    rules: BTreeMap<PathBuf, PackageRules>,
+}
+
+#[derive(Clone, Debug)]
+struct DirOwner<'g> {
+    package: PackageMetadata<'g>,
+    // None for the package directory itself.
+    target: Option<BuildTargetId<'g>>,
+}
+
+impl<'g> PathOwners<'g> {
+    /// Creates a new `PathOwners` for the workspace members in this graph.
+    pub fn new(graph: &'g PackageGraph) -> Self {
+        let mut target_files = BTreeMap::new();
+        let mut dirs = BTreeMap::new();
+        let mut rules = BTreeMap::new();
+
+        for (_, package) in graph.workspace().members() {
+            let package_dir = normalize(
+                package
+                    .manifest_path()
+                    .parent()
+                    .expect("manifest paths have a parent"),
+            );
+
+            for build_target in package.build_targets() {
+                let path = normalize(build_target.path());
+                if let Some(target_dir) = path.parent() {
+                    if !target_dir.starts_with(&package_dir) {
+                        dirs.entry(target_dir.to_path_buf())
+                            .or_insert_with(|| DirOwner {
+                                package,
+                                target: Some(build_target.id()),
+                            });
+                    }
+                }
+                target_files.insert(path, (package, build_target.id()));
+            }
+
+            rules.insert(
+                package_dir.clone(),
+                PackageRules::new(package.include(), package.exclude()),
+            );
+            // Package directories take precedence over target directories.
+            dirs.insert(
+                package_dir,
+                DirOwner {
+                    package,
+                    target: None,
+                },
+            );
+        }
+
+        Self {
+            graph,
+            target_files,
+            dirs,
+            rules,
+        }
+    }
+
+    /// Returns the `PackageGraph` this map was built from.
+    pub fn graph(&self) -> &'g PackageGraph {
+        self.graph
+    }
+
+    /// Returns the workspace member that owns this path, or `None` if no member owns it.
+    ///
+    /// The path can either be absolute or relative to the workspace root. It doesn't need to
+    /// exist on disk.
+    pub fn owner(&self, path: impl AsRef<Path>) -> Option<PathOwner<'g>> {
+        let path = normalize(&self.graph.workspace().root().join(path.as_ref()));
+
+        let (package, kind) = match self.target_files.get(&path) {
+            Some((package, target)) => (*package, PathOwnerKind::BuildTarget(*target)),
+            None => {
+                let dir_owner = path.ancestors().find_map(|dir| self.dirs.get(dir))?;
+                let kind = match dir_owner.target {
+                    Some(target) => PathOwnerKind::TargetDir(target),
+                    None => PathOwnerKind::PackageDir,
+                };
+                (dir_owner.package, kind)
+            }
+        };
+
+        let package_dir = normalize(
+            package
+                .manifest_path()
+                .parent()
+                .expect("manifest paths have a parent"),
+        );
+        let packaged = match (
+            path.strip_prefix(&package_dir),
+            self.rules.get(&package_dir),
+        ) {
+            (Ok(rel_path), Some(rules)) => rules.is_included(rel_path),
+            _ => false,
+        };
+
+        Some(PathOwner {
+            package,
+            kind,
+            packaged,
+        })
+    }
+}
+
+/// The workspace member that owns a path, as returned by `PathOwners::owner`.
+#[derive(Copy, Clone, Debug)]
+pub struct PathOwner<'g> {
+    package: PackageMetadata<'g>,
+    kind: PathOwnerKind<'g>,
+    packaged: bool,
+}
+
+impl<'g> PathOwner<'g> {
+    /// Returns the workspace member that owns this path.
+    pub fn package(&self) -> PackageMetadata<'g> {
+        self.package
+    }
+
+    /// Returns the reason this path is owned by the package.
+    pub fn kind(&self) -> PathOwnerKind<'g> {
+        self.kind
+    }
+
+    /// Returns true if this path is part of the package as published, as determined by the
+    /// `include` and `exclude` keys in its manifest.
+    ///
+    /// Paths outside the package's directory are never part of the published package.
+    pub fn is_packaged(&self) -> bool {
+        self.packaged
+    }
+}
+
+/// The reason a path is owned by a package.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum PathOwnerKind<'g> {
+    /// The path is the source file of this build target.
+    BuildTarget(BuildTargetId<'g>),
+
+    /// The path is within the directory containing this build target's source file, which is
+    /// outside the package's directory.
+    TargetDir(BuildTargetId<'g>),
+
+    /// The path is within the package's directory.
+    PackageDir,
+}
+
+/// The `include` and `exclude` rules for a package.
+///
+/// Patterns follow `gitignore` conventions, as described in the Cargo reference.
+#[derive(Clone, Debug)]
+struct PackageRules {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl PackageRules {
+    fn new(include: &[String], exclude: &[String]) -> Self {
+        Self {
+            include: include
+                .iter()
+                .map(|pattern| Pattern::new(pattern))
+                .collect(),
+            exclude: exclude
+                .iter()
+                .map(|pattern| Pattern::new(pattern))
+                .collect(),
+        }
+    }
+
+    /// Returns true if this path, relative to the package directory, is part of the package.
+    fn is_included(&self, rel_path: &Path) -> bool {
+        let components: Vec<_> = rel_path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect();
+        if components.len() == 1 && components[0] == "Cargo.toml" {
+            // The manifest is always included.
+            return true;
+        }
+        if !self.include.is_empty() {
+            // exclude is ignored if include is specified.
+            Self::matches(&self.include, &components)
+        } else {
+            !Self::matches(&self.exclude, &components)
+        }
+    }
+
+    fn matches(patterns: &[Pattern], components: &[impl AsRef<str>]) -> bool {
+        // The last matching pattern wins.
+        let mut matched = false;
+        for pattern in patterns {
+            if pattern.matches(components) {
+                matched = !pattern.negated;
+            }
+        }
+        matched
+    }
+}
+
+/// A single `gitignore`-style pattern.
+#[derive(Clone, Debug)]
+struct Pattern {
+    glob: Vec<char>,
+    negated: bool,
+    // Whether the pattern only matches directories, i.e. it ends with a slash.
+    dir_only: bool,
+    // Whether the pattern is matched against the full path rather than individual components,
+    // i.e. it contains a slash other than at the end.
+    anchored: bool,
+}
+
+impl Pattern {
+    fn new(pattern: &str) -> Self {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        let (dir_only, pattern) = match pattern.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        let anchored = pattern.contains('/');
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+        Self {
+            glob: pattern.chars().collect(),
+            negated,
+            dir_only,
+            anchored,
+        }
+    }
+
+    /// Returns true if the pattern matches this path or any of its ancestors.
+    fn matches(&self, components: &[impl AsRef<str>]) -> bool {
+        // A pattern matching a directory matches everything under it.
+        let prefix_count = if self.dir_only {
+            components.len().saturating_sub(1)
+        } else {
+            components.len()
+        };
+        (1..=prefix_count).any(|len| {
+            let text: Vec<char> = if self.anchored {
+                let prefix: Vec<_> = components[..len].iter().map(|c| c.as_ref()).collect();
+                prefix.join("/").chars().collect()
+            } else {
+                components[len - 1].as_ref().chars().collect()
+            };
+            glob_match(&self.glob, &text)
+        })
+    }
+}
+
+/// Matches a glob pattern against text, with `/` as the path separator.
+///
+/// Supports `*`, `?`, `**` and character classes like `[a-z]` or `[!0-9]`.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            match rest.first() {
+                // A trailing ** matches everything.
+                None => true,
+                // **/ matches zero or more directories.
+                Some('/') => {
+                    let rest = &rest[1..];
+                    glob_match(rest, text)
+                        || text
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, c)| **c == '/')
+                            .any(|(idx, _)| glob_match(rest, &text[idx + 1..]))
+                }
+                // Otherwise, ** behaves like *.
+                Some(_) => glob_match(&pattern[1..], text),
+            }
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for idx in 0..=text.len() {
+                if glob_match(rest, &text[idx..]) {
+                    return true;
+                }
+                if text.get(idx) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => match text.first() {
+            Some(c) if *c != '/' => glob_match(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+        Some('[') => match (parse_class(&pattern[1..]), text.first()) {
+            (Some((matcher, len)), Some(c)) => {
+                *c != '/' && matcher(*c) && glob_match(&pattern[len + 1..], &text[1..])
+            }
+            (Some(_), None) => false,
+            // An unterminated class is treated as a literal '['.
+            (None, _) => text.first() == Some(&'[') && glob_match(&pattern[1..], &text[1..]),
+        },
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Parses a character class, not including the leading `[`. Returns a matcher for the class and
+/// the number of characters consumed, including the closing `]`.
+fn parse_class(pattern: &[char]) -> Option<(impl Fn(char) -> bool + '_, usize)> {
+    let (negated, start) = match pattern.first() {
+        Some('!') | Some('^') => (true, 1),
+        _ => (false, 0),
+    };
+    // A ']' right at the start is part of the class.
+    let end = start + 1 + pattern.get(start + 1..)?.iter().position(|c| *c == ']')?;
+    let class = &pattern[start..end];
+    let matcher = move |c: char| {
+        let mut idx = 0;
+        let mut found = false;
+        while idx < class.len() {
+            if idx + 2 < class.len() && class[idx + 1] == '-' {
+                found |= class[idx] <= c && c <= class[idx + 2];
+                idx += 3;
+            } else {
+                found |= class[idx] == c;
+                idx += 1;
+            }
+        }
+        found != negated
+    };
+    Some((matcher, end + 1))
+}
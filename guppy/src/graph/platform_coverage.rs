@@ -0,0 +1,305 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{EnabledTernary, PackageGraph, PackageLink, PlatformStatus};
+use crate::report::{Report, ReportDocument};
+use crate::{DependencyKind, Platform, TargetSpec};
+use std::fmt;
+
+impl PackageGraph {
+    /// Reports which of the given platforms each platform-specific dependency applies to.
+    ///
+    /// Every dependency edge declared only in `[target.'...'.dependencies]` sections (or their
+    /// build and dev equivalents) is included, once per kind of dependency. Edges that are also
+    /// declared unconditionally for a kind are left out, since they apply to every platform.
+    pub fn platform_coverage<'g, 'a>(
+        &'g self,
+        platforms: &'a [Platform<'a>],
+    ) -> PlatformCoverage<'g, 'a> {
+        let mut packages: Vec<_> = self.packages().collect();
+        packages.sort_by_key(|package| package.id());
+
+        let mut edges = vec![];
+        for package in packages {
+            let mut links: Vec<_> = package.direct_links().collect();
+            links.sort_by_key(|link| link.to().id());
+            for link in links {
+                for kind in &[
+                    DependencyKind::Normal,
+                    DependencyKind::Build,
+                    DependencyKind::Development,
+                ] {
+                    if let Some(edge) = ConditionalEdge::new(link, *kind, platforms) {
+                        edges.push(edge);
+                    }
+                }
+            }
+        }
+
+        PlatformCoverage { platforms, edges }
+    }
+}
+
+/// A report of which platforms the platform-specific dependencies in a graph apply to.
+///
+/// Returned by `PackageGraph::platform_coverage`. The `Display` implementation renders the report
+/// in a human-readable format.
+#[derive(Clone, Debug)]
+pub struct PlatformCoverage<'g, 'a> {
+    platforms: &'a [Platform<'a>],
+    edges: Vec<ConditionalEdge<'g>>,
+}
+
+impl<'g, 'a> PlatformCoverage<'g, 'a> {
+    /// Returns the platforms this report was computed for.
+    pub fn platforms(&self) -> &'a [Platform<'a>] {
+        self.platforms
+    }
+
+    /// Returns every platform-specific dependency edge, sorted by package ID and dependency kind.
+    pub fn edges(&self) -> &[ConditionalEdge<'g>] {
+        &self.edges
+    }
+
+    /// Iterates over edges that don't apply to any of the platforms.
+    ///
+    /// Edges whose status is unknown on some platform, for example because target features
+    /// aren't known, are not included.
+    pub fn uncovered<'b>(&'b self) -> impl Iterator<Item = &'b ConditionalEdge<'g>> + 'b {
+        self.edges.iter().filter(|edge| edge.is_uncovered())
+    }
+
+    /// Iterates over edges declared with several specs, no two of which apply to the same
+    /// platform.
+    ///
+    /// These are typically the same dependency declared separately for each platform, for
+    /// example once for `cfg(unix)` and once for `cfg(windows)`.
+    pub fn mutually_exclusive<'b>(&'b self) -> impl Iterator<Item = &'b ConditionalEdge<'g>> + 'b {
+        self.edges
+            .iter()
+            .filter(|edge| edge.is_mutually_exclusive())
+    }
+}
+
+impl<'g, 'a> fmt::Display for PlatformCoverage<'g, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "platforms: {}",
+            join(self.platforms.iter().map(|platform| platform.triple()))
+        )?;
+        for edge in &self.edges {
+            let (from, to) = edge.link.endpoints();
+            write!(
+                f,
+                "{} {} -> {} {} ({}): {}",
+                from.name(),
+                from.version(),
+                to.name(),
+                to.version(),
+                edge.kind,
+                join(edge.specs.iter().map(|spec| spec.as_str()))
+            )?;
+
+            let statuses = edge.statuses.iter().zip(self.platforms);
+            let enabled = statuses
+                .clone()
+                .filter(|(status, _)| **status == EnabledTernary::Enabled)
+                .map(|(_, platform)| platform.triple());
+            let unknown = statuses
+                .filter(|(status, _)| **status == EnabledTernary::Unknown)
+                .map(|(_, platform)| platform.triple());
+            write!(f, "\n    enabled on: [{}]", join(enabled))?;
+            let unknown = join(unknown);
+            if !unknown.is_empty() {
+                write!(f, ", unknown on: [{}]", unknown)?;
+            }
+            if edge.is_uncovered() {
+                write!(f, " (applies to no configured platforms)")?;
+            }
+            if edge.is_mutually_exclusive() {
+                write!(f, " (mutually exclusive specs)")?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(
+            f,
+            "{} platform-specific edges, {} uncovered, {} with mutually exclusive specs",
+            self.edges.len(),
+            self.uncovered().count(),
+            self.mutually_exclusive().count()
+        )
+    }
+}
+
+impl<'g, 'a> Report for PlatformCoverage<'g, 'a> {
+    fn to_document(&self) -> ReportDocument {
+        let mut document = ReportDocument::new("Platform coverage");
+        let section = document.add_section(Some(format!(
+            "platforms: {}",
+            join(self.platforms.iter().map(|platform| platform.triple()))
+        )));
+        for edge in &self.edges {
+            let (from, to) = edge.link.endpoints();
+            let item = section.add_item(format!(
+                "{} {} -> {} {} ({}): {}",
+                from.name(),
+                from.version(),
+                to.name(),
+                to.version(),
+                edge.kind,
+                join(edge.specs.iter().map(|spec| spec.as_str()))
+            ));
+
+            let statuses = edge.statuses.iter().zip(self.platforms);
+            let enabled = statuses
+                .clone()
+                .filter(|(status, _)| **status == EnabledTernary::Enabled)
+                .map(|(_, platform)| platform.triple());
+            let unknown = statuses
+                .filter(|(status, _)| **status == EnabledTernary::Unknown)
+                .map(|(_, platform)| platform.triple());
+            item.add_detail(format!("enabled on: [{}]", join(enabled)));
+            let unknown = join(unknown);
+            if !unknown.is_empty() {
+                item.add_detail(format!("unknown on: [{}]", unknown));
+            }
+            if edge.is_uncovered() {
+                item.add_detail("applies to no configured platforms");
+            }
+            if edge.is_mutually_exclusive() {
+                item.add_detail("mutually exclusive specs");
+            }
+        }
+        document.set_summary(format!(
+            "{} platform-specific edges, {} uncovered, {} with mutually exclusive specs",
+            self.edges.len(),
+            self.uncovered().count(),
+            self.mutually_exclusive().count()
+        ));
+        document
+    }
+}
+
+/// A platform-specific dependency edge, as part of a `PlatformCoverage` report.
+#[derive(Clone, Debug)]
+pub struct ConditionalEdge<'g> {
+    link: PackageLink<'g>,
+    kind: DependencyKind,
+    specs: Vec<&'g TargetSpec>,
+    // The status of the edge on each platform, in the same order as the platforms.
+    statuses: Vec<EnabledTernary>,
+    // For each spec, whether it matches or may match each platform.
+    spec_matches: Vec<Vec<bool>>,
+}
+
+impl<'g> ConditionalEdge<'g> {
+    fn new(
+        link: PackageLink<'g>,
+        kind: DependencyKind,
+        platforms: &[Platform<'_>],
+    ) -> Option<Self> {
+        let status = link.req_for_kind(kind).status();
+        let mut specs: Vec<&'g TargetSpec> = vec![];
+        for platform_status in &[status.required_status(), status.optional_status()] {
+            match platform_status {
+                PlatformStatus::Never => {}
+                PlatformStatus::Always => return None,
+                PlatformStatus::PlatformDependent { eval } => {
+                    for spec in eval.specs() {
+                        if specs
+                            .iter()
+                            .all(|existing| existing.as_str() != spec.as_str())
+                        {
+                            specs.push(spec);
+                        }
+                    }
+                }
+            }
+        }
+        if specs.is_empty() {
+            return None;
+        }
+
+        let statuses = platforms
+            .iter()
+            .map(|platform| status.enabled_on(platform))
+            .collect();
+        // spec_matches[spec][platform] is true if the spec matches or may match the platform.
+        let spec_matches = specs
+            .iter()
+            .map(|spec| {
+                let summary = spec.eval_all(platforms);
+                (0..summary.len())
+                    .map(|index| summary.result(index) != Some(false))
+                    .collect()
+            })
+            .collect();
+        Some(Self {
+            link,
+            kind,
+            specs,
+            statuses,
+            spec_matches,
+        })
+    }
+
+    /// Returns the link this edge is for.
+    pub fn link(&self) -> PackageLink<'g> {
+        self.link
+    }
+
+    /// Returns the kind of dependency this edge is for.
+    pub fn kind(&self) -> DependencyKind {
+        self.kind
+    }
+
+    /// Returns the distinct target specs this dependency is declared with, in the order they
+    /// were declared. The dependency applies to a platform if any of them match.
+    pub fn specs(&self) -> &[&'g TargetSpec] {
+        &self.specs
+    }
+
+    /// Returns the status of this edge on each platform, in the order the platforms were
+    /// provided.
+    pub fn statuses(&self) -> &[EnabledTernary] {
+        &self.statuses
+    }
+
+    /// Returns true if this edge is known not to apply to any of the platforms.
+    ///
+    /// Returns false if no platforms were provided.
+    pub fn is_uncovered(&self) -> bool {
+        !self.statuses.is_empty()
+            && self
+                .statuses
+                .iter()
+                .all(|status| *status == EnabledTernary::Disabled)
+    }
+
+    /// Returns true if this edge is declared with several specs that each apply to some of the
+    /// platforms, but no two of which apply to the same platform.
+    ///
+    /// Specs whose result is unknown on a platform are treated as possibly applying to it.
+    pub fn is_mutually_exclusive(&self) -> bool {
+        if self.specs.len() < 2 {
+            return false;
+        }
+        let each_applies = self
+            .spec_matches
+            .iter()
+            .all(|matches| matches.iter().any(|matched| *matched));
+        let disjoint = (0..self.statuses.len()).all(|platform_idx| {
+            self.spec_matches
+                .iter()
+                .filter(|matches| matches[platform_idx])
+                .count()
+                <= 1
+        });
+        each_applies && disjoint
+    }
+}
+
+fn join<'a>(strs: impl Iterator<Item = &'a str>) -> String {
+    strs.collect::<Vec<_>>().join(", ")
+}
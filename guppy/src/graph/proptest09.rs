@@ -1,13 +1,17 @@
 // Copyright (c) The cargo-guppy Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::graph::{PackageGraph, PackageLink, PackageQuery, PackageResolver, Workspace};
-use crate::PackageId;
+use crate::graph::{
+    BuilderDependency, PackageGraph, PackageGraphBuilder, PackageLink, PackageQuery,
+    PackageResolver, Workspace,
+};
+use crate::{DependencyKind, FeatureName, PackageId, PackageName};
 use fixedbitset::FixedBitSet;
 use petgraph::prelude::*;
 use petgraph::visit::VisitMap;
 use proptest::collection::vec;
 use proptest::prelude::*;
+use semver::Version;
 
 /// ## Helpers for property testing
 ///
@@ -159,3 +163,296 @@ pub(super) fn fixedbitset_strategy(len: usize) -> impl Strategy<Value = FixedBit
             .collect()
     })
 }
+
+/// ## Generating random graphs
+///
+/// The methods in this section generate entire `PackageGraph` instances, for property-testing
+/// analyses against graphs of arbitrary shape.
+impl PackageGraph {
+    /// Returns a `Strategy` that generates random, internally consistent `PackageGraph` instances.
+    ///
+    /// Generated graphs have a workspace with at least one member, third-party dependencies
+    /// (including multiple versions of the same package), named and optional features, and
+    /// platform-specific dependencies. They don't contain any dependency cycles.
+    ///
+    /// Requires the `proptest09` feature to be enabled.
+    pub fn prop09_strategy(params: Prop09GraphParams) -> impl Strategy<Value = PackageGraph> {
+        let workspace_len = 1..=params.max_workspace_members.max(1);
+        let third_party_len = 0..=params.max_third_party;
+        (workspace_len, third_party_len).prop_flat_map(move |(workspace_len, third_party_len)| {
+            let params = params.clone();
+            vec(package_strategy(&params), workspace_len + third_party_len)
+                .prop_map(move |packages| build_graph(&params, workspace_len, &packages))
+        })
+    }
+}
+
+/// Parameters for generating random `PackageGraph` instances.
+///
+/// Used by `PackageGraph::prop09_strategy`. Requires the `proptest09` feature to be enabled.
+#[derive(Clone, Debug)]
+pub struct Prop09GraphParams {
+    /// The maximum number of workspace members. At least one member is always generated.
+    pub max_workspace_members: usize,
+
+    /// The maximum number of third-party packages.
+    pub max_third_party: usize,
+
+    /// The maximum number of named features per package.
+    pub max_features: usize,
+
+    /// The maximum number of dependencies per package.
+    pub max_deps: usize,
+
+    /// The target specifications that platform-specific dependencies can be declared for, e.g.
+    /// `cfg(unix)`. If empty, no platform-specific dependencies are generated.
+    pub platform_specs: Vec<String>,
+}
+
+impl Default for Prop09GraphParams {
+    fn default() -> Self {
+        Self {
+            max_workspace_members: 8,
+            max_third_party: 24,
+            max_features: 4,
+            max_deps: 6,
+            platform_specs: vec![
+                "cfg(unix)".to_string(),
+                "cfg(windows)".to_string(),
+                "cfg(target_os = \"linux\")".to_string(),
+            ],
+        }
+    }
+}
+
+// ---
+// Graph generation
+// ---
+
+static WORKSPACE_ROOT: &str = "/fakeworkspace";
+
+/// Random choices for a single package. These are turned into a consistent graph by `build_graph`,
+/// which interprets indexes relative to the packages that can actually be chosen.
+#[derive(Clone, Debug)]
+struct RawPackage {
+    // For third-party packages, the earlier package to share a name with, if any.
+    shared_name: Option<prop::sample::Index>,
+    major: u64,
+    proc_macro: bool,
+    features: Vec<Vec<prop::sample::Index>>,
+    default_feature: bool,
+    deps: Vec<RawDep>,
+}
+
+#[derive(Clone, Debug)]
+struct RawDep {
+    to: prop::sample::Index,
+    // 0 = normal, 1 = build, 2 = dev.
+    kind: u8,
+    optional: bool,
+    default_features: bool,
+    features: Vec<prop::sample::Index>,
+    platform: Option<prop::sample::Index>,
+}
+
+fn package_strategy(params: &Prop09GraphParams) -> impl Strategy<Value = RawPackage> {
+    let dep = (
+        any::<prop::sample::Index>(),
+        0..3u8,
+        any::<bool>(),
+        any::<bool>(),
+        vec(any::<prop::sample::Index>(), 0..3),
+        prop::option::weighted(
+            if params.platform_specs.is_empty() {
+                0.0
+            } else {
+                0.25
+            },
+            any::<prop::sample::Index>(),
+        ),
+    )
+        .prop_map(
+            |(to, kind, optional, default_features, features, platform)| RawDep {
+                to,
+                kind,
+                optional,
+                default_features,
+                features,
+                platform,
+            },
+        );
+    (
+        prop::option::of(any::<prop::sample::Index>()),
+        0..3u64,
+        prop::bool::weighted(0.1),
+        vec(
+            vec(any::<prop::sample::Index>(), 0..3),
+            0..=params.max_features,
+        ),
+        any::<bool>(),
+        vec(dep, 0..=params.max_deps),
+    )
+        .prop_map(
+            |(shared_name, major, proc_macro, features, default_feature, deps)| RawPackage {
+                shared_name,
+                major,
+                proc_macro,
+                features,
+                default_feature,
+                deps,
+            },
+        )
+}
+
+/// Turns random choices into a `PackageGraph`.
+///
+/// Packages can only depend on packages that come after them, so the graph has no cycles. The
+/// first `workspace_len` packages are workspace members, which can depend on anything. Third-party
+/// packages can only depend on other third-party packages and can't have dev-dependencies.
+fn build_graph(
+    params: &Prop09GraphParams,
+    workspace_len: usize,
+    packages: &[RawPackage],
+) -> PackageGraph {
+    use std::collections::BTreeMap;
+
+    let mut builder = PackageGraphBuilder::new(WORKSPACE_ROOT);
+
+    // Add all packages first so that dependencies can refer to them.
+    let mut infos: Vec<(PackageName, PackageId)> = Vec::with_capacity(packages.len());
+    for (idx, package) in packages.iter().enumerate() {
+        let info = if idx < workspace_len {
+            let name = package_name(format!("ws-{}", idx));
+            let id = builder.add_workspace_member(name.clone(), Version::new(0, 1, 0));
+            (name, id)
+        } else {
+            // Third-party packages may share names with earlier ones, creating duplicates. The
+            // minor version is unique so that package IDs are unique.
+            let name = match package.shared_name {
+                Some(index) if idx > workspace_len => infos
+                    [workspace_len + index.index(idx - workspace_len)]
+                .0
+                .clone(),
+                _ => package_name(format!("dep-{}", idx)),
+            };
+            let id =
+                builder.add_third_party(name.clone(), Version::new(package.major, idx as u64, 0));
+            (name, id)
+        };
+        infos.push(info);
+    }
+
+    for (idx, package) in packages.iter().enumerate() {
+        let (_, id) = &infos[idx];
+        let in_workspace = idx < workspace_len;
+        // Third-party packages come after workspace members, so only workspace members can depend
+        // on workspace members.
+        let candidates = idx + 1..packages.len();
+
+        let mut resolved: BTreeMap<&str, &PackageId> = BTreeMap::new();
+        let mut seen = BTreeMap::new();
+        let mut optional_deps = vec![];
+        for dep in &package.deps {
+            if candidates.is_empty() {
+                break;
+            }
+            let to_idx = candidates.start + dep.to.index(candidates.len());
+            let (to_name, to_id) = &infos[to_idx];
+            let kind = match dep.kind {
+                2 if in_workspace => DependencyKind::Development,
+                1 => DependencyKind::Build,
+                _ => DependencyKind::Normal,
+            };
+            // Cargo doesn't allow the same name to be used twice within a section.
+            if seen.insert((to_name.clone(), kind.to_str()), ()).is_some() {
+                continue;
+            }
+            // A package can't depend on two versions of the same name in different sections
+            // unless the requirements tell them apart, which exact requirements do.
+            if matches!(resolved.get(to_name.as_str()), Some(resolved_id) if *resolved_id != to_id)
+            {
+                continue;
+            }
+            let optional = dep.optional && kind != DependencyKind::Development;
+            if optional {
+                optional_deps.push(to_name.to_string());
+            }
+            let feature_count = packages[to_idx].features.len();
+            let features: Vec<_> = if feature_count == 0 {
+                vec![]
+            } else {
+                let mut features: Vec<_> = dep
+                    .features
+                    .iter()
+                    .map(|index| feature_name(index.index(feature_count)))
+                    .collect();
+                features.sort();
+                features.dedup();
+                features
+            };
+
+            let mut dependency = BuilderDependency::new(kind)
+                .with_optional(optional)
+                .with_default_features(dep.default_features)
+                .with_features(features);
+            if let (Some(index), false) = (&dep.platform, params.platform_specs.is_empty()) {
+                dependency = dependency
+                    .with_target(&params.platform_specs[index.index(params.platform_specs.len())]);
+            }
+            builder
+                .add_dependency(id, to_id, dependency)
+                .expect("package IDs were added to the builder");
+            resolved.insert(to_name.as_str(), to_id);
+        }
+
+        // Named features can enable earlier features and optional dependencies.
+        for (feature_idx, refs) in package.features.iter().enumerate() {
+            let mut values: Vec<String> = refs
+                .iter()
+                .filter_map(|index| {
+                    let choices = feature_idx + optional_deps.len();
+                    if choices == 0 {
+                        return None;
+                    }
+                    let choice = index.index(choices);
+                    if choice < feature_idx {
+                        Some(format!("feature-{}", choice))
+                    } else {
+                        Some(optional_deps[choice - feature_idx].clone())
+                    }
+                })
+                .collect();
+            values.sort();
+            values.dedup();
+            builder
+                .add_feature(id, feature_name(feature_idx), values)
+                .expect("package ID was added to the builder");
+        }
+        if package.default_feature && !package.features.is_empty() {
+            builder
+                .add_feature(
+                    id,
+                    FeatureName::new("default").expect("valid name"),
+                    vec!["feature-0"],
+                )
+                .expect("package ID was added to the builder");
+        }
+        if package.proc_macro {
+            builder
+                .set_proc_macro(id, true)
+                .expect("package ID was added to the builder");
+        }
+    }
+
+    builder
+        .build()
+        .expect("generated packages should form a valid graph")
+}
+
+fn package_name(name: String) -> PackageName {
+    PackageName::new(name).expect("generated package names are valid")
+}
+
+fn feature_name(idx: usize) -> FeatureName {
+    FeatureName::new(format!("feature-{}", idx)).expect("generated feature names are valid")
+}
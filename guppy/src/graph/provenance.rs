@@ -0,0 +1,268 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Provenance for package sets.
+//!
+//! Reports built from a `PackageSet` can be serialized along with a `Provenance`, which records
+//! the graph the set was resolved against, how it was resolved, and which tool produced it. This
+//! lets consumers of a report check that it was produced from the inputs they expect.
+
+use crate::graph::{DependencyDirection, PackageGraph, PackageIx, PackageQuery, PackageSet};
+use petgraph::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+impl PackageGraph {
+    /// Returns a fingerprint of this graph, as a hex-encoded string.
+    ///
+    /// The fingerprint covers package IDs, named features and dependency links, including their
+    /// names, version requirements and kinds. It is stable across runs and platforms, but it is
+    /// not a cryptographic hash and shouldn't be relied on to detect tampering.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Fnv::new();
+        let mut packages: Vec<_> = self.packages().collect();
+        packages.sort_by_key(|package| package.id());
+        for package in packages {
+            hasher.write_str(package.id().repr());
+            let mut features: Vec<_> = package.named_features().collect();
+            features.sort_unstable();
+            for feature in features {
+                hasher.write_str(feature);
+            }
+
+            // Links between the same two packages are merged in the graph, so sorting by the
+            // target's ID is enough to make this deterministic.
+            let mut links: Vec<_> = package.direct_links().collect();
+            links.sort_by_key(|link| link.to().id());
+            for link in links {
+                hasher.write_str(link.to().id().repr());
+                hasher.write_str(link.dep_name());
+                hasher.write_str(&link.version_req().to_string());
+                for present in &[
+                    link.normal().is_present(),
+                    link.build().is_present(),
+                    link.dev().is_present(),
+                ] {
+                    hasher.write_str(if *present { "1" } else { "0" });
+                }
+            }
+        }
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+impl<'g> PackageSet<'g> {
+    /// Returns the provenance of this set, timestamped with the current time.
+    pub fn provenance(&self) -> Provenance {
+        Provenance {
+            graph_fingerprint: self.graph.fingerprint(),
+            origin: self.origin().clone(),
+            package_count: self.len(),
+            guppy_version: env!("CARGO_PKG_VERSION").to_string(),
+            tool: None,
+            timestamp: unix_secs(SystemTime::now()),
+        }
+    }
+}
+
+/// Where a `PackageSet` came from, and how it was produced.
+///
+/// A `Provenance` is serializable, so that it can be stored alongside reports built from the set.
+/// Returned by `PackageSet::provenance`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Provenance {
+    graph_fingerprint: String,
+    origin: SetOrigin,
+    package_count: usize,
+    guppy_version: String,
+    tool: Option<String>,
+    timestamp: u64,
+}
+
+impl Provenance {
+    /// Records the tool that produced the report, for example `cargo-guppy 0.1.0`.
+    pub fn with_tool(mut self, name: &str, version: &str) -> Self {
+        self.tool = Some(format!("{} {}", name, version));
+        self
+    }
+
+    /// Sets the timestamp to the given time.
+    ///
+    /// This is useful for reproducible output.
+    ///
+    /// Times before the Unix epoch are recorded as the epoch.
+    pub fn with_timestamp(mut self, time: SystemTime) -> Self {
+        self.timestamp = unix_secs(time);
+        self
+    }
+
+    /// Returns the fingerprint of the graph the set was resolved against.
+    ///
+    /// See `PackageGraph::fingerprint` for more.
+    pub fn graph_fingerprint(&self) -> &str {
+        &self.graph_fingerprint
+    }
+
+    /// Returns how the set was produced.
+    pub fn origin(&self) -> &SetOrigin {
+        &self.origin
+    }
+
+    /// Returns the number of packages in the set.
+    pub fn package_count(&self) -> usize {
+        self.package_count
+    }
+
+    /// Returns the version of guppy that resolved the set.
+    pub fn guppy_version(&self) -> &str {
+        &self.guppy_version
+    }
+
+    /// Returns the tool that produced the report, if recorded.
+    pub fn tool(&self) -> Option<&str> {
+        self.tool.as_deref()
+    }
+
+    /// Returns when the provenance was recorded, in seconds since the Unix epoch.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns true if the set was resolved against a graph identical to this one.
+    pub fn matches_graph(&self, graph: &PackageGraph) -> bool {
+        self.graph_fingerprint == graph.fingerprint()
+    }
+}
+
+/// How a `PackageSet` was produced.
+///
+/// Package IDs are stored as strings.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum SetOrigin {
+    /// Every package in the graph, from `PackageGraph::resolve_all`.
+    All,
+
+    /// The workspace members, from `PackageGraph::resolve_workspace`.
+    Workspace,
+
+    /// A specific list of packages, from `PackageGraph::resolve_ids` or
+    /// `PackageGraph::resolve_workspace_names`.
+    Packages {
+        /// The packages in the set, sorted.
+        ids: Vec<String>,
+    },
+
+    /// A query.
+    #[serde(rename_all = "kebab-case")]
+    Query {
+        /// The direction of the query.
+        direction: DependencyDirection,
+        /// The packages the query started from, sorted.
+        initials: Vec<String>,
+        /// True if the query was resolved with a custom resolver, which may have skipped some
+        /// links.
+        custom_resolver: bool,
+    },
+
+    /// Packages visited by `PackageGraph::visit`.
+    Visit,
+
+    /// The packages in a feature set, from `FeatureSet::to_package_set`.
+    FeatureSet,
+
+    /// A set operation on two other sets.
+    Combined {
+        /// The set operation.
+        operation: SetOperation,
+        /// The left-hand side of the operation.
+        left: Box<SetOrigin>,
+        /// The right-hand side of the operation.
+        right: Box<SetOrigin>,
+    },
+}
+
+impl SetOrigin {
+    pub(super) fn packages(
+        graph: &PackageGraph,
+        package_ixs: impl IntoIterator<Item = NodeIndex<PackageIx>>,
+    ) -> Self {
+        SetOrigin::Packages {
+            ids: sorted_ids(graph, package_ixs),
+        }
+    }
+
+    pub(super) fn query(query: &PackageQuery<'_>, custom_resolver: bool) -> Self {
+        SetOrigin::Query {
+            direction: query.direction(),
+            initials: sorted_ids(query.graph, query.params.initials().iter().copied()),
+            custom_resolver,
+        }
+    }
+
+    pub(super) fn combined(operation: SetOperation, left: &Self, right: &Self) -> Self {
+        SetOrigin::Combined {
+            operation,
+            left: Box::new(left.clone()),
+            right: Box::new(right.clone()),
+        }
+    }
+}
+
+/// A set operation, as part of a `SetOrigin`.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SetOperation {
+    /// `PackageSet::union`.
+    Union,
+    /// `PackageSet::intersection`.
+    Intersection,
+    /// `PackageSet::difference`.
+    Difference,
+    /// `PackageSet::symmetric_difference`.
+    SymmetricDifference,
+}
+
+// ---
+// Helper methods
+// ---
+
+fn sorted_ids(
+    graph: &PackageGraph,
+    package_ixs: impl IntoIterator<Item = NodeIndex<PackageIx>>,
+) -> Vec<String> {
+    let mut ids: Vec<_> = package_ixs
+        .into_iter()
+        .map(|package_ix| graph.dep_graph[package_ix].repr().to_string())
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// FNV-1a, which unlike the standard library's hashers is stable across Rust versions.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write_str(&mut self, s: &str) {
+        // Terminate each string so that ("ab", "c") and ("a", "bc") hash differently.
+        for byte in s.bytes().chain(std::iter::once(0xff)) {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,327 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Changelog-style summaries of dependency changes, for release notes.
+
+use crate::graph::{
+    DependencyDirection, GraphPair, PackageChangeKind, PackageGraph, PackageMetadata, PackageSet,
+};
+use crate::report::{Report, ReportDocument};
+use crate::{Error, PackageId};
+use std::collections::HashSet;
+use std::fmt;
+
+impl<'a> GraphPair<'a> {
+    /// Returns the changes to the dependencies of the workspace member with this name, suitable
+    /// for pasting into release notes.
+    ///
+    /// Dependencies are the third-party packages the member transitively depends on, without
+    /// following dev-only links: these are the packages that ship with the member. Workspace
+    /// members are skipped, but their dependencies are included.
+    ///
+    /// The member is looked up in the new graph, or in the old graph if it was removed. Returns an
+    /// error if it's in neither.
+    pub fn dependency_changelog(&self, name: &str) -> Result<DependencyChangelog<'a>, Error> {
+        let old_member = self.old_graph().workspace().member_by_name(name);
+        let new_member = self.new_graph().workspace().member_by_name(name);
+        let member = new_member
+            .or(old_member)
+            .ok_or_else(|| Error::UnknownWorkspaceName(name.to_string()))?;
+
+        let old_deps = MemberDeps::new(self.old_graph(), old_member)?;
+        let new_deps = MemberDeps::new(self.new_graph(), new_member)?;
+
+        let mut changes = vec![];
+        let mut matched_old = HashSet::new();
+        for new in new_deps.packages() {
+            let old = self
+                .new_to_old(new.id())
+                .filter(|old| old_deps.contains(old.id()));
+            if let Some(old) = old {
+                matched_old.insert(old.id());
+                if old.version() == new.version() {
+                    continue;
+                }
+            }
+            changes.push(DependencyChange {
+                old,
+                new: Some(new),
+                direct: new_deps.is_direct(new.id()),
+            });
+        }
+        for old in old_deps.packages() {
+            if !matched_old.contains(old.id()) {
+                changes.push(DependencyChange {
+                    old: Some(old),
+                    new: None,
+                    direct: old_deps.is_direct(old.id()),
+                });
+            }
+        }
+        changes.sort_by(|a, b| {
+            let a = a.package();
+            let b = b.package();
+            (a.name(), a.version()).cmp(&(b.name(), b.version()))
+        });
+
+        Ok(DependencyChangelog {
+            old_member,
+            new_member,
+            member,
+            changes,
+        })
+    }
+}
+
+/// The changes to a workspace member's dependencies between two graphs.
+///
+/// Returned by `GraphPair::dependency_changelog`. The `Display` implementation renders the changes
+/// as a Markdown section, with direct and transitive dependencies listed separately.
+#[derive(Clone, Debug)]
+pub struct DependencyChangelog<'a> {
+    old_member: Option<PackageMetadata<'a>>,
+    new_member: Option<PackageMetadata<'a>>,
+    member: PackageMetadata<'a>,
+    changes: Vec<DependencyChange<'a>>,
+}
+
+impl<'a> DependencyChangelog<'a> {
+    /// Returns the workspace member in the old graph, or `None` if it was added.
+    pub fn old_member(&self) -> Option<PackageMetadata<'a>> {
+        self.old_member
+    }
+
+    /// Returns the workspace member in the new graph, or `None` if it was removed.
+    pub fn new_member(&self) -> Option<PackageMetadata<'a>> {
+        self.new_member
+    }
+
+    /// Returns every dependency that was added, removed or changed versions, sorted by name and
+    /// version.
+    pub fn changes(&self) -> &[DependencyChange<'a>] {
+        &self.changes
+    }
+
+    /// Returns the changes to direct dependencies.
+    pub fn direct_changes<'b>(&'b self) -> impl Iterator<Item = &'b DependencyChange<'a>> + 'b {
+        self.changes.iter().filter(|change| change.direct)
+    }
+
+    /// Returns the changes to transitive dependencies that aren't direct dependencies.
+    pub fn transitive_changes<'b>(&'b self) -> impl Iterator<Item = &'b DependencyChange<'a>> + 'b {
+        self.changes.iter().filter(|change| !change.direct)
+    }
+
+    /// Returns true if no dependencies changed.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl<'a> fmt::Display for DependencyChangelog<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "## {} {}", self.member.name(), self.member.version())?;
+        if self.is_empty() {
+            writeln!(f)?;
+            return writeln!(f, "No dependency changes.");
+        }
+
+        for (heading, direct) in &[
+            ("Direct dependencies", true),
+            ("Transitive dependencies", false),
+        ] {
+            let mut changes = self
+                .changes
+                .iter()
+                .filter(|change| change.direct == *direct)
+                .peekable();
+            if changes.peek().is_none() {
+                continue;
+            }
+            writeln!(f)?;
+            writeln!(f, "### {}", heading)?;
+            writeln!(f)?;
+            for change in changes {
+                writeln!(f, "- {}", change)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Report for DependencyChangelog<'a> {
+    fn to_document(&self) -> ReportDocument {
+        let mut document =
+            ReportDocument::new(format!("{} {}", self.member.name(), self.member.version()));
+        for (heading, direct) in &[
+            ("Direct dependencies", true),
+            ("Transitive dependencies", false),
+        ] {
+            let mut changes = self
+                .changes
+                .iter()
+                .filter(|change| change.direct == *direct)
+                .peekable();
+            if changes.peek().is_none() {
+                continue;
+            }
+            let section = document.add_section(Some(heading.to_string()));
+            for change in changes {
+                section.add_item(change.to_string());
+            }
+        }
+        if self.is_empty() {
+            document.set_summary("No dependency changes.");
+        }
+        document
+    }
+}
+
+/// A dependency that was added, removed or changed versions.
+#[derive(Clone, Debug)]
+pub struct DependencyChange<'a> {
+    old: Option<PackageMetadata<'a>>,
+    new: Option<PackageMetadata<'a>>,
+    direct: bool,
+}
+
+impl<'a> DependencyChange<'a> {
+    /// Returns the dependency in the old graph, or `None` if it was added.
+    pub fn old_package(&self) -> Option<PackageMetadata<'a>> {
+        self.old
+    }
+
+    /// Returns the dependency in the new graph, or `None` if it was removed.
+    pub fn new_package(&self) -> Option<PackageMetadata<'a>> {
+        self.new
+    }
+
+    /// Returns the kind of change this is: `Added`, `Removed` or `VersionChanged`.
+    ///
+    /// Packages that existed in the old graph, but only became dependencies of the member in the
+    /// new one, are reported as added.
+    pub fn kind(&self) -> PackageChangeKind {
+        match (self.old, self.new) {
+            (Some(_), Some(_)) => PackageChangeKind::VersionChanged,
+            (None, Some(_)) => PackageChangeKind::Added,
+            (Some(_), None) => PackageChangeKind::Removed,
+            (None, None) => unreachable!("at least one of old and new is present"),
+        }
+    }
+
+    /// Returns true if this is a direct dependency of the workspace member.
+    ///
+    /// For removed dependencies, this is whether it was a direct dependency in the old graph.
+    pub fn is_direct(&self) -> bool {
+        self.direct
+    }
+
+    /// Returns a link to the upstream changelog for this dependency, derived from its
+    /// `repository` URL.
+    ///
+    /// For repositories on GitHub and GitLab, this is the project's releases page. For other
+    /// hosts, it is the repository URL itself. Returns `None` if the package doesn't specify a
+    /// repository.
+    pub fn changelog_url(&self) -> Option<String> {
+        self.package().repository().map(changelog_url)
+    }
+
+    /// Returns the package in the new graph if present, otherwise the one in the old graph.
+    fn package(&self) -> PackageMetadata<'a> {
+        self.new
+            .or(self.old)
+            .expect("at least one of old and new is present")
+    }
+}
+
+impl<'a> fmt::Display for DependencyChange<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let package = self.package();
+        match (self.old, self.new) {
+            (Some(old), Some(new)) => write!(
+                f,
+                "Updated `{}` from {} to {}",
+                package.name(),
+                old.version(),
+                new.version()
+            )?,
+            (None, Some(new)) => write!(f, "Added `{}` {}", package.name(), new.version())?,
+            (Some(old), None) => write!(f, "Removed `{}` {}", package.name(), old.version())?,
+            (None, None) => unreachable!("at least one of old and new is present"),
+        }
+        // Removed packages don't need a changelog link.
+        match self.changelog_url() {
+            Some(url) if self.new.is_some() => write!(f, " ([changelog]({}))", url),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The shipped dependencies of a workspace member in one graph.
+struct MemberDeps<'a> {
+    set: Option<PackageSet<'a>>,
+    direct: HashSet<&'a PackageId>,
+}
+
+impl<'a> MemberDeps<'a> {
+    fn new(graph: &'a PackageGraph, member: Option<PackageMetadata<'a>>) -> Result<Self, Error> {
+        let member = match member {
+            Some(member) => member,
+            None => {
+                return Ok(Self {
+                    set: None,
+                    direct: HashSet::new(),
+                })
+            }
+        };
+        let set = graph
+            .query_forward(vec![member.id()])?
+            .resolve_with_fn(|_, link| !link.dev_only());
+        let direct = member
+            .direct_links()
+            .filter(|link| !link.dev_only())
+            .map(|link| link.to().id())
+            .collect();
+        Ok(Self {
+            set: Some(set),
+            direct,
+        })
+    }
+
+    fn packages<'b>(&'b self) -> impl Iterator<Item = PackageMetadata<'a>> + 'b {
+        self.set
+            .iter()
+            .flat_map(|set| set.packages(DependencyDirection::Forward))
+            .filter(|package| !package.in_workspace())
+    }
+
+    fn contains(&self, package_id: &PackageId) -> bool {
+        match &self.set {
+            Some(set) => set.contains(package_id) == Some(true),
+            None => false,
+        }
+    }
+
+    fn is_direct(&self, package_id: &PackageId) -> bool {
+        self.direct.contains(package_id)
+    }
+}
+
+/// Returns the releases page for repositories on well-known hosts, or the URL itself otherwise.
+fn changelog_url(repository: &str) -> String {
+    let url = repository.trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    for (host, releases) in &[
+        ("https://github.com/", "releases"),
+        ("https://gitlab.com/", "-/releases"),
+    ] {
+        if let Some(path) = url.strip_prefix(host) {
+            // Repository URLs sometimes point to a subdirectory, e.g. /owner/repo/tree/master/crate.
+            let mut parts = path.splitn(3, '/');
+            if let (Some(owner), Some(repo)) = (parts.next(), parts.next()) {
+                return format!("{}{}/{}/{}", host, owner, repo, releases);
+            }
+        }
+    }
+    url.to_string()
+}
@@ -4,7 +4,7 @@
 use crate::graph::resolve_core::{ResolveCore, Topo};
 use crate::graph::{
     DependencyDirection, PackageGraph, PackageIx, PackageLink, PackageLinkImpl, PackageMetadata,
-    PackageQuery,
+    PackageQuery, SetOperation, SetOrigin,
 };
 use crate::petgraph_support::dot::{DotFmt, DotVisitor, DotWrite};
 use crate::petgraph_support::edge_ref::GraphEdgeRef;
@@ -27,6 +27,7 @@ impl PackageGraph {
         PackageSet {
             graph: self,
             core: ResolveCore::all_nodes(&self.dep_graph),
+            origin: SetOrigin::All,
         }
     }
 
@@ -39,9 +40,12 @@ impl PackageGraph {
         &self,
         package_ids: impl IntoIterator<Item = &'a PackageId>,
     ) -> Result<PackageSet, Error> {
+        let package_ixs: Vec<_> = self.package_ixs(package_ids)?;
+        let origin = SetOrigin::packages(self, package_ixs.iter().copied());
         Ok(PackageSet {
             graph: self,
-            core: ResolveCore::from_included::<IxBitSet>(self.package_ixs(package_ids)?),
+            core: ResolveCore::from_included(package_ixs.into_iter().collect::<IxBitSet>()),
+            origin,
         })
     }
 
@@ -57,6 +61,7 @@ impl PackageGraph {
         PackageSet {
             graph: self,
             core: ResolveCore::from_included(included),
+            origin: SetOrigin::Workspace,
         }
     }
 
@@ -70,7 +75,7 @@ impl PackageGraph {
         names: impl IntoIterator<Item = &'a str>,
     ) -> Result<PackageSet, Error> {
         let workspace = self.workspace();
-        let included: IxBitSet = names
+        let package_ixs = names
             .into_iter()
             .map(|name| {
                 workspace
@@ -78,10 +83,13 @@ impl PackageGraph {
                     .map(|package| package.package_ix())
                     .ok_or_else(|| Error::UnknownWorkspaceName(name.to_string()))
             })
-            .collect::<Result<_, _>>()?;
+            .collect::<Result<Vec<_>, _>>()?;
+        let origin = SetOrigin::packages(self, package_ixs.iter().copied());
+        let included: IxBitSet = package_ixs.into_iter().collect();
         Ok(PackageSet {
             graph: self,
             core: ResolveCore::from_included(included),
+            origin,
         })
     }
 }
@@ -91,23 +99,31 @@ impl PackageGraph {
 /// Created by `PackageQuery::resolve`.
 #[derive(Clone, Debug)]
 pub struct PackageSet<'g> {
-    graph: &'g PackageGraph,
+    pub(super) graph: &'g PackageGraph,
     core: ResolveCore<PackageGraph>,
+    origin: SetOrigin,
 }
 
 impl<'g> PackageSet<'g> {
     pub(super) fn new(query: PackageQuery<'g>) -> Self {
         let graph = query.graph;
+        let origin = SetOrigin::query(&query, false);
         Self {
             graph,
             core: ResolveCore::new(graph.dep_graph(), query.params),
+            origin,
         }
     }
 
-    pub(super) fn from_included(graph: &'g PackageGraph, included: FixedBitSet) -> Self {
+    pub(super) fn from_included(
+        graph: &'g PackageGraph,
+        included: FixedBitSet,
+        origin: SetOrigin,
+    ) -> Self {
         Self {
             graph,
             core: ResolveCore::from_included(included),
+            origin,
         }
     }
 
@@ -117,12 +133,14 @@ impl<'g> PackageSet<'g> {
     ) -> Self {
         let graph = query.graph;
         let params = query.params.clone();
+        let origin = SetOrigin::query(&query, true);
         Self {
             graph,
             core: ResolveCore::with_edge_filter(graph.dep_graph(), params, |edge| {
                 let link = graph.edge_ref_to_link(edge);
                 resolver.accept(&query, link)
             }),
+            origin,
         }
     }
 
@@ -142,6 +160,11 @@ impl<'g> PackageSet<'g> {
         Some(self.core.contains(self.graph.package_ix(package_id)?))
     }
 
+    /// Returns a description of how this set was produced.
+    pub fn origin(&self) -> &SetOrigin {
+        &self.origin
+    }
+
     // ---
     // Set operations
     // ---
@@ -159,6 +182,7 @@ impl<'g> PackageSet<'g> {
         );
         let mut res = self.clone();
         res.core.union_with(&other.core);
+        res.origin = SetOrigin::combined(SetOperation::Union, &self.origin, &other.origin);
         res
     }
 
@@ -174,6 +198,7 @@ impl<'g> PackageSet<'g> {
         );
         let mut res = self.clone();
         res.core.intersect_with(&other.core);
+        res.origin = SetOrigin::combined(SetOperation::Intersection, &self.origin, &other.origin);
         res
     }
 
@@ -190,6 +215,7 @@ impl<'g> PackageSet<'g> {
         Self {
             graph: self.graph,
             core: self.core.difference(&other.core),
+            origin: SetOrigin::combined(SetOperation::Difference, &self.origin, &other.origin),
         }
     }
 
@@ -206,6 +232,11 @@ impl<'g> PackageSet<'g> {
         );
         let mut res = self.clone();
         res.core.symmetric_difference_with(&other.core);
+        res.origin = SetOrigin::combined(
+            SetOperation::SymmetricDifference,
+            &self.origin,
+            &other.origin,
+        );
         res
     }
 
@@ -0,0 +1,429 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Suggestions for merging and splitting workspace members.
+
+use crate::graph::{PackageGraph, PackageLink, PackageMetadata};
+use crate::report::{Report, ReportDocument};
+use crate::PackageId;
+use petgraph::unionfind::UnionFind;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+impl PackageGraph {
+    /// Suggests ways to restructure the workspace, based on how its members depend on each other.
+    ///
+    /// Merge candidates are groups of tightly coupled members. A member is grouped with another
+    /// member if that member is its only non-dev dependent in the workspace, or if the two depend
+    /// on each other through a dev-dependency. Groups are ranked by cohesion: the share of their
+    /// links to and from workspace members that stay within the group.
+    ///
+    /// Split candidates are members whose dependents within the workspace request disjoint sets of
+    /// their features, which suggests that the member bundles unrelated functionality. They are
+    /// ranked by the number of disjoint feature groups.
+    pub fn restructure_report(&self) -> RestructureReport<'_> {
+        let members: Vec<_> = self
+            .workspace()
+            .members()
+            .map(|(_, member)| member)
+            .collect();
+
+        let mut merges = merge_candidates(&members);
+        merges.sort_by(|a, b| {
+            // Compare cohesion without going through floating point.
+            (b.internal_links * a.total_links())
+                .cmp(&(a.internal_links * b.total_links()))
+                .then_with(|| b.members.len().cmp(&a.members.len()))
+                .then_with(|| a.members[0].name().cmp(b.members[0].name()))
+        });
+
+        let mut splits: Vec<_> = members
+            .iter()
+            .filter_map(|member| SplitCandidate::new(*member))
+            .collect();
+        splits.sort_by(|a, b| {
+            b.groups
+                .len()
+                .cmp(&a.groups.len())
+                .then_with(|| b.dependent_count().cmp(&a.dependent_count()))
+                .then_with(|| a.member.name().cmp(b.member.name()))
+        });
+
+        RestructureReport { merges, splits }
+    }
+}
+
+/// Suggestions for merging and splitting workspace members.
+///
+/// Returned by `PackageGraph::restructure_report`. The `Display` implementation renders the
+/// suggestions in a human-readable format, ranked from strongest to weakest.
+#[derive(Clone, Debug)]
+pub struct RestructureReport<'g> {
+    merges: Vec<MergeCandidate<'g>>,
+    splits: Vec<SplitCandidate<'g>>,
+}
+
+impl<'g> RestructureReport<'g> {
+    /// Returns groups of workspace members that could be merged, ranked by cohesion.
+    pub fn merges(&self) -> &[MergeCandidate<'g>] {
+        &self.merges
+    }
+
+    /// Returns workspace members that could be split, ranked by the number of disjoint feature
+    /// groups.
+    pub fn splits(&self) -> &[SplitCandidate<'g>] {
+        &self.splits
+    }
+
+    /// Returns true if there are no suggestions.
+    pub fn is_empty(&self) -> bool {
+        self.merges.is_empty() && self.splits.is_empty()
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn summary(&self) -> String {
+        format!(
+            "{} merge candidates, {} split candidates",
+            self.merges.len(),
+            self.splits.len()
+        )
+    }
+}
+
+impl<'g> fmt::Display for RestructureReport<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.merges.is_empty() {
+            writeln!(f, "merge candidates:")?;
+            for merge in &self.merges {
+                writeln!(f, "    {}", merge)?;
+            }
+        }
+        if !self.splits.is_empty() {
+            writeln!(f, "split candidates:")?;
+            for split in &self.splits {
+                writeln!(f, "    {}", split)?;
+                for group in &split.groups {
+                    writeln!(f, "        {}", group)?;
+                }
+            }
+        }
+        writeln!(f, "{}", self.summary())
+    }
+}
+
+impl<'g> Report for RestructureReport<'g> {
+    fn to_document(&self) -> ReportDocument {
+        let mut document = ReportDocument::new("Workspace restructuring");
+        if !self.merges.is_empty() {
+            let section = document.add_section(Some("Merge candidates".to_string()));
+            for merge in &self.merges {
+                section.add_item(merge.to_string());
+            }
+        }
+        if !self.splits.is_empty() {
+            let section = document.add_section(Some("Split candidates".to_string()));
+            for split in &self.splits {
+                let item = section.add_item(split.to_string());
+                for group in &split.groups {
+                    item.add_detail(group.to_string());
+                }
+            }
+        }
+        document.set_summary(self.summary());
+        document
+    }
+}
+
+/// A group of tightly coupled workspace members that could be merged into one.
+#[derive(Clone, Debug)]
+pub struct MergeCandidate<'g> {
+    members: Vec<PackageMetadata<'g>>,
+    internal_links: usize,
+    external_links: usize,
+}
+
+impl<'g> MergeCandidate<'g> {
+    /// Returns the members in this group, ordered by workspace path.
+    pub fn members(&self) -> &[PackageMetadata<'g>] {
+        &self.members
+    }
+
+    /// Returns the number of direct links between members of this group.
+    pub fn internal_links(&self) -> usize {
+        self.internal_links
+    }
+
+    /// Returns the number of direct links between members of this group and other workspace
+    /// members, in either direction.
+    pub fn external_links(&self) -> usize {
+        self.external_links
+    }
+
+    /// Returns the share of links to and from workspace members that stay within this group,
+    /// between 0 and 1.
+    pub fn cohesion(&self) -> f64 {
+        self.internal_links as f64 / self.total_links() as f64
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn total_links(&self) -> usize {
+        self.internal_links + self.external_links
+    }
+}
+
+impl<'g> fmt::Display for MergeCandidate<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self.members.iter().map(|member| member.name()).collect();
+        write!(
+            f,
+            "{}: {} internal links, {} external ({}% cohesion)",
+            names.join(", "),
+            self.internal_links,
+            self.external_links,
+            self.internal_links * 100 / self.total_links()
+        )
+    }
+}
+
+/// A workspace member whose dependents use disjoint sets of its features.
+#[derive(Clone, Debug)]
+pub struct SplitCandidate<'g> {
+    member: PackageMetadata<'g>,
+    groups: Vec<FeatureGroup<'g>>,
+}
+
+impl<'g> SplitCandidate<'g> {
+    /// Returns the workspace member that could be split.
+    pub fn member(&self) -> PackageMetadata<'g> {
+        self.member
+    }
+
+    /// Returns the disjoint groups of features requested by the member's dependents, sorted by
+    /// their first feature.
+    ///
+    /// There are always at least two groups. Dependents that only use the member without any of
+    /// its features aren't part of any group.
+    pub fn groups(&self) -> &[FeatureGroup<'g>] {
+        &self.groups
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn new(member: PackageMetadata<'g>) -> Option<Self> {
+        let mut usages: Vec<(PackageMetadata<'g>, BTreeSet<&'g str>)> = member
+            .reverse_direct_links()
+            .filter(|link| link.from().in_workspace())
+            .filter_map(|link| {
+                let features = requested_features(member, &link);
+                if features.is_empty() {
+                    None
+                } else {
+                    Some((link.from(), features))
+                }
+            })
+            .collect();
+        usages.sort_by_key(|(dependent, _)| dependent.name());
+
+        // Group together dependents that share at least one feature.
+        let mut sets = UnionFind::new(usages.len());
+        for a in 0..usages.len() {
+            for b in (a + 1)..usages.len() {
+                if !usages[a].1.is_disjoint(&usages[b].1) {
+                    sets.union(a, b);
+                }
+            }
+        }
+        let mut groups: Vec<FeatureGroup<'g>> = groups(sets)
+            .into_iter()
+            .map(|indexes| {
+                let mut features = BTreeSet::new();
+                let mut dependents = vec![];
+                for index in indexes {
+                    let (dependent, requested) = &usages[index];
+                    features.extend(requested.iter().copied());
+                    dependents.push(*dependent);
+                }
+                FeatureGroup {
+                    features: features.into_iter().collect(),
+                    dependents,
+                }
+            })
+            .collect();
+        if groups.len() < 2 {
+            return None;
+        }
+        groups.sort_by(|a, b| a.features.cmp(&b.features));
+
+        Some(Self { member, groups })
+    }
+
+    fn dependent_count(&self) -> usize {
+        self.groups.iter().map(|group| group.dependents.len()).sum()
+    }
+}
+
+impl<'g> fmt::Display for SplitCandidate<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} disjoint feature groups",
+            self.member.name(),
+            self.groups.len()
+        )
+    }
+}
+
+/// A set of features of a `SplitCandidate`, and the dependents that request them.
+#[derive(Clone, Debug)]
+pub struct FeatureGroup<'g> {
+    features: Vec<&'g str>,
+    dependents: Vec<PackageMetadata<'g>>,
+}
+
+impl<'g> FeatureGroup<'g> {
+    /// Returns the features in this group, sorted by name.
+    ///
+    /// These include features enabled by other features, but not `default` itself.
+    pub fn features(&self) -> &[&'g str] {
+        &self.features
+    }
+
+    /// Returns the workspace members that request these features, sorted by name.
+    pub fn dependents(&self) -> &[PackageMetadata<'g>] {
+        &self.dependents
+    }
+}
+
+impl<'g> fmt::Display for FeatureGroup<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self
+            .dependents
+            .iter()
+            .map(|dependent| dependent.name())
+            .collect();
+        write!(
+            f,
+            "{}: used by {}",
+            self.features.join(", "),
+            names.join(", ")
+        )
+    }
+}
+
+/// Groups workspace members joined by links that suggest they could be merged.
+fn merge_candidates<'g>(members: &[PackageMetadata<'g>]) -> Vec<MergeCandidate<'g>> {
+    let indexes: HashMap<&PackageId, usize> = members
+        .iter()
+        .enumerate()
+        .map(|(index, member)| (member.id(), index))
+        .collect();
+    let links: Vec<(usize, usize)> = members
+        .iter()
+        .flat_map(|member| member.direct_links())
+        .filter_map(|link| {
+            let to = *indexes.get(link.to().id())?;
+            Some((indexes[link.from().id()], to))
+        })
+        .collect();
+
+    let mut sets = UnionFind::new(members.len());
+    for (index, member) in members.iter().enumerate() {
+        let dependents: HashSet<_> = member
+            .reverse_direct_links()
+            .filter(|link| !link.dev_only())
+            .filter_map(|link| indexes.get(link.from().id()))
+            .collect();
+        if dependents.len() == 1 {
+            let dependent = dependents.into_iter().next().expect("one element");
+            sets.union(index, *dependent);
+        }
+    }
+    let links_set: HashSet<_> = links.iter().copied().collect();
+    for (from, to) in &links {
+        // Cargo only allows cycles through dev-dependencies.
+        if links_set.contains(&(*to, *from)) {
+            sets.union(*from, *to);
+        }
+    }
+
+    groups(sets)
+        .into_iter()
+        .filter(|group| group.len() > 1)
+        .map(|group| {
+            let in_group: HashSet<_> = group.iter().copied().collect();
+            let mut internal_links = 0;
+            let mut external_links = 0;
+            for (from, to) in &links {
+                match (in_group.contains(from), in_group.contains(to)) {
+                    (true, true) => internal_links += 1,
+                    (true, false) | (false, true) => external_links += 1,
+                    (false, false) => {}
+                }
+            }
+            MergeCandidate {
+                members: group.into_iter().map(|index| members[index]).collect(),
+                internal_links,
+                external_links,
+            }
+        })
+        .collect()
+}
+
+/// Returns the features of `member` that this link requests, including features enabled by them.
+///
+/// Default features are expanded if they're enabled, but `default` itself isn't included.
+fn requested_features<'g>(
+    member: PackageMetadata<'g>,
+    link: &PackageLink<'g>,
+) -> BTreeSet<&'g str> {
+    let mut pending = vec![];
+    for req in &[link.normal(), link.build(), link.dev()] {
+        if !req.is_present() {
+            continue;
+        }
+        pending.extend(req.features());
+        if req.default_features().enabled_on_any() {
+            pending.push("default");
+        }
+    }
+
+    let implied: HashMap<_, _> = member.named_features_with_deps().collect();
+    let mut features = BTreeSet::new();
+    while let Some(feature) = pending.pop() {
+        if !features.insert(feature) {
+            continue;
+        }
+        if let Some(deps) = implied.get(feature) {
+            // Features of dependencies like "serde/std" don't affect this member's structure.
+            pending.extend(
+                deps.iter()
+                    .filter(|dep| !dep.contains('/'))
+                    .map(|dep| dep.as_str()),
+            );
+        }
+    }
+    features.remove("default");
+    features
+}
+
+/// Returns the groups of indexes in these sets, each sorted and ordered by their lowest index.
+fn groups(sets: UnionFind<usize>) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = vec![];
+    let mut group_indexes = HashMap::new();
+    for (index, root) in sets.into_labeling().into_iter().enumerate() {
+        let group_index = *group_indexes.entry(root).or_insert_with(|| {
+            groups.push(vec![]);
+            groups.len() - 1
+        });
+        groups[group_index].push(index);
+    }
+    groups
+}
@@ -0,0 +1,239 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::cargo::{CargoOptions, CargoSet};
+use crate::graph::feature::{FeatureFilter, FeatureId};
+use crate::graph::{BuildTargetId, PackageMetadata};
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::iter;
+
+/// A kind of build target, as selected by flags like `--lib`, `--tests` or `--examples` to Cargo
+/// commands.
+///
+/// Cargo computes one dependency closure per package, but whether dev-dependencies are part of it
+/// depends on the kind of target being built.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TargetKind {
+    /// The library target, as built by `cargo build --lib`.
+    Library,
+
+    /// Binary targets, as built by `cargo build --bins`.
+    Binaries,
+
+    /// Test targets, as built by `cargo test --tests`.
+    ///
+    /// This includes unit tests for the library and binary targets, along with integration tests.
+    Tests,
+
+    /// Example targets, as built by `cargo build --examples`.
+    Examples,
+
+    /// Benchmark targets, as built by `cargo bench --benches`.
+    Benchmarks,
+}
+
+impl TargetKind {
+    /// A list of all `TargetKind` values.
+    pub const VALUES: &'static [Self] = &[
+        TargetKind::Library,
+        TargetKind::Binaries,
+        TargetKind::Tests,
+        TargetKind::Examples,
+        TargetKind::Benchmarks,
+    ];
+
+    /// Returns true if building targets of this kind pulls in dev-dependencies.
+    pub fn includes_dev(self) -> bool {
+        match self {
+            TargetKind::Library | TargetKind::Binaries => false,
+            TargetKind::Tests | TargetKind::Examples | TargetKind::Benchmarks => true,
+        }
+    }
+
+    /// Returns true if the build target with this ID is built for this kind.
+    ///
+    /// Build scripts are never selected directly: they're built as needed for any other target.
+    pub fn matches(self, id: BuildTargetId<'_>) -> bool {
+        matches!(
+            (self, id),
+            (TargetKind::Library, BuildTargetId::Library)
+                | (TargetKind::Binaries, BuildTargetId::Binary(_))
+                | (TargetKind::Tests, BuildTargetId::Library)
+                | (TargetKind::Tests, BuildTargetId::Binary(_))
+                | (TargetKind::Tests, BuildTargetId::Test(_))
+                | (TargetKind::Examples, BuildTargetId::Example(_))
+                | (TargetKind::Benchmarks, BuildTargetId::Benchmark(_))
+        )
+    }
+}
+
+impl<'g> PackageMetadata<'g> {
+    /// Simulates a Cargo build of this package's targets of the given kind, with the features
+    /// selected by the filter.
+    ///
+    /// The filter is applied to this package only, similar to passing `--features` to Cargo.
+    /// Dev-dependencies are included as `TargetKind::includes_dev` describes, overriding the
+    /// setting in `opts`.
+    ///
+    /// Like Cargo, targets whose `required-features` aren't enabled are skipped rather than
+    /// having those features turned on. If every target of this kind is skipped, nothing is built
+    /// and the closure has no `CargoSet`.
+    pub fn target_closure(
+        &self,
+        kind: TargetKind,
+        filter: impl FeatureFilter<'g>,
+        opts: &CargoOptions<'_>,
+    ) -> Result<TargetClosure<'g>, Error> {
+        let package_graph = self.graph;
+        let query = package_graph
+            .feature_graph()
+            .query_packages(&package_graph.query_forward(iter::once(self.id()))?, filter);
+        let opts = opts.clone().with_dev_deps(kind.includes_dev());
+        let cargo_set = CargoSet::new(query, &opts)?;
+
+        let mut targets = vec![];
+        let mut skipped = vec![];
+        for target in self.build_targets() {
+            if !kind.matches(target.id()) {
+                continue;
+            }
+            // required-features has no effect on the library target.
+            if target.id() == BuildTargetId::Library {
+                targets.push(target.id());
+                continue;
+            }
+            let missing_features: Vec<_> = target
+                .required_features()
+                .iter()
+                .map(|feature| feature.as_str())
+                .filter(|feature| !self.is_feature_enabled(&cargo_set, feature))
+                .collect();
+            if missing_features.is_empty() {
+                targets.push(target.id());
+            } else {
+                skipped.push(SkippedTarget {
+                    id: target.id(),
+                    missing_features,
+                });
+            }
+        }
+        targets.sort_unstable();
+        skipped.sort_by_key(|skipped| skipped.id);
+
+        let cargo_set = if targets.is_empty() {
+            None
+        } else {
+            Some(cargo_set)
+        };
+        Ok(TargetClosure {
+            package: *self,
+            kind,
+            targets,
+            skipped,
+            cargo_set,
+        })
+    }
+
+    /// Returns true if a `required-features` entry is enabled in this `CargoSet`.
+    ///
+    /// Entries are either features of this package, or `dep/feature` for a feature of a
+    /// dependency.
+    fn is_feature_enabled(&self, cargo_set: &CargoSet<'g>, feature: &str) -> bool {
+        let target_features = cargo_set.target_features();
+        let mut parts = feature.splitn(2, '/');
+        let dep = parts.next().expect("splitn returns at least one part");
+        match parts.next() {
+            None => target_features
+                .contains(FeatureId::new(self.id(), dep))
+                .unwrap_or(false),
+            Some(dep_feature) => self
+                .direct_links()
+                .filter(|link| link.dep_name() == dep)
+                .any(|link| {
+                    target_features
+                        .contains((link.to().id(), dep_feature))
+                        .unwrap_or(false)
+                }),
+        }
+    }
+}
+
+/// The targets of one kind in a package, along with the packages and features Cargo would build
+/// for them.
+///
+/// Returned by `PackageMetadata::target_closure`.
+pub struct TargetClosure<'g> {
+    package: PackageMetadata<'g>,
+    kind: TargetKind,
+    targets: Vec<BuildTargetId<'g>>,
+    skipped: Vec<SkippedTarget<'g>>,
+    cargo_set: Option<CargoSet<'g>>,
+}
+
+impl<'g> TargetClosure<'g> {
+    /// Returns the package the targets are in.
+    pub fn package(&self) -> PackageMetadata<'g> {
+        self.package
+    }
+
+    /// Returns the kind of targets this closure is for.
+    pub fn kind(&self) -> TargetKind {
+        self.kind
+    }
+
+    /// Returns the targets that are built, sorted.
+    pub fn targets(&self) -> &[BuildTargetId<'g>] {
+        &self.targets
+    }
+
+    /// Returns the targets that are skipped because their `required-features` aren't enabled,
+    /// sorted.
+    pub fn skipped(&self) -> &[SkippedTarget<'g>] {
+        &self.skipped
+    }
+
+    /// Returns the features that need to be enabled for every target of this kind to be built,
+    /// sorted.
+    ///
+    /// This is empty if no targets are skipped.
+    pub fn missing_features(&self) -> BTreeSet<&'g str> {
+        self.skipped
+            .iter()
+            .flat_map(|skipped| skipped.missing_features.iter().copied())
+            .collect()
+    }
+
+    /// Returns the packages and features built for these targets, or `None` if every target was
+    /// skipped.
+    pub fn cargo_set(&self) -> Option<&CargoSet<'g>> {
+        self.cargo_set.as_ref()
+    }
+
+    /// Converts this closure into its `CargoSet`, or `None` if every target was skipped.
+    pub fn into_cargo_set(self) -> Option<CargoSet<'g>> {
+        self.cargo_set
+    }
+}
+
+/// A build target skipped by `PackageMetadata::target_closure`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SkippedTarget<'g> {
+    id: BuildTargetId<'g>,
+    missing_features: Vec<&'g str>,
+}
+
+impl<'g> SkippedTarget<'g> {
+    /// Returns the ID of the build target.
+    pub fn id(&self) -> BuildTargetId<'g> {
+        self.id
+    }
+
+    /// Returns the entries in the target's `required-features` that aren't enabled, in the order
+    /// they're listed.
+    pub fn missing_features(&self) -> &[&'g str] {
+        &self.missing_features
+    }
+}
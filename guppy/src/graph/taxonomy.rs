@@ -0,0 +1,337 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Tag packages with broad categories like "async runtime" or "native code".
+//!
+//! Policy rules often care about kinds of crates rather than individual names: "no native code in
+//! the closure of this binary", or "only one async runtime per service". A `Taxonomy` assigns
+//! `PackageTag` instances to packages based on their names, their crates.io categories and
+//! keywords, and a few heuristics:
+//!
+//! * `ProcMacro` is assigned to every package with a procedural macro library target.
+//! * `Native` is assigned to packages that set the `links` key, whose names end in `-sys`, or
+//!   whose build scripts depend on tools like `cc`, `cmake` or `bindgen`.
+//! * `AsyncRuntime` is assigned to packages in the `asynchronous` category that have a `runtime`
+//!   or `executor` keyword.
+//!
+//! `Taxonomy::new` comes with rules for well-known crates, which can be extended or overridden.
+//!
+//! This module is experimental, and requires the `unstable` feature to be enabled.
+//!
+//! ## Examples
+//!
+//! Print all native-code packages in the closure of a workspace member:
+//!
+//! ```rust,no_run
+//! use guppy::graph::taxonomy::{PackageTag, Taxonomy};
+//! use guppy::MetadataCommand;
+//!
+//! let graph = MetadataCommand::new().build_graph().unwrap();
+//! let member = graph.workspace().member_by_name("my-binary").unwrap();
+//! let closure = graph.query_forward(vec![member.id()]).unwrap().resolve();
+//!
+//! for package in Taxonomy::new().packages_with_tag(&closure, PackageTag::Native) {
+//!     println!("{} {}", package.name(), package.version());
+//! }
+//! ```
+
+use crate::graph::{DependencyDirection, PackageMetadata, PackageSet};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// A broad category of packages, assigned by a `Taxonomy`.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PackageTag {
+    /// Async runtimes and executors, like `tokio` or `async-std`.
+    AsyncRuntime,
+
+    /// TLS implementations and bindings to them, like `rustls` or `native-tls`.
+    Tls,
+
+    /// Serialization frameworks and data formats, like `serde` or `bincode`.
+    Serialization,
+
+    /// Packages that build or link to native code, like `openssl-sys`.
+    Native,
+
+    /// Procedural macros.
+    ProcMacro,
+}
+
+impl PackageTag {
+    /// A list of all `PackageTag` values.
+    pub const VALUES: &'static [Self] = &[
+        PackageTag::AsyncRuntime,
+        PackageTag::Tls,
+        PackageTag::Serialization,
+        PackageTag::Native,
+        PackageTag::ProcMacro,
+    ];
+
+    /// Returns the name of this tag, as used by `Display`, `FromStr` and serialization.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PackageTag::AsyncRuntime => "async-runtime",
+            PackageTag::Tls => "tls",
+            PackageTag::Serialization => "serialization",
+            PackageTag::Native => "native",
+            PackageTag::ProcMacro => "proc-macro",
+        }
+    }
+}
+
+impl fmt::Display for PackageTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for PackageTag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::VALUES
+            .iter()
+            .copied()
+            .find(|tag| tag.as_str() == s)
+            .ok_or_else(|| format!("unknown package tag: {}", s))
+    }
+}
+
+/// Build dependencies that indicate that a build script compiles or binds to native code.
+static NATIVE_BUILD_TOOLS: &[&str] = &["bindgen", "cc", "cmake", "gcc", "pkg-config", "vcpkg"];
+
+static DEFAULT_RULES: &[(PackageTag, TagRuleSpec)] = &[
+    (
+        PackageTag::AsyncRuntime,
+        TagRuleSpec {
+            names: &[
+                "actix-rt",
+                "async-executor",
+                "async-global-executor",
+                "async-std",
+                "glommio",
+                "smol",
+                "tokio",
+            ],
+            categories: &[],
+            keywords: &[],
+            excluded: &[],
+        },
+    ),
+    (
+        PackageTag::Tls,
+        TagRuleSpec {
+            names: &[
+                "boring",
+                "native-tls",
+                "openssl",
+                "rustls",
+                "schannel",
+                "security-framework",
+            ],
+            categories: &[],
+            keywords: &["ssl", "tls"],
+            excluded: &[],
+        },
+    ),
+    (
+        PackageTag::Serialization,
+        TagRuleSpec {
+            names: &["bincode", "prost", "protobuf", "serde"],
+            categories: &["encoding"],
+            keywords: &["deserialization", "serde", "serialization", "serializer"],
+            excluded: &[],
+        },
+    ),
+    (
+        PackageTag::Native,
+        TagRuleSpec {
+            names: &[],
+            categories: &["external-ffi-bindings"],
+            keywords: &["ffi"],
+            // These are bindings to JavaScript APIs, not native code.
+            excluded: &["js-sys", "wasm-bindgen-shared", "web-sys"],
+        },
+    ),
+];
+
+struct TagRuleSpec {
+    names: &'static [&'static str],
+    categories: &'static [&'static str],
+    keywords: &'static [&'static str],
+    excluded: &'static [&'static str],
+}
+
+/// Rules for assigning `PackageTag` instances to packages.
+///
+/// Names are compared with `-` and `_` treated as equivalent, and categories and keywords are
+/// compared case-insensitively. A package gets a tag if it matches any of the tag's rules or
+/// heuristics, unless it has been excluded from the tag by name.
+#[derive(Clone, Debug)]
+pub struct Taxonomy {
+    rules: BTreeMap<PackageTag, TagRule>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct TagRule {
+    names: BTreeSet<String>,
+    categories: BTreeSet<String>,
+    keywords: BTreeSet<String>,
+    excluded: BTreeSet<String>,
+}
+
+impl Taxonomy {
+    /// Creates a new `Taxonomy` with the default rules for well-known crates.
+    pub fn new() -> Self {
+        let mut taxonomy = Self::empty();
+        for (tag, spec) in DEFAULT_RULES {
+            for name in spec.names {
+                taxonomy.add_name(*tag, name);
+            }
+            for category in spec.categories {
+                taxonomy.add_category(*tag, category);
+            }
+            for keyword in spec.keywords {
+                taxonomy.add_keyword(*tag, keyword);
+            }
+            for name in spec.excluded {
+                taxonomy.exclude_name(*tag, name);
+            }
+        }
+        taxonomy
+    }
+
+    /// Creates a new `Taxonomy` with no name, category or keyword rules.
+    ///
+    /// The heuristics listed in the module documentation still apply.
+    pub fn empty() -> Self {
+        Self {
+            rules: PackageTag::VALUES
+                .iter()
+                .map(|tag| (*tag, TagRule::default()))
+                .collect(),
+        }
+    }
+
+    /// Assigns this tag to packages with this name.
+    pub fn add_name(&mut self, tag: PackageTag, name: impl AsRef<str>) -> &mut Self {
+        let name = normalize_name(name.as_ref());
+        let rule = self.rule_mut(tag);
+        rule.excluded.remove(&name);
+        rule.names.insert(name);
+        self
+    }
+
+    /// Assigns this tag to packages in this crates.io category, such as `encoding`.
+    ///
+    /// Categories are matched by their slugs, as they appear in `Cargo.toml`. Subcategories like
+    /// `development-tools::testing` are matched exactly.
+    pub fn add_category(&mut self, tag: PackageTag, category: impl AsRef<str>) -> &mut Self {
+        self.rule_mut(tag)
+            .categories
+            .insert(category.as_ref().to_lowercase());
+        self
+    }
+
+    /// Assigns this tag to packages with this keyword.
+    pub fn add_keyword(&mut self, tag: PackageTag, keyword: impl AsRef<str>) -> &mut Self {
+        self.rule_mut(tag)
+            .keywords
+            .insert(keyword.as_ref().to_lowercase());
+        self
+    }
+
+    /// Never assigns this tag to packages with this name, even if they match other rules or
+    /// heuristics.
+    pub fn exclude_name(&mut self, tag: PackageTag, name: impl AsRef<str>) -> &mut Self {
+        let name = normalize_name(name.as_ref());
+        let rule = self.rule_mut(tag);
+        rule.names.remove(&name);
+        rule.excluded.insert(name);
+        self
+    }
+
+    /// Returns true if this package is assigned this tag.
+    pub fn has_tag(&self, package: &PackageMetadata<'_>, tag: PackageTag) -> bool {
+        let rule = &self.rules[&tag];
+        let name = normalize_name(package.name());
+        if rule.excluded.contains(&name) {
+            return false;
+        }
+        if rule.names.contains(&name) || matches_heuristic(package, tag) {
+            return true;
+        }
+        package
+            .categories()
+            .iter()
+            .any(|category| rule.categories.contains(&category.to_lowercase()))
+            || package
+                .keywords()
+                .iter()
+                .any(|keyword| rule.keywords.contains(&keyword.to_lowercase()))
+    }
+
+    /// Returns the set of tags assigned to this package.
+    pub fn tags(&self, package: &PackageMetadata<'_>) -> BTreeSet<PackageTag> {
+        PackageTag::VALUES
+            .iter()
+            .copied()
+            .filter(|tag| self.has_tag(package, *tag))
+            .collect()
+    }
+
+    /// Returns the packages in this set that are assigned this tag, in topological order.
+    pub fn packages_with_tag<'g>(
+        &self,
+        set: &PackageSet<'g>,
+        tag: PackageTag,
+    ) -> Vec<PackageMetadata<'g>> {
+        set.packages(DependencyDirection::Forward)
+            .filter(|package| self.has_tag(package, tag))
+            .collect()
+    }
+
+    fn rule_mut(&mut self, tag: PackageTag) -> &mut TagRule {
+        self.rules.get_mut(&tag).expect("every tag has a rule")
+    }
+}
+
+impl Default for Taxonomy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn matches_heuristic(package: &PackageMetadata<'_>, tag: PackageTag) -> bool {
+    match tag {
+        PackageTag::ProcMacro => package.is_proc_macro(),
+        PackageTag::Native => {
+            package.links().is_some()
+                || package.name().ends_with("-sys")
+                || package.name().ends_with("_sys")
+                || (package.has_build_script()
+                    && package.direct_links().any(|link| {
+                        link.build().is_present() && NATIVE_BUILD_TOOLS.contains(&link.to().name())
+                    }))
+        }
+        PackageTag::AsyncRuntime => {
+            package
+                .categories()
+                .iter()
+                .any(|category| category == "asynchronous")
+                && package
+                    .keywords()
+                    .iter()
+                    .any(|keyword| keyword == "runtime" || keyword == "executor")
+        }
+        PackageTag::Tls | PackageTag::Serialization => false,
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.replace('_', "-")
+}
@@ -0,0 +1,566 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Deriving simplified graphs through a pipeline of transforms.
+
+use crate::graph::{cargo_version_matches, PackageGraph, PackageLink, PackageMetadata};
+use crate::{CargoMetadata, Error};
+use semver::VersionReq;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+/// A pipeline of transforms that derives a simplified `PackageGraph` from another one.
+///
+/// Each transform is applied in the order it was added. The result is an ordinary
+/// `PackageGraph`, so every query, report and exporter works on it as is.
+///
+/// # Examples
+///
+/// ```
+/// use guppy::CargoMetadata;
+/// use guppy::graph::GraphTransforms;
+///
+/// let metadata = CargoMetadata::parse_json(include_str!("../../../fixtures/small/metadata1.json")).unwrap();
+/// let package_graph = metadata.build_graph().unwrap();
+///
+/// let simplified = GraphTransforms::new()
+///     .drop_dev_edges()
+///     .collapse_third_party()
+///     .apply(&package_graph)
+///     .unwrap();
+/// assert!(simplified
+///     .packages()
+///     .filter(|package| !package.in_workspace())
+///     .all(|package| package.direct_links().next().is_none()));
+/// ```
+#[derive(Default)]
+pub struct GraphTransforms<'a> {
+    steps: Vec<Transform<'a>>,
+}
+
+impl<'a> GraphTransforms<'a> {
+    /// Creates a new, empty pipeline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes dev-only links, along with any packages that are no longer reachable from the
+    /// workspace.
+    ///
+    /// Links that are both dev and non-dev dependencies are kept, without their dev instances.
+    pub fn drop_dev_edges(&mut self) -> &mut Self {
+        self.steps.push(Transform::DropDevEdges);
+        self
+    }
+
+    /// Collapses the subtree under each direct third-party dependency of the workspace into a
+    /// single node.
+    ///
+    /// Third-party packages lose all their dependencies, and packages that are no longer
+    /// reachable from the workspace are removed. Named features are kept, but no longer enable
+    /// anything.
+    pub fn collapse_third_party(&mut self) -> &mut Self {
+        self.steps.push(Transform::CollapseThirdParty);
+        self
+    }
+
+    /// Merges third-party packages that have the same name into the highest version among them.
+    ///
+    /// Links to the other versions are redirected to the highest version, and the other versions
+    /// are removed along with any dependencies only they brought in.
+    pub fn merge_duplicate_versions(&mut self) -> &mut Self {
+        self.steps.push(Transform::MergeDuplicateVersions);
+        self
+    }
+
+    /// Renames packages according to `label`, for example to group them by team ownership.
+    ///
+    /// `label` returns the new name of a package, or `None` to keep its current name. Packages
+    /// that end up with the same name are merged into a single node that has the dependencies of
+    /// all of them. The merged node is a workspace member if any of the packages is one.
+    ///
+    /// Package IDs are not changed.
+    pub fn relabel(
+        &mut self,
+        label: impl Fn(PackageMetadata<'_>) -> Option<String> + 'a,
+    ) -> &mut Self {
+        self.steps.push(Transform::Relabel(Box::new(label)));
+        self
+    }
+
+    /// Applies the transforms to this graph, returning the derived graph.
+    pub fn apply(&self, graph: &PackageGraph) -> Result<PackageGraph, Error> {
+        let mut current: Option<PackageGraph> = None;
+        for step in &self.steps {
+            let input = current.as_ref().unwrap_or(graph);
+            current = Some(step.apply(input)?);
+        }
+        Ok(current.unwrap_or_else(|| graph.clone()))
+    }
+}
+
+impl<'a> fmt::Debug for GraphTransforms<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.steps.iter().map(|step| step.name()))
+            .finish()
+    }
+}
+
+enum Transform<'a> {
+    DropDevEdges,
+    CollapseThirdParty,
+    MergeDuplicateVersions,
+    Relabel(Box<dyn Fn(PackageMetadata<'_>) -> Option<String> + 'a>),
+}
+
+impl<'a> Transform<'a> {
+    fn name(&self) -> &'static str {
+        match self {
+            Transform::DropDevEdges => "drop_dev_edges",
+            Transform::CollapseThirdParty => "collapse_third_party",
+            Transform::MergeDuplicateVersions => "merge_duplicate_versions",
+            Transform::Relabel(_) => "relabel",
+        }
+    }
+
+    fn apply(&self, graph: &PackageGraph) -> Result<PackageGraph, Error> {
+        let metadata = graph.to_cargo_metadata()?;
+        let value = serde_json::to_value(&metadata.0).map_err(Error::MetadataSerializeError)?;
+        let mut editor = Editor::new(value);
+
+        match self {
+            Transform::DropDevEdges => drop_dev_edges(graph, &mut editor),
+            Transform::CollapseThirdParty => collapse_third_party(graph, &mut editor),
+            Transform::MergeDuplicateVersions => {
+                let mut by_name: BTreeMap<&str, Vec<PackageMetadata<'_>>> = BTreeMap::new();
+                for package in graph.packages().filter(|package| !package.in_workspace()) {
+                    by_name.entry(package.name()).or_default().push(package);
+                }
+                let groups: Vec<_> = by_name
+                    .into_values()
+                    .filter(|packages| packages.len() > 1)
+                    .collect();
+                merge(graph, &mut editor, &groups, &HashMap::new(), false);
+            }
+            Transform::Relabel(label) => {
+                let mut names = HashMap::new();
+                let mut by_name: BTreeMap<String, Vec<PackageMetadata<'_>>> = BTreeMap::new();
+                for package in graph.packages() {
+                    let name = match label(package) {
+                        Some(name) => {
+                            if name != package.name() {
+                                names.insert(package.id().repr(), name.clone());
+                            }
+                            name
+                        }
+                        None => package.name().to_string(),
+                    };
+                    by_name.entry(name).or_default().push(package);
+                }
+                // Duplicate versions of packages that weren't relabeled are left alone.
+                let groups: Vec<_> = by_name
+                    .into_values()
+                    .filter(|packages| {
+                        packages.len() > 1
+                            && packages
+                                .iter()
+                                .any(|package| names.contains_key(package.id().repr()))
+                    })
+                    .collect();
+                merge(graph, &mut editor, &groups, &names, true);
+            }
+        }
+
+        let metadata = serde_json::from_value(editor.finish())
+            .map(CargoMetadata)
+            .map_err(Error::MetadataSerializeError)?;
+        PackageGraph::from_metadata(metadata)
+    }
+}
+
+fn drop_dev_edges(graph: &PackageGraph, editor: &mut Editor) {
+    for package in graph.packages() {
+        let id = package.id().repr();
+        array_mut(editor.package_mut(id), "dependencies").retain(|dep| dep["kind"] != "dev");
+
+        let dev_only: HashSet<_> = package
+            .direct_links()
+            .filter(|link| link.dev_only())
+            .map(|link| link.to().id().repr())
+            .collect();
+        let deps = array_mut(editor.node_mut(id), "deps");
+        deps.retain(|dep| !dev_only.contains(str_field(dep, "pkg")));
+        for dep in deps {
+            if let Some(kinds) = dep.get_mut("dep_kinds").and_then(Value::as_array_mut) {
+                kinds.retain(|kind| kind["kind"] != "dev");
+            }
+        }
+    }
+}
+
+fn collapse_third_party(graph: &PackageGraph, editor: &mut Editor) {
+    for package in graph.packages().filter(|package| !package.in_workspace()) {
+        let id = package.id().repr();
+        let package_value = editor.package_mut(id);
+        let features = package_value["features"]
+            .as_object_mut()
+            .expect("features is an object");
+        for values in features.values_mut() {
+            *values = json!([]);
+        }
+        // Optional dependencies are implicit features, so keep them around as named features.
+        for dep in package.inner.dependencies.iter().filter(|dep| dep.optional) {
+            let name = dep.rename.as_ref().unwrap_or(&dep.name);
+            features.entry(name.as_str()).or_insert_with(|| json!([]));
+        }
+        package_value["dependencies"] = json!([]);
+        editor.node_mut(id)["deps"] = json!([]);
+    }
+}
+
+/// Merges each group of packages into one representative package.
+///
+/// Links into merged packages are redirected to the representative, and packages in `names` are
+/// renamed. If `move_outgoing` is true, the dependencies of merged packages are moved over to the
+/// representative.
+fn merge(
+    graph: &PackageGraph,
+    editor: &mut Editor,
+    groups: &[Vec<PackageMetadata<'_>>],
+    names: &HashMap<&str, String>,
+    move_outgoing: bool,
+) {
+    // Pairs of (merged package, representative).
+    let mut merged = vec![];
+    for group in groups {
+        let representative = group
+            .iter()
+            .copied()
+            .max_by(|a, b| {
+                (a.in_workspace(), a.version())
+                    .cmp(&(b.in_workspace(), b.version()))
+                    // Prefer the first package by ID among equals.
+                    .then_with(|| b.id().cmp(a.id()))
+            })
+            .expect("groups are non-empty");
+        for package in group {
+            if package.id() != representative.id() {
+                merged.push((*package, representative));
+            }
+        }
+    }
+    // Sort merged packages so that the result doesn't depend on hash map ordering.
+    merged.sort_by(|a, b| a.0.id().cmp(b.0.id()));
+    let targets: HashMap<_, _> = merged
+        .iter()
+        .map(|(package, representative)| (package.id().repr(), *representative))
+        .collect();
+
+    // Update the declarations that link to renamed or merged packages. This is done before any
+    // declarations are moved around, so that they can be looked up by index.
+    for link in graph.packages().flat_map(|package| package.direct_links()) {
+        let to_id = link.to().id().repr();
+        let name = names.get(to_id);
+        let target = targets.get(to_id);
+        if name.is_none() && target.is_none() {
+            continue;
+        }
+        let from_id = link.from().id().repr();
+        for idx in link_decls(link) {
+            let decl = &mut array_mut(editor.package_mut(from_id), "dependencies")[idx];
+            // Links into merged packages take on the name of the representative.
+            let new_name = match target {
+                Some(target) => Some(
+                    names
+                        .get(target.id().repr())
+                        .map_or(target.name(), |name| name.as_str()),
+                ),
+                None => name.map(|name| name.as_str()),
+            };
+            if let Some(new_name) = new_name {
+                decl["name"] = json!(new_name);
+            }
+            if let Some(target) = target {
+                let matches = str_field(decl, "req")
+                    .parse::<VersionReq>()
+                    .map(|req| cargo_version_matches(&req, target.version()))
+                    .unwrap_or(true);
+                if !matches {
+                    decl["req"] = json!("*");
+                }
+            }
+        }
+    }
+
+    for (id, name) in names {
+        editor.package_mut(id)["name"] = json!(name);
+    }
+
+    let mut names_in_use = HashMap::new();
+    for (package, representative) in &merged {
+        if move_outgoing {
+            let names_in_use = names_in_use
+                .entry(representative.id())
+                .or_insert_with(|| resolved_names(*representative, &targets));
+            move_links(editor, *package, *representative, &targets, names_in_use);
+        }
+        editor.remove(package.id().repr());
+    }
+
+    // Redirect resolved dependencies, dropping self-links and duplicates.
+    for node in editor.nodes_mut() {
+        let node_id = str_field(node, "id").to_string();
+        let mut seen = HashSet::new();
+        array_mut(node, "deps").retain(|dep| {
+            let pkg = str_field(dep, "pkg");
+            let pkg = redirect(pkg, &targets);
+            pkg != node_id && seen.insert(pkg.to_string())
+        });
+        for dep in array_mut(node, "deps") {
+            if let Some(representative) = targets.get(str_field(dep, "pkg")) {
+                dep["pkg"] = json!(representative.id().repr());
+            }
+        }
+    }
+}
+
+/// Moves the links out of `package` over to `representative`, along with its features.
+///
+/// Resolved names need to stay unambiguous within the representative, so links whose resolved name
+/// is already used for another package are renamed. Declarations that aren't part of any link,
+/// such as disabled optional dependencies, are dropped.
+fn move_links(
+    editor: &mut Editor,
+    package: PackageMetadata<'_>,
+    representative: PackageMetadata<'_>,
+    targets: &HashMap<&str, PackageMetadata<'_>>,
+    names_in_use: &mut HashMap<String, String>,
+) {
+    let id = package.id().repr();
+    let representative_id = representative.id().repr();
+    let mut decls = vec![];
+    let mut deps = vec![];
+    for link in package.direct_links() {
+        let to = link.to();
+        let to_id = redirect(to.id().repr(), targets);
+        if to_id == representative_id {
+            continue;
+        }
+        let mut dep = match array_mut(editor.node_mut(id), "deps")
+            .iter()
+            .find(|dep| str_field(dep, "pkg") == to.id().repr())
+        {
+            Some(dep) => dep.clone(),
+            None => continue,
+        };
+        let mut link_decls: Vec<_> = link_decls(link)
+            .into_iter()
+            .map(|idx| editor.package_mut(id)["dependencies"][idx].clone())
+            .collect();
+
+        let resolved_name = link.resolved_name().to_string();
+        let name = match names_in_use.get(&resolved_name) {
+            Some(pkg) if pkg != to_id => {
+                let alias = (2..)
+                    .map(|n| format!("{}_{}", resolved_name, n))
+                    .find(|alias| !names_in_use.contains_key(alias))
+                    .expect("an unused alias exists");
+                for decl in &mut link_decls {
+                    decl["rename"] = json!(alias);
+                }
+                dep["name"] = json!(alias);
+                alias
+            }
+            _ => resolved_name,
+        };
+        names_in_use.insert(name, to_id.to_string());
+        decls.extend(link_decls);
+        deps.push(dep);
+    }
+
+    let features = editor.package_mut(id)["features"].take();
+    let package_value = editor.package_mut(representative_id);
+    array_mut(package_value, "dependencies").extend(decls);
+    let representative_features = package_value["features"]
+        .as_object_mut()
+        .expect("features is an object");
+    for (feature, values) in features.as_object().into_iter().flatten() {
+        let existing = representative_features
+            .entry(feature.as_str())
+            .or_insert_with(|| json!([]))
+            .as_array_mut()
+            .expect("feature values are an array");
+        for value in values.as_array().into_iter().flatten() {
+            if !existing.contains(value) {
+                existing.push(value.clone());
+            }
+        }
+    }
+    array_mut(editor.node_mut(representative_id), "deps").extend(deps);
+}
+
+/// Returns the resolved names used by this package, mapped to the IDs of the packages they refer
+/// to after merging.
+///
+/// Names used by renamed declarations are reserved even if they aren't part of a link, since
+/// cargo looks up resolved names among renamed declarations first.
+fn resolved_names(
+    package: PackageMetadata<'_>,
+    targets: &HashMap<&str, PackageMetadata<'_>>,
+) -> HashMap<String, String> {
+    let mut names: HashMap<_, _> = package
+        .inner
+        .dependencies
+        .iter()
+        .filter_map(|dep| match &dep.rename {
+            Some(rename) if rename != &dep.name => Some((rename.replace("-", "_"), String::new())),
+            _ => None,
+        })
+        .collect();
+    for link in package.direct_links() {
+        names.insert(
+            link.resolved_name().to_string(),
+            redirect(link.to().id().repr(), targets).to_string(),
+        );
+    }
+    names
+}
+
+/// Returns the ID of the package that links to this package should point to after merging.
+fn redirect<'a>(id: &'a str, targets: &HashMap<&str, PackageMetadata<'a>>) -> &'a str {
+    targets
+        .get(id)
+        .map_or(id, |representative| representative.id().repr())
+}
+
+/// Returns the indexes of the declarations in the `from` package that make up this link.
+fn link_decls(link: PackageLink<'_>) -> Vec<usize> {
+    let to = link.to();
+    link.from()
+        .inner
+        .dependencies
+        .iter()
+        .enumerate()
+        .filter(|(_, dep)| {
+            dep.name == to.name()
+                && dep.rename.as_ref().unwrap_or(&dep.name) == link.dep_name()
+                && cargo_version_matches(&dep.req, to.version())
+        })
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Edits `cargo metadata` output in place, looking packages and resolve nodes up by ID.
+struct Editor {
+    value: Value,
+    packages: HashMap<String, usize>,
+    nodes: HashMap<String, usize>,
+    removed: HashSet<String>,
+}
+
+impl Editor {
+    fn new(value: Value) -> Self {
+        let index = |values: &Value| -> HashMap<String, usize> {
+            values
+                .as_array()
+                .expect("value is an array")
+                .iter()
+                .enumerate()
+                .map(|(idx, value)| (str_field(value, "id").to_string(), idx))
+                .collect()
+        };
+        let packages = index(&value["packages"]);
+        let nodes = index(&value["resolve"]["nodes"]);
+        Self {
+            value,
+            packages,
+            nodes,
+            removed: HashSet::new(),
+        }
+    }
+
+    fn package_mut(&mut self, id: &str) -> &mut Value {
+        let idx = self.packages[id];
+        &mut self.value["packages"][idx]
+    }
+
+    fn node_mut(&mut self, id: &str) -> &mut Value {
+        let idx = self.nodes[id];
+        &mut self.value["resolve"]["nodes"][idx]
+    }
+
+    fn nodes_mut(&mut self) -> &mut Vec<Value> {
+        array_mut(&mut self.value["resolve"], "nodes")
+    }
+
+    fn remove(&mut self, id: &str) {
+        self.removed.insert(id.to_string());
+    }
+
+    /// Removes packages that were removed or are no longer reachable from the workspace, and
+    /// returns the edited metadata.
+    fn finish(mut self) -> Value {
+        let removed = self.removed;
+        array_mut(&mut self.value, "workspace_members")
+            .retain(|id| !removed.contains(id.as_str().expect("package IDs are strings")));
+
+        let nodes = array_mut(&mut self.value["resolve"], "nodes");
+        nodes.retain(|node| !removed.contains(str_field(node, "id")));
+        for node in nodes.iter_mut() {
+            let dependencies: Vec<_> = node["deps"]
+                .as_array()
+                .expect("deps is an array")
+                .iter()
+                .map(|dep| dep["pkg"].clone())
+                .collect();
+            node["dependencies"] = Value::Array(dependencies);
+        }
+
+        let deps: HashMap<&str, Vec<&str>> = self.value["resolve"]["nodes"]
+            .as_array()
+            .expect("nodes is an array")
+            .iter()
+            .map(|node| {
+                let deps = node["dependencies"]
+                    .as_array()
+                    .expect("dependencies is an array")
+                    .iter()
+                    .map(|id| id.as_str().expect("package IDs are strings"))
+                    .collect();
+                (str_field(node, "id"), deps)
+            })
+            .collect();
+        let mut reachable: HashSet<String> = HashSet::new();
+        let mut stack: Vec<&str> = self.value["workspace_members"]
+            .as_array()
+            .expect("workspace_members is an array")
+            .iter()
+            .map(|id| id.as_str().expect("package IDs are strings"))
+            .collect();
+        while let Some(id) = stack.pop() {
+            if reachable.insert(id.to_string()) {
+                stack.extend(deps.get(id).into_iter().flatten().copied());
+            }
+        }
+
+        array_mut(&mut self.value["resolve"], "nodes")
+            .retain(|node| reachable.contains(str_field(node, "id")));
+        array_mut(&mut self.value, "packages")
+            .retain(|package| reachable.contains(str_field(package, "id")));
+        self.value
+    }
+}
+
+fn array_mut<'a>(value: &'a mut Value, field: &str) -> &'a mut Vec<Value> {
+    value[field]
+        .as_array_mut()
+        .unwrap_or_else(|| panic!("field '{}' is an array", field))
+}
+
+fn str_field<'a>(value: &'a Value, field: &str) -> &'a str {
+    value[field]
+        .as_str()
+        .unwrap_or_else(|| panic!("field '{}' is a string", field))
+}
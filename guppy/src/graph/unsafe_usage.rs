@@ -0,0 +1,429 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Indicators of `unsafe` code and FFI surface in packages.
+//!
+//! Security reviews often prioritize dependencies by how much `unsafe` code they contain, and how
+//! much of it is reachable from a given binary. This module provides an optional pass that scans
+//! each package's source files and attaches `UnsafeStats` to it, with counts of `unsafe` blocks,
+//! functions and impls, and of `extern` blocks and functions with a non-Rust ABI. The stats can
+//! then be retrieved through `PackageMetadata::unsafe_stats`, and summed over or ranked within a
+//! `PackageSet`.
+//!
+//! Stats can also be computed elsewhere, for example from the output of `cargo geiger`, and
+//! attached with `PackageGraph::attach_unsafe_stats`.
+//!
+//! The scanner is a lightweight tokenizer rather than a full parser: it skips comments and string
+//! literals, but it doesn't expand macros or evaluate `cfg` attributes. Only the directories
+//! containing library, binary and procedural macro targets are scanned, so tests, examples and
+//! build scripts aren't counted.
+//!
+//! This module is experimental, and requires the `unstable` feature to be enabled.
+
+use crate::graph::{BuildTargetId, DependencyDirection, PackageGraph, PackageMetadata, PackageSet};
+use crate::{Error, PackageId};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign};
+use std::path::{Path, PathBuf};
+
+/// Counts of `unsafe` code and FFI items in a package.
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct UnsafeStats {
+    /// The number of `unsafe { ... }` blocks.
+    pub unsafe_blocks: usize,
+    /// The number of `unsafe fn` items, including methods.
+    pub unsafe_fns: usize,
+    /// The number of `unsafe impl` items.
+    pub unsafe_impls: usize,
+    /// The number of `extern` blocks and `extern fn` items with an ABI other than `"Rust"`, such
+    /// as `extern "C" { ... }`.
+    pub extern_items: usize,
+}
+
+impl UnsafeStats {
+    /// Counts `unsafe` code and FFI items in this Rust source.
+    pub fn from_source(source: &str) -> Self {
+        let tokens = tokenize(source);
+        let mut stats = Self::default();
+        for (idx, token) in tokens.iter().enumerate() {
+            let next = tokens.get(idx + 1);
+            match token {
+                Token::Ident("unsafe") => match next {
+                    Some(Token::Punct('{')) => stats.unsafe_blocks += 1,
+                    Some(Token::Ident("fn")) => stats.unsafe_fns += 1,
+                    Some(Token::Ident("impl")) => stats.unsafe_impls += 1,
+                    // unsafe extern "C" fn.
+                    Some(Token::Ident("extern")) if extern_fn_follows(&tokens[idx + 1..]) => {
+                        stats.unsafe_fns += 1
+                    }
+                    _ => {}
+                },
+                Token::Ident("extern") => {
+                    let (abi, rest) = match next {
+                        Some(Token::Str(abi)) => (*abi, &tokens[idx + 2..]),
+                        // The default ABI is "C".
+                        _ => ("C", &tokens[idx + 1..]),
+                    };
+                    let is_item = matches!(
+                        rest.first(),
+                        Some(Token::Punct('{')) | Some(Token::Ident("fn"))
+                    );
+                    if is_item && abi != "Rust" {
+                        stats.extern_items += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        stats
+    }
+
+    /// Returns true if no `unsafe` code or FFI items were counted.
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    /// Returns the sum of all counts.
+    pub fn total(&self) -> usize {
+        self.unsafe_blocks + self.unsafe_fns + self.unsafe_impls + self.extern_items
+    }
+}
+
+impl Add for UnsafeStats {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for UnsafeStats {
+    fn add_assign(&mut self, other: Self) {
+        self.unsafe_blocks += other.unsafe_blocks;
+        self.unsafe_fns += other.unsafe_fns;
+        self.unsafe_impls += other.unsafe_impls;
+        self.extern_items += other.extern_items;
+    }
+}
+
+impl Sum for UnsafeStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a UnsafeStats> for UnsafeStats {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+impl PackageGraph {
+    /// Scans the source files of every package in this graph, and attaches the resulting
+    /// `UnsafeStats` to each package.
+    ///
+    /// Stats from a previous scan or from `attach_unsafe_stats` are replaced. Packages whose
+    /// sources aren't on disk, for example because the graph was built from serialized metadata,
+    /// are skipped. Returns the number of packages that were scanned, or an error if a source file
+    /// couldn't be read.
+    pub fn scan_unsafe_usage(&mut self) -> Result<usize, Error> {
+        let mut stats = HashMap::new();
+        for package in self.packages() {
+            if let Some(package_stats) = scan_package(package)? {
+                stats.insert(package.id().clone(), package_stats);
+            }
+        }
+        let count = stats.len();
+        self.data.unsafe_stats = stats;
+        Ok(count)
+    }
+
+    /// Attaches stats computed elsewhere, such as from `cargo geiger` output, to packages in this
+    /// graph.
+    ///
+    /// Stats for packages that already have them are replaced. Returns an error if any package IDs
+    /// are unknown, in which case nothing is attached.
+    pub fn attach_unsafe_stats(
+        &mut self,
+        stats: impl IntoIterator<Item = (PackageId, UnsafeStats)>,
+    ) -> Result<(), Error> {
+        let stats: Vec<_> = stats.into_iter().collect();
+        for (package_id, _) in &stats {
+            self.metadata(package_id)
+                .ok_or_else(|| Error::UnknownPackageId(package_id.clone()))?;
+        }
+        self.data.unsafe_stats.extend(stats);
+        Ok(())
+    }
+}
+
+impl<'g> PackageMetadata<'g> {
+    /// Returns the `unsafe` usage stats attached to this package, if any.
+    ///
+    /// Stats are only available after `PackageGraph::scan_unsafe_usage` or
+    /// `PackageGraph::attach_unsafe_stats` has been called.
+    ///
+    /// Requires the `unstable` feature to be enabled.
+    pub fn unsafe_stats(&self) -> Option<&'g UnsafeStats> {
+        self.graph.data.unsafe_stats.get(self.id())
+    }
+}
+
+impl<'g> PackageSet<'g> {
+    /// Returns the sum of the `unsafe` usage stats of packages in this set.
+    ///
+    /// For the closure of a binary, this is its reachable `unsafe` surface. Packages without stats
+    /// are not counted.
+    ///
+    /// Requires the `unstable` feature to be enabled.
+    pub fn unsafe_stats(&self) -> UnsafeStats {
+        self.packages(DependencyDirection::Forward)
+            .filter_map(|package| package.unsafe_stats())
+            .sum()
+    }
+
+    /// Returns the packages in this set with any `unsafe` code or FFI items, along with their
+    /// stats, sorted by `UnsafeStats::total` in descending order.
+    ///
+    /// Requires the `unstable` feature to be enabled.
+    pub fn packages_by_unsafe_usage(&self) -> Vec<(PackageMetadata<'g>, &'g UnsafeStats)> {
+        let mut packages: Vec<_> = self
+            .packages(DependencyDirection::Forward)
+            .filter_map(|package| Some((package, package.unsafe_stats()?)))
+            .filter(|(_, stats)| !stats.is_empty())
+            .collect();
+        // sort_by_key is stable, so ties stay in topological order.
+        packages.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.total()));
+        packages
+    }
+}
+
+// ---
+// Scanning
+// ---
+
+/// Scans the sources for this package's library, binary and procedural macro targets.
+///
+/// Returns `None` if none of the source directories exist.
+fn scan_package(package: PackageMetadata<'_>) -> Result<Option<UnsafeStats>, Error> {
+    // Scan the directory containing each target's root file. Keep the shortest directories so
+    // that nested ones aren't scanned twice.
+    let dirs: BTreeSet<_> = package
+        .build_targets()
+        .filter(|target| {
+            matches!(
+                target.id(),
+                BuildTargetId::Library | BuildTargetId::Binary(_)
+            )
+        })
+        .filter_map(|target| target.path().parent())
+        .filter(|dir| dir.is_dir())
+        .collect();
+    let mut roots: Vec<&Path> = vec![];
+    for dir in dirs {
+        if !roots.iter().any(|root| dir.starts_with(root)) {
+            roots.push(dir);
+        }
+    }
+    if roots.is_empty() {
+        return Ok(None);
+    }
+
+    let mut stats = UnsafeStats::default();
+    for root in roots {
+        scan_dir(root, &mut stats)
+            .map_err(|(path, err)| Error::UnsafeScanError(package.id().clone(), path, err))?;
+    }
+    Ok(Some(stats))
+}
+
+fn scan_dir(dir: &Path, stats: &mut UnsafeStats) -> Result<(), (PathBuf, io::Error)> {
+    let entries = fs::read_dir(dir).map_err(|err| (dir.to_path_buf(), err))?;
+    let mut paths = vec![];
+    for entry in entries {
+        paths.push(entry.map_err(|err| (dir.to_path_buf(), err))?.path());
+    }
+    // Sort for determinism in error reporting.
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            // Nested packages are scanned separately.
+            if !path.join("Cargo.toml").exists() {
+                scan_dir(&path, stats)?;
+            }
+        } else if path.extension().and_then(|ext| ext.to_str()) == Some("rs") {
+            let source = fs::read_to_string(&path).map_err(|err| (path.clone(), err))?;
+            *stats += UnsafeStats::from_source(&source);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Token<'a> {
+    Ident(&'a str),
+    /// The contents of a string literal, without quotes.
+    Str(&'a str),
+    Punct(char),
+}
+
+/// Returns true if these tokens start with `extern "ABI" fn` or `extern fn`.
+fn extern_fn_follows(tokens: &[Token<'_>]) -> bool {
+    matches!(
+        tokens,
+        [
+            Token::Ident("extern"),
+            Token::Str(_),
+            Token::Ident("fn"),
+            ..
+        ] | [Token::Ident("extern"), Token::Ident("fn"), ..]
+    )
+}
+
+/// Splits Rust source into identifiers, string literals and punctuation, skipping whitespace,
+/// comments, numbers, and character and byte literals.
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let bytes = source.as_bytes();
+    let mut tokens = vec![];
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let b = bytes[idx];
+        match b {
+            b'/' if bytes.get(idx + 1) == Some(&b'/') => {
+                idx = find_from(bytes, idx, b"\n").unwrap_or(bytes.len());
+            }
+            b'/' if bytes.get(idx + 1) == Some(&b'*') => {
+                idx = skip_block_comment(bytes, idx);
+            }
+            b'"' => {
+                let end = skip_string(bytes, idx + 1);
+                // Exclude the closing quote, unless the string is unterminated.
+                let contents_end = if end > idx + 1 && bytes[end - 1] == b'"' {
+                    end - 1
+                } else {
+                    end
+                };
+                tokens.push(Token::Str(&source[idx + 1..contents_end]));
+                idx = end;
+            }
+            b'\'' => idx = skip_char_or_lifetime(source, idx),
+            b'r' | b'b' if raw_string_hashes(bytes, idx).is_some() => {
+                let (start, hashes) = raw_string_hashes(bytes, idx).expect("checked above");
+                let terminator = format!("\"{}", "#".repeat(hashes)).into_bytes();
+                let end = find_from(bytes, start, &terminator).unwrap_or(bytes.len());
+                tokens.push(Token::Str(&source[start..end]));
+                idx = (end + terminator.len()).min(bytes.len());
+            }
+            b'b' if bytes.get(idx + 1) == Some(&b'"') => {
+                idx = skip_string(bytes, idx + 2);
+            }
+            b'b' if bytes.get(idx + 1) == Some(&b'\'') => {
+                idx = skip_char_or_lifetime(source, idx + 1);
+            }
+            _ if b.is_ascii_alphabetic() || b == b'_' => {
+                let end = bytes[idx..]
+                    .iter()
+                    .position(|b| !(b.is_ascii_alphanumeric() || *b == b'_'))
+                    .map_or(bytes.len(), |len| idx + len);
+                tokens.push(Token::Ident(&source[idx..end]));
+                idx = end;
+            }
+            _ if b.is_ascii_digit() => {
+                // Skip numbers, including suffixes like 1u8 and 0xff, so they aren't mistaken for
+                // identifiers.
+                idx += bytes[idx..]
+                    .iter()
+                    .position(|b| !(b.is_ascii_alphanumeric() || *b == b'_'))
+                    .unwrap_or(bytes.len() - idx);
+            }
+            _ if b.is_ascii_whitespace() || !b.is_ascii() => idx += 1,
+            _ => {
+                tokens.push(Token::Punct(b as char));
+                idx += 1;
+            }
+        }
+    }
+    tokens
+}
+
+fn find_from(bytes: &[u8], start: usize, needle: &[u8]) -> Option<usize> {
+    bytes[start..]
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|pos| start + pos)
+}
+
+/// Returns the index after the end of the block comment starting at `idx`. Block comments nest.
+fn skip_block_comment(bytes: &[u8], mut idx: usize) -> usize {
+    let mut depth = 0;
+    while idx < bytes.len() {
+        if bytes[idx..].starts_with(b"/*") {
+            depth += 1;
+            idx += 2;
+        } else if bytes[idx..].starts_with(b"*/") {
+            depth -= 1;
+            idx += 2;
+            if depth == 0 {
+                return idx;
+            }
+        } else {
+            idx += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// Returns the index after the closing quote of the string whose contents start at `idx`.
+fn skip_string(bytes: &[u8], mut idx: usize) -> usize {
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'\\' => idx += 2,
+            b'"' => return idx + 1,
+            _ => idx += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Skips a character literal like `'a'` or `'\\n'`, or the quote of a lifetime like `'a`.
+fn skip_char_or_lifetime(source: &str, idx: usize) -> usize {
+    let bytes = source.as_bytes();
+    match source[idx + 1..].chars().next() {
+        Some('\\') => find_from(bytes, idx + 3, b"'").map_or(bytes.len(), |end| end + 1),
+        Some(c) if bytes.get(idx + 1 + c.len_utf8()) == Some(&b'\'') => idx + 2 + c.len_utf8(),
+        // A lifetime or label: the identifier is tokenized normally.
+        Some(_) => idx + 1,
+        None => bytes.len(),
+    }
+}
+
+/// If a raw string like `r#"..."#` or `br"..."` starts at `idx`, returns the index its contents
+/// start at and the number of hashes.
+fn raw_string_hashes(bytes: &[u8], idx: usize) -> Option<(usize, usize)> {
+    // Don't treat the end of an identifier like `bar"` as the start of a raw string.
+    if idx > 0 && (bytes[idx - 1].is_ascii_alphanumeric() || bytes[idx - 1] == b'_') {
+        return None;
+    }
+    let mut pos = idx;
+    if bytes[pos] == b'b' {
+        pos += 1;
+    }
+    if bytes.get(pos) != Some(&b'r') {
+        return None;
+    }
+    pos += 1;
+    let hashes = bytes[pos..].iter().take_while(|b| **b == b'#').count();
+    pos += hashes;
+    if bytes.get(pos) == Some(&b'"') {
+        Some((pos + 1, hashes))
+    } else {
+        None
+    }
+}
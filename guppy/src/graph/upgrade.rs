@@ -0,0 +1,158 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{DependencyDirection, PackageGraph, PackageLink, PackageMetadata};
+use crate::{Error, PackageId};
+use semver::Version;
+use std::collections::BTreeSet;
+
+impl PackageGraph {
+    /// Estimates the impact of changing the version of a package, without applying the change.
+    ///
+    /// Dependents whose version requirements match the new version are assumed to switch to it,
+    /// while the others keep using the current version. The new version's own dependencies and
+    /// feature definitions aren't known without resolving it, so they aren't part of the
+    /// estimate.
+    ///
+    /// Returns an error if the package ID is unknown.
+    ///
+    /// Requires the `unstable` feature to be enabled.
+    pub fn impact_of_upgrade(
+        &self,
+        package_id: &PackageId,
+        new_version: Version,
+    ) -> Result<UpgradeImpact<'_>, Error> {
+        let package = self
+            .metadata(package_id)
+            .ok_or_else(|| Error::UnknownPackageId(package_id.clone()))?;
+
+        let (upgraded, held_back): (Vec<_>, Vec<_>) = package
+            .reverse_direct_links()
+            .partition(|link| link.version_req().matches(&new_version));
+
+        // Only workspace members that reach the package through an upgraded link see a different
+        // closure. Cargo doesn't build dev-dependencies of non-workspace packages.
+        let affected_members = self
+            .query_reverse(upgraded.iter().map(|link| link.from().id()))
+            .expect("dependents should be known")
+            .resolve_with_fn(|_, link| !link.dev_only() || link.from().in_workspace())
+            .packages(DependencyDirection::Forward)
+            .filter(|package| package.in_workspace())
+            .collect();
+
+        let other_versions: Vec<_> = self
+            .packages()
+            .filter(|other| other.name() == package.name() && other.id() != package.id())
+            .map(|other| other.version().clone())
+            .collect();
+        let mut versions_before: BTreeSet<_> = other_versions.iter().cloned().collect();
+        versions_before.insert(package.version().clone());
+        let mut versions_after: BTreeSet<_> = other_versions.into_iter().collect();
+        if !upgraded.is_empty() {
+            versions_after.insert(new_version.clone());
+        }
+        if !held_back.is_empty() {
+            versions_after.insert(package.version().clone());
+        }
+
+        Ok(UpgradeImpact {
+            package,
+            new_version,
+            upgraded,
+            held_back,
+            affected_members,
+            versions_before,
+            versions_after,
+        })
+    }
+}
+
+/// The estimated impact of changing the version of a package.
+///
+/// Created through `PackageGraph::impact_of_upgrade`. Requires the `unstable` feature to be
+/// enabled.
+#[derive(Clone, Debug)]
+pub struct UpgradeImpact<'g> {
+    package: PackageMetadata<'g>,
+    new_version: Version,
+    upgraded: Vec<PackageLink<'g>>,
+    held_back: Vec<PackageLink<'g>>,
+    affected_members: Vec<PackageMetadata<'g>>,
+    versions_before: BTreeSet<Version>,
+    versions_after: BTreeSet<Version>,
+}
+
+impl<'g> UpgradeImpact<'g> {
+    /// Returns the package whose version is being changed.
+    pub fn package(&self) -> PackageMetadata<'g> {
+        self.package
+    }
+
+    /// Returns the proposed version.
+    pub fn new_version(&self) -> &Version {
+        &self.new_version
+    }
+
+    /// Returns the links from dependents whose version requirements match the new version.
+    pub fn upgraded_links<'a>(&'a self) -> impl Iterator<Item = PackageLink<'g>> + 'a {
+        self.upgraded.iter().copied()
+    }
+
+    /// Returns the links from dependents whose version requirements don't match the new version.
+    /// These dependents keep using the current version.
+    pub fn held_back_links<'a>(&'a self) -> impl Iterator<Item = PackageLink<'g>> + 'a {
+        self.held_back.iter().copied()
+    }
+
+    /// Returns the workspace members whose dependency closures would change, in forward
+    /// topological order.
+    pub fn affected_members<'a>(&'a self) -> impl Iterator<Item = PackageMetadata<'g>> + 'a {
+        self.affected_members.iter().copied()
+    }
+
+    /// Returns the versions of this package's name in the graph before the change.
+    pub fn versions_before(&self) -> &BTreeSet<Version> {
+        &self.versions_before
+    }
+
+    /// Returns the versions of this package's name that would be in the graph after the change.
+    pub fn versions_after(&self) -> &BTreeSet<Version> {
+        &self.versions_after
+    }
+
+    /// Returns true if the change would cause more versions of this package to be built than
+    /// before, for example because some dependents can't use the new version.
+    pub fn introduces_duplicate(&self) -> bool {
+        self.versions_after.len() > self.versions_before.len()
+    }
+
+    /// Returns the features that dependents switching to the new version request directly.
+    ///
+    /// These features are no longer enabled on the current version through these dependents.
+    pub fn upgraded_features(&self) -> BTreeSet<&'g str> {
+        requested_features(&self.upgraded)
+    }
+
+    /// Returns the features that dependents staying on the current version request directly.
+    pub fn held_back_features(&self) -> BTreeSet<&'g str> {
+        requested_features(&self.held_back)
+    }
+}
+
+/// Returns the features requested directly by these links. `default` is included if default
+/// features are enabled on any platform.
+fn requested_features<'g>(links: &[PackageLink<'g>]) -> BTreeSet<&'g str> {
+    let mut features = BTreeSet::new();
+    for link in links {
+        for req in &[link.normal(), link.build(), link.dev()] {
+            if !req.is_present() {
+                continue;
+            }
+            if req.default_features().enabled_on_any() {
+                features.insert("default");
+            }
+            features.extend(req.features());
+        }
+    }
+    features
+}
@@ -0,0 +1,185 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{CorrelationKey, PackageGraph, PackageMetadata};
+use crate::report::{Report, ReportDocument};
+use semver::Version;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// A comparison of resolved dependency versions across several package graphs.
+///
+/// This is typically used with graphs for the same code built in different ways: for example,
+/// with different feature sets, or before and after a workspace is split into several
+/// workspaces. Dependencies are correlated across graphs the same way `GraphPair` does it, by
+/// their `CorrelationKey`. Workspace members are skipped, since they typically move around in a
+/// split.
+///
+/// A dependency is *forked* if the graphs it appears in don't all resolve it to the same set of
+/// versions. Dependencies missing from some graphs aren't counted as forked.
+#[derive(Clone, Debug)]
+pub struct VersionComparison<'a> {
+    labels: Vec<&'a str>,
+    entries: Vec<VersionEntry<'a>>,
+}
+
+impl<'a> VersionComparison<'a> {
+    /// Compares resolved versions across these graphs, each identified by a label.
+    pub fn new(graphs: impl IntoIterator<Item = (&'a str, &'a PackageGraph)>) -> Self {
+        let graphs: Vec<_> = graphs.into_iter().collect();
+        let mut groups: BTreeMap<CorrelationKey, Vec<Vec<PackageMetadata<'a>>>> = BTreeMap::new();
+        for (idx, (_, graph)) in graphs.iter().enumerate() {
+            for package in graph.packages().filter(|package| !package.in_workspace()) {
+                groups
+                    .entry(CorrelationKey::new(package))
+                    .or_insert_with(|| vec![vec![]; graphs.len()])[idx]
+                    .push(package);
+            }
+        }
+
+        let entries = groups
+            .into_iter()
+            .map(|(key, mut packages)| {
+                for graph_packages in &mut packages {
+                    graph_packages.sort_by(|a, b| a.version().cmp(b.version()));
+                }
+                VersionEntry { key, packages }
+            })
+            .collect();
+
+        Self {
+            labels: graphs.into_iter().map(|(label, _)| label).collect(),
+            entries,
+        }
+    }
+
+    /// Returns the labels of the graphs being compared, in the order they were passed in.
+    pub fn labels(&self) -> &[&'a str] {
+        &self.labels
+    }
+
+    /// Iterates over every dependency in any of the graphs, ordered by name and source.
+    pub fn entries<'b>(&'b self) -> impl Iterator<Item = &'b VersionEntry<'a>> + 'b {
+        self.entries.iter()
+    }
+
+    /// Iterates over dependencies that resolve to different versions in different graphs.
+    pub fn forked<'b>(&'b self) -> impl Iterator<Item = &'b VersionEntry<'a>> + 'b {
+        self.entries.iter().filter(|entry| entry.is_forked())
+    }
+
+    /// Returns true if no dependencies are forked.
+    pub fn is_consistent(&self) -> bool {
+        self.forked().next().is_none()
+    }
+}
+
+impl<'a> fmt::Display for VersionComparison<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut forked = 0;
+        for entry in self.forked() {
+            forked += 1;
+            writeln!(f, "{}", entry.key)?;
+            for (label, packages) in self.labels.iter().zip(&entry.packages) {
+                if packages.is_empty() {
+                    writeln!(f, "    {}: (missing)", label)?;
+                } else {
+                    let versions: Vec<_> = packages
+                        .iter()
+                        .map(|package| package.version().to_string())
+                        .collect();
+                    writeln!(f, "    {}: {}", label, versions.join(", "))?;
+                }
+            }
+        }
+        writeln!(
+            f,
+            "{} dependencies across {} graphs, {} forked",
+            self.entries.len(),
+            self.labels.len(),
+            forked
+        )
+    }
+}
+
+impl<'a> Report for VersionComparison<'a> {
+    fn to_document(&self) -> ReportDocument {
+        let mut document = ReportDocument::new("Version comparison");
+        let section = document.add_section(None);
+        let mut forked = 0;
+        for entry in self.forked() {
+            forked += 1;
+            let item = section.add_item(entry.key.to_string());
+            for (label, packages) in self.labels.iter().zip(&entry.packages) {
+                if packages.is_empty() {
+                    item.add_detail(format!("{}: (missing)", label));
+                } else {
+                    let versions: Vec<_> = packages
+                        .iter()
+                        .map(|package| package.version().to_string())
+                        .collect();
+                    item.add_detail(format!("{}: {}", label, versions.join(", ")));
+                }
+            }
+        }
+        document.set_summary(format!(
+            "{} dependencies across {} graphs, {} forked",
+            self.entries.len(),
+            self.labels.len(),
+            forked
+        ));
+        document
+    }
+}
+
+/// A dependency correlated across the graphs in a `VersionComparison`.
+#[derive(Clone, Debug)]
+pub struct VersionEntry<'a> {
+    key: CorrelationKey,
+    // One list of packages per graph, sorted by version.
+    packages: Vec<Vec<PackageMetadata<'a>>>,
+}
+
+impl<'a> VersionEntry<'a> {
+    /// Returns the key this dependency was correlated by.
+    pub fn key(&self) -> &CorrelationKey {
+        &self.key
+    }
+
+    /// Returns the packages for this dependency in the graph at this index, sorted by version.
+    ///
+    /// This is empty if the dependency isn't present in that graph.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of range.
+    pub fn packages(&self, idx: usize) -> &[PackageMetadata<'a>] {
+        &self.packages[idx]
+    }
+
+    /// Returns every version this dependency resolves to, across all graphs.
+    pub fn versions(&self) -> BTreeSet<&'a Version> {
+        self.packages
+            .iter()
+            .flatten()
+            .map(|package| package.version())
+            .collect()
+    }
+
+    /// Returns true if the graphs this dependency is present in don't all resolve it to the same
+    /// set of versions.
+    pub fn is_forked(&self) -> bool {
+        let mut present = self
+            .packages
+            .iter()
+            .filter(|packages| !packages.is_empty())
+            .map(|packages| packages.iter().map(|package| package.version()));
+        match present.next() {
+            Some(first) => {
+                let first: Vec<_> = first.collect();
+                present.any(|versions| !versions.eq(first.iter().copied()))
+            }
+            None => false,
+        }
+    }
+}
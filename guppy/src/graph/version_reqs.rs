@@ -0,0 +1,444 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Analysis of declared version requirements.
+
+use crate::graph::{PackageGraph, PackageLink, PackageMetadata};
+use crate::report::{Report, ReportDocument};
+use semver::{Version, VersionReq};
+use std::collections::BTreeMap;
+use std::fmt;
+
+impl PackageGraph {
+    /// Analyzes the version requirements declared in the graph, looking for requirements that
+    /// are stricter than they need to be.
+    ///
+    /// Two kinds of issues are reported:
+    /// * Requirements declared by workspace members that are exact pins (`=1.2.3`), or narrower
+    ///   than the default caret requirement with the same lower bound (e.g. `~1.2` or
+    ///   `>= 1.2, < 1.5`).
+    /// * Packages with several versions in the graph, where some dependents declare requirements
+    ///   that don't match the highest version. These requirements prevent the versions from being
+    ///   unified into one. Dependents outside the workspace are included here, since the fix may
+    ///   need to happen upstream.
+    ///
+    /// Each issue comes with a suggestion for the loosest requirement that keeps the same lower
+    /// bound, or for conflicts that can't be fixed that way, a requirement on the highest version.
+    pub fn requirement_report(&self) -> RequirementReport<'_> {
+        let mut findings = vec![];
+        for (_, member) in self.workspace().members() {
+            for link in member.direct_links() {
+                let shape = match ReqShape::new(link.version_req()) {
+                    Some(shape) => shape,
+                    None => continue,
+                };
+                if let (Some(kind), Some(lower)) = (shape.kind, shape.lower) {
+                    findings.push(RequirementFinding {
+                        link,
+                        kind,
+                        suggestion: lower.to_string(),
+                    });
+                }
+            }
+        }
+        findings.sort_by(|a, b| {
+            (a.link.from().name(), a.link.to().name())
+                .cmp(&(b.link.from().name(), b.link.to().name()))
+        });
+
+        let mut by_name: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for package in self.packages() {
+            by_name.entry(package.name()).or_default().push(package);
+        }
+        let conflicts = by_name
+            .into_iter()
+            .filter(|(_, packages)| packages.len() > 1)
+            .filter_map(|(name, mut packages)| {
+                packages.sort_by(|a, b| a.version().cmp(b.version()));
+                let highest = *packages.last().expect("at least two packages");
+                let blocking: Vec<_> = packages
+                    .iter()
+                    .filter(|package| package.id() != highest.id())
+                    .flat_map(|package| package.reverse_direct_links())
+                    .filter(|link| !link.version_req().matches(highest.version()))
+                    .map(|link| BlockingRequirement {
+                        link,
+                        suggestion: unifying_suggestion(link.version_req(), highest.version()),
+                    })
+                    .collect();
+                // Without blocking requirements, the duplicates can be removed by updating the
+                // lockfile.
+                if blocking.is_empty() {
+                    None
+                } else {
+                    Some(RequirementConflict {
+                        name,
+                        packages,
+                        blocking,
+                    })
+                }
+            })
+            .collect();
+
+        RequirementReport {
+            findings,
+            conflicts,
+        }
+    }
+}
+
+/// A report of overly strict version requirements in a package graph.
+///
+/// Returned by `PackageGraph::requirement_report`. The `Display` implementation renders the
+/// report in a human-readable format.
+#[derive(Clone, Debug)]
+pub struct RequirementReport<'g> {
+    findings: Vec<RequirementFinding<'g>>,
+    conflicts: Vec<RequirementConflict<'g>>,
+}
+
+impl<'g> RequirementReport<'g> {
+    /// Returns exact pins and narrow ranges declared by workspace members, sorted by the names of
+    /// the packages on either side of the dependency.
+    pub fn findings(&self) -> &[RequirementFinding<'g>] {
+        &self.findings
+    }
+
+    /// Returns packages with several versions in the graph that can't be unified because of the
+    /// requirements of some dependents, sorted by name.
+    pub fn conflicts(&self) -> &[RequirementConflict<'g>] {
+        &self.conflicts
+    }
+
+    /// Returns true if no issues were found.
+    pub fn is_empty(&self) -> bool {
+        self.findings.is_empty() && self.conflicts.is_empty()
+    }
+}
+
+impl<'g> fmt::Display for RequirementReport<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for finding in &self.findings {
+            writeln!(
+                f,
+                "{} -> {}: {} \"{}\", consider \"{}\"",
+                finding.link.from().name(),
+                finding.link.to().name(),
+                finding.kind,
+                finding.link.version_req(),
+                finding.suggestion
+            )?;
+        }
+        for conflict in &self.conflicts {
+            let versions: Vec<_> = conflict
+                .packages
+                .iter()
+                .map(|package| package.version().to_string())
+                .collect();
+            writeln!(
+                f,
+                "{} has {} versions ({})",
+                conflict.name,
+                versions.len(),
+                versions.join(", ")
+            )?;
+            for blocking in &conflict.blocking {
+                writeln!(
+                    f,
+                    "    {} {} requires \"{}\", consider \"{}\"",
+                    blocking.link.from().name(),
+                    blocking.link.from().version(),
+                    blocking.link.version_req(),
+                    blocking.suggestion
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'g> Report for RequirementReport<'g> {
+    fn to_document(&self) -> ReportDocument {
+        let mut document = ReportDocument::new("Version requirements");
+        let section = document.add_section(Some("Exact pins and narrow ranges".to_string()));
+        for finding in &self.findings {
+            section.add_item(format!(
+                "{} -> {}: {} \"{}\", consider \"{}\"",
+                finding.link.from().name(),
+                finding.link.to().name(),
+                finding.kind,
+                finding.link.version_req(),
+                finding.suggestion
+            ));
+        }
+        let section = document.add_section(Some("Conflicts".to_string()));
+        for conflict in &self.conflicts {
+            let versions: Vec<_> = conflict
+                .packages
+                .iter()
+                .map(|package| package.version().to_string())
+                .collect();
+            let item = section.add_item(format!(
+                "{} has {} versions ({})",
+                conflict.name,
+                versions.len(),
+                versions.join(", ")
+            ));
+            for blocking in &conflict.blocking {
+                item.add_detail(format!(
+                    "{} {} requires \"{}\", consider \"{}\"",
+                    blocking.link.from().name(),
+                    blocking.link.from().version(),
+                    blocking.link.version_req(),
+                    blocking.suggestion
+                ));
+            }
+        }
+        document.set_summary(format!(
+            "{} overly strict requirements, {} packages that can't be unified",
+            self.findings.len(),
+            self.conflicts.len()
+        ));
+        document
+    }
+}
+
+/// A version requirement declared by a workspace member that is stricter than it needs to be.
+#[derive(Clone, Debug)]
+pub struct RequirementFinding<'g> {
+    link: PackageLink<'g>,
+    kind: RequirementFindingKind,
+    suggestion: String,
+}
+
+impl<'g> RequirementFinding<'g> {
+    /// Returns the dependency with this requirement.
+    pub fn link(&self) -> PackageLink<'g> {
+        self.link
+    }
+
+    /// Returns the kind of issue with this requirement.
+    pub fn kind(&self) -> RequirementFindingKind {
+        self.kind
+    }
+
+    /// Returns the suggested requirement, in the format used in manifests.
+    ///
+    /// This is the caret requirement with the same lower bound, so it doesn't allow any older
+    /// versions than the current requirement does.
+    pub fn suggestion(&self) -> &str {
+        &self.suggestion
+    }
+}
+
+/// The kind of a `RequirementFinding`.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum RequirementFindingKind {
+    /// The requirement only matches a single version, e.g. `=1.2.3`.
+    ExactPin,
+    /// The requirement excludes semver-compatible versions above its lower bound, e.g. `~1.2` or
+    /// `>= 1.2, < 1.5`.
+    NarrowRange,
+}
+
+impl fmt::Display for RequirementFindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequirementFindingKind::ExactPin => write!(f, "exact pin"),
+            RequirementFindingKind::NarrowRange => write!(f, "narrow range"),
+        }
+    }
+}
+
+/// A package with several versions in the graph, where some requirements prevent unifying them.
+#[derive(Clone, Debug)]
+pub struct RequirementConflict<'g> {
+    name: &'g str,
+    packages: Vec<PackageMetadata<'g>>,
+    blocking: Vec<BlockingRequirement<'g>>,
+}
+
+impl<'g> RequirementConflict<'g> {
+    /// Returns the name of the package.
+    pub fn name(&self) -> &'g str {
+        self.name
+    }
+
+    /// Returns every version of this package in the graph, sorted by version.
+    pub fn packages(&self) -> &[PackageMetadata<'g>] {
+        &self.packages
+    }
+
+    /// Returns the highest version of this package in the graph, which the other versions could
+    /// be unified into.
+    pub fn highest(&self) -> PackageMetadata<'g> {
+        *self
+            .packages
+            .last()
+            .expect("conflicts have at least two packages")
+    }
+
+    /// Returns the requirements that don't match the highest version.
+    pub fn blocking(&self) -> &[BlockingRequirement<'g>] {
+        &self.blocking
+    }
+}
+
+/// A requirement that prevents unifying the versions of a package.
+#[derive(Clone, Debug)]
+pub struct BlockingRequirement<'g> {
+    link: PackageLink<'g>,
+    suggestion: String,
+}
+
+impl<'g> BlockingRequirement<'g> {
+    /// Returns the dependency with this requirement.
+    ///
+    /// The dependent may not be in the workspace.
+    pub fn link(&self) -> PackageLink<'g> {
+        self.link
+    }
+
+    /// Returns the suggested requirement, in the format used in manifests.
+    ///
+    /// If the caret requirement with the same lower bound matches the highest version, that is
+    /// suggested. Otherwise, the suggestion is a requirement on the highest version, which is an
+    /// upgrade across a semver-incompatible boundary.
+    pub fn suggestion(&self) -> &str {
+        &self.suggestion
+    }
+}
+
+// ---
+// Helper methods
+// ---
+
+fn unifying_suggestion(req: &VersionReq, highest: &Version) -> String {
+    if let Some(lower) = ReqShape::new(req).and_then(|shape| shape.lower) {
+        let caret = VersionReq::parse(&format!("^{}", lower)).expect("valid caret requirement");
+        if caret.matches(highest) {
+            return lower.to_string();
+        }
+    }
+    format!("{}.{}.{}", highest.major, highest.minor, highest.patch)
+}
+
+/// The parts of a version requirement that matter for this analysis.
+///
+/// `semver` doesn't expose the predicates of a `VersionReq`, so this is recovered from its
+/// `Display` output, e.g. `>= 1.2, < 1.5`.
+struct ReqShape {
+    lower: Option<PartialVersion>,
+    kind: Option<RequirementFindingKind>,
+}
+
+impl ReqShape {
+    fn new(req: &VersionReq) -> Option<Self> {
+        let req = req.to_string();
+        let mut lower = None;
+        let mut upper = None;
+        let mut exact = false;
+        let mut narrow = false;
+        for predicate in req.split(", ") {
+            if predicate == "*" {
+                continue;
+            }
+            if let Some(version) = predicate.strip_prefix("= ") {
+                exact = true;
+                lower = Some(PartialVersion::parse(version)?);
+            } else if let Some(version) = predicate
+                .strip_prefix(">= ")
+                .or_else(|| predicate.strip_prefix("> "))
+                .or_else(|| predicate.strip_prefix('^'))
+            {
+                lower = Some(PartialVersion::parse(version)?);
+            } else if let Some(version) = predicate
+                .strip_prefix("<= ")
+                .or_else(|| predicate.strip_prefix("< "))
+            {
+                upper = Some(PartialVersion::parse(version)?);
+            } else {
+                // Tilde requirements and wildcards like `1.2.*` fix the minor version.
+                let version = match predicate.strip_prefix('~') {
+                    Some(version) => version,
+                    None => {
+                        let mut version = predicate;
+                        while let Some(rest) = version.strip_suffix(".*") {
+                            version = rest;
+                        }
+                        version
+                    }
+                };
+                let version = PartialVersion::parse(version)?;
+                // For 0.x versions, caret requirements already fix the minor version.
+                narrow |= version.major > 0 && version.minor.is_some();
+                lower = Some(version);
+            }
+        }
+
+        let kind = if exact {
+            Some(RequirementFindingKind::ExactPin)
+        } else {
+            match (lower, upper) {
+                (Some(lower), Some(upper)) if upper.triple() < lower.caret_ceiling() => {
+                    Some(RequirementFindingKind::NarrowRange)
+                }
+                (Some(_), _) if narrow => Some(RequirementFindingKind::NarrowRange),
+                _ => None,
+            }
+        };
+        Some(Self { lower, kind })
+    }
+}
+
+/// A version as written in a requirement, where the minor and patch versions may be missing.
+#[derive(Copy, Clone, Debug)]
+struct PartialVersion {
+    major: u64,
+    minor: Option<u64>,
+    patch: Option<u64>,
+}
+
+impl PartialVersion {
+    fn parse(version: &str) -> Option<Self> {
+        // Pre-release and build metadata don't affect the analysis.
+        let version = version.split(&['-', '+'][..]).next()?;
+        let mut split = version.split('.');
+        let major = split.next()?.parse().ok()?;
+        let minor = split.next().map(|minor| minor.parse()).transpose().ok()?;
+        let patch = split.next().map(|patch| patch.parse()).transpose().ok()?;
+        Some(Self {
+            major,
+            minor,
+            patch,
+        })
+    }
+
+    fn triple(&self) -> (u64, u64, u64) {
+        (self.major, self.minor.unwrap_or(0), self.patch.unwrap_or(0))
+    }
+
+    /// Returns the first version that the caret requirement on this version doesn't match.
+    fn caret_ceiling(&self) -> (u64, u64, u64) {
+        match (self.major, self.minor, self.patch) {
+            (major, None, _) => (major + 1, 0, 0),
+            (major, _, _) if major > 0 => (major + 1, 0, 0),
+            (_, Some(minor), None) => (0, minor + 1, 0),
+            (_, Some(minor), _) if minor > 0 => (0, minor + 1, 0),
+            (_, _, Some(patch)) => (0, 0, patch + 1),
+        }
+    }
+}
+
+impl fmt::Display for PartialVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.major)?;
+        if let Some(minor) = self.minor {
+            write!(f, ".{}", minor)?;
+        }
+        if let Some(patch) = self.patch {
+            write!(f, ".{}", patch)?;
+        }
+        Ok(())
+    }
+}
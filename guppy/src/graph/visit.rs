@@ -0,0 +1,157 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{
+    DependencyDirection, PackageGraph, PackageLink, PackageMetadata, PackageSet, SetOrigin,
+};
+use crate::{Error, PackageId};
+use fixedbitset::FixedBitSet;
+
+impl PackageGraph {
+    /// Visits the transitive dependencies of the given packages depth-first, calling `visitor` for
+    /// every dependency link encountered.
+    ///
+    /// The visitor decides whether to follow each link, which makes it possible to prune parts of
+    /// the graph as soon as they're known to be uninteresting, rather than resolving everything
+    /// first. For each link, it has access to a `VisitContext` describing the path from the root
+    /// the traversal started at.
+    ///
+    /// Every package is expanded at most once, so each link is passed to the visitor at most once.
+    ///
+    /// Returns the packages reached by the traversal, including the initial ones. Returns an error
+    /// if any package IDs are unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use guppy::graph::{PackageGraph, VisitAction};
+    /// use guppy::PackageId;
+    ///
+    /// let graph = PackageGraph::from_json(include_str!("../../../fixtures/small/metadata1.json"))
+    ///     .unwrap();
+    /// let testcrate = PackageId::new("testcrate 0.1.0 (path+file:///fakepath/testcrate)");
+    ///
+    /// // Only look at direct dependencies that aren't dev-only.
+    /// let direct = graph
+    ///     .visit(&[testcrate], |link, ctx| {
+    ///         if ctx.depth() > 0 || link.dev_only() {
+    ///             VisitAction::Prune
+    ///         } else {
+    ///             VisitAction::Continue
+    ///         }
+    ///     })
+    ///     .unwrap();
+    /// assert!(direct.len() > 1);
+    /// ```
+    pub fn visit<'g, 'a>(
+        &'g self,
+        package_ids: impl IntoIterator<Item = &'a PackageId>,
+        visitor: impl FnMut(PackageLink<'g>, &VisitContext<'g, '_>) -> VisitAction,
+    ) -> Result<PackageSet<'g>, Error> {
+        self.visit_directed(package_ids, DependencyDirection::Forward, visitor)
+    }
+
+    /// Visits the transitive dependencies or reverse dependencies of the given packages
+    /// depth-first, calling `visitor` for every dependency link encountered.
+    ///
+    /// Links are passed in unreversed form, so for reverse traversals the package being expanded
+    /// is `link.to()` and the package the link leads to is `link.from()`.
+    ///
+    /// See `visit` for more.
+    pub fn visit_directed<'g, 'a>(
+        &'g self,
+        package_ids: impl IntoIterator<Item = &'a PackageId>,
+        direction: DependencyDirection,
+        mut visitor: impl FnMut(PackageLink<'g>, &VisitContext<'g, '_>) -> VisitAction,
+    ) -> Result<PackageSet<'g>, Error> {
+        let roots = package_ids
+            .into_iter()
+            .map(|package_id| {
+                self.metadata(package_id)
+                    .ok_or_else(|| Error::UnknownPackageId(package_id.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut visited = FixedBitSet::with_capacity(self.package_count());
+        // The links from the current root to the package whose links are being iterated over.
+        let mut path: Vec<PackageLink<'g>> = vec![];
+
+        'roots: for root in roots {
+            if visited.put(root.package_ix().index()) {
+                continue;
+            }
+
+            let mut stack = vec![root.direct_links_directed(direction)];
+            while let Some(links) = stack.last_mut() {
+                let link = match links.next() {
+                    Some(link) => link,
+                    None => {
+                        stack.pop();
+                        path.pop();
+                        continue;
+                    }
+                };
+
+                let ctx = VisitContext { root, path: &path };
+                match visitor(link, &ctx) {
+                    VisitAction::Continue => {
+                        let next = match direction {
+                            DependencyDirection::Forward => link.to(),
+                            DependencyDirection::Reverse => link.from(),
+                        };
+                        if !visited.put(next.package_ix().index()) {
+                            path.push(link);
+                            stack.push(next.direct_links_directed(direction));
+                        }
+                    }
+                    VisitAction::Prune => {}
+                    VisitAction::Stop => break 'roots,
+                }
+            }
+        }
+
+        Ok(PackageSet::from_included(self, visited, SetOrigin::Visit))
+    }
+}
+
+/// What a visitor passed to `PackageGraph::visit` wants to do with a link.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum VisitAction {
+    /// Follow this link, and visit the links of the package it leads to if it hasn't been visited
+    /// yet.
+    Continue,
+
+    /// Don't follow this link. The package it leads to is only reached if another link leads to
+    /// it.
+    Prune,
+
+    /// Stop the traversal. The packages reached so far are returned.
+    Stop,
+}
+
+/// Information about where a traversal started by `PackageGraph::visit` currently is.
+#[derive(Clone, Debug)]
+pub struct VisitContext<'g, 'a> {
+    root: PackageMetadata<'g>,
+    path: &'a [PackageLink<'g>],
+}
+
+impl<'g, 'a> VisitContext<'g, 'a> {
+    /// Returns the initial package this part of the traversal started from.
+    pub fn root(&self) -> PackageMetadata<'g> {
+        self.root
+    }
+
+    /// Returns the links followed from the root to the package being expanded, in order.
+    ///
+    /// This doesn't include the link currently being visited.
+    pub fn path(&self) -> &'a [PackageLink<'g>] {
+        self.path
+    }
+
+    /// Returns the number of links between the root and the package being expanded. This is 0 for
+    /// links out of the root.
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+/// A normalized path relative to the workspace root, using `/` as the separator on every platform.
+///
+/// Paths like these can be written out to files and compared across platforms: the same
+/// workspace checked out on Windows and Linux produces the same `WorkspacePath` instances. Use
+/// `to_native_path` or `to_absolute_path` to get a path suitable for the current platform.
+///
+/// A `WorkspacePath` never contains `.` or `..` components, and the workspace root itself is
+/// represented by the empty string.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct WorkspacePath(Box<str>);
+
+impl WorkspacePath {
+    /// Creates a new `WorkspacePath` from a path relative to the workspace root. Either `/` or the
+    /// platform's separator may be used.
+    ///
+    /// Returns `None` if the path is absolute, is not valid UTF-8 or points outside the workspace
+    /// root.
+    pub fn new(path: impl AsRef<Path>) -> Option<Self> {
+        let mut components: Vec<&str> = vec![];
+        for component in path.as_ref().components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    components.pop()?;
+                }
+                Component::Normal(component) => components.push(component.to_str()?),
+                Component::Prefix(_) | Component::RootDir => return None,
+            }
+        }
+        Some(Self(components.join("/").into_boxed_str()))
+    }
+
+    /// Creates a new `WorkspacePath` for an absolute path within the workspace root.
+    ///
+    /// Returns `None` if the path isn't within the workspace root or is not valid UTF-8.
+    pub fn from_absolute(workspace_root: impl AsRef<Path>, path: impl AsRef<Path>) -> Option<Self> {
+        let workspace_root = normalize(workspace_root.as_ref());
+        let path = normalize(path.as_ref());
+        Self::new(path.strip_prefix(&workspace_root).ok()?)
+    }
+
+    /// Returns the path as a string, with `/` as the separator.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns true if this is the workspace root.
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the components of this path.
+    pub fn components(&self) -> impl Iterator<Item = &str> {
+        self.0.split('/').filter(|component| !component.is_empty())
+    }
+
+    /// Returns this path as a relative path using the current platform's separator.
+    pub fn to_native_path(&self) -> PathBuf {
+        self.components().collect()
+    }
+
+    /// Joins this path onto the workspace root, returning an absolute path for the current
+    /// platform.
+    pub fn to_absolute_path(&self, workspace_root: impl AsRef<Path>) -> PathBuf {
+        workspace_root.as_ref().join(self.to_native_path())
+    }
+}
+
+impl fmt::Display for WorkspacePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for WorkspacePath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// Every platform Rust supports accepts `/` as a separator, so this is a valid relative path.
+impl AsRef<Path> for WorkspacePath {
+    fn as_ref(&self) -> &Path {
+        Path::new(&*self.0)
+    }
+}
+
+/// Normalizes a path lexically, without accessing the file system.
+pub(super) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
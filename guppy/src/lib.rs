@@ -45,24 +45,55 @@
 //!
 //! For more examples, see
 //! [the `examples` directory](https://github.com/facebookincubator/cargo-guppy/tree/master/guppy/examples).
+//!
+//! # Stability
+//!
+//! APIs available by default follow semantic versioning. Experimental query and simulation APIs,
+//! which may change or be removed in minor releases, are only available with the `unstable`
+//! feature:
+//!
+//! * the maintenance signal enrichment pass in `graph::maintenance`
+//! * estimating the impact of a version change through `PackageGraph::impact_of_upgrade`
+//! * dependency freshness scores and health reports in `graph::health`
+//! * the minimal-versions check in `graph::minimal_versions`
+//! * dependency graph metrics over a range of commits in `graph::history`
+//! * analyzing many graphs in parallel with `graph::batch`
+//! * package taxonomy tags in `graph::taxonomy`
+//! * unsafe usage and FFI surface indicators in `graph::unsafe_usage`
+//!
+//! Enabling `unstable` only adds APIs: the `Error` variants they return are present either way.
+//!
+//! `VERSION_INFO` reports the version of `guppy`, which `cargo metadata` output formats it
+//! supports and whether unstable APIs are enabled.
 
 #![warn(missing_docs)]
 
+pub mod cargo_config;
 mod debug_ignore;
 mod dependency_kind;
 pub mod errors;
+mod file_source;
+mod git;
 pub mod graph;
 mod metadata_command;
+mod names;
 mod package_id;
 pub(crate) mod petgraph_support;
+pub mod report;
 pub(crate) mod sorted_set;
+pub mod testing;
+pub mod toolchain;
 #[cfg(test)]
 mod unit_tests;
+mod version_info;
 
 pub use dependency_kind::*;
 pub use errors::Error;
+pub use file_source::*;
 pub use metadata_command::*;
-pub use package_id::PackageId;
+pub use names::{FeatureName, PackageName};
+pub use package_id::{PackageId, PackageIdParts, PackageSpec};
+pub use version_info::*;
 
 // Public re-exports for upstream crates used in APIs. The no_inline ensures that they show up as
 // re-exports in documentation.
@@ -72,4 +103,4 @@ pub use semver::Version;
 pub use serde_json::Value as JsonValue;
 // These are inlined -- generally, treat target_spec as a private dependency so expose these types
 // as part of guppy's API.
-pub use target_spec::{Platform, TargetFeatures};
+pub use target_spec::{Platform, PlatformSpec, SpecCoverage, TargetFeatures, TargetSpec};
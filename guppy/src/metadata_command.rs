@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::graph::PackageGraph;
-use crate::Error;
+use crate::{Error, RealFileSource};
 use cargo_metadata::CargoOpt;
 use serde::{Deserialize, Serialize};
 use std::io;
@@ -26,6 +26,10 @@ use std::path::Path;
 #[derive(Clone, Debug, Default)]
 pub struct MetadataCommand {
     inner: cargo_metadata::MetadataCommand,
+    offline: bool,
+    cargo_config: bool,
+    toolchain: bool,
+    other_options: Vec<String>,
 }
 
 impl MetadataCommand {
@@ -37,7 +41,13 @@ impl MetadataCommand {
         let mut inner = cargo_metadata::MetadataCommand::new();
         // Always use --all-features so that we get a full view of the graph.
         inner.features(CargoOpt::AllFeatures);
-        Self { inner }
+        Self {
+            inner,
+            offline: false,
+            cargo_config: false,
+            toolchain: false,
+            other_options: Vec::new(),
+        }
     }
 
     /// Sets the path to the `cargo` executable.
@@ -66,6 +76,39 @@ impl MetadataCommand {
         self
     }
 
+    /// Runs `cargo metadata` without accessing the network, by passing in `--offline`.
+    ///
+    /// Dependencies that aren't already downloaded cause `cargo metadata` to fail.
+    pub fn offline(&mut self, offline: bool) -> &mut Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Reads Cargo configuration files while building the graph, making them available through
+    /// `Workspace::cargo_config`.
+    ///
+    /// Files are looked for in the workspace root, its ancestors and `$CARGO_HOME`, so this makes
+    /// the graph depend on the environment it's built in, beyond the workspace. If a file can't
+    /// be read or parsed, `build_graph` fails.
+    ///
+    /// This is off by default.
+    pub fn cargo_config(&mut self, discover: bool) -> &mut Self {
+        self.cargo_config = discover;
+        self
+    }
+
+    /// Reads toolchain files while building the graph, making them available through
+    /// `Workspace::toolchain` and `PackageMetadata::toolchain`.
+    ///
+    /// Files are looked for in the workspace root, and in the directories between each workspace
+    /// member and the root. Files that can't be read or parsed are skipped.
+    ///
+    /// This is off by default.
+    pub fn toolchain(&mut self, discover: bool) -> &mut Self {
+        self.toolchain = discover;
+        self
+    }
+
     // *Do not* implement no_deps or features.
 
     /// Arbitrary flags to pass to `cargo metadata`. These will be added to the end of the
@@ -77,13 +120,22 @@ impl MetadataCommand {
     /// * does not pass in `--no-deps`, so that `guppy` knows about non-workspace dependencies.
     ///
     /// Attempting to override either of those options may lead to unexpected results.
+    ///
+    /// Calling this again replaces the options that were previously set. The `--offline` flag set
+    /// with `offline` is passed in separately, so it isn't affected.
     pub fn other_options(&mut self, options: impl AsRef<[String]>) -> &mut Self {
-        self.inner.other_options(options);
+        self.other_options = options.as_ref().to_vec();
         self
     }
 
     /// Runs the configured `cargo metadata` and returns a deserialized `CargoMetadata`.
     pub fn exec(&mut self) -> Result<CargoMetadata, Error> {
+        let mut options = Vec::with_capacity(self.other_options.len() + 1);
+        if self.offline {
+            options.push("--offline".to_string());
+        }
+        options.extend(self.other_options.iter().cloned());
+        self.inner.other_options(options);
         let inner = self.inner.exec().map_err(Error::command_error)?;
         Ok(CargoMetadata(inner))
     }
@@ -91,7 +143,12 @@ impl MetadataCommand {
     /// Runs the configured `cargo metadata` and returns a parsed `PackageGraph`.
     pub fn build_graph(&mut self) -> Result<PackageGraph, Error> {
         let metadata = self.exec()?;
-        metadata.build_graph()
+        PackageGraph::build(
+            metadata.0,
+            &RealFileSource,
+            self.cargo_config,
+            self.toolchain,
+        )
     }
 }
 
@@ -0,0 +1,158 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::Error;
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+/// The maximum length of a package name on crates.io.
+const MAX_PACKAGE_NAME_LEN: usize = 64;
+
+/// A package name that follows the crates.io naming rules.
+///
+/// A valid name:
+/// * is between 1 and 64 characters long,
+/// * starts with an ASCII letter, and
+/// * only contains ASCII letters, digits, `-` and `_`.
+///
+/// Packages that don't come from crates.io may have names that don't follow these rules, so
+/// `PackageMetadata::name` returns a plain string.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct PackageName(String);
+
+impl PackageName {
+    /// Creates a new `PackageName`, returning an error if the name isn't valid.
+    pub fn new(name: impl Into<String>) -> Result<Self, Error> {
+        let name = name.into();
+        let invalid = |msg: &str| Err(Error::InvalidPackageName(name.clone(), msg.to_string()));
+
+        match name.chars().next() {
+            None => return invalid("name is empty"),
+            Some(c) if !c.is_ascii_alphabetic() => {
+                return invalid("name must start with an ASCII letter")
+            }
+            Some(_) => {}
+        }
+        if name.len() > MAX_PACKAGE_NAME_LEN {
+            return invalid("name is longer than 64 characters");
+        }
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return invalid("name must only contain ASCII letters, digits, '-' and '_'");
+        }
+        Ok(Self(name))
+    }
+
+    /// Returns the name as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the name that the package's library is imported as, with `-` replaced by `_`.
+    pub fn lib_name(&self) -> String {
+        self.0.replace('-', "_")
+    }
+}
+
+/// A feature name that follows the crates.io naming rules.
+///
+/// A valid name:
+/// * is not empty,
+/// * starts with an ASCII letter, digit or `_`, and
+/// * only contains ASCII letters, digits, `_`, `-`, `+` and `.`.
+///
+/// Names of the form `dep:name` or `dep/feature` are feature *values*, not names, and are
+/// rejected.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FeatureName(String);
+
+impl FeatureName {
+    /// Creates a new `FeatureName`, returning an error if the name isn't valid.
+    pub fn new(name: impl Into<String>) -> Result<Self, Error> {
+        let name = name.into();
+        let invalid = |msg: &str| Err(Error::InvalidFeatureName(name.clone(), msg.to_string()));
+
+        match name.chars().next() {
+            None => return invalid("name is empty"),
+            Some(c) if !(c.is_ascii_alphanumeric() || c == '_') => {
+                return invalid("name must start with an ASCII letter, digit or '_'")
+            }
+            Some(_) => {}
+        }
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '+' || c == '.')
+        {
+            return invalid("name must only contain ASCII letters, digits, '_', '-', '+' and '.'");
+        }
+        Ok(Self(name))
+    }
+
+    /// Returns the name as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+macro_rules! name_impls {
+    ($name: ident) => {
+        impl FromStr for $name {
+            type Err = Error;
+
+            fn from_str(name: &str) -> Result<Self, Self::Err> {
+                Self::new(name)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(name: $name) -> Self {
+                name.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+    };
+}
+
+name_impls!(PackageName);
+name_impls!(FeatureName);
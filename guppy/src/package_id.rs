@@ -1,26 +1,37 @@
 // Copyright (c) The cargo-guppy Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::graph::PackageMetadata;
+use crate::Error;
+use semver::Version;
 use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
 
 /// An "opaque" identifier for a package.
+///
+/// Cloning a `PackageId` is cheap, since the underlying representation is reference-counted.
+///
+/// The format of package IDs has changed across Cargo versions. Use `parts` to get at the
+/// components of an ID, and `PackageSpec` to refer to packages in a format that's stable across
+/// Cargo versions.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 #[allow(clippy::derive_hash_xor_eq)] // safe because the same PartialEq impl is used everywhere
 pub struct PackageId {
     /// The underlying string representation of an ID.
-    repr: Box<str>,
+    repr: Arc<str>,
 }
 
 impl PackageId {
     /// Creates a new `PackageId`.
     pub fn new(s: impl Into<Box<str>>) -> Self {
-        Self { repr: s.into() }
+        Self {
+            repr: s.into().into(),
+        }
     }
 
     pub(super) fn from_metadata(id: cargo_metadata::PackageId) -> Self {
-        Self {
-            repr: id.repr.into_boxed_str(),
-        }
+        Self::new(id.repr)
     }
 
     /// Returns the inner representation of a package ID. This is generally an opaque string and its
@@ -28,6 +39,15 @@ impl PackageId {
     pub fn repr(&self) -> &str {
         &self.repr
     }
+
+    /// Parses the name, version and source out of this package ID.
+    ///
+    /// Both the `name version (source)` format used by older versions of Cargo and the
+    /// `source#name@version` format used by newer versions are supported. Returns `None` if the
+    /// ID is in neither format, e.g. if it was constructed by hand.
+    pub fn parts(&self) -> Option<PackageIdParts<'_>> {
+        PackageIdParts::parse(&self.repr)
+    }
 }
 
 impl fmt::Display for PackageId {
@@ -47,3 +67,278 @@ impl PartialEq<PackageId> for &PackageId {
         (*self).eq(other)
     }
 }
+
+/// The components of a `PackageId`.
+///
+/// Returned by `PackageId::parts`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PackageIdParts<'a> {
+    name: &'a str,
+    version: Version,
+    source: &'a str,
+}
+
+impl<'a> PackageIdParts<'a> {
+    /// Returns the name of the package.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the version of the package.
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// Returns the source URL of the package, including the kind of source, e.g.
+    /// `registry+https://github.com/rust-lang/crates.io-index` or `path+file:///path/to/package`.
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Returns the source URL of the package without the kind of source or any query string or
+    /// fragment, e.g. `https://github.com/rust-lang/crates.io-index`.
+    pub fn source_url(&self) -> &'a str {
+        strip_url(self.source)
+    }
+
+    /// Returns a fully qualified `PackageSpec` for this package, in the format printed by
+    /// `cargo pkgid`.
+    pub fn to_spec(&self) -> PackageSpec {
+        PackageSpec {
+            name: self.name.to_string(),
+            version: Some(self.version.to_string()),
+            url: Some(strip_fragment(self.source).to_string()),
+        }
+    }
+
+    fn parse(repr: &'a str) -> Option<Self> {
+        // The format used before Cargo 1.77: "name version (source)".
+        if let Some(rest) = repr.strip_suffix(')') {
+            let mut split = rest.splitn(3, ' ');
+            let name = split.next()?;
+            let version = split.next()?;
+            let source = split.next()?.strip_prefix('(')?;
+            return Some(Self {
+                name,
+                version: Version::parse(version).ok()?,
+                source,
+            });
+        }
+
+        // The newer format: "source#name@version", where the name is omitted if it's the same as
+        // the last path segment of the URL.
+        let fragment_idx = repr.rfind('#')?;
+        let (source, fragment) = (&repr[..fragment_idx], &repr[fragment_idx + 1..]);
+        let (name, version) = match fragment.rfind('@') {
+            Some(idx) => (&fragment[..idx], &fragment[idx + 1..]),
+            None => (last_segment(source)?, fragment),
+        };
+        Some(Self {
+            name,
+            version: Version::parse(version).ok()?,
+            source,
+        })
+    }
+}
+
+/// A package specification, in any of the formats accepted by Cargo's `-p` option and printed by
+/// `cargo pkgid`.
+///
+/// Examples of valid specifications:
+/// * `serde`
+/// * `serde@1.0.101` or `serde:1.0.101`
+/// * `serde@1`, which matches any 1.x.y version
+/// * `https://github.com/rust-lang/crates.io-index#serde@1.0.101`
+/// * `registry+https://github.com/rust-lang/crates.io-index#serde@1.0.101`
+/// * `file:///path/to/serde#1.0.101`, where the name is the last path segment
+///
+/// For more, see [Package ID
+/// specifications](https://doc.rust-lang.org/cargo/reference/pkgid-spec.html) in the Cargo
+/// reference.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct PackageSpec {
+    name: String,
+    version: Option<String>,
+    url: Option<String>,
+}
+
+impl PackageSpec {
+    /// Parses a package specification.
+    pub fn parse(spec: &str) -> Result<Self, Error> {
+        let invalid = |msg: &str| Error::PackageSpecParseError(spec.to_string(), msg.to_string());
+
+        let (url, name_version) = if spec.contains("://") {
+            match spec.rfind('#') {
+                Some(idx) => (Some(&spec[..idx]), &spec[idx + 1..]),
+                None => (Some(spec), ""),
+            }
+        } else {
+            (None, spec)
+        };
+
+        let (name, version) = match name_version.rfind(&['@', ':'][..]) {
+            Some(idx) => {
+                let (name, version) = (&name_version[..idx], &name_version[idx + 1..]);
+                (Some(name), Some(version))
+            }
+            None if name_version.is_empty() => (None, None),
+            None => match url {
+                // A fragment without a name is a version if it starts with a digit.
+                Some(_) if name_version.starts_with(|c: char| c.is_ascii_digit()) => {
+                    (None, Some(name_version))
+                }
+                _ => (Some(name_version), None),
+            },
+        };
+
+        let name = match (name, url) {
+            (Some(""), _) => return Err(invalid("package name is empty")),
+            (Some(name), _) => name.to_string(),
+            (None, Some(url)) => last_segment(url)
+                .ok_or_else(|| invalid("URL has no path to take the package name from"))?
+                .to_string(),
+            (None, None) => return Err(invalid("package name is empty")),
+        };
+        if !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(invalid("package name contains invalid characters"));
+        }
+
+        if let Some(version) = version {
+            parse_partial_version(version).ok_or_else(|| invalid("invalid version"))?;
+        }
+
+        Ok(Self {
+            name,
+            version: version.map(|version| version.to_string()),
+            url: url.map(|url| url.to_string()),
+        })
+    }
+
+    /// Returns the package name in this specification.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the version in this specification, if any. This may be a partial version like
+    /// `1.2`.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Returns the source URL in this specification, if any.
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    /// Returns true if this specification matches the given package ID.
+    ///
+    /// Returns false if the components of the package ID can't be parsed.
+    pub fn matches_id(&self, package_id: &PackageId) -> bool {
+        let parts = match package_id.parts() {
+            Some(parts) => parts,
+            None => return false,
+        };
+        if parts.name() != self.name() {
+            return false;
+        }
+        if let Some(version) = &self.version {
+            let (major, minor, patch, rest) =
+                parse_partial_version(version).expect("version was validated on parse");
+            let actual = parts.version();
+            let matches = actual.major == major
+                && minor.iter().all(|minor| actual.minor == *minor)
+                && patch.iter().all(|patch| actual.patch == *patch)
+                && (rest.is_empty() || actual.to_string() == *version);
+            if !matches {
+                return false;
+            }
+        }
+        match &self.url {
+            Some(url) => strip_url(url) == parts.source_url(),
+            None => true,
+        }
+    }
+
+    /// Returns true if this specification matches the given package.
+    pub fn matches(&self, package: &PackageMetadata<'_>) -> bool {
+        self.matches_id(package.id())
+    }
+}
+
+impl FromStr for PackageSpec {
+    type Err = Error;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        Self::parse(spec)
+    }
+}
+
+impl fmt::Display for PackageSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(url) = &self.url {
+            write!(f, "{}#", url)?;
+        }
+        write!(f, "{}", self.name())?;
+        if let Some(version) = &self.version {
+            write!(f, "@{}", version)?;
+        }
+        Ok(())
+    }
+}
+
+// ---
+// Helper functions
+// ---
+
+/// Parses a version with optional minor and patch components. Anything after the patch version
+/// (pre-release and build metadata) is returned as is.
+fn parse_partial_version(version: &str) -> Option<(u64, Option<u64>, Option<u64>, &str)> {
+    let (numbers, rest) = match version.find(&['-', '+'][..]) {
+        Some(idx) => (&version[..idx], &version[idx..]),
+        None => (version, ""),
+    };
+    let mut split = numbers.split('.');
+    let major = split.next()?.parse().ok()?;
+    let minor = split.next().map(|minor| minor.parse()).transpose().ok()?;
+    let patch = split.next().map(|patch| patch.parse()).transpose().ok()?;
+    if split.next().is_some() || (!rest.is_empty() && patch.is_none()) {
+        return None;
+    }
+    if !rest.is_empty() {
+        Version::parse(version).ok()?;
+    }
+    Some((major, minor, patch, rest))
+}
+
+/// Strips the fragment from a source URL.
+fn strip_fragment(url: &str) -> &str {
+    match url.find('#') {
+        Some(idx) => &url[..idx],
+        None => url,
+    }
+}
+
+/// Strips the kind of source, query string and fragment from a source URL.
+fn strip_url(url: &str) -> &str {
+    let url = match url.find('+') {
+        Some(idx) if url[..idx].chars().all(|c| c.is_ascii_lowercase()) => &url[idx + 1..],
+        _ => url,
+    };
+    let url = strip_fragment(url);
+    let url = match url.find('?') {
+        Some(idx) => &url[..idx],
+        None => url,
+    };
+    url.trim_end_matches('/')
+}
+
+/// Returns the last path segment of a URL.
+fn last_segment(url: &str) -> Option<&str> {
+    strip_url(url)
+        .rsplit('/')
+        .next()
+        .filter(|segment| !segment.is_empty() && !segment.contains(':'))
+}
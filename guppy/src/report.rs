@@ -0,0 +1,350 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Rendering analysis reports in several output formats.
+//!
+//! Analysis reports such as `DevWeightReport` or `RequirementReport` implement `Report`, which
+//! describes their contents as a `ReportDocument`: a title, sections of nested items and a
+//! summary line. A `Renderer` turns a document into output. Text, Markdown, JSON and HTML
+//! renderers are provided, and other formats can be supported by implementing `Renderer`.
+//!
+//! # Examples
+//!
+//! ```
+//! use guppy::CargoMetadata;
+//! use guppy::report::{MarkdownRenderer, Report};
+//!
+//! let metadata = CargoMetadata::parse_json(include_str!("../../fixtures/small/metadata1.json")).unwrap();
+//! let package_graph = metadata.build_graph().unwrap();
+//!
+//! let markdown = package_graph.dev_weight_report().render(&MarkdownRenderer);
+//! assert!(markdown.starts_with("# Dev-dependency weight\n"));
+//! ```
+
+use serde::Serialize;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+/// An analysis report that can be rendered in any output format.
+pub trait Report {
+    /// Describes the contents of this report as a document.
+    fn to_document(&self) -> ReportDocument;
+
+    /// Renders this report with the given renderer.
+    fn render(&self, renderer: &dyn Renderer) -> String {
+        renderer.render(&self.to_document())
+    }
+}
+
+/// Renders report documents in an output format.
+pub trait Renderer {
+    /// Writes the document to `out`.
+    fn render_to(&self, document: &ReportDocument, out: &mut dyn Write) -> fmt::Result;
+
+    /// Renders the document to a string.
+    fn render(&self, document: &ReportDocument) -> String {
+        let mut out = String::new();
+        self.render_to(document, &mut out)
+            .expect("writing to a String doesn't fail");
+        out
+    }
+}
+
+/// The contents of a report, independent of output format.
+///
+/// The `Serialize` implementation is what `JsonRenderer` writes out.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ReportDocument {
+    title: String,
+    sections: Vec<ReportSection>,
+    summary: Option<String>,
+}
+
+impl ReportDocument {
+    /// Creates a new, empty document with this title.
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            sections: vec![],
+            summary: None,
+        }
+    }
+
+    /// Returns the title of this document.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Returns the sections of this document, in order.
+    pub fn sections(&self) -> &[ReportSection] {
+        &self.sections
+    }
+
+    /// Returns the summary of this document, if any.
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+
+    /// Adds a section with this heading, and returns it so that items can be added to it.
+    ///
+    /// Reports consisting of a single list use a section without a heading.
+    pub fn add_section(&mut self, heading: Option<String>) -> &mut ReportSection {
+        self.sections.push(ReportSection {
+            heading,
+            items: vec![],
+        });
+        self.sections.last_mut().expect("just pushed a section")
+    }
+
+    /// Sets the summary of this document, typically a line of counts.
+    pub fn set_summary(&mut self, summary: impl Into<String>) -> &mut Self {
+        self.summary = Some(summary.into());
+        self
+    }
+}
+
+/// A section of a `ReportDocument`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ReportSection {
+    heading: Option<String>,
+    items: Vec<ReportItem>,
+}
+
+impl ReportSection {
+    /// Returns the heading of this section, if any.
+    pub fn heading(&self) -> Option<&str> {
+        self.heading.as_deref()
+    }
+
+    /// Returns the items in this section, in order.
+    pub fn items(&self) -> &[ReportItem] {
+        &self.items
+    }
+
+    /// Adds an item to this section, and returns it so that details can be added to it.
+    pub fn add_item(&mut self, text: impl Into<String>) -> &mut ReportItem {
+        self.items.push(ReportItem::new(text));
+        self.items.last_mut().expect("just pushed an item")
+    }
+}
+
+/// An item in a `ReportSection`, with any number of nested details.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ReportItem {
+    text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    details: Vec<ReportItem>,
+}
+
+impl ReportItem {
+    fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            details: vec![],
+        }
+    }
+
+    /// Returns the text of this item.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the details nested under this item, in order.
+    pub fn details(&self) -> &[ReportItem] {
+        &self.details
+    }
+
+    /// Adds a detail to this item, and returns it so that further details can be nested under it.
+    pub fn add_detail(&mut self, text: impl Into<String>) -> &mut ReportItem {
+        self.details.push(ReportItem::new(text));
+        self.details.last_mut().expect("just pushed a detail")
+    }
+}
+
+/// The output formats supported out of the box.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ReportFormat {
+    /// Plain text, rendered with `TextRenderer`.
+    Text,
+    /// Markdown, rendered with `MarkdownRenderer`.
+    Markdown,
+    /// JSON, rendered with `JsonRenderer`.
+    Json,
+    /// An HTML fragment, rendered with `HtmlRenderer`.
+    Html,
+}
+
+impl ReportFormat {
+    /// A list of all the formats supported out of the box.
+    pub const VALUES: &'static [Self] = &[
+        ReportFormat::Text,
+        ReportFormat::Markdown,
+        ReportFormat::Json,
+        ReportFormat::Html,
+    ];
+
+    /// Returns the name of this format, as accepted by `FromStr`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportFormat::Text => "text",
+            ReportFormat::Markdown => "markdown",
+            ReportFormat::Json => "json",
+            ReportFormat::Html => "html",
+        }
+    }
+
+    /// Returns the renderer for this format.
+    pub fn renderer(self) -> &'static dyn Renderer {
+        match self {
+            ReportFormat::Text => &TextRenderer,
+            ReportFormat::Markdown => &MarkdownRenderer,
+            ReportFormat::Json => &JsonRenderer,
+            ReportFormat::Html => &HtmlRenderer,
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::VALUES
+            .iter()
+            .copied()
+            .find(|format| format.as_str() == s)
+            .ok_or_else(|| format!("unknown report format: {}", s))
+    }
+}
+
+/// Renders documents as plain text, with details indented by four spaces per level.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TextRenderer;
+
+impl Renderer for TextRenderer {
+    fn render_to(&self, document: &ReportDocument, out: &mut dyn Write) -> fmt::Result {
+        writeln!(out, "{}", document.title)?;
+        for section in &document.sections {
+            writeln!(out)?;
+            if let Some(heading) = &section.heading {
+                writeln!(out, "{}:", heading)?;
+            }
+            for item in &section.items {
+                write_text_item(out, item, 0)?;
+            }
+        }
+        if let Some(summary) = &document.summary {
+            writeln!(out)?;
+            writeln!(out, "{}", summary)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_text_item(out: &mut dyn Write, item: &ReportItem, depth: usize) -> fmt::Result {
+    writeln!(out, "{:indent$}{}", "", item.text, indent = depth * 4)?;
+    for detail in &item.details {
+        write_text_item(out, detail, depth + 1)?;
+    }
+    Ok(())
+}
+
+/// Renders documents as Markdown, with sections as headings and items as nested lists.
+///
+/// Item text is written out as is, so it may contain Markdown formatting.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MarkdownRenderer;
+
+impl Renderer for MarkdownRenderer {
+    fn render_to(&self, document: &ReportDocument, out: &mut dyn Write) -> fmt::Result {
+        writeln!(out, "# {}", document.title)?;
+        for section in &document.sections {
+            if let Some(heading) = &section.heading {
+                writeln!(out)?;
+                writeln!(out, "## {}", heading)?;
+            }
+            if !section.items.is_empty() {
+                writeln!(out)?;
+            }
+            for item in &section.items {
+                write_markdown_item(out, item, 0)?;
+            }
+        }
+        if let Some(summary) = &document.summary {
+            writeln!(out)?;
+            writeln!(out, "{}", summary)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_markdown_item(out: &mut dyn Write, item: &ReportItem, depth: usize) -> fmt::Result {
+    writeln!(out, "{:indent$}- {}", "", item.text, indent = depth * 2)?;
+    for detail in &item.details {
+        write_markdown_item(out, detail, depth + 1)?;
+    }
+    Ok(())
+}
+
+/// Renders documents as JSON, using the `Serialize` implementation of `ReportDocument`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct JsonRenderer;
+
+impl Renderer for JsonRenderer {
+    fn render_to(&self, document: &ReportDocument, out: &mut dyn Write) -> fmt::Result {
+        let json = serde_json::to_string_pretty(document).map_err(|_| fmt::Error)?;
+        writeln!(out, "{}", json)
+    }
+}
+
+/// Renders documents as an HTML fragment, with sections as headings and items as nested lists.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct HtmlRenderer;
+
+impl Renderer for HtmlRenderer {
+    fn render_to(&self, document: &ReportDocument, out: &mut dyn Write) -> fmt::Result {
+        writeln!(out, "<h1>{}</h1>", HtmlEscaped(&document.title))?;
+        for section in &document.sections {
+            if let Some(heading) = &section.heading {
+                writeln!(out, "<h2>{}</h2>", HtmlEscaped(heading))?;
+            }
+            write_html_items(out, &section.items)?;
+        }
+        if let Some(summary) = &document.summary {
+            writeln!(out, "<p>{}</p>", HtmlEscaped(summary))?;
+        }
+        Ok(())
+    }
+}
+
+fn write_html_items(out: &mut dyn Write, items: &[ReportItem]) -> fmt::Result {
+    if items.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "<ul>")?;
+    for item in items {
+        write!(out, "<li>{}", HtmlEscaped(&item.text))?;
+        if !item.details.is_empty() {
+            writeln!(out)?;
+            write_html_items(out, &item.details)?;
+        }
+        writeln!(out, "</li>")?;
+    }
+    writeln!(out, "</ul>")
+}
+
+struct HtmlEscaped<'a>(&'a str);
+
+impl<'a> fmt::Display for HtmlEscaped<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Helpers for snapshot tests of reports derived from package graphs.
+//!
+//! Output like diffs between graphs or DOT renderings often contains absolute paths, along with
+//! the hashes Cargo uses to name directories for registries and git checkouts. These differ
+//! across machines, so tests that compare such output against a checked-in file need to scrub
+//! them first. A `Redactor` replaces them with stable placeholders, and `assert_snapshot` compares
+//! the result against a snapshot file.
+//!
+//! # Examples
+//!
+//! ```
+//! use guppy::graph::PackageGraph;
+//! use guppy::testing::Redactor;
+//!
+//! let graph = PackageGraph::from_json(include_str!("../../fixtures/small/metadata1.json")).unwrap();
+//! let redactor = Redactor::for_graph(&graph);
+//!
+//! let testcrate = graph.workspace().member_by_path("").unwrap();
+//! let report = format!("{}", testcrate.manifest_path().display());
+//! assert_eq!(redactor.redact(&report), "[WORKSPACE]/Cargo.toml");
+//! ```
+
+use crate::graph::PackageGraph;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// The environment variable that causes `assert_snapshot` to update snapshot files instead of
+/// comparing against them.
+pub static UPDATE_SNAPSHOTS_ENV: &str = "GUPPY_UPDATE_SNAPSHOTS";
+
+// Directories within the Cargo home whose children are named `<name>-<hash>`.
+static HASHED_DIRS: &[&str] = &[
+    "registry/src/",
+    "registry/index/",
+    "registry/cache/",
+    "git/checkouts/",
+    "git/db/",
+];
+
+/// Replaces machine-specific strings in text with stable placeholders.
+///
+/// Redactions are applied in this order:
+/// 1. Every registered string is replaced with its placeholder, longest strings first, so that a
+///    target directory within the workspace root is replaced with its own placeholder.
+/// 2. The hashes in the names of registry and git directories within the Cargo home, such as
+///    `github.com-1ecc6299db9ec823`, are replaced with `[HASH]`. The revision directories within
+///    git checkouts are replaced with `[REV]`.
+#[derive(Clone, Debug, Default)]
+pub struct Redactor {
+    replacements: Vec<(String, String)>,
+}
+
+impl Redactor {
+    /// Creates a new `Redactor` that only redacts hashes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `Redactor` for output derived from this graph.
+    ///
+    /// This redacts the workspace root as `[WORKSPACE]`, the target directory as `[TARGET]`, and
+    /// the Cargo home directory that third-party packages are stored in as `[CARGO_HOME]`.
+    pub fn for_graph(graph: &PackageGraph) -> Self {
+        let workspace = graph.workspace();
+        let mut redactor = Self::new()
+            .with_path(workspace.root(), "[WORKSPACE]")
+            .with_path(workspace.target_directory(), "[TARGET]");
+
+        let mut cargo_homes: Vec<String> = graph
+            .packages()
+            .filter_map(|package| cargo_home(&package.manifest_path().to_string_lossy()))
+            .collect();
+        cargo_homes.sort();
+        cargo_homes.dedup();
+        for cargo_home in cargo_homes {
+            redactor = redactor.with_text(cargo_home, "[CARGO_HOME]");
+        }
+        redactor
+    }
+
+    /// Redacts this path, as displayed on this platform and with `/` as the separator.
+    pub fn with_path(self, path: impl AsRef<Path>, placeholder: impl Into<String>) -> Self {
+        let path = path.as_ref().to_string_lossy();
+        let placeholder = placeholder.into();
+        let forward_slashes = path.replace('\\', "/");
+        let redactor = self.with_text(path.into_owned(), placeholder.clone());
+        redactor.with_text(forward_slashes, placeholder)
+    }
+
+    /// Redacts this string.
+    pub fn with_text(mut self, text: impl Into<String>, placeholder: impl Into<String>) -> Self {
+        let text = text.into();
+        if !text.is_empty()
+            && self
+                .replacements
+                .iter()
+                .all(|(existing, _)| *existing != text)
+        {
+            self.replacements.push((text, placeholder.into()));
+        }
+        self
+    }
+
+    /// Returns a redacted copy of this text.
+    pub fn redact(&self, text: &str) -> String {
+        let mut replacements: Vec<_> = self.replacements.iter().collect();
+        replacements.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+
+        let mut text = text.to_string();
+        for (from, to) in replacements {
+            text = text.replace(from.as_str(), to);
+        }
+        redact_hashes(&text)
+    }
+}
+
+/// Compares `actual` against the snapshot file at `path`. Line endings are normalized before the
+/// comparison, so snapshots checked out on Windows still match.
+///
+/// If the `GUPPY_UPDATE_SNAPSHOTS` environment variable is set, the snapshot file is written out
+/// instead.
+///
+/// # Panics
+///
+/// Panics if the snapshot file is missing or doesn't match, or if it can't be written out.
+pub fn assert_snapshot(path: impl AsRef<Path>, actual: impl AsRef<str>) {
+    let update = env::var_os(UPDATE_SNAPSHOTS_ENV).is_some();
+    if let Err(msg) = check_snapshot(path.as_ref(), actual.as_ref(), update) {
+        panic!("{}", msg);
+    }
+}
+
+pub(crate) fn check_snapshot(path: &Path, actual: &str, update: bool) -> Result<(), String> {
+    let actual = actual.replace("\r\n", "\n");
+    if update {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| format!("error creating directory {:?}: {}", parent, err))?;
+        }
+        return fs::write(path, actual)
+            .map_err(|err| format!("error writing snapshot {:?}: {}", path, err));
+    }
+
+    let expected = match fs::read_to_string(path) {
+        Ok(expected) => expected.replace("\r\n", "\n"),
+        Err(err) => {
+            return Err(format!(
+                "error reading snapshot {:?}: {} (set {} to create it)",
+                path, err, UPDATE_SNAPSHOTS_ENV
+            ))
+        }
+    };
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(format!(
+            "snapshot {:?} doesn't match (set {} to update it)\n\
+             --- expected\n{}\n--- actual\n{}",
+            path, UPDATE_SNAPSHOTS_ENV, expected, actual
+        ))
+    }
+}
+
+/// Returns the Cargo home directory for this manifest path, if it's within one.
+fn cargo_home(manifest_path: &str) -> Option<String> {
+    let manifest_path = manifest_path.replace('\\', "/");
+    ["/registry/src/", "/git/checkouts/"]
+        .iter()
+        .filter_map(|marker| manifest_path.find(marker))
+        .min()
+        .map(|idx| manifest_path[..idx].to_string())
+}
+
+fn redact_hashes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((idx, marker)) = HASHED_DIRS
+        .iter()
+        .filter_map(|marker| rest.find(marker).map(|idx| (idx, *marker)))
+        .min()
+    {
+        let end = idx + marker.len();
+        out.push_str(&rest[..end]);
+        rest = &rest[end..];
+
+        let segment = &rest[..segment_len(rest)];
+        match segment.rfind('-') {
+            Some(dash) if is_hash(&segment[dash + 1..], 16) => {
+                out.push_str(&segment[..=dash]);
+                out.push_str("[HASH]");
+                rest = &rest[segment.len()..];
+
+                // Git checkouts have a directory for each revision.
+                if marker == "git/checkouts/" && rest.starts_with('/') {
+                    let revision = &rest[1..1 + segment_len(&rest[1..])];
+                    if is_hash(revision, 7) {
+                        out.push_str("/[REV]");
+                        rest = &rest[1 + revision.len()..];
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Returns the length of the path segment at the start of this text.
+fn segment_len(text: &str) -> usize {
+    text.find(|c: char| c == '/' || c == '\\' || c == '"' || c == '\'' || c.is_whitespace())
+        .unwrap_or(text.len())
+}
+
+/// Returns true if this is a lowercase hex string of at least `min_len` characters.
+fn is_hash(s: &str, min_len: usize) -> bool {
+    s.len() >= min_len
+        && s.chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+}
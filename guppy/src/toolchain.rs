@@ -0,0 +1,316 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Information about pinned Rust toolchains.
+//!
+//! Workspaces commonly pin the toolchain they're built with through a `rust-toolchain.toml` or
+//! `rust-toolchain` file. If `MetadataCommand::toolchain` is set, `guppy` looks for these files
+//! while constructing a `PackageGraph` and attaches the result to the workspace and its members,
+//! so that analyses can take the toolchain that will actually be used into account.
+//!
+//! If the workspace's toolchain is pinned to a specific release, Cargo simulations through
+//! `CargoSet` evaluate `cfg(version(...))` predicates against it, for platforms that don't have
+//! a rustc version set. Other evaluations, like `PlatformStatus::enabled_on`, only use the
+//! platform's own rustc version.
+
+use crate::graph::{PackageGraph, PackageMetadata};
+use crate::{Error, FileSource, RealFileSource};
+use semver::Version;
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use target_spec::RustcVersion;
+
+/// The names of toolchain files, in the order `rustup` checks them.
+pub const TOOLCHAIN_FILE_NAMES: &[&str] = &["rust-toolchain", "rust-toolchain.toml"];
+
+/// A toolchain pinned through a `rust-toolchain.toml` or `rust-toolchain` file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Toolchain {
+    path: PathBuf,
+    channel: ToolchainChannel,
+    components: Vec<String>,
+    targets: Vec<String>,
+    profile: Option<String>,
+}
+
+impl Toolchain {
+    /// Parses the contents of a toolchain file found at `path`.
+    ///
+    /// Both the TOML format and the legacy single-line format are supported.
+    pub fn parse(path: impl Into<PathBuf>, contents: &str) -> Result<Self, Error> {
+        let path = path.into();
+        let trimmed = contents.trim();
+        if !trimmed.contains('\n') && !trimmed.contains('[') && !trimmed.contains('=') {
+            // Legacy format: the file contains just the channel name.
+            if trimmed.is_empty() {
+                return Err(Error::ToolchainFileError(path, "file is empty".into()));
+            }
+            return Ok(Self {
+                path,
+                channel: ToolchainChannel::new(trimmed),
+                components: vec![],
+                targets: vec![],
+                profile: None,
+            });
+        }
+
+        let file: ToolchainFile = toml::from_str(contents)
+            .map_err(|err| Error::ToolchainFileError(path.clone(), Box::new(err)))?;
+        let section = file.toolchain;
+        let channel = match (section.channel, section.path) {
+            (Some(channel), _) => ToolchainChannel::new(&channel),
+            (None, Some(path)) => ToolchainChannel::Custom(path),
+            (None, None) => {
+                return Err(Error::ToolchainFileError(
+                    path,
+                    "[toolchain] section must specify either 'channel' or 'path'".into(),
+                ))
+            }
+        };
+        Ok(Self {
+            path,
+            channel,
+            components: section.components,
+            targets: section.targets,
+            profile: section.profile,
+        })
+    }
+
+    /// Looks for a toolchain file in `dir`.
+    ///
+    /// Returns `Ok(None)` if no toolchain file is present.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Option<Self>, Error> {
+        Self::from_dir_with_files(dir, &RealFileSource)
+    }
+
+    /// Looks for a toolchain file in `dir`, reading files through this `FileSource`.
+    ///
+    /// Returns `Ok(None)` if no toolchain file is present.
+    pub fn from_dir_with_files(
+        dir: impl AsRef<Path>,
+        files: &dyn FileSource,
+    ) -> Result<Option<Self>, Error> {
+        let dir = dir.as_ref();
+        for file_name in TOOLCHAIN_FILE_NAMES {
+            let path = dir.join(file_name);
+            match files.read_to_string(&path) {
+                Ok(contents) => return Self::parse(path, &contents).map(Some),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(Error::ToolchainFileError(path, Box::new(err))),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Looks for a toolchain file in `dir` and its ancestors, the way `rustup` does.
+    pub fn discover(dir: impl AsRef<Path>) -> Result<Option<Self>, Error> {
+        Self::discover_with_files(dir, &RealFileSource)
+    }
+
+    /// Looks for a toolchain file in `dir` and its ancestors, reading files through this
+    /// `FileSource`.
+    pub fn discover_with_files(
+        dir: impl AsRef<Path>,
+        files: &dyn FileSource,
+    ) -> Result<Option<Self>, Error> {
+        for dir in dir.as_ref().ancestors() {
+            if let Some(toolchain) = Self::from_dir_with_files(dir, files)? {
+                return Ok(Some(toolchain));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the path to the toolchain file this was read from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the channel this toolchain is pinned to.
+    pub fn channel(&self) -> &ToolchainChannel {
+        &self.channel
+    }
+
+    /// Returns the Rust version this toolchain is pinned to, if it is pinned to a specific
+    /// release.
+    pub fn version(&self) -> Option<&Version> {
+        match &self.channel {
+            ToolchainChannel::Version(version) => Some(version),
+            _ => None,
+        }
+    }
+
+    /// Returns the pinned version as a `RustcVersion`, for evaluating `version()` predicates.
+    pub(crate) fn rustc_version(&self) -> Option<RustcVersion> {
+        let version = self.version()?;
+        Some(RustcVersion::new(
+            u16::try_from(version.major).ok()?,
+            u16::try_from(version.minor).ok()?,
+            u16::try_from(version.patch).ok()?,
+        ))
+    }
+
+    /// Returns the additional components requested by this toolchain file.
+    pub fn components(&self) -> &[String] {
+        &self.components
+    }
+
+    /// Returns the additional targets requested by this toolchain file.
+    pub fn targets(&self) -> &[String] {
+        &self.targets
+    }
+
+    /// Returns the `rustup` profile requested by this toolchain file, if any.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+}
+
+/// The channel a toolchain is pinned to.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ToolchainChannel {
+    /// The latest stable release.
+    Stable,
+    /// The latest beta release.
+    Beta,
+    /// A nightly release, optionally pinned to a date like `2020-05-01`.
+    Nightly(Option<String>),
+    /// A specific stable release, e.g. `1.42.0`. Versions like `1.42` are treated as `1.42.0`.
+    Version(Version),
+    /// A custom toolchain name or path.
+    Custom(String),
+}
+
+impl ToolchainChannel {
+    /// Parses a channel as written in a toolchain file.
+    ///
+    /// Host triples at the end of channel names (e.g. `stable-x86_64-unknown-linux-gnu`) are
+    /// ignored.
+    pub fn new(channel: &str) -> Self {
+        let mut parts = channel.splitn(2, '-');
+        let name = parts.next().unwrap_or_default();
+        let rest = parts.next();
+        match name {
+            "stable" => ToolchainChannel::Stable,
+            "beta" => ToolchainChannel::Beta,
+            "nightly" => ToolchainChannel::Nightly(rest.and_then(parse_date).map(String::from)),
+            _ => match parse_version(name) {
+                Some(version) => ToolchainChannel::Version(version),
+                None => ToolchainChannel::Custom(channel.to_string()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ToolchainChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolchainChannel::Stable => write!(f, "stable"),
+            ToolchainChannel::Beta => write!(f, "beta"),
+            ToolchainChannel::Nightly(Some(date)) => write!(f, "nightly-{}", date),
+            ToolchainChannel::Nightly(None) => write!(f, "nightly"),
+            ToolchainChannel::Version(version) => write!(f, "{}", version),
+            ToolchainChannel::Custom(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Parses a Rust version like `1.42` or `1.42.0`, as used by toolchain channels and the
+/// `rust-version` manifest key.
+pub(crate) fn parse_version(s: &str) -> Option<Version> {
+    let components = s.split('.').count();
+    match components {
+        2 => Version::parse(&format!("{}.0", s)).ok(),
+        3 => Version::parse(s).ok(),
+        _ => None,
+    }
+}
+
+fn parse_date(s: &str) -> Option<&str> {
+    // Dates are of the form YYYY-MM-DD, optionally followed by a host triple.
+    let date = s.get(..10)?;
+    let is_date = date.char_indices().all(|(idx, c)| match idx {
+        4 | 7 => c == '-',
+        _ => c.is_ascii_digit(),
+    });
+    if is_date {
+        Some(date)
+    } else {
+        None
+    }
+}
+
+#[derive(Deserialize)]
+struct ToolchainFile {
+    toolchain: ToolchainSection,
+}
+
+#[derive(Deserialize)]
+struct ToolchainSection {
+    channel: Option<String>,
+    path: Option<String>,
+    #[serde(default)]
+    components: Vec<String>,
+    #[serde(default)]
+    targets: Vec<String>,
+    profile: Option<String>,
+}
+
+impl PackageGraph {
+    /// Returns workspace members whose declared `rust-version` is newer than the toolchain pinned
+    /// for them.
+    ///
+    /// Members without a `rust-version`, or whose toolchain isn't pinned to a specific release,
+    /// are skipped.
+    pub fn toolchain_msrv_violations(&self) -> Vec<MsrvViolation<'_>> {
+        self.workspace()
+            .members()
+            .filter_map(|(_, package)| {
+                let rust_version = package.rust_version()?;
+                let toolchain = package.toolchain()?;
+                let toolchain_version = toolchain.version()?;
+                if toolchain_version < rust_version {
+                    Some(MsrvViolation {
+                        package,
+                        rust_version,
+                        toolchain,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// A workspace member whose minimum supported Rust version isn't satisfied by its pinned
+/// toolchain.
+///
+/// Returned by `PackageGraph::toolchain_msrv_violations`.
+#[derive(Clone, Debug)]
+pub struct MsrvViolation<'g> {
+    package: PackageMetadata<'g>,
+    rust_version: &'g Version,
+    toolchain: &'g Toolchain,
+}
+
+impl<'g> MsrvViolation<'g> {
+    /// Returns the package.
+    pub fn package(&self) -> PackageMetadata<'g> {
+        self.package
+    }
+
+    /// Returns the `rust-version` declared by the package.
+    pub fn rust_version(&self) -> &'g Version {
+        self.rust_version
+    }
+
+    /// Returns the toolchain pinned for this package.
+    pub fn toolchain(&self) -> &'g Toolchain {
+        self.toolchain
+    }
+}
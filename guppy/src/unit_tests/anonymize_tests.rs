@@ -0,0 +1,102 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::PackageGraph;
+use crate::unit_tests::export_tests::roundtrip;
+use crate::unit_tests::fixtures::{Fixture, METADATA1_DATATEST};
+use crate::{CargoMetadata, PackageId};
+
+fn to_json(metadata: &CargoMetadata) -> String {
+    let mut json = vec![];
+    metadata
+        .serialize(&mut json)
+        .expect("serialization succeeded");
+    String::from_utf8(json).expect("valid UTF-8")
+}
+
+fn assert_same_shape(original: &PackageGraph, new: &PackageGraph) {
+    assert_eq!(original.package_count(), new.package_count());
+    assert_eq!(original.link_count(), new.link_count());
+    assert_eq!(
+        original.workspace().member_count(),
+        new.workspace().member_count()
+    );
+    let (feature_graph, new_feature_graph) = (original.feature_graph(), new.feature_graph());
+    assert_eq!(
+        feature_graph.feature_count(),
+        new_feature_graph.feature_count()
+    );
+    assert_eq!(feature_graph.link_count(), new_feature_graph.link_count());
+    assert_eq!(
+        feature_graph.build_warnings().len(),
+        new_feature_graph.build_warnings().len()
+    );
+}
+
+#[test]
+fn anonymize_metadata1() {
+    let graph = Fixture::metadata1().graph();
+    let anonymized = graph.anonymize(42).expect("anonymization succeeded");
+    let json = to_json(&anonymized);
+    assert!(!json.contains("testcrate"), "workspace names are scrubbed");
+    assert!(!json.contains("fakepath"), "paths are scrubbed");
+
+    let new_graph = roundtrip(&anonymized);
+    assert_same_shape(graph, &new_graph);
+
+    // crates.io packages are kept as-is.
+    let datatest_id = PackageId::new(METADATA1_DATATEST);
+    let datatest = new_graph
+        .metadata(&datatest_id)
+        .expect("datatest is preserved");
+    assert_eq!(datatest.name(), "datatest");
+    assert_eq!(
+        datatest.named_features().count(),
+        graph
+            .metadata(&datatest_id)
+            .unwrap()
+            .named_features()
+            .count()
+    );
+}
+
+#[test]
+fn anonymize_deterministic() {
+    // Compare JSON values rather than strings, since cargo_metadata stores features in hash maps.
+    let anonymize = |seed| {
+        let metadata = Fixture::metadata1()
+            .graph()
+            .anonymize(seed)
+            .expect("anonymization succeeded");
+        serde_json::to_value(&metadata).expect("serialization succeeded")
+    };
+    assert_eq!(
+        anonymize(42),
+        anonymize(42),
+        "same seed produces the same output"
+    );
+    assert_ne!(
+        anonymize(42),
+        anonymize(43),
+        "different seeds produce different output"
+    );
+}
+
+#[test]
+fn anonymize_libra() {
+    let graph = Fixture::metadata_libra().graph();
+    let anonymized = graph.anonymize(7).expect("anonymization succeeded");
+    let json = to_json(&anonymized);
+    assert!(!json.contains("fakeuser"), "paths are scrubbed");
+    assert!(
+        !json.contains("git+https://github.com"),
+        "git sources are scrubbed"
+    );
+    assert!(
+        !json.contains("\"libra-types\""),
+        "workspace names are scrubbed"
+    );
+
+    let new_graph = roundtrip(&anonymized);
+    assert_same_shape(graph, &new_graph);
+}
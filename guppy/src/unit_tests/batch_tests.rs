@@ -0,0 +1,74 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::batch::{BatchRunner, BatchSource};
+use crate::unit_tests::helpers::{git, write};
+use crate::MetadataCommand;
+
+#[test]
+fn batch_runner() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+
+    write(
+        &root,
+        "one/Cargo.toml",
+        "[package]\nname = \"one\"\nversion = \"0.1.0\"\n",
+    );
+    write(&root, "one/src/lib.rs", "");
+
+    let repo = root.join("two");
+    write(
+        &repo,
+        "Cargo.toml",
+        "[workspace]\nmembers = [\"a\", \"b\"]\n",
+    );
+    write(
+        &repo,
+        "a/Cargo.toml",
+        "[package]\nname = \"a\"\nversion = \"0.1.0\"\n\n[dependencies]\nb = { path = \"../b\" }\n",
+    );
+    write(&repo, "a/src/lib.rs", "");
+    write(
+        &repo,
+        "b/Cargo.toml",
+        "[package]\nname = \"b\"\nversion = \"0.1.0\"\n",
+    );
+    write(&repo, "b/src/lib.rs", "");
+    git(&repo, &["init", "-q"]);
+    git(&repo, &["add", "-A"]);
+    git(&repo, &["commit", "-q", "-m", "initial commit"]);
+
+    let sources = vec![
+        BatchSource::manifest(root.join("one/Cargo.toml")),
+        BatchSource::manifest(root.join("missing/Cargo.toml")),
+        BatchSource::git_ref(&repo, "HEAD"),
+        BatchSource::manifest(repo.join("a/Cargo.toml")),
+    ];
+    let mut runner = BatchRunner::new(MetadataCommand::new());
+    runner.jobs(2).add_sources(sources.clone());
+
+    let results = runner.run(|_, graph| graph.package_count());
+    let result_sources: Vec<_> = results.iter().map(|result| result.source()).collect();
+    assert_eq!(
+        result_sources,
+        sources.iter().collect::<Vec<_>>(),
+        "results are in source order"
+    );
+    let counts: Vec<_> = results
+        .iter()
+        .map(|result| result.result().ok().copied())
+        .collect();
+    assert_eq!(counts, vec![Some(1), None, Some(2), Some(2)]);
+    assert_eq!(
+        results[2].source().to_string(),
+        format!("{}@HEAD", repo.display())
+    );
+}
+
+#[test]
+fn batch_runner_empty() {
+    let runner = BatchRunner::new(MetadataCommand::new());
+    let results = runner.run(|_, graph| graph.package_count());
+    assert!(results.is_empty());
+}
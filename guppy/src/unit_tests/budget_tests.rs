@@ -0,0 +1,116 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::budget::{BudgetViolation, DependencyBudget};
+use crate::graph::PackageGraph;
+use crate::unit_tests::fixtures::{METADATA1, METADATA1_TESTCRATE};
+use crate::{Error, PackageId};
+use serde_json::{json, Value};
+
+/// Returns metadata1 with testcrate's budget set to `budget`, and with lazy_static in the
+/// `test-category` category.
+fn metadata1_with_budget(budget: Value) -> PackageGraph {
+    let mut metadata: Value = serde_json::from_str(METADATA1).expect("valid JSON");
+    for package in metadata["packages"].as_array_mut().unwrap() {
+        match package["name"].as_str().unwrap() {
+            "testcrate" => package["metadata"] = json!({ "guppy": { "budget": budget } }),
+            "lazy_static" => package["categories"] = json!(["test-category"]),
+            _ => {}
+        }
+    }
+    PackageGraph::from_json(metadata.to_string()).expect("valid metadata")
+}
+
+#[test]
+fn budget_no_violations() {
+    let graph = metadata1_with_budget(json!({
+        "max-direct-deps": 1,
+        "max-transitive-deps": 1000,
+        "banned-categories": ["no-such-category"],
+    }));
+    let testcrate = graph
+        .metadata(&PackageId::new(METADATA1_TESTCRATE))
+        .unwrap();
+    assert_eq!(
+        testcrate.dependency_budget().unwrap(),
+        Some(
+            DependencyBudget::new()
+                .with_max_direct_deps(1)
+                .with_max_transitive_deps(1000)
+                .with_banned_categories(vec!["no-such-category"])
+        )
+    );
+
+    let report = graph.check_budgets().unwrap();
+    assert_eq!(report.checked(), 1);
+    assert!(report.is_success(), "report: {}", report);
+}
+
+#[test]
+fn budget_violations() {
+    let graph = metadata1_with_budget(json!({
+        "max-direct-deps": 0,
+        "max-transitive-deps": 1,
+        "banned-categories": ["test-category"],
+    }));
+    let report = graph.check_budgets().unwrap();
+    assert_eq!(report.violations().len(), 3, "report: {}", report);
+
+    for violation in report.violations() {
+        assert_eq!(violation.package().name(), "testcrate");
+        match violation {
+            BudgetViolation::DirectDeps { limit, deps, .. } => {
+                assert_eq!(*limit, 0);
+                // datatest is a normal, build and dev dependency, but should only be counted once.
+                let names: Vec<_> = deps.iter().map(|dep| dep.name()).collect();
+                assert_eq!(names, ["datatest"]);
+            }
+            BudgetViolation::TransitiveDeps { limit, count, .. } => {
+                assert_eq!(*limit, 1);
+                assert!(*count > 1, "testcrate has many transitive deps");
+            }
+            BudgetViolation::BannedCategory { category, path, .. } => {
+                assert_eq!(*category, "test-category");
+                let names: Vec<_> = path.iter().map(|package| package.name()).collect();
+                assert_eq!(names.first(), Some(&"testcrate"));
+                assert_eq!(names.last(), Some(&"lazy_static"));
+                for pair in path.windows(2) {
+                    assert!(
+                        pair[0]
+                            .direct_links()
+                            .any(|link| link.to().id() == pair[1].id()),
+                        "path {:?} consists of direct links",
+                        names
+                    );
+                }
+            }
+        }
+    }
+
+    let report = report.to_string();
+    assert!(
+        report.contains(
+            "testcrate: depends on banned category 'test-category' via testcrate 0.1.0 -> "
+        ),
+        "report: {}",
+        report
+    );
+    assert!(report.ends_with("checked 1 packages with budgets, 3 violations found\n"));
+}
+
+#[test]
+fn budget_errors() {
+    let graph = metadata1_with_budget(json!({ "max-direct-dependencies": 1 }));
+    match graph.check_budgets() {
+        Err(Error::DependencyBudgetError(package_id, _)) => {
+            assert_eq!(package_id, PackageId::new(METADATA1_TESTCRATE))
+        }
+        other => panic!("expected a budget error, found {:?}", other),
+    }
+
+    // Packages without a budget aren't checked.
+    let graph = PackageGraph::from_json(METADATA1).unwrap();
+    let report = graph.check_budgets().unwrap();
+    assert_eq!(report.checked(), 0);
+    assert!(report.is_success());
+}
@@ -0,0 +1,111 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::cargo::{BuildMatrix, CargoSet, StandardFeatures};
+use crate::graph::PackageGraph;
+use crate::unit_tests::fixtures::{METADATA_TARGETS1, METADATA_TARGETS1_TESTCRATE};
+use crate::PackageId;
+use std::iter;
+use target_spec::{Platform, TargetFeatures};
+
+#[test]
+fn default_matrix() {
+    let matrix = BuildMatrix::new();
+    assert!(matrix.platforms().is_empty());
+    assert_eq!(matrix.features(), StandardFeatures::VALUES);
+    assert_eq!(matrix.include_dev(), &[false, true]);
+    assert_eq!(matrix.builds_per_platform(), 6);
+
+    let builds: Vec<_> = matrix
+        .builds()
+        .map(|build| (build.platform_idx, build.include_dev, build.features))
+        .collect();
+    assert_eq!(
+        builds,
+        vec![
+            (None, false, StandardFeatures::None),
+            (None, false, StandardFeatures::Default),
+            (None, false, StandardFeatures::All),
+            (None, true, StandardFeatures::None),
+            (None, true, StandardFeatures::Default),
+            (None, true, StandardFeatures::All),
+        ]
+    );
+}
+
+#[test]
+fn matrix_serialization() {
+    let matrix: BuildMatrix = toml::from_str(
+        r#"
+        platforms = ["x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc"]
+        features = ["default", "all", "default"]
+        "#,
+    )
+    .expect("valid matrix");
+    let triples: Vec<_> = matrix
+        .platforms()
+        .iter()
+        .map(|platform| platform.triple())
+        .collect();
+    assert_eq!(
+        triples,
+        vec!["x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc"]
+    );
+    assert_eq!(
+        matrix.features(),
+        &[StandardFeatures::Default, StandardFeatures::All],
+        "duplicates removed"
+    );
+    assert_eq!(matrix.include_dev(), &[false, true], "default value");
+    assert_eq!(matrix.platform_idxs(), vec![Some(0), Some(1)]);
+
+    let serialized = toml::to_string(&matrix).expect("serialization succeeded");
+    let roundtrip: BuildMatrix = toml::from_str(&serialized).expect("valid matrix");
+    assert_eq!(roundtrip.platforms().len(), 2);
+    assert_eq!(roundtrip.features(), matrix.features());
+    assert_eq!(roundtrip.include_dev(), matrix.include_dev());
+
+    assert!(
+        toml::from_str::<BuildMatrix>("platforms = [\"x86_64-unknown-nonexistent\"]").is_err(),
+        "unknown platform"
+    );
+    assert!(
+        toml::from_str::<BuildMatrix>("features = [\"some\"]").is_err(),
+        "unknown features"
+    );
+}
+
+#[test]
+fn matrix_builds() {
+    let graph = PackageGraph::from_json(METADATA_TARGETS1).expect("valid metadata");
+    let feature_graph = graph.feature_graph();
+    let testcrate = PackageId::new(METADATA_TARGETS1_TESTCRATE);
+    let package_query = graph
+        .query_forward(iter::once(&testcrate))
+        .expect("valid package ID");
+
+    let linux = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+    let matrix = BuildMatrix::new()
+        .with_platforms(vec![linux])
+        .with_features(vec![StandardFeatures::All, StandardFeatures::All])
+        .with_include_dev(vec![true]);
+    assert_eq!(
+        matrix.features(),
+        &[StandardFeatures::All],
+        "duplicates removed"
+    );
+    assert_eq!(matrix.builds_per_platform(), 1);
+
+    let build = matrix.builds().next().expect("one build");
+    assert_eq!(build.platform_idx, Some(0));
+    assert_eq!(
+        build.platform.map(|platform| platform.triple()),
+        Some("x86_64-unknown-linux-gnu")
+    );
+    let query = build.feature_query(&feature_graph, &package_query);
+    let cargo_set = CargoSet::new(query, &build.cargo_options()).expect("valid options");
+    assert!(cargo_set
+        .target_features()
+        .contains((&testcrate, None))
+        .expect("valid feature ID"));
+}
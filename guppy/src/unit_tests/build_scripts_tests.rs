@@ -0,0 +1,121 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::PackageGraph;
+use crate::unit_tests::fixtures::METADATA1;
+use crate::Error;
+use serde_json::{json, Value};
+
+/// Returns metadata1 with proc-macro2 linking to a native library and declaring `metadata`.
+fn metadata1_with_build_script(metadata: Value) -> PackageGraph {
+    let mut json: Value = serde_json::from_str(METADATA1).expect("valid JSON");
+    for package in json["packages"].as_array_mut().unwrap() {
+        if package["name"] == "proc-macro2" {
+            package["links"] = json!("proc_macro2_native");
+            package["metadata"] = json!({ "guppy": { "build-script": metadata } });
+        }
+    }
+    PackageGraph::from_json(json.to_string()).expect("valid metadata")
+}
+
+#[test]
+fn build_script_inputs() {
+    let graph = metadata1_with_build_script(json!({
+        "rerun-if-changed": ["build.rs"],
+        "rerun-if-env-changed": ["PROC_MACRO2_LIB_DIR"],
+    }));
+    let proc_macro2 = graph
+        .packages()
+        .find(|package| package.name() == "proc-macro2")
+        .unwrap();
+    let inputs = proc_macro2
+        .build_script_inputs()
+        .unwrap()
+        .expect("proc-macro2 has a build script");
+    assert!(inputs.is_declared());
+    assert_eq!(inputs.links(), Some("proc_macro2_native"));
+    assert_eq!(inputs.rerun_if_changed(), ["build.rs"]);
+    assert_eq!(inputs.rerun_if_env_changed(), ["PROC_MACRO2_LIB_DIR"]);
+
+    let testcrate = graph
+        .packages()
+        .find(|package| package.name() == "testcrate")
+        .unwrap();
+    assert!(
+        testcrate.build_script_inputs().unwrap().is_none(),
+        "testcrate has no build script"
+    );
+}
+
+#[test]
+fn build_script_report() {
+    let graph = metadata1_with_build_script(json!({}));
+    let report = graph.build_script_report().unwrap();
+
+    let names: Vec<_> = report
+        .scripts()
+        .iter()
+        .map(|script| script.inputs().package().name())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "bitflags",
+            "datatest",
+            "libc",
+            "memchr",
+            "proc-macro2",
+            "serde",
+            "syn",
+            "winapi",
+            "winapi-i686-pc-windows-gnu",
+            "winapi-x86_64-pc-windows-gnu",
+        ],
+        "report: {}",
+        report
+    );
+    // Only proc-macro2 declares its inputs, even if the table is empty.
+    let undeclared: Vec<_> = report
+        .undeclared()
+        .map(|script| script.inputs().package().name())
+        .collect();
+    assert!(!undeclared.contains(&"proc-macro2"));
+    assert_eq!(undeclared.len(), names.len() - 1);
+
+    let proc_macro2 = report
+        .scripts()
+        .iter()
+        .find(|script| script.inputs().package().name() == "proc-macro2")
+        .unwrap();
+    let links_dependents: Vec<_> = proc_macro2
+        .links_dependents()
+        .iter()
+        .map(|package| package.name())
+        .collect();
+    assert_eq!(links_dependents, ["syn"]);
+    let affected: Vec<_> = proc_macro2
+        .affected_members()
+        .iter()
+        .map(|package| package.name())
+        .collect();
+    assert_eq!(affected, ["testcrate"]);
+
+    // Packages without `links` don't pass metadata on.
+    let serde = report
+        .scripts()
+        .iter()
+        .find(|script| script.inputs().package().name() == "serde")
+        .unwrap();
+    assert!(serde.links_dependents().is_empty());
+}
+
+#[test]
+fn build_script_inputs_malformed() {
+    let graph = metadata1_with_build_script(json!({ "rerun-if-changed": "build.rs" }));
+    match graph.build_script_report() {
+        Err(Error::BuildScriptMetadataError(package_id, _)) => {
+            assert!(package_id.repr().starts_with("proc-macro2 "));
+        }
+        other => panic!("expected BuildScriptMetadataError, got {:?}", other),
+    }
+}
@@ -0,0 +1,394 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::cargo_config::{CargoConfig, SourceLocation};
+use crate::graph::cargo::CargoOptions;
+use crate::graph::feature::all_filter;
+use crate::graph::{DependencyDirection, PackageSource};
+use crate::unit_tests::helpers::{sorted_names, write};
+use crate::{Error, MemoryFileSource, MetadataCommand};
+use std::path::{Path, PathBuf};
+use target_spec::{Platform, TargetFeatures};
+
+const VENDORED: &str = r#"
+[source.crates-io]
+replace-with = "vendored-sources"
+
+[source.vendored-sources]
+directory = "vendor"
+"#;
+
+#[test]
+fn discover() {
+    let mut files = MemoryFileSource::new();
+    files
+        .insert("/repo/.cargo/config.toml", VENDORED)
+        .insert(
+            "/repo/workspace/.cargo/config",
+            "[build]\ntarget = \"x86_64-unknown-linux-gnu\"\nrustflags = [\"--cfg\", \"inner\"]\n",
+        )
+        // Only the first of config and config.toml is read.
+        .insert("/repo/workspace/.cargo/config.toml", "invalid = [");
+
+    let config = CargoConfig::discover_with_files("/repo/workspace", &files).unwrap();
+    assert_eq!(
+        config.files(),
+        &[
+            PathBuf::from("/repo/workspace/.cargo/config"),
+            PathBuf::from("/repo/.cargo/config.toml"),
+        ]
+    );
+    assert_eq!(config.build_targets(), &["x86_64-unknown-linux-gnu"]);
+
+    // Relative paths are resolved against the directory containing .cargo.
+    let vendored = config.source("vendored-sources").unwrap();
+    assert_eq!(
+        vendored.location(),
+        Some(&SourceLocation::Directory(PathBuf::from("/repo/vendor")))
+    );
+    assert_eq!(
+        config.replaced_source(PackageSource::CRATES_IO_REGISTRY),
+        Some(vendored)
+    );
+
+    let config = CargoConfig::discover_with_files("/elsewhere", &files).unwrap();
+    assert!(config.is_empty());
+    assert_eq!(
+        config.replaced_source(PackageSource::CRATES_IO_REGISTRY),
+        None
+    );
+}
+
+#[test]
+fn merge_precedence() {
+    let config = CargoConfig::parse(vec![
+        (
+            PathBuf::from("/home/.cargo/config.toml"),
+            "[build]\ntarget = \"aarch64-unknown-linux-gnu\"\nrustflags = \"-Dwarnings\"\n",
+        ),
+        (
+            PathBuf::from("/repo/.cargo/config.toml"),
+            "[build]\ntarget = [\"x86_64-pc-windows-msvc\", \"x86_64-apple-darwin\"]\n\
+             rustflags = [\"--cfg\", \"repo\"]\n",
+        ),
+    ])
+    .unwrap();
+    assert_eq!(
+        config.files(),
+        &[
+            PathBuf::from("/repo/.cargo/config.toml"),
+            PathBuf::from("/home/.cargo/config.toml"),
+        ]
+    );
+    // Scalars are overridden, but arrays are concatenated.
+    assert_eq!(
+        config.build_targets(),
+        &["x86_64-pc-windows-msvc", "x86_64-apple-darwin"]
+    );
+    let platform = Platform::new("x86_64-pc-windows-msvc", TargetFeatures::Unknown).unwrap();
+    assert_eq!(
+        config.rustflags(&platform),
+        vec!["-Dwarnings", "--cfg", "repo"]
+    );
+}
+
+#[test]
+fn target_overrides() {
+    let config = CargoConfig::parse(vec![(
+        PathBuf::from("/repo/.cargo/config.toml"),
+        r#"
+[build]
+target = "x86_64-unknown-linux-gnu"
+rustflags = ["--cfg", "ignored"]
+
+[target.'cfg(unix)']
+rustflags = ["--cfg", "tokio_unstable", "-C", "target-feature=+avx2,-sse4.1"]
+
+[target.x86_64-unknown-linux-gnu]
+linker = "clang"
+rustflags = ["-Ctarget-feature=+aes", "--cfg=linux_only"]
+
+[target.'cfg(windows)']
+rustflags = ["--cfg", "windows_only"]
+"#,
+    )])
+    .unwrap();
+
+    let keys: Vec<_> = config
+        .target_overrides()
+        .iter()
+        .map(|target| target.key())
+        .collect();
+    assert_eq!(
+        keys,
+        vec!["cfg(unix)", "cfg(windows)", "x86_64-unknown-linux-gnu"]
+    );
+
+    let linux = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+    let matching: Vec<_> = config
+        .matching_overrides(&linux)
+        .into_iter()
+        .map(|target| target.key())
+        .collect();
+    // The override for the triple comes first.
+    assert_eq!(matching, vec!["x86_64-unknown-linux-gnu", "cfg(unix)"]);
+    assert_eq!(
+        config.matching_overrides(&linux)[0].linker(),
+        Some(Path::new("clang"))
+    );
+
+    let platform = config
+        .target_platform(TargetFeatures::features(&["sse4.1", "fxsr"]))
+        .expect("build.target is known");
+    assert_eq!(platform.triple(), "x86_64-unknown-linux-gnu");
+    assert!(platform.has_flag("tokio_unstable"));
+    assert!(platform.has_flag("linux_only"));
+    assert!(!platform.has_flag("ignored"));
+    assert!(!platform.has_flag("windows_only"));
+    match platform.target_features() {
+        TargetFeatures::Features(features) => {
+            let mut features: Vec<_> = features.iter().copied().collect();
+            features.sort_unstable();
+            assert_eq!(features, vec!["aes", "avx2", "fxsr"]);
+        }
+        other => panic!("expected features, found {:?}", other),
+    }
+
+    let windows = Platform::new("x86_64-pc-windows-gnu", TargetFeatures::Unknown).unwrap();
+    assert_eq!(config.rustflags(&windows), vec!["--cfg", "windows_only"]);
+    let mac = Platform::new("x86_64-apple-darwin", TargetFeatures::Unknown).unwrap();
+    assert_eq!(config.matching_overrides(&mac).len(), 1);
+
+    // Without a matching override, build.rustflags are used.
+    let wasm = Platform::new("wasm32-unknown-unknown", TargetFeatures::Unknown).unwrap();
+    assert_eq!(config.rustflags(&wasm), vec!["--cfg", "ignored"]);
+
+    // Overrides can be applied to any platform, not just the one from build.target.
+    let mac = config.apply_rustflags(&mac);
+    assert!(mac.has_flag("tokio_unstable"));
+    assert!(!mac.has_flag("linux_only"));
+    // Target features that aren't known stay unknown.
+    assert!(matches!(mac.target_features(), TargetFeatures::Unknown));
+    assert!(config.apply_rustflags(&wasm).has_flag("ignored"));
+}
+
+#[test]
+fn replaced_sources() {
+    let config = CargoConfig::parse(vec![(
+        PathBuf::from("/repo/.cargo/config.toml"),
+        r#"
+[source.crates-io]
+replace-with = "mirror"
+
+[source.mirror]
+registry = "https://mirror.example.com/index"
+replace-with = "vendored"
+
+[source.vendored]
+local-registry = "/opt/registry"
+
+[source.internal-git]
+git = "https://example.com/internal.git"
+replace-with = "vendored"
+
+[source.cycle-a]
+registry = "https://a.example.com/index"
+replace-with = "cycle-b"
+
+[source.cycle-b]
+replace-with = "cycle-a"
+"#,
+    )])
+    .unwrap();
+
+    // Chains are followed to the end.
+    let vendored = config.source("vendored").unwrap();
+    assert_eq!(
+        config.replaced_source(PackageSource::CRATES_IO_REGISTRY),
+        Some(vendored)
+    );
+    assert_eq!(
+        config.replaced_source("registry+https://mirror.example.com/index"),
+        Some(vendored)
+    );
+    assert_eq!(
+        config.replaced_source("git+https://example.com/internal.git?branch=main#0123abcd"),
+        Some(vendored)
+    );
+    assert_eq!(
+        vendored.location(),
+        Some(&SourceLocation::LocalRegistry(PathBuf::from(
+            "/opt/registry"
+        )))
+    );
+    assert_eq!(
+        config.replaced_source("git+https://example.com/other.git#0123abcd"),
+        None
+    );
+    assert_eq!(
+        config.replaced_source("registry+https://a.example.com/index"),
+        None
+    );
+}
+
+#[test]
+fn invalid_config() {
+    let path = PathBuf::from("/repo/.cargo/config.toml");
+    for contents in &[
+        "[source.crates-io]\nreplace-with = 1\n",
+        "[target.'cfg(not(unix)']\nrustflags = []\n",
+        "[build",
+    ] {
+        match CargoConfig::parse(vec![(path.clone(), *contents)]) {
+            Err(err @ Error::CargoConfigError(_, _)) => {
+                assert_eq!(err.path(), Some(path.as_path()))
+            }
+            other => panic!("expected config error for {}, found {:?}", contents, other),
+        }
+    }
+}
+
+#[test]
+fn vendored_workspace() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    write(
+        root,
+        "app/Cargo.toml",
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n\
+         [dependencies]\nfoo = \"1\"\nlocal = { path = \"../local\" }\n",
+    );
+    write(root, "app/src/lib.rs", "");
+    write(root, "app/.cargo/config.toml", VENDORED);
+    write(
+        root,
+        "app/vendor/foo/Cargo.toml",
+        "[package]\nname = \"foo\"\nversion = \"1.0.0\"\n",
+    );
+    write(root, "app/vendor/foo/src/lib.rs", "");
+    write(
+        root,
+        "app/vendor/foo/.cargo-checksum.json",
+        "{\"files\":{},\"package\":null}",
+    );
+    write(
+        root,
+        "local/Cargo.toml",
+        "[package]\nname = \"local\"\nversion = \"0.1.0\"\n",
+    );
+    write(root, "local/src/lib.rs", "");
+
+    // Cargo reads configuration relative to the directory it's run in.
+    let graph = MetadataCommand::new()
+        .current_dir(root.join("app"))
+        .cargo_config(true)
+        .build_graph()
+        .expect("graph built");
+    let config = graph.workspace().cargo_config();
+    assert!(config
+        .files()
+        .iter()
+        .any(|path| path.ends_with("app/.cargo/config.toml")));
+
+    let foo = graph.packages().find(|p| p.name() == "foo").unwrap();
+    // cargo metadata reports the original source.
+    assert!(foo.source().is_crates_io());
+    let replaced = foo.replaced_source().expect("crates.io is replaced");
+    assert_eq!(replaced.name(), "vendored-sources");
+    match replaced.location() {
+        Some(SourceLocation::Directory(path)) => assert!(path.ends_with("app/vendor")),
+        other => panic!("expected directory source, found {:?}", other),
+    }
+
+    let local = graph.packages().find(|p| p.name() == "local").unwrap();
+    assert_eq!(local.replaced_source(), None);
+}
+
+#[test]
+fn cargo_options() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    write(
+        root,
+        "app/Cargo.toml",
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n\
+         [dependencies]\nfoo = \"1\"\n\n\
+         [target.'cfg(windows)'.dependencies]\nwinonly = { path = \"../winonly\" }\n\n\
+         [target.'cfg(tokio_unstable)'.dependencies]\nunstable = { path = \"../unstable\" }\n",
+    );
+    write(root, "app/src/lib.rs", "");
+    write(
+        root,
+        "app/.cargo/config.toml",
+        "[build]\ntarget = \"x86_64-pc-windows-msvc\"\n\n\
+         [target.'cfg(windows)']\nrustflags = [\"--cfg\", \"tokio_unstable\"]\n\n\
+         [patch.crates-io]\nfoo = { path = \"../foo\" }\n",
+    );
+    for name in &["foo", "winonly", "unstable"] {
+        write(
+            root,
+            &format!("{}/Cargo.toml", name),
+            &format!("[package]\nname = \"{}\"\nversion = \"1.0.0\"\n", name),
+        );
+        write(root, &format!("{}/src/lib.rs", name), "");
+    }
+
+    let graph = MetadataCommand::new()
+        .current_dir(root.join("app"))
+        .offline(true)
+        .cargo_config(true)
+        .build_graph()
+        .expect("graph built");
+    let config = graph.workspace().cargo_config();
+    let feature_graph = graph.feature_graph();
+    let resolve = |opts: CargoOptions<'_>| {
+        let packages: Vec<_> = feature_graph
+            .query_workspace(all_filter())
+            .resolve_cargo(&opts)
+            .expect("valid query")
+            .target_features()
+            .to_package_set()
+            .packages(DependencyDirection::Forward)
+            .collect();
+        sorted_names(&packages)
+    };
+
+    // build.target is used if the target platform isn't set, along with its rustflags.
+    assert_eq!(
+        resolve(CargoOptions::new().with_cargo_config(config)),
+        vec!["app", "foo", "unstable", "winonly"]
+    );
+    // [target] rustflags are applied to platforms that are set explicitly, too.
+    let windows = Platform::new("x86_64-pc-windows-gnu", TargetFeatures::Unknown).unwrap();
+    assert_eq!(
+        resolve(CargoOptions::new().with_target_platform(&windows)),
+        vec!["app", "foo", "winonly"]
+    );
+    assert_eq!(
+        resolve(
+            CargoOptions::new()
+                .with_target_platform(&windows)
+                .with_cargo_config(config)
+        ),
+        vec!["app", "foo", "unstable", "winonly"]
+    );
+    let linux = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+    assert_eq!(
+        resolve(
+            CargoOptions::new()
+                .with_target_platform(&linux)
+                .with_cargo_config(config)
+        ),
+        vec!["app", "foo"]
+    );
+
+    // cargo metadata has already applied the patch.
+    let patch = &config.patches()[0];
+    assert_eq!(patch.source(), "crates-io");
+    assert_eq!(patch.version(), None);
+    let foo = graph.packages().find(|p| p.name() == "foo").unwrap();
+    assert!(!foo.source().is_external());
+    assert_eq!(config.patch_for(&foo), Some(patch));
+    let app = graph.packages().find(|p| p.name() == "app").unwrap();
+    assert_eq!(config.patch_for(&app), None);
+}
@@ -0,0 +1,82 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{PackageLink, PackageQuery, PackageResolver};
+use crate::unit_tests::fixtures::{Fixture, METADATA_CYCLE1_BASE, METADATA_CYCLE1_HELPER};
+use crate::PackageId;
+use std::iter;
+
+struct NoDevResolver;
+
+impl<'g> PackageResolver<'g> for NoDevResolver {
+    fn accept(&mut self, _query: &PackageQuery<'g>, link: PackageLink<'g>) -> bool {
+        !link.dev_only()
+    }
+}
+
+#[test]
+fn cycle_audit_reports_dev_cycle() {
+    let graph = Fixture::metadata_cycle1().graph();
+    let base = PackageId::new(METADATA_CYCLE1_BASE);
+    let helper = PackageId::new(METADATA_CYCLE1_HELPER);
+
+    let audit = graph
+        .query_forward(iter::once(&base))
+        .expect("valid package ID")
+        .resolve_cycle_audit();
+    assert!(audit.reaches_initials(), "base reaches itself");
+    assert_eq!(audit.cycles().len(), 1, "one cycle followed");
+    let cycle = &audit.cycles()[0];
+    let members: Vec<_> = cycle.members().iter().map(|package| package.id()).collect();
+    assert_eq!(members, vec![&base, &helper]);
+    assert!(cycle.includes_initial());
+    assert!(cycle.dev_links().count() > 0, "cycle has a dev-only link");
+    assert_eq!(
+        audit.set().len(),
+        graph
+            .query_forward(iter::once(&base))
+            .unwrap()
+            .resolve()
+            .len(),
+        "same set as resolve"
+    );
+
+    // Not following dev-only links breaks the cycle.
+    let audit = graph
+        .query_forward(iter::once(&base))
+        .expect("valid package ID")
+        .resolve_with_cycle_audit(NoDevResolver);
+    assert!(audit.cycles().is_empty(), "no cycles followed");
+    assert!(!audit.reaches_initials());
+}
+
+#[test]
+fn cycle_audit_matches_all_cycles() {
+    let graph = Fixture::metadata_cycle2().graph();
+    let audit = graph.query_workspace().resolve_cycle_audit();
+
+    let mut expected: Vec<_> = graph
+        .cycles()
+        .all_cycles()
+        .map(|mut cycle| {
+            cycle.sort();
+            cycle
+        })
+        .collect();
+    expected.sort();
+    let actual: Vec<_> = audit
+        .cycles()
+        .iter()
+        .map(|cycle| {
+            cycle
+                .members()
+                .iter()
+                .map(|package| package.id())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    assert_eq!(
+        actual, expected,
+        "every cycle is followed by a full resolve"
+    );
+}
@@ -0,0 +1,110 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{BuilderDependency, PackageGraph, PackageGraphBuilder};
+use crate::unit_tests::helpers::sorted_names;
+use crate::DependencyKind;
+use semver::Version;
+
+fn dev_weight_graph() -> PackageGraph {
+    let mut builder = PackageGraphBuilder::new("/fakeworkspace");
+    let version = Version::new(1, 0, 0);
+    let app = builder.add_workspace_member("app".parse().unwrap(), version.clone());
+    let util = builder.add_workspace_member("util".parse().unwrap(), version.clone());
+    let serde = builder.add_third_party("serde".parse().unwrap(), version.clone());
+    let proptest = builder.add_third_party("proptest".parse().unwrap(), version.clone());
+    let criterion = builder.add_third_party("criterion".parse().unwrap(), version.clone());
+    let rand = builder.add_third_party("rand".parse().unwrap(), version.clone());
+    let libc = builder.add_third_party("libc".parse().unwrap(), version.clone());
+    let plotters = builder.add_third_party("plotters".parse().unwrap(), version);
+
+    let normal = || BuilderDependency::new(DependencyKind::Normal);
+    let dev = || BuilderDependency::new(DependencyKind::Development);
+    builder
+        .add_dependency(&app, &serde, normal())
+        .unwrap()
+        .add_dependency(&app, &proptest, dev())
+        .unwrap()
+        .add_dependency(&app, &criterion, dev())
+        .unwrap()
+        .add_dependency(&proptest, &rand, normal())
+        .unwrap()
+        .add_dependency(&criterion, &rand, normal())
+        .unwrap()
+        .add_dependency(&criterion, &plotters, normal())
+        .unwrap()
+        .add_dependency(&rand, &libc, normal())
+        .unwrap()
+        .add_dependency(&util, &plotters, normal())
+        .unwrap()
+        // Dev-dependencies of non-workspace packages are never built.
+        .add_dependency(&serde, &rand, dev())
+        .unwrap();
+    builder.build().expect("valid graph")
+}
+
+#[test]
+fn dev_weight_report() {
+    let graph = dev_weight_graph();
+    let report = graph.dev_weight_report();
+
+    let app = report
+        .members()
+        .iter()
+        .find(|member| member.member().name() == "app")
+        .expect("app is a member");
+    assert_eq!(app.normal_count(), 2, "app and serde");
+    assert_eq!(
+        sorted_names(app.dev_only()),
+        ["criterion", "libc", "plotters", "proptest", "rand"],
+        "report: {}",
+        report
+    );
+    assert_eq!(app.dev_only_crates(), 5);
+    assert_eq!(app.dev_only_units(), 5, "no build scripts");
+
+    let suggestions: Vec<_> = app
+        .suggestions()
+        .iter()
+        .map(|suggestion| {
+            (
+                suggestion.link().to().name(),
+                sorted_names(suggestion.dropped()),
+            )
+        })
+        .collect();
+    assert_eq!(
+        suggestions,
+        vec![
+            ("criterion", vec!["criterion", "plotters"]),
+            ("proptest", vec!["proptest"]),
+        ]
+    );
+
+    let util = report
+        .members()
+        .iter()
+        .find(|member| member.member().name() == "util")
+        .expect("util is a member");
+    assert!(util.dev_only().is_empty());
+    assert!(util.suggestions().is_empty());
+
+    // plotters is a normal dependency of util, so it isn't dev-only across the workspace.
+    assert_eq!(
+        sorted_names(report.dev_only()),
+        ["criterion", "libc", "proptest", "rand"]
+    );
+    assert_eq!(report.dev_only_units(), 4);
+
+    let output = report.to_string();
+    assert!(
+        output.contains("app: 7 packages, 5 only for dev (5 unique crates, ~5 units)\n"),
+        "output: {}",
+        output
+    );
+    assert!(
+        output.contains("    removing dev-dependency criterion would drop 2 packages\n"),
+        "output: {}",
+        output
+    );
+}
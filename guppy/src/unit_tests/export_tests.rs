@@ -0,0 +1,176 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{DependencyDirection, EnabledTernary, PackageGraph};
+use crate::unit_tests::fixtures::{self, Fixture};
+use crate::{CargoMetadata, Platform};
+use pretty_assertions::assert_eq;
+use std::iter;
+use target_spec::TargetFeatures;
+
+pub(super) fn roundtrip(metadata: &CargoMetadata) -> PackageGraph {
+    let mut json = vec![];
+    metadata
+        .serialize(&mut json)
+        .expect("serialization succeeded");
+    PackageGraph::from_json(String::from_utf8(json).expect("valid UTF-8"))
+        .expect("re-emitted metadata is valid")
+}
+
+pub(super) fn assert_same_graphs(original: &PackageGraph, new: &PackageGraph) {
+    let mut original_ids: Vec<_> = original.package_ids().collect();
+    original_ids.sort();
+    let mut new_ids: Vec<_> = new.package_ids().collect();
+    new_ids.sort();
+    assert_eq!(original_ids, new_ids, "package IDs match");
+    assert_eq!(original.link_count(), new.link_count(), "link counts match");
+
+    for package in original.packages() {
+        let new_package = new.metadata(package.id()).expect("package exists");
+        assert_eq!(package.manifest_path(), new_package.manifest_path());
+        assert_eq!(package.in_workspace(), new_package.in_workspace());
+        assert_eq!(
+            package.build_targets().count(),
+            new_package.build_targets().count(),
+            "build targets match for {}",
+            package.id()
+        );
+        let mut named_features: Vec<_> = package.named_features_with_deps().collect();
+        named_features.sort();
+        let mut new_named_features: Vec<_> = new_package.named_features_with_deps().collect();
+        new_named_features.sort();
+        assert_eq!(named_features, new_named_features);
+        let mut optional_deps: Vec<_> = package.optional_deps().collect();
+        optional_deps.sort();
+        let mut new_optional_deps: Vec<_> = new_package.optional_deps().collect();
+        new_optional_deps.sort();
+        assert_eq!(optional_deps, new_optional_deps);
+
+        let mut deps: Vec<_> = package
+            .direct_links()
+            .map(|link| (link.dep_name(), link.to().id()))
+            .collect();
+        deps.sort();
+        let mut new_deps: Vec<_> = new_package
+            .direct_links()
+            .map(|link| (link.dep_name(), link.to().id()))
+            .collect();
+        new_deps.sort();
+        assert_eq!(deps, new_deps, "direct links match for {}", package.id());
+    }
+}
+
+#[test]
+fn export_roundtrip() {
+    for fixture in &[
+        Fixture::metadata1(),
+        Fixture::metadata2(),
+        Fixture::metadata_dups(),
+        Fixture::metadata_cycle1(),
+        Fixture::metadata_targets1(),
+        Fixture::metadata_build_targets1(),
+        Fixture::metadata_proc_macro1(),
+    ] {
+        let graph = fixture.graph();
+        let exported = graph.to_cargo_metadata().expect("export succeeded");
+        let new_graph = roundtrip(&exported);
+        new_graph.verify().expect("re-built graph is valid");
+        assert_same_graphs(graph, &new_graph);
+    }
+}
+
+#[test]
+fn export_subgraph() {
+    let graph = Fixture::metadata1().graph();
+    let region = fixtures::package_id(fixtures::METADATA1_REGION);
+    let set = graph
+        .query_forward(iter::once(&region))
+        .expect("valid package ID")
+        .resolve();
+    let new_graph = roundtrip(&set.to_cargo_metadata().expect("export succeeded"));
+    new_graph.verify().expect("re-built graph is valid");
+
+    assert_eq!(new_graph.package_count(), set.len());
+    assert_eq!(
+        new_graph.link_count(),
+        set.links(DependencyDirection::Forward).count()
+    );
+    assert_eq!(
+        new_graph.workspace().member_count(),
+        0,
+        "no workspace members in this subgraph"
+    );
+}
+
+#[test]
+fn export_platform_filtered() {
+    let graph = Fixture::metadata_targets1().graph();
+    let platform =
+        Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).expect("known triple");
+    let enabled = |link: crate::graph::PackageLink<'_>| {
+        link.normal().status().enabled_on(&platform) != EnabledTernary::Disabled
+            || link.build().status().enabled_on(&platform) != EnabledTernary::Disabled
+            || link.dev().status().enabled_on(&platform) != EnabledTernary::Disabled
+    };
+
+    let set = graph.resolve_all();
+    let expected_links = set
+        .links(DependencyDirection::Forward)
+        .filter(|link| enabled(*link))
+        .count();
+    assert!(
+        expected_links < graph.link_count(),
+        "some links are filtered out on this platform"
+    );
+
+    let exported = set
+        .to_cargo_metadata_filtered(enabled)
+        .expect("export succeeded");
+    let new_graph = roundtrip(&exported);
+    assert_eq!(new_graph.package_count(), graph.package_count());
+    assert_eq!(new_graph.link_count(), expected_links);
+}
+
+#[test]
+fn export_ndjson() {
+    let graph = Fixture::metadata_libra().graph();
+    let set = graph.resolve_workspace();
+    let mut out = vec![];
+    set.write_ndjson(DependencyDirection::Reverse, &mut out)
+        .expect("write succeeded");
+    let out = String::from_utf8(out).expect("valid UTF-8");
+
+    let lines: Vec<serde_json::Value> = out
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line is valid JSON"))
+        .collect();
+    let expected_ids: Vec<_> = set
+        .package_ids(DependencyDirection::Reverse)
+        .map(|id| id.repr())
+        .collect();
+    let ids: Vec<_> = lines
+        .iter()
+        .map(|line| line["id"].as_str().expect("id is a string"))
+        .collect();
+    assert_eq!(ids, expected_ids, "packages are written in order");
+
+    // Each line matches the corresponding entry in cargo metadata output.
+    let mut metadata = vec![];
+    set.to_cargo_metadata()
+        .expect("export succeeded")
+        .serialize(&mut metadata)
+        .expect("serialization succeeded");
+    let metadata: serde_json::Value = serde_json::from_slice(&metadata).expect("valid JSON");
+    for package in metadata["packages"]
+        .as_array()
+        .expect("packages is an array")
+    {
+        let line = lines
+            .iter()
+            .find(|line| line["id"] == package["id"])
+            .expect("package is written");
+        assert_eq!(line["name"], package["name"]);
+        assert_eq!(line["manifest_path"], package["manifest_path"]);
+        assert_eq!(line["dependencies"], package["dependencies"]);
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{MetadataExtension, PackageGraph};
+use crate::unit_tests::fixtures::{Fixture, METADATA1, METADATA_LIBRA};
+use crate::Error;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::error;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct DocsRs {
+    #[serde(default)]
+    all_features: bool,
+    #[serde(default)]
+    features: Vec<String>,
+}
+
+impl MetadataExtension for DocsRs {
+    const NAMESPACE: &'static str = "docs";
+
+    fn parse(table: &Value) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+        Ok(DocsRs::deserialize(&table["rs"])?)
+    }
+}
+
+#[test]
+fn extension_values() {
+    let mut graph = PackageGraph::from_json(METADATA_LIBRA).expect("valid metadata");
+    let package_id = Fixture::metadata_libra()
+        .graph()
+        .packages()
+        .find(|package| package.metadata_table().get("docs").is_some())
+        .expect("a package has docs.rs metadata")
+        .id()
+        .clone();
+    assert!(!graph.has_extension::<DocsRs>());
+    assert!(
+        graph
+            .metadata(&package_id)
+            .unwrap()
+            .extension::<DocsRs>()
+            .is_none(),
+        "not registered yet"
+    );
+
+    let count = graph.register_extension::<DocsRs>().expect("tables parse");
+    assert!(graph.has_extension::<DocsRs>());
+    let expected = graph
+        .packages()
+        .filter(|package| package.metadata_table().get("docs").is_some())
+        .count();
+    assert_eq!(count, expected, "one value per table");
+
+    let package = graph.metadata(&package_id).unwrap();
+    let docs_rs = package.extension::<DocsRs>().expect("value is attached");
+    assert_eq!(
+        docs_rs.all_features,
+        package.metadata_table()["docs"]["rs"]["all-features"] == json!(true)
+    );
+    assert_eq!(
+        docs_rs.features.len(),
+        package.metadata_table()["docs"]["rs"]["features"]
+            .as_array()
+            .map_or(0, |features| features.len())
+    );
+    assert!(
+        graph
+            .packages()
+            .filter(|package| package.metadata_table().get("docs").is_none())
+            .all(|package| package.extension::<DocsRs>().is_none()),
+        "packages without a table have no value"
+    );
+}
+
+#[test]
+fn extension_error() {
+    let mut json: Value = serde_json::from_str(METADATA1).expect("valid JSON");
+    for package in json["packages"].as_array_mut().unwrap() {
+        if package["name"] == "testcrate" {
+            package["metadata"] = json!({ "docs": { "rs": { "features": "not-a-list" } } });
+        }
+    }
+    let mut graph = PackageGraph::from_json(json.to_string()).expect("valid metadata");
+    match graph.register_extension::<DocsRs>() {
+        Err(Error::MetadataExtensionError(package_id, namespace, _)) => {
+            assert_eq!(graph.metadata(&package_id).unwrap().name(), "testcrate");
+            assert_eq!(namespace, "docs");
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(
+        !graph.has_extension::<DocsRs>(),
+        "nothing attached on error"
+    );
+}
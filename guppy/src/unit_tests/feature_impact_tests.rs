@@ -0,0 +1,140 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::cargo::CargoOptions;
+use crate::graph::feature::FeatureId;
+use crate::graph::{BuilderDependency, PackageGraph, PackageGraphBuilder};
+use crate::report::{MarkdownRenderer, Report};
+use crate::unit_tests::helpers::sorted_names;
+use crate::DependencyKind;
+use semver::Version;
+
+fn feature_impact_graph() -> PackageGraph {
+    let mut builder = PackageGraphBuilder::new("/fakeworkspace");
+    let version = Version::new(1, 0, 0);
+    let app = builder.add_workspace_member("app".parse().unwrap(), version.clone());
+    builder.add_workspace_member("util".parse().unwrap(), version.clone());
+    let serde = builder.add_third_party("serde".parse().unwrap(), version.clone());
+    let derive = builder.add_third_party("serde_derive".parse().unwrap(), version.clone());
+    let openssl = builder.add_third_party("openssl".parse().unwrap(), version.clone());
+    let libc = builder.add_third_party("libc".parse().unwrap(), version);
+
+    builder
+        .set_proc_macro(&derive, true)
+        .unwrap()
+        .add_feature(&serde, "default".parse().unwrap(), vec!["std"])
+        .unwrap()
+        .add_feature(&serde, "std".parse().unwrap(), Vec::<String>::new())
+        .unwrap()
+        .add_feature(&serde, "derive".parse().unwrap(), vec!["serde_derive"])
+        .unwrap()
+        .add_feature(&app, "default".parse().unwrap(), vec!["std"])
+        .unwrap()
+        .add_feature(&app, "std".parse().unwrap(), vec!["serde/std"])
+        .unwrap()
+        .add_feature(&app, "derive".parse().unwrap(), vec!["serde/derive"])
+        .unwrap()
+        .add_feature(&app, "tls".parse().unwrap(), vec!["openssl"])
+        .unwrap();
+
+    let normal = || BuilderDependency::new(DependencyKind::Normal);
+    builder
+        .add_dependency(&serde, &derive, normal().with_optional(true))
+        .unwrap()
+        .add_dependency(&app, &serde, normal().with_default_features(false))
+        .unwrap()
+        .add_dependency(&app, &openssl, normal().with_optional(true))
+        .unwrap()
+        .add_dependency(&openssl, &libc, normal())
+        .unwrap();
+    builder.build().expect("valid graph")
+}
+
+#[test]
+fn feature_impact_matrix() {
+    let graph = feature_impact_graph();
+    let matrix = graph
+        .feature_impact_matrix(&CargoOptions::new())
+        .expect("valid options");
+
+    let app = &matrix.members()[0];
+    assert_eq!(app.member().name(), "app");
+    assert_eq!(app.default_count(), 1, "serde");
+    let features: Vec<_> = app
+        .features()
+        .iter()
+        .map(|impact| (impact.feature(), impact.is_default()))
+        .collect();
+    assert_eq!(
+        features,
+        vec![
+            ("derive", false),
+            ("openssl", false),
+            ("std", true),
+            ("tls", false)
+        ],
+        "the default feature itself is skipped"
+    );
+
+    let derive = matrix.impact("app", "derive").expect("app/derive exists");
+    assert_eq!(sorted_names(derive.added_packages()), ["serde_derive"]);
+    let serde = graph
+        .packages()
+        .find(|package| package.name() == "serde")
+        .unwrap();
+    assert_eq!(
+        derive.added_features(),
+        &[
+            FeatureId::new(serde.id(), "derive"),
+            FeatureId::new(serde.id(), "serde_derive")
+        ],
+        "base features of added packages aren't listed"
+    );
+
+    for feature in &["openssl", "tls"] {
+        let impact = matrix.impact("app", feature).expect("feature exists");
+        assert_eq!(sorted_names(impact.added_packages()), ["libc", "openssl"]);
+        assert!(impact.added_features().is_empty());
+    }
+    let std = matrix.impact("app", "std").expect("app/std exists");
+    assert!(std.added_packages().is_empty());
+
+    let util = &matrix.members()[1];
+    assert_eq!(util.member().name(), "util");
+    assert!(util.features().is_empty());
+    assert!(matrix.impact("util", "std").is_none());
+    assert!(matrix.impact("missing", "std").is_none());
+    assert_eq!(matrix.costly_features(), 3);
+
+    let output = matrix.to_string();
+    assert!(
+        output.contains(
+            "app: 1 third-party packages in default build\n    \
+             derive: +1 packages, +2 features\n        serde_derive 1.0.0\n"
+        ),
+        "output: {}",
+        output
+    );
+    assert!(
+        output.contains("    std: enabled by default\n"),
+        "output: {}",
+        output
+    );
+    assert!(
+        output.ends_with("3 features add third-party packages, across 2 members\n"),
+        "output: {}",
+        output
+    );
+
+    let markdown = matrix.render(&MarkdownRenderer);
+    assert!(
+        markdown.contains("## app: 1 third-party packages in default build\n"),
+        "markdown: {}",
+        markdown
+    );
+    assert!(
+        markdown.contains("- tls: +2 packages, +0 features\n  - "),
+        "markdown: {}",
+        markdown
+    );
+}
@@ -0,0 +1,78 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::PackageGraph;
+use crate::toolchain::ToolchainChannel;
+use crate::unit_tests::fixtures::METADATA1;
+use crate::{CargoMetadata, Error, FileSource, MemoryFileSource, Version};
+use std::io;
+use std::path::Path;
+
+#[test]
+fn graph_from_memory() {
+    let metadata = CargoMetadata::parse_json(METADATA1).expect("valid metadata");
+    let mut files = MemoryFileSource::new();
+    files
+        .insert(
+            "/fakepath/testcrate/Cargo.toml",
+            "[package]\nname = \"testcrate\"\nversion = \"0.1.0\"\nrust-version = \"1.42\"\n",
+        )
+        .insert("/fakepath/testcrate/rust-toolchain", "nightly\n")
+        .insert("/fakepath/rust-toolchain", "1.40.0\n");
+
+    let graph = PackageGraph::from_metadata_with_files(metadata, &files).expect("graph built");
+    let testcrate = graph.workspace().member_by_name("testcrate").unwrap();
+    assert_eq!(testcrate.rust_version(), Some(&Version::new(1, 42, 0)));
+    assert_eq!(
+        testcrate.toolchain().map(|toolchain| toolchain.channel()),
+        Some(&ToolchainChannel::Nightly(None)),
+        "toolchain discovered in the workspace root, not above it"
+    );
+    assert!(
+        testcrate
+            .manifest_spans()
+            .and_then(|spans| spans.package_key("rust-version"))
+            .is_some(),
+        "spans recorded from the in-memory manifest"
+    );
+
+    // Without any files, the graph is still built but has no manifest information.
+    let metadata = CargoMetadata::parse_json(METADATA1).expect("valid metadata");
+    let graph = PackageGraph::from_metadata_with_files(metadata, &MemoryFileSource::new())
+        .expect("graph built");
+    let testcrate = graph.workspace().member_by_name("testcrate").unwrap();
+    assert_eq!(testcrate.rust_version(), None);
+    assert!(testcrate.manifest_spans().is_none());
+}
+
+#[test]
+fn read_errors() {
+    struct FailingSource {
+        manifests: bool,
+    }
+
+    impl FileSource for FailingSource {
+        fn read_to_string(&self, path: &Path) -> io::Result<String> {
+            if self.manifests || path.ends_with("rust-toolchain") {
+                Err(io::Error::new(io::ErrorKind::PermissionDenied, "denied"))
+            } else {
+                Err(io::Error::new(io::ErrorKind::NotFound, "not found"))
+            }
+        }
+    }
+
+    // Toolchain files that can't be read are skipped.
+    let metadata = CargoMetadata::parse_json(METADATA1).expect("valid metadata");
+    let graph =
+        PackageGraph::from_metadata_with_files(metadata, &FailingSource { manifests: false })
+            .expect("graph built");
+    assert_eq!(graph.workspace().toolchain(), None);
+
+    let metadata = CargoMetadata::parse_json(METADATA1).expect("valid metadata");
+    match PackageGraph::from_metadata_with_files(metadata, &FailingSource { manifests: true }) {
+        Err(Error::ManifestError { manifest_path, .. }) => {
+            assert_eq!(manifest_path, Path::new("/fakepath/testcrate/Cargo.toml"));
+        }
+        other => panic!("expected a manifest error, got {:?}", other.map(|_| ())),
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::PackageGraph;
+use crate::unit_tests::helpers::{git, write};
+use crate::{Error, Version};
+
+#[test]
+fn graph_from_git_ref() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path().canonicalize().unwrap();
+    git(&repo, &["init", "-q"]);
+    write(
+        &repo,
+        "Cargo.toml",
+        "[workspace]\nmembers = [\"a\", \"b\"]\n",
+    );
+    write(
+        &repo,
+        "a/Cargo.toml",
+        "[package]\nname = \"a\"\nversion = \"0.1.0\"\nrust-version = \"1.42\"\n\n\
+         [dependencies]\nb = { path = \"../b\" }\n",
+    );
+    write(&repo, "a/src/lib.rs", "pub fn a() {}\n");
+    write(
+        &repo,
+        "b/Cargo.toml",
+        "[package]\nname = \"b\"\nversion = \"0.1.0\"\n",
+    );
+    write(&repo, "b/src/lib.rs", "pub fn b() {}\n");
+    write(&repo, "b/src/bin/tool.rs", "fn main() {}\n");
+    git(&repo, &["add", "-A"]);
+    git(&repo, &["commit", "-q", "-m", "initial"]);
+
+    // Change the working tree: the graph should still reflect the commit.
+    write(
+        &repo,
+        "b/Cargo.toml",
+        "[package]\nname = \"b\"\nversion = \"0.2.0\"\n",
+    );
+
+    let graph = PackageGraph::from_git_ref(&repo, "HEAD").expect("graph built from HEAD");
+    assert_eq!(graph.workspace().root(), repo);
+
+    let a = graph.workspace().member_by_name("a").unwrap();
+    assert_eq!(a.manifest_path(), repo.join("a/Cargo.toml"));
+    assert_eq!(
+        a.rust_version(),
+        Some(&Version::new(1, 42, 0)),
+        "manifest read from git"
+    );
+    assert!(a.manifest_spans().is_some());
+
+    let b = graph.workspace().member_by_name("b").unwrap();
+    assert_eq!(b.version(), &Version::new(0, 1, 0));
+    assert!(
+        b.id().repr().contains(&*repo.to_string_lossy()),
+        "package ID paths rewritten: {}",
+        b.id()
+    );
+    assert_eq!(
+        b.build_targets().count(),
+        2,
+        "library and binary targets discovered"
+    );
+    assert!(a.direct_links().any(|link| link.to().id() == b.id()));
+
+    match PackageGraph::from_git_ref(&repo, "no-such-branch") {
+        Err(Error::GitError(path, _)) => assert_eq!(path, repo),
+        other => panic!("expected GitError, got {:?}", other.map(|_| ())),
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{BuilderDependency, EnabledTernary, PackageGraphBuilder};
+use crate::{DependencyKind, Error, PackageId, Platform};
+use semver::Version;
+use target_spec::TargetFeatures;
+
+#[test]
+fn builder_topology() {
+    let mut builder = PackageGraphBuilder::new("/fakeworkspace");
+    let app = builder.add_workspace_member("app".parse().unwrap(), Version::new(0, 1, 0));
+    let util = builder.add_workspace_member("util".parse().unwrap(), Version::new(0, 2, 0));
+    let serde = builder.add_third_party("serde".parse().unwrap(), Version::new(1, 0, 100));
+    let derive = builder.add_third_party("serde_derive".parse().unwrap(), Version::new(1, 0, 100));
+    let cc = builder.add_third_party("cc".parse().unwrap(), Version::new(1, 0, 50));
+    let winapi = builder.add_third_party("winapi".parse().unwrap(), Version::new(0, 3, 8));
+
+    builder
+        .set_proc_macro(&derive, true)
+        .unwrap()
+        .add_feature(&serde, "derive".parse().unwrap(), vec!["serde_derive"])
+        .unwrap()
+        .add_feature(&serde, "default".parse().unwrap(), vec!["std"])
+        .unwrap()
+        .add_feature(&serde, "std".parse().unwrap(), Vec::<String>::new())
+        .unwrap();
+    builder
+        .add_dependency(
+            &serde,
+            &derive,
+            BuilderDependency::new(DependencyKind::Normal).with_optional(true),
+        )
+        .unwrap()
+        .add_dependency(
+            &app,
+            &serde,
+            BuilderDependency::new(DependencyKind::Normal)
+                .with_default_features(false)
+                .with_features(vec!["derive".parse().unwrap()]),
+        )
+        .unwrap()
+        .add_dependency(
+            &app,
+            &util,
+            BuilderDependency::new(DependencyKind::Normal).with_rename("app-util".parse().unwrap()),
+        )
+        .unwrap()
+        .add_dependency(&app, &cc, BuilderDependency::new(DependencyKind::Build))
+        .unwrap()
+        .add_dependency(
+            &util,
+            &winapi,
+            BuilderDependency::new(DependencyKind::Normal).with_target("cfg(windows)"),
+        )
+        .unwrap()
+        .add_dependency(
+            &util,
+            &serde,
+            BuilderDependency::new(DependencyKind::Development),
+        )
+        .unwrap();
+
+    let graph = builder.build().expect("graph is valid");
+    graph.verify().expect("graph is consistent");
+    assert_eq!(graph.package_count(), 6);
+    assert_eq!(graph.link_count(), 6);
+
+    let workspace = graph.workspace();
+    assert_eq!(workspace.root(), std::path::Path::new("/fakeworkspace"));
+    assert_eq!(workspace.member_count(), 2);
+    let util_metadata = workspace.member_by_path("util").expect("util is a member");
+    assert_eq!(util_metadata.id(), &util);
+    assert_eq!(util_metadata.version(), &Version::new(0, 2, 0));
+
+    let app_metadata = graph.metadata(&app).unwrap();
+    assert!(
+        app_metadata.has_build_script(),
+        "app has build dependencies"
+    );
+    assert!(
+        !util_metadata.has_build_script(),
+        "util has no build dependencies"
+    );
+    assert!(graph.metadata(&derive).unwrap().is_proc_macro());
+    assert!(!graph.metadata(&serde).unwrap().in_workspace());
+
+    let serde_link = app_metadata
+        .direct_links()
+        .find(|link| link.to().id() == serde)
+        .expect("app depends on serde");
+    assert!(!serde_link.normal().default_features().enabled_on_any());
+    assert_eq!(
+        serde_link.normal().features().collect::<Vec<_>>(),
+        ["derive"]
+    );
+    let util_link = app_metadata
+        .direct_links()
+        .find(|link| link.to().id() == util)
+        .expect("app depends on util");
+    assert_eq!(util_link.dep_name(), "app-util");
+    assert_eq!(util_link.resolved_name(), "app_util");
+
+    let winapi_link = util_metadata
+        .direct_links()
+        .find(|link| link.to().id() == winapi)
+        .expect("util depends on winapi");
+    let linux = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+    let windows = Platform::new("x86_64-pc-windows-msvc", TargetFeatures::Unknown).unwrap();
+    assert_eq!(
+        winapi_link.normal().status().enabled_on(&linux),
+        EnabledTernary::Disabled
+    );
+    assert_eq!(
+        winapi_link.normal().status().enabled_on(&windows),
+        EnabledTernary::Enabled
+    );
+
+    assert!(graph.depends_on(&app, &derive).unwrap());
+    assert!(graph.depends_on(&util, &serde).unwrap());
+    assert_eq!(
+        graph
+            .metadata(&serde)
+            .unwrap()
+            .optional_deps()
+            .collect::<Vec<_>>(),
+        ["serde_derive"]
+    );
+}
+
+#[test]
+fn builder_errors() {
+    let mut builder = PackageGraphBuilder::new("/fakeworkspace");
+    let app = builder.add_workspace_member("app".parse().unwrap(), Version::new(0, 1, 0));
+    let unknown = PackageId::new("unknown 1.0.0 (registry+https://example.com)");
+    match builder.add_dependency(
+        &app,
+        &unknown,
+        BuilderDependency::new(DependencyKind::Normal),
+    ) {
+        Err(Error::UnknownPackageId(package_id)) => assert_eq!(package_id, unknown),
+        other => panic!("expected UnknownPackageId, found {:?}", other),
+    }
+    assert!(builder
+        .add_feature(&unknown, "foo".parse().unwrap(), vec!["bar"])
+        .is_err());
+
+    builder.add_workspace_member("app".parse().unwrap(), Version::new(0, 1, 0));
+    match builder.build() {
+        Err(Error::PackageGraphConstructError(msg)) => {
+            assert!(msg.contains("more than once"), "message: {}", msg)
+        }
+        other => panic!("expected PackageGraphConstructError, found {:?}", other),
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{
+    BuilderDependency, GraphPair, PackageChangeKind, PackageGraph, PackageGraphBuilder,
+};
+use crate::DependencyKind;
+use semver::Version;
+
+/// Builds a workspace with an `app` member depending on the given third-party packages.
+fn build_graph(workspace_root: &str, deps: &[(&str, Version)]) -> PackageGraph {
+    let mut builder = PackageGraphBuilder::new(workspace_root);
+    let app = builder.add_workspace_member("app".parse().unwrap(), Version::new(0, 1, 0));
+    for (name, version) in deps {
+        let dep = builder.add_third_party(name.parse().unwrap(), version.clone());
+        builder
+            .add_dependency(&app, &dep, BuilderDependency::new(DependencyKind::Normal))
+            .unwrap();
+    }
+    builder.build().expect("valid graph")
+}
+
+#[test]
+fn graph_pair_changes() {
+    let old = build_graph(
+        "/old/workspace",
+        &[
+            ("bar", Version::new(1, 0, 0)),
+            ("foo", Version::new(1, 0, 0)),
+            ("rand", Version::new(0, 6, 0)),
+            ("rand", Version::new(0, 7, 0)),
+            ("removed", Version::new(2, 0, 0)),
+        ],
+    );
+    let new = build_graph(
+        "/new/workspace",
+        &[
+            ("added", Version::new(0, 1, 0)),
+            ("bar", Version::new(1, 0, 0)),
+            ("foo", Version::new(1, 1, 0)),
+            ("rand", Version::new(0, 7, 0)),
+            ("rand", Version::new(0, 8, 0)),
+        ],
+    );
+    let pair = GraphPair::new(&old, &new);
+
+    let changes: Vec<_> = pair
+        .changes()
+        .map(|change| {
+            (
+                change.key().name(),
+                change.old_package().map(|p| p.version().to_string()),
+                change.new_package().map(|p| p.version().to_string()),
+                change.kind(),
+            )
+        })
+        .collect();
+    let v = |s: &str| Some(s.to_string());
+    assert_eq!(
+        changes,
+        vec![
+            ("added", None, v("0.1.0"), PackageChangeKind::Added),
+            // Workspace members are correlated even though the workspace root moved.
+            ("app", v("0.1.0"), v("0.1.0"), PackageChangeKind::Unchanged),
+            ("bar", v("1.0.0"), v("1.0.0"), PackageChangeKind::Unchanged),
+            (
+                "foo",
+                v("1.0.0"),
+                v("1.1.0"),
+                PackageChangeKind::VersionChanged
+            ),
+            // Identical versions are matched first, then the rest in version order.
+            (
+                "rand",
+                v("0.6.0"),
+                v("0.8.0"),
+                PackageChangeKind::VersionChanged
+            ),
+            ("rand", v("0.7.0"), v("0.7.0"), PackageChangeKind::Unchanged),
+            ("removed", v("2.0.0"), None, PackageChangeKind::Removed),
+        ]
+    );
+
+    let added: Vec<_> = pair.added().map(|p| p.name()).collect();
+    assert_eq!(added, ["added"]);
+    let removed: Vec<_> = pair.removed().map(|p| p.name()).collect();
+    assert_eq!(removed, ["removed"]);
+    assert_eq!(pair.version_changes().count(), 2);
+
+    // Lookups in both directions.
+    let old_app = old.workspace().member_by_path("app").unwrap();
+    let new_app = pair
+        .old_to_new(old_app.id())
+        .expect("app is in the new graph");
+    assert_eq!(
+        new_app.id(),
+        new.workspace().member_by_path("app").unwrap().id()
+    );
+    assert_eq!(pair.new_to_old(new_app.id()).unwrap().id(), old_app.id());
+
+    let removed = old.packages().find(|p| p.name() == "removed").unwrap();
+    assert!(pair.old_to_new(removed.id()).is_none());
+    let added = new.packages().find(|p| p.name() == "added").unwrap();
+    assert!(pair.new_to_old(added.id()).is_none());
+    // IDs from the wrong graph aren't found.
+    assert!(pair.new_to_old(removed.id()).is_none());
+}
@@ -0,0 +1,45 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{DependencyDirection, PackageGraph, Prop09GraphParams};
+use crate::unit_tests::export_tests::{assert_same_graphs, roundtrip};
+use proptest::prelude::*;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn generated_graphs_are_consistent(
+        graph in PackageGraph::prop09_strategy(Prop09GraphParams::default()),
+    ) {
+        graph.verify().expect("generated graph is valid");
+        prop_assert!(graph.workspace().member_count() > 0);
+
+        let feature_graph = graph.feature_graph();
+        prop_assert!(
+            feature_graph.build_warnings().is_empty(),
+            "no warnings: {:?}",
+            feature_graph.build_warnings()
+        );
+
+        // Query results are a subset of the graph, and everything in them is depended on.
+        for (_, member) in graph.workspace().members() {
+            let set = graph
+                .query_forward(std::iter::once(member.id()))
+                .expect("valid ID")
+                .resolve();
+            for package_id in set.package_ids(DependencyDirection::Forward) {
+                prop_assert!(graph.metadata(package_id).is_some());
+                prop_assert!(graph.depends_on(member.id(), package_id).expect("valid IDs"));
+            }
+        }
+    }
+
+    #[test]
+    fn generated_graphs_roundtrip(
+        graph in PackageGraph::prop09_strategy(Prop09GraphParams::default()),
+    ) {
+        let metadata = graph.to_cargo_metadata().expect("export succeeded");
+        assert_same_graphs(&graph, &roundtrip(&metadata));
+    }
+}
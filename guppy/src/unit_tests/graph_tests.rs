@@ -5,10 +5,11 @@ use super::fixtures::{self, Fixture};
 use crate::graph::feature::{all_filter, none_filter, FeatureId};
 use crate::graph::{
     BuildTargetId, BuildTargetKind, DependencyDirection, DotWrite, PackageDotVisitor, PackageLink,
-    PackageMetadata,
+    PackageMetadata, PackageSource,
 };
 use std::fmt;
 use std::iter;
+use std::path::Path;
 
 mod small {
     use super::*;
@@ -149,6 +150,38 @@ mod small {
         assert_eq!(root_ids, expected, "feature graph root IDs match");
     }
 
+    #[test]
+    fn metadata2_sources() {
+        let graph = Fixture::metadata2().graph();
+
+        let testcrate = graph
+            .metadata(&package_id(fixtures::METADATA2_TESTCRATE))
+            .expect("testcrate should exist");
+        assert_eq!(
+            testcrate.source(),
+            PackageSource::Workspace(Path::new("testcrate"))
+        );
+
+        let quote = graph
+            .metadata(&package_id(fixtures::METADATA2_QUOTE))
+            .expect("quote should exist");
+        assert_eq!(
+            quote.source(),
+            PackageSource::Path(Path::new("/Users/fakeuser/local/quote"))
+        );
+
+        let serde = graph
+            .metadata(&package_id(
+                "serde 1.0.100 (registry+https://github.com/rust-lang/crates.io-index)",
+            ))
+            .expect("serde should exist");
+        assert!(serde.source().is_crates_io());
+        assert_eq!(
+            serde.source().external_source(),
+            Some(PackageSource::CRATES_IO_REGISTRY)
+        );
+    }
+
     proptest_suite!(metadata2);
 
     #[test]
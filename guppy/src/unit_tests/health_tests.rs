@@ -0,0 +1,109 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::health::FreshnessPolicy;
+use crate::graph::maintenance::{MaintenanceEnricher, MaintenanceSignals};
+use crate::graph::PackageGraph;
+use crate::unit_tests::fixtures::METADATA1;
+use crate::Version;
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::{Duration, UNIX_EPOCH};
+
+const DAY: u64 = 24 * 60 * 60;
+
+#[test]
+fn health_metadata1() {
+    let dir = tempfile::tempdir().expect("created temp dir");
+    let root = dir.path();
+    let json = METADATA1.replace("/fakepath", root.to_str().expect("valid UTF-8 path"));
+    fs::create_dir_all(root.join("testcrate")).unwrap();
+    fs::write(
+        root.join("testcrate").join("Cargo.toml"),
+        "[package]\nname = \"testcrate\"\nversion = \"0.1.0\"\nrust-version = \"1.42\"\n",
+    )
+    .unwrap();
+    let mut graph = PackageGraph::from_json(&json).expect("graph built");
+
+    let mut index = BTreeMap::new();
+    index.insert(
+        "datatest".to_string(),
+        MaintenanceSignals::new().with_latest_version(Version::new(0, 6, 0)),
+    );
+    index.insert(
+        "serde".to_string(),
+        MaintenanceSignals::new()
+            .with_latest_version(Version::new(2, 1, 0))
+            .with_yanked(true),
+    );
+    index.insert(
+        "region".to_string(),
+        MaintenanceSignals::new().with_release_dates(vec![UNIX_EPOCH + Duration::from_secs(DAY)]),
+    );
+    index.insert(
+        "libc".to_string(),
+        MaintenanceSignals::new().with_rust_version(Version::new(1, 50, 0)),
+    );
+    graph
+        .enrich_maintenance(&MaintenanceEnricher::new().add_source(&index))
+        .expect("enrichment succeeded");
+
+    let policy = FreshnessPolicy::new(
+        Duration::from_secs(365 * DAY),
+        UNIX_EPOCH + Duration::from_secs(1200 * DAY),
+    );
+    let report = graph.health_report(&policy);
+    assert_eq!(report.members().len(), 1);
+    assert!(!report.is_healthy());
+
+    let member = &report.members()[0];
+    assert_eq!(member.member().name(), "testcrate");
+    let score_of = |name: &str| {
+        member
+            .deps()
+            .iter()
+            .find(|dep| dep.package().name() == name)
+            .unwrap_or_else(|| panic!("{} is a dependency", name))
+    };
+
+    let datatest = score_of("datatest");
+    assert_eq!(datatest.majors_behind(), 0);
+    assert_eq!(datatest.minors_behind(), 2);
+    assert_eq!(datatest.score(), 94);
+
+    let serde = score_of("serde");
+    assert_eq!(serde.majors_behind(), 1);
+    assert!(serde.is_yanked());
+    assert_eq!(serde.score(), 35);
+
+    let region = score_of("region");
+    assert!(region.is_unmaintained());
+    assert_eq!(region.score(), 70);
+
+    let quote = score_of("quote");
+    assert_eq!(quote.duplicates().len(), 1, "quote is duplicated");
+    assert_eq!(quote.score(), 95);
+
+    assert!(score_of("memchr").is_fresh());
+
+    let msrv_violations: Vec<_> = member
+        .msrv_violations()
+        .iter()
+        .map(|dep| dep.name())
+        .collect();
+    assert_eq!(msrv_violations, ["libc"]);
+    assert_eq!(member.worst().unwrap().package().name(), "serde");
+    assert!(member.score() < 100);
+
+    let output = report.to_string();
+    assert!(
+        output.contains("serde 1.0.100: score 35 (1 major versions behind, yanked)"),
+        "report: {}",
+        output
+    );
+    assert!(
+        output.contains("libc 0.2.62: requires Rust 1.50.0, newer than 1.42.0"),
+        "report: {}",
+        output
+    );
+}
@@ -0,0 +1,121 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::PackageMetadata;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+pub(super) fn git(repo: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args([
+            "-c",
+            "user.name=guppy",
+            "-c",
+            "user.email=guppy@example.com",
+        ])
+        .args(args)
+        .status()
+        .expect("git ran");
+    assert!(status.success(), "git {:?} succeeded", args);
+}
+
+pub(super) fn write(repo: &Path, path: &str, contents: &str) {
+    let path = repo.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+pub(super) fn names<'g>(packages: &[PackageMetadata<'g>]) -> Vec<&'g str> {
+    packages.iter().map(|package| package.name()).collect()
+}
+
+pub(super) fn sorted_names<'g>(packages: &[PackageMetadata<'g>]) -> Vec<&'g str> {
+    let mut names: Vec<_> = packages.iter().map(|package| package.name()).collect();
+    names.sort_unstable();
+    names
+}
+
+pub(super) fn make_notice_workspace(root: &Path) {
+    write(
+        root,
+        "app/Cargo.toml",
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n\
+         [dependencies]\nlicensed = { path = \"../vendor/licensed\" }\n\
+         macros = { path = \"../vendor/macros\" }\n\n[workspace]\n",
+    );
+    write(root, "app/src/main.rs", "fn main() {}\n");
+    write(
+        root,
+        "vendor/licensed/Cargo.toml",
+        "[package]\nname = \"licensed\"\nversion = \"1.2.0\"\nedition = \"2018\"\n\
+         authors = [\"Alice <alice@example.com>\", \"Bob\"]\nlicense = \"MIT OR Apache-2.0\"\n\
+         repository = \"https://example.com/licensed\"\n\n\
+         [dependencies]\ncustom = { path = \"../custom\" }\n",
+    );
+    write(root, "vendor/licensed/src/lib.rs", "");
+    write(root, "vendor/licensed/LICENSE-MIT", "MIT license text\n");
+    write(
+        root,
+        "vendor/licensed/LICENSE-APACHE",
+        "Apache license text",
+    );
+    write(
+        root,
+        "vendor/custom/Cargo.toml",
+        "[package]\nname = \"custom\"\nversion = \"0.3.0\"\nedition = \"2018\"\n\
+         license-file = \"legal/TERMS.txt\"\n",
+    );
+    write(root, "vendor/custom/src/lib.rs", "");
+    write(root, "vendor/custom/legal/TERMS.txt", "Custom terms\n");
+    // Also present, but license-file takes precedence.
+    write(root, "vendor/custom/LICENSE", "Ignored\n");
+    write(
+        root,
+        "vendor/macros/Cargo.toml",
+        "[package]\nname = \"macros\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n\
+         [lib]\nproc-macro = true\n",
+    );
+    write(root, "vendor/macros/src/lib.rs", "");
+}
+
+#[cfg(feature = "unstable")]
+pub(super) fn make_unsafe_workspace(root: &Path) {
+    write(
+        root,
+        "Cargo.toml",
+        "[workspace]\nmembers = [\"app\", \"ffi\"]\n",
+    );
+    write(
+        root,
+        "app/Cargo.toml",
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n\
+         [dependencies]\nffi = { path = \"../ffi\" }\n",
+    );
+    write(root, "app/src/main.rs", "fn main() { ffi::call(); }\n");
+    write(
+        root,
+        "app/tests/integration.rs",
+        "#[test]\nfn test() { unsafe { ffi::raw() } }\n",
+    );
+    write(
+        root,
+        "ffi/Cargo.toml",
+        "[package]\nname = \"ffi\"\nversion = \"0.1.0\"\nedition = \"2018\"\n",
+    );
+    write(
+        root,
+        "ffi/src/lib.rs",
+        "mod sys;\npub fn call() { unsafe { raw() } }\npub unsafe fn raw() { sys::raw() }\n",
+    );
+    write(root, "ffi/src/sys.rs", "extern \"C\" { pub fn raw(); }\n");
+    // Nested packages aren't counted as part of the package containing them.
+    write(
+        root,
+        "ffi/src/nested/Cargo.toml",
+        "[package]\nname = \"nested\"\nversion = \"0.1.0\"\n",
+    );
+    write(root, "ffi/src/nested/lib.rs", "unsafe fn nested() {}\n");
+}
@@ -0,0 +1,80 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::history::GraphMetrics;
+use crate::graph::PackageGraph;
+use crate::unit_tests::helpers::{git, write};
+use crate::MetadataCommand;
+
+#[test]
+fn history_metrics() {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = dir.path().canonicalize().unwrap();
+    git(&repo, &["init", "-q"]);
+
+    write(&repo, "Cargo.toml", "[workspace]\nmembers = [\"a\"]\n");
+    write(
+        &repo,
+        "a/Cargo.toml",
+        "[package]\nname = \"a\"\nversion = \"0.1.0\"\nlicense = \"MIT\"\n",
+    );
+    write(&repo, "a/src/lib.rs", "");
+    git(&repo, &["add", "-A"]);
+    git(&repo, &["commit", "-q", "-m", "add a"]);
+
+    write(&repo, "a/src/lib.rs", "pub fn a() {}\n");
+    git(&repo, &["commit", "-q", "-am", "change a's source"]);
+
+    write(
+        &repo,
+        "Cargo.toml",
+        "[workspace]\nmembers = [\"a\", \"b\"]\n",
+    );
+    write(
+        &repo,
+        "a/Cargo.toml",
+        "[package]\nname = \"a\"\nversion = \"0.1.0\"\nlicense = \"MIT\"\n\n\
+         [dependencies]\nb = { path = \"../b\" }\n",
+    );
+    write(
+        &repo,
+        "b/Cargo.toml",
+        "[package]\nname = \"b\"\nversion = \"0.1.0\"\n",
+    );
+    write(&repo, "b/src/lib.rs", "");
+    git(&repo, &["add", "-A"]);
+    git(&repo, &["commit", "-q", "-m", "add b"]);
+
+    write(&repo, "b/Cargo.toml", "[package\n");
+    git(&repo, &["commit", "-q", "-am", "break b"]);
+
+    let entries = PackageGraph::history(&repo, "HEAD", &mut MetadataCommand::new())
+        .expect("history computed");
+    let summaries: Vec<_> = entries.iter().map(|entry| entry.summary()).collect();
+    assert_eq!(
+        summaries,
+        vec!["add a", "change a's source", "add b", "break b"],
+        "oldest commit first"
+    );
+
+    let first = entries[0].metrics().expect("first commit has metrics");
+    assert_eq!(first.package_count(), 1);
+    assert_eq!(first.workspace_member_count(), 1);
+    assert_eq!(first.duplicate_count(), 0);
+    assert_eq!(first.closure_sizes().get("a"), Some(&0));
+    assert_eq!(first.licenses().get("MIT"), Some(&1));
+    assert_eq!(
+        entries[1].metrics(),
+        Some(first),
+        "source changes don't affect metrics"
+    );
+
+    let third = entries[2].metrics().expect("third commit has metrics");
+    assert_eq!(third.package_count(), 2);
+    assert_eq!(third.closure_sizes().get("a"), Some(&1));
+    assert_eq!(third.closure_sizes().get("b"), Some(&0));
+    assert_eq!(third.licenses().get(GraphMetrics::NO_LICENSE), Some(&1));
+
+    assert!(entries[3].metrics().is_none());
+    assert!(entries[3].error().is_some(), "invalid manifest recorded");
+}
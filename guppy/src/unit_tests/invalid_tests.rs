@@ -1,6 +1,8 @@
 use crate::{graph::PackageGraph, unit_tests::fixtures, Error};
 use assert_matches::assert_matches;
 use cargo_metadata::{Metadata, Target};
+use std::error::Error as _;
+use std::fs;
 
 #[test]
 fn optional_dev_dep() {
@@ -80,6 +82,53 @@ fn proc_macro_mixed_kinds() {
     assert_invalid(&json, "proc-macro mixed with other crate types");
 }
 
+#[test]
+fn manifest_errors() {
+    let dir = tempfile::tempdir().expect("created temp dir");
+    let root = dir.path();
+    let json = fixtures::METADATA1.replace("/fakepath", root.to_str().expect("valid UTF-8 path"));
+    let manifest_path = root.join("testcrate").join("Cargo.toml");
+    fs::create_dir_all(root.join("testcrate")).unwrap();
+
+    // A manifest that doesn't parse is treated as if it couldn't be found.
+    fs::write(&manifest_path, "[package\nrust-version = \"1.40\"\n").unwrap();
+    let graph = PackageGraph::from_json(&json).expect("unparseable manifests are skipped");
+    let testcrate = graph
+        .packages()
+        .find(|package| package.name() == "testcrate")
+        .expect("testcrate is in the graph");
+    assert!(testcrate.manifest_spans().is_none());
+    assert_eq!(testcrate.rust_version(), None);
+    assert!(testcrate.include().is_empty());
+
+    for (contents, message) in &[
+        (
+            "[package]\nrust-version = \"latest\"\n",
+            "invalid rust-version 'latest'",
+        ),
+        (
+            "[package]\nexclude = [1]\n",
+            "'exclude' must be an array of strings",
+        ),
+    ] {
+        fs::write(&manifest_path, contents).unwrap();
+        let err = PackageGraph::from_json(&json).expect_err("expected error for invalid manifest");
+        assert_matches!(err, Error::ManifestError { .. }, "actual error is: {}", err);
+        let package_id = err.package_id().expect("manifest errors have a package ID");
+        assert_eq!(
+            package_id.parts().map(|parts| parts.name()),
+            Some("testcrate")
+        );
+        assert_eq!(err.path(), Some(manifest_path.as_path()));
+        let source = err.source().expect("manifest errors have a source");
+        assert!(
+            source.to_string().contains(message),
+            "actual source is: {}",
+            source
+        );
+    }
+}
+
 fn assert_invalid(json: &str, search_str: &str) {
     let err = PackageGraph::from_json(json).expect_err("expected error for invalid metadata");
     assert_matches!(
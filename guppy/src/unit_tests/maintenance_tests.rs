@@ -0,0 +1,165 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::maintenance::{MaintenanceEnricher, MaintenanceSignals, SignalSource};
+use crate::graph::PackageMetadata;
+use crate::unit_tests::fixtures::{self, Fixture};
+use crate::Error;
+use std::collections::BTreeMap;
+use std::error;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn days(n: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(n * DAY)
+}
+
+#[test]
+fn signals_basic() {
+    let signals = MaintenanceSignals::new().with_release_dates(vec![days(30), days(10), days(20)]);
+    assert_eq!(signals.release_dates(), &[days(10), days(20), days(30)]);
+    assert_eq!(signals.latest_release(), Some(days(30)));
+    assert_eq!(
+        signals.release_cadence(),
+        Some(Duration::from_secs(10 * DAY))
+    );
+    assert_eq!(
+        signals.unreleased_for(days(100)),
+        Some(Duration::from_secs(70 * DAY))
+    );
+    assert!(signals.is_stale(Duration::from_secs(50 * DAY), days(100)));
+    assert!(!signals.is_stale(Duration::from_secs(80 * DAY), days(100)));
+
+    let single = MaintenanceSignals::new().with_release_dates(vec![days(1)]);
+    assert_eq!(single.release_cadence(), None, "one release has no cadence");
+
+    let archived = MaintenanceSignals::new().with_archived(true);
+    assert!(
+        archived.is_stale(Duration::from_secs(DAY), days(0)),
+        "archived packages are always stale"
+    );
+    assert!(
+        !MaintenanceSignals::new().is_stale(Duration::from_secs(0), days(100)),
+        "no information means not stale"
+    );
+}
+
+#[test]
+fn enrich_metadata1() {
+    let mut graph = Fixture::metadata1().graph().clone();
+
+    let mut index = BTreeMap::new();
+    index.insert(
+        "datatest".to_string(),
+        MaintenanceSignals::new().with_release_dates(vec![days(10), days(400)]),
+    );
+    index.insert(
+        "region".to_string(),
+        MaintenanceSignals::new().with_release_dates(vec![days(1000)]),
+    );
+    // Workspace members are skipped by default.
+    index.insert(
+        "testcrate".to_string(),
+        MaintenanceSignals::new().with_release_dates(vec![days(1)]),
+    );
+    let mut forge = BTreeMap::new();
+    forge.insert(
+        "datatest".to_string(),
+        MaintenanceSignals::new()
+            .with_release_dates(vec![days(10), days(500)])
+            .with_archived(false),
+    );
+
+    let enricher = MaintenanceEnricher::new()
+        .add_source(&index)
+        .add_source(&forge);
+    let count = graph
+        .enrich_maintenance(&enricher)
+        .expect("enrichment succeeded");
+    assert_eq!(count, 2, "datatest and region have signals");
+
+    let datatest = graph
+        .metadata(&fixtures::package_id(fixtures::METADATA1_DATATEST))
+        .expect("datatest exists");
+    let signals = datatest.maintenance_signals().expect("signals attached");
+    assert_eq!(
+        signals.release_dates(),
+        &[days(10), days(400), days(500)],
+        "release dates are merged across sources"
+    );
+    assert_eq!(signals.archived(), Some(false));
+
+    let testcrate = graph
+        .metadata(&fixtures::package_id(fixtures::METADATA1_TESTCRATE))
+        .expect("testcrate exists");
+    assert!(testcrate.maintenance_signals().is_none());
+
+    let stale: Vec<_> = graph
+        .stale_packages(Duration::from_secs(365 * DAY), days(1200))
+        .map(|package| package.name())
+        .collect();
+    assert_eq!(stale, vec!["datatest"], "only datatest is stale");
+}
+
+#[test]
+fn overlapping_release_dates() {
+    let mut graph = Fixture::metadata1().graph().clone();
+
+    // A source that combines two views of the same releases, such as a registry and its mirror.
+    let registry = vec![days(10), days(20), days(30)];
+    let mirror = vec![days(20), days(30)];
+    let mut combined = BTreeMap::new();
+    combined.insert(
+        "datatest".to_string(),
+        MaintenanceSignals::new().with_release_dates(registry.into_iter().chain(mirror)),
+    );
+    let mut forge = BTreeMap::new();
+    forge.insert(
+        "datatest".to_string(),
+        MaintenanceSignals::new().with_release_dates(vec![days(30), days(40), days(40)]),
+    );
+
+    let enricher = MaintenanceEnricher::new()
+        .add_source(&combined)
+        .add_source(&forge);
+    graph
+        .enrich_maintenance(&enricher)
+        .expect("enrichment succeeded");
+    let datatest = graph
+        .metadata(&fixtures::package_id(fixtures::METADATA1_DATATEST))
+        .expect("datatest exists");
+    let signals = datatest.maintenance_signals().expect("signals attached");
+    assert_eq!(
+        signals.release_dates(),
+        &[days(10), days(20), days(30), days(40)],
+        "each release is only counted once"
+    );
+    assert_eq!(
+        signals.release_cadence(),
+        Some(Duration::from_secs(10 * DAY))
+    );
+}
+
+struct FailingSource;
+
+impl SignalSource for FailingSource {
+    fn signals(
+        &self,
+        _package: PackageMetadata<'_>,
+    ) -> Result<Option<MaintenanceSignals>, Box<dyn error::Error + Send + Sync>> {
+        Err("source unavailable".into())
+    }
+}
+
+#[test]
+fn enrich_error() {
+    let mut graph = Fixture::metadata1().graph().clone();
+    let enricher = MaintenanceEnricher::new().add_source(&FailingSource);
+    match graph.enrich_maintenance(&enricher) {
+        Err(Error::MaintenanceSignalError(_, err)) => {
+            assert_eq!(err.to_string(), "source unavailable")
+        }
+        other => panic!("expected MaintenanceSignalError, found {:?}", other),
+    }
+}
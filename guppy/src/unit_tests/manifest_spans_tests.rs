@@ -0,0 +1,106 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{BuildTargetId, ManifestSpan, PackageGraph};
+use crate::unit_tests::fixtures::METADATA1;
+use crate::DependencyKind;
+use std::fs;
+
+static TESTCRATE_MANIFEST: &str = r#"[package]
+name = "testcrate"
+version = "0.1.0"
+rust-version = "1.42"
+build = "build.rs"
+
+[features]
+default = [
+    "extra",
+]
+extra = []
+
+[dependencies]
+datatest = "0.4.2"
+
+[dev-dependencies.datatest]
+version = "0.4.2"
+# comment
+
+[target.'cfg(unix)'.build-dependencies]
+datatest = { version = "0.4.2", optional = true }
+
+[[bin]]
+name = "testcrate"
+path = "src/main.rs"
+"#;
+
+#[test]
+fn graph_manifest_spans() {
+    let dir = tempfile::tempdir().expect("created temp dir");
+    let root = dir.path();
+    let json = METADATA1.replace("/fakepath", root.to_str().expect("valid UTF-8 path"));
+    fs::create_dir_all(root.join("testcrate")).unwrap();
+    fs::write(
+        root.join("testcrate").join("Cargo.toml"),
+        TESTCRATE_MANIFEST,
+    )
+    .unwrap();
+
+    let graph = PackageGraph::from_json(&json).expect("graph built");
+    let testcrate = graph.workspace().member_by_name("testcrate").unwrap();
+    let spans = testcrate
+        .manifest_spans()
+        .expect("workspace member has spans");
+
+    assert_eq!(
+        spans.package_key("rust-version").map(bounds),
+        Some((4, 1, 4, 22))
+    );
+    assert_eq!(spans.package_key("missing"), None);
+    assert_eq!(spans.feature("default").map(bounds), Some((8, 1, 10, 2)));
+    assert_eq!(spans.feature("extra").map(bounds), Some((11, 1, 11, 11)));
+    assert_eq!(
+        spans.target(BuildTargetId::Binary("testcrate")).map(bounds),
+        Some((23, 1, 25, 21))
+    );
+    assert_eq!(
+        spans.target(BuildTargetId::BuildScript).map(bounds),
+        Some((5, 1, 5, 19))
+    );
+    assert_eq!(spans.target(BuildTargetId::Library), None);
+
+    let link = testcrate
+        .direct_links()
+        .find(|link| link.dep_name() == "datatest")
+        .expect("datatest is a dependency");
+    let declarations: Vec<_> = link
+        .declaration_spans()
+        .map(|span| (span.kind(), span.target(), bounds(span.span())))
+        .collect();
+    assert_eq!(
+        declarations,
+        vec![
+            (DependencyKind::Normal, None, (14, 1, 14, 19)),
+            (DependencyKind::Development, None, (16, 1, 17, 18)),
+            (DependencyKind::Build, Some("cfg(unix)"), (21, 1, 21, 50)),
+        ]
+    );
+
+    // Packages outside the workspace don't have spans.
+    assert!(link.to().manifest_spans().is_none());
+    assert_eq!(
+        link.to()
+            .direct_links()
+            .flat_map(|link| link.declaration_spans())
+            .count(),
+        0
+    );
+}
+
+fn bounds(span: ManifestSpan) -> (usize, usize, usize, usize) {
+    (
+        span.start_line(),
+        span.start_column(),
+        span.end_line(),
+        span.end_column(),
+    )
+}
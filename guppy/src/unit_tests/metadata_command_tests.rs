@@ -0,0 +1,29 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::unit_tests::helpers::write;
+use crate::MetadataCommand;
+
+#[test]
+fn offline_with_other_options() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    write(
+        &root,
+        "Cargo.toml",
+        "[package]\nname = \"offline\"\nversion = \"0.1.0\"\n",
+    );
+    write(&root, "src/lib.rs", "");
+
+    let mut command = MetadataCommand::new();
+    command
+        .manifest_path(root.join("Cargo.toml"))
+        .offline(true)
+        .other_options(vec!["--quiet".to_string()]);
+    let graph = command.build_graph().expect("offline workspace builds");
+    assert_eq!(graph.workspace().member_count(), 1);
+
+    // Options passed to cargo are kept separately from the --offline flag.
+    command.other_options(vec!["--bogus-option".to_string()]);
+    assert!(command.build_graph().is_err());
+}
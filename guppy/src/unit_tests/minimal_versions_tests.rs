@@ -0,0 +1,90 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::minimal_versions::{IndexEntry, IndexSource};
+use crate::unit_tests::fixtures::Fixture;
+use crate::{Error, Version};
+use std::collections::BTreeMap;
+use std::error;
+
+fn entry(version: &str, features: &[&str]) -> IndexEntry {
+    IndexEntry::new(Version::parse(version).unwrap()).with_features(features.iter().copied())
+}
+
+fn datatest_index(entries: Vec<IndexEntry>) -> BTreeMap<String, Vec<IndexEntry>> {
+    let mut index = BTreeMap::new();
+    index.insert("datatest".to_string(), entries);
+    index
+}
+
+#[test]
+fn minimal_versions_ok() {
+    // testcrate requires datatest ^0.4.2 with the unsafe_test_runner feature (as a
+    // dev-dependency).
+    let graph = Fixture::metadata1().graph();
+    let index = datatest_index(vec![
+        entry("0.4.1", &[]),
+        entry("0.4.2", &["unsafe_test_runner"]),
+    ]);
+    let report = graph.minimal_versions_check(&index).unwrap();
+    assert_eq!(report.checked(), 1);
+    assert!(report.is_ok(), "minimal version has the feature");
+}
+
+#[test]
+fn minimal_versions_missing_feature() {
+    let graph = Fixture::metadata1().graph();
+    let index = datatest_index(vec![
+        entry("0.4.10", &["unsafe_test_runner"]),
+        entry("0.4.2", &[]),
+        entry("0.4.3", &["unsafe_test_runner"]).with_yanked(true),
+    ]);
+    let report = graph.minimal_versions_check(&index).unwrap();
+    assert_eq!(report.issues().len(), 1);
+    let issue = &report.issues()[0];
+    assert_eq!(issue.link().from().name(), "testcrate");
+    assert_eq!(issue.link().to().name(), "datatest");
+    assert_eq!(issue.minimal_version(), Some(&Version::new(0, 4, 2)));
+    assert_eq!(issue.missing_features(), ["unsafe_test_runner"]);
+    assert_eq!(
+        issue.suggested_minimum(),
+        Some(&Version::new(0, 4, 10)),
+        "yanked versions are skipped"
+    );
+    assert!(format!("{}", report).contains("(require at least 0.4.10)"));
+}
+
+#[test]
+fn minimal_versions_no_match() {
+    let graph = Fixture::metadata1().graph();
+    let index = datatest_index(vec![
+        entry("0.4.1", &[]),
+        entry("0.4.2", &["unsafe_test_runner"]).with_yanked(true),
+    ]);
+    let report = graph.minimal_versions_check(&index).unwrap();
+    assert_eq!(report.issues().len(), 1);
+    assert_eq!(report.issues()[0].minimal_version(), None);
+
+    let report = graph.minimal_versions_check(&BTreeMap::new()).unwrap();
+    assert_eq!(report.checked(), 0, "unknown crates aren't checked");
+}
+
+struct FailingSource;
+
+impl IndexSource for FailingSource {
+    fn versions(
+        &self,
+        _name: &str,
+    ) -> Result<Option<Vec<IndexEntry>>, Box<dyn error::Error + Send + Sync>> {
+        Err("index unavailable".into())
+    }
+}
+
+#[test]
+fn minimal_versions_source_error() {
+    let graph = Fixture::metadata1().graph();
+    match graph.minimal_versions_check(&FailingSource) {
+        Err(Error::IndexSourceError(name, _)) => assert_eq!(name, "datatest"),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
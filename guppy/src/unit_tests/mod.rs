@@ -12,9 +12,64 @@ macro_rules! proptest_suite {
     };
 }
 
+mod anonymize_tests;
+#[cfg(feature = "unstable")]
+mod batch_tests;
+mod budget_tests;
+mod build_matrix_tests;
+mod build_scripts_tests;
+mod cargo_config_tests;
+mod cycle_audit_tests;
 mod dep_helpers;
+mod dev_weight_tests;
 mod dot_tests;
+mod export_tests;
+mod extensions_tests;
 mod feature_helpers;
+mod feature_impact_tests;
+mod file_source_tests;
 mod fixtures;
+mod git_tests;
+mod graph_builder_tests;
+mod graph_pair_tests;
+#[cfg(feature = "proptest09")]
+mod graph_strategy_tests;
 mod graph_tests;
+#[cfg(feature = "unstable")]
+mod health_tests;
+mod helpers;
+#[cfg(feature = "unstable")]
+mod history_tests;
 mod invalid_tests;
+#[cfg(feature = "unstable")]
+mod maintenance_tests;
+mod manifest_spans_tests;
+mod metadata_command_tests;
+#[cfg(feature = "unstable")]
+mod minimal_versions_tests;
+mod names_tests;
+mod notice_tests;
+mod ownership_tests;
+mod package_id_tests;
+mod path_owners_tests;
+mod platform_coverage_tests;
+mod platform_spec_tests;
+mod provenance_tests;
+mod release_notes_tests;
+mod report_tests;
+mod resolver_migration_tests;
+mod restructure_tests;
+mod target_closure_tests;
+#[cfg(feature = "unstable")]
+mod taxonomy_tests;
+mod testing_tests;
+mod toolchain_tests;
+mod transform_tests;
+#[cfg(feature = "unstable")]
+mod unsafe_usage_tests;
+#[cfg(feature = "unstable")]
+mod upgrade_tests;
+mod version_comparison_tests;
+mod version_reqs_tests;
+mod visit_tests;
+mod workspace_path_tests;
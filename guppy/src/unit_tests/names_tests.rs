@@ -0,0 +1,87 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::feature::FeatureId;
+use crate::unit_tests::fixtures::{Fixture, METADATA1_TESTCRATE};
+use crate::{Error, FeatureName, PackageId, PackageName};
+
+#[test]
+fn package_names() {
+    for name in &["serde", "serde_json", "proc-macro2", "a", "X_1-2"] {
+        let package_name = PackageName::new(*name).expect("valid name");
+        assert_eq!(package_name, *name);
+        assert_eq!(package_name.to_string(), *name);
+    }
+    assert_eq!(
+        PackageName::new("proc-macro2").unwrap().lib_name(),
+        "proc_macro2"
+    );
+
+    let long_name = "a".repeat(65);
+    for name in &[
+        "",
+        "1password",
+        "_private",
+        "-dash",
+        "foo bar",
+        "foo.rs",
+        "héllo",
+    ] {
+        match PackageName::new(*name) {
+            Err(Error::InvalidPackageName(invalid, _)) => assert_eq!(invalid, *name),
+            other => panic!("expected '{}' to be invalid, found {:?}", name, other),
+        }
+    }
+    assert!(PackageName::new(long_name).is_err());
+    assert!(PackageName::new("a".repeat(64)).is_ok());
+    assert!("serde".parse::<PackageName>().is_ok());
+}
+
+#[test]
+fn feature_names() {
+    for name in &[
+        "default",
+        "std",
+        "_internal",
+        "1.0",
+        "serde-1",
+        "c++",
+        "v1.2",
+    ] {
+        let feature_name = FeatureName::new(*name).expect("valid name");
+        assert_eq!(feature_name.as_str(), *name);
+    }
+    for name in &[
+        "",
+        "dep:serde",
+        "serde/std",
+        "-foo",
+        "+foo",
+        "foo bar",
+        "ümlaut",
+    ] {
+        match FeatureName::new(*name) {
+            Err(Error::InvalidFeatureName(invalid, _)) => assert_eq!(invalid, *name),
+            other => panic!("expected '{}' to be invalid, found {:?}", name, other),
+        }
+    }
+}
+
+#[test]
+fn feature_name_lookup() {
+    let graph = Fixture::metadata1().graph();
+    let testcrate = PackageId::new(METADATA1_TESTCRATE);
+    let feature_graph = graph.feature_graph();
+
+    let default = FeatureName::new("default").unwrap();
+    let feature_id = FeatureId::from((&testcrate, &default));
+    assert_eq!(feature_id.feature(), Some("default"));
+    assert_eq!(
+        feature_graph.metadata(feature_id).is_some(),
+        graph
+            .metadata(&testcrate)
+            .unwrap()
+            .named_features()
+            .any(|feature| default == feature)
+    );
+}
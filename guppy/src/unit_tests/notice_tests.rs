@@ -0,0 +1,96 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::cargo::CargoOptions;
+use crate::graph::feature::{all_filter, none_filter};
+use crate::graph::{BuildTargetId, PackageGraph, TargetKind};
+use crate::unit_tests::fixtures::Fixture;
+use crate::unit_tests::helpers::make_notice_workspace;
+use crate::MetadataCommand;
+use std::path::Path;
+
+fn build_graph(root: &Path) -> PackageGraph {
+    MetadataCommand::new()
+        .manifest_path(root.join("app/Cargo.toml"))
+        .build_graph()
+        .expect("graph built")
+}
+
+#[test]
+fn binary_notice() {
+    let dir = tempfile::tempdir().unwrap();
+    make_notice_workspace(dir.path());
+    let graph = build_graph(dir.path());
+    let app = graph.workspace().member_by_name("app").unwrap();
+
+    let notice = app
+        .target_closure(TargetKind::Binaries, none_filter(), &CargoOptions::new())
+        .unwrap()
+        .notice()
+        .unwrap()
+        .expect("binary is built");
+    assert_eq!(notice.package().name(), "app");
+    assert_eq!(notice.targets(), &[BuildTargetId::Binary("app")]);
+
+    // The workspace member and the proc macro aren't included.
+    let names: Vec<_> = notice
+        .entries()
+        .iter()
+        .map(|entry| entry.package().name())
+        .collect();
+    assert_eq!(names, vec!["custom", "licensed"]);
+    assert_eq!(notice.missing_license_texts().count(), 0);
+
+    let custom = &notice.entries()[0];
+    let paths: Vec<_> = custom
+        .license_texts()
+        .iter()
+        .map(|text| (text.path().to_str().unwrap(), text.text()))
+        .collect();
+    assert_eq!(paths, vec![("legal/TERMS.txt", "Custom terms\n")]);
+
+    let expected = "app 0.1.0 includes the following third-party packages.
+
+================================================================================
+custom 0.3.0
+License: unknown
+
+--- legal/TERMS.txt ---
+Custom terms
+
+================================================================================
+licensed 1.2.0
+Authors: Alice <alice@example.com>, Bob
+License: MIT OR Apache-2.0
+Repository: https://example.com/licensed
+
+--- LICENSE-APACHE ---
+Apache license text
+
+--- LICENSE-MIT ---
+MIT license text
+";
+    assert_eq!(notice.to_string(), expected);
+}
+
+#[test]
+fn sources_not_on_disk() {
+    let graph = Fixture::metadata_libra().graph();
+    let package = graph.workspace().member_by_name("libra-node").unwrap();
+    let notice = package
+        .target_closure(TargetKind::Binaries, all_filter(), &CargoOptions::new())
+        .unwrap()
+        .notice()
+        .unwrap()
+        .expect("binary is built");
+
+    assert!(!notice.entries().is_empty());
+    assert!(notice
+        .entries()
+        .iter()
+        .all(|entry| !entry.package().in_workspace() && entry.license_texts().is_empty()));
+    assert_eq!(
+        notice.missing_license_texts().count(),
+        notice.entries().len()
+    );
+}
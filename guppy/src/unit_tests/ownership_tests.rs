@@ -0,0 +1,149 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::ownership::Ownership;
+use crate::graph::{PackageGraph, PackageGraphBuilder};
+use crate::report::{MarkdownRenderer, Report};
+use crate::unit_tests::helpers::names;
+use crate::{Error, MemoryFileSource};
+use semver::Version;
+
+fn ownership_graph() -> PackageGraph {
+    let mut builder = PackageGraphBuilder::new("/fakeworkspace");
+    let version = Version::new(1, 0, 0);
+    for name in &["cli", "core", "core-macros", "server", "xtask"] {
+        builder.add_workspace_member(name.parse().unwrap(), version.clone());
+    }
+    builder.build().expect("valid graph")
+}
+
+#[test]
+fn ownership_toml() {
+    let graph = ownership_graph();
+    let ownership = Ownership::parse(
+        "owners.toml",
+        r#"
+        [teams.platform]
+        members = ["core-macros"]
+        paths = ["/core/"]
+
+        [teams.services]
+        members = ["cli", "server", "core"]
+
+        [teams.infra]
+        "#,
+    )
+    .expect("valid TOML ownership file");
+    let teams = graph.team_ownership(&ownership).expect("valid members");
+
+    assert_eq!(
+        teams.teams().collect::<Vec<_>>(),
+        vec!["infra", "platform", "services"]
+    );
+    assert_eq!(
+        names(teams.members("platform").unwrap()),
+        vec!["core", "core-macros"]
+    );
+    assert_eq!(
+        names(teams.members("services").unwrap()),
+        vec!["cli", "core", "server"]
+    );
+    assert_eq!(teams.members("infra").map(|members| members.len()), Some(0));
+    assert!(teams.members("nonexistent").is_none());
+    assert_eq!(names(teams.unowned()), vec!["xtask"]);
+
+    let core = graph.workspace().member_by_name("core").unwrap();
+    assert_eq!(teams.teams_for(core.id()), &["platform", "services"]);
+    assert!(teams.owns("services", core.id()));
+    assert!(!teams.owns("infra", core.id()));
+
+    let unknown = Ownership::parse("owners.toml", "[teams.x]\nmembers = [\"nope\"]\n").unwrap();
+    match graph.team_ownership(&unknown) {
+        Err(Error::UnknownWorkspaceName(name)) => assert_eq!(name, "nope"),
+        other => panic!("expected unknown workspace name, got {:?}", other),
+    }
+    match Ownership::parse("owners.toml", "[teams.x]\nowners = []\n") {
+        Err(Error::OwnershipFileError(path, _)) => assert_eq!(path.to_str(), Some("owners.toml")),
+        other => panic!("expected ownership file error, got {:?}", other),
+    }
+}
+
+#[test]
+fn ownership_codeowners() {
+    let graph = ownership_graph();
+    let contents = "\
+        # Default owners.\n\
+        *        @org/platform\n\
+        \n\
+        /server/ @org/services @org/oncall\n\
+        xtask\n";
+    let mut files = MemoryFileSource::new();
+    files.insert("/fakeworkspace/.github/CODEOWNERS", contents);
+    let ownership =
+        Ownership::from_path_with_files("/fakeworkspace/.github/CODEOWNERS", &files).unwrap();
+    let teams = graph.team_ownership(&ownership).unwrap();
+
+    assert_eq!(
+        names(teams.members("@org/platform").unwrap()),
+        vec!["cli", "core", "core-macros"],
+        "the last matching rule wins"
+    );
+    assert_eq!(names(teams.members("@org/oncall").unwrap()), vec!["server"]);
+    assert_eq!(
+        names(teams.unowned()),
+        vec!["xtask"],
+        "rules without teams make members unowned"
+    );
+
+    assert!(
+        Ownership::parse("CODEOWNERS", "*.rs @org/rust\n").is_err(),
+        "other glob patterns aren't supported"
+    );
+    assert!(Ownership::from_path_with_files("/missing/CODEOWNERS", &files).is_err());
+}
+
+#[test]
+fn team_breakdown() {
+    let graph = ownership_graph();
+    let ownership = Ownership::parse("CODEOWNERS", "* @core\nserver @services\nxtask\n").unwrap();
+    let teams = graph.team_ownership(&ownership).unwrap();
+
+    let items = graph
+        .workspace()
+        .members()
+        .flat_map(|(_, member)| vec![(member, "syn 1.0.0"), (member, member.name())]);
+    let breakdown = teams.breakdown("Duplicates", items);
+    assert_eq!(
+        breakdown.items("@core").unwrap(),
+        &["syn 1.0.0", "cli", "core", "core-macros"],
+        "items are deduplicated within a team"
+    );
+    assert_eq!(breakdown.unowned(), &["syn 1.0.0", "xtask"]);
+    assert_eq!(breakdown.item_count(), 8);
+    assert_eq!(
+        breakdown.to_string(),
+        "@core: 4 items\n    \
+         syn 1.0.0\n    \
+         cli\n    \
+         core\n    \
+         core-macros\n\
+         @services: 2 items\n    \
+         syn 1.0.0\n    \
+         server\n\
+         unowned: 2 items\n    \
+         syn 1.0.0\n    \
+         xtask\n\
+         Duplicates: 8 items across 2 teams\n"
+    );
+
+    let services = breakdown.clone().filter_team("@services").unwrap();
+    assert_eq!(services.teams().collect::<Vec<_>>(), vec!["@services"]);
+    assert!(services.unowned().is_empty());
+    let markdown = services.render(&MarkdownRenderer);
+    assert!(
+        markdown.contains("Duplicates by team") && markdown.contains("- server\n"),
+        "markdown: {}",
+        markdown
+    );
+    assert!(breakdown.filter_team("@nonexistent").is_none());
+}
@@ -0,0 +1,155 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::unit_tests::fixtures::{Fixture, METADATA1_TESTCRATE, METADATA_DUPS_LAZY_STATIC_1};
+use crate::{PackageId, PackageSpec};
+use semver::Version;
+
+#[test]
+fn parse_ids() {
+    let legacy =
+        PackageId::new("serde 1.0.101 (registry+https://github.com/rust-lang/crates.io-index)");
+    let parts = legacy.parts().expect("legacy format is parsed");
+    assert_eq!(parts.name(), "serde");
+    assert_eq!(parts.version(), &Version::new(1, 0, 101));
+    assert_eq!(
+        parts.source(),
+        "registry+https://github.com/rust-lang/crates.io-index"
+    );
+    assert_eq!(
+        parts.source_url(),
+        "https://github.com/rust-lang/crates.io-index"
+    );
+
+    let current =
+        PackageId::new("registry+https://github.com/rust-lang/crates.io-index#serde@1.0.101");
+    assert_eq!(current.parts(), Some(parts.clone()));
+
+    // The name is omitted if it's the same as the last path segment.
+    let path = PackageId::new("path+file:///fakepath/testcrate#0.1.0");
+    let parts = path.parts().expect("path format is parsed");
+    assert_eq!(parts.name(), "testcrate");
+    assert_eq!(parts.source_url(), "file:///fakepath/testcrate");
+
+    let git = PackageId::new("git+https://github.com/foo/bar?branch=main#baz@0.2.0");
+    let parts = git.parts().expect("git format is parsed");
+    assert_eq!(parts.name(), "baz");
+    assert_eq!(parts.source_url(), "https://github.com/foo/bar");
+    assert_eq!(
+        parts.to_spec().to_string(),
+        "git+https://github.com/foo/bar?branch=main#baz@0.2.0"
+    );
+
+    assert_eq!(PackageId::new("unknown format").parts(), None);
+    assert_eq!(PackageId::new("foo x.y.z (path+file:///foo)").parts(), None);
+}
+
+#[test]
+fn parse_specs() {
+    let spec: PackageSpec = "serde".parse().expect("valid spec");
+    assert_eq!(spec.name(), "serde");
+    assert_eq!(spec.version(), None);
+    assert_eq!(spec.url(), None);
+
+    for (input, name, version, url, display) in &[
+        (
+            "serde@1.0.101",
+            "serde",
+            Some("1.0.101"),
+            None,
+            "serde@1.0.101",
+        ),
+        ("serde:1.0", "serde", Some("1.0"), None, "serde@1.0"),
+        (
+            "https://github.com/rust-lang/crates.io-index#serde@1",
+            "serde",
+            Some("1"),
+            Some("https://github.com/rust-lang/crates.io-index"),
+            "https://github.com/rust-lang/crates.io-index#serde@1",
+        ),
+        (
+            "file:///fakepath/testcrate#0.1.0",
+            "testcrate",
+            Some("0.1.0"),
+            Some("file:///fakepath/testcrate"),
+            "file:///fakepath/testcrate#testcrate@0.1.0",
+        ),
+        (
+            "file:///fakepath/testcrate",
+            "testcrate",
+            None,
+            Some("file:///fakepath/testcrate"),
+            "file:///fakepath/testcrate#testcrate",
+        ),
+    ] {
+        let spec = PackageSpec::parse(input).expect("valid spec");
+        assert_eq!(spec.name(), *name, "name for {}", input);
+        assert_eq!(spec.version(), *version, "version for {}", input);
+        assert_eq!(spec.url(), *url, "url for {}", input);
+        assert_eq!(spec.to_string(), *display, "display for {}", input);
+        assert_eq!(
+            PackageSpec::parse(&spec.to_string()).expect("display output is valid"),
+            spec,
+            "display output for {} round-trips",
+            input
+        );
+    }
+
+    for invalid in &[
+        "",
+        "@1.0",
+        "serde@1.x",
+        "serde@1.0.0.0",
+        "ser de",
+        "file:///",
+    ] {
+        PackageSpec::parse(invalid).expect_err("invalid spec");
+    }
+}
+
+#[test]
+fn match_specs() {
+    let graph = Fixture::metadata_dups().graph();
+    let matching = |spec: &str| {
+        let spec = PackageSpec::parse(spec).expect("valid spec");
+        let mut ids: Vec<_> = graph
+            .packages_matching(&spec)
+            .map(|package| package.id().clone())
+            .collect();
+        ids.sort();
+        ids
+    };
+
+    assert_eq!(matching("lazy_static").len(), 2);
+    assert_eq!(
+        matching("lazy_static@1"),
+        vec![PackageId::new(METADATA_DUPS_LAZY_STATIC_1)]
+    );
+    assert_eq!(
+        matching("lazy_static@1.4.0"),
+        vec![PackageId::new(METADATA_DUPS_LAZY_STATIC_1)]
+    );
+    assert_eq!(
+        matching("https://github.com/rust-lang/crates.io-index#lazy_static@1.4"),
+        vec![PackageId::new(METADATA_DUPS_LAZY_STATIC_1)]
+    );
+    assert!(matching("lazy_static@1.3").is_empty());
+    assert!(matching("https://example.com/index#lazy_static").is_empty());
+
+    // Fully qualified specs match exactly one package.
+    let graph = Fixture::metadata1().graph();
+    for package in graph.packages() {
+        let spec = package
+            .id()
+            .parts()
+            .expect("fixture IDs are parsed")
+            .to_spec();
+        let matches: Vec<_> = graph.packages_matching(&spec).map(|p| p.id()).collect();
+        assert_eq!(matches, vec![package.id()], "spec {}", spec);
+    }
+    let testcrate = PackageId::new(METADATA1_TESTCRATE);
+    assert_eq!(
+        testcrate.parts().unwrap().to_spec().to_string(),
+        "path+file:///fakepath/testcrate#testcrate@0.1.0"
+    );
+}
@@ -0,0 +1,100 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{BuildTargetId, PackageGraph, PathOwnerKind};
+use crate::unit_tests::fixtures::{Fixture, METADATA1, METADATA1_TESTCRATE};
+use crate::PackageId;
+use std::fs;
+
+#[test]
+fn fixture_owners() {
+    let graph = Fixture::metadata1().graph();
+    let owners = graph.path_owners();
+    let testcrate = PackageId::new(METADATA1_TESTCRATE);
+
+    let owner = owners.owner("src/main.rs").expect("main.rs is owned");
+    assert_eq!(owner.package().id(), &testcrate);
+    assert_eq!(
+        owner.kind(),
+        PathOwnerKind::BuildTarget(BuildTargetId::Binary("testcrate"))
+    );
+    assert!(owner.is_packaged());
+
+    // Absolute paths and paths with "." and ".." components are accepted.
+    let owner = owners
+        .owner("/fakepath/testcrate/./src/../Cargo.toml")
+        .expect("Cargo.toml is owned");
+    assert_eq!(owner.package().id(), &testcrate);
+    assert_eq!(owner.kind(), PathOwnerKind::PackageDir);
+
+    // quote is a path dependency, not a workspace member.
+    assert!(owners.owner("../quote/src/lib.rs").is_none());
+    assert!(owners.owner("/fakepath/other/src/main.rs").is_none());
+}
+
+#[test]
+fn packaged_paths() {
+    let dir = tempfile::tempdir().expect("created temp dir");
+    let root = dir.path();
+    let json = METADATA1.replace("/fakepath", root.to_str().expect("valid UTF-8 path"));
+
+    fs::create_dir_all(root.join("testcrate")).unwrap();
+    fs::write(
+        root.join("testcrate").join("Cargo.toml"),
+        r#"[package]
+name = "testcrate"
+version = "0.1.0"
+exclude = ["/ci", "*.png", "!important.png", "tests/**/fixtures/", "data[0-9].txt"]
+"#,
+    )
+    .unwrap();
+
+    let graph = PackageGraph::from_json(&json).expect("graph built");
+    let testcrate = graph.workspace().member_by_name("testcrate").unwrap();
+    assert_eq!(testcrate.include(), &[] as &[String]);
+    assert_eq!(testcrate.exclude().len(), 5);
+
+    let owners = graph.path_owners();
+    let is_packaged = |path: &str| {
+        owners
+            .owner(path)
+            .unwrap_or_else(|| panic!("{} is owned", path))
+            .is_packaged()
+    };
+    assert!(is_packaged("Cargo.toml"));
+    assert!(is_packaged("src/main.rs"));
+    assert!(!is_packaged("ci/run.sh"));
+    assert!(is_packaged("src/ci/mod.rs"), "/ci is anchored");
+    assert!(!is_packaged("docs/logo.png"));
+    assert!(is_packaged("docs/important.png"));
+    assert!(!is_packaged("tests/a/b/fixtures/input.json"));
+    assert!(is_packaged("tests/fixtures"), "only directories match");
+    assert!(!is_packaged("data1.txt"));
+    assert!(is_packaged("datax.txt"));
+
+    fs::write(
+        root.join("testcrate").join("Cargo.toml"),
+        r#"[package]
+name = "testcrate"
+version = "0.1.0"
+include = ["src/**/*.rs", "README.md"]
+exclude = ["src"]
+"#,
+    )
+    .unwrap();
+    let graph = PackageGraph::from_json(&json).expect("graph built");
+    let owners = graph.path_owners();
+    let is_packaged = |path: &str| owners.owner(path).expect("path is owned").is_packaged();
+    assert!(is_packaged("Cargo.toml"), "always included");
+    assert!(is_packaged("src/main.rs"), "exclude is ignored");
+    assert!(is_packaged("src/a/b.rs"));
+    assert!(is_packaged("README.md"));
+    assert!(!is_packaged("build.rs"));
+
+    fs::write(
+        root.join("testcrate").join("Cargo.toml"),
+        "[package]\nname = \"testcrate\"\nversion = \"0.1.0\"\ninclude = \"src\"\n",
+    )
+    .unwrap();
+    PackageGraph::from_json(&json).expect_err("include must be an array");
+}
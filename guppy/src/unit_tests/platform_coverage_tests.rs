@@ -0,0 +1,128 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{BuilderDependency, EnabledTernary, PackageGraph, PackageGraphBuilder};
+use crate::unit_tests::fixtures::METADATA_TARGETS1;
+use crate::{DependencyKind, Platform, TargetFeatures};
+use semver::Version;
+
+fn linux_and_windows() -> Vec<Platform<'static>> {
+    vec![
+        Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::none()).unwrap(),
+        Platform::new("x86_64-pc-windows-msvc", TargetFeatures::none()).unwrap(),
+    ]
+}
+
+#[test]
+fn platform_coverage_targets1() {
+    let graph = PackageGraph::from_json(METADATA_TARGETS1).expect("valid metadata");
+    let platforms = linux_and_windows();
+    let coverage = graph.platform_coverage(&platforms);
+
+    let edges: Vec<_> = coverage
+        .edges()
+        .iter()
+        .map(|edge| {
+            (
+                edge.link().to().name(),
+                edge.kind(),
+                edge.specs().len(),
+                edge.statuses().to_vec(),
+            )
+        })
+        .collect();
+    use EnabledTernary::*;
+    assert_eq!(
+        edges,
+        vec![
+            ("bytes", DependencyKind::Build, 1, vec![Enabled, Disabled]),
+            ("dep-a", DependencyKind::Build, 3, vec![Enabled, Disabled]),
+            (
+                "dep-a",
+                DependencyKind::Development,
+                3,
+                vec![Enabled, Disabled]
+            ),
+            (
+                "lazy_static",
+                DependencyKind::Development,
+                1,
+                vec![Disabled, Enabled]
+            ),
+            (
+                "lazy_static",
+                DependencyKind::Normal,
+                1,
+                vec![Enabled, Disabled]
+            ),
+        ]
+    );
+    // bytes and dep-a are also unconditional normal dependencies, so those edges aren't included.
+    assert_eq!(coverage.uncovered().count(), 0);
+    // The specs for dep-a overlap on Linux.
+    assert_eq!(coverage.mutually_exclusive().count(), 0);
+}
+
+#[test]
+fn platform_coverage_builder() {
+    let mut builder = PackageGraphBuilder::new("/fakeworkspace");
+    let app = builder.add_workspace_member("app".parse().unwrap(), Version::new(0, 1, 0));
+    let os_shim = builder.add_third_party("os-shim".parse().unwrap(), Version::new(1, 0, 0));
+    let redox = builder.add_third_party("redox_syscall".parse().unwrap(), Version::new(0, 1, 0));
+    builder
+        .add_dependency(
+            &app,
+            &os_shim,
+            BuilderDependency::new(DependencyKind::Normal).with_target("cfg(unix)"),
+        )
+        .unwrap()
+        .add_dependency(
+            &app,
+            &os_shim,
+            BuilderDependency::new(DependencyKind::Normal).with_target("cfg(windows)"),
+        )
+        .unwrap()
+        .add_dependency(
+            &app,
+            &redox,
+            BuilderDependency::new(DependencyKind::Normal)
+                .with_target("cfg(target_os = \"redox\")"),
+        )
+        .unwrap();
+    let graph = builder.build().expect("valid graph");
+
+    let platforms = linux_and_windows();
+    let coverage = graph.platform_coverage(&platforms);
+    assert_eq!(coverage.edges().len(), 2);
+
+    let exclusive: Vec<_> = coverage.mutually_exclusive().collect();
+    assert_eq!(exclusive.len(), 1);
+    let specs: Vec<_> = exclusive[0]
+        .specs()
+        .iter()
+        .map(|spec| spec.as_str())
+        .collect();
+    assert_eq!(specs, ["cfg(unix)", "cfg(windows)"]);
+
+    let uncovered: Vec<_> = coverage
+        .uncovered()
+        .map(|edge| edge.link().to().name())
+        .collect();
+    assert_eq!(uncovered, ["redox_syscall"]);
+
+    let report = coverage.to_string();
+    assert!(
+        report.contains(
+            "app 0.1.0 -> redox_syscall 0.1.0 (normal): cfg(target_os = \"redox\")\n    \
+             enabled on: [] (applies to no configured platforms)\n"
+        ),
+        "report: {}",
+        report
+    );
+    assert!(
+        report
+            .ends_with("2 platform-specific edges, 1 uncovered, 1 with mutually exclusive specs\n"),
+        "report: {}",
+        report
+    );
+}
@@ -0,0 +1,51 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::cargo::CargoOptions;
+use crate::graph::feature::all_filter;
+use crate::unit_tests::fixtures::{self, Fixture};
+use crate::{Platform, PlatformSpec, TargetFeatures};
+
+#[test]
+fn platform_spec_targets1() {
+    let graph = Fixture::metadata_targets1().graph();
+    let feature_graph = graph.feature_graph();
+    let linux =
+        Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).expect("known triple");
+
+    let resolve = |platform_spec: PlatformSpec<'_>| {
+        let opts = CargoOptions::new().with_platform(platform_spec);
+        feature_graph
+            .query_workspace(all_filter())
+            .resolve_cargo(&opts)
+            .expect("valid query")
+            .target_features()
+            .to_package_set()
+    };
+    let always = resolve(PlatformSpec::Always);
+    let on_linux = resolve(PlatformSpec::Platform(&linux));
+    let any = resolve(PlatformSpec::Any);
+
+    let lazy_static_1 = fixtures::package_id(fixtures::METADATA_TARGETS1_LAZY_STATIC_1);
+    let lazy_static_02 = fixtures::package_id(fixtures::METADATA_TARGETS1_LAZY_STATIC_02);
+    for set in &[&always, &on_linux, &any] {
+        assert_eq!(set.contains(&lazy_static_1), Some(true));
+    }
+    // lazy_static 0.2 is only a dependency on not-Windows.
+    assert_eq!(always.contains(&lazy_static_02), Some(false));
+    assert_eq!(on_linux.contains(&lazy_static_02), Some(true));
+    assert_eq!(any.contains(&lazy_static_02), Some(true));
+
+    assert!(always.difference(&on_linux).is_empty());
+    assert!(on_linux.difference(&any).is_empty());
+
+    // None is the same as PlatformSpec::Any.
+    let opts = CargoOptions::new().with_platform(None);
+    let none = feature_graph
+        .query_workspace(all_filter())
+        .resolve_cargo(&opts)
+        .expect("valid query")
+        .target_features()
+        .to_package_set();
+    assert_eq!(none.len(), any.len());
+}
@@ -0,0 +1,88 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{DependencyDirection, PackageGraph, Provenance, SetOperation, SetOrigin};
+use crate::unit_tests::fixtures::{Fixture, METADATA1, METADATA1_TESTCRATE};
+use crate::PackageId;
+use std::iter;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[test]
+fn fingerprint_stable() {
+    let graph = Fixture::metadata1().graph();
+    let reparsed = PackageGraph::from_json(METADATA1).expect("valid metadata");
+    assert_eq!(graph.fingerprint(), reparsed.fingerprint());
+    assert_eq!(graph.fingerprint().len(), 16, "64-bit hex fingerprint");
+    assert_ne!(
+        graph.fingerprint(),
+        Fixture::metadata2().graph().fingerprint(),
+        "different graphs have different fingerprints"
+    );
+}
+
+#[test]
+fn provenance_origins() {
+    let graph = Fixture::metadata1().graph();
+    let testcrate = PackageId::new(METADATA1_TESTCRATE);
+
+    let query_set = graph
+        .query_forward(iter::once(&testcrate))
+        .expect("valid package ID")
+        .resolve();
+    let query_origin = SetOrigin::Query {
+        direction: DependencyDirection::Forward,
+        initials: vec![METADATA1_TESTCRATE.to_string()],
+        custom_resolver: false,
+    };
+    assert_eq!(query_set.origin(), &query_origin);
+
+    let filtered = graph
+        .query_forward(iter::once(&testcrate))
+        .expect("valid package ID")
+        .resolve_with_fn(|_, link| !link.dev_only());
+    match filtered.origin() {
+        SetOrigin::Query {
+            custom_resolver, ..
+        } => assert!(custom_resolver, "custom resolver recorded"),
+        other => panic!("unexpected origin: {:?}", other),
+    }
+
+    let ids_set = graph
+        .resolve_ids(iter::once(&testcrate))
+        .expect("valid package ID");
+    let union = query_set.union(&ids_set);
+    assert_eq!(
+        union.origin(),
+        &SetOrigin::Combined {
+            operation: SetOperation::Union,
+            left: Box::new(query_origin),
+            right: Box::new(SetOrigin::Packages {
+                ids: vec![METADATA1_TESTCRATE.to_string()],
+            }),
+        }
+    );
+    assert_eq!(graph.resolve_all().origin(), &SetOrigin::All);
+    assert_eq!(graph.resolve_workspace().origin(), &SetOrigin::Workspace);
+}
+
+#[test]
+fn provenance_roundtrip() {
+    let graph = Fixture::metadata1().graph();
+    let time = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+    let set = graph.query_workspace().resolve();
+    let provenance = set
+        .provenance()
+        .with_tool("cargo-guppy", "0.1.0")
+        .with_timestamp(time);
+    assert_eq!(provenance.package_count(), set.len());
+    assert_eq!(provenance.tool(), Some("cargo-guppy 0.1.0"));
+    assert_eq!(provenance.timestamp(), 1_600_000_000);
+    assert_eq!(provenance.guppy_version(), env!("CARGO_PKG_VERSION"));
+    assert!(provenance.matches_graph(graph));
+    assert!(!provenance.matches_graph(Fixture::metadata2().graph()));
+
+    let json = serde_json::to_string(&provenance).expect("serialization succeeded");
+    assert!(json.contains("\"graph-fingerprint\""), "kebab-case keys");
+    let deserialized: Provenance = serde_json::from_str(&json).expect("deserialization succeeded");
+    assert_eq!(provenance, deserialized);
+}
@@ -0,0 +1,128 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{
+    BuilderDependency, GraphPair, PackageChangeKind, PackageGraph, PackageGraphBuilder,
+};
+use crate::unit_tests::fixtures::Fixture;
+use crate::{DependencyKind, Error};
+use semver::Version;
+
+/// Builds a workspace with an `app` member. `deps` are (from, to, version, kind) edges, where
+/// `from` is either `app` or a previously added package.
+fn build_graph(deps: &[(&str, &str, Version, DependencyKind)]) -> PackageGraph {
+    let mut builder = PackageGraphBuilder::new("/workspace");
+    let mut ids = vec![(
+        "app",
+        builder.add_workspace_member("app".parse().unwrap(), Version::new(0, 2, 0)),
+    )];
+    for (from, to, version, kind) in deps {
+        let to_id = builder.add_third_party(to.parse().unwrap(), version.clone());
+        let from_id = ids
+            .iter()
+            .find(|(name, _)| name == from)
+            .map(|(_, id)| id.clone())
+            .expect("from was added");
+        builder
+            .add_dependency(&from_id, &to_id, BuilderDependency::new(*kind))
+            .unwrap();
+        ids.push((to, to_id));
+    }
+    builder.build().expect("valid graph")
+}
+
+#[test]
+fn dependency_changelog() {
+    use DependencyKind::*;
+
+    let old = build_graph(&[
+        ("app", "foo", Version::new(1, 0, 0), Normal),
+        ("foo", "bar", Version::new(0, 3, 0), Normal),
+        ("app", "gone", Version::new(2, 0, 0), Build),
+        ("app", "testonly", Version::new(1, 0, 0), Development),
+    ]);
+    let new = build_graph(&[
+        ("app", "foo", Version::new(1, 1, 0), Normal),
+        ("foo", "bar", Version::new(0, 3, 0), Normal),
+        ("foo", "fresh", Version::new(0, 1, 0), Normal),
+        // Dev-only dependencies aren't shipped, so changes to them aren't reported.
+        ("app", "testonly", Version::new(2, 0, 0), Development),
+    ]);
+    let pair = GraphPair::new(&old, &new);
+    let changelog = pair.dependency_changelog("app").unwrap();
+
+    let changes: Vec<_> = changelog
+        .changes()
+        .iter()
+        .map(|change| {
+            (
+                change
+                    .new_package()
+                    .or(change.old_package())
+                    .unwrap()
+                    .name(),
+                change.kind(),
+                change.is_direct(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            ("foo", PackageChangeKind::VersionChanged, true),
+            ("fresh", PackageChangeKind::Added, false),
+            ("gone", PackageChangeKind::Removed, true),
+        ]
+    );
+    assert_eq!(changelog.direct_changes().count(), 2);
+    assert_eq!(changelog.transitive_changes().count(), 1);
+
+    let expected = "## app 0.2.0
+
+### Direct dependencies
+
+- Updated `foo` from 1.0.0 to 1.1.0
+- Removed `gone` 2.0.0
+
+### Transitive dependencies
+
+- Added `fresh` 0.1.0
+";
+    assert_eq!(changelog.to_string(), expected);
+
+    let unchanged = GraphPair::new(&old, &old);
+    let changelog = unchanged.dependency_changelog("app").unwrap();
+    assert!(changelog.is_empty());
+    assert_eq!(
+        changelog.to_string(),
+        "## app 0.2.0\n\nNo dependency changes.\n"
+    );
+
+    match pair.dependency_changelog("unknown") {
+        Err(Error::UnknownWorkspaceName(name)) => assert_eq!(name, "unknown"),
+        other => panic!("expected unknown workspace name, found {:?}", other),
+    }
+}
+
+#[test]
+fn changelog_urls() {
+    let old = Fixture::metadata_libra_9ffd93b().graph();
+    let new = Fixture::metadata_libra().graph();
+    let pair = GraphPair::new(old, new);
+    let changelog = pair.dependency_changelog("libra-node").unwrap();
+    assert!(!changelog.is_empty());
+
+    for change in changelog.changes() {
+        let package = change.new_package().or(change.old_package()).unwrap();
+        let url = change.changelog_url();
+        match package.repository() {
+            Some(repository) if repository.starts_with("https://github.com/") => {
+                let url = url.expect("repository is specified");
+                assert!(url.ends_with("/releases"), "{} -> {}", repository, url);
+                assert_eq!(url.matches('/').count(), 5, "{}", url);
+            }
+            Some(_) => assert!(url.is_some()),
+            None => assert_eq!(url, None),
+        }
+    }
+}
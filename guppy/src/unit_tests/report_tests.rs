@@ -0,0 +1,147 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::report::{
+    HtmlRenderer, JsonRenderer, MarkdownRenderer, Renderer, Report, ReportDocument, ReportFormat,
+    TextRenderer,
+};
+use crate::unit_tests::fixtures::Fixture;
+
+fn document() -> ReportDocument {
+    let mut document = ReportDocument::new("Example <report>");
+    let section = document.add_section(Some("Findings".to_string()));
+    section
+        .add_item("app -> serde")
+        .add_detail("via \"derive\" & more")
+        .add_detail("nested");
+    section.add_item("util");
+    document.add_section(None).add_item("ungrouped");
+    document.set_summary("2 findings");
+    document
+}
+
+#[test]
+fn render_text() {
+    assert_eq!(
+        TextRenderer.render(&document()),
+        "Example <report>\n\
+         \n\
+         Findings:\n\
+         app -> serde\n\
+         \x20   via \"derive\" & more\n\
+         \x20       nested\n\
+         util\n\
+         \n\
+         ungrouped\n\
+         \n\
+         2 findings\n"
+    );
+}
+
+#[test]
+fn render_markdown() {
+    assert_eq!(
+        MarkdownRenderer.render(&document()),
+        "# Example <report>\n\
+         \n\
+         ## Findings\n\
+         \n\
+         - app -> serde\n\
+         \x20 - via \"derive\" & more\n\
+         \x20   - nested\n\
+         - util\n\
+         \n\
+         - ungrouped\n\
+         \n\
+         2 findings\n"
+    );
+}
+
+#[test]
+fn render_html() {
+    assert_eq!(
+        HtmlRenderer.render(&document()),
+        "<h1>Example &lt;report&gt;</h1>\n\
+         <h2>Findings</h2>\n\
+         <ul>\n\
+         <li>app -&gt; serde\n\
+         <ul>\n\
+         <li>via &quot;derive&quot; &amp; more\n\
+         <ul>\n\
+         <li>nested</li>\n\
+         </ul>\n\
+         </li>\n\
+         </ul>\n\
+         </li>\n\
+         <li>util</li>\n\
+         </ul>\n\
+         <ul>\n\
+         <li>ungrouped</li>\n\
+         </ul>\n\
+         <p>2 findings</p>\n"
+    );
+}
+
+#[test]
+fn render_json() {
+    let json: serde_json::Value =
+        serde_json::from_str(&JsonRenderer.render(&document())).expect("valid JSON");
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "title": "Example <report>",
+            "sections": [
+                {
+                    "heading": "Findings",
+                    "items": [
+                        {
+                            "text": "app -> serde",
+                            "details": [{
+                                "text": "via \"derive\" & more",
+                                "details": [{ "text": "nested" }],
+                            }],
+                        },
+                        { "text": "util" },
+                    ],
+                },
+                { "heading": null, "items": [{ "text": "ungrouped" }] },
+            ],
+            "summary": "2 findings",
+        })
+    );
+}
+
+#[test]
+fn report_formats() {
+    for format in ReportFormat::VALUES {
+        assert_eq!(format.as_str().parse::<ReportFormat>(), Ok(*format));
+    }
+    assert!("slack".parse::<ReportFormat>().is_err());
+
+    // Custom renderers only need to implement render_to.
+    struct TitleOnly;
+    impl Renderer for TitleOnly {
+        fn render_to(
+            &self,
+            document: &ReportDocument,
+            out: &mut dyn std::fmt::Write,
+        ) -> std::fmt::Result {
+            write!(out, "*{}*", document.title())
+        }
+    }
+
+    let graph = Fixture::metadata_libra().graph();
+    let report = graph.dev_weight_report();
+    assert_eq!(report.render(&TitleOnly), "*Dev-dependency weight*");
+
+    let document = report.to_document();
+    assert_eq!(document.sections()[0].items().len(), report.members().len());
+    assert!(document
+        .summary()
+        .expect("dev weight reports have a summary")
+        .starts_with("workspace: "));
+    assert_eq!(
+        report.render(ReportFormat::Text.renderer()),
+        TextRenderer.render(&document)
+    );
+}
@@ -0,0 +1,106 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::cargo::{BuildPlatform, CargoOptions, FeatureChangeKind, ResolverMigration};
+use crate::graph::feature::{all_filter, FeatureId};
+use crate::unit_tests::fixtures::{self, Fixture};
+use crate::{Platform, TargetFeatures};
+
+#[test]
+fn resolver_migration_targets1() {
+    let graph = Fixture::metadata_targets1().graph();
+    let feature_graph = graph.feature_graph();
+    let platform =
+        Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::none()).expect("known triple");
+    let opts = CargoOptions::new().with_platform(Some(&platform));
+
+    let migration = ResolverMigration::new(feature_graph.query_workspace(all_filter()), &opts)
+        .expect("valid query");
+    assert!(!migration.is_unchanged());
+
+    let testcrate = fixtures::package_id(fixtures::METADATA_TARGETS1_TESTCRATE);
+    let dep_a = fixtures::package_id(fixtures::METADATA_TARGETS1_DEP_A);
+    let bytes = fixtures::package_id(fixtures::METADATA_TARGETS1_BYTES);
+
+    let disabled: Vec<_> = migration
+        .disabled()
+        .map(|change| (change.feature_id(), change.platform()))
+        .collect();
+    assert_eq!(
+        disabled,
+        vec![
+            (FeatureId::new(&bytes, "default"), BuildPlatform::Target),
+            (FeatureId::new(&bytes, "std"), BuildPlatform::Target),
+            (FeatureId::new(&dep_a, "bar"), BuildPlatform::Target),
+            (FeatureId::new(&dep_a, "baz"), BuildPlatform::Target),
+            (FeatureId::new(&dep_a, "quux"), BuildPlatform::Target),
+        ],
+        "migration: {}",
+        migration
+    );
+
+    // dep-a/bar is only requested on x86, which the V1 resolver unifies anyway.
+    let bar = migration
+        .changes()
+        .iter()
+        .find(|change| change.feature_id() == FeatureId::new(&dep_a, "bar"))
+        .expect("dep-a/bar changes");
+    assert_eq!(bar.kind(), FeatureChangeKind::Disabled);
+    assert_eq!(
+        bar.chain(),
+        &[
+            FeatureId::new(&testcrate, "dep-a"),
+            FeatureId::new(&dep_a, "bar")
+        ]
+    );
+    assert_eq!(
+        migration
+            .v1_set()
+            .target_features()
+            .contains(bar.feature_id()),
+        Some(true)
+    );
+    assert_eq!(
+        migration
+            .v2_set()
+            .target_features()
+            .contains(bar.feature_id()),
+        Some(false)
+    );
+
+    // The V2 resolver builds build dependencies on the host even if the package doesn't appear to
+    // have a build script.
+    let enabled: Vec<_> = migration
+        .changes()
+        .iter()
+        .filter(|change| change.kind() == FeatureChangeKind::Enabled)
+        .map(|change| change.platform())
+        .collect();
+    assert!(!enabled.is_empty());
+    assert!(enabled
+        .iter()
+        .all(|platform| *platform == BuildPlatform::Host));
+
+    let output = migration.to_string();
+    assert!(
+        output.contains("dep-a 0.1.0/bar (target): disabled\n    via testcrate-targets 0.1.0/dep-a -> dep-a 0.1.0/bar\n"),
+        "output: {}",
+        output
+    );
+}
+
+#[test]
+fn resolver_migration_unchanged() {
+    let graph = Fixture::metadata1().graph();
+    let feature_graph = graph.feature_graph();
+    let lazy_static = graph
+        .packages()
+        .find(|package| package.name() == "lazy_static")
+        .expect("lazy_static exists");
+
+    // A leaf package with no dependencies resolves the same way under both versions.
+    let query = feature_graph.query_forward(vec![FeatureId::base(lazy_static.id())]);
+    let migration = ResolverMigration::new(query.expect("valid ID"), &CargoOptions::new())
+        .expect("valid query");
+    assert!(migration.is_unchanged(), "migration: {}", migration);
+}
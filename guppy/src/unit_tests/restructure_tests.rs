@@ -0,0 +1,128 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{BuilderDependency, PackageGraph, PackageGraphBuilder};
+use crate::report::{MarkdownRenderer, Report};
+use crate::unit_tests::helpers::names;
+use crate::DependencyKind;
+use semver::Version;
+
+fn restructure_graph() -> PackageGraph {
+    let mut builder = PackageGraphBuilder::new("/fakeworkspace");
+    let version = Version::new(1, 0, 0);
+    let mut member =
+        |name: &str| builder.add_workspace_member(name.parse().unwrap(), version.clone());
+    let app = member("app");
+    let cli = member("cli");
+    let core = member("core");
+    let core_macros = member("core-macros");
+    let server = member("server");
+    let server_tests = member("server-tests");
+    let util = member("util");
+
+    builder
+        .add_feature(&util, "json".parse().unwrap(), Vec::<String>::new())
+        .unwrap()
+        .add_feature(&util, "yaml".parse().unwrap(), vec!["json"])
+        .unwrap()
+        .add_feature(&util, "tls".parse().unwrap(), Vec::<String>::new())
+        .unwrap()
+        .add_feature(&core, "macros".parse().unwrap(), Vec::<String>::new())
+        .unwrap();
+
+    let normal = || BuilderDependency::new(DependencyKind::Normal);
+    let features = |features: &[&str]| {
+        normal().with_features(features.iter().map(|feature| feature.parse().unwrap()))
+    };
+    builder
+        .add_dependency(&app, &core, features(&["macros"]))
+        .unwrap()
+        .add_dependency(&app, &util, features(&["json"]))
+        .unwrap()
+        .add_dependency(&cli, &core, normal())
+        .unwrap()
+        .add_dependency(&cli, &util, features(&["yaml"]))
+        .unwrap()
+        .add_dependency(&core, &core_macros, normal())
+        .unwrap()
+        .add_dependency(&server, &util, features(&["tls"]))
+        .unwrap()
+        .add_dependency(
+            &server,
+            &server_tests,
+            BuilderDependency::new(DependencyKind::Development),
+        )
+        .unwrap()
+        .add_dependency(&server_tests, &server, normal())
+        .unwrap();
+    builder.build().expect("valid graph")
+}
+
+#[test]
+fn restructure_report() {
+    let graph = restructure_graph();
+    let report = graph.restructure_report();
+    assert!(!report.is_empty());
+
+    let merges: Vec<_> = report
+        .merges()
+        .iter()
+        .map(|merge| {
+            (
+                names(merge.members()),
+                merge.internal_links(),
+                merge.external_links(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        merges,
+        vec![
+            (vec!["server", "server-tests"], 2, 1),
+            (vec!["core", "core-macros"], 1, 2),
+        ],
+        "groups are ranked by cohesion"
+    );
+    assert!((report.merges()[1].cohesion() - 1.0 / 3.0).abs() < 1e-9);
+
+    let splits = report.splits();
+    assert_eq!(splits.len(), 1, "core's features are only used by app");
+    assert_eq!(splits[0].member().name(), "util");
+    let groups: Vec<_> = splits[0]
+        .groups()
+        .iter()
+        .map(|group| (group.features().to_vec(), names(group.dependents())))
+        .collect();
+    assert_eq!(
+        groups,
+        vec![
+            (vec!["json", "yaml"], vec!["app", "cli"]),
+            (vec!["tls"], vec!["server"]),
+        ],
+        "yaml enables json, so app and cli overlap"
+    );
+
+    assert_eq!(
+        report.to_string(),
+        "merge candidates:\n    \
+         server, server-tests: 2 internal links, 1 external (66% cohesion)\n    \
+         core, core-macros: 1 internal links, 2 external (33% cohesion)\n\
+         split candidates:\n    \
+         util: 2 disjoint feature groups\n        \
+         json, yaml: used by app, cli\n        \
+         tls: used by server\n\
+         2 merge candidates, 1 split candidates\n"
+    );
+
+    let markdown = report.render(&MarkdownRenderer);
+    assert!(
+        markdown.contains("## Split candidates\n"),
+        "markdown: {}",
+        markdown
+    );
+    assert!(
+        markdown.contains("- util: 2 disjoint feature groups\n  - json, yaml: used by app, cli\n"),
+        "markdown: {}",
+        markdown
+    );
+}
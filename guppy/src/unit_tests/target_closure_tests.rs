@@ -0,0 +1,149 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::cargo::CargoOptions;
+use crate::graph::feature::{all_filter, feature_filter, none_filter};
+use crate::graph::{BuildTargetId, DependencyDirection, PackageGraph, TargetKind};
+use crate::unit_tests::fixtures::{
+    Fixture, METADATA_BUILD_TARGETS1, METADATA_BUILD_TARGETS1_TESTCRATE,
+};
+use crate::PackageId;
+use serde_json::{json, Value};
+
+#[test]
+fn kinds_select_targets() {
+    let graph = Fixture::metadata_build_targets1().graph();
+    let testcrate = graph
+        .metadata(&PackageId::new(METADATA_BUILD_TARGETS1_TESTCRATE))
+        .expect("testcrate is present");
+    let opts = CargoOptions::new();
+
+    let expected: &[(TargetKind, &[BuildTargetId])] = &[
+        (TargetKind::Library, &[BuildTargetId::Library]),
+        (TargetKind::Binaries, &[BuildTargetId::Binary("testcrate")]),
+        (
+            TargetKind::Tests,
+            &[BuildTargetId::Library, BuildTargetId::Binary("testcrate")],
+        ),
+        (TargetKind::Examples, &[BuildTargetId::Example("example1")]),
+        (
+            TargetKind::Benchmarks,
+            &[
+                BuildTargetId::Benchmark("bench1"),
+                BuildTargetId::Benchmark("bench2"),
+            ],
+        ),
+    ];
+    for (kind, targets) in expected {
+        let closure = testcrate
+            .target_closure(*kind, all_filter(), &opts)
+            .expect("valid closure");
+        assert_eq!(closure.kind(), *kind);
+        assert_eq!(closure.targets(), *targets, "targets for {:?}", kind);
+        assert!(
+            closure.skipped().is_empty(),
+            "nothing skipped for {:?}",
+            kind
+        );
+        assert!(closure.cargo_set().is_some(), "{:?} is built", kind);
+    }
+}
+
+#[test]
+fn dev_deps_per_kind() {
+    let graph = Fixture::metadata_libra().graph();
+    let network = graph
+        .metadata(&PackageId::new(
+            "network 0.1.0 (path+file:///Users/fakeuser/local/libra/network)",
+        ))
+        .expect("network is present");
+    // Options that include dev-dependencies are overridden by the target kind.
+    let opts = CargoOptions::new().with_dev_deps(true);
+
+    let has_criterion = |kind| {
+        let closure = network
+            .target_closure(kind, none_filter(), &opts)
+            .expect("valid closure");
+        let cargo_set = closure.into_cargo_set().expect("targets are built");
+        let package_set = cargo_set.target_features().to_package_set();
+        let found = package_set
+            .packages(DependencyDirection::Forward)
+            .any(|package| package.name() == "criterion");
+        found
+    };
+    assert!(!has_criterion(TargetKind::Library), "lib has no dev-deps");
+    assert!(has_criterion(TargetKind::Tests), "tests have dev-deps");
+    assert!(
+        has_criterion(TargetKind::Benchmarks),
+        "benches have dev-deps"
+    );
+}
+
+#[test]
+fn required_features() {
+    let graph = build_targets_with_required_features();
+    let testcrate = graph
+        .metadata(&PackageId::new(METADATA_BUILD_TARGETS1_TESTCRATE))
+        .expect("testcrate is present");
+    let opts = CargoOptions::new();
+
+    let closure = testcrate
+        .target_closure(TargetKind::Benchmarks, none_filter(), &opts)
+        .expect("valid closure");
+    assert_eq!(closure.targets(), &[BuildTargetId::Benchmark("bench2")]);
+    assert_eq!(closure.skipped().len(), 1);
+    assert_eq!(
+        closure.skipped()[0].id(),
+        BuildTargetId::Benchmark("bench1")
+    );
+    assert_eq!(closure.skipped()[0].missing_features(), &["extra"]);
+    assert_eq!(
+        closure.missing_features().into_iter().collect::<Vec<_>>(),
+        vec!["extra"]
+    );
+
+    let closure = testcrate
+        .target_closure(TargetKind::Examples, none_filter(), &opts)
+        .expect("valid closure");
+    assert!(closure.targets().is_empty(), "example1 is skipped");
+    assert!(closure.cargo_set().is_none(), "nothing is built");
+
+    let closure = testcrate
+        .target_closure(
+            TargetKind::Benchmarks,
+            feature_filter(none_filter(), vec!["extra"]),
+            &opts,
+        )
+        .expect("valid closure");
+    assert_eq!(
+        closure.targets(),
+        &[
+            BuildTargetId::Benchmark("bench1"),
+            BuildTargetId::Benchmark("bench2"),
+        ]
+    );
+    assert!(closure.missing_features().is_empty());
+}
+
+/// Returns the build targets fixture, with bench1 and example1 requiring a new feature.
+fn build_targets_with_required_features() -> PackageGraph {
+    let mut metadata: Value =
+        serde_json::from_str(METADATA_BUILD_TARGETS1).expect("fixture is valid JSON");
+    let testcrate = metadata["packages"]
+        .as_array_mut()
+        .expect("packages is an array")
+        .iter_mut()
+        .find(|package| package["id"] == METADATA_BUILD_TARGETS1_TESTCRATE)
+        .expect("testcrate is present");
+    testcrate["features"] = json!({ "extra": [] });
+    for target in testcrate["targets"]
+        .as_array_mut()
+        .expect("targets is an array")
+    {
+        let kind = &target["kind"][0];
+        if (kind == "bench" && target["name"] == "bench1") || kind == "example" {
+            target["required-features"] = json!(["extra"]);
+        }
+    }
+    PackageGraph::from_json(metadata.to_string()).expect("modified fixture is valid")
+}
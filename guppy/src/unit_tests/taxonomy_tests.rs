@@ -0,0 +1,96 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::taxonomy::{PackageTag, Taxonomy};
+use crate::graph::{PackageGraph, PackageMetadata};
+use crate::unit_tests::fixtures::Fixture;
+use std::collections::BTreeSet;
+use std::iter;
+
+fn package<'g>(graph: &'g PackageGraph, name: &str) -> PackageMetadata<'g> {
+    graph
+        .packages()
+        .find(|package| package.name() == name)
+        .unwrap_or_else(|| panic!("package {} found", name))
+}
+
+fn tags(taxonomy: &Taxonomy, graph: &PackageGraph, name: &str) -> Vec<PackageTag> {
+    taxonomy.tags(&package(graph, name)).into_iter().collect()
+}
+
+#[test]
+fn default_tags() {
+    let graph = Fixture::metadata_libra_9ffd93b().graph();
+    let taxonomy = Taxonomy::new();
+
+    for (name, expected) in &[
+        ("tokio", vec![PackageTag::AsyncRuntime]),
+        ("rustls", vec![PackageTag::Tls]),
+        ("openssl", vec![PackageTag::Tls]),
+        ("openssl-sys", vec![PackageTag::Native]),
+        (
+            "security-framework-sys",
+            vec![PackageTag::Tls, PackageTag::Native],
+        ),
+        ("serde", vec![PackageTag::Serialization]),
+        (
+            "serde_derive",
+            vec![PackageTag::Serialization, PackageTag::ProcMacro],
+        ),
+        // Build scripts that depend on cc.
+        ("libloading", vec![PackageTag::Native]),
+        ("clear_on_drop", vec![PackageTag::Native]),
+        ("web-sys", vec![]),
+        ("cc", vec![]),
+        ("thiserror-impl", vec![PackageTag::ProcMacro]),
+    ] {
+        assert_eq!(&tags(&taxonomy, graph, name), expected, "tags for {}", name);
+    }
+}
+
+#[test]
+fn custom_rules() {
+    let graph = Fixture::metadata_libra_9ffd93b().graph();
+    let mut taxonomy = Taxonomy::new();
+    taxonomy
+        .add_name(PackageTag::Native, "web_sys")
+        .exclude_name(PackageTag::Native, "openssl-sys")
+        .exclude_name(PackageTag::ProcMacro, "thiserror-impl");
+    assert_eq!(tags(&taxonomy, graph, "web-sys"), vec![PackageTag::Native]);
+    assert_eq!(tags(&taxonomy, graph, "openssl-sys"), vec![]);
+    assert_eq!(tags(&taxonomy, graph, "thiserror-impl"), vec![]);
+
+    let empty = Taxonomy::empty();
+    assert_eq!(tags(&empty, graph, "tokio"), vec![]);
+    assert_eq!(
+        tags(&empty, graph, "serde_derive"),
+        vec![PackageTag::ProcMacro],
+        "heuristics apply to empty taxonomies"
+    );
+}
+
+#[test]
+fn packages_with_tag() {
+    let graph = Fixture::metadata_libra_9ffd93b().graph();
+    let openssl = package(graph, "openssl");
+    let closure = graph
+        .query_forward(iter::once(openssl.id()))
+        .unwrap()
+        .resolve();
+    let native: BTreeSet<_> = Taxonomy::new()
+        .packages_with_tag(&closure, PackageTag::Native)
+        .into_iter()
+        .map(|package| package.name())
+        .collect();
+    assert!(native.contains("openssl-sys"), "native: {:?}", native);
+    assert!(!native.contains("openssl"), "native: {:?}", native);
+}
+
+#[test]
+fn tag_names() {
+    for tag in PackageTag::VALUES {
+        assert_eq!(tag.as_str().parse::<PackageTag>(), Ok(*tag));
+        assert_eq!(serde_json::to_string(tag).unwrap(), format!("\"{}\"", tag));
+    }
+    assert!("bogus".parse::<PackageTag>().is_err());
+}
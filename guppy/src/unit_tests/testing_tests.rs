@@ -0,0 +1,78 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::testing::{check_snapshot, Redactor};
+use crate::unit_tests::fixtures::Fixture;
+use std::fs;
+
+#[test]
+fn redact_libra_paths() {
+    let graph = Fixture::metadata_libra().graph();
+    let redactor = Redactor::for_graph(graph);
+
+    let mut report: Vec<_> = graph
+        .packages()
+        .map(|package| format!("{}: {}", package.name(), package.manifest_path().display()))
+        .collect();
+    report.push(format!(
+        "target: {}",
+        graph.workspace().target_directory().display()
+    ));
+    let redacted = redactor.redact(&report.join("\n"));
+
+    assert!(
+        !redacted.contains("/Users/fakeuser"),
+        "redacted: {}",
+        redacted
+    );
+    assert!(
+        !redacted.contains("1ecc6299db9ec823"),
+        "redacted: {}",
+        redacted
+    );
+    for expected in &[
+        "bech32: [CARGO_HOME]/registry/src/github.com-[HASH]/bech32-0.6.0/Cargo.toml",
+        "bzip2-sys: [CARGO_HOME]/git/checkouts/bzip2-rs-[HASH]/[REV]/bzip2-sys/Cargo.toml",
+        "libra-logger: [WORKSPACE]/common/logger/Cargo.toml",
+        "target: [TARGET]",
+    ] {
+        assert!(
+            redacted.lines().any(|line| line == *expected),
+            "expected line {:?} in:\n{}",
+            expected,
+            redacted
+        );
+    }
+}
+
+#[test]
+fn redact_text() {
+    let redactor = Redactor::new()
+        .with_text("secret", "[SECRET]")
+        .with_text("secret-token", "[TOKEN]");
+    assert_eq!(
+        redactor.redact("secret secret-token registry/src/index.crates.io-6f17d22bba15001f/x"),
+        "[SECRET] [TOKEN] registry/src/index.crates.io-[HASH]/x"
+    );
+    // Only hashes in Cargo home directories are redacted.
+    assert_eq!(
+        redactor.redact("build/foo-6f17d22bba15001f registry/src/not-a-hash/x"),
+        "build/foo-6f17d22bba15001f registry/src/not-a-hash/x"
+    );
+}
+
+#[test]
+fn snapshots() {
+    let dir = tempfile::tempdir().expect("tempdir created");
+    let path = dir.path().join("snapshots/report.txt");
+
+    let err = check_snapshot(&path, "report\n", false).expect_err("snapshot is missing");
+    assert!(err.contains("GUPPY_UPDATE_SNAPSHOTS"), "error: {}", err);
+
+    check_snapshot(&path, "report\r\n", true).expect("snapshot written");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "report\n");
+    check_snapshot(&path, "report\n", false).expect("snapshot matches");
+    check_snapshot(&path, "report\r\n", false).expect("line endings are normalized");
+    let err = check_snapshot(&path, "other\n", false).expect_err("snapshot doesn't match");
+    assert!(err.contains("--- actual\nother"), "error: {}", err);
+}
@@ -0,0 +1,197 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::cargo::CargoOptions;
+use crate::graph::feature::all_filter;
+use crate::graph::{BuilderDependency, PackageGraph, PackageGraphBuilder};
+use crate::toolchain::{Toolchain, ToolchainChannel};
+use crate::unit_tests::fixtures::METADATA1;
+use crate::{CargoMetadata, DependencyKind, PackageName, Platform, RealFileSource, TargetFeatures};
+use semver::Version;
+use std::fs;
+use target_spec::RustcVersion;
+
+#[test]
+fn parse_channels() {
+    assert_eq!(ToolchainChannel::new("stable"), ToolchainChannel::Stable);
+    assert_eq!(
+        ToolchainChannel::new("stable-x86_64-unknown-linux-gnu"),
+        ToolchainChannel::Stable
+    );
+    assert_eq!(ToolchainChannel::new("beta"), ToolchainChannel::Beta);
+    assert_eq!(
+        ToolchainChannel::new("nightly"),
+        ToolchainChannel::Nightly(None)
+    );
+    assert_eq!(
+        ToolchainChannel::new("nightly-2020-05-01"),
+        ToolchainChannel::Nightly(Some("2020-05-01".into()))
+    );
+    assert_eq!(
+        ToolchainChannel::new("1.42"),
+        ToolchainChannel::Version(Version::new(1, 42, 0))
+    );
+    assert_eq!(
+        ToolchainChannel::new("1.43.1"),
+        ToolchainChannel::Version(Version::new(1, 43, 1))
+    );
+    assert_eq!(
+        ToolchainChannel::new("my-toolchain"),
+        ToolchainChannel::Custom("my-toolchain".into())
+    );
+}
+
+#[test]
+fn parse_files() {
+    let legacy = Toolchain::parse("rust-toolchain", "nightly-2020-05-01\n").unwrap();
+    assert_eq!(
+        legacy.channel(),
+        &ToolchainChannel::Nightly(Some("2020-05-01".into()))
+    );
+    assert_eq!(legacy.version(), None);
+
+    let toml = Toolchain::parse(
+        "rust-toolchain.toml",
+        r#"
+[toolchain]
+channel = "1.43.0"
+components = ["rustfmt", "clippy"]
+targets = ["wasm32-unknown-unknown"]
+profile = "minimal"
+"#,
+    )
+    .unwrap();
+    assert_eq!(toml.version(), Some(&Version::new(1, 43, 0)));
+    assert_eq!(toml.components(), &["rustfmt", "clippy"]);
+    assert_eq!(toml.targets(), &["wasm32-unknown-unknown"]);
+    assert_eq!(toml.profile(), Some("minimal"));
+
+    assert!(Toolchain::parse("rust-toolchain.toml", "[toolchain]\n").is_err());
+    assert!(Toolchain::parse("rust-toolchain", "").is_err());
+}
+
+#[test]
+fn discover_toolchains() {
+    let dir = tempfile::tempdir().expect("created temp dir");
+    let root = dir.path();
+    assert_eq!(Toolchain::discover(root).unwrap(), None);
+
+    fs::write(
+        root.join("rust-toolchain.toml"),
+        "[toolchain]\nchannel = \"1.40.0\"\n",
+    )
+    .unwrap();
+    let nested = root.join("a").join("b");
+    fs::create_dir_all(&nested).unwrap();
+    let toolchain = Toolchain::discover(&nested)
+        .unwrap()
+        .expect("toolchain found in ancestor");
+    assert_eq!(toolchain.version(), Some(&Version::new(1, 40, 0)));
+    assert_eq!(toolchain.path(), root.join("rust-toolchain.toml"));
+
+    // The legacy file takes precedence, like with rustup.
+    fs::write(root.join("rust-toolchain"), "nightly\n").unwrap();
+    let toolchain = Toolchain::from_dir(root).unwrap().expect("toolchain found");
+    assert_eq!(toolchain.channel(), &ToolchainChannel::Nightly(None));
+}
+
+#[test]
+fn graph_toolchains() {
+    let dir = tempfile::tempdir().expect("created temp dir");
+    let root = dir.path();
+    let json = METADATA1.replace("/fakepath", root.to_str().expect("valid UTF-8 path"));
+    let build = || {
+        let metadata = CargoMetadata::parse_json(&json).expect("valid metadata");
+        PackageGraph::from_metadata_with_files(metadata, &RealFileSource).expect("graph built")
+    };
+
+    // The workspace root is testcrate, so this file is ignored.
+    fs::write(root.join("rust-toolchain"), "nightly\n").unwrap();
+    fs::create_dir_all(root.join("testcrate")).unwrap();
+    fs::write(
+        root.join("testcrate").join("rust-toolchain.toml"),
+        "[toolchain]\nchannel = \"1.40\"\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("testcrate").join("Cargo.toml"),
+        "[package]\nname = \"testcrate\"\nversion = \"0.1.0\"\nrust-version = \"1.42\"\n",
+    )
+    .unwrap();
+
+    let graph = build();
+    let workspace_toolchain = graph.workspace().toolchain().expect("toolchain found");
+    assert_eq!(workspace_toolchain.version(), Some(&Version::new(1, 40, 0)));
+
+    let testcrate = graph.workspace().member_by_name("testcrate").unwrap();
+    assert_eq!(testcrate.rust_version(), Some(&Version::new(1, 42, 0)));
+    assert_eq!(testcrate.toolchain(), Some(workspace_toolchain));
+
+    let violations = graph.toolchain_msrv_violations();
+    assert_eq!(violations.len(), 1, "testcrate needs a newer toolchain");
+    assert_eq!(violations[0].package().id(), testcrate.id());
+
+    // Toolchain files are only read if asked for.
+    let graph = PackageGraph::from_json(&json).expect("graph built");
+    assert_eq!(graph.workspace().toolchain(), None);
+    assert!(graph.toolchain_msrv_violations().is_empty());
+
+    // A toolchain file that doesn't parse is skipped rather than failing the build.
+    fs::write(
+        root.join("testcrate").join("rust-toolchain.toml"),
+        "[toolchain]\n",
+    )
+    .unwrap();
+    let graph = build();
+    assert_eq!(graph.workspace().toolchain(), None);
+    assert_eq!(
+        graph
+            .workspace()
+            .member_by_name("testcrate")
+            .unwrap()
+            .toolchain(),
+        None
+    );
+}
+
+#[test]
+fn pinned_version_predicates() {
+    let dir = tempfile::tempdir().expect("created temp dir");
+    let root = dir.path();
+    fs::write(root.join("rust-toolchain"), "1.45.0\n").unwrap();
+
+    let mut builder = PackageGraphBuilder::new(root);
+    let app = builder.add_workspace_member(PackageName::new("app").unwrap(), Version::new(0, 1, 0));
+    let newdep =
+        builder.add_third_party(PackageName::new("newdep").unwrap(), Version::new(1, 0, 0));
+    builder
+        .add_dependency(
+            &app,
+            &newdep,
+            BuilderDependency::new(DependencyKind::Normal).with_target("cfg(version(\"1.50\"))"),
+        )
+        .unwrap();
+    let graph = builder.build().expect("graph is valid");
+
+    let includes_newdep = |platform: &Platform<'_>| {
+        let opts = CargoOptions::new().with_platform(platform);
+        graph
+            .feature_graph()
+            .query_workspace(all_filter())
+            .resolve_cargo(&opts)
+            .expect("valid query")
+            .target_features()
+            .to_package_set()
+            .contains(&newdep)
+            .expect("valid package ID")
+    };
+
+    // The pinned toolchain is older than 1.50, so the dependency isn't enabled. Without a known
+    // version, the dependency would be treated as possibly enabled.
+    let mut linux =
+        Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).expect("known triple");
+    assert!(!includes_newdep(&linux));
+    // A rustc version set on the platform takes precedence.
+    linux.set_rustc_version(Some(RustcVersion::new(1, 52, 0)));
+    assert!(includes_newdep(&linux));
+}
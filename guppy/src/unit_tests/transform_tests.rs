@@ -0,0 +1,194 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{
+    BuilderDependency, DependencyDirection, GraphTransforms, PackageGraph, PackageGraphBuilder,
+};
+use crate::unit_tests::fixtures::Fixture;
+use crate::DependencyKind;
+use semver::Version;
+use std::collections::{BTreeMap, HashSet};
+
+fn duplicate_count(graph: &PackageGraph) -> usize {
+    let mut names = HashSet::new();
+    graph
+        .packages()
+        .filter(|package| !names.insert(package.name()))
+        .count()
+}
+
+#[test]
+fn drop_dev_edges() {
+    let graph = Fixture::metadata_libra().graph();
+    let transformed = GraphTransforms::new()
+        .drop_dev_edges()
+        .apply(graph)
+        .expect("transform succeeded");
+
+    assert_eq!(
+        transformed.workspace().member_count(),
+        graph.workspace().member_count()
+    );
+    for package in transformed.packages() {
+        for link in package.direct_links() {
+            assert!(!link.dev_only(), "dev-only link remains: {:?}", link);
+            assert!(!link.dev().is_present());
+        }
+    }
+    // Packages that were only brought in through dev-dependencies are gone.
+    let shipped = graph
+        .query_workspace()
+        .resolve_with_fn(|_, link| !link.dev_only());
+    assert_eq!(transformed.package_count(), shipped.len());
+}
+
+#[test]
+fn collapse_third_party() {
+    let graph = Fixture::metadata_libra().graph();
+    let transformed = GraphTransforms::new()
+        .collapse_third_party()
+        .apply(graph)
+        .expect("transform succeeded");
+
+    let direct: HashSet<_> = transformed
+        .workspace()
+        .members()
+        .flat_map(|(_, member)| member.direct_links())
+        .map(|link| link.to().id())
+        .collect();
+    let third_party: Vec<_> = transformed
+        .packages()
+        .filter(|package| !package.in_workspace())
+        .collect();
+    assert!(!third_party.is_empty());
+    for package in third_party {
+        assert_eq!(package.direct_links().count(), 0, "{}", package.name());
+        assert!(direct.contains(package.id()), "{}", package.name());
+    }
+}
+
+#[test]
+fn merge_duplicate_versions() {
+    let graph = Fixture::metadata_libra().graph();
+    let mut highest = BTreeMap::new();
+    for package in graph.packages() {
+        let version = highest.entry(package.name()).or_insert(package.version());
+        if package.version() > *version {
+            *version = package.version();
+        }
+    }
+    assert!(duplicate_count(graph) > 0, "fixture has duplicates");
+
+    let transformed = GraphTransforms::new()
+        .merge_duplicate_versions()
+        .apply(graph)
+        .expect("transform succeeded");
+    assert_eq!(duplicate_count(&transformed), 0);
+    for package in transformed.packages() {
+        assert_eq!(
+            Some(&package.version()),
+            highest.get(package.name()),
+            "{} is the highest version",
+            package.name()
+        );
+    }
+}
+
+#[test]
+fn relabel() {
+    let mut builder = PackageGraphBuilder::new("/fakeworkspace");
+    let serde = builder.add_third_party("serde".parse().unwrap(), Version::new(1, 0, 100));
+    let rand = builder.add_third_party("rand".parse().unwrap(), Version::new(0, 7, 2));
+    let version = Version::new(0, 1, 0);
+    let app = builder.add_workspace_member("app".parse().unwrap(), version.clone());
+    let util = builder.add_workspace_member("util".parse().unwrap(), version.clone());
+    let tool = builder.add_workspace_member("tool".parse().unwrap(), version);
+    let dep = || BuilderDependency::new(DependencyKind::Normal);
+    builder
+        .add_dependency(&app, &util, dep())
+        .unwrap()
+        .add_dependency(&app, &serde, dep())
+        .unwrap()
+        .add_dependency(&util, &rand, dep())
+        .unwrap()
+        .add_dependency(&tool, &app, dep())
+        .unwrap();
+    let graph = builder.build().expect("valid graph");
+
+    let transformed = GraphTransforms::new()
+        .relabel(|package| match package.name() {
+            "app" | "util" => Some("team-a".to_string()),
+            "tool" => Some("team-b".to_string()),
+            _ => None,
+        })
+        .apply(&graph)
+        .expect("transform succeeded");
+
+    let mut members: Vec<_> = transformed
+        .workspace()
+        .members()
+        .map(|(_, member)| member.name())
+        .collect();
+    members.sort_unstable();
+    assert_eq!(members, vec!["team-a", "team-b"]);
+
+    let links = |name: &str| {
+        let mut links: Vec<_> = transformed
+            .packages()
+            .find(|package| package.name() == name)
+            .expect("package exists")
+            .direct_links()
+            .map(|link| link.to().name())
+            .collect();
+        links.sort_unstable();
+        links
+    };
+    // The link between app and util is internal to team-a, so it's dropped.
+    assert_eq!(links("team-a"), vec!["rand", "serde"]);
+    assert_eq!(links("team-b"), vec!["team-a"]);
+}
+
+#[test]
+fn compose() {
+    let graph = Fixture::metadata_libra().graph();
+    let transformed = GraphTransforms::new().apply(graph).unwrap();
+    assert_eq!(transformed.package_count(), graph.package_count());
+
+    let transformed = GraphTransforms::new()
+        .drop_dev_edges()
+        .merge_duplicate_versions()
+        .collapse_third_party()
+        .apply(graph)
+        .expect("transform succeeded");
+    assert_eq!(duplicate_count(&transformed), 0);
+    assert!(transformed.package_count() < graph.package_count());
+    // Derived graphs work with queries and exporters like any other graph.
+    let set = transformed.query_workspace().resolve();
+    assert_eq!(set.len(), transformed.package_count());
+    transformed.to_cargo_metadata().expect("export succeeded");
+
+    // Merging the whole workspace into one node keeps all of its third-party dependencies.
+    let transformed = GraphTransforms::new()
+        .drop_dev_edges()
+        .relabel(|package| {
+            if package.in_workspace() {
+                Some("libra".to_string())
+            } else {
+                None
+            }
+        })
+        .apply(graph)
+        .expect("transform succeeded");
+    assert_eq!(transformed.workspace().member_count(), 1);
+    let shipped = graph
+        .query_workspace()
+        .resolve_with_fn(|_, link| !link.dev_only());
+    assert_eq!(
+        transformed.package_count(),
+        shipped
+            .packages(DependencyDirection::Forward)
+            .filter(|package| !package.in_workspace())
+            .count()
+            + 1
+    );
+}
@@ -0,0 +1,153 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::unsafe_usage::UnsafeStats;
+use crate::graph::PackageGraph;
+use crate::unit_tests::fixtures::Fixture;
+use crate::unit_tests::helpers::make_unsafe_workspace;
+use crate::{Error, MetadataCommand, PackageId};
+
+fn stats(
+    unsafe_blocks: usize,
+    unsafe_fns: usize,
+    unsafe_impls: usize,
+    extern_items: usize,
+) -> UnsafeStats {
+    UnsafeStats {
+        unsafe_blocks,
+        unsafe_fns,
+        unsafe_impls,
+        extern_items,
+    }
+}
+
+#[test]
+fn from_source() {
+    let source = r##"
+extern crate libc;
+
+extern "C" {
+    fn strlen(s: *const u8) -> usize;
+}
+
+extern {
+    fn abort() -> !;
+}
+
+pub extern "C" fn callback() {}
+
+pub extern "Rust" fn not_ffi() {}
+
+pub unsafe extern "C" fn unsafe_callback() {}
+
+unsafe fn danger<'a>(x: &'a u8) -> u8 {
+    let c = '{';
+    let q = '\'';
+    let s = "unsafe { }";
+    let r = r#"unsafe fn "quoted" extern "C" {"#;
+    unsafe { *x }
+}
+
+// unsafe { commented out }
+/* unsafe impl Send for Foo {} /* nested */ unsafe { } */
+
+struct Foo;
+unsafe impl Send for Foo {}
+
+fn main() {
+    'outer: loop {
+        let _ = unsafe { strlen(b"unsafe\0".as_ptr()) };
+        break 'outer;
+    }
+}
+"##;
+    assert_eq!(UnsafeStats::from_source(source), stats(2, 2, 1, 4));
+    assert!(UnsafeStats::from_source("fn main() {}").is_empty());
+}
+
+#[test]
+fn sum() {
+    let total: UnsafeStats = vec![stats(1, 2, 0, 0), stats(0, 1, 1, 3)].into_iter().sum();
+    assert_eq!(total, stats(1, 3, 1, 3));
+    assert_eq!(total.total(), 8);
+}
+
+fn package_id(graph: &PackageGraph, name: &str) -> PackageId {
+    graph
+        .packages()
+        .find(|package| package.name() == name)
+        .unwrap_or_else(|| panic!("package {} found", name))
+        .id()
+        .clone()
+}
+
+#[test]
+fn scan_workspace() {
+    let dir = tempfile::tempdir().unwrap();
+    make_unsafe_workspace(dir.path());
+    let mut graph = MetadataCommand::new()
+        .manifest_path(dir.path().join("Cargo.toml"))
+        .build_graph()
+        .unwrap();
+
+    assert_eq!(graph.scan_unsafe_usage().unwrap(), 2);
+    let ffi = graph.metadata(&package_id(&graph, "ffi")).unwrap();
+    assert_eq!(ffi.unsafe_stats(), Some(&stats(1, 1, 0, 1)));
+    // Integration tests aren't scanned.
+    let app = graph.metadata(&package_id(&graph, "app")).unwrap();
+    assert_eq!(app.unsafe_stats(), Some(&UnsafeStats::default()));
+
+    let app_id = app.id().clone();
+    let closure = graph.query_forward(vec![&app_id]).unwrap().resolve();
+    assert_eq!(closure.unsafe_stats(), stats(1, 1, 0, 1));
+    let ranked: Vec<_> = closure
+        .packages_by_unsafe_usage()
+        .into_iter()
+        .map(|(package, _)| package.name())
+        .collect();
+    assert_eq!(ranked, vec!["ffi"]);
+}
+
+#[test]
+fn attach_stats() {
+    let mut graph = Fixture::metadata1().graph().clone();
+    let package_ids: Vec<_> = graph
+        .packages()
+        .map(|package| package.id().clone())
+        .collect();
+    assert!(graph
+        .packages()
+        .all(|package| package.unsafe_stats().is_none()));
+
+    graph
+        .attach_unsafe_stats(vec![
+            (package_ids[0].clone(), stats(1, 0, 0, 0)),
+            (package_ids[1].clone(), stats(3, 0, 0, 2)),
+        ])
+        .unwrap();
+    let ranked: Vec<_> = graph
+        .resolve_all()
+        .packages_by_unsafe_usage()
+        .into_iter()
+        .map(|(package, stats)| (package.id().clone(), stats.total()))
+        .collect();
+    assert_eq!(
+        ranked,
+        vec![(package_ids[1].clone(), 5), (package_ids[0].clone(), 1)]
+    );
+
+    let unknown = PackageId::new("unknown 0.1.0");
+    match graph.attach_unsafe_stats(vec![
+        (package_ids[2].clone(), stats(1, 0, 0, 0)),
+        (unknown.clone(), stats(1, 0, 0, 0)),
+    ]) {
+        Err(Error::UnknownPackageId(package_id)) => assert_eq!(package_id, unknown),
+        other => panic!("expected unknown package ID error, found {:?}", other),
+    }
+    // Nothing was attached.
+    assert!(graph
+        .metadata(&package_ids[2])
+        .unwrap()
+        .unsafe_stats()
+        .is_none());
+}
@@ -0,0 +1,103 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::PackageLink;
+use crate::unit_tests::fixtures::{
+    Fixture, METADATA1_TESTCRATE, METADATA_DUPS_LAZY_STATIC_02, METADATA_DUPS_LAZY_STATIC_1,
+    METADATA_DUPS_TESTCRATE,
+};
+use crate::{Error, PackageId};
+use semver::Version;
+
+#[test]
+fn upgrade_split() {
+    let graph = Fixture::metadata1().graph();
+    let proc_macro2 = graph
+        .packages()
+        .find(|package| package.name() == "proc-macro2")
+        .expect("proc-macro2 is in the graph");
+
+    // datatest-derive requires ^1.0.1, while quote and syn require ^1.0.
+    let impact = graph
+        .impact_of_upgrade(proc_macro2.id(), Version::new(1, 0, 0))
+        .expect("package ID is known");
+    let names = |links: Vec<PackageLink<'_>>| {
+        let mut names: Vec<_> = links
+            .iter()
+            .map(|link| link.from().name().to_string())
+            .collect();
+        names.sort_unstable();
+        names
+    };
+    assert_eq!(
+        names(impact.upgraded_links().collect()),
+        vec!["quote", "syn"]
+    );
+    assert_eq!(
+        names(impact.held_back_links().collect()),
+        vec!["datatest-derive"]
+    );
+    assert!(impact.introduces_duplicate());
+    assert_eq!(impact.versions_before().len(), 1);
+    assert_eq!(impact.versions_after().len(), 2);
+    assert!(impact.upgraded_features().is_empty());
+    assert_eq!(
+        impact.held_back_features().into_iter().collect::<Vec<_>>(),
+        vec!["default"]
+    );
+
+    let members: Vec<_> = impact
+        .affected_members()
+        .map(|package| package.id())
+        .collect();
+    assert_eq!(members, vec![&PackageId::new(METADATA1_TESTCRATE)]);
+
+    // A compatible version is picked up by every dependent.
+    let impact = graph
+        .impact_of_upgrade(proc_macro2.id(), Version::new(1, 0, 10))
+        .expect("package ID is known");
+    assert_eq!(impact.held_back_links().count(), 0);
+    assert!(!impact.introduces_duplicate());
+    assert_eq!(impact.affected_members().count(), 1);
+}
+
+#[test]
+fn upgrade_existing_duplicate() {
+    let graph = Fixture::metadata_dups().graph();
+    let lazy_static_02 = PackageId::new(METADATA_DUPS_LAZY_STATIC_02);
+
+    // The dev-dependency on lazy_static requires ^0.2, so it can't move to 1.4.0.
+    let impact = graph
+        .impact_of_upgrade(&lazy_static_02, Version::new(1, 4, 0))
+        .expect("package ID is known");
+    assert_eq!(impact.upgraded_links().count(), 0);
+    assert_eq!(impact.affected_members().count(), 0);
+    assert!(!impact.introduces_duplicate());
+    assert_eq!(impact.versions_before(), impact.versions_after());
+
+    let impact = graph
+        .impact_of_upgrade(&lazy_static_02, Version::new(0, 2, 12))
+        .expect("package ID is known");
+    let members: Vec<_> = impact
+        .affected_members()
+        .map(|package| package.id())
+        .collect();
+    assert_eq!(members, vec![&PackageId::new(METADATA_DUPS_TESTCRATE)]);
+    assert!(!impact.introduces_duplicate());
+    assert!(impact.versions_after().contains(
+        graph
+            .metadata(&PackageId::new(METADATA_DUPS_LAZY_STATIC_1))
+            .unwrap()
+            .version()
+    ));
+
+    match graph.impact_of_upgrade(&PackageId::new("fake 1.0.0"), Version::new(1, 0, 1)) {
+        Err(Error::UnknownPackageId(package_id)) => {
+            assert_eq!(package_id, PackageId::new("fake 1.0.0"))
+        }
+        other => panic!(
+            "expected an unknown package ID error, got {:?}",
+            other.map(|_| ())
+        ),
+    }
+}
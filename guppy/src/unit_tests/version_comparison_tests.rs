@@ -0,0 +1,71 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{GraphPair, VersionComparison};
+use crate::unit_tests::fixtures::Fixture;
+
+#[test]
+fn same_graph_is_consistent() {
+    // metadata_dups has several versions of the same crates, which isn't a fork by itself.
+    let graph = Fixture::metadata_dups().graph();
+    let comparison = VersionComparison::new(vec![("a", graph), ("b", graph)]);
+    assert!(comparison.is_consistent(), "same graph is consistent");
+    assert_eq!(comparison.labels(), &["a", "b"]);
+
+    let lazy_static = comparison
+        .entries()
+        .find(|entry| entry.key().name() == "lazy_static")
+        .expect("lazy_static is present");
+    assert_eq!(
+        lazy_static.versions().len(),
+        2,
+        "two versions of lazy_static"
+    );
+    assert_eq!(lazy_static.packages(0).len(), 2);
+    assert!(
+        comparison.entries().all(|entry| entry
+            .packages(0)
+            .iter()
+            .all(|package| !package.in_workspace())),
+        "workspace members are skipped"
+    );
+}
+
+#[test]
+fn libra_versions_forked() {
+    let old = Fixture::metadata_libra_f0091a4().graph();
+    let new = Fixture::metadata_libra_9ffd93b().graph();
+    let comparison = VersionComparison::new(vec![("old", old), ("new", new)]);
+    assert!(
+        !comparison.is_consistent(),
+        "versions changed between commits"
+    );
+
+    // Every version change between a pair of graphs is a fork.
+    let pair = GraphPair::new(old, new);
+    let mut expected: Vec<_> = pair
+        .version_changes()
+        .map(|change| change.key().clone())
+        .collect();
+    expected.dedup();
+    assert!(!expected.is_empty(), "some versions changed");
+    for key in &expected {
+        let entry = comparison
+            .entries()
+            .find(|entry| entry.key() == key)
+            .expect("changed package is present");
+        assert!(entry.is_forked(), "{} is forked", key);
+    }
+
+    // Dependencies only present in one graph aren't forks.
+    for entry in comparison.forked() {
+        assert!(!entry.packages(0).is_empty() && !entry.packages(1).is_empty());
+    }
+
+    let summary = format!("{}", comparison);
+    assert!(summary.ends_with(&format!(
+        "{} dependencies across 2 graphs, {} forked\n",
+        comparison.entries().count(),
+        comparison.forked().count()
+    )));
+}
@@ -0,0 +1,123 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{BuilderDependency, PackageGraph, PackageGraphBuilder, RequirementFindingKind};
+use crate::DependencyKind;
+use semver::Version;
+
+fn requirements_graph() -> PackageGraph {
+    let mut builder = PackageGraphBuilder::new("/fakeworkspace");
+    let mut add_third_party = |name: &str, version: &str| {
+        builder.add_third_party(name.parse().unwrap(), Version::parse(version).unwrap())
+    };
+    let serde = add_third_party("serde", "1.0.100");
+    let rand_07 = add_third_party("rand", "0.7.2");
+    let rand_08 = add_third_party("rand", "0.8.1");
+    let log_14 = add_third_party("log", "1.4.0");
+    let log_16 = add_third_party("log", "1.6.0");
+    let libc_80 = add_third_party("libc", "0.2.80");
+    let libc_81 = add_third_party("libc", "0.2.81");
+    let bitflags = add_third_party("bitflags", "1.2.1");
+    let env_logger = add_third_party("env_logger", "0.8.0");
+    let fern = add_third_party("fern", "0.6.0");
+    let version = Version::new(0, 1, 0);
+    let app = builder.add_workspace_member("app".parse().unwrap(), version.clone());
+    let util = builder.add_workspace_member("util".parse().unwrap(), version);
+
+    let dep = |req: &str| BuilderDependency::new(DependencyKind::Normal).with_version_req(req);
+    builder
+        .add_dependency(&app, &serde, dep("=1.0.100"))
+        .unwrap()
+        .add_dependency(&app, &rand_07, dep(">=0.7.1, <0.7.3"))
+        .unwrap()
+        .add_dependency(&app, &log_14, dep("~1.4"))
+        .unwrap()
+        // For 0.x versions, tilde and caret requirements are the same.
+        .add_dependency(&app, &libc_80, dep("~0.2.80"))
+        .unwrap()
+        .add_dependency(&util, &rand_08, dep("0.8"))
+        .unwrap()
+        .add_dependency(&util, &bitflags, dep(">=1.0, <2"))
+        .unwrap()
+        .add_dependency(&util, &env_logger, dep("0.8"))
+        .unwrap()
+        .add_dependency(&util, &fern, dep("0.6"))
+        .unwrap()
+        .add_dependency(&env_logger, &log_16, dep("1.6"))
+        .unwrap()
+        .add_dependency(&fern, &log_14, dep("1.4"))
+        .unwrap()
+        // This requirement also matches 0.2.81, so the duplicate can be removed by updating the
+        // lockfile.
+        .add_dependency(&fern, &libc_81, dep("0.2.80"))
+        .unwrap();
+    builder.build().expect("valid graph")
+}
+
+#[test]
+fn requirement_findings() {
+    let graph = requirements_graph();
+    let report = graph.requirement_report();
+    let findings: Vec<_> = report
+        .findings()
+        .iter()
+        .map(|finding| {
+            (
+                finding.link().from().name(),
+                finding.link().to().name(),
+                finding.kind(),
+                finding.suggestion(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        findings,
+        vec![
+            ("app", "log", RequirementFindingKind::NarrowRange, "1.4"),
+            ("app", "rand", RequirementFindingKind::NarrowRange, "0.7.1"),
+            ("app", "serde", RequirementFindingKind::ExactPin, "1.0.100"),
+        ]
+    );
+}
+
+#[test]
+fn requirement_conflicts() {
+    let graph = requirements_graph();
+    let report = graph.requirement_report();
+    let conflicts: Vec<_> = report
+        .conflicts()
+        .iter()
+        .map(|conflict| {
+            let blocking: Vec<_> = conflict
+                .blocking()
+                .iter()
+                .map(|blocking| (blocking.link().from().name(), blocking.suggestion()))
+                .collect();
+            (
+                conflict.name(),
+                conflict.highest().version().to_string(),
+                blocking,
+            )
+        })
+        .collect();
+    assert_eq!(
+        conflicts,
+        vec![
+            // The tilde requirement can be loosened to allow 1.6.
+            ("log", "1.6.0".to_string(), vec![("app", "1.4")]),
+            // Unifying rand requires an upgrade across a semver-incompatible boundary.
+            ("rand", "0.8.1".to_string(), vec![("app", "0.8.1")]),
+        ]
+    );
+
+    assert_eq!(
+        report.to_string(),
+        "app -> log: narrow range \"~1.4\", consider \"1.4\"\n\
+         app -> rand: narrow range \">= 0.7.1, < 0.7.3\", consider \"0.7.1\"\n\
+         app -> serde: exact pin \"= 1.0.100\", consider \"1.0.100\"\n\
+         log has 2 versions (1.4.0, 1.6.0)\n\
+         \x20   app 0.1.0 requires \"~1.4\", consider \"1.4\"\n\
+         rand has 2 versions (0.7.2, 0.8.1)\n\
+         \x20   app 0.1.0 requires \">= 0.7.1, < 0.7.3\", consider \"0.8.1\"\n"
+    );
+}
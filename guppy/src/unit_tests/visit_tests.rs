@@ -0,0 +1,151 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::{
+    BuilderDependency, DependencyDirection, PackageGraph, PackageGraphBuilder, PackageSet,
+    VisitAction,
+};
+use crate::unit_tests::fixtures::{Fixture, METADATA1_TESTCRATE};
+use crate::{DependencyKind, PackageId};
+use semver::Version;
+use std::iter;
+
+/// Builds the graph:
+///
+/// ```text
+/// app -> lib-a -> common
+///   \--> lib-b -> common
+///          \----> leaf
+/// ```
+fn diamond() -> (PackageGraph, PackageId) {
+    let mut builder = PackageGraphBuilder::new("/fakeworkspace");
+    let app = builder.add_workspace_member("app".parse().unwrap(), Version::new(0, 1, 0));
+    let lib_a = builder.add_workspace_member("lib-a".parse().unwrap(), Version::new(0, 1, 0));
+    let lib_b = builder.add_workspace_member("lib-b".parse().unwrap(), Version::new(0, 1, 0));
+    let common = builder.add_third_party("common".parse().unwrap(), Version::new(1, 0, 0));
+    let leaf = builder.add_third_party("leaf".parse().unwrap(), Version::new(1, 0, 0));
+    for (from, to) in &[
+        (&app, &lib_a),
+        (&app, &lib_b),
+        (&lib_a, &common),
+        (&lib_b, &common),
+        (&lib_b, &leaf),
+    ] {
+        builder
+            .add_dependency(from, to, BuilderDependency::new(DependencyKind::Normal))
+            .unwrap();
+    }
+    (builder.build().expect("valid graph"), app)
+}
+
+fn names(set: &PackageSet<'_>) -> Vec<String> {
+    let mut names: Vec<_> = set
+        .packages(DependencyDirection::Forward)
+        .map(|package| package.name().to_string())
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+#[test]
+fn visit_continue_matches_query() {
+    let graph = Fixture::metadata1().graph();
+    let testcrate = PackageId::new(METADATA1_TESTCRATE);
+    let visited = graph
+        .visit(iter::once(&testcrate), |_, _| VisitAction::Continue)
+        .unwrap();
+    let resolved = graph.query_forward(&[testcrate]).unwrap().resolve();
+    assert_eq!(names(&visited), names(&resolved));
+}
+
+#[test]
+fn visit_prune_and_stop() {
+    let (graph, app) = diamond();
+
+    let direct = graph
+        .visit(iter::once(&app), |_, ctx| {
+            if ctx.depth() == 0 {
+                VisitAction::Continue
+            } else {
+                VisitAction::Prune
+            }
+        })
+        .unwrap();
+    assert_eq!(names(&direct), ["app", "lib-a", "lib-b"]);
+
+    // Pruning lib-b's subtree still reaches common through lib-a.
+    let without_b = graph
+        .visit(iter::once(&app), |link, _| {
+            if link.to().name() == "lib-b" {
+                VisitAction::Prune
+            } else {
+                VisitAction::Continue
+            }
+        })
+        .unwrap();
+    assert_eq!(names(&without_b), ["app", "common", "lib-a"]);
+
+    let mut visit_count = 0;
+    let stopped = graph
+        .visit(&[app], |_, _| {
+            visit_count += 1;
+            VisitAction::Stop
+        })
+        .unwrap();
+    assert_eq!(visit_count, 1, "traversal stops after the first link");
+    assert_eq!(names(&stopped), ["app"]);
+}
+
+#[test]
+fn visit_context() {
+    let (graph, app) = diamond();
+
+    let mut seen = vec![];
+    graph
+        .visit(&[app], |link, ctx| {
+            assert_eq!(ctx.root().name(), "app");
+            let path: Vec<_> = ctx.path().iter().map(|link| link.to().name()).collect();
+            seen.push((link.to().name(), ctx.depth(), path));
+            VisitAction::Continue
+        })
+        .unwrap();
+    seen.sort();
+
+    // Every link is visited exactly once, and common is only expanded through the first path
+    // that reaches it.
+    assert_eq!(seen.len(), graph.link_count());
+    for (name, depth, path) in &seen {
+        assert_eq!(*depth, path.len());
+        match *name {
+            "lib-a" | "lib-b" => assert!(path.is_empty(), "{} is a direct dependency", name),
+            "common" => assert!(*path == ["lib-a"] || *path == ["lib-b"]),
+            "leaf" => assert_eq!(path, &["lib-b"]),
+            other => panic!("unexpected package {}", other),
+        }
+    }
+}
+
+#[test]
+fn visit_reverse() {
+    let (graph, _) = diamond();
+    let common = graph
+        .packages()
+        .find(|package| package.name() == "common")
+        .unwrap()
+        .id()
+        .clone();
+
+    let dependents = graph
+        .visit_directed(&[common], DependencyDirection::Reverse, |link, ctx| {
+            assert_eq!(ctx.depth() == 0, link.to().name() == "common");
+            VisitAction::Continue
+        })
+        .unwrap();
+    assert_eq!(names(&dependents), ["app", "common", "lib-a", "lib-b"]);
+
+    assert!(graph
+        .visit(&[PackageId::new("fake 1.0.0")], |_, _| {
+            VisitAction::Continue
+        })
+        .is_err());
+}
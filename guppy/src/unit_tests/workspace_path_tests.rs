@@ -0,0 +1,105 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::graph::WorkspacePath;
+use crate::unit_tests::fixtures::Fixture;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn workspace_path_new() {
+    let path = WorkspacePath::new("common/./logger/../debug-interface/").unwrap();
+    assert_eq!(path.as_str(), "common/debug-interface");
+    assert_eq!(
+        path.components().collect::<Vec<_>>(),
+        ["common", "debug-interface"]
+    );
+    assert_eq!(
+        path.to_native_path(),
+        ["common", "debug-interface"].iter().collect::<PathBuf>()
+    );
+    assert_eq!(
+        path.to_absolute_path("/fakeworkspace"),
+        Path::new("/fakeworkspace")
+            .join("common")
+            .join("debug-interface")
+    );
+
+    let root = WorkspacePath::new(".").unwrap();
+    assert!(root.is_root());
+    assert_eq!(root.as_str(), "");
+    assert_eq!(root.to_native_path(), PathBuf::new());
+
+    assert_eq!(WorkspacePath::new("/absolute"), None);
+    assert_eq!(WorkspacePath::new("a/../.."), None);
+}
+
+#[test]
+fn workspace_path_from_absolute() {
+    let path = WorkspacePath::from_absolute("/root/./workspace", "/root/workspace/a/../b/lib.rs");
+    assert_eq!(path.unwrap().as_str(), "b/lib.rs");
+    assert_eq!(
+        WorkspacePath::from_absolute("/root/workspace", "/root/workspace")
+            .unwrap()
+            .as_str(),
+        ""
+    );
+    assert_eq!(
+        WorkspacePath::from_absolute("/root/workspace", "/root/other/lib.rs"),
+        None
+    );
+    assert_eq!(
+        WorkspacePath::from_absolute("/root/workspace", "/root/workspace-2/lib.rs"),
+        None
+    );
+}
+
+#[test]
+fn normalized_package_paths() {
+    let graph = Fixture::metadata_libra().graph();
+    let workspace = graph.workspace();
+    for (workspace_path, package) in workspace.members() {
+        let normalized = package
+            .normalized_workspace_path()
+            .expect("workspace members have a workspace path");
+        assert_eq!(
+            normalized.components().collect::<Vec<_>>(),
+            workspace_path
+                .components()
+                .map(|component| component.as_os_str().to_str().unwrap())
+                .collect::<Vec<_>>(),
+        );
+        assert!(!normalized.as_str().contains('\\'));
+        assert_eq!(
+            normalized.to_absolute_path(workspace.root()),
+            package.manifest_path().parent().unwrap()
+        );
+
+        let manifest_path = package.normalized_manifest_path().unwrap();
+        if normalized.is_root() {
+            assert_eq!(manifest_path.as_str(), "Cargo.toml");
+        } else {
+            assert_eq!(manifest_path.as_str(), format!("{}/Cargo.toml", normalized));
+        }
+
+        for build_target in package.build_targets() {
+            let target_path = build_target
+                .normalized_path()
+                .expect("libra's build targets are in the workspace");
+            assert_eq!(
+                target_path.to_absolute_path(workspace.root()),
+                build_target.path()
+            );
+        }
+    }
+
+    // Packages from the registry are outside the workspace root.
+    let third_party = graph
+        .packages()
+        .find(|package| !package.in_workspace())
+        .expect("libra has third-party packages");
+    assert_eq!(third_party.normalized_workspace_path(), None);
+    assert_eq!(third_party.normalized_manifest_path(), None);
+    assert!(third_party
+        .build_targets()
+        .all(|build_target| build_target.normalized_path().is_none()));
+}
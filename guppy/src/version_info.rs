@@ -0,0 +1,52 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+/// Information about this build of `guppy`, for tools that need to report or check what they can
+/// rely on at runtime.
+///
+/// # Examples
+///
+/// ```
+/// use guppy::VERSION_INFO;
+///
+/// println!("guppy {}", VERSION_INFO.version());
+/// assert!(VERSION_INFO.supports_metadata_format(1));
+/// ```
+pub static VERSION_INFO: VersionInfo = VersionInfo {
+    version: env!("CARGO_PKG_VERSION"),
+    metadata_format_versions: &[1],
+    unstable: cfg!(feature = "unstable"),
+};
+
+/// Version information for `guppy`.
+///
+/// The only instance of this is `VERSION_INFO`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VersionInfo {
+    version: &'static str,
+    metadata_format_versions: &'static [usize],
+    unstable: bool,
+}
+
+impl VersionInfo {
+    /// Returns the version of `guppy`, e.g. `0.4.1`.
+    pub fn version(&self) -> &'static str {
+        self.version
+    }
+
+    /// Returns the versions of the `cargo metadata` output format, as reported by its
+    /// `"version"` field, that `guppy` can parse.
+    pub fn metadata_format_versions(&self) -> &'static [usize] {
+        self.metadata_format_versions
+    }
+
+    /// Returns true if `guppy` can parse this version of the `cargo metadata` output format.
+    pub fn supports_metadata_format(&self, format_version: usize) -> bool {
+        self.metadata_format_versions.contains(&format_version)
+    }
+
+    /// Returns true if the experimental APIs behind the `unstable` feature are available.
+    pub fn unstable_enabled(&self) -> bool {
+        self.unstable
+    }
+}
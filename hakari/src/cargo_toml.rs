@@ -0,0 +1,246 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Read and update the `Cargo.toml` of a workspace-hack package.
+
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The `Cargo.toml` of a workspace-hack package.
+///
+/// The section managed by `hakari` is delimited by the lines `### BEGIN HAKARI SECTION` and
+/// `### END HAKARI SECTION`. Everything outside of these lines is left untouched.
+#[derive(Clone, Debug)]
+pub struct HakariCargoToml {
+    toml_path: PathBuf,
+    contents: String,
+    // start_offset is the position right after the start line.
+    start_offset: usize,
+    // end_offset is the position right before the end line.
+    end_offset: usize,
+}
+
+impl HakariCargoToml {
+    /// The line that marks the beginning of the section managed by `hakari`.
+    pub const BEGIN_SECTION: &'static str = "### BEGIN HAKARI SECTION\n";
+
+    /// The line that marks the end of the section managed by `hakari`.
+    pub const END_SECTION: &'static str = "### END HAKARI SECTION";
+
+    /// Reads the `Cargo.toml` at this path.
+    ///
+    /// Returns an error if the file couldn't be read, or if it doesn't contain the section
+    /// markers.
+    pub fn new(toml_path: impl Into<PathBuf>) -> Result<Self, CargoTomlError> {
+        let toml_path = toml_path.into();
+        let contents = match fs::read_to_string(&toml_path) {
+            Ok(contents) => contents,
+            Err(err) => return Err(CargoTomlError::Io(toml_path, err)),
+        };
+        Self::new_in_memory(toml_path, contents)
+    }
+
+    /// Creates a new instance from the given contents, which are assumed to be at the path
+    /// specified.
+    ///
+    /// Returns an error if the contents don't contain the section markers.
+    pub fn new_in_memory(
+        toml_path: impl Into<PathBuf>,
+        contents: impl Into<String>,
+    ) -> Result<Self, CargoTomlError> {
+        let toml_path = toml_path.into();
+        let contents = contents.into();
+
+        let start_offset = match contents.find(Self::BEGIN_SECTION) {
+            Some(offset) => offset + Self::BEGIN_SECTION.len(),
+            None => return Err(CargoTomlError::StartNotFound(toml_path)),
+        };
+        let end_offset = match contents[start_offset..].find(Self::END_SECTION) {
+            Some(offset) => start_offset + offset,
+            None => return Err(CargoTomlError::EndNotFound(toml_path)),
+        };
+
+        Ok(Self {
+            toml_path,
+            contents,
+            start_offset,
+            end_offset,
+        })
+    }
+
+    /// Returns the contents of a new `Cargo.toml` for a workspace-hack package with this name,
+    /// with an empty section managed by `hakari`.
+    ///
+    /// The workspace-hack package also needs a library target, which can be an empty
+    /// `src/lib.rs`.
+    pub fn template(package_name: &str) -> String {
+        format!(
+            "[package]\n\
+             name = \"{}\"\n\
+             version = \"0.1.0\"\n\
+             edition = \"2018\"\n\
+             description = \"workspace-hack package, managed by hakari\"\n\
+             publish = false\n\
+             \n\
+             # The parts of the file between the BEGIN HAKARI SECTION and END HAKARI SECTION \
+             comments\n\
+             # are managed by hakari.\n\
+             \n\
+             {}\
+             {}\n",
+            package_name,
+            Self::BEGIN_SECTION,
+            Self::END_SECTION,
+        )
+    }
+
+    /// Returns the path to the `Cargo.toml`.
+    pub fn toml_path(&self) -> &Path {
+        &self.toml_path
+    }
+
+    /// Returns the full contents of the `Cargo.toml`.
+    pub fn contents(&self) -> &str {
+        &self.contents
+    }
+
+    /// Returns the contents of the section managed by `hakari`.
+    pub fn section(&self) -> &str {
+        &self.contents[self.start_offset..self.end_offset]
+    }
+
+    /// Returns the part of the file before the section managed by `hakari`, including the
+    /// `BEGIN_SECTION` line.
+    pub fn contents_before_section(&self) -> &str {
+        &self.contents[..self.start_offset]
+    }
+
+    /// Returns the part of the file after the section managed by `hakari`, including the
+    /// `END_SECTION` line.
+    pub fn contents_after_section(&self) -> &str {
+        &self.contents[self.end_offset..]
+    }
+
+    /// Returns true if the section managed by `hakari` would change if replaced with `toml`.
+    pub fn is_changed(&self, toml: &str) -> bool {
+        self.section() != toml
+    }
+
+    /// Writes out the contents of the `Cargo.toml`, with the section managed by `hakari` replaced
+    /// by `toml`, to the given output.
+    pub fn write(&self, toml: &str, mut out: impl fmt::Write) -> fmt::Result {
+        out.write_str(self.contents_before_section())?;
+        out.write_str(toml)?;
+        out.write_str(self.contents_after_section())
+    }
+
+    /// Returns the contents of the `Cargo.toml`, with the section managed by `hakari` replaced by
+    /// `toml`.
+    pub fn write_to_string(&self, toml: &str) -> String {
+        let mut out = String::with_capacity(self.contents.len() + toml.len());
+        self.write(toml, &mut out)
+            .expect("writing to a string is infallible");
+        out
+    }
+
+    /// Replaces the section managed by `hakari` with `toml`, and writes the result out to disk.
+    ///
+    /// The file is only written out if the section changed. Returns true if the file was
+    /// written out.
+    pub fn write_to_file(&mut self, toml: &str) -> Result<bool, CargoTomlError> {
+        if !self.is_changed(toml) {
+            return Ok(false);
+        }
+        let contents = self.write_to_string(toml);
+        if let Err(err) = fs::write(&self.toml_path, &contents) {
+            return Err(CargoTomlError::Io(self.toml_path.clone(), err));
+        }
+        self.end_offset = self.start_offset + toml.len();
+        self.contents = contents;
+        Ok(true)
+    }
+}
+
+/// An error that occurred while reading or writing a workspace-hack package's `Cargo.toml`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CargoTomlError {
+    /// An error occurred while reading or writing the file.
+    Io(PathBuf, io::Error),
+
+    /// The `BEGIN_SECTION` line wasn't found.
+    StartNotFound(PathBuf),
+
+    /// The `END_SECTION` line wasn't found after the `BEGIN_SECTION` line.
+    EndNotFound(PathBuf),
+}
+
+impl fmt::Display for CargoTomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use CargoTomlError::*;
+
+        match self {
+            Io(path, err) => write!(f, "error accessing '{}': {}", path.display(), err),
+            StartNotFound(path) => write!(
+                f,
+                "in '{}', unable to find line: {}",
+                path.display(),
+                HakariCargoToml::BEGIN_SECTION.trim_end()
+            ),
+            EndNotFound(path) => write!(
+                f,
+                "in '{}', unable to find line: {}",
+                path.display(),
+                HakariCargoToml::END_SECTION
+            ),
+        }
+    }
+}
+
+impl error::Error for CargoTomlError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CargoTomlError::Io(_, err) => Some(err),
+            CargoTomlError::StartNotFound(_) | CargoTomlError::EndNotFound(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_section() {
+        let template = HakariCargoToml::template("workspace-hack");
+        let cargo_toml =
+            HakariCargoToml::new_in_memory("Cargo.toml", template.clone()).expect("markers found");
+        assert_eq!(cargo_toml.section(), "");
+        assert!(!cargo_toml.is_changed(""));
+
+        let section = "[dependencies]\nserde = { version = \"1.0.105\" }\n";
+        assert!(cargo_toml.is_changed(section));
+        let contents = cargo_toml.write_to_string(section);
+        assert!(contents.starts_with(cargo_toml.contents_before_section()));
+        assert!(contents.ends_with(&format!("{}\n", HakariCargoToml::END_SECTION)));
+
+        let cargo_toml =
+            HakariCargoToml::new_in_memory("Cargo.toml", contents).expect("markers still present");
+        assert_eq!(cargo_toml.section(), section);
+        assert!(!cargo_toml.is_changed(section));
+        toml::from_str::<toml::Value>(cargo_toml.contents()).expect("valid TOML");
+    }
+
+    #[test]
+    fn missing_markers() {
+        let err = HakariCargoToml::new_in_memory("Cargo.toml", "[package]\n").unwrap_err();
+        assert!(matches!(err, CargoTomlError::StartNotFound(_)));
+
+        let contents = format!("[package]\n{}", HakariCargoToml::BEGIN_SECTION);
+        let err = HakariCargoToml::new_in_memory("Cargo.toml", contents).unwrap_err();
+        assert!(matches!(err, CargoTomlError::EndNotFound(_)));
+    }
+}
@@ -0,0 +1,382 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Configuration for `hakari`, read from a file checked into the workspace.
+//!
+//! The configuration file lives at `.guppy/hakari.toml` relative to the workspace root, and looks
+//! like:
+//!
+//! ```toml
+//! # The name of the workspace-hack package.
+//! hakari-package = "workspace-hack"
+//!
+//! # The Cargo feature resolver version: "1", "1-install" or "2".
+//! resolver = "1"
+//!
+//! # Workspace members to omit from the simulated builds.
+//! omitted-members = ["fuzz-targets"]
+//!
+//! # Third-party packages to omit from unification. The version is optional.
+//! omitted-third-party = [
+//!     { name = "openssl-sys" },
+//!     { name = "syn", version = "0.15.44" },
+//! ]
+//!
+//! # The builds to simulate for each workspace member. See guppy's BuildMatrix for more.
+//! [build-matrix]
+//! # The platforms to simulate builds on. If empty, builds are simulated across all platforms.
+//! platforms = ["x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc"]
+//! features = ["none", "default", "all"]
+//! include-dev = [false, true]
+//!
+//! [output]
+//! # The format dependency lines are written out in: "full" or "workspace-dotted".
+//! dep-format = "full"
+//! # Whether to output exact versions, e.g. "=1.0.105".
+//! exact-versions = false
+//! # If set, a [patch] directive for this registry is written out for the root Cargo.toml.
+//! patch-registry = "crates-io"
+//! ```
+
+use crate::hakari::HakariBuilder;
+use crate::toml_out::{DepFormat, HakariOutputOptions};
+use guppy::graph::cargo::{BuildMatrix, CargoResolverVersion};
+use guppy::graph::PackageGraph;
+use serde::Deserialize;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Configuration for `hakari`.
+///
+/// All fields are optional, and correspond to options on `HakariBuilder` and
+/// `HakariOutputOptions`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct HakariConfig {
+    /// The name of the workspace-hack package, which must be a workspace member.
+    pub hakari_package: Option<String>,
+
+    /// The Cargo feature resolver version: `"1"`, `"1-install"` or `"2"`.
+    pub resolver: Option<String>,
+
+    /// The names of workspace members to omit from the simulated builds.
+    pub omitted_members: Vec<String>,
+
+    /// Third-party packages to omit from unification.
+    pub omitted_third_party: Vec<ThirdPartySpec>,
+
+    /// The builds to simulate for each workspace member.
+    pub build_matrix: BuildMatrix<'static>,
+
+    /// Options for writing out TOML.
+    pub output: OutputConfig,
+}
+
+impl HakariConfig {
+    /// The path of the configuration file, relative to the workspace root.
+    pub const CONFIG_PATH: &'static str = ".guppy/hakari.toml";
+
+    /// Parses a configuration from a TOML string.
+    pub fn parse(contents: &str) -> Result<Self, ConfigError> {
+        toml::from_str(contents).map_err(ConfigError::Parse)
+    }
+
+    /// Reads the configuration file for the workspace rooted at `workspace_root`.
+    ///
+    /// Returns `Ok(None)` if the configuration file doesn't exist.
+    pub fn read(workspace_root: impl AsRef<Path>) -> Result<Option<Self>, ConfigError> {
+        let path = workspace_root.as_ref().join(Self::CONFIG_PATH);
+        match fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&contents).map(Some),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(ConfigError::Io(path, err)),
+        }
+    }
+
+    /// Creates a `HakariBuilder` for this graph with the options in this configuration.
+    ///
+    /// Returns an error if a package or resolver version couldn't be found.
+    pub fn builder<'g>(
+        &self,
+        graph: &'g PackageGraph,
+    ) -> Result<HakariBuilder<'g, 'static>, ConfigError> {
+        let workspace = graph.workspace();
+        let member_id = |name: &str| match workspace.member_by_name(name) {
+            Some(package) => Ok(package.id()),
+            None => Err(ConfigError::UnknownMember(name.to_string())),
+        };
+
+        let hakari_id = match &self.hakari_package {
+            Some(name) => Some(member_id(name)?),
+            None => None,
+        };
+
+        let resolver_version = match self.resolver.as_deref() {
+            None | Some("1") => CargoResolverVersion::V1,
+            Some("1-install") => CargoResolverVersion::V1Install,
+            Some("2") => CargoResolverVersion::V2,
+            Some(other) => return Err(ConfigError::UnknownResolver(other.to_string())),
+        };
+
+        let omitted_members = self
+            .omitted_members
+            .iter()
+            .map(|name| member_id(name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut omitted_third_party = vec![];
+        for spec in &self.omitted_third_party {
+            let len = omitted_third_party.len();
+            omitted_third_party.extend(
+                graph
+                    .packages()
+                    .filter(|package| {
+                        !package.in_workspace()
+                            && spec.matches(package.name(), &package.version().to_string())
+                    })
+                    .map(|package| package.id()),
+            );
+            if omitted_third_party.len() == len {
+                return Err(ConfigError::UnknownThirdParty(spec.clone()));
+            }
+        }
+
+        let builder = HakariBuilder::new(graph, hakari_id)
+            .expect("hakari package ID was found in the graph")
+            .with_build_matrix(self.build_matrix.clone())
+            .with_resolver_version(resolver_version)
+            .with_omitted_members(omitted_members)
+            .with_omitted_third_party(omitted_third_party);
+        Ok(builder)
+    }
+
+    /// Returns the `HakariOutputOptions` specified by this configuration.
+    pub fn output_options(&self) -> HakariOutputOptions {
+        HakariOutputOptions::new()
+            .with_dep_format(self.output.dep_format)
+            .with_exact_versions(self.output.exact_versions)
+    }
+}
+
+/// A third-party package to omit from unification, specified by name and optionally by version.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ThirdPartySpec {
+    /// The name of the package.
+    pub name: String,
+
+    /// The exact version of the package. If unspecified, all versions are matched.
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+impl ThirdPartySpec {
+    fn matches(&self, name: &str, version: &str) -> bool {
+        self.name == name
+            && match &self.version {
+                Some(v) => v == version,
+                None => true,
+            }
+    }
+}
+
+impl fmt::Display for ThirdPartySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{} {}", self.name, version),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// The `[output]` section of the configuration.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct OutputConfig {
+    /// The format dependency lines are written out in.
+    pub dep_format: DepFormat,
+
+    /// Whether to output exact versions.
+    pub exact_versions: bool,
+
+    /// If set, a `[patch]` directive for this registry should be written out. See
+    /// `Hakari::to_patch_string`.
+    pub patch_registry: Option<String>,
+}
+
+/// An error that occurred while reading or applying a configuration.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// An error occurred while reading the configuration file.
+    Io(PathBuf, io::Error),
+
+    /// The configuration couldn't be parsed.
+    Parse(toml::de::Error),
+
+    /// A workspace member listed in the configuration wasn't found.
+    UnknownMember(String),
+
+    /// A third-party package listed in the configuration wasn't found.
+    UnknownThirdParty(ThirdPartySpec),
+
+    /// The resolver version isn't recognized.
+    UnknownResolver(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ConfigError::*;
+
+        match self {
+            Io(path, err) => write!(f, "error reading '{}': {}", path.display(), err),
+            Parse(err) => write!(f, "error parsing hakari config: {}", err),
+            UnknownMember(name) => write!(f, "unknown workspace member '{}'", name),
+            UnknownThirdParty(spec) => write!(f, "unknown third-party package '{}'", spec),
+            UnknownResolver(version) => write!(
+                f,
+                "unknown resolver version '{}' (expected \"1\", \"1-install\" or \"2\")",
+                version
+            ),
+        }
+    }
+}
+
+impl error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ConfigError::Io(_, err) => Some(err),
+            ConfigError::Parse(err) => Some(err),
+            ConfigError::UnknownMember(_)
+            | ConfigError::UnknownThirdParty(_)
+            | ConfigError::UnknownResolver(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use guppy::graph::cargo::StandardFeatures;
+
+    static METADATA_LIBRA: &str = include_str!("../../fixtures/large/metadata_libra.json");
+
+    #[test]
+    fn parse_config() {
+        let config = HakariConfig::parse(
+            r#"
+            hakari-package = "workspace-hack"
+            resolver = "2"
+            omitted-third-party = [{ name = "syn", version = "0.15.44" }]
+
+            [build-matrix]
+            features = ["default", "all"]
+            include-dev = [false]
+
+            [output]
+            dep-format = "workspace-dotted"
+            "#,
+        )
+        .expect("valid config");
+        assert_eq!(config.hakari_package.as_deref(), Some("workspace-hack"));
+        assert_eq!(config.resolver.as_deref(), Some("2"));
+        assert!(config.build_matrix.platforms().is_empty());
+        assert_eq!(
+            config.build_matrix.features(),
+            &[StandardFeatures::Default, StandardFeatures::All]
+        );
+        assert_eq!(config.build_matrix.include_dev(), &[false]);
+        assert_eq!(
+            config.omitted_third_party,
+            vec![ThirdPartySpec {
+                name: "syn".to_string(),
+                version: Some("0.15.44".to_string()),
+            }]
+        );
+        assert_eq!(config.output.dep_format, DepFormat::WorkspaceDotted);
+        assert!(!config.output.exact_versions);
+
+        let config = HakariConfig::parse("").expect("valid config");
+        assert_eq!(config.hakari_package, None);
+        assert_eq!(config.build_matrix.builds_per_platform(), 6);
+        assert_eq!(config.output, OutputConfig::default());
+
+        assert!(matches!(
+            HakariConfig::parse("unknown-key = true"),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            HakariConfig::parse("[build-matrix]\nplatforms = [\"x86_64-unknown-nonexistent\"]"),
+            Err(ConfigError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn unknown_names() {
+        let graph = PackageGraph::from_json(METADATA_LIBRA).expect("valid metadata");
+        let builder_err = |contents: &str| {
+            HakariConfig::parse(contents)
+                .expect("valid config")
+                .builder(&graph)
+                .expect_err("builder should fail")
+        };
+
+        assert!(matches!(
+            builder_err("hakari-package = \"serde\""),
+            ConfigError::UnknownMember(_)
+        ));
+        assert!(matches!(
+            builder_err("resolver = \"3\""),
+            ConfigError::UnknownResolver(_)
+        ));
+        assert!(matches!(
+            builder_err("omitted-third-party = [{ name = \"serde\", version = \"0.0.1\" }]"),
+            ConfigError::UnknownThirdParty(_)
+        ));
+    }
+
+    #[test]
+    fn libra_config() {
+        let graph = PackageGraph::from_json(METADATA_LIBRA).expect("valid metadata");
+        let config = HakariConfig::parse(
+            r#"
+            hakari-package = "libra-nibble"
+            omitted-third-party = [{ name = "serde" }]
+
+            [build-matrix]
+            platforms = ["x86_64-unknown-linux-gnu"]
+
+            [output]
+            dep-format = "workspace-dotted"
+            patch-registry = "crates-io"
+            "#,
+        )
+        .expect("valid config");
+        let hakari = config.builder(&graph).expect("valid builder").compute();
+
+        let toml = hakari
+            .to_toml_string(&config.output_options())
+            .expect("valid output");
+        let value: toml::Value = toml.parse().expect("valid TOML");
+        let deps = value["dependencies"]
+            .as_table()
+            .expect("dependencies table");
+        assert!(!deps.contains_key("serde"), "serde is omitted");
+        assert!(
+            deps.values()
+                .any(|dep| dep.get("workspace") == Some(&toml::Value::Boolean(true))),
+            "workspace-dotted output used"
+        );
+
+        let patch = hakari
+            .to_patch_string(config.output.patch_registry.as_deref().unwrap())
+            .expect("hakari package specified");
+        assert_eq!(
+            patch,
+            "[patch.crates-io]\nlibra-nibble = { path = \"common/nibble\" }\n"
+        );
+    }
+}
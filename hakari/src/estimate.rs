@@ -0,0 +1,283 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Estimate how many rebuilds a workspace-hack package avoids.
+//!
+//! Without a workspace-hack package, a third-party package is compiled once for every distinct
+//! feature set it's built with across the workspace. With one, every build sees the same
+//! features, so each package is compiled once per platform. Comparing the two gives teams a
+//! number to look at before adopting the pattern.
+
+use crate::hakari::{BuildKind, Hakari, OutputKey};
+use guppy::PackageId;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::{Add, AddAssign};
+
+impl<'g, 'a> Hakari<'g, 'a> {
+    /// Estimates the effect of the workspace-hack package on the number of distinct
+    /// `(package, feature set)` combinations built across the workspace, for every platform.
+    pub fn estimate_effect(&self) -> EffectEstimate {
+        let platform_idxs = self.builder().build_matrix().platform_idxs();
+        let platforms = platform_idxs
+            .into_iter()
+            .map(|platform_idx| {
+                let counts = |build_kind| self.instantiation_counts(platform_idx, build_kind);
+                PlatformEffect {
+                    triple: platform_idx
+                        .map(|idx| self.builder().platforms()[idx].triple().to_string()),
+                    target: counts(BuildKind::Target),
+                    host: counts(BuildKind::Host),
+                }
+            })
+            .collect();
+        EffectEstimate { platforms }
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn instantiation_counts(
+        &self,
+        platform_idx: Option<usize>,
+        build_kind: BuildKind,
+    ) -> InstantiationCounts {
+        let key = OutputKey {
+            platform_idx,
+            build_kind,
+        };
+        let computed = self.computed_map().get(&key);
+
+        let mut counts = InstantiationCounts::default();
+        let mut built: BTreeSet<&'g PackageId> = BTreeSet::new();
+        for (package_id, value) in computed.into_iter().flatten() {
+            counts.before += value.len();
+            if value.len() > 1 {
+                counts.unified += 1;
+            }
+            built.insert(package_id);
+        }
+
+        // Once the workspace-hack package is added, every package it depends on is built with a
+        // single feature set, including ones that weren't built at all before.
+        let mut output_keys = vec![OutputKey {
+            platform_idx: None,
+            build_kind,
+        }];
+        if platform_idx.is_some() {
+            output_keys.push(key);
+        }
+        for output_key in output_keys {
+            for package_id in self
+                .output_map()
+                .get(&output_key)
+                .into_iter()
+                .flatten()
+                .map(|(id, _)| *id)
+            {
+                if built.insert(package_id) {
+                    counts.added += 1;
+                }
+            }
+        }
+        counts.after = built.len();
+
+        counts
+    }
+}
+
+/// An estimate of how many rebuilds a workspace-hack package avoids.
+///
+/// Created through `Hakari::estimate_effect`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EffectEstimate {
+    platforms: Vec<PlatformEffect>,
+}
+
+impl EffectEstimate {
+    /// Returns the estimate for every platform the workspace-hack package was computed for.
+    ///
+    /// If no platforms were specified, this has a single entry with no target triple.
+    pub fn platforms(&self) -> &[PlatformEffect] {
+        &self.platforms
+    }
+
+    /// Returns the sum of the counts across all platforms and build kinds.
+    pub fn total(&self) -> InstantiationCounts {
+        self.platforms
+            .iter()
+            .map(|platform| platform.target + platform.host)
+            .fold(InstantiationCounts::default(), Add::add)
+    }
+}
+
+impl fmt::Display for EffectEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for platform in &self.platforms {
+            writeln!(
+                f,
+                "{}:",
+                platform.triple().unwrap_or("all platforms (unspecified)")
+            )?;
+            writeln!(f, "  target: {}", platform.target)?;
+            writeln!(f, "  host: {}", platform.host)?;
+        }
+        write!(f, "total: {}", self.total())
+    }
+}
+
+/// The estimated effect of a workspace-hack package on a single platform.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PlatformEffect {
+    triple: Option<String>,
+    target: InstantiationCounts,
+    host: InstantiationCounts,
+}
+
+impl PlatformEffect {
+    /// Returns the target triple for this platform, or `None` if no platforms were specified.
+    pub fn triple(&self) -> Option<&str> {
+        self.triple.as_deref()
+    }
+
+    /// Returns the counts for packages built for the target platform.
+    pub fn target(&self) -> InstantiationCounts {
+        self.target
+    }
+
+    /// Returns the counts for packages built for the host platform, such as build dependencies
+    /// and procedural macros.
+    pub fn host(&self) -> InstantiationCounts {
+        self.host
+    }
+}
+
+/// Counts of distinct `(package, feature set)` combinations for third-party packages, with and
+/// without the workspace-hack package.
+///
+/// These are estimates based on the builds that `hakari` simulates: actual numbers depend on
+/// which subsets of the workspace developers build.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct InstantiationCounts {
+    /// The number of combinations built without the workspace-hack package.
+    pub before: usize,
+
+    /// The number of combinations built with the workspace-hack package. Every package is built
+    /// with a single feature set, so this is the number of packages.
+    pub after: usize,
+
+    /// The number of packages that were built with more than one feature set before.
+    pub unified: usize,
+
+    /// The number of packages that are only built because the workspace-hack package enables
+    /// features that pull them in.
+    pub added: usize,
+}
+
+impl InstantiationCounts {
+    /// Returns the number of combinations that no longer need to be built, or 0 if the
+    /// workspace-hack package causes more packages to be built than it saves.
+    pub fn avoided(&self) -> usize {
+        self.before.saturating_sub(self.after)
+    }
+}
+
+impl Add for InstantiationCounts {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for InstantiationCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.before += other.before;
+        self.after += other.after;
+        self.unified += other.unified;
+        self.added += other.added;
+    }
+}
+
+impl fmt::Display for InstantiationCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} instantiations ({} unified, {} added, {} avoided)",
+            self.before,
+            self.after,
+            self.unified,
+            self.added,
+            self.avoided()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HakariBuilder;
+    use guppy::graph::PackageGraph;
+    use guppy::{Platform, TargetFeatures};
+
+    static METADATA1: &str = include_str!("../../fixtures/small/metadata1.json");
+    static METADATA_LIBRA: &str = include_str!("../../fixtures/large/metadata_libra.json");
+
+    #[test]
+    fn no_unification() {
+        let graph = PackageGraph::from_json(METADATA1).expect("valid metadata");
+        let hakari = HakariBuilder::new(&graph, None)
+            .expect("valid builder")
+            .compute();
+        let estimate = hakari.estimate_effect();
+        assert_eq!(estimate.platforms().len(), 1);
+        assert_eq!(estimate.platforms()[0].triple(), None);
+
+        // A single workspace member builds every package with the same features.
+        let total = estimate.total();
+        assert!(total.before > 0, "some packages are built");
+        assert_eq!(total.before, total.after);
+        assert_eq!(total.unified, 0);
+        assert_eq!(total.added, 0);
+        assert_eq!(total.avoided(), 0);
+    }
+
+    #[test]
+    fn libra_estimate() {
+        let graph = PackageGraph::from_json(METADATA_LIBRA).expect("valid metadata");
+        let platform = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+        let hakari = HakariBuilder::new(&graph, None)
+            .expect("valid builder")
+            .with_platforms(vec![platform])
+            .compute();
+        let estimate = hakari.estimate_effect();
+        assert_eq!(estimate.platforms().len(), 1);
+        let platform_effect = &estimate.platforms()[0];
+        assert_eq!(
+            platform_effect.triple(),
+            Some(hakari.builder().platforms()[0].triple())
+        );
+
+        let target = platform_effect.target();
+        assert!(target.unified > 0, "libra has packages to unify");
+        assert!(target.avoided() > 0, "unification avoids rebuilds");
+        assert_eq!(
+            target.before + target.added - target.after,
+            hakari.computed_map()[&OutputKey {
+                platform_idx: Some(0),
+                build_kind: BuildKind::Target,
+            }]
+                .values()
+                .map(|value| value.len() - 1)
+                .sum::<usize>(),
+            "every extra feature set is avoided"
+        );
+        assert_eq!(estimate.total(), target + platform_effect.host());
+
+        let display = estimate.to_string();
+        assert!(display.contains("target: "), "display: {}", display);
+        assert!(display.contains("total: "), "display: {}", display);
+    }
+}
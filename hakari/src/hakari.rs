@@ -0,0 +1,633 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use guppy::graph::cargo::{
+    BuildMatrix, CargoOptions, CargoResolverVersion, CargoSet, StandardFeatures,
+};
+use guppy::graph::feature::{FeatureGraph, FeatureId, FeatureSet};
+use guppy::graph::{DependencyDirection, PackageGraph, PackageMetadata};
+use guppy::{Error, PackageId, Platform, PlatformSpec};
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter;
+
+/// Configures and constructs `Hakari` instances.
+///
+/// A `HakariBuilder` describes the matrix of builds to simulate: by default, every workspace member
+/// is built with no features, default features and all features, with and without
+/// dev-dependencies, on each of the platforms provided.
+#[derive(Clone, Debug)]
+pub struct HakariBuilder<'g, 'a> {
+    graph: &'g PackageGraph,
+    hakari_package: Option<PackageMetadata<'g>>,
+    matrix: BuildMatrix<'a>,
+    resolver_version: CargoResolverVersion,
+    omitted_members: BTreeSet<&'g PackageId>,
+    omitted_third_party: BTreeSet<&'g PackageId>,
+}
+
+impl<'g, 'a> HakariBuilder<'g, 'a> {
+    /// Creates a new `HakariBuilder` for this graph, with the given workspace-hack package.
+    ///
+    /// The workspace-hack package is excluded from the simulated builds. It can be `None` to
+    /// compute the contents of a workspace-hack package that doesn't exist yet.
+    ///
+    /// Returns an error if the package ID isn't known to the graph.
+    pub fn new(graph: &'g PackageGraph, hakari_id: Option<&PackageId>) -> Result<Self, Error> {
+        let hakari_package = match hakari_id {
+            Some(package_id) => Some(
+                graph
+                    .metadata(package_id)
+                    .ok_or_else(|| Error::UnknownPackageId(package_id.clone()))?,
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            graph,
+            hakari_package,
+            matrix: BuildMatrix::new(),
+            resolver_version: CargoResolverVersion::V1,
+            omitted_members: BTreeSet::new(),
+            omitted_third_party: BTreeSet::new(),
+        })
+    }
+
+    /// Sets the platforms to simulate builds on.
+    ///
+    /// If no platforms are provided, builds are simulated across all possible platforms, and the
+    /// output will not contain any platform-specific sections.
+    pub fn with_platforms(mut self, platforms: impl IntoIterator<Item = Platform<'a>>) -> Self {
+        self.matrix = self.matrix.with_platforms(platforms);
+        self
+    }
+
+    /// Sets the matrix of builds to simulate, including the platforms.
+    pub fn with_build_matrix(mut self, matrix: BuildMatrix<'a>) -> Self {
+        self.matrix = matrix;
+        self
+    }
+
+    /// Sets the Cargo feature resolver version to simulate builds with.
+    pub fn with_resolver_version(mut self, resolver_version: CargoResolverVersion) -> Self {
+        self.resolver_version = resolver_version;
+        self
+    }
+
+    /// Omits these workspace members from the simulated builds.
+    ///
+    /// Omitted members are not built on their own, though they may still be built as dependencies
+    /// of other members. This is useful for members that are never built together with the rest
+    /// of the workspace.
+    ///
+    /// This method is additive. Package IDs that aren't workspace members are ignored.
+    pub fn with_omitted_members(
+        mut self,
+        package_ids: impl IntoIterator<Item = &'g PackageId>,
+    ) -> Self {
+        self.omitted_members.extend(package_ids);
+        self
+    }
+
+    /// Omits these third-party packages from unification.
+    ///
+    /// Omitted packages never show up in the output, and continue to be built with whatever
+    /// features each build requires.
+    ///
+    /// This method is additive.
+    pub fn with_omitted_third_party(
+        mut self,
+        package_ids: impl IntoIterator<Item = &'g PackageId>,
+    ) -> Self {
+        self.omitted_third_party.extend(package_ids);
+        self
+    }
+
+    /// Returns the `PackageGraph` this builder is for.
+    pub fn graph(&self) -> &'g PackageGraph {
+        self.graph
+    }
+
+    /// Returns the workspace-hack package, if one was specified.
+    pub fn hakari_package(&self) -> Option<&PackageMetadata<'g>> {
+        self.hakari_package.as_ref()
+    }
+
+    /// Returns the platforms builds are simulated on.
+    pub fn platforms(&self) -> &[Platform<'a>] {
+        self.matrix.platforms()
+    }
+
+    /// Returns the matrix of builds simulated for each workspace member.
+    pub fn build_matrix(&self) -> &BuildMatrix<'a> {
+        &self.matrix
+    }
+
+    /// Returns the Cargo feature resolver version builds are simulated with.
+    pub fn resolver_version(&self) -> CargoResolverVersion {
+        self.resolver_version
+    }
+
+    /// Returns the workspace members omitted from the simulated builds, in a stable order.
+    pub fn omitted_members<'b>(&'b self) -> impl Iterator<Item = &'g PackageId> + 'b {
+        self.omitted_members.iter().copied()
+    }
+
+    /// Returns the third-party packages omitted from unification, in a stable order.
+    pub fn omitted_third_party<'b>(&'b self) -> impl Iterator<Item = &'g PackageId> + 'b {
+        self.omitted_third_party.iter().copied()
+    }
+
+    /// Computes the contents of the workspace-hack package.
+    pub fn compute(self) -> Hakari<'g, 'a> {
+        Hakari::build(self)
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn cargo_options(&self, platform_idx: Option<usize>, include_dev: bool) -> CargoOptions<'_> {
+        let platform = match platform_idx {
+            Some(idx) => PlatformSpec::Platform(&self.matrix.platforms()[idx]),
+            None => PlatformSpec::Any,
+        };
+        CargoOptions::new()
+            .with_version(self.resolver_version)
+            .with_dev_deps(include_dev)
+            .with_platform(platform)
+            .with_omitted_packages(self.hakari_package.iter().map(|package| package.id()))
+    }
+
+    fn is_hakari_package(&self, package_id: &PackageId) -> bool {
+        match &self.hakari_package {
+            Some(package) => package.id() == package_id,
+            None => false,
+        }
+    }
+}
+
+/// Whether a dependency is built for the target or the host platform.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum BuildKind {
+    /// The target platform. Dependencies for this platform are listed under `[dependencies]`.
+    Target,
+    /// The host platform. Dependencies for this platform, including procedural macros, are listed
+    /// under `[build-dependencies]`.
+    Host,
+}
+
+impl BuildKind {
+    /// A list of all `BuildKind` values.
+    pub const VALUES: &'static [Self] = &[BuildKind::Target, BuildKind::Host];
+}
+
+/// The key for an entry in an `OutputMap` or `ComputedMap`.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct OutputKey {
+    /// The index of the platform in `HakariBuilder::platforms`, or `None` if this entry applies
+    /// to all platforms.
+    pub platform_idx: Option<usize>,
+
+    /// Whether this entry is for the target or the host platform.
+    pub build_kind: BuildKind,
+}
+
+/// A single simulated build of a workspace member.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct MemberBuild<'g> {
+    /// The workspace member that was built.
+    pub member: &'g PackageId,
+
+    /// The features the workspace member was built with.
+    pub features: StandardFeatures,
+
+    /// Whether dev-dependencies were included in the build.
+    pub include_dev: bool,
+}
+
+/// The contents of the workspace-hack package: for each section, the third-party packages that
+/// need to be unified and the union of their features.
+pub type OutputMap<'g> =
+    BTreeMap<OutputKey, BTreeMap<&'g PackageId, (PackageMetadata<'g>, BTreeSet<&'g str>)>>;
+
+/// The intermediate state used to compute an `OutputMap`: for each platform, every third-party
+/// package and the distinct feature sets it was built with, along with the builds that produced
+/// each feature set.
+///
+/// Unlike in the `OutputMap`, every key in this map corresponds to a single platform (or `None`
+/// if no platforms were specified).
+pub type ComputedMap<'g> = BTreeMap<OutputKey, BTreeMap<&'g PackageId, ComputedValue<'g>>>;
+
+/// For a single package in a `ComputedMap`, the feature sets it was built with and the builds that
+/// produced each one.
+pub type ComputedValue<'g> = BTreeMap<BTreeSet<&'g str>, BTreeSet<MemberBuild<'g>>>;
+
+/// The computed contents of a workspace-hack package.
+///
+/// Created through `HakariBuilder::compute`.
+#[derive(Clone, Debug)]
+pub struct Hakari<'g, 'a> {
+    builder: HakariBuilder<'g, 'a>,
+    output_map: OutputMap<'g>,
+    computed_map: ComputedMap<'g>,
+}
+
+impl<'g, 'a> Hakari<'g, 'a> {
+    /// Returns the `HakariBuilder` used to create this instance.
+    pub fn builder(&self) -> &HakariBuilder<'g, 'a> {
+        &self.builder
+    }
+
+    /// Returns the packages and features the workspace-hack package should depend on.
+    ///
+    /// Packages that are unified the same way on every platform are listed under keys with a
+    /// `platform_idx` of `None`.
+    pub fn output_map(&self) -> &OutputMap<'g> {
+        &self.output_map
+    }
+
+    /// Returns the intermediate state used to compute the output map.
+    ///
+    /// This records the feature sets each build produced without the workspace-hack package, and
+    /// can be used to figure out which builds caused a package to be included in the output.
+    /// Packages may also be included because unifying other packages changed their features, in
+    /// which case they are built with a single feature set in this map.
+    pub fn computed_map(&self) -> &ComputedMap<'g> {
+        &self.computed_map
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    pub(crate) fn from_parts(
+        builder: HakariBuilder<'g, 'a>,
+        output_map: OutputMap<'g>,
+        computed_map: ComputedMap<'g>,
+    ) -> Self {
+        Self {
+            builder,
+            output_map,
+            computed_map,
+        }
+    }
+
+    fn build(builder: HakariBuilder<'g, 'a>) -> Self {
+        let graph = builder.graph;
+        let feature_graph = graph.feature_graph();
+        let platform_idxs = builder.matrix.platform_idxs();
+
+        // 1. Simulate every build in the matrix.
+        let mut builds = Vec::new();
+        for matrix_build in builder.matrix.builds() {
+            let opts = builder.cargo_options(matrix_build.platform_idx, matrix_build.include_dev);
+            for (_, member) in graph.workspace().members() {
+                if builder.is_hakari_package(member.id())
+                    || builder.omitted_members.contains(member.id())
+                {
+                    continue;
+                }
+                let package_query = graph
+                    .query_forward(iter::once(member.id()))
+                    .expect("workspace members should be known");
+                let query = matrix_build.feature_query(&feature_graph, &package_query);
+                let cargo_set = CargoSet::new(query, &opts).expect("cargo options should be valid");
+                let build = MemberBuild {
+                    member: member.id(),
+                    features: matrix_build.features,
+                    include_dev: matrix_build.include_dev,
+                };
+                builds.push((matrix_build.platform_idx, build, cargo_set));
+            }
+        }
+
+        // 2. Figure out which packages are built with more than one feature set, then simulate
+        // what happens once the workspace-hack package is added as a dependency to every member.
+        // Adding the workspace-hack package may cause further packages to be built with more than
+        // one feature set, so repeat until a fixpoint is reached.
+        let mut hack_sets: BTreeMap<Option<usize>, (FeatureSet<'g>, FeatureSet<'g>)> =
+            BTreeMap::new();
+        //
+        // The computed map returned to callers is the one from the first iteration, since that
+        // records the feature sets each build produces without the workspace-hack package.
+        let computed_map = Self::compute_map(&builder, &builds, &hack_sets);
+        let mut raw_output = RawOutput::new();
+        let mut next_map = None;
+        let raw_output = loop {
+            let next_output = {
+                let map = next_map.as_ref().unwrap_or(&computed_map);
+                Self::raw_output(map, &raw_output)
+            };
+            if next_map.is_some() && next_output == raw_output {
+                break raw_output;
+            }
+            raw_output = next_output;
+
+            hack_sets = platform_idxs
+                .iter()
+                .map(|&platform_idx| {
+                    let opts = builder.cargo_options(platform_idx, false);
+                    let sets = Self::resolve_hack(&feature_graph, &raw_output, platform_idx, &opts);
+                    (platform_idx, sets)
+                })
+                .collect();
+            next_map = Some(Self::compute_map(&builder, &builds, &hack_sets));
+        };
+
+        let output_map = Self::normalize(graph, &platform_idxs, raw_output);
+
+        Self {
+            builder,
+            output_map,
+            computed_map,
+        }
+    }
+
+    fn compute_map(
+        builder: &HakariBuilder<'g, 'a>,
+        builds: &[(Option<usize>, MemberBuild<'g>, CargoSet<'g>)],
+        hack_sets: &BTreeMap<Option<usize>, (FeatureSet<'g>, FeatureSet<'g>)>,
+    ) -> ComputedMap<'g> {
+        let mut computed_map = ComputedMap::new();
+        for (platform_idx, build, cargo_set) in builds {
+            let hack = hack_sets.get(platform_idx);
+            let target_set = match hack {
+                Some((hack_target, _)) => cargo_set.target_features().union(hack_target),
+                None => cargo_set.target_features().clone(),
+            };
+            let host_set = match hack {
+                Some((_, hack_host)) => cargo_set.host_features().union(hack_host),
+                None => cargo_set.host_features().clone(),
+            };
+
+            for (build_kind, feature_set) in
+                &[(BuildKind::Target, target_set), (BuildKind::Host, host_set)]
+            {
+                let key = OutputKey {
+                    platform_idx: *platform_idx,
+                    build_kind: *build_kind,
+                };
+                let packages = computed_map.entry(key).or_default();
+                for feature_list in feature_set.packages_with_features(DependencyDirection::Forward)
+                {
+                    let package = feature_list.package();
+                    // Workspace packages are always built by Cargo with the features required by
+                    // the current build, so only third-party packages can be unified.
+                    if package.in_workspace() || builder.omitted_third_party.contains(package.id())
+                    {
+                        continue;
+                    }
+                    let features: BTreeSet<_> = feature_list.features().iter().copied().collect();
+                    packages
+                        .entry(package.id())
+                        .or_default()
+                        .entry(features)
+                        .or_default()
+                        .insert(*build);
+                }
+            }
+        }
+        computed_map
+    }
+
+    /// Returns the packages built with more than one feature set in this map, merged into the
+    /// previous output.
+    ///
+    /// Packages are never removed from the output: once the workspace-hack package is added,
+    /// every build sees the same features for them, but they still need to be unified.
+    fn raw_output(computed_map: &ComputedMap<'g>, prev_output: &RawOutput<'g>) -> RawOutput<'g> {
+        let mut raw_output = prev_output.clone();
+        for (key, packages) in computed_map {
+            let unified = raw_output.entry(*key).or_default();
+            for (package_id, value) in packages {
+                if value.len() > 1 {
+                    unified
+                        .entry(*package_id)
+                        .or_default()
+                        .extend(value.keys().flat_map(|features| features.iter().copied()));
+                }
+            }
+        }
+        raw_output
+    }
+
+    /// Simulates a build of the workspace-hack package on its own, returning the target and host
+    /// feature sets.
+    fn resolve_hack(
+        feature_graph: &FeatureGraph<'g>,
+        raw_output: &RawOutput<'g>,
+        platform_idx: Option<usize>,
+        opts: &CargoOptions<'_>,
+    ) -> (FeatureSet<'g>, FeatureSet<'g>) {
+        let resolve = |build_kind| {
+            let key = OutputKey {
+                platform_idx,
+                build_kind,
+            };
+            let feature_ids = raw_output.get(&key).into_iter().flat_map(|packages| {
+                packages.iter().flat_map(|(package_id, features)| {
+                    iter::once(FeatureId::base(package_id)).chain(
+                        features
+                            .iter()
+                            .map(move |feature| FeatureId::new(package_id, feature)),
+                    )
+                })
+            });
+            let query = feature_graph
+                .query_forward(feature_ids)
+                .expect("feature IDs should be valid");
+            CargoSet::new(query, opts).expect("cargo options should be valid")
+        };
+
+        let target = resolve(BuildKind::Target);
+        // Build dependencies of the workspace-hack package, and everything they pull in, are built
+        // on the host platform.
+        let host = resolve(BuildKind::Host);
+        let host_features = target
+            .host_features()
+            .union(host.target_features())
+            .union(host.host_features());
+        (target.target_features().clone(), host_features)
+    }
+
+    /// Moves packages that are unified identically on every platform into the `None` platform.
+    fn normalize(
+        graph: &'g PackageGraph,
+        platform_idxs: &[Option<usize>],
+        raw_output: RawOutput<'g>,
+    ) -> OutputMap<'g> {
+        let mut output_map = OutputMap::new();
+        let mut insert = |key, package_id: &'g PackageId, features| {
+            let package = graph.metadata(package_id).expect("valid package ID");
+            output_map
+                .entry(key)
+                .or_default()
+                .insert(package_id, (package, features));
+        };
+
+        for &build_kind in BuildKind::VALUES {
+            let mut by_package: BTreeMap<_, Vec<_>> = BTreeMap::new();
+            for &platform_idx in platform_idxs {
+                let key = OutputKey {
+                    platform_idx,
+                    build_kind,
+                };
+                for (package_id, features) in raw_output.get(&key).into_iter().flatten() {
+                    by_package
+                        .entry(package_id)
+                        .or_default()
+                        .push((platform_idx, features));
+                }
+            }
+
+            for (package_id, entries) in by_package {
+                let (_, first) = entries[0];
+                let unconditional = entries.len() == platform_idxs.len()
+                    && entries.iter().all(|(_, features)| *features == first);
+                if unconditional {
+                    let key = OutputKey {
+                        platform_idx: None,
+                        build_kind,
+                    };
+                    insert(key, package_id, first.clone());
+                } else {
+                    for (platform_idx, features) in entries {
+                        let key = OutputKey {
+                            platform_idx,
+                            build_kind,
+                        };
+                        insert(key, package_id, features.clone());
+                    }
+                }
+            }
+        }
+
+        output_map
+    }
+}
+
+/// An `OutputMap` without package metadata, used to check for a fixpoint.
+type RawOutput<'g> = BTreeMap<OutputKey, BTreeMap<&'g PackageId, BTreeSet<&'g str>>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HakariOutputOptions;
+    use guppy::TargetFeatures;
+
+    static METADATA_LIBRA: &str = include_str!("../../fixtures/large/metadata_libra.json");
+
+    #[test]
+    fn libra_output() {
+        let graph = PackageGraph::from_json(METADATA_LIBRA).expect("valid metadata");
+        let platforms = vec![
+            Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap(),
+            Platform::new("x86_64-pc-windows-msvc", TargetFeatures::Unknown).unwrap(),
+        ];
+        let hakari = HakariBuilder::new(&graph, None)
+            .expect("valid builder")
+            .with_platforms(platforms)
+            .compute();
+
+        let unconditional = OutputKey {
+            platform_idx: None,
+            build_kind: BuildKind::Target,
+        };
+        let windows = OutputKey {
+            platform_idx: Some(1),
+            build_kind: BuildKind::Target,
+        };
+        let output_names = |key| -> BTreeSet<&str> {
+            hakari.output_map()[&key]
+                .values()
+                .map(|(package, _)| package.name())
+                .collect()
+        };
+        // serde is built with different features on every platform.
+        assert!(output_names(unconditional).contains("serde"));
+        // winapi is only unified on Windows.
+        assert!(!output_names(unconditional).contains("winapi"));
+        assert!(output_names(windows).contains("winapi"));
+        assert!(!hakari.output_map().contains_key(&OutputKey {
+            platform_idx: Some(0),
+            build_kind: BuildKind::Target,
+        }));
+
+        // Every package built with more than one feature set must be in the output, with a
+        // superset of those features.
+        for (key, packages) in hakari.computed_map() {
+            for (package_id, value) in packages {
+                if value.len() < 2 {
+                    continue;
+                }
+                let output_features = [key.platform_idx, None]
+                    .iter()
+                    .find_map(|&platform_idx| {
+                        let key = OutputKey {
+                            platform_idx,
+                            build_kind: key.build_kind,
+                        };
+                        hakari.output_map().get(&key)?.get(package_id)
+                    })
+                    .map(|(_, features)| features)
+                    .unwrap_or_else(|| panic!("{} not in output for {:?}", package_id, key));
+                for features in value.keys() {
+                    assert!(
+                        features.is_subset(output_features),
+                        "for {}, {:?} is not a subset of {:?}",
+                        package_id,
+                        features,
+                        output_features
+                    );
+                }
+            }
+        }
+
+        let toml = hakari
+            .to_toml_string(&HakariOutputOptions::new())
+            .expect("TOML output should succeed");
+        let value: toml::Value = toml::from_str(&toml).expect("output should be valid TOML");
+        let serde = &value["dependencies"]["serde"];
+        assert_eq!(serde["default-features"].as_bool(), Some(false));
+        assert!(serde["features"]
+            .as_array()
+            .expect("features is an array")
+            .contains(&toml::Value::String("derive".into())));
+        assert!(value["target"]["x86_64-pc-windows-msvc"]["dependencies"]
+            .get("winapi")
+            .is_some());
+        // Several versions of syn are unified on the host, so they must be renamed.
+        let build_deps = value["build-dependencies"]
+            .as_table()
+            .expect("build-dependencies is a table");
+        assert_eq!(
+            build_deps["syn-1-0-5"]["package"].as_str(),
+            Some("syn"),
+            "syn 1.0.5 is renamed"
+        );
+    }
+
+    #[test]
+    fn unknown_hakari_package() {
+        let graph =
+            PackageGraph::from_json(include_str!("../../fixtures/small/metadata_targets1.json"))
+                .expect("valid metadata");
+        let package_id = PackageId::new("unknown 0.1.0 (path+file:///fakepath/unknown)");
+        assert!(HakariBuilder::new(&graph, Some(&package_id)).is_err());
+
+        // No packages in this fixture need to be unified.
+        let hakari = HakariBuilder::new(&graph, None)
+            .expect("valid builder")
+            .compute();
+        assert!(hakari
+            .output_map()
+            .values()
+            .all(|packages| packages.is_empty()));
+        assert_eq!(
+            hakari
+                .to_toml_string(&HakariOutputOptions::new())
+                .expect("TOML output should succeed"),
+            ""
+        );
+    }
+}
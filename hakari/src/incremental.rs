@@ -0,0 +1,493 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Incrementally update a workspace-hack computation after package versions change.
+
+use crate::hakari::{BuildKind, ComputedMap, Hakari, HakariBuilder, MemberBuild, OutputMap};
+use guppy::graph::{EnabledStatus, EnabledTernary, PackageMetadata, PackageSource};
+use guppy::{DependencyKind, PackageId, Platform};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+impl<'g, 'a> HakariBuilder<'g, 'a> {
+    /// Computes the contents of the workspace-hack package, reusing a previous result if possible.
+    ///
+    /// If the graph `prev` was computed from differs from this builder's graph only in package
+    /// versions, as is the case after a lockfile bump, and the options are otherwise the same, the
+    /// previous result is carried over to the new versions without simulating any builds.
+    /// Otherwise, the contents are computed from scratch.
+    ///
+    /// Platforms are compared by their triples.
+    pub fn compute_incremental(self, prev: &Hakari<'_, '_>) -> HakariUpdate<'g, 'a> {
+        let (hakari, kind) = match remap(prev, &self) {
+            Ok((output_map, computed_map)) => (
+                Hakari::from_parts(self, output_map, computed_map),
+                UpdateKind::Incremental,
+            ),
+            Err(kind) => (self.compute(), kind),
+        };
+
+        let changed = hakari_path(prev) != hakari_path(&hakari)
+            || output_summary(prev) != output_summary(&hakari);
+        HakariUpdate {
+            hakari,
+            kind,
+            changed,
+        }
+    }
+}
+
+/// The result of `HakariBuilder::compute_incremental`.
+#[derive(Clone, Debug)]
+pub struct HakariUpdate<'g, 'a> {
+    hakari: Hakari<'g, 'a>,
+    kind: UpdateKind,
+    changed: bool,
+}
+
+impl<'g, 'a> HakariUpdate<'g, 'a> {
+    /// Returns the updated contents of the workspace-hack package.
+    pub fn hakari(&self) -> &Hakari<'g, 'a> {
+        &self.hakari
+    }
+
+    /// Consumes `self`, returning the updated contents of the workspace-hack package.
+    pub fn into_hakari(self) -> Hakari<'g, 'a> {
+        self.hakari
+    }
+
+    /// Returns how the update was performed.
+    pub fn kind(&self) -> UpdateKind {
+        self.kind
+    }
+
+    /// Returns true if the contents of the workspace-hack package changed.
+    ///
+    /// If this is false, writing out the new contents with the same `HakariOutputOptions` produces
+    /// the same `Cargo.toml` as before. This is typically the case if none of the packages whose
+    /// versions were bumped are unified by the workspace-hack package.
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
+}
+
+/// How `HakariBuilder::compute_incremental` produced its result.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum UpdateKind {
+    /// Only package versions changed, so the previous result was carried over to the new
+    /// versions.
+    Incremental,
+
+    /// The builder options were different from the ones the previous result was computed with,
+    /// so the result was computed from scratch.
+    OptionsChanged,
+
+    /// The graph changed in ways other than package versions, so the result was computed from
+    /// scratch.
+    GraphChanged,
+}
+
+impl UpdateKind {
+    /// Returns true if the previous result was reused.
+    pub fn is_incremental(self) -> bool {
+        self == UpdateKind::Incremental
+    }
+}
+
+/// Carries the output and computed maps of `prev` over to the graph in `builder`, if only package
+/// versions changed.
+fn remap<'g>(
+    prev: &Hakari<'_, '_>,
+    builder: &HakariBuilder<'g, '_>,
+) -> Result<(OutputMap<'g>, ComputedMap<'g>), UpdateKind> {
+    let prev_builder = prev.builder();
+    let package_map = package_map(prev_builder, builder).ok_or(UpdateKind::GraphChanged)?;
+    let map_id = |package_id: &PackageId| package_map.get(package_id).map(|package| package.id());
+
+    // Check that the options are the same, modulo package versions.
+    let prev_triples = prev_builder
+        .platforms()
+        .iter()
+        .map(|platform| platform.triple());
+    let triples = builder.platforms().iter().map(|platform| platform.triple());
+    let prev_matrix = prev_builder.build_matrix();
+    let matrix = builder.build_matrix();
+    let options_same = prev_triples.eq(triples)
+        && prev_matrix.features() == matrix.features()
+        && prev_matrix.include_dev() == matrix.include_dev()
+        && prev_builder.resolver_version() == builder.resolver_version()
+        && prev_builder
+            .hakari_package()
+            .and_then(|package| map_id(package.id()))
+            == builder.hakari_package().map(|package| package.id())
+        && prev_builder
+            .omitted_members()
+            .map(map_id)
+            .collect::<Option<BTreeSet<_>>>()
+            == Some(builder.omitted_members().collect())
+        && prev_builder
+            .omitted_third_party()
+            .map(map_id)
+            .collect::<Option<BTreeSet<_>>>()
+            == Some(builder.omitted_third_party().collect());
+    if !options_same {
+        return Err(UpdateKind::OptionsChanged);
+    }
+
+    // Check that the dependency and feature structure is the same.
+    for (prev_id, package) in &package_map {
+        let prev_package = prev_builder
+            .graph()
+            .metadata(prev_id)
+            .expect("valid package ID");
+        let prev_sig = package_sig(prev_package, prev_builder.platforms(), map_id);
+        let sig = package_sig(*package, builder.platforms(), Some);
+        if prev_sig.is_none() || prev_sig != sig {
+            return Err(UpdateKind::GraphChanged);
+        }
+    }
+
+    // Carry the maps over.
+    let mut all_features = BTreeMap::new();
+    let mut remap_package = |package_id: &PackageId| {
+        let package = *package_map.get(package_id)?;
+        let features = all_features.entry(package.id()).or_insert_with(|| {
+            package
+                .named_features()
+                .chain(package.optional_deps())
+                .collect::<BTreeSet<_>>()
+        });
+        Some((package, features.clone()))
+    };
+    let remap_features = |features: &BTreeSet<&str>, new_features: &BTreeSet<&'g str>| {
+        features
+            .iter()
+            .map(|feature| new_features.get(*feature).copied())
+            .collect::<Option<BTreeSet<_>>>()
+    };
+
+    let mut output_map = OutputMap::new();
+    for (key, packages) in prev.output_map() {
+        let new_packages = output_map.entry(*key).or_default();
+        for (package_id, (_, features)) in packages {
+            let (package, new_features) =
+                remap_package(package_id).ok_or(UpdateKind::GraphChanged)?;
+            let features =
+                remap_features(features, &new_features).ok_or(UpdateKind::GraphChanged)?;
+            new_packages.insert(package.id(), (package, features));
+        }
+    }
+
+    let mut computed_map = ComputedMap::new();
+    for (key, packages) in prev.computed_map() {
+        let new_packages = computed_map.entry(*key).or_default();
+        for (package_id, value) in packages {
+            let (package, new_features) =
+                remap_package(package_id).ok_or(UpdateKind::GraphChanged)?;
+            let new_value = new_packages.entry(package.id()).or_default();
+            for (features, builds) in value {
+                let features =
+                    remap_features(features, &new_features).ok_or(UpdateKind::GraphChanged)?;
+                let builds = builds
+                    .iter()
+                    .map(|build| {
+                        Some(MemberBuild {
+                            member: map_id(build.member)?,
+                            ..*build
+                        })
+                    })
+                    .collect::<Option<BTreeSet<_>>>()
+                    .ok_or(UpdateKind::GraphChanged)?;
+                new_value.insert(features, builds);
+            }
+        }
+    }
+
+    Ok((output_map, computed_map))
+}
+
+/// Maps every package in the previous graph to the corresponding package in the new one.
+///
+/// Packages are matched by name and source, and packages with the same name and source are
+/// matched in version order. Returns `None` if the packages in the two graphs don't match up.
+fn package_map<'g1, 'g2>(
+    prev_builder: &HakariBuilder<'g1, '_>,
+    builder: &HakariBuilder<'g2, '_>,
+) -> Option<BTreeMap<&'g1 PackageId, PackageMetadata<'g2>>> {
+    fn group<'g>(
+        packages: impl Iterator<Item = PackageMetadata<'g>>,
+    ) -> BTreeMap<(String, String), Vec<PackageMetadata<'g>>> {
+        let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for package in packages {
+            let source = match package.source() {
+                // Git sources include the resolved commit, which may change in a lockfile bump.
+                PackageSource::External(source) => match source.find('#') {
+                    Some(idx) => source[..idx].to_string(),
+                    None => source.to_string(),
+                },
+                other => other.to_string(),
+            };
+            groups
+                .entry((package.name().to_string(), source))
+                .or_default()
+                .push(package);
+        }
+        for packages in groups.values_mut() {
+            packages.sort_by(|a, b| a.version().cmp(b.version()));
+        }
+        groups
+    }
+
+    let prev_groups = group(prev_builder.graph().packages());
+    let mut groups = group(builder.graph().packages());
+    if prev_groups.len() != groups.len() {
+        return None;
+    }
+
+    let mut package_map = BTreeMap::new();
+    for (key, prev_packages) in prev_groups {
+        let packages = groups.remove(&key)?;
+        if prev_packages.len() != packages.len() {
+            return None;
+        }
+        package_map.extend(
+            prev_packages
+                .into_iter()
+                .map(|package| package.id())
+                .zip(packages),
+        );
+    }
+    Some(package_map)
+}
+
+/// The parts of a package that affect simulated builds. Packages that links point to are mapped to
+/// the new graph.
+#[derive(Debug, Eq, PartialEq)]
+struct PackageSig<'g, 'm> {
+    in_workspace: bool,
+    is_proc_macro: bool,
+    has_build_script: bool,
+    has_default_feature: bool,
+    named_features: BTreeMap<&'g str, &'g [String]>,
+    optional_deps: BTreeSet<&'g str>,
+    links: Vec<LinkSig<'g, 'm>>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct LinkSig<'g, 'm> {
+    to: &'m PackageId,
+    dep_name: &'g str,
+    resolved_name: &'g str,
+    reqs: Vec<ReqSig<'g>>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct ReqSig<'g> {
+    is_present: bool,
+    status: StatusSig,
+    default_features: StatusSig,
+    features: Vec<(&'g str, StatusSig)>,
+}
+
+/// Whether something is required and whether it is enabled, on any platform followed by each
+/// platform builds are simulated on.
+type StatusSig = Vec<(EnabledTernary, EnabledTernary)>;
+
+fn package_sig<'g, 'm>(
+    package: PackageMetadata<'g>,
+    platforms: &[Platform<'_>],
+    map_id: impl Fn(&'g PackageId) -> Option<&'m PackageId>,
+) -> Option<PackageSig<'g, 'm>> {
+    let mut links = package
+        .direct_links()
+        .map(|link| {
+            let reqs = [
+                DependencyKind::Normal,
+                DependencyKind::Build,
+                DependencyKind::Development,
+            ]
+            .iter()
+            .map(|kind| {
+                let req = link.req_for_kind(*kind);
+                let mut features: Vec<_> = req
+                    .features()
+                    .map(|feature| (feature, status_sig(req.feature_status(feature), platforms)))
+                    .collect();
+                features.sort();
+                ReqSig {
+                    is_present: req.is_present(),
+                    status: status_sig(req.status(), platforms),
+                    default_features: status_sig(req.default_features(), platforms),
+                    features,
+                }
+            })
+            .collect();
+            Some(LinkSig {
+                to: map_id(link.to().id())?,
+                dep_name: link.dep_name(),
+                resolved_name: link.resolved_name(),
+                reqs,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    links.sort_by(|a, b| (a.to, a.dep_name).cmp(&(b.to, b.dep_name)));
+
+    Some(PackageSig {
+        in_workspace: package.in_workspace(),
+        is_proc_macro: package.is_proc_macro(),
+        has_build_script: package.has_build_script(),
+        has_default_feature: package.has_default_feature(),
+        named_features: package.named_features_with_deps().collect(),
+        optional_deps: package.optional_deps().collect(),
+        links,
+    })
+}
+
+fn status_sig(status: EnabledStatus<'_>, platforms: &[Platform<'_>]) -> StatusSig {
+    let ternary = |enabled| {
+        if enabled {
+            EnabledTernary::Enabled
+        } else {
+            EnabledTernary::Disabled
+        }
+    };
+    let any = (
+        ternary(status.required_on_any()),
+        ternary(status.enabled_on_any()),
+    );
+    let platforms = platforms
+        .iter()
+        .map(|platform| (status.required_on(platform), status.enabled_on(platform)));
+    std::iter::once(any).chain(platforms).collect()
+}
+
+/// An entry in the output map, in a form that can be compared across graphs.
+#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
+struct OutputEntry<'g> {
+    triple: Option<String>,
+    build_kind: BuildKind,
+    name: &'g str,
+    version: String,
+    source: String,
+    features: BTreeSet<&'g str>,
+}
+
+fn output_summary<'g>(hakari: &Hakari<'g, '_>) -> BTreeSet<OutputEntry<'g>> {
+    let platforms = hakari.builder().platforms();
+    hakari
+        .output_map()
+        .iter()
+        .flat_map(|(key, packages)| {
+            packages
+                .values()
+                .map(move |(package, features)| OutputEntry {
+                    triple: key
+                        .platform_idx
+                        .map(|idx| platforms[idx].triple().to_string()),
+                    build_kind: key.build_kind,
+                    name: package.name(),
+                    version: package.version().to_string(),
+                    source: package.source().to_string(),
+                    features: features.clone(),
+                })
+        })
+        .collect()
+}
+
+fn hakari_path<'g>(hakari: &Hakari<'g, '_>) -> Option<&'g Path> {
+    hakari
+        .builder()
+        .hakari_package()
+        .map(|package| package.manifest_path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HakariOutputOptions;
+    use guppy::graph::cargo::CargoResolverVersion;
+    use guppy::graph::PackageGraph;
+    use guppy::TargetFeatures;
+
+    static METADATA_LIBRA: &str = include_str!("../../fixtures/large/metadata_libra.json");
+    static METADATA1: &str = include_str!("../../fixtures/small/metadata1.json");
+    static METADATA2: &str = include_str!("../../fixtures/small/metadata2.json");
+
+    fn bump(json: &str, name: &str, from: &str, to: &str) -> String {
+        json.replace(
+            &format!("\"name\":\"{}\",\"version\":\"{}\"", name, from),
+            &format!("\"name\":\"{}\",\"version\":\"{}\"", name, to),
+        )
+        .replace(
+            &format!("\"{} {} (", name, from),
+            &format!("\"{} {} (", name, to),
+        )
+    }
+
+    fn builder(graph: &PackageGraph) -> HakariBuilder<'_, 'static> {
+        let platform = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+        HakariBuilder::new(graph, None)
+            .expect("valid builder")
+            .with_platforms(vec![platform])
+    }
+
+    #[test]
+    fn libra_version_bumps() {
+        let graph = PackageGraph::from_json(METADATA_LIBRA).expect("valid metadata");
+        let prev = builder(&graph).compute();
+        let options = HakariOutputOptions::new();
+
+        // itoa isn't unified, so bumping it doesn't change the output.
+        let itoa_graph = PackageGraph::from_json(bump(METADATA_LIBRA, "itoa", "0.4.4", "0.4.5"))
+            .expect("valid metadata");
+        let update = builder(&itoa_graph).compute_incremental(&prev);
+        assert_eq!(update.kind(), UpdateKind::Incremental);
+        assert!(!update.is_changed(), "itoa bump doesn't change output");
+        assert_eq!(
+            update.hakari().to_toml_string(&options).unwrap(),
+            prev.to_toml_string(&options).unwrap(),
+        );
+
+        // serde is unified, so bumping it does change the output.
+        let serde_graph =
+            PackageGraph::from_json(bump(METADATA_LIBRA, "serde", "1.0.101", "1.0.102"))
+                .expect("valid metadata");
+        let update = builder(&serde_graph).compute_incremental(&prev);
+        assert_eq!(update.kind(), UpdateKind::Incremental);
+        assert!(update.is_changed(), "serde bump changes output");
+        let toml = update.hakari().to_toml_string(&options).unwrap();
+        assert!(
+            toml.contains("version = \"1.0.102\""),
+            "new serde version used"
+        );
+
+        // The incremental result matches a full computation.
+        let full = builder(&serde_graph).compute();
+        assert_eq!(full.output_map(), update.hakari().output_map());
+        assert_eq!(full.computed_map(), update.hakari().computed_map());
+    }
+
+    #[test]
+    fn full_updates() {
+        let graph1 = PackageGraph::from_json(METADATA1).expect("valid metadata");
+        let graph2 = PackageGraph::from_json(METADATA2).expect("valid metadata");
+        let prev = builder(&graph1).compute();
+
+        let update = builder(&graph1).compute_incremental(&prev);
+        assert_eq!(update.kind(), UpdateKind::Incremental);
+        assert!(!update.is_changed());
+
+        let update = builder(&graph1)
+            .with_resolver_version(CargoResolverVersion::V2)
+            .compute_incremental(&prev);
+        assert_eq!(update.kind(), UpdateKind::OptionsChanged);
+
+        let update = builder(&graph2).compute_incremental(&prev);
+        assert_eq!(update.kind(), UpdateKind::GraphChanged);
+        assert_eq!(
+            update.hakari().output_map(),
+            builder(&graph2).compute().output_map()
+        );
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Manage workspace-hack packages that unify features across a workspace.
+//!
+//! In a large workspace, each member may be built with a different set of features for its
+//! third-party dependencies. Cargo unifies features within a single build, so a shared
+//! dependency can end up being rebuilt many times as developers build different subsets of the
+//! workspace. A common workaround is a *workspace-hack* package: a workspace member that every
+//! other member depends on, and that itself depends on every third-party package with the union
+//! of the features used across the workspace. With the workspace-hack package in place, every
+//! build sees the same features and dependencies are only built once.
+//!
+//! `hakari` computes the contents of a workspace-hack package using `guppy`'s simulation of
+//! Cargo's feature resolution, and writes them out as the package's `Cargo.toml`.
+//!
+//! # Examples
+//!
+//! ```
+//! use guppy::graph::PackageGraph;
+//! use hakari::{HakariBuilder, HakariOutputOptions};
+//!
+//! // Use a pre-existing fixture for this example.
+//! let graph = PackageGraph::from_json(include_str!("../../fixtures/small/metadata_targets1.json"))
+//!     .unwrap();
+//!
+//! // There's no workspace-hack package in this fixture, so pass in None.
+//! let hakari = HakariBuilder::new(&graph, None).unwrap().compute();
+//!
+//! // Write out the dependency sections of the workspace-hack package's Cargo.toml.
+//! let toml = hakari.to_toml_string(&HakariOutputOptions::new()).unwrap();
+//! println!("{}", toml);
+//! ```
+
+#![warn(missing_docs)]
+
+mod cargo_toml;
+mod config;
+mod estimate;
+mod hakari;
+mod incremental;
+mod toml_out;
+mod verify;
+
+pub use crate::hakari::*;
+pub use cargo_toml::*;
+pub use config::*;
+pub use estimate::*;
+pub use incremental::*;
+pub use toml_out::*;
+pub use verify::*;
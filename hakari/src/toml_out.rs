@@ -0,0 +1,484 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Facilities for writing out TOML data from a Hakari map.
+
+use crate::hakari::{BuildKind, Hakari, OutputKey};
+use guppy::graph::{PackageMetadata, PackageSource};
+use guppy::PackageId;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error;
+use std::fmt::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Options for writing out TOML.
+#[derive(Clone, Debug, Default)]
+pub struct HakariOutputOptions {
+    exact_versions: bool,
+    dep_format: DepFormat,
+}
+
+impl HakariOutputOptions {
+    /// Creates a new instance with default settings.
+    ///
+    /// The default settings are:
+    /// * do not output exact versions
+    /// * output dependencies in the full format.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// If set to true, outputs exact versions in package version fields, e.g. `=1.0.105`.
+    ///
+    /// By default, versions are output as caret requirements, e.g. `1.0.105`.
+    pub fn with_exact_versions(mut self, exact_versions: bool) -> Self {
+        self.exact_versions = exact_versions;
+        self
+    }
+
+    /// Sets the format dependency lines are written out in.
+    pub fn with_dep_format(mut self, dep_format: DepFormat) -> Self {
+        self.dep_format = dep_format;
+        self
+    }
+}
+
+/// The format dependency lines are written out in.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum DepFormat {
+    /// Specify the version and source of each dependency in full:
+    ///
+    /// ```toml
+    /// serde = { version = "1.0.105", default-features = false, features = ["derive", "std"] }
+    /// ```
+    Full,
+
+    /// Inherit the version and source of each dependency from the workspace's
+    /// `[workspace.dependencies]` table, using dotted keys:
+    ///
+    /// ```toml
+    /// serde.workspace = true
+    /// serde.features = ["derive", "std"]
+    /// ```
+    ///
+    /// Packages that need to be renamed because several versions are unified are always written
+    /// out in the full format, since they can't be inherited from the workspace.
+    WorkspaceDotted,
+}
+
+impl Default for DepFormat {
+    fn default() -> Self {
+        DepFormat::Full
+    }
+}
+
+/// An error that occurred while writing out TOML.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TomlOutError {
+    /// A package outside the workspace was specified by path, but no workspace-hack package was
+    /// provided to compute a relative path from.
+    PathWithoutHakari(PackageId, PathBuf),
+
+    /// A package came from an external source that isn't supported.
+    UnrecognizedExternal(PackageId, String),
+
+    /// A `[patch]` directive was requested, but no workspace-hack package was provided.
+    PatchWithoutHakari,
+
+    /// An error occurred while writing to the output.
+    FmtWrite(fmt::Error),
+}
+
+impl fmt::Display for TomlOutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use TomlOutError::*;
+
+        match self {
+            PathWithoutHakari(package_id, path) => write!(
+                f,
+                "for package '{}', path '{}' cannot be made relative without a workspace-hack \
+                 package",
+                package_id,
+                path.display()
+            ),
+            UnrecognizedExternal(package_id, source) => write!(
+                f,
+                "for package '{}', unrecognized external source '{}'",
+                package_id, source
+            ),
+            PatchWithoutHakari => write!(
+                f,
+                "a [patch] directive cannot be written out without a workspace-hack package"
+            ),
+            FmtWrite(err) => write!(f, "error while writing TOML: {}", err),
+        }
+    }
+}
+
+impl error::Error for TomlOutError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            TomlOutError::PathWithoutHakari(_, _)
+            | TomlOutError::UnrecognizedExternal(_, _)
+            | TomlOutError::PatchWithoutHakari => None,
+            TomlOutError::FmtWrite(err) => Some(err),
+        }
+    }
+}
+
+impl From<fmt::Error> for TomlOutError {
+    fn from(err: fmt::Error) -> Self {
+        TomlOutError::FmtWrite(err)
+    }
+}
+
+impl<'g, 'a> Hakari<'g, 'a> {
+    /// Returns the dependency sections of the workspace-hack package's `Cargo.toml` as a string.
+    pub fn to_toml_string(&self, options: &HakariOutputOptions) -> Result<String, TomlOutError> {
+        let mut out = String::new();
+        self.write_toml(options, &mut out)?;
+        Ok(out)
+    }
+
+    /// Writes out the dependency sections of the workspace-hack package's `Cargo.toml` to the
+    /// given output.
+    ///
+    /// Sections are written out in a stable order, so that the output can be checked in and
+    /// compared against.
+    pub fn write_toml(
+        &self,
+        options: &HakariOutputOptions,
+        mut out: impl fmt::Write,
+    ) -> Result<(), TomlOutError> {
+        let hakari_dir = self.builder().hakari_package().map(|package| {
+            package
+                .manifest_path()
+                .parent()
+                .expect("manifest has a parent")
+        });
+        let dep_names = self.dep_names();
+
+        let mut first = true;
+        for (key, packages) in self.output_map() {
+            if packages.is_empty() {
+                continue;
+            }
+            if !first {
+                writeln!(out)?;
+            }
+            first = false;
+
+            writeln!(out, "{}", self.section_header(*key))?;
+            for (package_id, (package, features)) in packages {
+                let dep_name = &dep_names[package_id];
+                write_dep_line(options, hakari_dir, dep_name, *package, features, &mut out)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a `[patch]` directive that points this registry's copy of the workspace-hack
+    /// package at the one in the workspace, for inclusion in the workspace's root `Cargo.toml`.
+    ///
+    /// This is useful for workspaces that publish their members: the published members depend on
+    /// a stub workspace-hack package in the registry, while local builds use the real one.
+    pub fn to_patch_string(&self, registry: &str) -> Result<String, TomlOutError> {
+        let hakari_package = self
+            .builder()
+            .hakari_package()
+            .ok_or(TomlOutError::PatchWithoutHakari)?;
+        let package_dir = hakari_package
+            .manifest_path()
+            .parent()
+            .expect("manifest has a parent");
+        let path = relative_path(self.builder().graph().workspace().root(), package_dir);
+
+        let mut out = String::new();
+        writeln!(out, "[patch.{}]", toml_key(registry))?;
+        writeln!(
+            out,
+            "{} = {{ path = {} }}",
+            toml_key(hakari_package.name()),
+            toml_string(&path.to_string_lossy()),
+        )?;
+        Ok(out)
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    /// Returns the names to use for each package in the output.
+    pub(crate) fn dep_names(&self) -> HashMap<&'g PackageId, String> {
+        dep_names(
+            self.output_map()
+                .values()
+                .flat_map(|packages| packages.values().map(|(package, _)| *package)),
+        )
+    }
+
+    fn section_header(&self, key: OutputKey) -> String {
+        let section = match key.build_kind {
+            BuildKind::Target => "dependencies",
+            BuildKind::Host => "build-dependencies",
+        };
+        match key.platform_idx {
+            Some(idx) => {
+                let triple = self.builder().platforms()[idx].triple();
+                format!("[target.{}.{}]", toml_key(triple), section)
+            }
+            None => format!("[{}]", section),
+        }
+    }
+}
+
+/// Returns the names to use for each package in the output.
+///
+/// If several packages with the same name need to be unified, Cargo requires them to be renamed
+/// so that their keys are distinct. Such packages get the version (and if necessary, a hash of
+/// the source) appended to their names.
+fn dep_names<'g>(
+    packages: impl IntoIterator<Item = PackageMetadata<'g>>,
+) -> HashMap<&'g PackageId, String> {
+    let mut by_name: BTreeMap<&'g str, BTreeSet<&'g PackageId>> = BTreeMap::new();
+    let mut metadatas = HashMap::new();
+    for package in packages {
+        by_name
+            .entry(package.name())
+            .or_default()
+            .insert(package.id());
+        metadatas.insert(package.id(), package);
+    }
+
+    let mut dep_names = HashMap::new();
+    for (name, package_ids) in by_name {
+        if package_ids.len() == 1 {
+            let package_id = package_ids.into_iter().next().expect("one element");
+            dep_names.insert(package_id, name.to_string());
+            continue;
+        }
+
+        let mut versioned: BTreeMap<String, Vec<&'g PackageId>> = BTreeMap::new();
+        for package_id in package_ids {
+            let version = metadatas[package_id].version().to_string();
+            let dep_name = format!("{}-{}", name, version.replace(&['.', '+'][..], "-"));
+            versioned.entry(dep_name).or_default().push(package_id);
+        }
+        for (dep_name, package_ids) in versioned {
+            if package_ids.len() == 1 {
+                dep_names.insert(package_ids[0], dep_name);
+            } else {
+                // Same name and version but different sources.
+                for package_id in package_ids {
+                    let source = metadatas[package_id].source().to_string();
+                    let dep_name = format!("{}-{:08x}", dep_name, fnv_hash(&source) as u32);
+                    dep_names.insert(package_id, dep_name);
+                }
+            }
+        }
+    }
+
+    dep_names
+}
+
+fn write_dep_line(
+    options: &HakariOutputOptions,
+    hakari_dir: Option<&Path>,
+    dep_name: &str,
+    package: PackageMetadata<'_>,
+    features: &BTreeSet<&str>,
+    mut out: impl fmt::Write,
+) -> Result<(), TomlOutError> {
+    let features: Vec<_> = features
+        .iter()
+        .map(|feature| toml_string(feature))
+        .collect();
+
+    if options.dep_format == DepFormat::WorkspaceDotted && dep_name == package.name() {
+        let key = toml_key(dep_name);
+        writeln!(out, "{}.workspace = true", key)?;
+        if !features.is_empty() {
+            writeln!(out, "{}.features = [{}]", key, features.join(", "))?;
+        }
+        return Ok(());
+    }
+
+    write!(out, "{} = {{ ", toml_key(dep_name))?;
+    if dep_name != package.name() {
+        write!(out, "package = {}, ", toml_string(package.name()))?;
+    }
+
+    match package.source() {
+        PackageSource::Workspace(_) | PackageSource::Path(_) => {
+            let package_dir = package
+                .manifest_path()
+                .parent()
+                .expect("manifest has a parent");
+            let hakari_dir = hakari_dir.ok_or_else(|| {
+                TomlOutError::PathWithoutHakari(package.id().clone(), package_dir.to_path_buf())
+            })?;
+            let path = relative_path(hakari_dir, package_dir);
+            write!(out, "path = {}, ", toml_string(&path.to_string_lossy()))?;
+        }
+        PackageSource::External(source) => {
+            if let Some(git) = source.strip_prefix("git+") {
+                let (url, rev) = match git.find('#') {
+                    Some(idx) => (&git[..idx], Some(&git[idx + 1..])),
+                    None => (git, None),
+                };
+                let (url, query) = match url.find('?') {
+                    Some(idx) => (&url[..idx], Some(&url[idx + 1..])),
+                    None => (url, None),
+                };
+                write!(out, "git = {}, ", toml_string(url))?;
+                match (rev, query) {
+                    (Some(rev), _) => write!(out, "rev = {}, ", toml_string(rev))?,
+                    (None, Some(query)) => {
+                        // The query is of the form "branch=foo", "tag=foo" or "rev=foo".
+                        if let Some(idx) = query.find('=') {
+                            let (key, value) = (&query[..idx], &query[idx + 1..]);
+                            write!(out, "{} = {}, ", key, toml_string(value))?;
+                        }
+                    }
+                    (None, None) => {}
+                }
+            } else if !package.source().is_crates_io() {
+                return Err(TomlOutError::UnrecognizedExternal(
+                    package.id().clone(),
+                    source.to_string(),
+                ));
+            }
+        }
+    }
+
+    let version = if options.exact_versions {
+        format!("={}", package.version())
+    } else {
+        package.version().to_string()
+    };
+    write!(out, "version = {}, ", toml_string(&version))?;
+
+    // Default features are listed explicitly if they're enabled.
+    write!(out, "default-features = false")?;
+    if !features.is_empty() {
+        write!(out, ", features = [{}]", features.join(", "))?;
+    }
+    writeln!(out, " }}")?;
+
+    Ok(())
+}
+
+/// Returns the path to `to`, relative to `from`. Both paths are expected to be absolute.
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut path = PathBuf::new();
+    for _ in common..from.len() {
+        path.push(Component::ParentDir);
+    }
+    for component in &to[common..] {
+        path.push(component);
+    }
+    if path.as_os_str().is_empty() {
+        path.push(Component::CurDir);
+    }
+    path
+}
+
+/// Returns `key` as a TOML key, quoting it if necessary.
+fn toml_key(key: &str) -> String {
+    let is_bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if is_bare {
+        key.to_string()
+    } else {
+        toml_string(key)
+    }
+}
+
+/// Returns `s` as a TOML basic string.
+fn toml_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => write!(out, "\\u{:04X}", c as u32).expect("writing to a string"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A 64-bit FNV-1a hash, used because its output is stable across Rust versions.
+fn fnv_hash(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use guppy::graph::PackageGraph;
+
+    #[test]
+    fn dep_names_renames_duplicates() {
+        let graph =
+            PackageGraph::from_json(include_str!("../../fixtures/small/metadata_dups.json"))
+                .expect("valid metadata");
+        let dep_names = dep_names(graph.packages());
+        let mut names: Vec<_> = dep_names.values().map(|name| name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            vec![
+                "bytes-0-3-0",
+                "bytes-0-5-4",
+                "lazy_static-0-2-11",
+                "lazy_static-1-4-0",
+                "testcrate-dups",
+            ]
+        );
+    }
+
+    #[test]
+    fn relative_paths() {
+        assert_eq!(
+            relative_path(Path::new("/ws/hack"), Path::new("/ws/hack")),
+            Path::new(".")
+        );
+        assert_eq!(
+            relative_path(Path::new("/ws/hack"), Path::new("/ws/deps/foo")),
+            Path::new("../deps/foo")
+        );
+        assert_eq!(
+            relative_path(Path::new("/ws/hack"), Path::new("/other")),
+            Path::new("../../other")
+        );
+    }
+
+    #[test]
+    fn toml_quoting() {
+        assert_eq!(toml_key("serde_derive-1-0"), "serde_derive-1-0");
+        assert_eq!(
+            toml_key("thumbv8m.main-none-eabi"),
+            "\"thumbv8m.main-none-eabi\""
+        );
+        assert_eq!(toml_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\n\"");
+    }
+}
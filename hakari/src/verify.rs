@@ -0,0 +1,663 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Verify that a checked-in workspace-hack package is up to date.
+//!
+//! Verification recomputes the workspace-hack package's contents, compares them against the
+//! section managed by `hakari` in its `Cargo.toml`, and reports every difference along with the
+//! builds that caused it. This lets CI say exactly why the package needs to be regenerated.
+
+use crate::cargo_toml::HakariCargoToml;
+use crate::hakari::{BuildKind, Hakari, MemberBuild};
+use crate::toml_out::{HakariOutputOptions, TomlOutError};
+use guppy::graph::cargo::StandardFeatures;
+use guppy::PackageId;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error;
+use std::fmt;
+
+impl<'g, 'a> Hakari<'g, 'a> {
+    /// Compares the computed contents of the workspace-hack package against the section managed
+    /// by `hakari` in its checked-in `Cargo.toml`.
+    ///
+    /// Returns an error if the expected contents couldn't be written out, or if the checked-in
+    /// section isn't valid TOML.
+    pub fn verify(
+        &self,
+        cargo_toml: &HakariCargoToml,
+        options: &HakariOutputOptions,
+    ) -> Result<VerifyReport<'g>, VerifyError> {
+        let expected_toml = self.to_toml_string(options)?;
+        let expected = parse_section(&expected_toml).expect("hakari output should be valid TOML");
+        let actual = parse_section(cargo_toml.section()).map_err(VerifyError::Parse)?;
+
+        let package_ids = self.package_ids_by_dep_name();
+
+        let mut drifts = vec![];
+        let section_keys: BTreeSet<_> = expected.keys().chain(actual.keys()).cloned().collect();
+        for section in section_keys {
+            let empty = BTreeMap::new();
+            let expected_deps = expected.get(&section).unwrap_or(&empty);
+            let actual_deps = actual.get(&section).unwrap_or(&empty);
+
+            let dep_names: BTreeSet<_> = expected_deps.keys().chain(actual_deps.keys()).collect();
+            for dep_name in dep_names {
+                let package_id = package_ids
+                    .get(&(section.clone(), dep_name.clone()))
+                    .copied();
+                let kind = match (expected_deps.get(dep_name), actual_deps.get(dep_name)) {
+                    (Some(_), None) => DriftKind::Missing,
+                    (None, Some(_)) => DriftKind::Extra,
+                    (Some(expected), Some(actual)) => {
+                        let expected_features = dep_features(expected);
+                        let actual_features = dep_features(actual);
+                        if expected_features != actual_features {
+                            DriftKind::FeaturesChanged {
+                                added: expected_features
+                                    .difference(&actual_features)
+                                    .cloned()
+                                    .collect(),
+                                removed: actual_features
+                                    .difference(&expected_features)
+                                    .cloned()
+                                    .collect(),
+                            }
+                        } else if without_features(expected) != without_features(actual) {
+                            DriftKind::SpecChanged
+                        } else {
+                            continue;
+                        }
+                    }
+                    (None, None) => unreachable!("dep name came from one of the two maps"),
+                };
+
+                let causes = match package_id {
+                    Some(package_id) => self.drift_causes(&section, package_id, &kind),
+                    None => vec![],
+                };
+                drifts.push(Drift {
+                    section: section.clone(),
+                    dep_name: dep_name.clone(),
+                    package_id,
+                    kind,
+                    causes,
+                });
+            }
+        }
+
+        let formatting_changed = drifts.is_empty() && cargo_toml.is_changed(&expected_toml);
+
+        Ok(VerifyReport {
+            drifts,
+            formatting_changed,
+            expected_toml,
+        })
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn package_ids_by_dep_name(&self) -> BTreeMap<(SectionKey, String), &'g PackageId> {
+        let dep_names = self.dep_names();
+        let mut package_ids = BTreeMap::new();
+        for (key, packages) in self.output_map() {
+            let section = SectionKey {
+                triple: key
+                    .platform_idx
+                    .map(|idx| self.builder().platforms()[idx].triple().to_string()),
+                build_kind: key.build_kind,
+            };
+            for package_id in packages.keys() {
+                let dep_name = dep_names[package_id].clone();
+                package_ids.insert((section.clone(), dep_name), *package_id);
+            }
+        }
+        package_ids
+    }
+
+    /// Finds the builds that caused this drift, from the computed map.
+    fn drift_causes(
+        &self,
+        section: &SectionKey,
+        package_id: &PackageId,
+        kind: &DriftKind,
+    ) -> Vec<DriftCause<'g>> {
+        let graph = self.builder().graph();
+        let platforms = self.builder().platforms();
+        let mut causes = vec![];
+        for (key, packages) in self.computed_map() {
+            if key.build_kind != section.build_kind {
+                continue;
+            }
+            let triple = key.platform_idx.map(|idx| platforms[idx].triple());
+            if section.triple.is_some() && section.triple.as_deref() != triple {
+                continue;
+            }
+            let value = match packages.get(package_id) {
+                Some(value) => value,
+                None => continue,
+            };
+            // A package built with a single feature set doesn't cause any unification by itself.
+            if value.len() < 2 {
+                continue;
+            }
+
+            for (features, builds) in value {
+                let features: BTreeSet<&'g str> = match kind {
+                    DriftKind::Missing | DriftKind::SpecChanged => features.clone(),
+                    DriftKind::FeaturesChanged { added, .. } => features
+                        .iter()
+                        .copied()
+                        .filter(|feature| added.contains(*feature))
+                        .collect(),
+                    // Extra dependencies are no longer needed, so there's nothing to blame.
+                    DriftKind::Extra => continue,
+                };
+                if features.is_empty() && matches!(kind, DriftKind::FeaturesChanged { .. }) {
+                    continue;
+                }
+                let mut builds_by_member: BTreeMap<_, Vec<_>> = BTreeMap::new();
+                for build in builds {
+                    let member = graph.metadata(build.member).expect("valid package ID");
+                    builds_by_member
+                        .entry(member.name())
+                        .or_default()
+                        .push(*build);
+                }
+                causes.push(DriftCause {
+                    platform: triple.map(str::to_string),
+                    features,
+                    builds_by_member,
+                    builds_per_member: self.builder().build_matrix().builds_per_platform(),
+                });
+            }
+        }
+        causes
+    }
+}
+
+/// The result of verifying a workspace-hack package.
+///
+/// Returned by `Hakari::verify`. The `Display` implementation produces a message suitable for
+/// printing in CI.
+#[derive(Clone, Debug)]
+pub struct VerifyReport<'g> {
+    drifts: Vec<Drift<'g>>,
+    formatting_changed: bool,
+    expected_toml: String,
+}
+
+impl<'g> VerifyReport<'g> {
+    /// Returns true if the checked-in section matches the computed contents exactly.
+    pub fn is_up_to_date(&self) -> bool {
+        self.drifts.is_empty() && !self.formatting_changed
+    }
+
+    /// Returns the differences between the computed contents and the checked-in section, in a
+    /// stable order.
+    pub fn drifts(&self) -> &[Drift<'g>] {
+        &self.drifts
+    }
+
+    /// Returns true if the checked-in section has the same dependencies and features as the
+    /// computed contents, but is formatted differently.
+    pub fn formatting_changed(&self) -> bool {
+        self.formatting_changed
+    }
+
+    /// Returns the computed contents of the section managed by `hakari`, which the checked-in
+    /// section should be replaced with.
+    pub fn expected_toml(&self) -> &str {
+        &self.expected_toml
+    }
+}
+
+impl<'g> fmt::Display for VerifyReport<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_up_to_date() {
+            return writeln!(f, "workspace-hack package is up to date");
+        }
+
+        writeln!(
+            f,
+            "workspace-hack package is out of date, regenerate it because:"
+        )?;
+        if self.formatting_changed {
+            writeln!(
+                f,
+                "  * the section managed by hakari is formatted differently"
+            )?;
+        }
+        for drift in &self.drifts {
+            write!(f, "{}", drift)?;
+        }
+        Ok(())
+    }
+}
+
+/// A single difference between the computed contents of a workspace-hack package and its
+/// checked-in `Cargo.toml`.
+#[derive(Clone, Debug)]
+pub struct Drift<'g> {
+    section: SectionKey,
+    dep_name: String,
+    package_id: Option<&'g PackageId>,
+    kind: DriftKind,
+    causes: Vec<DriftCause<'g>>,
+}
+
+impl<'g> Drift<'g> {
+    /// Returns the `Cargo.toml` section this difference is in.
+    pub fn section(&self) -> &SectionKey {
+        &self.section
+    }
+
+    /// Returns the name of the dependency as written in `Cargo.toml`.
+    pub fn dep_name(&self) -> &str {
+        &self.dep_name
+    }
+
+    /// Returns the package ID of the dependency, or `None` if the dependency is no longer part of
+    /// the computed contents.
+    pub fn package_id(&self) -> Option<&'g PackageId> {
+        self.package_id
+    }
+
+    /// Returns the kind of difference.
+    pub fn kind(&self) -> &DriftKind {
+        &self.kind
+    }
+
+    /// Returns the builds that caused this difference.
+    ///
+    /// This is empty for dependencies that are no longer required, and for features that are only
+    /// required because unifying other dependencies enabled them.
+    pub fn causes(&self) -> &[DriftCause<'g>] {
+        &self.causes
+    }
+}
+
+impl<'g> fmt::Display for Drift<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "  * {} {}: ", self.section, self.dep_name)?;
+        match &self.kind {
+            DriftKind::Missing => writeln!(f, "dependency missing")?,
+            DriftKind::Extra => writeln!(f, "dependency no longer required")?,
+            DriftKind::FeaturesChanged { added, removed } => {
+                let mut parts = vec![];
+                if !added.is_empty() {
+                    parts.push(format!("features added: {}", join(added)));
+                }
+                if !removed.is_empty() {
+                    parts.push(format!("features removed: {}", join(removed)));
+                }
+                writeln!(f, "{}", parts.join("; "))?;
+            }
+            DriftKind::SpecChanged => writeln!(f, "version or source changed")?,
+        }
+
+        match &self.kind {
+            DriftKind::Missing | DriftKind::FeaturesChanged { .. } if self.causes.is_empty() => {
+                writeln!(f, "    - required to unify other dependencies")?;
+            }
+            _ => {}
+        }
+        for cause in &self.causes {
+            write!(f, "{}", cause)?;
+        }
+        Ok(())
+    }
+}
+
+/// The kind of difference found while verifying a workspace-hack package.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DriftKind {
+    /// The dependency is required but missing from the checked-in section.
+    Missing,
+
+    /// The dependency is in the checked-in section but is no longer required.
+    Extra,
+
+    /// The features of the dependency changed.
+    FeaturesChanged {
+        /// Features that are required but missing from the checked-in section.
+        added: BTreeSet<String>,
+        /// Features that are in the checked-in section but are no longer required.
+        removed: BTreeSet<String>,
+    },
+
+    /// The features of the dependency are the same, but other parts of its specification, such as
+    /// the version or the source, changed.
+    SpecChanged,
+}
+
+/// A set of builds that caused a difference.
+#[derive(Clone, Debug)]
+pub struct DriftCause<'g> {
+    platform: Option<String>,
+    features: BTreeSet<&'g str>,
+    // Workspace member names are unique, so group builds by them for display.
+    builds_by_member: BTreeMap<&'g str, Vec<MemberBuild<'g>>>,
+    // The number of builds simulated for each member on this platform.
+    builds_per_member: usize,
+}
+
+impl<'g> DriftCause<'g> {
+    /// The maximum number of workspace members listed in the `Display` output.
+    pub const DISPLAY_MEMBER_LIMIT: usize = 5;
+
+    /// Returns the triple of the platform these builds were simulated on, or `None` if builds
+    /// were simulated across all platforms.
+    pub fn platform(&self) -> Option<&str> {
+        self.platform.as_deref()
+    }
+
+    /// Returns the features these builds enabled that caused the difference.
+    ///
+    /// For missing dependencies, this is every feature enabled by these builds.
+    pub fn features(&self) -> &BTreeSet<&'g str> {
+        &self.features
+    }
+
+    /// Returns the builds, in a stable order.
+    pub fn builds<'a>(&'a self) -> impl Iterator<Item = &'a MemberBuild<'g>> + 'a {
+        self.builds_by_member.values().flatten()
+    }
+
+    /// Returns the builds, grouped by the name of the workspace member.
+    pub fn builds_by_member(&self) -> &BTreeMap<&'g str, Vec<MemberBuild<'g>>> {
+        &self.builds_by_member
+    }
+}
+
+impl<'g> fmt::Display for DriftCause<'g> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "    - ")?;
+        if self.features.is_empty() {
+            write!(f, "no features")?;
+        } else {
+            write!(f, "features {}", join(&self.features))?;
+        }
+        if let Some(platform) = &self.platform {
+            write!(f, " on {}", platform)?;
+        }
+        writeln!(f, " enabled by {} builds of:", self.builds().count())?;
+
+        for (name, builds) in self
+            .builds_by_member
+            .iter()
+            .take(Self::DISPLAY_MEMBER_LIMIT)
+        {
+            if builds.len() == self.builds_per_member {
+                writeln!(f, "      {}: all builds", name)?;
+                continue;
+            }
+            let builds: Vec<_> = builds
+                .iter()
+                .map(|build| {
+                    let features = match build.features {
+                        StandardFeatures::None => "no features",
+                        StandardFeatures::Default => "default features",
+                        StandardFeatures::All => "all features",
+                    };
+                    if build.include_dev {
+                        format!("{} + dev", features)
+                    } else {
+                        features.to_string()
+                    }
+                })
+                .collect();
+            writeln!(f, "      {}: {}", name, builds.join(", "))?;
+        }
+        if self.builds_by_member.len() > Self::DISPLAY_MEMBER_LIMIT {
+            writeln!(
+                f,
+                "      ... and {} more members",
+                self.builds_by_member.len() - Self::DISPLAY_MEMBER_LIMIT
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A section in the part of a workspace-hack package's `Cargo.toml` managed by `hakari`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SectionKey {
+    /// The target triple for platform-specific sections, or `None` for sections that apply to
+    /// every platform.
+    pub triple: Option<String>,
+
+    /// Whether this is a `dependencies` or a `build-dependencies` section.
+    pub build_kind: BuildKind,
+}
+
+impl fmt::Display for SectionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let section = match self.build_kind {
+            BuildKind::Target => "dependencies",
+            BuildKind::Host => "build-dependencies",
+        };
+        match &self.triple {
+            Some(triple) => write!(f, "[target.{}.{}]", triple, section),
+            None => write!(f, "[{}]", section),
+        }
+    }
+}
+
+/// An error that occurred while verifying a workspace-hack package.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum VerifyError {
+    /// An error occurred while writing out the expected contents.
+    TomlOut(TomlOutError),
+
+    /// The checked-in section couldn't be parsed as TOML.
+    Parse(toml::de::Error),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use VerifyError::*;
+
+        match self {
+            TomlOut(err) => write!(f, "error while computing expected contents: {}", err),
+            Parse(err) => write!(f, "error while parsing checked-in section: {}", err),
+        }
+    }
+}
+
+impl error::Error for VerifyError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            VerifyError::TomlOut(err) => Some(err),
+            VerifyError::Parse(err) => Some(err),
+        }
+    }
+}
+
+impl From<TomlOutError> for VerifyError {
+    fn from(err: TomlOutError) -> Self {
+        VerifyError::TomlOut(err)
+    }
+}
+
+// ---
+// Helper functions
+// ---
+
+type SectionMap = BTreeMap<SectionKey, BTreeMap<String, toml::value::Table>>;
+
+/// Parses the section managed by `hakari` into a map of sections to dependencies.
+fn parse_section(contents: &str) -> Result<SectionMap, toml::de::Error> {
+    let value: toml::value::Table = toml::from_str(contents)?;
+    let mut sections = SectionMap::new();
+
+    let mut add_deps = |triple: Option<&str>, table: &toml::value::Table| {
+        for (name, build_kind) in &[
+            ("dependencies", BuildKind::Target),
+            ("build-dependencies", BuildKind::Host),
+        ] {
+            let deps = match table.get(*name).and_then(|deps| deps.as_table()) {
+                Some(deps) => deps,
+                None => continue,
+            };
+            let key = SectionKey {
+                triple: triple.map(|triple| triple.to_string()),
+                build_kind: *build_kind,
+            };
+            let section = sections.entry(key).or_default();
+            for (dep_name, spec) in deps {
+                section.insert(dep_name.clone(), dep_table(spec));
+            }
+        }
+    };
+
+    add_deps(None, &value);
+    if let Some(targets) = value.get("target").and_then(|targets| targets.as_table()) {
+        for (triple, table) in targets {
+            if let Some(table) = table.as_table() {
+                add_deps(Some(triple), table);
+            }
+        }
+    }
+
+    Ok(sections)
+}
+
+/// Normalizes a dependency specification into a table. `foo = "1.0"` is equivalent to
+/// `foo = { version = "1.0" }`.
+fn dep_table(spec: &toml::Value) -> toml::value::Table {
+    match spec {
+        toml::Value::Table(table) => table.clone(),
+        other => {
+            let mut table = toml::value::Table::new();
+            table.insert("version".to_string(), other.clone());
+            table
+        }
+    }
+}
+
+fn dep_features(table: &toml::value::Table) -> BTreeSet<String> {
+    table
+        .get("features")
+        .and_then(|features| features.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|feature| feature.as_str().map(|feature| feature.to_string()))
+        .collect()
+}
+
+fn without_features(table: &toml::value::Table) -> toml::value::Table {
+    let mut table = table.clone();
+    table.remove("features");
+    table
+}
+
+fn join<'a>(items: impl IntoIterator<Item = &'a (impl AsRef<str> + 'a)>) -> String {
+    let items: Vec<_> = items.into_iter().map(|item| item.as_ref()).collect();
+    items.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HakariBuilder;
+    use guppy::graph::PackageGraph;
+
+    static METADATA_LIBRA: &str = include_str!("../../fixtures/large/metadata_libra.json");
+
+    #[test]
+    fn verify_drift() {
+        let graph = PackageGraph::from_json(METADATA_LIBRA).expect("valid metadata");
+        let hakari = HakariBuilder::new(&graph, None)
+            .expect("valid builder")
+            .compute();
+        let options = HakariOutputOptions::new();
+        let expected = hakari.to_toml_string(&options).expect("valid output");
+        let verify = |section: &str| {
+            let template = HakariCargoToml::template("workspace-hack");
+            let cargo_toml =
+                HakariCargoToml::new_in_memory("Cargo.toml", template).expect("valid template");
+            let contents = cargo_toml.write_to_string(section);
+            let cargo_toml =
+                HakariCargoToml::new_in_memory("Cargo.toml", contents).expect("markers present");
+            hakari
+                .verify(&cargo_toml, &options)
+                .expect("verify succeeded")
+        };
+
+        let report = verify(&expected);
+        assert!(report.is_up_to_date(), "report: {}", report);
+        assert_eq!(report.expected_toml(), expected);
+
+        // Formatting-only changes.
+        let report = verify(&expected.replace(" = { ", " = {  "));
+        assert!(!report.is_up_to_date());
+        assert!(report.formatting_changed());
+        assert!(report.drifts().is_empty());
+
+        // Remove a feature from serde in [dependencies].
+        let serde_line = expected
+            .lines()
+            .find(|line| line.starts_with("serde = "))
+            .expect("serde is in the output");
+        let changed = expected.replacen(serde_line, &serde_line.replace("\"derive\", ", ""), 1);
+        let report = verify(&changed);
+        assert_eq!(report.drifts().len(), 1, "report: {}", report);
+        let drift = &report.drifts()[0];
+        assert_eq!(drift.section().to_string(), "[dependencies]");
+        assert_eq!(drift.dep_name(), "serde");
+        let added: BTreeSet<_> = vec!["derive".to_string()].into_iter().collect();
+        assert_eq!(
+            drift.kind(),
+            &DriftKind::FeaturesChanged {
+                added,
+                removed: BTreeSet::new(),
+            }
+        );
+        assert!(!drift.causes().is_empty(), "causes found");
+        for cause in drift.causes() {
+            assert_eq!(cause.features().iter().collect::<Vec<_>>(), vec![&"derive"]);
+            assert!(cause.builds().next().is_some());
+        }
+        assert!(report.to_string().contains("features added: derive"));
+
+        // Remove serde entirely, and add an unknown dependency.
+        let changed = expected.replacen(
+            serde_line,
+            "nonexistent = { version = \"1.0.0\", default-features = false }",
+            1,
+        );
+        let report = verify(&changed);
+        let kinds: Vec<_> = report
+            .drifts()
+            .iter()
+            .map(|drift| (drift.dep_name(), drift.kind().clone()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("nonexistent", DriftKind::Extra),
+                ("serde", DriftKind::Missing)
+            ]
+        );
+        assert!(report.drifts()[0].causes().is_empty());
+        assert!(report.drifts()[0].package_id().is_none());
+        assert!(!report.drifts()[1].causes().is_empty());
+
+        // Invalid TOML.
+        let template = HakariCargoToml::template("workspace-hack");
+        let cargo_toml = HakariCargoToml::new_in_memory("Cargo.toml", template).unwrap();
+        let cargo_toml = HakariCargoToml::new_in_memory(
+            "Cargo.toml",
+            cargo_toml.write_to_string("[dependencies\n"),
+        )
+        .unwrap();
+        assert!(matches!(
+            hakari.verify(&cargo_toml, &options),
+            Err(VerifyError::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Support for `cfg(target_abi = "...")`, which the version of `cfg-expr` used here predates.
+
+use std::collections::BTreeSet;
+
+/// The `target_abi` values of builtin targets, sorted by triple. Targets that aren't listed have an
+/// empty ABI.
+///
+/// These match the values reported by versions of rustc that support `target_abi`.
+static BUILTIN_ABIS: &[(&str, &str)] = &[
+    ("aarch64-unknown-none-softfloat", "softfloat"),
+    ("aarch64-uwp-windows-msvc", "uwp"),
+    ("arm-linux-androideabi", "eabi"),
+    ("arm-unknown-linux-gnueabi", "eabi"),
+    ("arm-unknown-linux-gnueabihf", "eabihf"),
+    ("arm-unknown-linux-musleabi", "eabi"),
+    ("arm-unknown-linux-musleabihf", "eabihf"),
+    ("armebv7r-none-eabi", "eabi"),
+    ("armebv7r-none-eabihf", "eabihf"),
+    ("armv4t-unknown-linux-gnueabi", "eabi"),
+    ("armv5te-unknown-linux-gnueabi", "eabi"),
+    ("armv5te-unknown-linux-musleabi", "eabi"),
+    ("armv6-unknown-freebsd", "eabihf"),
+    ("armv6-unknown-netbsd-eabihf", "eabihf"),
+    ("armv7-linux-androideabi", "eabi"),
+    ("armv7-unknown-cloudabi-eabihf", "eabihf"),
+    ("armv7-unknown-freebsd", "eabihf"),
+    ("armv7-unknown-linux-gnueabi", "eabi"),
+    ("armv7-unknown-linux-gnueabihf", "eabihf"),
+    ("armv7-unknown-linux-musleabi", "eabi"),
+    ("armv7-unknown-linux-musleabihf", "eabihf"),
+    ("armv7-unknown-netbsd-eabihf", "eabihf"),
+    ("armv7-wrs-vxworks-eabihf", "eabihf"),
+    ("armv7r-none-eabi", "eabi"),
+    ("armv7r-none-eabihf", "eabihf"),
+    ("i386-apple-ios", "sim"),
+    ("i686-uwp-windows-gnu", "uwp"),
+    ("i686-uwp-windows-msvc", "uwp"),
+    ("mips64-unknown-linux-gnuabi64", "abi64"),
+    ("mips64-unknown-linux-muslabi64", "abi64"),
+    ("mips64el-unknown-linux-gnuabi64", "abi64"),
+    ("mips64el-unknown-linux-muslabi64", "abi64"),
+    ("mipsisa64r6-unknown-linux-gnuabi64", "abi64"),
+    ("mipsisa64r6el-unknown-linux-gnuabi64", "abi64"),
+    ("powerpc-unknown-linux-gnuspe", "spe"),
+    ("powerpc-wrs-vxworks-spe", "spe"),
+    ("powerpc64-unknown-freebsd", "elfv2"),
+    ("powerpc64-unknown-linux-gnu", "elfv1"),
+    ("powerpc64-unknown-linux-musl", "elfv2"),
+    ("powerpc64-wrs-vxworks", "elfv1"),
+    ("powerpc64le-unknown-linux-gnu", "elfv2"),
+    ("powerpc64le-unknown-linux-musl", "elfv2"),
+    ("thumbv6m-none-eabi", "eabi"),
+    ("thumbv7em-none-eabi", "eabi"),
+    ("thumbv7em-none-eabihf", "eabihf"),
+    ("thumbv7m-none-eabi", "eabi"),
+    ("thumbv7neon-linux-androideabi", "eabi"),
+    ("thumbv7neon-unknown-linux-gnueabihf", "eabihf"),
+    ("thumbv7neon-unknown-linux-musleabihf", "eabihf"),
+    ("thumbv8m.base-none-eabi", "eabi"),
+    ("thumbv8m.main-none-eabi", "eabi"),
+    ("thumbv8m.main-none-eabihf", "eabihf"),
+    ("x86_64-apple-ios", "sim"),
+    ("x86_64-apple-ios-macabi", "macabi"),
+    ("x86_64-fortanix-unknown-sgx", "fortanix"),
+    ("x86_64-unknown-linux-gnux32", "x32"),
+    ("x86_64-uwp-windows-gnu", "uwp"),
+    ("x86_64-uwp-windows-msvc", "uwp"),
+];
+
+/// Returns the `target_abi` value for this builtin triple.
+pub(crate) fn builtin_abi(triple: &str) -> &'static str {
+    match BUILTIN_ABIS.binary_search_by(|(abi_triple, _)| (*abi_triple).cmp(triple)) {
+        Ok(idx) => BUILTIN_ABIS[idx].1,
+        Err(_) => "",
+    }
+}
+
+/// Returns the distinct non-empty `target_abi` values of builtin targets, in sorted order.
+pub(crate) fn builtin_abis() -> impl Iterator<Item = &'static str> {
+    let abis: BTreeSet<_> = BUILTIN_ABIS.iter().map(|(_, abi)| *abi).collect();
+    abis.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_abi() {
+        assert_eq!(builtin_abi("thumbv7em-none-eabihf"), "eabihf");
+        assert_eq!(builtin_abi("mips64-unknown-linux-gnuabi64"), "abi64");
+        assert_eq!(builtin_abi("x86_64-unknown-linux-gnu"), "");
+    }
+}
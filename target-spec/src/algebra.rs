@@ -0,0 +1,465 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Symbolic reasoning over target specifications.
+//!
+//! Two specs are compared by enumerating every combination of values for the predicates they
+//! mention, rather than by evaluating them against known platforms. Keys like `target_os` have
+//! exactly one value on any platform, so for each such key the combinations pick one of the values
+//! mentioned, or some other value. Target features, `target_has_atomic` values, `version()`
+//! predicates and flags are treated as independent of each other.
+//!
+//! This is sound but not complete: if two specs are reported to be disjoint, no platform can match
+//! both of them. But relationships that depend on facts not modeled here, like a target feature
+//! only being available on some architectures, aren't found.
+
+use crate::rewrite::{ABI_KEY, HAS_ATOMIC_KEY, VERSION_KEY, WASM_FAMILY_KEY};
+use crate::{Platform, Target, TargetFeatures};
+use cfg_expr::targets::Arch;
+use cfg_expr::{Predicate, TargetPredicate};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The maximum number of combinations to try before giving up.
+const MAX_ASSIGNMENTS: u64 = 1 << 20;
+
+/// Returns `Some(true)` if some combination of predicate values satisfies `accept`, given the
+/// results of evaluating each target under it, `Some(false)` if none does, or `None` if there are
+/// too many combinations to try.
+pub(crate) fn satisfiable(targets: &[&Target], accept: impl Fn(&[bool]) -> bool) -> Option<bool> {
+    let universe = Universe::new(targets);
+    let mut assignment = universe.first_assignment()?;
+    let mut results = vec![false; targets.len()];
+    loop {
+        for (result, target) in results.iter_mut().zip(targets) {
+            *result = universe.eval(target, &assignment);
+        }
+        if accept(&results) {
+            return Some(true);
+        }
+        if !universe.advance(&mut assignment) {
+            return Some(false);
+        }
+    }
+}
+
+/// A key that has exactly one value on any platform.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum Key {
+    Triple,
+    Arch,
+    Endian,
+    Env,
+    Family,
+    Os,
+    PointerWidth,
+    Vendor,
+    Abi,
+}
+
+impl Key {
+    /// Returns the number of values this key can possibly have, if that's known.
+    fn domain_size(self) -> Option<usize> {
+        match self {
+            // little and big.
+            Key::Endian => Some(2),
+            // unix, windows and none, as far as cfg-expr is concerned.
+            Key::Family => Some(3),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this key on a builtin platform.
+    fn platform_value(self, platform: &Platform<'_>) -> String {
+        let target_info = platform
+            .target_info()
+            .expect("builtin platforms have target info");
+        match self {
+            Key::Triple => platform.triple().to_string(),
+            Key::Arch => format!("{:?}", target_info.arch),
+            Key::Endian => format!("{:?}", target_info.endian),
+            Key::Env => format!("{:?}", target_info.env),
+            Key::Family => format!("{:?}", target_info.family),
+            Key::Os => format!("{:?}", target_info.os),
+            Key::PointerWidth => target_info.pointer_width.to_string(),
+            Key::Vendor => format!("{:?}", target_info.vendor),
+            Key::Abi => platform.abi().to_string(),
+        }
+    }
+}
+
+/// A predicate whose value varies between platforms.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Atom {
+    Value(Key, String),
+    TargetFeature(String),
+    HasAtomic(String),
+    RustcVersion(String),
+    Flag(String),
+}
+
+impl Atom {
+    /// Returns the atom for this predicate, or `None` if the predicate is always false.
+    fn from_predicate(pred: &Predicate<'_>) -> Option<Self> {
+        let atom = match pred {
+            Predicate::Target(target) => {
+                let (key, value) = match target {
+                    TargetPredicate::Arch(arch) => (Key::Arch, format!("{:?}", arch)),
+                    TargetPredicate::Endian(endian) => (Key::Endian, format!("{:?}", endian)),
+                    TargetPredicate::Env(env) => (Key::Env, format!("{:?}", env)),
+                    TargetPredicate::Family(family) => (Key::Family, format!("{:?}", family)),
+                    TargetPredicate::Os(os) => (Key::Os, format!("{:?}", os)),
+                    TargetPredicate::PointerWidth(width) => (Key::PointerWidth, width.to_string()),
+                    TargetPredicate::Vendor(vendor) => (Key::Vendor, format!("{:?}", vendor)),
+                };
+                Atom::Value(key, value)
+            }
+            Predicate::TargetFeature(feature) => Atom::TargetFeature(feature.to_string()),
+            // These are evaluated as flags: see eval_expr.
+            Predicate::Test => Atom::Flag("test".to_string()),
+            Predicate::DebugAssertions => Atom::Flag("debug_assertions".to_string()),
+            Predicate::ProcMacro => Atom::Flag("proc_macro".to_string()),
+            Predicate::Flag(flag) => Atom::Flag(flag.to_string()),
+            Predicate::KeyValue { key, val } if *key == ABI_KEY => {
+                Atom::Value(Key::Abi, val.to_string())
+            }
+            Predicate::KeyValue { key, val } if *key == HAS_ATOMIC_KEY => {
+                Atom::HasAtomic(val.to_string())
+            }
+            // All builtin wasm targets, and only those, have the wasm32 architecture.
+            Predicate::KeyValue { key, .. } if *key == WASM_FAMILY_KEY => {
+                Atom::Value(Key::Arch, format!("{:?}", Arch::wasm32))
+            }
+            Predicate::KeyValue { key, val } if *key == VERSION_KEY => {
+                Atom::RustcVersion(val.to_string())
+            }
+            // Cargo always evaluates feature predicates to false.
+            Predicate::Feature(_) => return None,
+            Predicate::KeyValue { .. } => {
+                unreachable!("these predicates are disallowed at TargetSpec construction time")
+            }
+        };
+        Some(atom)
+    }
+}
+
+/// The atoms mentioned by a set of targets.
+struct Universe {
+    /// For each key, the values mentioned, and whether the key can have some other value. Every
+    /// group has at least one value.
+    groups: Vec<(Key, Vec<String>, bool)>,
+    group_idxs: BTreeMap<Key, usize>,
+    bools: Vec<Atom>,
+    /// For builtin triples, the choices their platform determines.
+    platforms: BTreeMap<&'static str, Vec<Choice>>,
+}
+
+/// A value chosen for an atom.
+enum Choice {
+    /// The index of a group, and the index of its value, or `None` for some other value.
+    Group(usize, Option<usize>),
+    /// The index of a boolean atom, and its value.
+    Bool(usize, bool),
+}
+
+/// One combination of values for the atoms in a `Universe`.
+struct Assignment {
+    groups: Vec<Option<usize>>,
+    bools: Vec<bool>,
+}
+
+impl Universe {
+    fn new(targets: &[&Target]) -> Self {
+        let mut atoms = BTreeSet::new();
+        let mut triples = vec![];
+        for target in targets {
+            match target {
+                Target::Builtin(target_info) => {
+                    atoms.insert(Atom::Value(Key::Triple, target_info.triple.to_string()));
+                    triples.push(target_info.triple);
+                }
+                Target::Custom(triple) => {
+                    atoms.insert(Atom::Value(Key::Triple, triple.to_string()));
+                }
+                Target::Spec(expr) => {
+                    atoms.extend(
+                        expr.predicates()
+                            .filter_map(|pred| Atom::from_predicate(&pred)),
+                    );
+                }
+            }
+        }
+
+        let mut values: BTreeMap<Key, Vec<String>> = BTreeMap::new();
+        let mut bools = vec![];
+        for atom in atoms {
+            match atom {
+                Atom::Value(key, value) => values.entry(key).or_default().push(value),
+                other => bools.push(other),
+            }
+        }
+        let groups: Vec<_> = values
+            .into_iter()
+            .map(|(key, values)| {
+                let has_other = match key.domain_size() {
+                    Some(size) => values.len() < size,
+                    None => true,
+                };
+                (key, values, has_other)
+            })
+            .collect();
+        let group_idxs = groups
+            .iter()
+            .enumerate()
+            .map(|(idx, (key, _, _))| (*key, idx))
+            .collect();
+
+        let mut universe = Self {
+            groups,
+            group_idxs,
+            bools,
+            platforms: BTreeMap::new(),
+        };
+        for triple in triples {
+            let platform = Platform::new(triple, TargetFeatures::Unknown)
+                .expect("TargetInfo triples are builtin");
+            let choices = universe.platform_choices(&platform);
+            universe.platforms.insert(triple, choices);
+        }
+        universe
+    }
+
+    fn platform_choices(&self, platform: &Platform<'_>) -> Vec<Choice> {
+        let group_choices = self
+            .groups
+            .iter()
+            .enumerate()
+            .map(|(group_idx, (key, values, _))| {
+                let value = key.platform_value(platform);
+                Choice::Group(group_idx, values.iter().position(|v| *v == value))
+            });
+        // Target features and flags can be set with -C target-feature and --cfg, so they aren't
+        // determined by the platform.
+        let bool_choices =
+            self.bools
+                .iter()
+                .enumerate()
+                .filter_map(|(bool_idx, atom)| match atom {
+                    Atom::HasAtomic(value) => {
+                        Some(Choice::Bool(bool_idx, platform.has_atomic(value)))
+                    }
+                    _ => None,
+                });
+        group_choices.chain(bool_choices).collect()
+    }
+
+    /// Returns the first assignment, or `None` if there are too many assignments to try.
+    fn first_assignment(&self) -> Option<Assignment> {
+        let mut count: u64 = 1;
+        for (_, values, has_other) in &self.groups {
+            count = count.checked_mul(values.len() as u64 + *has_other as u64)?;
+        }
+        count = count.checked_mul(1u64.checked_shl(self.bools.len() as u32)?)?;
+        if count > MAX_ASSIGNMENTS {
+            return None;
+        }
+        Some(Assignment {
+            groups: self.groups.iter().map(|_| Some(0)).collect(),
+            bools: vec![false; self.bools.len()],
+        })
+    }
+
+    /// Moves to the next assignment, returning false if this was the last one.
+    fn advance(&self, assignment: &mut Assignment) -> bool {
+        for value in &mut assignment.bools {
+            *value = !*value;
+            if *value {
+                return true;
+            }
+        }
+        for (choice, (_, values, has_other)) in assignment.groups.iter_mut().zip(&self.groups) {
+            let next = match *choice {
+                Some(idx) if idx + 1 < values.len() => Some(idx + 1),
+                Some(_) if *has_other => None,
+                Some(_) | None => Some(0),
+            };
+            let wrapped = next == Some(0);
+            *choice = next;
+            if !wrapped {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn eval(&self, target: &Target, assignment: &Assignment) -> bool {
+        match target {
+            Target::Builtin(target_info) => {
+                self.platforms[target_info.triple]
+                    .iter()
+                    .all(|choice| match choice {
+                        Choice::Group(group_idx, value) => assignment.groups[*group_idx] == *value,
+                        Choice::Bool(bool_idx, value) => assignment.bools[*bool_idx] == *value,
+                    })
+            }
+            Target::Custom(triple) => self.eval_value(Key::Triple, triple, assignment),
+            Target::Spec(expr) => expr.eval(|pred| match Atom::from_predicate(pred) {
+                Some(Atom::Value(key, value)) => self.eval_value(key, &value, assignment),
+                Some(atom) => {
+                    let idx = self
+                        .bools
+                        .binary_search(&atom)
+                        .expect("atoms were collected");
+                    assignment.bools[idx]
+                }
+                None => false,
+            }),
+        }
+    }
+
+    fn eval_value(&self, key: Key, value: &str, assignment: &Assignment) -> bool {
+        let group_idx = self.group_idxs[&key];
+        match assignment.groups[group_idx] {
+            Some(idx) => self.groups[group_idx].1[idx] == value,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Platform, TargetFeatures, TargetSpec};
+
+    fn spec(input: &str) -> TargetSpec {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn test_implies() {
+        let linux_gnu = spec("x86_64-unknown-linux-gnu");
+        for (other, expected) in &[
+            ("x86_64-unknown-linux-gnu", true),
+            ("i686-unknown-linux-gnu", false),
+            (
+                "cfg(all(unix, target_arch = \"x86_64\", target_pointer_width = \"64\"))",
+                true,
+            ),
+            ("cfg(not(windows))", true),
+            ("cfg(target_env = \"musl\")", false),
+            // Target features aren't determined by the platform.
+            ("cfg(target_feature = \"sse2\")", false),
+            ("cfg(target_has_atomic = \"64\")", true),
+            ("cfg(target_has_atomic = \"128\")", false),
+        ] {
+            assert_eq!(
+                linux_gnu.implies(&spec(other)),
+                *expected,
+                "x86_64-unknown-linux-gnu implies {}",
+                other
+            );
+        }
+
+        // Keys with a value on every platform.
+        let feature = spec("cfg(target_feature = \"avx\")");
+        assert!(feature.implies(&spec(
+            "cfg(any(target_endian = \"little\", target_endian = \"big\"))"
+        )));
+        assert!(feature.implies(&spec("cfg(any(unix, windows, not(any(unix, windows))))")));
+        assert!(!feature.implies(&spec("cfg(any(unix, windows))")));
+        assert!(!feature.implies(&spec(
+            "cfg(any(target_os = \"linux\", not(target_os = \"macos\")))"
+        )));
+
+        assert!(spec("cfg(all(unix, target_feature = \"avx\"))").implies(&spec("cfg(unix)")));
+        assert!(spec("arm-unknown-linux-gnueabihf").implies(&spec("cfg(target_abi = \"eabihf\")")));
+        // Cargo evaluates feature predicates to false.
+        assert!(spec("cfg(feature = \"foo\")").implies(&spec("cfg(windows)")));
+    }
+
+    #[test]
+    fn test_is_disjoint() {
+        for (a, b, expected) in &[
+            ("cfg(unix)", "cfg(windows)", true),
+            ("cfg(unix)", "cfg(not(unix))", true),
+            ("cfg(unix)", "cfg(target_os = \"linux\")", false),
+            (
+                "cfg(target_os = \"linux\")",
+                "cfg(any(target_os = \"macos\", target_os = \"ios\"))",
+                true,
+            ),
+            ("x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc", true),
+            ("x86_64-unknown-linux-gnu", "cfg(windows)", true),
+            (
+                "x86_64-unknown-linux-gnu",
+                "cfg(target_feature = \"sse\")",
+                false,
+            ),
+            ("thumbv6m-none-eabi", "cfg(target_has_atomic = \"8\")", true),
+            ("cfg(foo)", "cfg(not(foo))", true),
+            ("cfg(foo)", "cfg(bar)", false),
+        ] {
+            let (a, b) = (spec(a), spec(b));
+            assert_eq!(a.is_disjoint(&b), *expected, "{} is disjoint from {}", a, b);
+            assert_eq!(b.is_disjoint(&a), *expected, "{} is disjoint from {}", b, a);
+        }
+
+        #[cfg(feature = "custom")]
+        {
+            let custom = TargetSpec::custom("x86_64-custom-none");
+            assert!(custom.is_disjoint(&spec("x86_64-unknown-linux-gnu")));
+            assert!(!custom.is_disjoint(&spec("cfg(unix)")));
+            assert!(!custom.implies(&spec("cfg(unix)")));
+            assert!(custom.implies(&TargetSpec::custom("x86_64-custom-none")));
+        }
+    }
+
+    #[test]
+    fn test_intersect_union() {
+        let unix = spec("cfg(unix)");
+        let x86_64 = spec("cfg(target_arch = \"x86_64\")");
+        let linux_gnu = spec("x86_64-unknown-linux-gnu");
+        let windows_msvc = spec("x86_64-pc-windows-msvc");
+
+        assert_eq!(
+            unix.intersect(&x86_64).unwrap().as_str(),
+            "cfg(all(unix, target_arch = \"x86_64\"))"
+        );
+        assert_eq!(
+            unix.union(&x86_64).unwrap().as_str(),
+            "cfg(any(unix, target_arch = \"x86_64\"))"
+        );
+        assert_eq!(
+            linux_gnu.intersect(&unix).unwrap().as_str(),
+            "x86_64-unknown-linux-gnu"
+        );
+        assert_eq!(linux_gnu.union(&unix).unwrap().as_str(), "cfg(unix)");
+
+        let empty = windows_msvc.intersect(&unix).unwrap();
+        let platform = Platform::new("x86_64-pc-windows-msvc", TargetFeatures::Unknown).unwrap();
+        assert_eq!(empty.eval(&platform), Some(false));
+        assert!(empty.is_disjoint(&empty));
+
+        assert!(linux_gnu
+            .intersect(&spec("cfg(target_feature = \"sse\")"))
+            .is_none());
+        assert!(linux_gnu.union(&windows_msvc).is_none());
+
+        // Rewritten keys are preserved.
+        assert_eq!(
+            spec("cfg(target_abi = \"eabihf\")")
+                .intersect(&unix)
+                .unwrap()
+                .as_str(),
+            "cfg(all(target_abi = \"eabihf\", unix))"
+        );
+    }
+
+    #[test]
+    fn test_too_many_predicates() {
+        let features: Vec<_> = (0..21)
+            .map(|idx| format!("target_feature = \"f{}\"", idx))
+            .collect();
+        let large = spec(&format!("cfg(all({}))", features.join(", ")));
+        // This can't be proven, even though it's true.
+        assert!(!large.implies(&large));
+    }
+}
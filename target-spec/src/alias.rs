@@ -0,0 +1,116 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Other spellings of builtin target triples.
+//!
+//! Rust occasionally renames targets, such as `wasm32-wasi` to `wasm32-wasip1`. Both spellings
+//! refer to the same builtin target, so that specs and platforms written with either of them are
+//! understood regardless of which one this version of `target-spec` knows.
+
+use cfg_expr::targets::{get_target_by_triple, TargetInfo};
+
+/// Builtin triples, and other spellings of them.
+static TRIPLE_ALIASES: &[(&str, &str)] = &[
+    // Renamed in Rust 1.49.
+    ("x86_64-linux-kernel", "x86_64-unknown-none-linuxkernel"),
+    // Renamed in Rust 1.67.
+    ("aarch64-fuchsia", "aarch64-unknown-fuchsia"),
+    ("x86_64-fuchsia", "x86_64-unknown-fuchsia"),
+    // Renamed in Rust 1.78.
+    ("wasm32-wasi", "wasm32-wasip1"),
+];
+
+/// Looks up a builtin target by its triple, or by another spelling of it.
+pub(crate) fn builtin_target(triple: &str) -> Option<&'static TargetInfo> {
+    get_target_by_triple(canonical_triple(triple)?)
+}
+
+/// Returns the builtin triple this triple refers to, or `None` if it's unknown to `target-spec`.
+///
+/// This is the triple itself if it's builtin. For other spellings of builtin triples, such as
+/// `wasm32-wasip1`, this is the builtin triple, such as `wasm32-wasi`. Platforms created from
+/// either spelling return the builtin triple from `Platform::triple`.
+///
+/// ## Examples
+///
+/// ```
+/// use target_spec::canonical_triple;
+///
+/// assert_eq!(canonical_triple("wasm32-wasip1"), Some("wasm32-wasi"));
+/// assert_eq!(canonical_triple("wasm32-wasi"), Some("wasm32-wasi"));
+/// assert_eq!(canonical_triple("x86_64-bogus-none"), None);
+/// ```
+pub fn canonical_triple(triple: &str) -> Option<&'static str> {
+    if let Some(target_info) = get_target_by_triple(triple) {
+        return Some(target_info.triple);
+    }
+    TRIPLE_ALIASES
+        .iter()
+        .find(|(_, alias)| *alias == triple)
+        .map(|(builtin, _)| *builtin)
+}
+
+/// Returns the other spellings of this triple that `target-spec` knows about.
+///
+/// The triple may be either a builtin triple or another spelling of one. The builtin triple
+/// itself isn't included: use `canonical_triple` to get it.
+///
+/// ## Examples
+///
+/// ```
+/// use target_spec::triple_aliases;
+///
+/// assert_eq!(triple_aliases("wasm32-wasi").collect::<Vec<_>>(), vec!["wasm32-wasip1"]);
+/// assert_eq!(triple_aliases("x86_64-unknown-linux-gnu").count(), 0);
+/// ```
+pub fn triple_aliases(triple: &str) -> impl Iterator<Item = &'static str> {
+    let builtin = canonical_triple(triple);
+    TRIPLE_ALIASES
+        .iter()
+        .filter(move |(aliased, _)| Some(*aliased) == builtin)
+        .map(|(_, alias)| *alias)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Platform, TargetFeatures, TargetSpec};
+
+    #[test]
+    fn test_aliases_builtin() {
+        for (builtin, alias) in TRIPLE_ALIASES {
+            assert!(
+                get_target_by_triple(builtin).is_some(),
+                "{} is builtin",
+                builtin
+            );
+            assert!(
+                get_target_by_triple(alias).is_none(),
+                "{} isn't builtin",
+                alias
+            );
+        }
+    }
+
+    #[test]
+    fn test_alias_eval() {
+        let platform = Platform::new("wasm32-wasip1", TargetFeatures::Unknown).unwrap();
+        assert_eq!(platform.triple(), "wasm32-wasi");
+        assert!(!platform.is_heuristic());
+
+        for spec in &["wasm32-wasi", "wasm32-wasip1", "cfg(target_os = \"wasi\")"] {
+            let spec: TargetSpec = spec.parse().unwrap();
+            assert_eq!(spec.eval(&platform), Some(true), "{}", spec);
+        }
+        let spec: TargetSpec = "wasm32-wasip1".parse().unwrap();
+        assert_eq!(spec.as_str(), "wasm32-wasip1", "the spelling is preserved");
+        let wasi = Platform::new("wasm32-wasi", TargetFeatures::Unknown).unwrap();
+        assert_eq!(spec.eval(&wasi), Some(true));
+        assert!(spec.implies(&"wasm32-wasi".parse().unwrap()));
+
+        assert_eq!(
+            triple_aliases("wasm32-wasip1").collect::<Vec<_>>(),
+            vec!["wasm32-wasip1"]
+        );
+    }
+}
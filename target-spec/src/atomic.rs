@@ -0,0 +1,131 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Support for `cfg(target_has_atomic = "...")`, which the version of `cfg-expr` used here
+//! predates.
+
+/// The maximum atomic width in bits of builtin targets, sorted by triple. Targets that aren't
+/// listed have 64-bit atomics, and 0 means the target has no atomics at all.
+///
+/// These match the values reported by versions of rustc that support `target_has_atomic`.
+static BUILTIN_MAX_ATOMIC_WIDTHS: &[(&str, u8)] = &[
+    ("aarch64-apple-ios", 128),
+    ("aarch64-fuchsia", 128),
+    ("aarch64-linux-android", 128),
+    ("aarch64-pc-windows-msvc", 128),
+    ("aarch64-unknown-cloudabi", 128),
+    ("aarch64-unknown-freebsd", 128),
+    ("aarch64-unknown-hermit", 128),
+    ("aarch64-unknown-linux-gnu", 128),
+    ("aarch64-unknown-linux-musl", 128),
+    ("aarch64-unknown-netbsd", 128),
+    ("aarch64-unknown-none", 128),
+    ("aarch64-unknown-none-softfloat", 128),
+    ("aarch64-unknown-openbsd", 128),
+    ("aarch64-unknown-redox", 128),
+    ("aarch64-uwp-windows-msvc", 128),
+    ("aarch64-wrs-vxworks", 128),
+    ("arm-linux-androideabi", 32),
+    ("armv4t-unknown-linux-gnueabi", 32),
+    ("armv5te-unknown-linux-gnueabi", 32),
+    ("armv5te-unknown-linux-musleabi", 32),
+    ("hexagon-unknown-linux-musl", 32),
+    ("mips-unknown-linux-gnu", 32),
+    ("mips-unknown-linux-musl", 32),
+    ("mips-unknown-linux-uclibc", 32),
+    ("mipsel-unknown-linux-gnu", 32),
+    ("mipsel-unknown-linux-musl", 32),
+    ("mipsel-unknown-linux-uclibc", 32),
+    ("mipsisa32r6-unknown-linux-gnu", 32),
+    ("mipsisa32r6el-unknown-linux-gnu", 32),
+    ("msp430-none-elf", 0),
+    ("powerpc-unknown-linux-gnu", 32),
+    ("powerpc-unknown-linux-gnuspe", 32),
+    ("powerpc-unknown-linux-musl", 32),
+    ("powerpc-unknown-netbsd", 32),
+    ("powerpc-wrs-vxworks", 32),
+    ("powerpc-wrs-vxworks-spe", 32),
+    ("riscv32i-unknown-none-elf", 0),
+    ("riscv32imac-unknown-none-elf", 32),
+    ("riscv32imc-unknown-none-elf", 0),
+    ("s390x-unknown-linux-gnu", 128),
+    ("sparc-unknown-linux-gnu", 32),
+    ("thumbv6m-none-eabi", 0),
+    ("thumbv7em-none-eabi", 32),
+    ("thumbv7em-none-eabihf", 32),
+    ("thumbv7m-none-eabi", 32),
+    ("thumbv8m.base-none-eabi", 32),
+    ("thumbv8m.main-none-eabi", 32),
+    ("thumbv8m.main-none-eabihf", 32),
+    ("x86_64-apple-darwin", 128),
+    ("x86_64-apple-ios", 128),
+    ("x86_64-apple-ios-macabi", 128),
+    ("x86_64-pc-windows-gnu", 128),
+    ("x86_64-pc-windows-msvc", 128),
+    ("x86_64-uwp-windows-gnu", 128),
+    ("x86_64-uwp-windows-msvc", 128),
+];
+
+/// Returns the maximum atomic width in bits for this builtin triple.
+pub(crate) fn builtin_max_atomic_width(triple: &str) -> u8 {
+    match BUILTIN_MAX_ATOMIC_WIDTHS
+        .binary_search_by(|(atomic_triple, _)| (*atomic_triple).cmp(triple))
+    {
+        Ok(idx) => BUILTIN_MAX_ATOMIC_WIDTHS[idx].1,
+        Err(_) => 64,
+    }
+}
+
+/// The range of atomic widths supported by a target.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct AtomicWidths {
+    pub(crate) min: u8,
+    pub(crate) max: u8,
+}
+
+/// The values that `cfg(target_has_atomic = "...")` can match.
+pub(crate) static HAS_ATOMIC_VALUES: &[&str] = &["8", "16", "32", "64", "128", "ptr"];
+
+impl AtomicWidths {
+    /// Returns true if `cfg(target_has_atomic = "value")` matches a target with these widths and
+    /// this pointer width.
+    ///
+    /// As with rustc, `value` is either a width in bits or `"ptr"`. Other values never match.
+    pub(crate) fn has_atomic(self, value: &str, pointer_width: u8) -> bool {
+        let width = match value {
+            "ptr" => pointer_width,
+            "8" | "16" | "32" | "64" | "128" => value.parse().expect("value is a valid u8"),
+            _ => return false,
+        };
+        self.min <= width && width <= self.max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_max_atomic_width() {
+        assert_eq!(builtin_max_atomic_width("x86_64-apple-darwin"), 128);
+        assert_eq!(builtin_max_atomic_width("x86_64-unknown-linux-gnu"), 64);
+        assert_eq!(builtin_max_atomic_width("thumbv7em-none-eabi"), 32);
+        assert_eq!(builtin_max_atomic_width("thumbv6m-none-eabi"), 0);
+    }
+
+    #[test]
+    fn test_has_atomic() {
+        let widths = AtomicWidths { min: 8, max: 32 };
+        assert!(widths.has_atomic("8", 32));
+        assert!(widths.has_atomic("32", 32));
+        assert!(widths.has_atomic("ptr", 32));
+        assert!(!widths.has_atomic("64", 32));
+        assert!(!widths.has_atomic("ptr", 64));
+        assert!(!widths.has_atomic("3", 32));
+        assert!(!widths.has_atomic("cas", 32));
+
+        let none = AtomicWidths { min: 8, max: 0 };
+        assert!(!none.has_atomic("8", 32));
+        assert!(!none.has_atomic("ptr", 32));
+    }
+}
@@ -0,0 +1,318 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::abi::builtin_abi;
+use crate::alias::builtin_target;
+use crate::atomic::{builtin_max_atomic_width, AtomicWidths};
+use crate::custom::{parse_optional, parse_value, CustomTarget, CustomTargetError};
+use crate::{Platform, TargetFeatures};
+use cfg_expr::targets::{self, TargetInfo};
+use std::fmt;
+
+/// The byte order of a platform, as matched by `cfg(target_endian = "...")`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Endian {
+    /// Little-endian, as matched by `target_endian = "little"`.
+    Little,
+    /// Big-endian, as matched by `target_endian = "big"`.
+    Big,
+}
+
+impl Endian {
+    /// Returns the value as it's written in `cfg()` expressions.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Endian::Little => "little",
+            Endian::Big => "big",
+        }
+    }
+
+    pub(crate) fn new(endian: targets::Endian) -> Self {
+        match endian {
+            targets::Endian::little => Endian::Little,
+            targets::Endian::big => Endian::Big,
+        }
+    }
+}
+
+impl fmt::Display for Endian {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A builder for platforms whose properties differ from a builtin target.
+///
+/// Returned by `Platform::builder`. If the triple is builtin, or another spelling of one, the
+/// platform starts out with that target's properties. Otherwise, properties that aren't set
+/// default the way they do in custom target specs: the architecture and pointer width must be set,
+/// and everything else defaults to no value, except for a little-endian byte order and an
+/// `unknown` vendor.
+///
+/// ## Examples
+///
+/// ```
+/// use target_spec::{Endian, Platform, TargetSpec};
+///
+/// // A big-endian variant of a 32-bit ARM bare-metal target.
+/// let platform = Platform::builder("thumbv7em-none-eabi")
+///     .endian(Endian::Big)
+///     .os("none")
+///     .build()
+///     .unwrap();
+/// assert!(platform.is_custom());
+///
+/// let spec: TargetSpec = "cfg(all(target_endian = \"big\", target_pointer_width = \"32\"))"
+///     .parse()
+///     .unwrap();
+/// assert_eq!(spec.eval(&platform), Some(true));
+/// ```
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct PlatformBuilder<'a> {
+    triple: String,
+    target_features: TargetFeatures<'a>,
+    arch: Option<String>,
+    os: Option<String>,
+    env: Option<String>,
+    vendor: Option<String>,
+    family: Option<String>,
+    endian: Option<Endian>,
+    pointer_width: Option<u8>,
+    abi: Option<String>,
+    max_atomic_width: Option<u8>,
+}
+
+impl<'a> PlatformBuilder<'a> {
+    pub(crate) fn new(triple: impl Into<String>) -> Self {
+        Self {
+            triple: triple.into(),
+            target_features: TargetFeatures::Unknown,
+            arch: None,
+            os: None,
+            env: None,
+            vendor: None,
+            family: None,
+            endian: None,
+            pointer_width: None,
+            abi: None,
+            max_atomic_width: None,
+        }
+    }
+
+    /// Sets the target features. The default is `TargetFeatures::Unknown`.
+    pub fn target_features(mut self, target_features: TargetFeatures<'a>) -> Self {
+        self.target_features = target_features;
+        self
+    }
+
+    /// Sets the architecture, as matched by `cfg(target_arch = "...")`.
+    pub fn arch(mut self, arch: impl Into<String>) -> Self {
+        self.arch = Some(arch.into());
+        self
+    }
+
+    /// Sets the operating system, as matched by `cfg(target_os = "...")`. `"none"` means there's
+    /// no operating system.
+    pub fn os(mut self, os: impl Into<String>) -> Self {
+        self.os = Some(os.into());
+        self
+    }
+
+    /// Sets the environment, as matched by `cfg(target_env = "...")`. An empty string means
+    /// there's no environment.
+    pub fn env(mut self, env: impl Into<String>) -> Self {
+        self.env = Some(env.into());
+        self
+    }
+
+    /// Sets the vendor, as matched by `cfg(target_vendor = "...")`. An empty string means there's
+    /// no vendor.
+    pub fn vendor(mut self, vendor: impl Into<String>) -> Self {
+        self.vendor = Some(vendor.into());
+        self
+    }
+
+    /// Sets the family, as matched by `cfg(target_family = "...")`: either `"unix"`, `"windows"`,
+    /// or an empty string for no family.
+    pub fn family(mut self, family: impl Into<String>) -> Self {
+        self.family = Some(family.into());
+        self
+    }
+
+    /// Sets the byte order, as matched by `cfg(target_endian = "...")`.
+    pub fn endian(mut self, endian: Endian) -> Self {
+        self.endian = Some(endian);
+        self
+    }
+
+    /// Sets the pointer width in bits, as matched by `cfg(target_pointer_width = "...")`.
+    ///
+    /// Unless the maximum atomic width is set as well, it's set to the pointer width, as rustc
+    /// does by default.
+    pub fn pointer_width(mut self, pointer_width: u8) -> Self {
+        self.pointer_width = Some(pointer_width);
+        self
+    }
+
+    /// Sets the ABI, as matched by `cfg(target_abi = "...")`.
+    pub fn abi(mut self, abi: impl Into<String>) -> Self {
+        self.abi = Some(abi.into());
+        self
+    }
+
+    /// Sets the width in bits of the largest atomics, as matched by
+    /// `cfg(target_has_atomic = "...")`. 0 means the platform has no atomics.
+    pub fn max_atomic_width(mut self, max_atomic_width: u8) -> Self {
+        self.max_atomic_width = Some(max_atomic_width);
+        self
+    }
+
+    /// Creates the platform.
+    ///
+    /// Returns an error if a value isn't known to `target-spec`, or if the triple isn't builtin
+    /// and the architecture or pointer width wasn't set.
+    pub fn build(self) -> Result<Platform<'a>, CustomTargetError> {
+        let base = builtin_target(&self.triple);
+        let pointer_width = match (self.pointer_width, base) {
+            (Some(pointer_width), _) => pointer_width,
+            (None, Some(base)) => base.pointer_width,
+            (None, None) => return Err(CustomTargetError::MissingProperty("target_pointer_width")),
+        };
+        let arch = match (&self.arch, base) {
+            (Some(arch), _) => parse_value("target_arch", arch)?,
+            (None, Some(base)) => base.arch,
+            (None, None) => return Err(CustomTargetError::MissingProperty("target_arch")),
+        };
+        let max_atomic_width = match (self.max_atomic_width, self.pointer_width, base) {
+            (Some(max), _, _) => max,
+            (None, None, Some(base)) => builtin_max_atomic_width(base.triple),
+            (None, _, _) => pointer_width,
+        };
+        let endian = match (self.endian, base) {
+            (Some(Endian::Little), _) => targets::Endian::little,
+            (Some(Endian::Big), _) => targets::Endian::big,
+            (None, Some(base)) => base.endian,
+            (None, None) => targets::Endian::little,
+        };
+
+        let target_info = TargetInfo {
+            os: match (&self.os, base) {
+                (Some(os), _) => parse_optional("target_os", Some(os), "none")?,
+                (None, Some(base)) => base.os,
+                (None, None) => None,
+            },
+            arch,
+            env: match (&self.env, base) {
+                (Some(env), _) => parse_optional("target_env", Some(env), "")?,
+                (None, Some(base)) => base.env,
+                (None, None) => None,
+            },
+            vendor: match (&self.vendor, base) {
+                (Some(vendor), _) => parse_optional("target_vendor", Some(vendor), "")?,
+                (None, Some(base)) => base.vendor,
+                (None, None) => Some(targets::Vendor::unknown),
+            },
+            family: match (&self.family, base) {
+                (Some(family), _) => parse_optional("target_family", Some(family), "")?,
+                (None, Some(base)) => base.family,
+                (None, None) => None,
+            },
+            pointer_width,
+            endian,
+            triple: "",
+        };
+        let abi = match (self.abi, base) {
+            (Some(abi), _) => abi,
+            (None, Some(base)) => builtin_abi(base.triple).to_string(),
+            (None, None) => String::new(),
+        };
+        let custom = CustomTarget {
+            triple: self.triple.into(),
+            target_info,
+            abi,
+            atomic_widths: AtomicWidths {
+                min: 8,
+                max: max_atomic_width,
+            },
+        };
+        Ok(Platform::from_custom_target(custom, self.target_features))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TargetSpec;
+
+    #[test]
+    fn test_builder() {
+        let eval = |spec: &str, platform: &Platform<'_>| {
+            let spec: TargetSpec = spec.parse().unwrap();
+            spec.eval(platform)
+        };
+
+        // Unchanged builtin targets evaluate the same way.
+        let platform = Platform::builder("x86_64-unknown-linux-gnu")
+            .build()
+            .unwrap();
+        assert_eq!(platform.triple(), "x86_64-unknown-linux-gnu");
+        for spec in &[
+            "cfg(all(unix, target_os = \"linux\", target_env = \"gnu\"))",
+            "cfg(target_has_atomic = \"64\")",
+            "cfg(target_endian = \"little\")",
+        ] {
+            assert_eq!(eval(spec, &platform), Some(true), "{}", spec);
+        }
+
+        let platform = Platform::builder("x86_64-unknown-linux-gnu")
+            .pointer_width(32)
+            .env("musl")
+            .build()
+            .unwrap();
+        assert_eq!(
+            eval(
+                "cfg(all(target_pointer_width = \"32\", target_env = \"musl\", unix))",
+                &platform
+            ),
+            Some(true)
+        );
+        assert_eq!(
+            eval("cfg(target_has_atomic = \"64\")", &platform),
+            Some(false),
+            "the maximum atomic width follows the pointer width"
+        );
+
+        let platform = Platform::builder("my-custom-target")
+            .arch("riscv32")
+            .pointer_width(32)
+            .build()
+            .unwrap();
+        assert_eq!(
+            eval(
+                "cfg(all(target_endian = \"little\", target_vendor = \"unknown\"))",
+                &platform
+            ),
+            Some(true)
+        );
+        assert_eq!(eval("cfg(any(unix, windows))", &platform), Some(false));
+
+        assert_eq!(
+            Platform::builder("my-custom-target")
+                .arch("riscv32")
+                .build()
+                .unwrap_err(),
+            CustomTargetError::MissingProperty("target_pointer_width")
+        );
+        assert_eq!(
+            Platform::builder("x86_64-unknown-linux-gnu")
+                .os("bogus")
+                .build()
+                .unwrap_err(),
+            CustomTargetError::UnknownValue {
+                key: "target_os",
+                value: "bogus".to_string(),
+            }
+        );
+    }
+}
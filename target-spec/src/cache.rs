@@ -0,0 +1,101 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{ParseError, TargetSpec};
+use std::collections::BTreeMap;
+
+/// A cache of parsed target specs, keyed by the string they were parsed from.
+///
+/// Workspaces tend to repeat the same handful of `cfg()` expressions across many manifests. Parsing
+/// through a cache means each distinct string is only parsed once, and every spec returned for it
+/// shares the same parsed expression, since cloning a `TargetSpec` is cheap.
+///
+/// Strings that fail to parse aren't cached.
+///
+/// ## Examples
+///
+/// ```
+/// use target_spec::TargetSpecCache;
+///
+/// let mut cache = TargetSpecCache::new();
+/// let first = cache.parse("cfg(unix)").unwrap();
+/// let second = cache.parse("cfg(unix)").unwrap();
+/// assert_eq!(first.as_str(), second.as_str());
+/// assert_eq!(cache.len(), 1);
+///
+/// assert!(cache.parse("cfg(bogus(unix))").is_err());
+/// assert_eq!(cache.len(), 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TargetSpecCache {
+    specs: BTreeMap<Box<str>, TargetSpec>,
+}
+
+impl TargetSpecCache {
+    /// Creates a new, empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses this string into a `TargetSpec`, or returns the spec it was previously parsed into.
+    pub fn parse(&mut self, input: &str) -> Result<TargetSpec, ParseError> {
+        if let Some(spec) = self.specs.get(input) {
+            return Ok(spec.clone());
+        }
+        let spec: TargetSpec = input.parse()?;
+        self.specs.insert(input.into(), spec.clone());
+        Ok(spec)
+    }
+
+    /// Returns the spec this string was parsed into, if it's in the cache.
+    pub fn get(&self, input: &str) -> Option<&TargetSpec> {
+        self.specs.get(input)
+    }
+
+    /// Returns the number of distinct strings in the cache.
+    pub fn len(&self) -> usize {
+        self.specs.len()
+    }
+
+    /// Returns true if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.specs.is_empty()
+    }
+
+    /// Returns the specs in the cache, in order of the strings they were parsed from.
+    pub fn specs(&self) -> impl Iterator<Item = &TargetSpec> + '_ {
+        self.specs.values()
+    }
+
+    /// Removes every spec from the cache.
+    pub fn clear(&mut self) {
+        self.specs.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Target;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_shared() {
+        let mut cache = TargetSpecCache::new();
+        let first = cache.parse("cfg(any(unix, windows))").unwrap();
+        let second = cache.parse("cfg(any(unix, windows))").unwrap();
+        match (&first.target, &second.target) {
+            (Target::Spec(first), Target::Spec(second)) => assert!(Arc::ptr_eq(first, second)),
+            _ => panic!("expected cfg() expressions"),
+        }
+
+        // Equivalent strings are cached separately.
+        cache.parse("cfg(any(windows, unix))").unwrap();
+        cache.parse("x86_64-unknown-linux-gnu").unwrap();
+        assert_eq!(cache.len(), 3);
+        assert!(cache.get("cfg(unix)").is_none());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
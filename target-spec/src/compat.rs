@@ -0,0 +1,89 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Whether binaries built for one platform run on another.
+
+use crate::{Platform, TargetArch, TargetEnv, TargetOs};
+
+/// Returns true if binaries built for `binary` can run on `host`. See `Platform::can_run`.
+pub(crate) fn can_run(host: &Platform<'_>, binary: &Platform<'_>) -> bool {
+    if host.triple() == binary.triple() {
+        return true;
+    }
+    // Properties that are unknown, as with some guessed platforms, can't be compared.
+    let (host_arch, binary_arch) = match (host.target_arch(), binary.target_arch()) {
+        (Some(host_arch), Some(binary_arch)) => (host_arch, binary_arch),
+        _ => return false,
+    };
+    let os = match (host.target_os(), binary.target_os()) {
+        (Some(host_os), Some(binary_os)) if host_os == binary_os => host_os,
+        // Bare-metal binaries only run on exactly the platform they were built for.
+        _ => return false,
+    };
+    match (host.endian(), binary.endian()) {
+        (Some(host_endian), Some(binary_endian)) if host_endian == binary_endian => {}
+        _ => return false,
+    }
+
+    // The ABI is only comparable between platforms with the same architecture: 32-bit ARM
+    // platforms have ABIs like eabihf that 64-bit ones don't.
+    let abi_can_run = host_arch != binary_arch || host.abi() == binary.abi();
+    arch_can_run(host_arch, binary_arch, os)
+        && abi_can_run
+        && env_can_run(host.target_env(), binary.target_env(), os)
+}
+
+fn arch_can_run(host: TargetArch, binary: TargetArch, os: TargetOs) -> bool {
+    match (host, binary) {
+        _ if host == binary => true,
+        // 64-bit x86 platforms run 32-bit binaries, except on macOS, which dropped support for
+        // them.
+        (TargetArch::X86_64, TargetArch::X86) => os != TargetOs::Macos,
+        // Most 64-bit ARM Linux kernels run 32-bit ARM binaries.
+        (TargetArch::Aarch64, TargetArch::Arm) => os == TargetOs::Linux,
+        _ => false,
+    }
+}
+
+fn env_can_run(host: Option<TargetEnv>, binary: Option<TargetEnv>, os: TargetOs) -> bool {
+    match os {
+        // MSVC and MinGW binaries both run on Windows.
+        TargetOs::Windows => true,
+        // musl binaries are statically linked, so they run on glibc hosts as well.
+        TargetOs::Linux if binary == Some(TargetEnv::Musl) => true,
+        _ => host == binary,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Platform, TargetFeatures};
+
+    #[test]
+    fn test_can_run() {
+        let platform = |triple: &str| Platform::new(triple, TargetFeatures::Unknown).unwrap();
+        let linux_gnu = platform("x86_64-unknown-linux-gnu");
+        let linux_musl = platform("x86_64-unknown-linux-musl");
+        let i686_gnu = platform("i686-unknown-linux-gnu");
+        let aarch64_gnu = platform("aarch64-unknown-linux-gnu");
+        let macos = platform("x86_64-apple-darwin");
+
+        assert!(linux_gnu.can_run(&linux_gnu));
+        assert!(linux_gnu.can_run(&linux_musl), "musl binaries run on glibc");
+        assert!(!linux_musl.can_run(&linux_gnu), "glibc binaries need glibc");
+        assert!(linux_gnu.can_run(&i686_gnu));
+        assert!(!i686_gnu.can_run(&linux_gnu));
+        assert!(!linux_gnu.can_run(&aarch64_gnu));
+        assert!(aarch64_gnu.can_run(&platform("armv7-unknown-linux-gnueabihf")));
+        assert!(!linux_gnu.can_run(&macos));
+        assert!(!macos.can_run(&platform("i686-apple-darwin")));
+        assert!(platform("x86_64-pc-windows-msvc").can_run(&platform("i686-pc-windows-gnu")));
+        assert!(!linux_gnu.can_run(&platform("wasm32-unknown-unknown")));
+        assert!(!platform("mips64el-unknown-linux-gnuabi64")
+            .can_run(&platform("mips64-unknown-linux-gnuabi64")));
+
+        let guessed = Platform::new_heuristic("riscv64gc-unknown-freebsd", TargetFeatures::Unknown);
+        assert!(guessed.can_run(&guessed));
+        assert!(!linux_gnu.can_run(&guessed));
+    }
+}
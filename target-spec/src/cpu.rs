@@ -0,0 +1,462 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Target features enabled by `-C target-cpu`.
+
+use cfg_expr::targets::{Arch, Os, TargetInfo, Vendor};
+
+/// The target features enabled by each `-C target-cpu` value, sorted by architecture and CPU.
+///
+/// This was generated with rustc 1.95.0 by running `rustc --print cfg -C target-cpu=<cpu>` for
+/// each CPU in `rustc --print target-cpus`, with the `aarch64-unknown-linux-gnu`,
+/// `i686-unknown-linux-gnu` and `x86_64-unknown-linux-gnu` targets. Features are separated by
+/// spaces.
+static TARGET_CPU_FEATURES: &[(&str, &str, &str)] = &[
+    ("aarch64", "a64fx", "aes crc dpb fcma fp16 lor lse neon pan pmuv3 ras rdm sha2 sve vh"),
+    ("aarch64", "ampere1", "aes bf16 bti crc dit dotprod dpb dpb2 fcma flagm fp16 frintts i8mm jsconv lor lse neon paca pacg pan pmuv3 rand ras rcpc rcpc2 rdm sb sha2 sha3 ssbs vh"),
+    ("aarch64", "ampere1a", "aes bf16 bti crc dit dotprod dpb dpb2 fcma flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 rand ras rcpc rcpc2 rdm sb sha2 sha3 sm4 ssbs vh"),
+    ("aarch64", "ampere1b", "aes bf16 bti crc dit dotprod dpb dpb2 fcma flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 rand ras rcpc rcpc2 rdm sb sha2 sha3 sm4 ssbs vh"),
+    ("aarch64", "ampere1c", "aes bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 rand ras rcpc rcpc2 rdm sb sha2 sha3 sm4 ssbs sve sve2 vh"),
+    ("aarch64", "apple-a10", "aes crc lor neon pan pmuv3 rdm sha2 vh"),
+    ("aarch64", "apple-a11", "aes crc dpb fp16 lor lse neon pan pmuv3 ras rdm sha2 vh"),
+    ("aarch64", "apple-a12", "aes crc dpb fcma fp16 jsconv lor lse neon paca pacg pan pmuv3 ras rcpc rdm sha2 vh"),
+    ("aarch64", "apple-a13", "aes crc dit dotprod dpb fcma fhm flagm fp16 jsconv lor lse neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sha2 sha3 vh"),
+    ("aarch64", "apple-a14", "aes crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts jsconv lor lse neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb sha2 sha3 ssbs vh"),
+    ("aarch64", "apple-a15", "aes bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb sha2 sha3 ssbs vh"),
+    ("aarch64", "apple-a16", "aes bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb sha2 sha3 ssbs vh"),
+    ("aarch64", "apple-a17", "aes bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb sha2 sha3 ssbs vh"),
+    ("aarch64", "apple-a7", "aes neon pmuv3 sha2"),
+    ("aarch64", "apple-m4", "aes bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb sha2 sha3 ssbs vh"),
+    ("aarch64", "apple-m5", "aes bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb sha2 sha3 ssbs vh"),
+    ("aarch64", "c1-nano", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb ssbs sve sve2 vh"),
+    ("aarch64", "c1-premium", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "c1-pro", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "c1-ultra", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "carmel", "aes crc dpb fp16 lor lse neon pan ras rdm sha2 vh"),
+    ("aarch64", "cortex-a320", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb ssbs sve sve2 vh"),
+    ("aarch64", "cortex-a34", "aes crc neon pmuv3 sha2"),
+    ("aarch64", "cortex-a35", "aes crc neon pmuv3 sha2"),
+    ("aarch64", "cortex-a510", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb ssbs sve sve2 vh"),
+    ("aarch64", "cortex-a520", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb ssbs sve sve2 vh"),
+    ("aarch64", "cortex-a520ae", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb ssbs sve sve2 vh"),
+    ("aarch64", "cortex-a53", "aes crc neon pmuv3 sha2"),
+    ("aarch64", "cortex-a55", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rcpc rdm sha2 vh"),
+    ("aarch64", "cortex-a57", "aes crc neon pmuv3 sha2"),
+    ("aarch64", "cortex-a65", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rcpc rdm sha2 ssbs vh"),
+    ("aarch64", "cortex-a65ae", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rcpc rdm sha2 ssbs vh"),
+    ("aarch64", "cortex-a710", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb ssbs sve sve2 vh"),
+    ("aarch64", "cortex-a715", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "cortex-a72", "aes crc neon pmuv3 sha2"),
+    ("aarch64", "cortex-a720", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "cortex-a720ae", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "cortex-a725", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "cortex-a73", "aes crc neon pmuv3 sha2"),
+    ("aarch64", "cortex-a75", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rcpc rdm sha2 vh"),
+    ("aarch64", "cortex-a76", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rcpc rdm sha2 ssbs vh"),
+    ("aarch64", "cortex-a76ae", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rcpc rdm sha2 ssbs vh"),
+    ("aarch64", "cortex-a77", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rcpc rdm sha2 ssbs vh"),
+    ("aarch64", "cortex-a78", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rcpc rdm sha2 spe ssbs vh"),
+    ("aarch64", "cortex-a78ae", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rcpc rdm sha2 spe ssbs vh"),
+    ("aarch64", "cortex-a78c", "aes crc dotprod dpb flagm fp16 lor lse neon paca pacg pan pmuv3 ras rcpc rdm sha2 spe ssbs vh"),
+    ("aarch64", "cortex-r82", "crc dit dotprod dpb dpb2 fcma fhm flagm fp16 jsconv lse neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb ssbs"),
+    ("aarch64", "cortex-r82ae", "crc dit dotprod dpb dpb2 fcma fhm flagm fp16 jsconv lse neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb ssbs"),
+    ("aarch64", "cortex-x1", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rcpc rdm sha2 spe ssbs vh"),
+    ("aarch64", "cortex-x1c", "aes crc dotprod dpb flagm fp16 lor lse neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sha2 spe ssbs vh"),
+    ("aarch64", "cortex-x2", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb ssbs sve sve2 vh"),
+    ("aarch64", "cortex-x3", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "cortex-x4", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "cortex-x925", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "exynos-m3", "aes crc neon pmuv3 sha2"),
+    ("aarch64", "exynos-m4", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rdm sha2 vh"),
+    ("aarch64", "exynos-m5", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rdm sha2 vh"),
+    ("aarch64", "falkor", "aes crc neon pmuv3 rdm sha2"),
+    ("aarch64", "fujitsu-monaka", "aes bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse neon paca pacg pan pmuv3 rand ras rcpc rcpc2 rdm sb sha2 sha3 sm4 ssbs sve sve2 vh"),
+    ("aarch64", "gb10", "aes bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb sha2 sha3 sm4 spe ssbs sve sve2 vh"),
+    ("aarch64", "generic", "neon"),
+    ("aarch64", "grace", "aes bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb sha2 sha3 sm4 spe ssbs sve sve2 vh"),
+    ("aarch64", "kryo", "aes crc neon pmuv3 sha2"),
+    ("aarch64", "neoverse-512tvb", "aes bf16 crc dit dotprod dpb dpb2 fcma fhm flagm fp16 i8mm jsconv lor lse neon paca pacg pan pmuv3 rand ras rcpc rcpc2 rdm sha2 sha3 sm4 spe ssbs sve vh"),
+    ("aarch64", "neoverse-e1", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rcpc rdm sha2 ssbs vh"),
+    ("aarch64", "neoverse-n1", "aes crc dotprod dpb fp16 lor lse neon pan pmuv3 ras rcpc rdm sha2 spe ssbs vh"),
+    ("aarch64", "neoverse-n2", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sb ssbs sve sve2 vh"),
+    ("aarch64", "neoverse-n3", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 rand ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "neoverse-v1", "aes bf16 crc dit dotprod dpb dpb2 fcma fhm flagm fp16 i8mm jsconv lor lse neon paca pacg pan pmuv3 rand ras rcpc rcpc2 rdm sha2 sha3 sm4 spe ssbs sve vh"),
+    ("aarch64", "neoverse-v2", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 rand ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "neoverse-v3", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 rand ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "neoverse-v3ae", "bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 rand ras rcpc rcpc2 rdm sb spe ssbs sve sve2 vh"),
+    ("aarch64", "olympus", "aes bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse mte neon paca pacg pan pmuv3 rand ras rcpc rcpc2 rdm sb sha2 sha3 sm4 spe ssbs sve sve2 vh"),
+    ("aarch64", "oryon-1", "aes bf16 bti crc dit dotprod dpb dpb2 fcma fhm flagm fp16 frintts i8mm jsconv lor lse neon paca pacg pan pmuv3 rand ras rcpc rcpc2 rdm sb sha2 sha3 sm4 spe ssbs vh"),
+    ("aarch64", "saphira", "aes crc dit dotprod dpb fcma flagm jsconv lor lse neon paca pacg pan pmuv3 ras rcpc rcpc2 rdm sha2 spe vh"),
+    ("aarch64", "thunderx", "aes crc neon pmuv3 sha2"),
+    ("aarch64", "thunderx2t99", "aes crc lor lse neon pan rdm sha2 vh"),
+    ("aarch64", "thunderx3t110", "aes crc dpb fcma jsconv lor lse neon paca pacg pan pmuv3 ras rcpc rdm sha2 vh"),
+    ("aarch64", "thunderxt81", "aes crc neon pmuv3 sha2"),
+    ("aarch64", "thunderxt83", "aes crc neon pmuv3 sha2"),
+    ("aarch64", "thunderxt88", "aes crc neon pmuv3 sha2"),
+    ("aarch64", "tsv110", "aes crc dotprod dpb fcma fhm fp16 jsconv lor lse neon pan pmuv3 ras rdm sha2 spe vh"),
+    ("x86", "alderlake", "adx aes avx avx2 avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86", "amdfam10", "cmpxchg16b fxsr lzcnt popcnt sse sse2 sse3 sse4a"),
+    ("x86", "arrowlake", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86", "arrowlake-s", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86", "arrowlake_s", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86", "athlon", ""),
+    ("x86", "athlon-4", "fxsr sse"),
+    ("x86", "athlon-fx", "fxsr sse sse2"),
+    ("x86", "athlon-mp", "fxsr sse"),
+    ("x86", "athlon-tbird", ""),
+    ("x86", "athlon-xp", "fxsr sse"),
+    ("x86", "athlon64", "fxsr sse sse2"),
+    ("x86", "athlon64-sse3", "cmpxchg16b fxsr sse sse2 sse3"),
+    ("x86", "atom", "cmpxchg16b fxsr movbe sse sse2 sse3 ssse3"),
+    ("x86", "atom_sse4_2", "cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86", "atom_sse4_2_movbe", "aes cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "barcelona", "cmpxchg16b fxsr lzcnt popcnt sse sse2 sse3 sse4a"),
+    ("x86", "bdver1", "aes avx cmpxchg16b fxsr lzcnt pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 xsave"),
+    ("x86", "bdver2", "aes avx bmi1 cmpxchg16b f16c fma fxsr lzcnt pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 tbm xsave"),
+    ("x86", "bdver3", "aes avx bmi1 cmpxchg16b f16c fma fxsr lzcnt pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 tbm xsave xsaveopt"),
+    ("x86", "bdver4", "aes avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 tbm xsave xsaveopt"),
+    ("x86", "bonnell", "cmpxchg16b fxsr movbe sse sse2 sse3 ssse3"),
+    ("x86", "broadwell", "adx avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "btver1", "cmpxchg16b fxsr lzcnt popcnt sse sse2 sse3 sse4a ssse3"),
+    ("x86", "btver2", "aes avx bmi1 cmpxchg16b f16c fxsr lzcnt movbe pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 xsave xsaveopt"),
+    ("x86", "c3", ""),
+    ("x86", "c3-2", "fxsr sse"),
+    ("x86", "cannonlake", "adx aes avx avx2 avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vl bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "cascadelake", "adx aes avx avx2 avx512bw avx512cd avx512dq avx512f avx512vl avx512vnni bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "clearwaterforest", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "cooperlake", "adx aes avx avx2 avx512bf16 avx512bw avx512cd avx512dq avx512f avx512vl avx512vnni bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "core-avx-i", "avx cmpxchg16b f16c fxsr pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "core-avx2", "avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "core2", "cmpxchg16b fxsr sse sse2 sse3 ssse3"),
+    ("x86", "core_2_duo_sse4_1", "cmpxchg16b fxsr sse sse2 sse3 sse4.1 ssse3"),
+    ("x86", "core_2_duo_ssse3", "cmpxchg16b fxsr sse sse2 sse3 ssse3"),
+    ("x86", "core_2nd_gen_avx", "avx cmpxchg16b fxsr pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "core_3rd_gen_avx", "avx cmpxchg16b f16c fxsr pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "core_4th_gen_avx", "avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "core_4th_gen_avx_tsx", "avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "core_5th_gen_avx", "adx avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "core_5th_gen_avx_tsx", "adx avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "core_aes_pclmulqdq", "cmpxchg16b fxsr pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86", "core_i7_sse4_2", "cmpxchg16b fxsr popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86", "corei7", "cmpxchg16b fxsr popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86", "corei7-avx", "avx cmpxchg16b fxsr pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "diamondrapids", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "emeraldrapids", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "generic", ""),
+    ("x86", "geode", ""),
+    ("x86", "goldmont", "aes cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "goldmont-plus", "aes cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "goldmont_plus", "aes cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "gracemont", "adx aes avx avx2 avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86", "grandridge", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86", "graniterapids", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "graniterapids-d", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "graniterapids_d", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "haswell", "avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "i386", ""),
+    ("x86", "i486", ""),
+    ("x86", "i586", ""),
+    ("x86", "i686", ""),
+    ("x86", "icelake-client", "adx aes avx avx2 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "icelake-server", "adx aes avx avx2 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "icelake_client", "adx aes avx avx2 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "icelake_server", "adx aes avx avx2 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "ivybridge", "avx cmpxchg16b f16c fxsr pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "k6", ""),
+    ("x86", "k6-2", ""),
+    ("x86", "k6-3", ""),
+    ("x86", "k8", "fxsr sse sse2"),
+    ("x86", "k8-sse3", "cmpxchg16b fxsr sse sse2 sse3"),
+    ("x86", "knl", "adx aes avx avx2 avx512cd avx512f bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "knm", "adx aes avx avx2 avx512cd avx512f avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "lakemont", ""),
+    ("x86", "lunarlake", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86", "meteorlake", "adx aes avx avx2 avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86", "mic_avx512", "adx aes avx avx2 avx512cd avx512f bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "nehalem", "cmpxchg16b fxsr popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86", "nocona", "cmpxchg16b fxsr sse sse2 sse3"),
+    ("x86", "novalake", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "opteron", "fxsr sse sse2"),
+    ("x86", "opteron-sse3", "cmpxchg16b fxsr sse sse2 sse3"),
+    ("x86", "pantherlake", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "penryn", "cmpxchg16b fxsr sse sse2 sse3 sse4.1 ssse3"),
+    ("x86", "pentium", ""),
+    ("x86", "pentium-m", "fxsr sse sse2"),
+    ("x86", "pentium-mmx", ""),
+    ("x86", "pentium2", "fxsr"),
+    ("x86", "pentium3", "fxsr sse"),
+    ("x86", "pentium3m", "fxsr sse"),
+    ("x86", "pentium4", "fxsr sse sse2"),
+    ("x86", "pentium4m", "fxsr sse sse2"),
+    ("x86", "pentium_4", "fxsr sse sse2"),
+    ("x86", "pentium_4_sse3", "fxsr sse sse2 sse3"),
+    ("x86", "pentium_ii", "fxsr"),
+    ("x86", "pentium_iii", "fxsr sse"),
+    ("x86", "pentium_iii_no_xmm_regs", "fxsr sse"),
+    ("x86", "pentium_m", "fxsr sse sse2"),
+    ("x86", "pentium_mmx", ""),
+    ("x86", "pentium_pro", ""),
+    ("x86", "pentiumpro", ""),
+    ("x86", "prescott", "fxsr sse sse2 sse3"),
+    ("x86", "raptorlake", "adx aes avx avx2 avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86", "rocketlake", "adx aes avx avx2 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "sandybridge", "avx cmpxchg16b fxsr pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86", "sapphirerapids", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "sierraforest", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86", "silvermont", "cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86", "skx", "adx aes avx avx2 avx512bw avx512cd avx512dq avx512f avx512vl bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "skylake", "adx aes avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "skylake-avx512", "adx aes avx avx2 avx512bw avx512cd avx512dq avx512f avx512vl bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "skylake_avx512", "adx aes avx avx2 avx512bw avx512cd avx512dq avx512f avx512vl bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "slm", "cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86", "tigerlake", "adx aes avx avx2 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vp2intersect avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "tremont", "aes cmpxchg16b fxsr gfni movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "westmere", "cmpxchg16b fxsr pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86", "wildcatlake", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "winchip-c6", ""),
+    ("x86", "winchip2", ""),
+    ("x86", "x86-64", "fxsr sse sse2"),
+    ("x86", "x86-64-v2", "cmpxchg16b fxsr popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86", "x86-64-v3", "avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave"),
+    ("x86", "x86-64-v4", "avx avx2 avx512bw avx512cd avx512dq avx512f avx512vl bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave"),
+    ("x86", "yonah", "fxsr sse sse2 sse3"),
+    ("x86", "znver1", "adx aes avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "znver2", "adx aes avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86", "znver3", "adx aes avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "znver4", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86", "znver5", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vp2intersect avx512vpopcntdq avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "alderlake", "adx aes avx avx2 avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "amdfam10", "cmpxchg16b fxsr lzcnt popcnt sse sse2 sse3 sse4a"),
+    ("x86_64", "arrowlake", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "arrowlake-s", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "arrowlake_s", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "athlon", "sse sse2"),
+    ("x86_64", "athlon-4", "fxsr sse sse2"),
+    ("x86_64", "athlon-fx", "fxsr sse sse2"),
+    ("x86_64", "athlon-mp", "fxsr sse sse2"),
+    ("x86_64", "athlon-tbird", "sse sse2"),
+    ("x86_64", "athlon-xp", "fxsr sse sse2"),
+    ("x86_64", "athlon64", "fxsr sse sse2"),
+    ("x86_64", "athlon64-sse3", "cmpxchg16b fxsr sse sse2 sse3"),
+    ("x86_64", "atom", "cmpxchg16b fxsr movbe sse sse2 sse3 ssse3"),
+    ("x86_64", "atom_sse4_2", "cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86_64", "atom_sse4_2_movbe", "aes cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "barcelona", "cmpxchg16b fxsr lzcnt popcnt sse sse2 sse3 sse4a"),
+    ("x86_64", "bdver1", "aes avx cmpxchg16b fxsr lzcnt pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 xsave"),
+    ("x86_64", "bdver2", "aes avx bmi1 cmpxchg16b f16c fma fxsr lzcnt pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 tbm xsave"),
+    ("x86_64", "bdver3", "aes avx bmi1 cmpxchg16b f16c fma fxsr lzcnt pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 tbm xsave xsaveopt"),
+    ("x86_64", "bdver4", "aes avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 tbm xsave xsaveopt"),
+    ("x86_64", "bonnell", "cmpxchg16b fxsr movbe sse sse2 sse3 ssse3"),
+    ("x86_64", "broadwell", "adx avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "btver1", "cmpxchg16b fxsr lzcnt popcnt sse sse2 sse3 sse4a ssse3"),
+    ("x86_64", "btver2", "aes avx bmi1 cmpxchg16b f16c fxsr lzcnt movbe pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 xsave xsaveopt"),
+    ("x86_64", "c3", "sse sse2"),
+    ("x86_64", "c3-2", "fxsr sse sse2"),
+    ("x86_64", "cannonlake", "adx aes avx avx2 avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vl bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "cascadelake", "adx aes avx avx2 avx512bw avx512cd avx512dq avx512f avx512vl avx512vnni bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "clearwaterforest", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "cooperlake", "adx aes avx avx2 avx512bf16 avx512bw avx512cd avx512dq avx512f avx512vl avx512vnni bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "core-avx-i", "avx cmpxchg16b f16c fxsr pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "core-avx2", "avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "core2", "cmpxchg16b fxsr sse sse2 sse3 ssse3"),
+    ("x86_64", "core_2_duo_sse4_1", "cmpxchg16b fxsr sse sse2 sse3 sse4.1 ssse3"),
+    ("x86_64", "core_2_duo_ssse3", "cmpxchg16b fxsr sse sse2 sse3 ssse3"),
+    ("x86_64", "core_2nd_gen_avx", "avx cmpxchg16b fxsr pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "core_3rd_gen_avx", "avx cmpxchg16b f16c fxsr pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "core_4th_gen_avx", "avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "core_4th_gen_avx_tsx", "avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "core_5th_gen_avx", "adx avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "core_5th_gen_avx_tsx", "adx avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "core_aes_pclmulqdq", "cmpxchg16b fxsr pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86_64", "core_i7_sse4_2", "cmpxchg16b fxsr popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86_64", "corei7", "cmpxchg16b fxsr popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86_64", "corei7-avx", "avx cmpxchg16b fxsr pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "diamondrapids", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "emeraldrapids", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "generic", "sse sse2"),
+    ("x86_64", "geode", "sse sse2"),
+    ("x86_64", "goldmont", "aes cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "goldmont-plus", "aes cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "goldmont_plus", "aes cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "gracemont", "adx aes avx avx2 avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "grandridge", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "graniterapids", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "graniterapids-d", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "graniterapids_d", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "haswell", "avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "i386", "sse sse2"),
+    ("x86_64", "i486", "sse sse2"),
+    ("x86_64", "i586", "sse sse2"),
+    ("x86_64", "i686", "sse sse2"),
+    ("x86_64", "icelake-client", "adx aes avx avx2 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "icelake-server", "adx aes avx avx2 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "icelake_client", "adx aes avx avx2 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "icelake_server", "adx aes avx avx2 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "ivybridge", "avx cmpxchg16b f16c fxsr pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "k6", "sse sse2"),
+    ("x86_64", "k6-2", "sse sse2"),
+    ("x86_64", "k6-3", "sse sse2"),
+    ("x86_64", "k8", "fxsr sse sse2"),
+    ("x86_64", "k8-sse3", "cmpxchg16b fxsr sse sse2 sse3"),
+    ("x86_64", "knl", "adx aes avx avx2 avx512cd avx512f bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "knm", "adx aes avx avx2 avx512cd avx512f avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "lakemont", "sse sse2"),
+    ("x86_64", "lunarlake", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "meteorlake", "adx aes avx avx2 avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "mic_avx512", "adx aes avx avx2 avx512cd avx512f bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "nehalem", "cmpxchg16b fxsr popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86_64", "nocona", "cmpxchg16b fxsr sse sse2 sse3"),
+    ("x86_64", "novalake", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "opteron", "fxsr sse sse2"),
+    ("x86_64", "opteron-sse3", "cmpxchg16b fxsr sse sse2 sse3"),
+    ("x86_64", "pantherlake", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "penryn", "cmpxchg16b fxsr sse sse2 sse3 sse4.1 ssse3"),
+    ("x86_64", "pentium", "sse sse2"),
+    ("x86_64", "pentium-m", "fxsr sse sse2"),
+    ("x86_64", "pentium-mmx", "sse sse2"),
+    ("x86_64", "pentium2", "fxsr sse sse2"),
+    ("x86_64", "pentium3", "fxsr sse sse2"),
+    ("x86_64", "pentium3m", "fxsr sse sse2"),
+    ("x86_64", "pentium4", "fxsr sse sse2"),
+    ("x86_64", "pentium4m", "fxsr sse sse2"),
+    ("x86_64", "pentium_4", "fxsr sse sse2"),
+    ("x86_64", "pentium_4_sse3", "fxsr sse sse2 sse3"),
+    ("x86_64", "pentium_ii", "fxsr sse sse2"),
+    ("x86_64", "pentium_iii", "fxsr sse sse2"),
+    ("x86_64", "pentium_iii_no_xmm_regs", "fxsr sse sse2"),
+    ("x86_64", "pentium_m", "fxsr sse sse2"),
+    ("x86_64", "pentium_mmx", "sse sse2"),
+    ("x86_64", "pentium_pro", "sse sse2"),
+    ("x86_64", "pentiumpro", "sse sse2"),
+    ("x86_64", "prescott", "fxsr sse sse2 sse3"),
+    ("x86_64", "raptorlake", "adx aes avx avx2 avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "rocketlake", "adx aes avx avx2 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "sandybridge", "avx cmpxchg16b fxsr pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsaveopt"),
+    ("x86_64", "sapphirerapids", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512fp16 avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "sierraforest", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni kl lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq widekl xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "silvermont", "cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86_64", "skx", "adx aes avx avx2 avx512bw avx512cd avx512dq avx512f avx512vl bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "skylake", "adx aes avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "skylake-avx512", "adx aes avx avx2 avx512bw avx512cd avx512dq avx512f avx512vl bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "skylake_avx512", "adx aes avx avx2 avx512bw avx512cd avx512dq avx512f avx512vl bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "slm", "cmpxchg16b fxsr movbe pclmulqdq popcnt rdrand sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86_64", "tigerlake", "adx aes avx avx2 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vp2intersect avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "tremont", "aes cmpxchg16b fxsr gfni movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "westmere", "cmpxchg16b fxsr pclmulqdq popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86_64", "wildcatlake", "adx aes avx avx2 avxifma avxneconvert avxvnni avxvnniint16 avxvnniint8 bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sha512 sm3 sm4 sse sse2 sse3 sse4.1 sse4.2 ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "winchip-c6", "sse sse2"),
+    ("x86_64", "winchip2", "sse sse2"),
+    ("x86_64", "x86-64", "fxsr sse sse2"),
+    ("x86_64", "x86-64-v2", "cmpxchg16b fxsr popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3"),
+    ("x86_64", "x86-64-v3", "avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave"),
+    ("x86_64", "x86-64-v4", "avx avx2 avx512bw avx512cd avx512dq avx512f avx512vl bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe popcnt sse sse2 sse3 sse4.1 sse4.2 ssse3 xsave"),
+    ("x86_64", "yonah", "fxsr sse sse2 sse3"),
+    ("x86_64", "znver1", "adx aes avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "znver2", "adx aes avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "znver3", "adx aes avx avx2 bmi1 bmi2 cmpxchg16b f16c fma fxsr lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "znver4", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vpopcntdq bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+    ("x86_64", "znver5", "adx aes avx avx2 avx512bf16 avx512bitalg avx512bw avx512cd avx512dq avx512f avx512ifma avx512vbmi avx512vbmi2 avx512vl avx512vnni avx512vp2intersect avx512vpopcntdq avxvnni bmi1 bmi2 cmpxchg16b f16c fma fxsr gfni lzcnt movbe pclmulqdq popcnt rdrand rdseed sha sse sse2 sse3 sse4.1 sse4.2 sse4a ssse3 vaes vpclmulqdq xsave xsavec xsaveopt xsaves"),
+];
+
+/// Returns the target features enabled by this CPU on this architecture, or `None` if either is
+/// unknown.
+pub(crate) fn target_cpu_features(
+    arch: Arch,
+    cpu: &str,
+) -> Option<impl Iterator<Item = &'static str>> {
+    let arch = match arch {
+        Arch::aarch64 => "aarch64",
+        Arch::x86 => "x86",
+        Arch::x86_64 => "x86_64",
+        _ => return None,
+    };
+    let idx = TARGET_CPU_FEATURES
+        .binary_search_by(|(cpu_arch, cpu_name, _)| (*cpu_arch, *cpu_name).cmp(&(arch, cpu)))
+        .ok()?;
+    Some(TARGET_CPU_FEATURES[idx].2.split_whitespace())
+}
+
+/// Returns the CPU rustc targets by default on this builtin target, if its architecture is
+/// covered by `TARGET_CPU_FEATURES`.
+pub(crate) fn default_target_cpu(target_info: &TargetInfo) -> Option<&'static str> {
+    let apple = target_info.vendor == Some(Vendor::apple);
+    let cpu = match target_info.arch {
+        Arch::aarch64 if apple && target_info.os == Some(Os::macos) => "apple-a14",
+        Arch::aarch64 if apple => "apple-a7",
+        Arch::aarch64 => "generic",
+        Arch::x86 if apple => "yonah",
+        Arch::x86 if target_info.triple.starts_with("i586-") => "pentium",
+        Arch::x86 => "pentium4",
+        Arch::x86_64 if apple => "penryn",
+        Arch::x86_64 => "x86-64",
+        _ => return None,
+    };
+    Some(cpu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TargetFeatures;
+
+    #[test]
+    fn test_sorted() {
+        assert!(
+            TARGET_CPU_FEATURES
+                .windows(2)
+                .all(|pair| (pair[0].0, pair[0].1) < (pair[1].0, pair[1].1)),
+            "table is sorted"
+        );
+    }
+
+    #[test]
+    fn test_target_cpu_features() {
+        let features: Vec<_> = target_cpu_features(Arch::x86_64, "skylake")
+            .expect("skylake is known")
+            .collect();
+        assert!(features.contains(&"avx2"));
+        assert!(!features.contains(&"avx512f"));
+        let features: Vec<_> = target_cpu_features(Arch::x86_64, "x86-64")
+            .expect("x86-64 is known")
+            .collect();
+        assert_eq!(features, vec!["fxsr", "sse", "sse2"]);
+
+        assert!(target_cpu_features(Arch::x86_64, "native").is_none());
+    }
+
+    #[test]
+    fn test_default_target_cpu() {
+        for target_info in cfg_expr::targets::ALL {
+            if let Some(cpu) = default_target_cpu(target_info) {
+                assert!(
+                    target_cpu_features(target_info.arch, cpu).is_some(),
+                    "default CPU {} for {} is known",
+                    cpu,
+                    target_info.triple
+                );
+            }
+        }
+
+        for (triple, feature, enabled) in &[
+            ("x86_64-unknown-linux-gnu", "sse2", Some(true)),
+            ("x86_64-unknown-linux-gnu", "sse3", Some(false)),
+            ("x86_64-apple-darwin", "sse4.1", Some(true)),
+            ("i686-pc-windows-msvc", "sse2", Some(true)),
+            ("i586-unknown-linux-gnu", "sse", Some(false)),
+            ("aarch64-unknown-linux-gnu", "neon", Some(true)),
+            ("aarch64-apple-ios", "aes", Some(true)),
+        ] {
+            let features = TargetFeatures::known(triple).expect("architecture is covered");
+            assert_eq!(
+                features.matches(feature),
+                *enabled,
+                "{} on {}",
+                feature,
+                triple
+            );
+        }
+        assert!(TargetFeatures::known("mips-unknown-linux-gnu").is_none());
+        assert!(TargetFeatures::known("bogus-triple").is_none());
+        assert!(target_cpu_features(Arch::mips, "generic").is_none());
+    }
+}
@@ -0,0 +1,299 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::atomic::AtomicWidths;
+use cfg_expr::targets::TargetInfo;
+#[cfg(any(feature = "custom", feature = "serde"))]
+use serde::Deserialize;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A target that isn't builtin, defined by a rustc custom target spec or by rustc's cfg output.
+#[derive(Debug)]
+pub(crate) struct CustomTarget {
+    pub(crate) triple: Box<str>,
+    /// The properties matched by `cfg()` expressions. `TargetInfo` requires a static triple, so its
+    /// `triple` field is empty: the triple is stored above instead.
+    pub(crate) target_info: TargetInfo,
+    /// The `target_abi` value, which `TargetInfo` doesn't have a field for.
+    pub(crate) abi: String,
+    /// The widths matched by `target_has_atomic`.
+    pub(crate) atomic_widths: AtomicWidths,
+}
+
+/// Returns the custom target registered with this triple through `register_custom_target`, if
+/// any.
+pub(crate) fn registered_target(triple: &str) -> Option<Arc<CustomTarget>> {
+    crate::registry::lookup(triple)
+}
+
+/// Parses a rustc custom target spec with this triple.
+#[cfg(feature = "custom")]
+pub(crate) fn custom_target(triple: &str, json: &str) -> Result<CustomTarget, CustomTargetError> {
+    let spec: CustomTargetSpec = serde_json::from_str(json)
+        .map_err(|err| CustomTargetError::InvalidJson(err.to_string()))?;
+    custom_target_from_spec(triple, spec)
+}
+
+/// Creates a custom target with this triple from a deserialized spec.
+#[cfg(any(feature = "custom", feature = "serde"))]
+pub(crate) fn custom_target_from_spec(
+    triple: &str,
+    spec: CustomTargetSpec,
+) -> Result<CustomTarget, CustomTargetError> {
+    let family = match spec.target_family {
+        Some(FamilyValue::One(family)) => Some(family),
+        // Newer spec files list every family, e.g. ["unix"] or ["unix", "wasm"]. cfg-expr only
+        // knows about unix and windows, so pick the first of those.
+        Some(FamilyValue::Many(families)) => families
+            .into_iter()
+            .find(|family| family == "unix" || family == "windows"),
+        None => None,
+    };
+    let pointer_width = match spec.target_pointer_width {
+        PointerWidthValue::Number(width) => width,
+        PointerWidthValue::String(width) => {
+            width.parse().map_err(|_| CustomTargetError::UnknownValue {
+                key: "target-pointer-width",
+                value: width,
+            })?
+        }
+    };
+    let vendor = spec.vendor.as_deref().unwrap_or("unknown");
+    // rustc defaults the maximum atomic width to the pointer width.
+    let atomic_widths = AtomicWidths {
+        min: spec.min_atomic_width.unwrap_or(8),
+        max: spec.max_atomic_width.unwrap_or(pointer_width),
+    };
+
+    let target_info = TargetInfo {
+        os: parse_optional("os", spec.os.as_deref(), "none")?,
+        arch: parse_value("arch", &spec.arch)?,
+        env: parse_optional("env", spec.env.as_deref(), "")?,
+        vendor: parse_optional("vendor", Some(vendor), "")?,
+        family: parse_optional("target-family", family.as_deref(), "")?,
+        pointer_width,
+        endian: parse_value(
+            "target-endian",
+            spec.target_endian.as_deref().unwrap_or("little"),
+        )?,
+        triple: "",
+    };
+    Ok(CustomTarget {
+        triple: triple.into(),
+        target_info,
+        abi: spec.abi.unwrap_or_default(),
+        atomic_widths,
+    })
+}
+
+#[cfg(feature = "serde")]
+impl CustomTarget {
+    /// Returns a custom target spec that `custom_target_from_spec` turns back into this target.
+    ///
+    /// Only the keys that `target-spec` reads are included.
+    pub(crate) fn to_spec(&self) -> CustomTargetSpec {
+        let target_info = &self.target_info;
+        // The enums in cfg-expr are named after their values.
+        let name = |value: &dyn fmt::Debug| format!("{:?}", value);
+        CustomTargetSpec {
+            arch: name(&target_info.arch),
+            target_pointer_width: PointerWidthValue::Number(target_info.pointer_width),
+            os: Some(
+                target_info
+                    .os
+                    .as_ref()
+                    .map_or_else(|| "none".to_string(), |os| name(os)),
+            ),
+            env: target_info.env.as_ref().map(|env| name(env)),
+            vendor: Some(
+                target_info
+                    .vendor
+                    .as_ref()
+                    .map_or_else(String::new, |vendor| name(vendor)),
+            ),
+            target_family: target_info
+                .family
+                .as_ref()
+                .map(|family| FamilyValue::One(name(family))),
+            target_endian: Some(name(&target_info.endian)),
+            abi: Some(self.abi.clone()).filter(|abi| !abi.is_empty()),
+            min_atomic_width: Some(self.atomic_widths.min),
+            max_atomic_width: Some(self.atomic_widths.max),
+        }
+    }
+}
+
+/// An error that occurred while parsing a custom target spec.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum CustomTargetError {
+    /// The spec wasn't valid JSON, or was missing required keys.
+    InvalidJson(String),
+    /// The spec had a value that isn't known to `target-spec`.
+    UnknownValue {
+        /// The key in the spec.
+        key: &'static str,
+        /// The unknown value.
+        value: String,
+    },
+    /// A property that has no default wasn't set on a `PlatformBuilder` for a triple that isn't
+    /// builtin.
+    MissingProperty(&'static str),
+}
+
+impl fmt::Display for CustomTargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CustomTargetError::InvalidJson(err) => write!(f, "invalid custom target spec: {}", err),
+            CustomTargetError::UnknownValue { key, value } => {
+                write!(f, "custom target spec has unknown {}: {}", key, value)
+            }
+            CustomTargetError::MissingProperty(key) => {
+                write!(f, "custom target is missing {}", key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CustomTargetError {}
+
+/// The subset of a custom target spec that's used to evaluate `cfg()` expressions.
+#[cfg(any(feature = "custom", feature = "serde"))]
+#[derive(Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct CustomTargetSpec {
+    arch: String,
+    target_pointer_width: PointerWidthValue,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    os: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    env: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    vendor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target_family: Option<FamilyValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target_endian: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    abi: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_atomic_width: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_atomic_width: Option<u8>,
+}
+
+/// Older spec files have a pointer width like "32", newer ones have 32.
+#[cfg(any(feature = "custom", feature = "serde"))]
+#[derive(Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[serde(untagged)]
+enum PointerWidthValue {
+    Number(u8),
+    String(String),
+}
+
+#[cfg(any(feature = "custom", feature = "serde"))]
+#[derive(Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[serde(untagged)]
+enum FamilyValue {
+    One(String),
+    Many(Vec<String>),
+}
+
+pub(crate) fn parse_value<T: FromStr>(
+    key: &'static str,
+    value: &str,
+) -> Result<T, CustomTargetError> {
+    value.parse().map_err(|_| CustomTargetError::UnknownValue {
+        key,
+        value: value.to_string(),
+    })
+}
+
+/// Parses a value where `none_value` (or a missing key) means there's no value, as with
+/// `"os": "none"` for bare-metal targets.
+pub(crate) fn parse_optional<T: FromStr>(
+    key: &'static str,
+    value: Option<&str>,
+    none_value: &str,
+) -> Result<Option<T>, CustomTargetError> {
+    match value {
+        None => Ok(None),
+        Some(value) if value.is_empty() || value == none_value => Ok(None),
+        Some(value) => parse_value(key, value).map(Some),
+    }
+}
+
+#[cfg(all(test, feature = "custom"))]
+mod tests {
+    use super::*;
+    use cfg_expr::targets::{Arch, Endian, Env, Family, Os, Vendor};
+
+    static THUMBV7EM: &str = r#"{
+        "llvm-target": "thumbv7em-none-eabi",
+        "data-layout": "e-m:e-p:32:32-Fi8-i64:64-v128:64:128-a:0:32-n32-S64",
+        "arch": "arm",
+        "target-endian": "little",
+        "target-pointer-width": "32",
+        "target-c-int-width": "32",
+        "os": "none",
+        "executables": true,
+        "linker-flavor": "ld.lld",
+        "panic-strategy": "abort"
+    }"#;
+
+    #[test]
+    fn test_bare_metal() {
+        let target = custom_target("thumbv7em-custom", THUMBV7EM).unwrap();
+        assert_eq!(target.abi, "");
+        assert_eq!(target.atomic_widths, AtomicWidths { min: 8, max: 32 });
+        assert_eq!(&*target.triple, "thumbv7em-custom");
+        let target_info = target.target_info;
+        assert_eq!(target_info.arch, Arch::arm);
+        assert_eq!(target_info.os, None);
+        assert_eq!(target_info.env, None);
+        assert_eq!(target_info.vendor, Some(Vendor::unknown));
+        assert_eq!(target_info.family, None);
+        assert_eq!(target_info.pointer_width, 32);
+        assert_eq!(target_info.endian, Endian::little);
+    }
+
+    #[test]
+    fn test_families() {
+        let json = r#"{
+            "arch": "x86_64",
+            "os": "linux",
+            "env": "musl",
+            "target-family": ["unix"],
+            "target-pointer-width": 64
+        }"#;
+        let target_info = custom_target("x86_64-custom-linux-musl", json)
+            .unwrap()
+            .target_info;
+        assert_eq!(target_info.os, Some(Os::linux));
+        assert_eq!(target_info.env, Some(Env::musl));
+        assert_eq!(target_info.family, Some(Family::unix));
+        assert_eq!(target_info.pointer_width, 64);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            custom_target("foo", "{}"),
+            Err(CustomTargetError::InvalidJson(_))
+        ));
+        assert_eq!(
+            custom_target("foo", r#"{ "arch": "z80", "target-pointer-width": "16" }"#).unwrap_err(),
+            CustomTargetError::UnknownValue {
+                key: "arch",
+                value: "z80".to_string()
+            },
+        );
+    }
+}
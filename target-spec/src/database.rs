@@ -0,0 +1,284 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{canonical_triple, InvalidRustcVersion, Platform, RustcVersion, TargetFeatures};
+use cfg_expr::targets::{rustc_version as builtin_rustc_version, ALL as ALL_TARGETS};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+/// Changes to the list of targets since the builtin one, in release order.
+///
+/// Only changes that affect how targets are spelled are tracked: triples added in later releases
+/// can also be evaluated through `TargetDatabase::from_target_list`.
+static TARGET_CHANGES: &[(RustcVersion, TargetChange)] = &[
+    (
+        RustcVersion::new(1, 49, 0),
+        TargetChange::Renamed("x86_64-linux-kernel", "x86_64-unknown-none-linuxkernel"),
+    ),
+    (
+        RustcVersion::new(1, 67, 0),
+        TargetChange::Renamed("aarch64-fuchsia", "aarch64-unknown-fuchsia"),
+    ),
+    (
+        RustcVersion::new(1, 67, 0),
+        TargetChange::Renamed("x86_64-fuchsia", "x86_64-unknown-fuchsia"),
+    ),
+    (
+        RustcVersion::new(1, 78, 0),
+        TargetChange::Added("wasm32-wasip1"),
+    ),
+    (
+        RustcVersion::new(1, 84, 0),
+        TargetChange::Removed("wasm32-wasi"),
+    ),
+];
+
+#[derive(Clone, Copy, Debug)]
+enum TargetChange {
+    Added(&'static str),
+    Removed(&'static str),
+    Renamed(&'static str, &'static str),
+}
+
+/// The list of targets known to a particular rustc release.
+///
+/// By default, `target-spec` evaluates specs against the targets bundled with it, which are the
+/// targets of a specific rustc release. A `TargetDatabase` selects the targets for the toolchain
+/// a project actually builds with instead, so that analyses over every target don't include
+/// targets that the toolchain doesn't know about, or miss ones it does.
+///
+/// Platforms returned by the database have their rustc version set, if it's known, so that
+/// `cfg(version("..."))` predicates are evaluated against it.
+///
+/// ## Examples
+///
+/// ```
+/// use target_spec::{TargetDatabase, TargetFeatures, TargetSpec};
+///
+/// let database = TargetDatabase::for_rustc("1.80.0").unwrap();
+/// assert!(database.contains("wasm32-wasip1"));
+/// assert!(!TargetDatabase::builtin().contains("wasm32-wasip1"));
+///
+/// let spec: TargetSpec = "cfg(target_os = \"wasi\")".parse().unwrap();
+/// let platforms = database.platforms(&TargetFeatures::Unknown);
+/// let summary = spec.eval_all(&platforms);
+/// // wasm32-wasi and wasm32-wasip1.
+/// assert_eq!(summary.matched_count(), 2);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TargetDatabase {
+    rustc_version: Option<RustcVersion>,
+    triples: BTreeSet<Cow<'static, str>>,
+}
+
+impl TargetDatabase {
+    /// Returns the targets bundled with `target-spec`.
+    pub fn builtin() -> Self {
+        Self {
+            rustc_version: builtin_rustc_version().parse().ok(),
+            triples: ALL_TARGETS
+                .iter()
+                .map(|target_info| Cow::Borrowed(target_info.triple))
+                .collect(),
+        }
+    }
+
+    /// Returns the targets known to this rustc release, such as `1.70.0`.
+    ///
+    /// This starts from the builtin targets, and applies the changes to target names made in
+    /// later releases, such as `wasm32-wasi` being renamed to `wasm32-wasip1`. For releases older
+    /// than the builtin targets, this is the same as `TargetDatabase::builtin`, except for the
+    /// rustc version.
+    ///
+    /// Returns an error if the version couldn't be parsed.
+    pub fn for_rustc(version: &str) -> Result<Self, InvalidRustcVersion> {
+        let version: RustcVersion = version.parse()?;
+        let mut database = Self::builtin();
+        for (changed_in, change) in TARGET_CHANGES {
+            if *changed_in > version {
+                break;
+            }
+            match change {
+                TargetChange::Added(triple) => {
+                    database.triples.insert(Cow::Borrowed(triple));
+                }
+                TargetChange::Removed(triple) => {
+                    database.triples.remove(*triple);
+                }
+                TargetChange::Renamed(old, new) => {
+                    database.triples.remove(*old);
+                    database.triples.insert(Cow::Borrowed(new));
+                }
+            }
+        }
+        database.rustc_version = Some(version);
+        Ok(database)
+    }
+
+    /// Creates a database from the output of `rustc --print target-list`, which lists one triple
+    /// per line.
+    ///
+    /// Triples that `target-spec` doesn't know, such as tier 3 targets added to rustc later, are
+    /// evaluated by guessing their properties: see `Platform::new_heuristic`.
+    pub fn from_target_list(output: &str, rustc_version: Option<RustcVersion>) -> Self {
+        Self {
+            rustc_version,
+            triples: output
+                .lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .map(|triple| Cow::Owned(triple.into()))
+                .collect(),
+        }
+    }
+
+    /// Returns the rustc version this database is for, if it's known.
+    pub fn rustc_version(&self) -> Option<RustcVersion> {
+        self.rustc_version
+    }
+
+    /// Returns the triples in this database, in sorted order.
+    pub fn triples(&self) -> impl Iterator<Item = &str> + '_ {
+        self.triples.iter().map(|triple| triple.as_ref())
+    }
+
+    /// Returns the number of triples in this database.
+    pub fn len(&self) -> usize {
+        self.triples.len()
+    }
+
+    /// Returns true if this database has no triples.
+    pub fn is_empty(&self) -> bool {
+        self.triples.is_empty()
+    }
+
+    /// Returns true if this database has this triple.
+    ///
+    /// Triples are matched as they're spelled, so for example a database for a rustc release
+    /// that calls a target `wasm32-wasip1` doesn't contain `wasm32-wasi`.
+    pub fn contains(&self, triple: impl AsRef<str>) -> bool {
+        self.triples.contains(triple.as_ref())
+    }
+
+    /// Returns true if every triple in this database is known to `target-spec`, either as a builtin
+    /// triple or as another spelling of one.
+    pub fn is_fully_known(&self) -> bool {
+        self.triples()
+            .all(|triple| canonical_triple(triple).is_some())
+    }
+
+    /// Creates a platform for this triple, or returns `None` if it isn't in this database.
+    pub fn platform<'a>(
+        &self,
+        triple: impl AsRef<str>,
+        target_features: TargetFeatures<'a>,
+    ) -> Option<Platform<'a>> {
+        let triple = triple.as_ref();
+        if !self.contains(triple) {
+            return None;
+        }
+        Some(self.make_platform(triple, target_features))
+    }
+
+    /// Creates a platform for each triple in this database, in sorted order, with these target
+    /// features.
+    ///
+    /// The result can be passed into `TargetSpec::eval_all`.
+    pub fn platforms<'a>(&self, target_features: &TargetFeatures<'a>) -> Vec<Platform<'a>> {
+        self.triples()
+            .map(|triple| self.make_platform(triple, target_features.clone()))
+            .collect()
+    }
+
+    // ---
+    // Helper methods
+    // ---
+
+    fn make_platform<'a>(&self, triple: &str, target_features: TargetFeatures<'a>) -> Platform<'a> {
+        let mut platform = Platform::new_heuristic(triple, target_features);
+        platform.set_rustc_version(self.rustc_version);
+        platform
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_rustc() {
+        let builtin = TargetDatabase::builtin();
+        assert_eq!(builtin.rustc_version(), Some(RustcVersion::new(1, 41, 0)));
+        assert_eq!(builtin.len(), ALL_TARGETS.len());
+        assert!(builtin.is_fully_known());
+
+        let old = TargetDatabase::for_rustc("1.40.0").unwrap();
+        assert_eq!(
+            old.triples, builtin.triples,
+            "older releases use the builtin list"
+        );
+
+        for (version, has_wasi, has_wasip1, has_fuchsia) in &[
+            ("1.41.0", true, false, true),
+            ("1.67.0", true, false, false),
+            ("1.78.0", true, true, false),
+            ("1.84.1", false, true, false),
+        ] {
+            let database = TargetDatabase::for_rustc(version).unwrap();
+            assert_eq!(database.contains("wasm32-wasi"), *has_wasi, "{}", version);
+            assert_eq!(
+                database.contains("wasm32-wasip1"),
+                *has_wasip1,
+                "{}",
+                version
+            );
+            assert_eq!(
+                database.contains("x86_64-fuchsia"),
+                *has_fuchsia,
+                "{}",
+                version
+            );
+            assert_eq!(
+                database.contains("x86_64-unknown-fuchsia"),
+                !*has_fuchsia,
+                "{}",
+                version
+            );
+            assert!(database.is_fully_known(), "{}", version);
+        }
+
+        let database = TargetDatabase::for_rustc("1.84.0").unwrap();
+        let platform = database
+            .platform("wasm32-wasip1", TargetFeatures::Unknown)
+            .unwrap();
+        assert_eq!(platform.triple(), "wasm32-wasi");
+        assert!(!platform.is_heuristic());
+        assert_eq!(platform.rustc_version(), Some(RustcVersion::new(1, 84, 0)));
+        assert!(database
+            .platform("wasm32-wasi", TargetFeatures::Unknown)
+            .is_none());
+
+        assert!(TargetDatabase::for_rustc("latest").is_err());
+    }
+
+    #[test]
+    fn test_from_target_list() {
+        let output = "aarch64-apple-darwin\nx86_64-unknown-linux-gnu\n\n  wasm32-wasip1\n";
+        let database = TargetDatabase::from_target_list(output, None);
+        assert_eq!(
+            database.triples().collect::<Vec<_>>(),
+            vec![
+                "aarch64-apple-darwin",
+                "wasm32-wasip1",
+                "x86_64-unknown-linux-gnu"
+            ]
+        );
+        assert!(!database.is_fully_known());
+
+        let platforms = database.platforms(&TargetFeatures::Unknown);
+        assert_eq!(platforms[0].triple(), "aarch64-apple-darwin");
+        assert!(platforms[0].is_heuristic());
+        assert!(!platforms[2].is_heuristic());
+        assert_eq!(platforms[2].rustc_version(), None);
+    }
+}
@@ -3,11 +3,14 @@
 
 use crate::parser::ParseError;
 use crate::platform::{Platform, TargetFeatures};
-use crate::Target;
+use crate::rewrite::{ABI_KEY, HAS_ATOMIC_KEY, VERSION_KEY, WASM_FAMILY_KEY};
 use crate::TargetSpec;
+use crate::{RustcVersion, Target};
+use cfg_expr::expr::{Logic, TargetPredicate};
 use cfg_expr::{Expression, Predicate};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::sync::Arc;
-use std::{error, fmt};
 
 /// An error that occurred during target evaluation.
 #[derive(PartialEq)]
@@ -34,8 +37,8 @@ impl fmt::Debug for EvalError {
     }
 }
 
-impl error::Error for EvalError {
-    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+impl std::error::Error for EvalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             EvalError::InvalidSpec(err) => Some(err),
             EvalError::PlatformNotFound => None,
@@ -43,6 +46,486 @@ impl error::Error for EvalError {
     }
 }
 
+/// The result of evaluating a `TargetSpec` against a platform.
+///
+/// Returned by `TargetSpec::eval_result`. Unlike the `Option<bool>` returned by
+/// `TargetSpec::eval`, an unknown result records why it is unknown. Use `From` to convert to and
+/// from `Option<bool>` and `bool`.
+///
+/// ## Examples
+///
+/// ```
+/// use target_spec::{EvalResult, Platform, TargetFeatures, TargetSpec};
+///
+/// let spec: TargetSpec = "cfg(all(unix, target_feature = \"sse2\"))".parse().unwrap();
+/// let linux = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+/// let windows = Platform::new("x86_64-pc-windows-msvc", TargetFeatures::Unknown).unwrap();
+///
+/// let result = spec.eval_result(&linux);
+/// let reason = result.unknown_reason().expect("target features are unknown");
+/// assert_eq!(reason.target_features(), &["sse2"]);
+/// assert_eq!(Option::<bool>::from(result), None);
+///
+/// // The target feature doesn't matter on Windows.
+/// assert_eq!(spec.eval_result(&windows), EvalResult::False);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EvalResult {
+    /// The spec matches the platform.
+    True,
+    /// The spec doesn't match the platform.
+    False,
+    /// Whether the spec matches the platform is unknown.
+    Unknown(UnknownReason),
+}
+
+impl EvalResult {
+    /// Returns true if the spec matches the platform.
+    pub fn is_true(&self) -> bool {
+        *self == EvalResult::True
+    }
+
+    /// Returns true if the spec doesn't match the platform.
+    pub fn is_false(&self) -> bool {
+        *self == EvalResult::False
+    }
+
+    /// Returns true if whether the spec matches the platform is unknown.
+    pub fn is_unknown(&self) -> bool {
+        !self.is_true() && !self.is_false()
+    }
+
+    /// Returns the reason the result is unknown, or `None` if it is known.
+    pub fn unknown_reason(&self) -> Option<&UnknownReason> {
+        match self {
+            EvalResult::Unknown(reason) => Some(reason),
+            EvalResult::True | EvalResult::False => None,
+        }
+    }
+}
+
+impl From<bool> for EvalResult {
+    fn from(value: bool) -> Self {
+        if value {
+            EvalResult::True
+        } else {
+            EvalResult::False
+        }
+    }
+}
+
+impl From<EvalResult> for Option<bool> {
+    fn from(result: EvalResult) -> Self {
+        match result {
+            EvalResult::True => Some(true),
+            EvalResult::False => Some(false),
+            EvalResult::Unknown(_) => None,
+        }
+    }
+}
+
+/// Converts `None` to an unknown result without a recorded reason.
+impl From<Option<bool>> for EvalResult {
+    fn from(value: Option<bool>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => EvalResult::Unknown(UnknownReason::default()),
+        }
+    }
+}
+
+impl fmt::Display for EvalResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalResult::True => write!(f, "true"),
+            EvalResult::False => write!(f, "false"),
+            EvalResult::Unknown(reason) => write!(f, "unknown ({})", reason),
+        }
+    }
+}
+
+// Unknown results combine the same way as with `Option<bool>`, while also collecting the reasons
+// of the operands that made the result unknown.
+impl Logic for EvalResult {
+    fn top() -> Self {
+        EvalResult::True
+    }
+
+    fn bottom() -> Self {
+        EvalResult::False
+    }
+
+    fn and(self, other: Self) -> Self {
+        match (self, other) {
+            (EvalResult::False, _) | (_, EvalResult::False) => EvalResult::False,
+            (EvalResult::Unknown(a), EvalResult::Unknown(b)) => EvalResult::Unknown(a.merge(b)),
+            (EvalResult::Unknown(reason), EvalResult::True)
+            | (EvalResult::True, EvalResult::Unknown(reason)) => EvalResult::Unknown(reason),
+            (EvalResult::True, EvalResult::True) => EvalResult::True,
+        }
+    }
+
+    fn or(self, other: Self) -> Self {
+        match (self, other) {
+            (EvalResult::True, _) | (_, EvalResult::True) => EvalResult::True,
+            (EvalResult::Unknown(a), EvalResult::Unknown(b)) => EvalResult::Unknown(a.merge(b)),
+            (EvalResult::Unknown(reason), EvalResult::False)
+            | (EvalResult::False, EvalResult::Unknown(reason)) => EvalResult::Unknown(reason),
+            (EvalResult::False, EvalResult::False) => EvalResult::False,
+        }
+    }
+
+    fn not(self) -> Self {
+        match self {
+            EvalResult::True => EvalResult::False,
+            EvalResult::False => EvalResult::True,
+            unknown @ EvalResult::Unknown(_) => unknown,
+        }
+    }
+}
+
+/// Options for evaluating `TargetSpec`s.
+///
+/// By default, specs are evaluated the way Cargo evaluates them: flags that weren't added to the
+/// platform and `feature = "..."` predicates are false. Analyses that need to consider what could
+/// happen under other configurations can choose other policies for these.
+///
+/// ## Examples
+///
+/// ```
+/// use target_spec::{EvalOptions, EvalResult, Platform, TargetFeatures, TargetSpec, UnknownPolicy};
+///
+/// let spec: TargetSpec = "cfg(any(windows, tokio_unstable))".parse().unwrap();
+/// let linux = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+/// assert_eq!(spec.eval(&linux), Some(false));
+///
+/// // A worst-case analysis assumes that any flag might be set.
+/// let mut options = EvalOptions::new();
+/// options.set_unknown_flags(UnknownPolicy::True);
+/// assert_eq!(spec.eval_with_options(&linux, &options), EvalResult::True);
+///
+/// options.set_unknown_flags(UnknownPolicy::Unknown);
+/// let result = spec.eval_with_options(&linux, &options);
+/// assert_eq!(result.unknown_reason().unwrap().flags(), &["tokio_unstable"]);
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct EvalOptions {
+    unknown_flags: UnknownPolicy,
+    features: UnknownPolicy,
+}
+
+impl EvalOptions {
+    /// Creates a new `EvalOptions` with the default policies, which match Cargo's behavior.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the policy for flags that weren't added to the platform with `Platform::add_flags`.
+    ///
+    /// This also applies to `test`, `debug_assertions` and `proc_macro`.
+    pub fn set_unknown_flags(&mut self, policy: UnknownPolicy) -> &mut Self {
+        self.unknown_flags = policy;
+        self
+    }
+
+    /// Returns the policy for flags that weren't added to the platform.
+    pub fn unknown_flags(&self) -> UnknownPolicy {
+        self.unknown_flags
+    }
+
+    /// Sets the policy for `feature = "..."` predicates.
+    pub fn set_features(&mut self, policy: UnknownPolicy) -> &mut Self {
+        self.features = policy;
+        self
+    }
+
+    /// Returns the policy for `feature = "..."` predicates.
+    pub fn features(&self) -> UnknownPolicy {
+        self.features
+    }
+}
+
+/// How predicates whose values aren't known are evaluated.
+///
+/// Used by `EvalOptions`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum UnknownPolicy {
+    /// Evaluate these predicates to false. This is the default, and what Cargo does.
+    #[default]
+    False,
+    /// Evaluate these predicates to true.
+    True,
+    /// Treat the values of these predicates as unknown, recording them in the `UnknownReason`.
+    Unknown,
+}
+
+impl UnknownPolicy {
+    fn apply(self, reason: impl FnOnce() -> UnknownReason) -> EvalResult {
+        match self {
+            UnknownPolicy::False => EvalResult::False,
+            UnknownPolicy::True => EvalResult::True,
+            UnknownPolicy::Unknown => EvalResult::Unknown(reason()),
+        }
+    }
+}
+
+/// Why the result of evaluating a `TargetSpec` is unknown.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct UnknownReason {
+    // Both sorted and deduplicated.
+    target_features: Vec<String>,
+    target_properties: Vec<String>,
+    flags: Vec<String>,
+    features: Vec<String>,
+    rustc_version: bool,
+}
+
+impl UnknownReason {
+    fn target_feature(feature: &str) -> Self {
+        Self {
+            target_features: vec![feature.to_string()],
+            ..Self::default()
+        }
+    }
+
+    fn target_property(key: &str) -> Self {
+        Self {
+            target_properties: vec![key.to_string()],
+            ..Self::default()
+        }
+    }
+
+    fn flag(flag: &str) -> Self {
+        Self {
+            flags: vec![flag.to_string()],
+            ..Self::default()
+        }
+    }
+
+    fn feature(feature: &str) -> Self {
+        Self {
+            features: vec![feature.to_string()],
+            ..Self::default()
+        }
+    }
+
+    fn rustc_version_unknown() -> Self {
+        Self {
+            rustc_version: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the target features that were checked by the spec, but that aren't known for the
+    /// platform, in sorted order.
+    ///
+    /// This is empty if the reason wasn't recorded, e.g. for results converted from
+    /// `Option<bool>`.
+    pub fn target_features(&self) -> &[String] {
+        &self.target_features
+    }
+
+    /// Returns the `cfg()` keys, such as `target_os`, that were checked by the spec but couldn't be
+    /// determined for the platform, in sorted order.
+    ///
+    /// This is only ever non-empty for platforms created with `Platform::new_heuristic`. The
+    /// `unix` and `windows` shorthands are reported as `target_family`.
+    pub fn target_properties(&self) -> &[String] {
+        &self.target_properties
+    }
+
+    /// Returns the flags that were checked by the spec but weren't added to the platform, in
+    /// sorted order.
+    ///
+    /// This is only ever non-empty if the spec was evaluated with `UnknownPolicy::Unknown` for
+    /// unknown flags.
+    pub fn flags(&self) -> &[String] {
+        &self.flags
+    }
+
+    /// Returns the Cargo features checked by the spec with `feature = "..."`, in sorted order.
+    ///
+    /// This is only ever non-empty if the spec was evaluated with `UnknownPolicy::Unknown` for
+    /// features.
+    pub fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    /// Returns true if the spec checked the rustc version with a `version()` predicate, but the
+    /// platform doesn't have one set.
+    pub fn rustc_version(&self) -> bool {
+        self.rustc_version
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        self.rustc_version |= other.rustc_version;
+        for (values, other_values) in &mut [
+            (&mut self.target_features, other.target_features),
+            (&mut self.target_properties, other.target_properties),
+            (&mut self.flags, other.flags),
+            (&mut self.features, other.features),
+        ] {
+            values.append(other_values);
+            values.sort_unstable();
+            values.dedup();
+        }
+        self
+    }
+}
+
+impl fmt::Display for UnknownReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        if !self.target_features.is_empty() {
+            parts.push(format!(
+                "target features not known: {}",
+                self.target_features.join(", ")
+            ));
+        }
+        if !self.target_properties.is_empty() {
+            parts.push(format!(
+                "target properties not known: {}",
+                self.target_properties.join(", ")
+            ));
+        }
+        if !self.flags.is_empty() {
+            parts.push(format!("flags not known: {}", self.flags.join(", ")));
+        }
+        if !self.features.is_empty() {
+            parts.push(format!("features not known: {}", self.features.join(", ")));
+        }
+        if self.rustc_version {
+            parts.push("rustc version not known".to_string());
+        }
+        if parts.is_empty() {
+            write!(f, "reason not recorded")
+        } else {
+            write!(f, "{}", parts.join("; "))
+        }
+    }
+}
+
+/// The results of evaluating a spec against a set of platforms.
+///
+/// Returned by `TargetSpec::eval_all`. Results are stored as bitsets, indexed by the position of
+/// each platform in the slice that was passed in.
+///
+/// ## Examples
+///
+/// ```
+/// use target_spec::{Platform, TargetFeatures, TargetSpec};
+///
+/// let platforms: Vec<_> = ["x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc", "x86_64-apple-darwin"]
+///     .iter()
+///     .map(|triple| Platform::new(triple, TargetFeatures::Unknown).unwrap())
+///     .collect();
+///
+/// let spec: TargetSpec = "cfg(unix)".parse().unwrap();
+/// let summary = spec.eval_all(&platforms);
+/// assert_eq!(summary.matched().collect::<Vec<_>>(), vec![0, 2]);
+/// assert_eq!(summary.result(1), Some(false));
+///
+/// let spec: TargetSpec = "cfg(target_feature = \"sse2\")".parse().unwrap();
+/// assert_eq!(spec.eval_all(&platforms).unknown_count(), 3);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EvalSummary {
+    len: usize,
+    matched: Vec<u64>,
+    unknown: Vec<u64>,
+}
+
+impl EvalSummary {
+    fn new(len: usize) -> Self {
+        let words = len.div_ceil(64);
+        Self {
+            len,
+            matched: vec![0; words],
+            unknown: vec![0; words],
+        }
+    }
+
+    fn set(&mut self, index: usize, result: Option<bool>) {
+        let (word, bit) = (index / 64, 1 << (index % 64));
+        match result {
+            Some(true) => self.matched[word] |= bit,
+            Some(false) => {}
+            None => self.unknown[word] |= bit,
+        }
+    }
+
+    /// Returns the number of platforms the spec was evaluated against.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the spec wasn't evaluated against any platforms.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the result for the platform at this index, as `TargetSpec::eval` would.
+    ///
+    /// Panics if the index is out of range.
+    pub fn result(&self, index: usize) -> Option<bool> {
+        assert!(
+            index < self.len,
+            "index {} out of range for {} platforms",
+            index,
+            self.len
+        );
+        let (word, bit) = (index / 64, 1 << (index % 64));
+        if self.unknown[word] & bit != 0 {
+            None
+        } else {
+            Some(self.matched[word] & bit != 0)
+        }
+    }
+
+    /// Returns the indexes of the platforms the spec matched, in increasing order.
+    pub fn matched(&self) -> impl Iterator<Item = usize> + '_ {
+        bits(&self.matched)
+    }
+
+    /// Returns the indexes of the platforms for which the result is unknown, in increasing order.
+    pub fn unknown(&self) -> impl Iterator<Item = usize> + '_ {
+        bits(&self.unknown)
+    }
+
+    /// Returns the number of platforms the spec matched.
+    pub fn matched_count(&self) -> usize {
+        count_bits(&self.matched)
+    }
+
+    /// Returns the number of platforms for which the result is unknown.
+    pub fn unknown_count(&self) -> usize {
+        count_bits(&self.unknown)
+    }
+
+    /// Returns true if the spec matched at least one platform.
+    pub fn any_matched(&self) -> bool {
+        self.matched.iter().any(|word| *word != 0)
+    }
+
+    /// Returns true if the spec matched every platform, or if there are no platforms.
+    pub fn all_matched(&self) -> bool {
+        self.matched_count() == self.len
+    }
+}
+
+fn bits(words: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    words.iter().enumerate().flat_map(|(word_index, word)| {
+        let word = *word;
+        (0..64)
+            .filter(move |bit| word & (1 << bit) != 0)
+            .map(move |bit| word_index * 64 + bit)
+    })
+}
+
+fn count_bits(words: &[u64]) -> usize {
+    words.iter().map(|word| word.count_ones() as usize).sum()
+}
+
 /// Evaluates the given spec against the provided target and returns `Some(true)` on a successful
 /// match, and `Some(false)` on a failing match.
 ///
@@ -62,37 +545,162 @@ pub fn eval(spec_or_triple: &str, platform: &str) -> Result<Option<bool>, EvalEr
     }
 }
 
-pub(crate) fn eval_target(target: &Target, platform: &Platform<'_>) -> Option<bool> {
+pub(crate) fn eval_target(
+    target: &Target,
+    platform: &Platform<'_>,
+    options: &EvalOptions,
+) -> EvalResult {
     match target {
-        Target::TargetInfo(ref target_info) => Some(platform.triple() == target_info.triple),
-        Target::Spec(ref expr) => eval_expr(expr, platform),
+        Target::Builtin(ref target_info) => (platform.triple() == target_info.triple).into(),
+        Target::Custom(ref triple) => (platform.triple() == &**triple).into(),
+        Target::Spec(ref expr) => eval_expr(expr, platform, options),
     }
 }
 
-fn eval_expr(spec: &Arc<Expression>, platform: &Platform<'_>) -> Option<bool> {
-    spec.eval(|pred| {
-        match pred {
-            Predicate::Target(target) => Some(target.matches(platform.target_info())),
-            Predicate::TargetFeature(feature) => platform.target_features().matches(feature),
-            Predicate::Test | Predicate::DebugAssertions | Predicate::ProcMacro => {
-                // Known families that always evaluate to false. See
-                // https://docs.rs/cargo-platform/0.1.1/src/cargo_platform/lib.rs.html#76.
-                Some(false)
+pub(crate) fn eval_target_all(
+    target: &Target,
+    platforms: &[Platform<'_>],
+    options: &EvalOptions,
+) -> EvalSummary {
+    let mut summary = EvalSummary::new(platforms.len());
+    let expr = match target {
+        Target::Builtin(_) | Target::Custom(_) => {
+            for (index, platform) in platforms.iter().enumerate() {
+                summary.set(index, eval_target(target, platform, options).into());
             }
-            Predicate::Feature(_) => {
-                // NOTE: This is not supported by Cargo which always evaluates this to false. See
-                // https://github.com/rust-lang/cargo/issues/7442 for more details.
-                Some(false)
+            return summary;
+        }
+        Target::Spec(expr) => expr,
+    };
+
+    // Each distinct predicate is looked up once per platform. Platforms for which every predicate
+    // has the same value share the result of evaluating the expression.
+    let mut predicates: Vec<Predicate<'_>> = vec![];
+    let slots: Vec<usize> = expr
+        .predicates()
+        .map(
+            |pred| match predicates.iter().position(|existing| *existing == pred) {
+                Some(slot) => slot,
+                None => {
+                    predicates.push(pred);
+                    predicates.len() - 1
+                }
+            },
+        )
+        .collect();
+    let mut cache: BTreeMap<Vec<Option<bool>>, Option<bool>> = BTreeMap::new();
+    for (index, platform) in platforms.iter().enumerate() {
+        let values: Vec<Option<bool>> = predicates
+            .iter()
+            .map(|pred| eval_predicate(pred, platform, options).into())
+            .collect();
+        let result = match cache.get(&values) {
+            Some(result) => *result,
+            None => {
+                let mut slots = slots.iter();
+                let result: EvalResult = expr.eval(|_| {
+                    let slot = slots.next().expect("predicates are evaluated in order");
+                    values[*slot].into()
+                });
+                let result = result.into();
+                cache.insert(values, result);
+                result
+            }
+        };
+        summary.set(index, result);
+    }
+    summary
+}
+
+fn eval_expr(spec: &Arc<Expression>, platform: &Platform<'_>, options: &EvalOptions) -> EvalResult {
+    spec.eval(|pred| eval_predicate(pred, platform, options))
+}
+
+fn eval_predicate(
+    pred: &Predicate<'_>,
+    platform: &Platform<'_>,
+    options: &EvalOptions,
+) -> EvalResult {
+    let result = match pred {
+        Predicate::Target(target) => match platform.matches_target(*target) {
+            Some(matches) => Some(matches),
+            None => return unknown_property(target_key(target)),
+        },
+        Predicate::TargetFeature(feature) => match platform.target_features().matches(feature) {
+            Some(matches) => Some(matches),
+            None => return EvalResult::Unknown(UnknownReason::target_feature(feature)),
+        },
+        // Cargo warns about these in target specs, since it evaluates them against rustc's
+        // cfg output rather than the build profile. See
+        // https://docs.rs/cargo-platform/0.1.1/src/cargo_platform/lib.rs.html#76.
+        //
+        // They're false unless the platform was created from rustc's cfg output and rustc
+        // reported them, or the options say otherwise.
+        Predicate::Test => return eval_flag("test", platform, options),
+        Predicate::DebugAssertions => return eval_flag("debug_assertions", platform, options),
+        Predicate::ProcMacro => return eval_flag("proc_macro", platform, options),
+        Predicate::Feature(feature) => {
+            // NOTE: This is not supported by Cargo which always evaluates this to false. See
+            // https://github.com/rust-lang/cargo/issues/7442 for more details.
+            return options.features.apply(|| UnknownReason::feature(feature));
+        }
+        Predicate::Flag(flag) => {
+            // This returns false by default but true in some cases.
+            return eval_flag(flag, platform, options);
+        }
+        Predicate::KeyValue { key, val } if *key == ABI_KEY => match platform.abi_if_known() {
+            Some(abi) => Some(abi == *val),
+            None => return unknown_property("target_abi"),
+        },
+        Predicate::KeyValue { key, val } if *key == HAS_ATOMIC_KEY => {
+            match platform.has_atomic_if_known(val) {
+                Some(matches) => Some(matches),
+                None => return unknown_property("target_has_atomic"),
             }
-            Predicate::Flag(flag) => {
-                // This returns false by default but true in some cases.
-                Some(platform.has_flag(flag))
+        }
+        Predicate::KeyValue { key, .. } if *key == WASM_FAMILY_KEY => {
+            match platform.is_wasm_if_known() {
+                Some(is_wasm) => Some(is_wasm),
+                None => return unknown_property("target_family"),
             }
-            Predicate::KeyValue { .. } => {
-                unreachable!("these predicates are disallowed at TargetSpec construction time")
+        }
+        Predicate::KeyValue { key, val } if *key == VERSION_KEY => match platform.rustc_version() {
+            Some(version) => {
+                let required = RustcVersion::parse_predicate(val)
+                    .expect("versions are checked at TargetSpec construction time");
+                Some(version >= required)
             }
+            None => return EvalResult::Unknown(UnknownReason::rustc_version_unknown()),
+        },
+        Predicate::KeyValue { .. } => {
+            unreachable!("these predicates are disallowed at TargetSpec construction time")
         }
-    })
+    };
+    result.into()
+}
+
+fn eval_flag(flag: &str, platform: &Platform<'_>, options: &EvalOptions) -> EvalResult {
+    if platform.has_flag(flag) {
+        EvalResult::True
+    } else {
+        options.unknown_flags.apply(|| UnknownReason::flag(flag))
+    }
+}
+
+fn unknown_property(key: &str) -> EvalResult {
+    EvalResult::Unknown(UnknownReason::target_property(key))
+}
+
+fn target_key(predicate: &TargetPredicate) -> &'static str {
+    match predicate {
+        TargetPredicate::Arch(_) => "target_arch",
+        TargetPredicate::Endian(_) => "target_endian",
+        TargetPredicate::Env(_) => "target_env",
+        TargetPredicate::Family(_) => "target_family",
+        TargetPredicate::Os(_) => "target_os",
+        TargetPredicate::PointerWidth(_) => "target_pointer_width",
+        TargetPredicate::Vendor(_) => "target_vendor",
+    }
 }
 
 #[cfg(test)]
@@ -204,6 +812,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_eval_options() {
+        let mut platform =
+            Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+        platform.add_flags(&["foo"]);
+        let spec: TargetSpec = "cfg(all(foo, any(bar, test, feature = \"baz\")))"
+            .parse()
+            .unwrap();
+
+        let mut options = EvalOptions::new();
+        assert_eq!(
+            spec.eval_with_options(&platform, &options),
+            EvalResult::False
+        );
+        assert_eq!(spec.eval_result(&platform), EvalResult::False);
+
+        options.set_unknown_flags(UnknownPolicy::True);
+        assert_eq!(
+            spec.eval_with_options(&platform, &options),
+            EvalResult::True
+        );
+
+        options.set_unknown_flags(UnknownPolicy::Unknown);
+        let result = spec.eval_with_options(&platform, &options);
+        let reason = result.unknown_reason().expect("flags are unknown");
+        assert_eq!(reason.flags(), &["bar", "test"]);
+        assert!(reason.features().is_empty());
+        assert_eq!(
+            reason.to_string(),
+            "flags not known: bar, test",
+            "flags that were added aren't unknown"
+        );
+
+        options
+            .set_unknown_flags(UnknownPolicy::False)
+            .set_features(UnknownPolicy::Unknown);
+        let result = spec.eval_with_options(&platform, &options);
+        assert_eq!(result.unknown_reason().unwrap().features(), &["baz"]);
+
+        options.set_features(UnknownPolicy::True);
+        assert_eq!(
+            spec.eval_with_options(&platform, &options),
+            EvalResult::True
+        );
+        let summary = spec.eval_all_with_options(&[platform.clone()], &options);
+        assert_eq!(summary.matched_count(), 1);
+
+        // Features are true, so only the flag outside any() makes the result unknown.
+        let plain = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+        options.set_unknown_flags(UnknownPolicy::Unknown);
+        let result = spec.eval_with_options(&plain, &options);
+        assert_eq!(result.unknown_reason().unwrap().flags(), &["foo"]);
+    }
+
     #[test]
     fn test_target_feature() {
         // target features are unknown by default.
@@ -286,4 +948,396 @@ mod tests {
             Some(false),
         );
     }
+
+    #[test]
+    fn test_target_abi() {
+        let spec = "cfg(all(target_arch = \"arm\", target_abi = \"eabihf\"))";
+        assert_eq!(eval(spec, "thumbv7em-none-eabihf"), Ok(Some(true)));
+        assert_eq!(eval(spec, "arm-unknown-linux-gnueabihf"), Ok(Some(true)));
+        assert_eq!(eval(spec, "thumbv7em-none-eabi"), Ok(Some(false)));
+        assert_eq!(
+            eval("cfg(target_abi = \"\")", "x86_64-unknown-linux-gnu"),
+            Ok(Some(true))
+        );
+        assert_eq!(
+            eval(
+                "cfg(not(target_abi = \"x32\"))",
+                "x86_64-unknown-linux-gnux32"
+            ),
+            Ok(Some(false))
+        );
+
+        #[cfg(feature = "custom")]
+        {
+            let json = r#"{ "arch": "arm", "target-pointer-width": "32", "abi": "eabihf" }"#;
+            let platform =
+                Platform::from_json("armv7-custom", json, TargetFeatures::Unknown).unwrap();
+            assert_eq!(platform.abi(), "eabihf");
+            let spec: TargetSpec = "cfg(target_abi = \"eabihf\")".parse().unwrap();
+            assert_eq!(spec.eval(&platform), Some(true));
+        }
+    }
+
+    #[test]
+    fn test_target_has_atomic() {
+        let spec = "cfg(target_has_atomic = \"64\")";
+        assert_eq!(eval(spec, "x86_64-unknown-linux-gnu"), Ok(Some(true)));
+        assert_eq!(eval(spec, "i686-unknown-linux-gnu"), Ok(Some(true)));
+        assert_eq!(eval(spec, "mips-unknown-linux-gnu"), Ok(Some(false)));
+        assert_eq!(eval(spec, "thumbv6m-none-eabi"), Ok(Some(false)));
+        assert_eq!(
+            eval(
+                "cfg(target_has_atomic = \"128\")",
+                "aarch64-unknown-linux-gnu"
+            ),
+            Ok(Some(true))
+        );
+        assert_eq!(
+            eval("cfg(target_has_atomic = \"ptr\")", "thumbv7m-none-eabi"),
+            Ok(Some(true))
+        );
+        assert_eq!(
+            eval(
+                "cfg(not(target_has_atomic = \"ptr\"))",
+                "riscv32imc-unknown-none-elf"
+            ),
+            Ok(Some(true))
+        );
+
+        #[cfg(feature = "custom")]
+        {
+            let json = r#"{ "arch": "arm", "target-pointer-width": "32", "max-atomic-width": 64 }"#;
+            let platform =
+                Platform::from_json("armv7-custom", json, TargetFeatures::Unknown).unwrap();
+            assert!(platform.has_atomic("64"));
+            assert!(!platform.has_atomic("128"));
+            let json = r#"{ "arch": "arm", "target-pointer-width": "32", "max-atomic-width": 0 }"#;
+            let platform =
+                Platform::from_json("thumbv6m-custom", json, TargetFeatures::Unknown).unwrap();
+            assert!(!platform.has_atomic("8"));
+        }
+    }
+
+    #[test]
+    fn test_wasm_family() {
+        let wasm_family: TargetSpec = "cfg(target_family = \"wasm\")".parse().unwrap();
+        let browser: TargetSpec = "cfg(all(target_family = \"wasm\", target_os = \"unknown\"))"
+            .parse()
+            .unwrap();
+        let wasi: TargetSpec = "cfg(target_os = \"wasi\")".parse().unwrap();
+
+        for (triple, is_wasm, is_browser, is_wasi) in &[
+            ("wasm32-unknown-unknown", true, true, false),
+            ("wasm32-wasi", true, false, true),
+            ("wasm32-wasip1", true, false, true),
+            ("wasm32-wasip2", true, false, true),
+            ("wasm32-unknown-emscripten", true, false, false),
+            ("x86_64-unknown-linux-gnu", false, false, false),
+        ] {
+            let platform = Platform::new_heuristic(triple, TargetFeatures::Unknown);
+            assert_eq!(platform.is_wasm(), *is_wasm, "{}", triple);
+            assert_eq!(wasm_family.eval(&platform), Some(*is_wasm), "{}", triple);
+            assert_eq!(browser.eval(&platform), Some(*is_browser), "{}", triple);
+            assert_eq!(wasi.eval(&platform), Some(*is_wasi), "{}", triple);
+            assert_eq!(platform.wasi_version().is_some(), *is_wasi, "{}", triple);
+        }
+
+        let emscripten =
+            Platform::new("wasm32-unknown-emscripten", TargetFeatures::Unknown).unwrap();
+        let unix_wasm: TargetSpec = "cfg(all(unix, target_family = \"wasm\"))".parse().unwrap();
+        assert_eq!(
+            unix_wasm.eval(&emscripten),
+            Some(true),
+            "emscripten is in both families"
+        );
+
+        // The wasm family is known from the architecture alone.
+        let wasm64 = Platform::new_heuristic("wasm64-unknown-unknown", TargetFeatures::Unknown);
+        assert_eq!(wasm_family.eval(&wasm64), Some(true));
+        let unknown = Platform::new_heuristic("bogus-unknown-unknown", TargetFeatures::Unknown);
+        let result = wasm_family.eval_result(&unknown);
+        assert_eq!(
+            result.unknown_reason().unwrap().target_properties(),
+            &["target_family"]
+        );
+
+        let not_wasm: TargetSpec = "cfg(all(target_family = \"wasm\", target_arch = \"x86_64\"))"
+            .parse()
+            .unwrap();
+        assert!(not_wasm.implies(&"cfg(any())".parse().unwrap()));
+        assert!(wasi.is_disjoint(&browser));
+    }
+
+    #[test]
+    fn test_rustc_version() {
+        let spec: TargetSpec = "cfg(all(unix, version(\"1.50\")))".parse().unwrap();
+        let mut platform =
+            Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+        let result = spec.eval_result(&platform);
+        assert!(result.unknown_reason().unwrap().rustc_version());
+        assert_eq!(result.to_string(), "unknown (rustc version not known)");
+
+        for (version, expected) in &[
+            ("1.49.9", false),
+            ("1.50.0", true),
+            ("1.50.0-nightly", true),
+            ("2.0.0", true),
+        ] {
+            platform.set_rustc_version(Some(version.parse().unwrap()));
+            assert_eq!(spec.eval(&platform), Some(*expected), "rustc {}", version);
+        }
+        let windows = Platform::new("x86_64-pc-windows-msvc", TargetFeatures::Unknown).unwrap();
+        assert_eq!(
+            spec.eval(&windows),
+            Some(false),
+            "the version doesn't matter"
+        );
+
+        assert_eq!(
+            "cfg(version(\"1.50.0.1\"))"
+                .parse::<TargetSpec>()
+                .unwrap_err(),
+            ParseError::InvalidVersion("1.50.0.1".to_string())
+        );
+        assert!("cfg(version(unix))".parse::<TargetSpec>().is_err());
+    }
+
+    #[test]
+    fn test_target_cpu() {
+        let triple = "x86_64-unknown-linux-gnu";
+        let spec: TargetSpec = "cfg(target_feature = \"avx2\")".parse().unwrap();
+        for (cpu, res) in &[("skylake", Some(true)), ("x86-64", Some(false))] {
+            let features = TargetFeatures::from_target_cpu(cpu, triple).expect("cpu is known");
+            let platform = Platform::new(triple, features).unwrap();
+            assert_eq!(&spec.eval(&platform), res, "{}", cpu);
+        }
+        assert!(TargetFeatures::from_target_cpu("native", triple).is_none());
+        assert!(TargetFeatures::from_target_cpu("skylake", "bogus-triple").is_none());
+    }
+
+    #[test]
+    fn test_rustc_cfg_output() {
+        let output = "debug_assertions\ntarget_arch=\"x86_64\"\ntarget_endian=\"little\"\n\
+                      target_env=\"gnu\"\ntarget_family=\"unix\"\ntarget_feature=\"sse2\"\n\
+                      target_has_atomic=\"8\"\ntarget_has_atomic=\"64\"\n\
+                      target_has_atomic=\"ptr\"\ntarget_os=\"linux\"\n\
+                      target_pointer_width=\"64\"\ntarget_vendor=\"unknown\"\nunix\n";
+        let platform = Platform::from_rustc_cfg_output("x86_64-unknown-linux-gnu", output)
+            .expect("rustc cfg output should parse");
+        assert!(platform.is_custom());
+        assert_eq!(platform.triple(), "x86_64-unknown-linux-gnu");
+
+        for (spec, res) in &[
+            ("x86_64-unknown-linux-gnu", Some(true)),
+            (
+                "cfg(all(unix, target_os = \"linux\", target_env = \"gnu\"))",
+                Some(true),
+            ),
+            ("cfg(target_feature = \"sse2\")", Some(true)),
+            ("cfg(target_feature = \"avx2\")", Some(false)),
+            ("cfg(target_has_atomic = \"64\")", Some(true)),
+            ("cfg(target_has_atomic = \"128\")", Some(false)),
+            ("cfg(debug_assertions)", Some(true)),
+            ("cfg(test)", Some(false)),
+        ] {
+            let target_spec: TargetSpec = spec.parse().unwrap();
+            assert_eq!(&target_spec.eval(&platform), res, "{}", spec);
+        }
+    }
+
+    #[cfg(feature = "custom")]
+    #[test]
+    fn test_custom() {
+        let json = r#"{
+            "llvm-target": "thumbv7em-none-eabihf",
+            "arch": "arm",
+            "os": "none",
+            "target-pointer-width": "32",
+            "target-endian": "little"
+        }"#;
+        let platform = Platform::from_json("thumbv7em-custom", json, TargetFeatures::Unknown)
+            .expect("custom target spec should parse");
+        assert!(platform.is_custom());
+        assert_eq!(platform.triple(), "thumbv7em-custom");
+
+        fn eval_custom(spec: &str, platform: &Platform<'_>) -> Option<bool> {
+            let spec: TargetSpec = spec.parse().unwrap();
+            spec.eval(platform)
+        }
+
+        assert_eq!(
+            eval_custom("cfg(target_arch = \"arm\")", &platform),
+            Some(true)
+        );
+        assert_eq!(
+            eval_custom("cfg(target_pointer_width = \"32\")", &platform),
+            Some(true)
+        );
+        assert_eq!(eval_custom("cfg(target_os = \"\")", &platform), Some(true));
+        assert_eq!(eval_custom("cfg(unix)", &platform), Some(false));
+        assert_eq!(eval_custom("cfg(windows)", &platform), Some(false));
+        assert_eq!(
+            eval_custom("x86_64-unknown-linux-gnu", &platform),
+            Some(false)
+        );
+
+        assert_eq!(
+            TargetSpec::custom("thumbv7em-custom").eval(&platform),
+            Some(true)
+        );
+        assert_eq!(
+            TargetSpec::custom("thumbv6m-custom").eval(&platform),
+            Some(false)
+        );
+        let linux = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+        assert_eq!(
+            TargetSpec::custom("thumbv7em-custom").eval(&linux),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_eval_result() {
+        let linux = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+        let eval_result = |spec: &str| spec.parse::<TargetSpec>().unwrap().eval_result(&linux);
+        let unknown_features = |spec: &str| {
+            eval_result(spec)
+                .unknown_reason()
+                .expect("result is unknown")
+                .target_features()
+                .to_vec()
+        };
+
+        assert_eq!(eval_result("cfg(unix)"), EvalResult::True);
+        assert_eq!(eval_result("x86_64-pc-windows-msvc"), EvalResult::False);
+        // Known operands short-circuit unknown ones.
+        assert_eq!(
+            eval_result("cfg(any(unix, target_feature = \"sse2\"))"),
+            EvalResult::True
+        );
+        assert_eq!(
+            eval_result("cfg(all(windows, target_feature = \"sse2\"))"),
+            EvalResult::False
+        );
+
+        assert_eq!(
+            unknown_features("cfg(not(target_feature = \"sse2\"))"),
+            vec!["sse2"]
+        );
+        assert_eq!(
+            unknown_features(
+                "cfg(all(unix, target_feature = \"sse2\", not(target_feature = \"avx\"), \
+                 target_feature = \"sse2\"))"
+            ),
+            vec!["avx", "sse2"]
+        );
+        // Only the unknown operands that determine the result are reported.
+        assert_eq!(
+            unknown_features(
+                "cfg(any(target_feature = \"fma\", all(windows, target_feature = \"avx\")))"
+            ),
+            vec!["fma"]
+        );
+
+        let result = eval_result("cfg(target_feature = \"sse2\")");
+        assert!(result.is_unknown());
+        assert_eq!(
+            result.to_string(),
+            "unknown (target features not known: sse2)"
+        );
+        assert_eq!(Option::<bool>::from(result), None);
+        assert_eq!(EvalResult::from(Some(true)), EvalResult::True);
+        assert!(EvalResult::from(None).unknown_reason().is_some());
+    }
+
+    #[test]
+    fn test_heuristic() {
+        let platform = Platform::new_heuristic("riscv64gc-unknown-freebsd", TargetFeatures::none());
+        for (spec, expected) in &[
+            ("cfg(unix)", Some(true)),
+            ("cfg(target_os = \"freebsd\")", Some(true)),
+            ("cfg(target_arch = \"riscv64\")", Some(true)),
+            ("cfg(target_pointer_width = \"64\")", Some(true)),
+            ("cfg(target_endian = \"little\")", Some(true)),
+            ("x86_64-unknown-freebsd", Some(false)),
+        ] {
+            let spec: TargetSpec = spec.parse().unwrap();
+            assert_eq!(spec.eval(&platform), *expected, "{}", spec);
+        }
+
+        // Components that aren't known to any builtin target leave their properties unknown.
+        let platform = Platform::new_heuristic("riscv64gc-unknown-someos", TargetFeatures::none());
+        let result = "cfg(any(unix, target_os = \"linux\"))"
+            .parse::<TargetSpec>()
+            .unwrap()
+            .eval_result(&platform);
+        let reason = result.unknown_reason().expect("result is unknown");
+        assert_eq!(reason.target_properties(), &["target_family", "target_os"]);
+        assert_eq!(
+            reason.to_string(),
+            "target properties not known: target_family, target_os"
+        );
+        assert_eq!(
+            "cfg(target_arch = \"riscv64\")"
+                .parse::<TargetSpec>()
+                .unwrap()
+                .eval(&platform),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_eval_all() {
+        use cfg_expr::targets::ALL;
+
+        // More than 64 platforms, to cover multiple words of each bitset.
+        let mut platforms: Vec<_> = ALL
+            .iter()
+            .map(|target_info| {
+                Platform::new(target_info.triple, TargetFeatures::Unknown).expect("known triple")
+            })
+            .collect();
+        let mut flagged = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::none())
+            .expect("known triple");
+        flagged.add_flags(&["cargo_web"]);
+        platforms.push(flagged);
+        assert!(platforms.len() > 64);
+
+        for input in &[
+            "x86_64-unknown-linux-gnu",
+            "cfg(unix)",
+            "cfg(any(windows, all(unix, not(windows))))",
+            "cfg(all(target_os = \"linux\", target_feature = \"sse2\"))",
+            "cfg(any(cargo_web, target_abi = \"eabihf\", target_has_atomic = \"64\"))",
+            "cfg(any())",
+        ] {
+            let spec: TargetSpec = input.parse().unwrap();
+            let summary = spec.eval_all(&platforms);
+            assert_eq!(summary.len(), platforms.len());
+            let expected: Vec<_> = platforms
+                .iter()
+                .map(|platform| spec.eval(platform))
+                .collect();
+            let actual: Vec<_> = (0..summary.len())
+                .map(|index| summary.result(index))
+                .collect();
+            assert_eq!(actual, expected, "{}", input);
+
+            let matched: Vec<_> = (0..expected.len())
+                .filter(|index| expected[*index] == Some(true))
+                .collect();
+            assert_eq!(summary.matched().collect::<Vec<_>>(), matched, "{}", input);
+            assert_eq!(summary.matched_count(), matched.len(), "{}", input);
+            assert_eq!(summary.any_matched(), !matched.is_empty(), "{}", input);
+            let unknown = expected.iter().filter(|result| result.is_none()).count();
+            assert_eq!(summary.unknown().count(), unknown, "{}", input);
+            assert_eq!(summary.unknown_count(), unknown, "{}", input);
+        }
+
+        let spec: TargetSpec = "cfg(unix)".parse().unwrap();
+        let summary = spec.eval_all(&[]);
+        assert!(summary.is_empty());
+        assert!(summary.all_matched());
+        assert!(!summary.any_matched());
+    }
 }
@@ -0,0 +1,423 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Guessing the properties of triples that aren't builtin, from their components.
+//!
+//! Triples are split into architecture, vendor, operating system and environment components the
+//! way LLVM and rustc name them, e.g. `riscv64gc-unknown-freebsd` or `aarch64-linux-android`. Each
+//! property is then looked up among the builtin targets that share the relevant component, and is
+//! only known if all of them agree on it.
+
+use crate::abi::builtin_abi;
+use crate::atomic::builtin_max_atomic_width;
+use cfg_expr::expr::TargetPredicate;
+use cfg_expr::targets::{Arch, Endian, Env, Family, Os, TargetInfo, Vendor, ALL as ALL_TARGETS};
+
+/// A target that isn't builtin, with the properties that could be guessed from its triple.
+///
+/// `None` means that a property is unknown. Properties that may be absent, like the environment,
+/// are `Some(None)` if they're known to be absent.
+#[derive(Debug)]
+pub(crate) struct HeuristicTarget {
+    pub(crate) triple: Box<str>,
+    arch: Option<Arch>,
+    pointer_width: Option<u8>,
+    endian: Option<Endian>,
+    vendor: Option<Option<Vendor>>,
+    os: Option<Option<Os>>,
+    env: Option<Option<Env>>,
+    family: Option<Option<Family>>,
+    abi: Option<&'static str>,
+    // The smallest and largest maximum atomic widths among targets with this architecture.
+    max_atomic_widths: Option<(u8, u8)>,
+}
+
+impl HeuristicTarget {
+    /// Returns whether this predicate matches, or `None` if the property it checks is unknown.
+    pub(crate) fn matches(&self, predicate: TargetPredicate) -> Option<bool> {
+        match predicate {
+            TargetPredicate::Arch(arch) => self.arch.map(|value| value == arch),
+            TargetPredicate::Endian(endian) => self.endian.map(|value| value == endian),
+            TargetPredicate::Env(env) => self.env.map(|value| value == env),
+            TargetPredicate::Family(family) => self.family.map(|value| value == family),
+            TargetPredicate::Os(os) => self.os.map(|value| value == os),
+            TargetPredicate::PointerWidth(width) => self.pointer_width.map(|value| value == width),
+            TargetPredicate::Vendor(vendor) => self.vendor.map(|value| value == vendor),
+        }
+    }
+
+    /// Returns whether this target is in the `wasm` family, or `None` if that's unknown.
+    ///
+    /// Architectures like wasm64 are in the family even if they're not builtin.
+    pub(crate) fn is_wasm(&self) -> Option<bool> {
+        if Components::split(&self.triple).arch.starts_with("wasm") {
+            return Some(true);
+        }
+        self.arch.map(|arch| arch == Arch::wasm32)
+    }
+
+    /// Returns the architecture, if it's known.
+    pub(crate) fn arch(&self) -> Option<Arch> {
+        self.arch
+    }
+
+    /// Returns the operating system, or `None` if it's unknown.
+    pub(crate) fn os(&self) -> Option<Option<Os>> {
+        self.os
+    }
+
+    /// Returns the environment, or `None` if it's unknown.
+    pub(crate) fn env(&self) -> Option<Option<Env>> {
+        self.env
+    }
+
+    /// Returns the vendor, or `None` if it's unknown.
+    pub(crate) fn vendor(&self) -> Option<Option<Vendor>> {
+        self.vendor
+    }
+
+    /// Returns the family, or `None` if it's unknown.
+    pub(crate) fn family(&self) -> Option<Option<Family>> {
+        self.family
+    }
+
+    /// Returns the byte order, if it's known.
+    pub(crate) fn endian(&self) -> Option<Endian> {
+        self.endian
+    }
+
+    /// Returns the pointer width, if it's known.
+    pub(crate) fn pointer_width(&self) -> Option<u8> {
+        self.pointer_width
+    }
+
+    /// Returns the `target_abi` value, if it's known.
+    pub(crate) fn abi(&self) -> Option<&'static str> {
+        self.abi
+    }
+
+    /// Returns whether `cfg(target_has_atomic = "value")` matches, or `None` if that's unknown.
+    pub(crate) fn has_atomic(&self, value: &str) -> Option<bool> {
+        let width = match value {
+            "ptr" => self.pointer_width?,
+            "8" | "16" | "32" | "64" | "128" => value.parse().expect("value is a valid u8"),
+            _ => return Some(false),
+        };
+        // Builtin targets support all atomic widths from 8 bits up to their maximum.
+        let (smallest_max, largest_max) = self.max_atomic_widths?;
+        if width <= smallest_max {
+            Some(true)
+        } else if width > largest_max {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+/// Guesses the properties of this triple from the builtin targets.
+pub(crate) fn heuristic_target(triple: &str) -> HeuristicTarget {
+    let components = Components::split(triple);
+    let builtins: Vec<_> = ALL_TARGETS
+        .iter()
+        .map(|target_info| (target_info, Components::split(target_info.triple)))
+        .collect();
+
+    // Targets with the same architecture component, or failing that, with an architecture
+    // component that's a prefix of this one: e.g. armv7k is a variant of armv7. Prefixes only
+    // count if the rest has no digits, since those often change the architecture, as with arm64
+    // or mipsisa64. The rest may also change the byte order, as with aarch64_be.
+    let mut exact_arch = true;
+    let mut arch_targets: Vec<_> = builtins
+        .iter()
+        .filter(|(_, builtin)| builtin.arch == components.arch)
+        .collect();
+    if arch_targets.is_empty() {
+        exact_arch = false;
+        let prefix = builtins
+            .iter()
+            .map(|(_, builtin)| builtin.arch)
+            .filter(|arch| match components.arch.strip_prefix(arch) {
+                Some(rest) => !rest.is_empty() && !rest.chars().any(|c| c.is_ascii_digit()),
+                None => false,
+            })
+            .max_by_key(|arch| arch.len());
+        arch_targets = builtins
+            .iter()
+            .filter(|(_, builtin)| Some(builtin.arch) == prefix)
+            .collect();
+    }
+    // Environments like gnux32 use 32-bit pointers on 64-bit architectures.
+    let width_targets: Vec<_> = arch_targets
+        .iter()
+        .copied()
+        .filter(|(_, builtin)| builtin.is_ilp32() == components.is_ilp32())
+        .collect();
+
+    let arch = unanimous(arch_targets.iter().map(|(target_info, _)| target_info.arch));
+    let pointer_width = unanimous(
+        width_targets
+            .iter()
+            .map(|(target_info, _)| target_info.pointer_width),
+    );
+    let endian = if exact_arch {
+        unanimous(
+            arch_targets
+                .iter()
+                .map(|(target_info, _)| target_info.endian),
+        )
+    } else {
+        None
+    };
+    let max_atomic_widths = if exact_arch {
+        let widths = width_targets
+            .iter()
+            .map(|(target_info, _)| builtin_max_atomic_width(target_info.triple));
+        widths.clone().min().zip(widths.max())
+    } else {
+        None
+    };
+
+    let vendor = match components.vendor {
+        Some(vendor) => lookup(
+            &builtins,
+            |builtin| builtin.vendor == Some(vendor),
+            |t| t.vendor,
+        )
+        .or_else(|| vendor.parse().ok().map(Some)),
+        None => lookup(&builtins, |builtin| builtin.vendor.is_none(), |t| t.vendor),
+    };
+
+    // Some environments determine the operating system, e.g. linux-android is Android rather than
+    // Linux. Targets with such environments are skipped when looking up the operating system.
+    let env_os = |env: Option<&str>| match env {
+        Some(env) => lookup(&builtins, |builtin| builtin.env == Some(env), |t| t.os),
+        None => None,
+    };
+    let os = env_os(components.env).or_else(|| {
+        let os = components.os?;
+        let matches_os = |builtin: &Components<'_>| builtin.os == Some(os);
+        lookup(
+            &builtins,
+            |builtin| matches_os(builtin) && env_os(builtin.env).is_none(),
+            |t| t.os,
+        )
+        .or_else(|| lookup(&builtins, matches_os, |t| t.os))
+        .or_else(|| os.parse().ok().map(Some))
+    });
+    // Targets with the same operating system component come first, since targets without an
+    // operating system can still belong to a family, as with x86_64-linux-kernel.
+    let family = match os {
+        Some(os) => unanimous(
+            builtins
+                .iter()
+                .filter(|(target_info, builtin)| {
+                    target_info.os == os && builtin.os == components.os
+                })
+                .map(|(target_info, _)| target_info.family),
+        )
+        .or_else(|| {
+            unanimous(
+                ALL_TARGETS
+                    .iter()
+                    .filter(|target_info| target_info.os == os)
+                    .map(|target_info| target_info.family),
+            )
+        }),
+        None => None,
+    };
+
+    let env = match components.env {
+        // Operating systems sometimes determine the environment, as with armv7-wrs-vxworks-eabihf.
+        Some(env) => lookup(
+            &builtins,
+            |builtin| builtin.env == Some(env) && builtin.os == components.os,
+            |t| t.env,
+        )
+        .or_else(|| lookup(&builtins, |builtin| builtin.env == Some(env), |t| t.env))
+        .or_else(|| {
+            // Variants of known environments, such as gnu_ilp32 or muslabi64.
+            let prefix = builtins
+                .iter()
+                .filter_map(|(_, builtin)| builtin.env)
+                .filter(|builtin_env| env.starts_with(builtin_env))
+                .max_by_key(|builtin_env| builtin_env.len())?;
+            lookup(&builtins, |builtin| builtin.env == Some(prefix), |t| t.env)
+        }),
+        None => lookup(
+            &builtins,
+            |builtin| builtin.env.is_none() && builtin.os == components.os,
+            |t| t.env,
+        ),
+    };
+    // Vendors sometimes determine the ABI too, as with x86_64-uwp-windows-gnu.
+    let abi = {
+        let same_env = |builtin: &Components<'_>| builtin.env == components.env;
+        let same_vendor = |builtin: &Components<'_>| builtin.vendor == components.vendor;
+        let same_arch = |builtin: &Components<'_>| builtin.arch == components.arch;
+        let abi = |t: &'static TargetInfo| builtin_abi(t.triple);
+        lookup(
+            &builtins,
+            |builtin| same_env(builtin) && same_vendor(builtin) && same_arch(builtin),
+            abi,
+        )
+        .or_else(|| {
+            lookup(
+                &builtins,
+                |builtin| same_env(builtin) && same_vendor(builtin),
+                abi,
+            )
+        })
+        .or_else(|| lookup(&builtins, same_env, abi))
+    };
+
+    HeuristicTarget {
+        triple: triple.into(),
+        arch,
+        pointer_width,
+        endian,
+        vendor,
+        os,
+        env,
+        family,
+        abi,
+        max_atomic_widths,
+    }
+}
+
+/// The components of a triple.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Components<'a> {
+    arch: &'a str,
+    vendor: Option<&'a str>,
+    os: Option<&'a str>,
+    env: Option<&'a str>,
+}
+
+impl<'a> Components<'a> {
+    /// Splits a triple into its components.
+    ///
+    /// Triples have the form `arch-vendor-os-env`, but the vendor or the environment may be
+    /// missing, as in `wasm32-wasi` or `thumbv7em-none-eabi`. With three components, the second
+    /// one is taken to be a vendor if it's a known vendor.
+    fn split(triple: &'a str) -> Self {
+        let mut parts = triple.splitn(4, '-');
+        let arch = parts.next().unwrap_or("");
+        let parts: Vec<_> = parts.collect();
+        let (vendor, os, env) = match parts.as_slice() {
+            [] => (None, None, None),
+            [os] => (None, Some(*os), None),
+            [vendor, os] if vendor.parse::<Vendor>().is_ok() => (Some(*vendor), Some(*os), None),
+            [os, env] => (None, Some(*os), Some(*env)),
+            [vendor, os, env] => (Some(*vendor), Some(*os), Some(*env)),
+            _ => unreachable!("splitn(4) returns at most 4 parts"),
+        };
+        // Versions of WASI, such as wasip1 and wasip2, are all the WASI operating system.
+        let os = os.map(|os| match os.strip_prefix("wasip") {
+            Some(version) if version.chars().all(|c| c.is_ascii_digit()) => "wasi",
+            _ => os,
+        });
+        Self {
+            arch,
+            vendor,
+            os,
+            env,
+        }
+    }
+
+    /// Returns true if the environment is an ILP32 variant of a 64-bit architecture.
+    fn is_ilp32(&self) -> bool {
+        match self.env {
+            Some(env) => env.ends_with("x32") || env.ends_with("ilp32"),
+            None => false,
+        }
+    }
+}
+
+/// Returns the value that the builtin targets matching `filter` agree on.
+///
+/// Returns `None` if no builtin targets match, or if they disagree.
+fn lookup<T: PartialEq>(
+    builtins: &[(&'static TargetInfo, Components<'static>)],
+    mut filter: impl FnMut(&Components<'static>) -> bool,
+    value: impl Fn(&'static TargetInfo) -> T,
+) -> Option<T> {
+    unanimous(
+        builtins
+            .iter()
+            .filter(|(_, components)| filter(components))
+            .map(|(target_info, _)| value(target_info)),
+    )
+}
+
+/// Returns the value that all of these values are equal to, or `None` if there are none or they
+/// differ.
+fn unanimous<T: PartialEq>(mut values: impl Iterator<Item = T>) -> Option<T> {
+    let first = values.next()?;
+    if values.all(|value| value == first) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let split = |triple| {
+            let components = Components::split(triple);
+            (
+                components.arch,
+                components.vendor,
+                components.os,
+                components.env,
+            )
+        };
+        assert_eq!(
+            split("x86_64-unknown-linux-gnu"),
+            ("x86_64", Some("unknown"), Some("linux"), Some("gnu"))
+        );
+        assert_eq!(
+            split("powerpc-wrs-vxworks-spe"),
+            ("powerpc", Some("wrs"), Some("vxworks"), Some("spe"))
+        );
+        assert_eq!(
+            split("x86_64-apple-darwin"),
+            ("x86_64", Some("apple"), Some("darwin"), None)
+        );
+        assert_eq!(
+            split("aarch64-linux-android"),
+            ("aarch64", None, Some("linux"), Some("android"))
+        );
+        assert_eq!(split("wasm32-wasi"), ("wasm32", None, Some("wasi"), None));
+        assert_eq!(split("wasm32-wasip2"), ("wasm32", None, Some("wasi"), None));
+        assert_eq!(
+            split("wasm32-wasip1-threads"),
+            ("wasm32", None, Some("wasi"), Some("threads"))
+        );
+        assert_eq!(split("bpf"), ("bpf", None, None, None));
+    }
+
+    #[test]
+    fn test_builtin_agreement() {
+        // Builtin triples that are well-formed enough for their components to be representative
+        // should be guessed correctly.
+        for triple in &[
+            "x86_64-unknown-linux-gnu",
+            "aarch64-linux-android",
+            "x86_64-pc-windows-msvc",
+            "thumbv7em-none-eabihf",
+        ] {
+            let target_info = cfg_expr::targets::get_target_by_triple(triple).unwrap();
+            let target = heuristic_target(triple);
+            assert_eq!(target.arch, Some(target_info.arch), "{}", triple);
+            assert_eq!(target.os, Some(target_info.os), "{}", triple);
+            assert_eq!(target.env, Some(target_info.env), "{}", triple);
+            assert_eq!(target.family, Some(target_info.family), "{}", triple);
+            assert_eq!(target.abi, Some(builtin_abi(triple)), "{}", triple);
+        }
+    }
+}
@@ -33,16 +33,68 @@
 //! assert_eq!(eval("x86_64-unknown-linux-gnu", "x86_64-unknown-linux-gnu"), Ok(Some(true)));
 //! assert_eq!(eval("x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc"), Ok(Some(false)));
 //! ```
+//!
+//! ## Optional features
+//!
+//! * `serde`: `Serialize` and `Deserialize` implementations for `TargetSpec`, `Platform`,
+//!   `TargetFeatures` and `PlatformSummary`.
+//! * `custom`: `Platform::from_json`, which reads rustc custom target specs, and
+//!   `TargetSpec::custom`.
+//! * `toolchain`: `Platform::from_toolchain`, which gets target specs from an installed
+//!   toolchain by running rustc. Implies `custom`.
+//! * `proptest09`: `proptest` strategies for platforms.
 
 #![warn(missing_docs)]
 #![forbid(unsafe_code)]
 
+mod abi;
+mod algebra;
+mod alias;
+mod atomic;
+mod builder;
+mod cache;
+mod compat;
+mod cpu;
+mod custom;
+mod database;
 mod evaluator;
+mod heuristic;
 mod parser;
 mod platform;
+mod platform_set;
+mod platform_spec;
+mod predicate;
+mod properties;
 #[cfg(feature = "proptest09")]
 mod proptest;
+mod registry;
+mod rewrite;
+mod rustc_cfg;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod simplify;
+mod summary;
+#[cfg(feature = "toolchain")]
+mod toolchain;
+mod version;
+mod warning;
 
+pub use alias::{canonical_triple, triple_aliases};
+pub use builder::{Endian, PlatformBuilder};
+pub use cache::TargetSpecCache;
+pub use custom::*;
+pub use database::TargetDatabase;
 pub use evaluator::*;
 pub use parser::*;
 pub use platform::*;
+pub use platform_set::PlatformSet;
+pub use platform_spec::PlatformSpec;
+pub use predicate::SpecPredicate;
+pub use properties::{TargetArch, TargetEnv, TargetFamily, TargetOs, TargetVendor};
+pub use registry::{register_custom_target, registered_custom_targets, RegisterTargetError};
+pub use rustc_cfg::RustcCfgError;
+pub use summary::{PlatformSummary, TargetFeaturesSummary};
+#[cfg(feature = "toolchain")]
+pub use toolchain::{target_spec_json, ToolchainError};
+pub use version::{InvalidRustcVersion, RustcVersion};
+pub use warning::SpecWarning;
@@ -1,16 +1,32 @@
 // Copyright (c) The cargo-guppy Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::{eval_target, Platform};
-use cfg_expr::targets::{get_target_by_triple, TargetInfo};
+use crate::algebra::satisfiable;
+use crate::alias::builtin_target;
+use crate::custom::registered_target;
+use crate::predicate::cfg_predicates;
+use crate::rewrite::{is_rewritten_key, rewrite_keys, RewrittenCfg, VERSION_KEY};
+use crate::simplify::simplify_cfg;
+use crate::warning::{spec_warnings, suggest};
+use crate::{
+    eval_target, eval_target_all, EvalOptions, EvalResult, EvalSummary, Platform, PlatformSet,
+    RustcVersion, SpecPredicate, SpecWarning, TargetFeatures, UnknownPolicy,
+};
+use cfg_expr::error::Reason;
+use cfg_expr::expr::lexer::{Lexer, Token};
+use cfg_expr::targets::{TargetInfo, ALL as ALL_TARGETS};
 use cfg_expr::{Expression, Predicate};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
 use std::str::FromStr;
 use std::sync::Arc;
-use std::{error, fmt};
 
 /// A parsed target specification or triple, as found in a `Cargo.toml` file.
 ///
-/// Use the `FromStr` implementation or `str::parse` to obtain an instance.
+/// Use the `FromStr` implementation or `str::parse` to obtain an instance. These parse with the
+/// default `ParseOptions`; use `TargetSpec::parse_with_options` to choose other limits.
 ///
 /// ## Examples
 ///
@@ -31,48 +47,714 @@ use std::{error, fmt};
 /// assert_eq!(spec.eval(&x86_64_mac), Some(false), "x86_64 MacOS matches no features");
 /// assert_eq!(spec.eval(&i686_linux), Some(true), "i686 Linux matches some features");
 /// ```
+///
+/// Specs compare equal, and hash the same way, if `simplify` returns the same spec for both, or if
+/// they're the same triple. This means they can be used as map keys to deduplicate `[target]`
+/// sections. Not every pair of equivalent specs compares equal, though: `simplify` doesn't apply
+/// rules like absorption, so `cfg(any(unix, all(unix, windows)))` and `cfg(unix)` are different.
+///
+/// ```
+/// use target_spec::TargetSpec;
+///
+/// let a: TargetSpec = "cfg(any(unix, windows))".parse().unwrap();
+/// let b: TargetSpec = "cfg(any(windows,unix))".parse().unwrap();
+/// assert_eq!(a, b);
+///
+/// let c: TargetSpec = "cfg(all(unix, windows))".parse().unwrap();
+/// assert_ne!(a, c);
+/// // Neither spec matches any platform.
+/// assert_eq!(c, "cfg(any())".parse().unwrap());
+/// ```
 #[derive(Clone, Debug)]
 pub struct TargetSpec {
-    target: Target,
+    input: Arc<str>,
+    pub(crate) target: Target,
+    // The simplified form of cfg() specs, which they're compared and hashed by.
+    simplified: Option<Arc<str>>,
 }
 
 impl TargetSpec {
+    /// Creates a spec that matches a custom target by name, as with a
+    /// `[target.thumbv7em-custom.dependencies]` section.
+    ///
+    /// `TargetSpec::from_str` only accepts triples known to `target-spec`, including those
+    /// registered with `register_custom_target`. Specs created with this method match platforms
+    /// created by `Platform::from_json` with the same triple.
+    ///
+    /// Requires the `custom` feature.
+    #[cfg(feature = "custom")]
+    pub fn custom(triple: impl Into<String>) -> Self {
+        Self::from_custom_triple(triple)
+    }
+
+    /// Creates a spec that matches a custom target by name. See `custom`.
+    #[cfg(any(feature = "custom", feature = "serde"))]
+    pub(crate) fn from_custom_triple(triple: impl Into<String>) -> Self {
+        let triple: Arc<str> = triple.into().into();
+        Self {
+            input: triple.clone(),
+            target: Target::Custom(triple),
+            simplified: None,
+        }
+    }
+
+    /// Parses a spec the way `str::parse` does, with these limits on how complex it can be.
+    ///
+    /// Returns `ParseError::TooComplex` if the spec exceeds a limit.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::{ParseError, ParseLimit, ParseOptions, TargetSpec};
+    ///
+    /// let mut options = ParseOptions::new();
+    /// options.set_max_depth(1);
+    /// assert!(TargetSpec::parse_with_options("cfg(not(unix))", &options).is_ok());
+    /// assert_eq!(
+    ///     TargetSpec::parse_with_options("cfg(not(any(unix)))", &options),
+    ///     Err(ParseError::TooComplex(ParseLimit::Depth(1))),
+    /// );
+    /// ```
+    pub fn parse_with_options(input: &str, options: &ParseOptions) -> Result<Self, ParseError> {
+        let target = Target::parse(input, options)?;
+        let simplified = match target {
+            Target::Spec(_) => Some(simplify_spec(input)),
+            Target::Builtin(_) | Target::Custom(_) => None,
+        };
+        Ok(Self {
+            input: input.into(),
+            target,
+            simplified,
+        })
+    }
+
+    /// Returns the specification or triple this was parsed from.
+    ///
+    /// This is also what the `Display` implementation writes out.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.input
+    }
+
+    /// Returns a formatter that writes this spec out in canonical form.
+    ///
+    /// `cfg()` expressions are written out with consistent spacing and without trailing commas,
+    /// the way they're usually written in `Cargo.toml` files. The result parses back into an
+    /// equivalent spec. Triples are written out as-is.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::TargetSpec;
+    ///
+    /// let spec: TargetSpec = "cfg( any(unix,target_os=\"redox\",) )".parse().unwrap();
+    /// assert_eq!(spec.to_string(), "cfg( any(unix,target_os=\"redox\",) )");
+    /// assert_eq!(
+    ///     spec.display_canonical().to_string(),
+    ///     "cfg(any(unix, target_os = \"redox\"))",
+    /// );
+    /// ```
+    pub fn display_canonical(&self) -> CanonicalTargetSpec<'_> {
+        CanonicalTargetSpec { spec: self }
+    }
+
     /// Evaluates this specification against the given platform triple.
     ///
     /// Returns `Some(true)` if there's a match, `Some(false)` if there's none, or `None` if the
-    /// result of the evaluation is unknown (typically found if target families are involved).
+    /// result of the evaluation is unknown (typically found if target features are involved).
+    ///
+    /// To find out why a result is unknown, use `eval_result`.
     #[inline]
     pub fn eval(&self, platform: &Platform<'_>) -> Option<bool> {
-        eval_target(&self.target, platform)
+        self.eval_result(platform).into()
+    }
+
+    /// Evaluates this specification against the given platform triple, recording why the result
+    /// is unknown if it is.
+    ///
+    /// This is the same as `eval`, except that it returns an `EvalResult` rather than an
+    /// `Option<bool>`.
+    #[inline]
+    pub fn eval_result(&self, platform: &Platform<'_>) -> EvalResult {
+        self.eval_with_options(platform, &EvalOptions::default())
+    }
+
+    /// Evaluates this specification against the given platform triple, with these options for
+    /// how unknown flags and features are treated.
+    ///
+    /// `eval_result` is the same as this with the default options.
+    pub fn eval_with_options(&self, platform: &Platform<'_>, options: &EvalOptions) -> EvalResult {
+        eval_target(&self.target, platform, options)
+    }
+
+    /// Evaluates this specification against each of the given platforms.
+    ///
+    /// This returns the same results as calling `eval` for each platform, but is faster for large
+    /// sets of platforms: each predicate in the spec is looked up once per platform, and the
+    /// expression is only evaluated once for platforms where the predicates have the same values.
+    pub fn eval_all(&self, platforms: &[Platform<'_>]) -> EvalSummary {
+        self.eval_all_with_options(platforms, &EvalOptions::default())
+    }
+
+    /// Evaluates this specification against each of the given platforms, with these options for
+    /// how unknown flags and features are treated.
+    pub fn eval_all_with_options(
+        &self,
+        platforms: &[Platform<'_>],
+        options: &EvalOptions,
+    ) -> EvalSummary {
+        eval_target_all(&self.target, platforms, options)
+    }
+
+    /// Evaluates this specification against each platform in a set, returning the results by
+    /// triple.
+    ///
+    /// The results are ordered by triple, so they can be compared against stored output.
+    pub fn eval_set<'s>(&self, set: &'s PlatformSet<'_>) -> BTreeMap<&'s str, EvalResult> {
+        self.eval_set_with_options(set, &EvalOptions::default())
+    }
+
+    /// Evaluates this specification against each platform in a set, with these options for how
+    /// unknown flags and features are treated.
+    pub fn eval_set_with_options<'s>(
+        &self,
+        set: &'s PlatformSet<'_>,
+        options: &EvalOptions,
+    ) -> BTreeMap<&'s str, EvalResult> {
+        set.eval(self, options)
+    }
+
+    /// Returns true if every platform matched by this spec is also matched by `other`.
+    ///
+    /// This reasons symbolically over the predicates in both specs, treating target features and
+    /// flags as independent of the platform. It returns false if the implication can't be proven,
+    /// including if the specs mention too many predicates to analyze.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::TargetSpec;
+    ///
+    /// let linux: TargetSpec = "cfg(target_os = \"linux\")".parse().unwrap();
+    /// let unix: TargetSpec = "cfg(unix)".parse().unwrap();
+    /// let linux_gnu: TargetSpec = "x86_64-unknown-linux-gnu".parse().unwrap();
+    ///
+    /// assert!(linux_gnu.implies(&linux));
+    /// assert!(linux_gnu.implies(&unix));
+    /// // Unknown operating systems could be Linux-like without being in the unix family.
+    /// assert!(!linux.implies(&unix));
+    /// ```
+    pub fn implies(&self, other: &TargetSpec) -> bool {
+        satisfiable(&[&self.target, &other.target], |results| {
+            results[0] && !results[1]
+        }) == Some(false)
+    }
+
+    /// Returns true if no platform can be matched by both this spec and `other`.
+    ///
+    /// For example, two platform-specific sections of a `Cargo.toml` with disjoint specs never
+    /// both apply. As with `implies`, this returns false if it can't be proven.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::TargetSpec;
+    ///
+    /// let unix: TargetSpec = "cfg(unix)".parse().unwrap();
+    /// let windows: TargetSpec = "cfg(windows)".parse().unwrap();
+    /// let msvc: TargetSpec = "cfg(target_env = \"msvc\")".parse().unwrap();
+    ///
+    /// assert!(unix.is_disjoint(&windows));
+    /// assert!(!unix.is_disjoint(&msvc));
+    /// ```
+    pub fn is_disjoint(&self, other: &TargetSpec) -> bool {
+        satisfiable(&[&self.target, &other.target], |results| {
+            results[0] && results[1]
+        }) == Some(false)
+    }
+
+    /// Returns a spec that matches the platforms matched by both this spec and `other`.
+    ///
+    /// Returns `None` if the intersection can't be expressed as a single spec. This happens if
+    /// either spec is a triple, and neither spec implies the other nor are they disjoint.
+    pub fn intersect(&self, other: &TargetSpec) -> Option<TargetSpec> {
+        if self.implies(other) {
+            return Some(self.clone());
+        }
+        if other.implies(self) {
+            return Some(other.clone());
+        }
+        match (self.cfg_inner(), other.cfg_inner()) {
+            (Some(a), Some(b)) => Some(Self::combine("all", a, b)),
+            _ if self.is_disjoint(other) => Some(
+                "cfg(any())"
+                    .parse()
+                    .expect("cfg(any()) is a valid expression"),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Returns a spec that matches the platforms matched by either this spec or `other`.
+    ///
+    /// Returns `None` if the union can't be expressed as a single spec. This happens if either
+    /// spec is a triple and neither spec implies the other.
+    pub fn union(&self, other: &TargetSpec) -> Option<TargetSpec> {
+        if self.implies(other) {
+            return Some(other.clone());
+        }
+        if other.implies(self) {
+            return Some(self.clone());
+        }
+        match (self.cfg_inner(), other.cfg_inner()) {
+            (Some(a), Some(b)) => Some(Self::combine("any", a, b)),
+            _ => None,
+        }
+    }
+
+    /// Returns an equivalent spec in a normal form.
+    ///
+    /// In the simplified spec, double negations are removed, nested `all()` and `any()`
+    /// operators are flattened, and their operands are sorted and deduplicated. Specs that never
+    /// match any platform simplify to `cfg(any())`, and specs that match every platform simplify
+    /// to `cfg(all())`. Triples are returned as-is.
+    ///
+    /// Specs that are equivalent up to these rules simplify to the same text, so the result can
+    /// be used to deduplicate `[target]` sections.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::TargetSpec;
+    ///
+    /// let spec: TargetSpec = "cfg(all(not(not(unix)), any(unix, unix)))".parse().unwrap();
+    /// assert_eq!(spec.simplify().as_str(), "cfg(unix)");
+    ///
+    /// let spec: TargetSpec = "cfg(all(windows, not(windows)))".parse().unwrap();
+    /// assert_eq!(spec.simplify().as_str(), "cfg(any())", "never matches");
+    /// ```
+    pub fn simplify(&self) -> TargetSpec {
+        match &self.simplified {
+            Some(simplified) => Self::parse_unlimited(simplified)
+                .expect("simplifying a valid expression produces a valid expression"),
+            None => self.clone(),
+        }
+    }
+
+    /// Returns warnings about predicates in this spec that are probably mistakes, in the order
+    /// they're written.
+    ///
+    /// Misspelled values of keys like `target_os` fail to parse, but `target_abi`,
+    /// `target_has_atomic` and `target_pointer_width` accept any value. A value that no builtin
+    /// target has would silently evaluate to false everywhere, so it's reported here, along with
+    /// a suggestion if it looks like a misspelling. Triples never have warnings.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::{SpecWarning, TargetSpec};
+    ///
+    /// let spec: TargetSpec = "cfg(all(unix, target_abi = \"eabhf\"))".parse().unwrap();
+    /// assert_eq!(
+    ///     spec.warnings(),
+    ///     vec![SpecWarning::UnknownValue {
+    ///         key: "target_abi",
+    ///         value: "eabhf",
+    ///         suggestion: Some("eabihf"),
+    ///     }],
+    /// );
+    /// ```
+    pub fn warnings(&self) -> Vec<SpecWarning<'_>> {
+        spec_warnings(self.predicates())
+    }
+
+    /// Returns the predicates this spec is gated on, in the order they're written.
+    ///
+    /// A triple is reported as a single `SpecPredicate::Triple`. Predicates that are mentioned
+    /// more than once are returned each time.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::{SpecPredicate, TargetSpec};
+    ///
+    /// let spec: TargetSpec = "cfg(all(windows, target_arch = \"x86_64\", not(test)))".parse().unwrap();
+    /// assert_eq!(
+    ///     spec.predicates().collect::<Vec<_>>(),
+    ///     vec![
+    ///         SpecPredicate::Target { key: "target_family", value: "windows" },
+    ///         SpecPredicate::Target { key: "target_arch", value: "x86_64" },
+    ///         SpecPredicate::Flag("test"),
+    ///     ],
+    /// );
+    /// ```
+    pub fn predicates(&self) -> impl Iterator<Item = SpecPredicate<'_>> + '_ {
+        let predicates = match &self.target {
+            Target::Spec(_) => cfg_predicates(self.as_str()),
+            Target::Builtin(_) | Target::Custom(_) => vec![SpecPredicate::Triple(self.as_str())],
+        };
+        predicates.into_iter()
+    }
+
+    /// Returns every builtin triple this spec matches, in the order `target-spec` knows them.
+    ///
+    /// Each triple is evaluated with unknown target features and no flags set, so triples for
+    /// which the result depends on target features aren't returned. Custom targets never match.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::TargetSpec;
+    ///
+    /// let spec: TargetSpec = "cfg(all(target_os = \"macos\", target_arch = \"x86_64\"))".parse().unwrap();
+    /// assert_eq!(spec.matching_triples().collect::<Vec<_>>(), vec!["x86_64-apple-darwin"]);
+    /// ```
+    pub fn matching_triples(&self) -> impl Iterator<Item = &'static str> + '_ {
+        ALL_TARGETS.iter().filter_map(move |target_info| {
+            let platform = Platform::new(target_info.triple, TargetFeatures::Unknown)
+                .expect("builtin triples are known");
+            if self.eval(&platform) == Some(true) {
+                Some(target_info.triple)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns whether this spec matches none, some or all of the builtin targets.
+    ///
+    /// Each builtin target is evaluated with unknown target features, and with flags like `test`
+    /// treated as unknown, so a spec that depends on either matches only some targets. This
+    /// catches `[target]` sections in a `Cargo.toml` that can never apply, like
+    /// `cfg(all(windows, target_os = "linux"))`, or that always apply and could be unconditional.
+    /// As with Cargo, `feature = "..."` predicates never match.
+    ///
+    /// Custom targets match some targets, since they're defined outside of `target-spec`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::{SpecCoverage, TargetSpec};
+    ///
+    /// let spec: TargetSpec = "cfg(all(windows, target_os = \"linux\"))".parse().unwrap();
+    /// assert_eq!(spec.coverage(), SpecCoverage::Never);
+    ///
+    /// let spec: TargetSpec = "cfg(any(target_endian = \"little\", target_endian = \"big\"))"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(spec.coverage(), SpecCoverage::Always);
+    ///
+    /// let spec: TargetSpec = "cfg(all(unix, not(test)))".parse().unwrap();
+    /// assert_eq!(spec.coverage(), SpecCoverage::Sometimes);
+    /// ```
+    pub fn coverage(&self) -> SpecCoverage {
+        if let Target::Custom(_) = self.target {
+            return SpecCoverage::Sometimes;
+        }
+        let platforms: Vec<_> = ALL_TARGETS
+            .iter()
+            .map(|target_info| {
+                Platform::new(target_info.triple, TargetFeatures::Unknown)
+                    .expect("builtin triples are known")
+            })
+            .collect();
+        let mut options = EvalOptions::new();
+        options.set_unknown_flags(UnknownPolicy::Unknown);
+        let summary = self.eval_all_with_options(&platforms, &options);
+        let results: Vec<_> = (0..summary.len()).map(|idx| summary.result(idx)).collect();
+        if results.iter().all(|result| *result == Some(false)) {
+            SpecCoverage::Never
+        } else if results.iter().all(|result| *result == Some(true)) {
+            SpecCoverage::Always
+        } else {
+            SpecCoverage::Sometimes
+        }
+    }
+
+    /// Returns what this spec is compared and hashed by.
+    fn normalized(&self) -> NormalizedSpec<'_> {
+        match &self.target {
+            Target::Spec(_) => NormalizedSpec::Cfg(
+                self.simplified
+                    .as_deref()
+                    .expect("cfg() specs are simplified while parsing"),
+            ),
+            Target::Builtin(target_info) => NormalizedSpec::Triple(target_info.triple),
+            Target::Custom(triple) => NormalizedSpec::Custom(triple),
+        }
+    }
+
+    /// Returns the expression inside `cfg()`, or `None` if this is a triple.
+    fn cfg_inner(&self) -> Option<&str> {
+        match self.target {
+            Target::Spec(_) => Some(&self.input[4..self.input.len() - 1]),
+            Target::Builtin(_) | Target::Custom(_) => None,
+        }
+    }
+
+    fn combine(func: &str, a: &str, b: &str) -> Self {
+        Self::parse_unlimited(&format!("cfg({}({}, {}))", func, a, b))
+            .expect("combining valid expressions produces a valid expression")
+    }
+
+    /// Parses a spec derived from specs that were already parsed, which may be a little more
+    /// complex than they were.
+    fn parse_unlimited(input: &str) -> Result<Self, ParseError> {
+        Self::parse_with_options(input, &ParseOptions::unlimited())
+    }
+}
+
+/// How many of the builtin targets a spec matches.
+///
+/// Returned by `TargetSpec::coverage`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum SpecCoverage {
+    /// The spec doesn't match any builtin target.
+    Never,
+    /// The spec matches some builtin targets, depends on target features or flags, or is for a
+    /// custom target.
+    Sometimes,
+    /// The spec matches every builtin target.
+    Always,
+}
+
+impl PartialEq for TargetSpec {
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl Eq for TargetSpec {}
+
+impl Hash for TargetSpec {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized().hash(state)
     }
 }
 
+#[derive(Eq, Hash, PartialEq)]
+enum NormalizedSpec<'a> {
+    Cfg(&'a str),
+    // Aliases parse into the canonical triple.
+    Triple(&'a str),
+    Custom(&'a str),
+}
+
 impl FromStr for TargetSpec {
     type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
-            target: Target::parse(input)?,
-        })
+        Self::parse_with_options(input, &ParseOptions::default())
+    }
+}
+
+/// Limits on how complex a spec can be, used by `TargetSpec::parse_with_options`.
+///
+/// Specs in `Cargo.toml` files are short, but services that parse untrusted manifests can be
+/// sent specs that are very long or very deeply nested. Parsing stops early with
+/// `ParseError::TooComplex` if a spec exceeds these limits.
+///
+/// By default, specs can be up to 65536 bytes long, and `all()`, `any()` and `not()` operators
+/// can be nested up to 128 levels deep. These defaults are also used by `str::parse`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ParseOptions {
+    max_length: usize,
+    max_depth: usize,
+}
+
+impl ParseOptions {
+    /// The default maximum length of a spec, in bytes.
+    pub const DEFAULT_MAX_LENGTH: usize = 65536;
+
+    /// The default maximum nesting depth of a spec.
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+    /// Creates a new `ParseOptions` with the default limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum length of a spec, in bytes.
+    ///
+    /// This applies to triples as well as `cfg()` expressions.
+    pub fn set_max_length(&mut self, max_length: usize) -> &mut Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Returns the maximum length of a spec, in bytes.
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// Sets how deeply `all()`, `any()` and `not()` operators can be nested in a `cfg()`
+    /// expression.
+    ///
+    /// `cfg(unix)` has a depth of 0, and `cfg(not(unix))` has a depth of 1.
+    pub fn set_max_depth(&mut self, max_depth: usize) -> &mut Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Returns how deeply operators can be nested in a `cfg()` expression.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    fn unlimited() -> Self {
+        Self {
+            max_length: usize::MAX,
+            max_depth: usize::MAX,
+        }
+    }
+
+    fn check(&self, input: &str) -> Result<(), ParseError> {
+        if input.len() > self.max_length {
+            return Err(ParseError::TooComplex(ParseLimit::Length(self.max_length)));
+        }
+        if !input.starts_with("cfg(") || self.max_depth == usize::MAX {
+            return Ok(());
+        }
+        // Check the depth before parsing, since the parser recurses into nested operators.
+        // Lexing doesn't recurse. If lexing fails, leave reporting the error to the parser.
+        let mut depth = 0usize;
+        for token in Lexer::new(input) {
+            match token {
+                Ok(token) => match token.token {
+                    Token::OpenParen => {
+                        depth += 1;
+                        if depth > self.max_depth {
+                            return Err(ParseError::TooComplex(ParseLimit::Depth(self.max_depth)));
+                        }
+                    }
+                    Token::CloseParen => depth = depth.saturating_sub(1),
+                    _ => {}
+                },
+                Err(_) => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            max_length: Self::DEFAULT_MAX_LENGTH,
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+/// A limit in `ParseOptions` that a spec exceeded.
+///
+/// Part of `ParseError::TooComplex`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ParseLimit {
+    /// The spec was longer than this many bytes.
+    Length(usize),
+    /// The spec's operators were nested more than this many levels deep.
+    Depth(usize),
+}
+
+impl fmt::Display for ParseLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseLimit::Length(max_length) => write!(f, "longer than {} bytes", max_length),
+            ParseLimit::Depth(max_depth) => write!(f, "nested more than {} levels deep", max_depth),
+        }
+    }
+}
+
+impl fmt::Display for TargetSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.input)
+    }
+}
+
+/// A formatter for a `TargetSpec` in canonical form.
+///
+/// Returned by `TargetSpec::display_canonical`.
+#[derive(Clone, Copy, Debug)]
+pub struct CanonicalTargetSpec<'a> {
+    spec: &'a TargetSpec,
+}
+
+impl<'a> fmt::Display for CanonicalTargetSpec<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let input = self.spec.as_str();
+        if self.spec.cfg_inner().is_none() {
+            return f.write_str(input);
+        }
+
+        // The input was parsed successfully, so it lexes without errors.
+        let tokens: Vec<_> = Lexer::new(input)
+            .map(|token| token.expect("parsed spec lexes successfully").token)
+            .collect();
+        f.write_str("cfg(")?;
+        for (idx, token) in tokens.iter().enumerate() {
+            match token {
+                Token::Key(key) => f.write_str(key)?,
+                Token::Value(value) => write!(f, "\"{}\"", value)?,
+                Token::Equals => f.write_str(" = ")?,
+                Token::All => f.write_str("all")?,
+                Token::Any => f.write_str("any")?,
+                Token::Not => f.write_str("not")?,
+                Token::OpenParen => f.write_str("(")?,
+                Token::CloseParen => f.write_str(")")?,
+                Token::Comma => {
+                    // Drop trailing commas.
+                    if tokens.get(idx + 1) != Some(&Token::CloseParen) {
+                        f.write_str(", ")?;
+                    }
+                }
+            }
+        }
+        f.write_str(")")
     }
 }
 
 #[derive(Clone, Debug)]
 pub(crate) enum Target {
-    TargetInfo(&'static TargetInfo),
+    Builtin(&'static TargetInfo),
+    Custom(Arc<str>),
     Spec(Arc<Expression>),
 }
 
+/// Simplifies this `cfg()` expression, which must have been successfully parsed.
+fn simplify_spec(input: &str) -> Arc<str> {
+    let simplified = simplify_cfg(input);
+    let target = Target::parse(&simplified, &ParseOptions::unlimited())
+        .expect("simplifying a valid expression produces a valid expression");
+    // Look for contradictions and tautologies that depend on what keys mean, like
+    // all(unix, windows).
+    if satisfiable(&[&target], |results| results[0]) == Some(false) {
+        "cfg(any())".into()
+    } else if satisfiable(&[&target], |results| !results[0]) == Some(false) {
+        "cfg(all())".into()
+    } else {
+        simplified.into()
+    }
+}
+
 impl Target {
     /// Parses this expression into a `Target` instance.
-    fn parse(input: &str) -> Result<Target, ParseError> {
+    fn parse(input: &str, options: &ParseOptions) -> Result<Target, ParseError> {
+        options.check(input)?;
         if input.starts_with("cfg(") {
-            let expr = Expression::parse(input).map_err(ParseError::invalid_cfg)?;
+            let rewritten = rewrite_keys(input);
+            let expr = Expression::parse(rewritten.as_str()).map_err(|err| {
+                ParseError::InvalidCfg(CfgParseError::new(input, &rewritten, err))
+            })?;
             Self::verify_expr(expr)
+        } else if let Some(target_info) = builtin_target(input) {
+            Ok(Target::Builtin(target_info))
+        } else if registered_target(input).is_some() {
+            Ok(Target::Custom(input.into()))
         } else {
-            Ok(Target::TargetInfo(get_target_by_triple(input).ok_or_else(
-                || ParseError::UnknownTriple(input.to_string()),
-            )?))
+            Err(ParseError::UnknownTriple(input.to_string()))
         }
     }
 
@@ -81,8 +763,16 @@ impl Target {
         // Error out on unknown key-value pairs. Everything else is recognized (though
         // DebugAssertions/ProcMacro etc always returns false, and flags return false by default).
         for pred in expr.predicates() {
-            if let Predicate::KeyValue { key, .. } = pred {
-                return Err(ParseError::UnknownPredicate(key.to_string()));
+            match pred {
+                Predicate::KeyValue { key, .. } if !is_rewritten_key(key) => {
+                    return Err(ParseError::UnknownPredicate(key.to_string()));
+                }
+                Predicate::KeyValue { key, val }
+                    if key == VERSION_KEY && RustcVersion::parse_predicate(val).is_none() =>
+                {
+                    return Err(ParseError::InvalidVersion(val.to_string()));
+                }
+                _ => {}
             }
         }
         Ok(Target::Spec(Arc::new(expr)))
@@ -94,16 +784,45 @@ impl Target {
 #[non_exhaustive]
 pub enum ParseError {
     /// This `cfg()` expression was invalid and could not be parsed.
-    InvalidCfg(String),
+    ///
+    /// The error records where in the expression parsing failed.
+    InvalidCfg(CfgParseError),
     /// The provided target triple was unknown.
     UnknownTriple(String),
     /// The provided `cfg()` expression parsed correctly, but it had an unknown predicate.
     UnknownPredicate(String),
+    /// The provided `cfg()` expression had a `version()` predicate with an invalid version.
+    ///
+    /// Versions must be in the form `major.minor` or `major.minor.patch`.
+    InvalidVersion(String),
+    /// The spec exceeded one of the limits in `ParseOptions`.
+    TooComplex(ParseLimit),
 }
 
 impl ParseError {
-    pub(crate) fn invalid_cfg(err: cfg_expr::ParseError<'_>) -> Self {
-        ParseError::InvalidCfg(format!("{}", err))
+    /// Renders this error for display to users, along with the caret diagnostic from
+    /// `CfgParseError::display_diagnostic` if the error is `InvalidCfg`.
+    ///
+    /// The `Display` implementation only prints a single line.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::TargetSpec;
+    ///
+    /// let err = "cfg(unix, windows)".parse::<TargetSpec>().unwrap_err();
+    /// assert_eq!(
+    ///     err.render(),
+    ///     "invalid cfg() expression: multiple root predicates (at byte 4)\n\
+    ///      cfg(unix, windows)\n    \
+    ///      ^^^^^^^^^^^^^ multiple root predicates",
+    /// );
+    /// ```
+    pub fn render(&self) -> String {
+        match self {
+            ParseError::InvalidCfg(err) => format!("{}\n{}", self, err.display_diagnostic()),
+            _ => self.to_string(),
+        }
     }
 }
 
@@ -115,11 +834,246 @@ impl fmt::Display for ParseError {
             ParseError::UnknownPredicate(pred) => {
                 write!(f, "cfg() expression has unknown predicate: {}", pred)
             }
+            ParseError::InvalidVersion(version) => {
+                write!(f, "cfg() expression has invalid version: \"{}\"", version)
+            }
+            ParseError::TooComplex(limit) => write!(f, "target spec is too complex: {}", limit),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::InvalidCfg(err) => Some(err),
+            ParseError::UnknownTriple(_)
+            | ParseError::UnknownPredicate(_)
+            | ParseError::InvalidVersion(_)
+            | ParseError::TooComplex(_) => None,
         }
     }
 }
 
-impl error::Error for ParseError {}
+/// An invalid `cfg()` expression, along with where in the expression parsing failed.
+///
+/// Part of `ParseError::InvalidCfg`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CfgParseError {
+    input: String,
+    span: Range<usize>,
+    kind: CfgParseErrorKind,
+    reason: String,
+}
+
+impl CfgParseError {
+    fn new(input: &str, rewritten: &RewrittenCfg<'_>, err: cfg_expr::ParseError<'_>) -> Self {
+        // cfg-expr reports spans relative to the part of the rewritten expression inside cfg().
+        let text = rewritten.as_str();
+        let offset = if text.starts_with("cfg(") && text.ends_with(')') {
+            4
+        } else {
+            0
+        };
+        let span = rewritten.original_span(err.span);
+
+        // Spans for invalid characters are a single byte long, so widen them to cover the whole
+        // character.
+        let mut end = (span.end + offset).min(input.len());
+        while !input.is_char_boundary(end) {
+            end += 1;
+        }
+        let mut start = (span.start + offset).min(end);
+        while !input.is_char_boundary(start) {
+            start -= 1;
+        }
+
+        Self {
+            input: input.to_string(),
+            span: start..end,
+            kind: CfgParseErrorKind::new(&err.reason),
+            reason: err.reason.to_string(),
+        }
+    }
+
+    /// Returns the expression that failed to parse.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Returns the byte range within the input where parsing failed.
+    ///
+    /// The range is relative to the start of the input, including the leading `cfg(`. It is empty
+    /// if the error is at a position rather than a token, for example if the input ended early.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Returns the kind of error this is, for matching on programmatically.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::{CfgParseErrorKind, ParseError, TargetSpec};
+    ///
+    /// let err = "cfg(not(unix, windows))".parse::<TargetSpec>().unwrap_err();
+    /// match err {
+    ///     ParseError::InvalidCfg(err) => assert_eq!(err.kind(), CfgParseErrorKind::InvalidNot(2)),
+    ///     other => panic!("unexpected error: {}", other),
+    /// }
+    /// ```
+    pub fn kind(&self) -> CfgParseErrorKind {
+        self.kind
+    }
+
+    /// Returns a description of why parsing failed, such as `unclosed parens`.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// Returns a formatter that renders the input, with a caret line underneath pointing at the
+    /// span and describing the error.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::{ParseError, TargetSpec};
+    ///
+    /// let err = "cfg(all(unix, = \"x\"))".parse::<TargetSpec>().unwrap_err();
+    /// let err = match err {
+    ///     ParseError::InvalidCfg(err) => err,
+    ///     other => panic!("unexpected error: {}", other),
+    /// };
+    /// assert_eq!(err.span(), 14..15);
+    /// assert_eq!(
+    ///     err.display_diagnostic().to_string(),
+    ///     "cfg(all(unix, = \"x\"))\n              ^ expected one of `)`, `<key>` here",
+    /// );
+    /// ```
+    pub fn display_diagnostic(&self) -> CfgDiagnostic<'_> {
+        CfgDiagnostic { err: self }
+    }
+
+    /// Returns the expected value closest to the unexpected one, if the error is
+    /// `CfgParseErrorKind::Unexpected` and the value looks like a misspelling.
+    ///
+    /// The suggestion is also shown by `display_diagnostic`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::{ParseError, TargetSpec};
+    ///
+    /// let err = "cfg(target_os = \"linx\")".parse::<TargetSpec>().unwrap_err();
+    /// match err {
+    ///     ParseError::InvalidCfg(err) => assert_eq!(err.suggestion(), Some("linux")),
+    ///     other => panic!("unexpected error: {}", other),
+    /// }
+    /// ```
+    pub fn suggestion(&self) -> Option<&'static str> {
+        match self.kind {
+            CfgParseErrorKind::Unexpected(expected) => {
+                // Only suggest names, not punctuation or placeholders like `<key>`.
+                let names = expected
+                    .iter()
+                    .copied()
+                    .filter(|name| name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+                suggest(&self.input[self.span.clone()], names)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CfgParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at byte {})", self.reason, self.span.start)
+    }
+}
+
+impl std::error::Error for CfgParseError {}
+
+/// The kind of a `CfgParseError`.
+///
+/// Returned by `CfgParseError::kind`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum CfgParseErrorKind {
+    /// `not()` was passed this number of predicates, rather than exactly one.
+    InvalidNot(usize),
+    /// The expression had characters that aren't valid in a `cfg()` expression.
+    InvalidCharacters,
+    /// An opening parenthesis wasn't matched by a closing one.
+    UnclosedParens,
+    /// A closing parenthesis wasn't matched by an opening one.
+    UnopenedParens,
+    /// An opening quote wasn't matched by a closing one.
+    UnclosedQuotes,
+    /// A closing quote wasn't matched by an opening one.
+    UnopenedQuotes,
+    /// The expression was empty.
+    Empty,
+    /// A term was found where one of these terms was expected.
+    Unexpected(&'static [&'static str]),
+    /// An integer value couldn't be parsed.
+    InvalidInteger,
+    /// `cfg()` had more than one predicate at its root.
+    MultipleRootPredicates,
+}
+
+impl CfgParseErrorKind {
+    fn new(reason: &Reason) -> Self {
+        match reason {
+            Reason::InvalidNot(count) => CfgParseErrorKind::InvalidNot(*count),
+            Reason::InvalidCharacters => CfgParseErrorKind::InvalidCharacters,
+            Reason::UnclosedParens => CfgParseErrorKind::UnclosedParens,
+            Reason::UnopenedParens => CfgParseErrorKind::UnopenedParens,
+            Reason::UnclosedQuotes => CfgParseErrorKind::UnclosedQuotes,
+            Reason::UnopenedQuotes => CfgParseErrorKind::UnopenedQuotes,
+            Reason::Empty => CfgParseErrorKind::Empty,
+            Reason::Unexpected(expected) => CfgParseErrorKind::Unexpected(expected),
+            Reason::InvalidInteger => CfgParseErrorKind::InvalidInteger,
+            Reason::MultipleRootPredicates => CfgParseErrorKind::MultipleRootPredicates,
+        }
+    }
+}
+
+/// A formatter for a `CfgParseError` as a caret diagnostic.
+///
+/// Returned by `CfgParseError::display_diagnostic`.
+#[derive(Clone, Copy, Debug)]
+pub struct CfgDiagnostic<'a> {
+    err: &'a CfgParseError,
+}
+
+impl<'a> fmt::Display for CfgDiagnostic<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let input = &self.err.input;
+        let span = &self.err.span;
+        // Count characters rather than bytes, so that the carets line up in a terminal.
+        let column = input[..span.start].chars().count();
+        let width = input[span.clone()].chars().count().max(1);
+        writeln!(f, "{}", input)?;
+        write!(
+            f,
+            "{:column$}{:^<width$} {}",
+            "",
+            "",
+            self.err.reason,
+            column = column,
+            width = width
+        )?;
+        if let Some(suggestion) = self.err.suggestion() {
+            write!(
+                f,
+                "\n{:column$}help: did you mean `{}`?",
+                "",
+                suggestion,
+                column = column
+            )?;
+        }
+        Ok(())
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -127,21 +1081,34 @@ mod tests {
     use cfg_expr::targets::{Family, Os};
     use cfg_expr::{Predicate, TargetPredicate};
 
+    #[test]
+    fn test_as_str() {
+        for input in &[
+            "x86_64-apple-darwin",
+            "cfg(any(unix, target_feature = \"sse\"))",
+        ] {
+            let spec: TargetSpec = input.parse().unwrap();
+            assert_eq!(spec.as_str(), *input);
+            assert_eq!(spec.to_string(), *input);
+        }
+    }
+
     #[test]
     fn test_triple() {
-        let res = Target::parse("x86_64-apple-darwin");
+        let res = Target::parse("x86_64-apple-darwin", &ParseOptions::default());
         assert!(matches!(
             res,
-            Ok(Target::TargetInfo(target_info)) if target_info.triple == "x86_64-apple-darwin"
+            Ok(Target::Builtin(target_info)) if target_info.triple == "x86_64-apple-darwin"
         ));
     }
 
     #[test]
     fn test_single() {
-        let expr = match Target::parse("cfg(windows)").unwrap() {
-            Target::TargetInfo(target_info) => {
+        let expr = match Target::parse("cfg(windows)", &ParseOptions::default()).unwrap() {
+            Target::Builtin(target_info) => {
                 panic!("expected spec, got target info: {:?}", target_info)
             }
+            Target::Custom(triple) => panic!("expected spec, got custom triple: {}", triple),
             Target::Spec(expr) => expr,
         };
         assert_eq!(
@@ -155,17 +1122,20 @@ mod tests {
     #[test]
     fn test_not() {
         assert!(matches!(
-            Target::parse("cfg(not(windows))"),
+            Target::parse("cfg(not(windows))", &ParseOptions::default()),
             Ok(Target::Spec(_))
         ));
     }
 
     #[test]
     fn test_testequal() {
-        let expr = match Target::parse("cfg(target_os = \"windows\")").unwrap() {
-            Target::TargetInfo(target_info) => {
+        let expr = match Target::parse("cfg(target_os = \"windows\")", &ParseOptions::default())
+            .unwrap()
+        {
+            Target::Builtin(target_info) => {
                 panic!("expected spec, got target info: {:?}", target_info)
             }
+            Target::Custom(triple) => panic!("expected spec, got custom triple: {}", triple),
             Target::Spec(expr) => expr,
         };
 
@@ -177,7 +1147,8 @@ mod tests {
 
     #[test]
     fn test_unknown_triple() {
-        let err = Target::parse("x86_64-pc-darwin").expect_err("unknown triple");
+        let err = Target::parse("x86_64-pc-darwin", &ParseOptions::default())
+            .expect_err("unknown triple");
         assert_eq!(
             err,
             ParseError::UnknownTriple("x86_64-pc-darwin".to_string())
@@ -186,10 +1157,11 @@ mod tests {
 
     #[test]
     fn test_unknown_flag() {
-        let expr = match Target::parse("cfg(foo)").unwrap() {
-            Target::TargetInfo(target_info) => {
+        let expr = match Target::parse("cfg(foo)", &ParseOptions::default()).unwrap() {
+            Target::Builtin(target_info) => {
                 panic!("expected spec, got target info: {:?}", target_info)
             }
+            Target::Custom(triple) => panic!("expected spec, got custom triple: {}", triple),
             Target::Spec(expr) => expr,
         };
 
@@ -201,20 +1173,359 @@ mod tests {
 
     #[test]
     fn test_unknown_predicate() {
-        let err = Target::parse("cfg(bogus_key = \"bogus_value\")").expect_err("unknown predicate");
+        let err = Target::parse("cfg(bogus_key = \"bogus_value\")", &ParseOptions::default())
+            .expect_err("unknown predicate");
         assert_eq!(err, ParseError::UnknownPredicate("bogus_key".to_string()));
     }
 
+    #[test]
+    fn test_target_abi() {
+        let spec: TargetSpec = "cfg(target_abi = \"eabihf\")".parse().unwrap();
+        assert_eq!(spec.as_str(), "cfg(target_abi = \"eabihf\")");
+        Target::parse("cfg(target_abi)", &ParseOptions::default())
+            .expect_err("target_abi needs a value");
+    }
+
+    #[test]
+    fn test_target_has_atomic() {
+        let spec: TargetSpec = "cfg(all(unix, target_has_atomic = \"64\"))"
+            .parse()
+            .unwrap();
+        assert_eq!(spec.as_str(), "cfg(all(unix, target_has_atomic = \"64\"))");
+    }
+
     #[test]
     fn test_extra() {
-        let res = Target::parse("cfg(unix)this-is-extra");
+        let res = Target::parse("cfg(unix)this-is-extra", &ParseOptions::default());
         res.expect_err("extra content at the end");
     }
 
     #[test]
     fn test_incomplete() {
         // This fails because the ) at the end is missing.
-        let res = Target::parse("cfg(not(unix)");
+        let res = Target::parse("cfg(not(unix)", &ParseOptions::default());
         res.expect_err("missing ) at the end");
     }
+
+    #[test]
+    fn test_invalid_cfg_span() {
+        fn invalid_cfg(input: &str) -> CfgParseError {
+            match Target::parse(input, &ParseOptions::default()) {
+                Err(ParseError::InvalidCfg(err)) => err,
+                other => panic!("{}: expected invalid cfg, got {:?}", input, other),
+            }
+        }
+
+        let err = invalid_cfg("cfg(not(unix)");
+        assert_eq!(err.input(), "cfg(not(unix)");
+        assert_eq!(err.span(), 7..12);
+        assert_eq!(err.kind(), CfgParseErrorKind::UnclosedParens);
+        assert_eq!(err.reason(), "unclosed parens");
+        assert_eq!(
+            err.display_diagnostic().to_string(),
+            "cfg(not(unix)\n       ^^^^^ unclosed parens"
+        );
+
+        // Spans point into the input as written, not the rewritten expression.
+        let err = invalid_cfg("cfg(all(target_abi = \"x\", = \"y\"))");
+        assert_eq!(err.span(), 26..27);
+
+        // Spans cover whole characters, and carets are aligned by character.
+        let err = invalid_cfg("cfg(all(é, ü))");
+        assert_eq!(err.span(), 8..10);
+        assert!(matches!(
+            err.kind(),
+            CfgParseErrorKind::Unexpected(expected) if expected.contains(&"all")
+        ));
+        assert_eq!(
+            err.display_diagnostic().to_string(),
+            "cfg(all(é, ü))\n        ^ expected one of `<key>`, `all`, `any`, `not` here"
+        );
+
+        // Misspelled values come with a suggestion.
+        let err = invalid_cfg("cfg(target_arch = \"x86-64\")");
+        assert_eq!(err.suggestion(), Some("x86_64"));
+        assert!(err
+            .display_diagnostic()
+            .to_string()
+            .ends_with("\n                   help: did you mean `x86_64`?"));
+        assert_eq!(
+            invalid_cfg("cfg(target_os = \"bogus_os\")").suggestion(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_error_source() {
+        use std::error::Error;
+
+        let err = "cfg(unix, windows)".parse::<TargetSpec>().unwrap_err();
+        let source = err
+            .source()
+            .expect("invalid cfg() expressions have a source");
+        let source = source
+            .downcast_ref::<CfgParseError>()
+            .expect("source is a CfgParseError");
+        assert_eq!(source.kind(), CfgParseErrorKind::MultipleRootPredicates);
+        // The diagnostic is only shown by render, so Display stays on one line.
+        assert!(!err.to_string().contains('\n'), "{}", err);
+        assert_eq!(
+            err.render(),
+            format!("{}\n{}", err, source.display_diagnostic())
+        );
+
+        let err = "x86_64-pc-darwin".parse::<TargetSpec>().unwrap_err();
+        assert!(err.source().is_none());
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        let nested =
+            |depth: usize| format!("cfg({}unix{})", "not(".repeat(depth), ")".repeat(depth));
+        let max_depth = ParseOptions::DEFAULT_MAX_DEPTH;
+        let spec: TargetSpec = nested(max_depth).parse().unwrap();
+        assert_eq!(
+            nested(max_depth + 1).parse::<TargetSpec>().unwrap_err(),
+            ParseError::TooComplex(ParseLimit::Depth(max_depth))
+        );
+        // Far deeper than the parser could recurse into.
+        assert_eq!(
+            nested(1_000_000).parse::<TargetSpec>().unwrap_err(),
+            ParseError::TooComplex(ParseLimit::Length(ParseOptions::DEFAULT_MAX_LENGTH))
+        );
+
+        // Specs built from specs within the limits aren't limited.
+        assert!(spec.union(&"cfg(windows)".parse().unwrap()).is_some());
+
+        let mut options = ParseOptions::new();
+        options.set_max_length(16).set_max_depth(0);
+        assert!(TargetSpec::parse_with_options("cfg(unix)", &options).is_ok());
+        assert_eq!(
+            TargetSpec::parse_with_options("cfg(all(unix))", &options),
+            Err(ParseError::TooComplex(ParseLimit::Depth(0)))
+        );
+        assert_eq!(
+            TargetSpec::parse_with_options("x86_64-unknown-linux-gnu", &options),
+            Err(ParseError::TooComplex(ParseLimit::Length(16)))
+        );
+        // Syntax errors are still reported by the parser.
+        options.set_max_depth(4);
+        assert!(matches!(
+            TargetSpec::parse_with_options("cfg(all(\"x))", &options),
+            Err(ParseError::InvalidCfg(_))
+        ));
+    }
+
+    #[test]
+    fn test_predicates() {
+        fn predicates(spec: &TargetSpec) -> Vec<SpecPredicate<'_>> {
+            spec.predicates().collect()
+        }
+
+        let spec: TargetSpec = "x86_64-pc-windows-msvc".parse().unwrap();
+        assert_eq!(
+            predicates(&spec),
+            vec![SpecPredicate::Triple("x86_64-pc-windows-msvc")]
+        );
+        #[cfg(feature = "custom")]
+        {
+            let custom = TargetSpec::custom("thumbv7em-custom");
+            assert_eq!(
+                predicates(&custom),
+                vec![SpecPredicate::Triple("thumbv7em-custom")]
+            );
+        }
+
+        let spec: TargetSpec =
+            "cfg(any(unix, all(target_abi=\"eabihf\", target_feature = \"neon\"), \
+                                feature = \"std\", my_flag, unix))"
+                .parse()
+                .unwrap();
+        let expected = vec![
+            SpecPredicate::Target {
+                key: "target_family",
+                value: "unix",
+            },
+            SpecPredicate::Target {
+                key: "target_abi",
+                value: "eabihf",
+            },
+            SpecPredicate::TargetFeature("neon"),
+            SpecPredicate::Feature("std"),
+            SpecPredicate::Flag("my_flag"),
+            SpecPredicate::Target {
+                key: "target_family",
+                value: "unix",
+            },
+        ];
+        assert_eq!(predicates(&spec), expected);
+        assert_eq!(expected[1].to_string(), "target_abi = \"eabihf\"");
+        assert_eq!(expected[2].key(), Some("target_feature"));
+        assert_eq!(expected[4].to_string(), "my_flag");
+        assert_eq!(expected[4].value(), None);
+
+        let spec: TargetSpec = "cfg(not(version(\"1.50\")))".parse().unwrap();
+        assert_eq!(predicates(&spec), vec![SpecPredicate::RustcVersion("1.50")]);
+        assert_eq!(predicates(&spec)[0].to_string(), "version(\"1.50\")");
+    }
+
+    #[test]
+    fn test_display_canonical() {
+        for (input, expected) in &[
+            ("x86_64-pc-windows-msvc", "x86_64-pc-windows-msvc"),
+            ("cfg(unix)", "cfg(unix)"),
+            ("cfg(all())", "cfg(all())"),
+            (
+                "cfg(  all( unix,not(target_env=\"musl\"),  ) )",
+                "cfg(all(unix, not(target_env = \"musl\")))",
+            ),
+            (
+                "cfg(any(target_abi = \"eabihf\" ,target_has_atomic=\"64\"))",
+                "cfg(any(target_abi = \"eabihf\", target_has_atomic = \"64\"))",
+            ),
+        ] {
+            let spec: TargetSpec = input.parse().unwrap();
+            assert_eq!(spec.to_string(), *input, "Display is lossless");
+            let canonical = spec.display_canonical().to_string();
+            assert_eq!(canonical, *expected);
+
+            let reparsed: TargetSpec = canonical.parse().unwrap();
+            assert_eq!(reparsed.display_canonical().to_string(), canonical);
+            assert!(spec.implies(&reparsed) && reparsed.implies(&spec));
+        }
+
+        #[cfg(feature = "custom")]
+        {
+            let custom = TargetSpec::custom("thumbv7em-custom");
+            assert_eq!(custom.display_canonical().to_string(), "thumbv7em-custom");
+        }
+    }
+
+    #[test]
+    fn test_matching_triples() {
+        let matching = |spec: &str| {
+            spec.parse::<TargetSpec>()
+                .unwrap()
+                .matching_triples()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            matching("i686-pc-windows-msvc"),
+            vec!["i686-pc-windows-msvc"]
+        );
+        let windows = matching("cfg(windows)");
+        assert!(windows.contains(&"x86_64-pc-windows-gnu"));
+        assert!(windows.iter().all(|triple| triple.contains("windows")));
+        assert_eq!(
+            matching(
+                "cfg(all(target_os = \"linux\", target_env = \"musl\", target_arch = \"x86_64\"))"
+            ),
+            vec!["x86_64-unknown-linux-musl"]
+        );
+        // Results that depend on target features or flags aren't known.
+        assert!(matching("cfg(target_feature = \"sse2\")").is_empty());
+        assert!(matching("cfg(my_flag)").is_empty());
+        assert_eq!(matching("cfg(not(my_flag))").len(), ALL_TARGETS.len());
+        #[cfg(feature = "custom")]
+        {
+            assert!(TargetSpec::custom("thumbv7em-custom")
+                .matching_triples()
+                .next()
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn test_eq_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashSet;
+
+        let hash = |spec: &TargetSpec| {
+            let mut hasher = DefaultHasher::new();
+            spec.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        for (a, b) in &[
+            ("cfg(any(unix, windows))", "cfg(any(windows, unix))"),
+            ("cfg(unix)", "cfg(all(not(not(unix)), any(unix, unix)))"),
+            ("cfg(target_os=\"linux\")", "cfg( target_os = \"linux\", )"),
+            ("x86_64-unknown-linux-gnu", "x86_64-unknown-linux-gnu"),
+            ("cfg(all(unix, windows))", "cfg(any())"),
+            ("cfg(any(unix, not(unix)))", "cfg(all())"),
+            ("cfg(feature = \"foo\")", "cfg(any())"),
+        ] {
+            let a: TargetSpec = a.parse().unwrap();
+            let b: TargetSpec = b.parse().unwrap();
+            assert_eq!(a, b);
+            assert_eq!(hash(&a), hash(&b), "{} and {} hash the same", a, b);
+        }
+
+        for (a, b) in &[
+            ("cfg(unix)", "cfg(windows)"),
+            ("cfg(any(unix, windows))", "cfg(all(unix, windows))"),
+            ("cfg(target_os = \"linux\")", "x86_64-unknown-linux-gnu"),
+            // These are equivalent, but simplify doesn't apply absorption.
+            ("cfg(any(unix, all(unix, windows)))", "cfg(unix)"),
+        ] {
+            let a: TargetSpec = a.parse().unwrap();
+            let b: TargetSpec = b.parse().unwrap();
+            assert_ne!(a, b);
+        }
+        #[cfg(feature = "custom")]
+        {
+            assert_ne!(
+                TargetSpec::custom("x86_64-unknown-linux-gnu"),
+                "x86_64-unknown-linux-gnu".parse().unwrap()
+            );
+        }
+
+        let specs: HashSet<TargetSpec> = [
+            "cfg(any(unix, windows))",
+            "cfg(any(windows, unix))",
+            "cfg(unix)",
+        ]
+        .iter()
+        .map(|spec| spec.parse().unwrap())
+        .collect();
+        assert_eq!(specs.len(), 2);
+    }
+
+    #[test]
+    fn test_coverage() {
+        let coverage = |spec: &str| spec.parse::<TargetSpec>().unwrap().coverage();
+
+        for spec in &[
+            "cfg(all(windows, target_os = \"linux\"))",
+            "cfg(all(unix, not(unix)))",
+            "cfg(any())",
+            "cfg(feature = \"foo\")",
+        ] {
+            assert_eq!(coverage(spec), SpecCoverage::Never, "{}", spec);
+        }
+        for spec in &[
+            "cfg(all())",
+            "cfg(not(all(windows, unix)))",
+            "cfg(any(target_endian = \"little\", target_endian = \"big\"))",
+        ] {
+            assert_eq!(coverage(spec), SpecCoverage::Always, "{}", spec);
+        }
+        for spec in &[
+            "x86_64-unknown-linux-gnu",
+            "cfg(unix)",
+            "cfg(test)",
+            "cfg(not(my_flag))",
+            "cfg(target_feature = \"sse2\")",
+        ] {
+            assert_eq!(coverage(spec), SpecCoverage::Sometimes, "{}", spec);
+        }
+        #[cfg(feature = "custom")]
+        {
+            assert_eq!(
+                TargetSpec::custom("thumbv7em-custom").coverage(),
+                SpecCoverage::Sometimes
+            );
+        }
+    }
 }
@@ -1,8 +1,24 @@
 // Copyright (c) The cargo-guppy Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use cfg_expr::targets::{get_target_by_triple, TargetInfo};
+use crate::abi::builtin_abi;
+use crate::alias::builtin_target;
+use crate::atomic::{builtin_max_atomic_width, AtomicWidths};
+use crate::compat::can_run;
+use crate::cpu::{default_target_cpu, target_cpu_features};
+#[cfg(feature = "custom")]
+use crate::custom::{custom_target, CustomTargetError};
+use crate::custom::{registered_target, CustomTarget};
+use crate::heuristic::{heuristic_target, HeuristicTarget};
+use crate::rustc_cfg::{build_script_flags, rustc_cfg, RustcCfgError};
+use crate::{
+    Endian, PlatformBuilder, RustcVersion, TargetArch, TargetEnv, TargetFamily, TargetOs,
+    TargetVendor,
+};
+use cfg_expr::expr::TargetPredicate;
+use cfg_expr::targets::{Arch, Os, TargetInfo};
 use std::collections::HashSet;
+use std::sync::Arc;
 
 // This is generated by the build script.
 include!(concat!(env!("OUT_DIR"), "/current_platform.rs"));
@@ -10,20 +26,147 @@ include!(concat!(env!("OUT_DIR"), "/current_platform.rs"));
 /// A platform to evaluate target specs against.
 #[derive(Clone, Debug)]
 pub struct Platform<'a> {
-    target_info: &'a TargetInfo,
+    target: PlatformTarget<'a>,
     target_features: TargetFeatures<'a>,
     flags: HashSet<&'a str>,
+    rustc_version: Option<RustcVersion>,
 }
 
 impl<'a> Platform<'a> {
     /// Creates a new `Platform` from the given triple and target features.
     ///
+    /// Other spellings of builtin triples, such as `wasm32-wasip1`, are accepted as well: see
+    /// `canonical_triple`.
+    ///
+    /// Custom targets registered with `register_custom_target` are accepted too, and produce
+    /// custom platforms.
+    ///
     /// Returns `None` if this platform wasn't known to `target-spec`.
     pub fn new(triple: impl AsRef<str>, target_features: TargetFeatures<'a>) -> Option<Self> {
+        let triple = triple.as_ref();
+        let target = match builtin_target(triple) {
+            Some(target_info) => PlatformTarget::Builtin(target_info),
+            None => PlatformTarget::Custom(registered_target(triple)?),
+        };
         Some(Self {
-            target_info: get_target_by_triple(triple.as_ref())?,
+            target,
+            target_features,
+            flags: HashSet::new(),
+            rustc_version: None,
+        })
+    }
+
+    /// Creates a new `Platform` from the given triple and target features, guessing its properties
+    /// if the triple isn't known to `target-spec`.
+    ///
+    /// Known triples produce the same platform as `new`. For other triples, such as tier 3 targets
+    /// added to rustc after this version of `target-spec`, the triple is split into its
+    /// architecture, vendor, operating system and environment components. Each property checked
+    /// by `cfg()` expressions is then looked up among the builtin targets that share the relevant
+    /// component: for example, `riscv64gc-unknown-freebsd` has the operating system and family of
+    /// the other FreeBSD targets. Properties that the builtin targets don't agree on are unknown,
+    /// and specs that check them evaluate to `None`; `TargetSpec::eval_result` reports which
+    /// properties those were.
+    pub fn new_heuristic(triple: impl AsRef<str>, target_features: TargetFeatures<'a>) -> Self {
+        let triple = triple.as_ref();
+        let target = match (builtin_target(triple), registered_target(triple)) {
+            (Some(target_info), _) => PlatformTarget::Builtin(target_info),
+            (None, Some(custom)) => PlatformTarget::Custom(custom),
+            (None, None) => PlatformTarget::Heuristic(Arc::new(heuristic_target(triple))),
+        };
+        Self {
+            target,
+            target_features,
+            flags: HashSet::new(),
+            rustc_version: None,
+        }
+    }
+
+    /// Creates a new `Platform` from a rustc
+    /// [custom target spec](https://doc.rust-lang.org/rustc/targets/custom.html), along with the
+    /// given target features.
+    ///
+    /// `triple` is the name of the target, which rustc and Cargo take from the file name: for
+    /// `thumbv7em-custom.json`, it is `thumbv7em-custom`. Triple-based specs match this name; see
+    /// `TargetSpec::custom`.
+    ///
+    /// Only the keys that affect `cfg()` expressions are read: `arch`, `target-pointer-width`,
+    /// `os`, `env`, `vendor`, `target-family`, `target-endian`, `abi`, `min-atomic-width` and
+    /// `max-atomic-width`. An `os` of `"none"`, as used by bare-metal targets, matches
+    /// `target_os = ""`.
+    ///
+    /// Returns an error if the spec couldn't be parsed, or if it has values unknown to
+    /// `target-spec`.
+    ///
+    /// Requires the `custom` feature.
+    #[cfg(feature = "custom")]
+    pub fn from_json(
+        triple: impl AsRef<str>,
+        json: impl AsRef<str>,
+        target_features: TargetFeatures<'a>,
+    ) -> Result<Self, CustomTargetError> {
+        let custom = custom_target(triple.as_ref(), json.as_ref())?;
+        Ok(Self {
+            target: PlatformTarget::Custom(Arc::new(custom)),
             target_features,
             flags: HashSet::new(),
+            rustc_version: None,
+        })
+    }
+
+    /// Creates a new `Platform` from the target spec that an installed toolchain has for this
+    /// triple, along with the given target features.
+    ///
+    /// This runs `rustc +<toolchain> --print target-spec-json -Z unstable-options`, so it works for
+    /// targets that aren't builtin yet, as long as their properties are known to `target-spec`.
+    /// See `target_spec_json` for how rustc is run. The spec is read as with `from_json`, so the
+    /// result is a custom platform.
+    ///
+    /// Requires the `toolchain` feature.
+    #[cfg(feature = "toolchain")]
+    pub fn from_toolchain(
+        toolchain: Option<&str>,
+        triple: impl AsRef<str>,
+        target_features: TargetFeatures<'a>,
+    ) -> Result<Self, crate::ToolchainError> {
+        let triple = triple.as_ref();
+        let json = crate::target_spec_json(toolchain, triple)?;
+        Self::from_json(triple, json, target_features).map_err(crate::ToolchainError::InvalidSpec)
+    }
+
+    /// Returns a builder for a platform with this triple, whose properties can be overridden.
+    ///
+    /// This is useful for evaluating specs against hypothetical platforms, or targets that differ
+    /// slightly from the nearest builtin one. For more, see `PlatformBuilder`.
+    pub fn builder(triple: impl Into<String>) -> PlatformBuilder<'a> {
+        PlatformBuilder::new(triple)
+    }
+
+    /// Creates a new `Platform` from the output of `rustc --print cfg`, so that evaluation
+    /// results match the compiler's.
+    ///
+    /// The output doesn't include a target triple, so it must be passed in: this is the
+    /// `--target` passed to rustc, or the host triple it reports with `rustc -vV` if there was none.
+    /// Pass the same `RUSTFLAGS` and `-C target-feature` options to rustc as the build would.
+    ///
+    /// Target features are taken from `target_feature` lines, and names without values, like
+    /// `unix` or `debug_assertions`, are added as flags. Other keys that `target-spec` can't
+    /// evaluate, like `panic`, are ignored.
+    ///
+    /// As with `from_json`, the result is a custom platform.
+    ///
+    /// Returns an error if the output couldn't be parsed, if it was missing `target_arch`,
+    /// `target_endian` or `target_pointer_width`, or if it has values unknown to `target-spec`.
+    pub fn from_rustc_cfg_output(
+        triple: impl AsRef<str>,
+        output: &'a str,
+    ) -> Result<Self, RustcCfgError> {
+        let cfg = rustc_cfg(triple.as_ref(), output)?;
+        Ok(Self {
+            target: PlatformTarget::Custom(Arc::new(cfg.target)),
+            target_features: TargetFeatures::features(&cfg.target_features),
+            flags: cfg.flags.into_iter().collect(),
+            rustc_version: None,
         })
     }
 
@@ -38,9 +181,318 @@ impl<'a> Platform<'a> {
         self.flags.extend(flags);
     }
 
+    /// Adds the flags that a build script sets, given its output.
+    ///
+    /// Lines of the form `cargo:rustc-cfg=foo` (or `cargo::rustc-cfg=foo`) add `foo` as a flag,
+    /// so that specs can be evaluated as they would be for a crate whose build script is known to
+    /// set it. Other lines are ignored. Key-value cfgs like `cargo:rustc-cfg=foo="bar"` are
+    /// skipped as well: `target-spec` only parses the keys rustc itself sets.
+    ///
+    /// Returns an error, without adding any flags, if a `cargo:rustc-cfg` directive is malformed.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::{Platform, TargetFeatures, TargetSpec};
+    ///
+    /// let mut platform = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+    /// platform
+    ///     .add_build_script_cfgs("cargo:rerun-if-changed=build.rs\ncargo:rustc-cfg=has_atomics\n")
+    ///     .unwrap();
+    ///
+    /// let spec: TargetSpec = "cfg(all(unix, has_atomics))".parse().unwrap();
+    /// assert_eq!(spec.eval(&platform), Some(true));
+    /// ```
+    pub fn add_build_script_cfgs(&mut self, output: &'a str) -> Result<(), RustcCfgError> {
+        let flags = build_script_flags(output)?;
+        self.flags.extend(flags);
+        Ok(())
+    }
+
+    /// Replaces the target features of this platform.
+    pub fn set_target_features(&mut self, target_features: TargetFeatures<'a>) {
+        self.target_features = target_features;
+    }
+
+    /// Sets the rustc version to evaluate `cfg(version("..."))` predicates against.
+    ///
+    /// If no version is set, `version()` predicates evaluate to unknown, and
+    /// `UnknownReason::rustc_version` is true.
+    pub fn set_rustc_version(&mut self, version: Option<RustcVersion>) {
+        self.rustc_version = version;
+    }
+
+    /// Returns the rustc version set with `set_rustc_version`.
+    pub fn rustc_version(&self) -> Option<RustcVersion> {
+        self.rustc_version
+    }
+
     /// Returns the target triple for this platform.
-    pub fn triple(&self) -> &'static str {
-        self.target_info.triple
+    ///
+    /// For custom platforms, this is the name passed into `Platform::from_json`.
+    pub fn triple(&self) -> &str {
+        match &self.target {
+            PlatformTarget::Builtin(target_info) => target_info.triple,
+            PlatformTarget::Custom(custom) => &custom.triple,
+            PlatformTarget::Heuristic(heuristic) => &heuristic.triple,
+        }
+    }
+
+    /// Returns the architecture of this platform, as matched by `cfg(target_arch = "...")`.
+    ///
+    /// Returns `None` if this is a guessed platform whose architecture is unknown.
+    pub fn target_arch(&self) -> Option<TargetArch> {
+        let arch = match &self.target {
+            PlatformTarget::Builtin(target_info) => target_info.arch,
+            PlatformTarget::Custom(custom) => custom.target_info.arch,
+            PlatformTarget::Heuristic(heuristic) => heuristic.arch()?,
+        };
+        Some(TargetArch::new(arch))
+    }
+
+    /// Returns the operating system of this platform, as matched by `cfg(target_os = "...")`.
+    ///
+    /// Returns `None` if the platform has no operating system, or if this is a guessed platform
+    /// whose operating system is unknown.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::{Endian, Platform, TargetArch, TargetEnv, TargetFeatures, TargetOs};
+    ///
+    /// let platform = Platform::new("aarch64-unknown-linux-musl", TargetFeatures::Unknown).unwrap();
+    /// assert_eq!(platform.target_arch(), Some(TargetArch::Aarch64));
+    /// assert_eq!(platform.target_os(), Some(TargetOs::Linux));
+    /// assert_eq!(platform.target_env(), Some(TargetEnv::Musl));
+    /// assert_eq!(platform.endian(), Some(Endian::Little));
+    /// assert_eq!(platform.pointer_width(), Some(64));
+    ///
+    /// let bare_metal = Platform::new("thumbv7em-none-eabi", TargetFeatures::Unknown).unwrap();
+    /// assert_eq!(bare_metal.target_os(), None);
+    /// ```
+    pub fn target_os(&self) -> Option<TargetOs> {
+        self.target_property(|target_info| target_info.os, HeuristicTarget::os)
+            .map(TargetOs::new)
+    }
+
+    /// Returns the environment of this platform, as matched by `cfg(target_env = "...")`.
+    ///
+    /// Returns `None` if the platform has no environment, or if this is a guessed platform
+    /// whose environment is unknown.
+    pub fn target_env(&self) -> Option<TargetEnv> {
+        self.target_property(|target_info| target_info.env, HeuristicTarget::env)
+            .map(TargetEnv::new)
+    }
+
+    /// Returns the vendor of this platform, as matched by `cfg(target_vendor = "...")`.
+    ///
+    /// Returns `None` if the platform has no vendor, or if this is a guessed platform whose
+    /// vendor is unknown.
+    pub fn target_vendor(&self) -> Option<TargetVendor> {
+        self.target_property(|target_info| target_info.vendor, HeuristicTarget::vendor)
+            .map(TargetVendor::new)
+    }
+
+    /// Returns the family of this platform, as matched by `cfg(target_family = "...")`.
+    ///
+    /// Returns `None` if the platform has no family, or if this is a guessed platform whose
+    /// family is unknown. The `wasm` family isn't returned: use `is_wasm` to check for it.
+    pub fn target_family(&self) -> Option<TargetFamily> {
+        self.target_property(|target_info| target_info.family, HeuristicTarget::family)
+            .map(TargetFamily::new)
+    }
+
+    /// Returns the byte order of this platform, as matched by `cfg(target_endian = "...")`.
+    ///
+    /// Returns `None` if this is a guessed platform whose byte order is unknown.
+    pub fn endian(&self) -> Option<Endian> {
+        let endian = match &self.target {
+            PlatformTarget::Builtin(target_info) => target_info.endian,
+            PlatformTarget::Custom(custom) => custom.target_info.endian,
+            PlatformTarget::Heuristic(heuristic) => heuristic.endian()?,
+        };
+        Some(Endian::new(endian))
+    }
+
+    /// Returns the pointer width of this platform in bits, as matched by
+    /// `cfg(target_pointer_width = "...")`.
+    ///
+    /// Returns `None` if this is a guessed platform whose pointer width is unknown.
+    pub fn pointer_width(&self) -> Option<u8> {
+        match &self.target {
+            PlatformTarget::Builtin(target_info) => Some(target_info.pointer_width),
+            PlatformTarget::Custom(custom) => Some(custom.target_info.pointer_width),
+            PlatformTarget::Heuristic(heuristic) => heuristic.pointer_width(),
+        }
+    }
+
+    /// Returns an optional property of this platform, or `None` if the platform doesn't have it
+    /// or if it's unknown.
+    fn target_property<T>(
+        &self,
+        builtin: impl FnOnce(&TargetInfo) -> Option<T>,
+        heuristic: impl FnOnce(&HeuristicTarget) -> Option<Option<T>>,
+    ) -> Option<T> {
+        match &self.target {
+            PlatformTarget::Builtin(target_info) => builtin(target_info),
+            PlatformTarget::Custom(custom) => builtin(&custom.target_info),
+            PlatformTarget::Heuristic(target) => heuristic(target).flatten(),
+        }
+    }
+
+    /// Returns the ABI for this platform, as matched by `cfg(target_abi = "...")`.
+    ///
+    /// This is empty for most platforms. For custom platforms, it is the `abi` key in the spec. For
+    /// guessed platforms, it is empty if the ABI is unknown.
+    pub fn abi(&self) -> &str {
+        self.abi_if_known().unwrap_or("")
+    }
+
+    /// Returns the ABI for this platform, or `None` if it's a guessed platform with an unknown ABI.
+    pub(crate) fn abi_if_known(&self) -> Option<&str> {
+        match &self.target {
+            PlatformTarget::Builtin(target_info) => Some(builtin_abi(target_info.triple)),
+            PlatformTarget::Custom(custom) => Some(&custom.abi),
+            PlatformTarget::Heuristic(heuristic) => heuristic.abi(),
+        }
+    }
+
+    /// Returns true if this platform supports atomics of this width, as matched by
+    /// `cfg(target_has_atomic = "...")`.
+    ///
+    /// The width is a size in bits such as `"64"`, or `"ptr"` for pointer-sized atomics. For custom
+    /// platforms, the supported widths are read from the `min-atomic-width` and
+    /// `max-atomic-width` keys in the spec. For guessed platforms, this returns false if it's
+    /// unknown.
+    pub fn has_atomic(&self, width: impl AsRef<str>) -> bool {
+        self.has_atomic_if_known(width.as_ref()).unwrap_or(false)
+    }
+
+    /// Returns whether this platform supports atomics of this width, or `None` if it's a guessed
+    /// platform for which that's unknown.
+    pub(crate) fn has_atomic_if_known(&self, width: &str) -> Option<bool> {
+        let (atomic_widths, pointer_width) = match &self.target {
+            PlatformTarget::Builtin(target_info) => (
+                AtomicWidths {
+                    min: 8,
+                    max: builtin_max_atomic_width(target_info.triple),
+                },
+                target_info.pointer_width,
+            ),
+            PlatformTarget::Custom(custom) => {
+                (custom.atomic_widths, custom.target_info.pointer_width)
+            }
+            PlatformTarget::Heuristic(heuristic) => return heuristic.has_atomic(width),
+        };
+        Some(atomic_widths.has_atomic(width, pointer_width))
+    }
+
+    /// Returns whether this target predicate matches, or `None` if it's a guessed platform for
+    /// which the property the predicate checks is unknown.
+    pub(crate) fn matches_target(&self, predicate: TargetPredicate) -> Option<bool> {
+        match &self.target {
+            PlatformTarget::Builtin(target_info) => Some(predicate.matches(target_info)),
+            PlatformTarget::Custom(custom) => Some(predicate.matches(&custom.target_info)),
+            PlatformTarget::Heuristic(heuristic) => heuristic.matches(predicate),
+        }
+    }
+
+    /// Returns true if this platform is in the `wasm` family, as matched by
+    /// `cfg(target_family = "wasm")`.
+    ///
+    /// This includes both targets without an operating system, such as `wasm32-unknown-unknown`
+    /// for browsers, and WASI targets: use `wasi_version` to tell them apart. For guessed
+    /// platforms, this returns false if it's unknown.
+    pub fn is_wasm(&self) -> bool {
+        self.is_wasm_if_known().unwrap_or(false)
+    }
+
+    /// Returns whether this platform is in the `wasm` family, or `None` if it's a guessed platform
+    /// for which that's unknown.
+    pub(crate) fn is_wasm_if_known(&self) -> Option<bool> {
+        match &self.target {
+            PlatformTarget::Builtin(target_info) => Some(target_info.arch == Arch::wasm32),
+            PlatformTarget::Custom(custom) => Some(custom.target_info.arch == Arch::wasm32),
+            PlatformTarget::Heuristic(heuristic) => heuristic.is_wasm(),
+        }
+    }
+
+    /// Returns the version of WASI this platform targets, or `None` if it doesn't target WASI.
+    ///
+    /// `wasm32-wasi` and `wasm32-wasip1` target preview 1, while triples like `wasm32-wasip2`,
+    /// which can be created with `Platform::new_heuristic`, target preview 2.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::{Platform, TargetFeatures, WasiVersion};
+    ///
+    /// let browser = Platform::new("wasm32-unknown-unknown", TargetFeatures::Unknown).unwrap();
+    /// assert!(browser.is_wasm());
+    /// assert_eq!(browser.wasi_version(), None);
+    ///
+    /// let wasip2 = Platform::new_heuristic("wasm32-wasip2", TargetFeatures::Unknown);
+    /// assert!(wasip2.is_wasm());
+    /// assert_eq!(wasip2.wasi_version(), Some(WasiVersion::Preview2));
+    /// ```
+    pub fn wasi_version(&self) -> Option<WasiVersion> {
+        if self.matches_target(TargetPredicate::Os(Some(Os::wasi))) != Some(true) {
+            return None;
+        }
+        if self
+            .triple()
+            .split('-')
+            .any(|component| component == "wasip2")
+        {
+            Some(WasiVersion::Preview2)
+        } else {
+            Some(WasiVersion::Preview1)
+        }
+    }
+
+    /// Returns true if this platform wasn't one of the builtin targets known to `target-spec`, but
+    /// was instead created with `from_json` or `from_rustc_cfg_output`.
+    pub fn is_custom(&self) -> bool {
+        match self.target {
+            PlatformTarget::Builtin(_) | PlatformTarget::Heuristic(_) => false,
+            PlatformTarget::Custom(_) => true,
+        }
+    }
+
+    /// Returns true if this platform's properties were guessed from its triple by `new_heuristic`,
+    /// since the triple wasn't known to `target-spec`.
+    pub fn is_heuristic(&self) -> bool {
+        match self.target {
+            PlatformTarget::Builtin(_) | PlatformTarget::Custom(_) => false,
+            PlatformTarget::Heuristic(_) => true,
+        }
+    }
+
+    /// Returns true if binaries built for `other` can run on this platform, as far as their
+    /// properties tell.
+    ///
+    /// Binaries run on platforms with the same OS and byte order, and either the same
+    /// architecture and `target_abi`, or a 64-bit version of the architecture (`x86_64` hosts run `x86` binaries except
+    /// on macOS, and `aarch64` Linux hosts run `arm` binaries). On Linux, musl binaries are
+    /// statically linked and run on hosts with any environment, while other environments must
+    /// match. On Windows, MSVC and MinGW binaries run on either.
+    ///
+    /// This doesn't account for emulation, like Rosetta or qemu, or for target features and OS
+    /// versions. Platforms without an OS, and platforms whose properties are unknown, can only run
+    /// binaries built for their own triple.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::{Platform, TargetFeatures};
+    ///
+    /// let glibc = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+    /// let musl = Platform::new("x86_64-unknown-linux-musl", TargetFeatures::Unknown).unwrap();
+    /// assert!(glibc.can_run(&musl));
+    /// assert!(!musl.can_run(&glibc));
+    /// ```
+    pub fn can_run(&self, other: &Platform<'_>) -> bool {
+        can_run(self, other)
     }
 
     /// Returns true if this flag was set with `add_flags`.
@@ -48,11 +500,50 @@ impl<'a> Platform<'a> {
         self.flags.contains(flag.as_ref())
     }
 
-    /// Returns the underlying `TargetInfo`.
+    /// Returns the flags set with `add_flags`, in arbitrary order.
+    pub(crate) fn flags(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.flags.iter().copied()
+    }
+
+    /// Returns the custom target for this platform, if it isn't builtin.
+    #[cfg(feature = "serde")]
+    pub(crate) fn custom_target(&self) -> Option<&CustomTarget> {
+        match &self.target {
+            PlatformTarget::Builtin(_) | PlatformTarget::Heuristic(_) => None,
+            PlatformTarget::Custom(custom) => Some(custom),
+        }
+    }
+
+    /// Returns the shared custom target for this platform, if it isn't builtin.
+    pub(crate) fn custom_target_arc(&self) -> Option<Arc<CustomTarget>> {
+        match &self.target {
+            PlatformTarget::Builtin(_) | PlatformTarget::Heuristic(_) => None,
+            PlatformTarget::Custom(custom) => Some(custom.clone()),
+        }
+    }
+
+    /// Creates a new `Platform` from a custom target.
+    pub(crate) fn from_custom_target(
+        custom: CustomTarget,
+        target_features: TargetFeatures<'a>,
+    ) -> Self {
+        Self {
+            target: PlatformTarget::Custom(Arc::new(custom)),
+            target_features,
+            flags: HashSet::new(),
+            rustc_version: None,
+        }
+    }
+
+    /// Returns the underlying `TargetInfo`, or `None` for guessed platforms.
     ///
     /// This is not exported since semver compatibility isn't guaranteed.
-    pub(crate) fn target_info(&self) -> &'a TargetInfo {
-        self.target_info
+    pub(crate) fn target_info(&self) -> Option<&TargetInfo> {
+        match &self.target {
+            PlatformTarget::Builtin(target_info) => Some(target_info),
+            PlatformTarget::Custom(custom) => Some(&custom.target_info),
+            PlatformTarget::Heuristic(_) => None,
+        }
     }
 
     /// Returns the set of target features for this platform.
@@ -67,16 +558,34 @@ impl Platform<'static> {
     /// This will return `None` if the current platform was unknown to this version of
     /// `target-spec`.
     pub fn current() -> Option<Self> {
-        let target_info = get_target_by_triple(CURRENT_TARGET)?;
+        let target_info = builtin_target(CURRENT_TARGET)?;
         let target_features = TargetFeatures::features(CURRENT_TARGET_FEATURES);
         Some(Self {
-            target_info,
+            target: PlatformTarget::Builtin(target_info),
             target_features,
             flags: HashSet::new(),
+            rustc_version: None,
         })
     }
 }
 
+#[derive(Clone, Debug)]
+enum PlatformTarget<'a> {
+    Builtin(&'a TargetInfo),
+    Custom(Arc<CustomTarget>),
+    Heuristic(Arc<HeuristicTarget>),
+}
+
+/// A version of the WebAssembly System Interface, as returned by `Platform::wasi_version`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
+pub enum WasiVersion {
+    /// WASI preview 1, targeted by `wasm32-wasi` and `wasm32-wasip1`.
+    Preview1,
+    /// WASI preview 2, targeted by `wasm32-wasip2`.
+    Preview2,
+}
+
 /// A set of target features to match.
 #[derive(Clone, Debug)]
 #[non_exhaustive]
@@ -100,6 +609,52 @@ impl<'a> TargetFeatures<'a> {
         TargetFeatures::Features(HashSet::new())
     }
 
+    /// Creates a new `TargetFeatures` with the features that `-C target-cpu=<cpu>` enables on
+    /// this platform, such as `avx2` for `skylake` on `x86_64-unknown-linux-gnu`.
+    ///
+    /// Features are looked up in a database bundled with `target-spec`, which covers the `x86`,
+    /// `x86_64` and `aarch64` architectures. It's generated from the Linux targets for each
+    /// architecture, so it doesn't include extra features that other triples enable by default.
+    /// For exact results, use `Platform::from_rustc_cfg_output` instead.
+    ///
+    /// Returns `None` if the triple or CPU is unknown, or if the architecture isn't covered.
+    /// `native` is never known, since it depends on the host.
+    pub fn from_target_cpu(cpu: impl AsRef<str>, triple: impl AsRef<str>) -> Option<Self> {
+        let target_info = builtin_target(triple.as_ref())?;
+        let features = target_cpu_features(target_info.arch, cpu.as_ref())?;
+        Some(TargetFeatures::Features(features.collect()))
+    }
+
+    /// Creates a new `TargetFeatures` with the features that rustc enables by default on this
+    /// platform, such as `sse2` on `x86_64-unknown-linux-gnu`.
+    ///
+    /// These are the features of the CPU the triple targets unless `-C target-cpu` is passed in,
+    /// looked up as with `from_target_cpu`. The same caveats apply: features that a triple
+    /// enables on top of its CPU, like `ssse3` on `i686-linux-android`, aren't included.
+    ///
+    /// Returns `None` if the triple is unknown, or if its architecture isn't covered.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use target_spec::{Platform, TargetFeatures, TargetSpec};
+    ///
+    /// let triple = "x86_64-unknown-linux-gnu";
+    /// let features = TargetFeatures::known(triple).unwrap();
+    /// let platform = Platform::new(triple, features).unwrap();
+    ///
+    /// let spec: TargetSpec = "cfg(target_feature = \"sse2\")".parse().unwrap();
+    /// assert_eq!(spec.eval(&platform), Some(true));
+    /// let spec: TargetSpec = "cfg(target_feature = \"avx2\")".parse().unwrap();
+    /// assert_eq!(spec.eval(&platform), Some(false));
+    /// ```
+    pub fn known(triple: impl AsRef<str>) -> Option<Self> {
+        let target_info = builtin_target(triple.as_ref())?;
+        let cpu = default_target_cpu(target_info)?;
+        let features = target_cpu_features(target_info.arch, cpu)?;
+        Some(TargetFeatures::Features(features.collect()))
+    }
+
     /// Returns `Some(true)` if this feature is a match, `Some(false)` if it isn't, and `None` if
     /// the set of target features is unknown.
     pub fn matches(&self, feature: &str) -> Option<bool> {
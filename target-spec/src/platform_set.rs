@@ -0,0 +1,161 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{EvalOptions, EvalResult, Platform, TargetSpec};
+use std::collections::BTreeMap;
+
+/// A named collection of platforms, keyed by triple.
+///
+/// Tools that check a fixed list of platforms, such as the ones a project's CI builds for, can
+/// keep them in a set and evaluate specs against all of them with `TargetSpec::eval_set`. Sets
+/// are ordered by triple, so results come out in a stable order.
+///
+/// ## Examples
+///
+/// ```
+/// use target_spec::{EvalResult, Platform, PlatformSet, TargetFeatures, TargetSpec};
+///
+/// let mut set = PlatformSet::new("ci");
+/// for triple in &["x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc", "x86_64-apple-darwin"] {
+///     set.add(Platform::new(triple, TargetFeatures::Unknown).unwrap());
+/// }
+///
+/// let spec: TargetSpec = "cfg(unix)".parse().unwrap();
+/// let results = spec.eval_set(&set);
+/// assert_eq!(
+///     results.into_iter().collect::<Vec<_>>(),
+///     vec![
+///         ("x86_64-apple-darwin", EvalResult::True),
+///         ("x86_64-pc-windows-msvc", EvalResult::False),
+///         ("x86_64-unknown-linux-gnu", EvalResult::True),
+///     ],
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct PlatformSet<'a> {
+    name: String,
+    platforms: BTreeMap<String, Platform<'a>>,
+}
+
+impl<'a> PlatformSet<'a> {
+    /// Creates a new, empty set with this name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            platforms: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a new set with this name and these platforms.
+    ///
+    /// If several platforms have the same triple, the last one is kept.
+    pub fn with_platforms(
+        name: impl Into<String>,
+        platforms: impl IntoIterator<Item = Platform<'a>>,
+    ) -> Self {
+        let mut set = Self::new(name);
+        for platform in platforms {
+            set.add(platform);
+        }
+        set
+    }
+
+    /// Returns the name of this set.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Adds a platform to this set, returning the platform with the same triple that it
+    /// replaced, if any.
+    pub fn add(&mut self, platform: Platform<'a>) -> Option<Platform<'a>> {
+        self.platforms
+            .insert(platform.triple().to_string(), platform)
+    }
+
+    /// Removes the platform with this triple from the set, returning it if it was present.
+    pub fn remove(&mut self, triple: &str) -> Option<Platform<'a>> {
+        self.platforms.remove(triple)
+    }
+
+    /// Returns the platform with this triple, if it's in the set.
+    pub fn get(&self, triple: &str) -> Option<&Platform<'a>> {
+        self.platforms.get(triple)
+    }
+
+    /// Returns true if the set contains a platform with this triple.
+    pub fn contains(&self, triple: &str) -> bool {
+        self.platforms.contains_key(triple)
+    }
+
+    /// Returns the number of platforms in the set.
+    pub fn len(&self) -> usize {
+        self.platforms.len()
+    }
+
+    /// Returns true if the set has no platforms.
+    pub fn is_empty(&self) -> bool {
+        self.platforms.is_empty()
+    }
+
+    /// Iterates over the platforms in the set, in order of their triples.
+    pub fn platforms(&self) -> impl Iterator<Item = &Platform<'a>> + '_ {
+        self.platforms.values()
+    }
+
+    /// Iterates over the triples of the platforms in the set, in order.
+    pub fn triples(&self) -> impl Iterator<Item = &str> + '_ {
+        self.platforms.keys().map(String::as_str)
+    }
+
+    pub(crate) fn eval(
+        &self,
+        spec: &TargetSpec,
+        options: &EvalOptions,
+    ) -> BTreeMap<&str, EvalResult> {
+        self.platforms
+            .iter()
+            .map(|(triple, platform)| (triple.as_str(), spec.eval_with_options(platform, options)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TargetFeatures, UnknownPolicy};
+
+    #[test]
+    fn test_eval_set() {
+        let platform = |triple: &str| Platform::new(triple, TargetFeatures::Unknown).unwrap();
+        let mut set = PlatformSet::with_platforms(
+            "tier1",
+            vec![
+                platform("x86_64-unknown-linux-gnu"),
+                platform("i686-pc-windows-msvc"),
+                platform("x86_64-unknown-linux-gnu"),
+            ],
+        );
+        assert_eq!(set.name(), "tier1");
+        assert_eq!(set.len(), 2);
+        assert_eq!(
+            set.triples().collect::<Vec<_>>(),
+            vec!["i686-pc-windows-msvc", "x86_64-unknown-linux-gnu"]
+        );
+
+        let spec: TargetSpec = "cfg(all(windows, not(my_flag)))".parse().unwrap();
+        let results = spec.eval_set(&set);
+        assert_eq!(results["i686-pc-windows-msvc"], EvalResult::True);
+        assert_eq!(results["x86_64-unknown-linux-gnu"], EvalResult::False);
+
+        let mut options = EvalOptions::new();
+        options.set_unknown_flags(UnknownPolicy::Unknown);
+        let results = spec.eval_set_with_options(&set, &options);
+        assert!(results["i686-pc-windows-msvc"].is_unknown());
+        assert_eq!(results["x86_64-unknown-linux-gnu"], EvalResult::False);
+
+        assert!(set.remove("i686-pc-windows-msvc").is_some());
+        assert!(!set.contains("i686-pc-windows-msvc"));
+        assert_eq!(spec.eval_set(&set).len(), 1);
+        assert!(PlatformSet::new("empty").is_empty());
+    }
+}
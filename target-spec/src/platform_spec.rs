@@ -0,0 +1,139 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::algebra::satisfiable;
+use crate::{Platform, TargetSpec};
+
+/// The platform or platforms to evaluate target specs against.
+///
+/// Analyses over platform-specific dependencies typically want to either look at a single
+/// platform, or at all platforms at once. When looking at all platforms, there are two ways to
+/// combine them:
+///
+/// * `Always` is the intersection of all platforms: a spec only matches if it matches every
+///   possible platform. For dependencies, this means that platform-specific ones are ignored.
+/// * `Any` is the union of all platforms: a spec matches if it matches at least one possible
+///   platform. For dependencies, this means that platform-specific ones are included unless they
+///   can never apply.
+///
+/// ## Examples
+///
+/// ```
+/// use target_spec::{Platform, PlatformSpec, TargetFeatures, TargetSpec};
+///
+/// let spec: TargetSpec = "cfg(windows)".parse().unwrap();
+/// let linux = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+///
+/// assert_eq!(PlatformSpec::Platform(&linux).eval(&spec), Some(false));
+/// assert_eq!(PlatformSpec::Any.eval(&spec), Some(true));
+/// assert_eq!(PlatformSpec::Always.eval(&spec), Some(false));
+///
+/// let spec: TargetSpec = "cfg(any(unix, not(unix)))".parse().unwrap();
+/// assert_eq!(PlatformSpec::Always.eval(&spec), Some(true));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub enum PlatformSpec<'a> {
+    /// The intersection of all platforms.
+    Always,
+    /// A single platform.
+    Platform(&'a Platform<'a>),
+    /// The union of all platforms.
+    Any,
+}
+
+impl<'a> PlatformSpec<'a> {
+    /// Returns the platform, if this is a single platform.
+    pub fn platform(&self) -> Option<&'a Platform<'a>> {
+        match self {
+            PlatformSpec::Platform(platform) => Some(platform),
+            PlatformSpec::Always | PlatformSpec::Any => None,
+        }
+    }
+
+    /// Evaluates a spec against this platform or set of platforms.
+    ///
+    /// For a single platform, this is the same as `TargetSpec::eval`. Otherwise, this reasons
+    /// symbolically about the spec, treating target features and flags as independent of the
+    /// platform. It returns `None` if the spec mentions too many predicates to analyze.
+    pub fn eval(&self, spec: &TargetSpec) -> Option<bool> {
+        match self {
+            PlatformSpec::Platform(platform) => spec.eval(platform),
+            PlatformSpec::Always => {
+                satisfiable(&[&spec.target], |results| !results[0]).map(|found| !found)
+            }
+            PlatformSpec::Any => satisfiable(&[&spec.target], |results| results[0]),
+        }
+    }
+}
+
+impl<'a> From<&'a Platform<'a>> for PlatformSpec<'a> {
+    fn from(platform: &'a Platform<'a>) -> Self {
+        PlatformSpec::Platform(platform)
+    }
+}
+
+/// Converts `None` to `PlatformSpec::Any`.
+impl<'a> From<Option<&'a Platform<'a>>> for PlatformSpec<'a> {
+    fn from(platform: Option<&'a Platform<'a>>) -> Self {
+        match platform {
+            Some(platform) => PlatformSpec::Platform(platform),
+            None => PlatformSpec::Any,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TargetFeatures;
+
+    #[test]
+    fn test_eval() {
+        let linux = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+        for (spec, on_linux, always, any) in &[
+            (
+                "x86_64-unknown-linux-gnu",
+                Some(true),
+                Some(false),
+                Some(true),
+            ),
+            ("cfg(unix)", Some(true), Some(false), Some(true)),
+            (
+                "cfg(all(unix, windows))",
+                Some(false),
+                Some(false),
+                Some(false),
+            ),
+            (
+                "cfg(not(all(unix, windows)))",
+                Some(true),
+                Some(true),
+                Some(true),
+            ),
+            (
+                "cfg(target_feature = \"sse2\")",
+                None,
+                Some(false),
+                Some(true),
+            ),
+        ] {
+            let spec: TargetSpec = spec.parse().unwrap();
+            assert_eq!(
+                PlatformSpec::from(&linux).eval(&spec),
+                *on_linux,
+                "{}",
+                spec
+            );
+            assert_eq!(PlatformSpec::Always.eval(&spec), *always, "{}", spec);
+            assert_eq!(PlatformSpec::Any.eval(&spec), *any, "{}", spec);
+        }
+
+        assert!(PlatformSpec::from(None).platform().is_none());
+        assert_eq!(
+            PlatformSpec::from(Some(&linux))
+                .platform()
+                .map(|p| p.triple()),
+            Some("x86_64-unknown-linux-gnu")
+        );
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use cfg_expr::expr::lexer::{Lexer, Token};
+use std::fmt;
+
+/// A single predicate used by a `TargetSpec`.
+///
+/// Returned by `TargetSpec::predicates`. Keys and values are borrowed from the spec as written,
+/// without any rewriting.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[non_exhaustive]
+pub enum SpecPredicate<'a> {
+    /// The spec is a target triple, such as `x86_64-unknown-linux-gnu`.
+    Triple(&'a str),
+    /// A key-value predicate describing the target, such as `target_os = "windows"`.
+    ///
+    /// The shorthands `unix` and `windows` are reported as `target_family = "unix"` and
+    /// `target_family = "windows"` respectively.
+    Target {
+        /// The key, for example `target_os`.
+        key: &'a str,
+        /// The value, for example `windows`.
+        value: &'a str,
+    },
+    /// A target feature, such as `target_feature = "sse2"`.
+    TargetFeature(&'a str),
+    /// A flag that isn't determined by the target, such as `test`, `debug_assertions`, or a
+    /// custom flag passed in with `--cfg`.
+    Flag(&'a str),
+    /// A minimum rustc version, such as `version("1.50")`.
+    RustcVersion(&'a str),
+    /// A Cargo feature, such as `feature = "serde"`.
+    ///
+    /// Cargo always evaluates these to false in target specs.
+    Feature(&'a str),
+}
+
+impl<'a> SpecPredicate<'a> {
+    /// Returns the key for this predicate, or `None` if this is a triple or a flag.
+    ///
+    /// For `version()` predicates, this is `version`.
+    pub fn key(&self) -> Option<&'a str> {
+        match self {
+            SpecPredicate::Target { key, .. } => Some(key),
+            SpecPredicate::TargetFeature(_) => Some("target_feature"),
+            SpecPredicate::RustcVersion(_) => Some("version"),
+            SpecPredicate::Feature(_) => Some("feature"),
+            SpecPredicate::Triple(_) | SpecPredicate::Flag(_) => None,
+        }
+    }
+
+    /// Returns the value for this predicate, or `None` if this is a triple or a flag.
+    pub fn value(&self) -> Option<&'a str> {
+        match self {
+            SpecPredicate::Target { value, .. }
+            | SpecPredicate::TargetFeature(value)
+            | SpecPredicate::RustcVersion(value)
+            | SpecPredicate::Feature(value) => Some(value),
+            SpecPredicate::Triple(_) | SpecPredicate::Flag(_) => None,
+        }
+    }
+}
+
+impl<'a> fmt::Display for SpecPredicate<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecPredicate::Triple(name) | SpecPredicate::Flag(name) => f.write_str(name),
+            SpecPredicate::RustcVersion(version) => write!(f, "version(\"{}\")", version),
+            _ => write!(
+                f,
+                "{} = \"{}\"",
+                self.key().expect("key-value predicates have a key"),
+                self.value().expect("key-value predicates have a value")
+            ),
+        }
+    }
+}
+
+/// Returns the predicates in this `cfg()` expression, which must have been successfully parsed,
+/// in the order they're written.
+pub(crate) fn cfg_predicates(input: &str) -> Vec<SpecPredicate<'_>> {
+    // The input was parsed successfully, so it lexes without errors.
+    let tokens: Vec<_> = Lexer::new(input)
+        .map(|token| token.expect("parsed spec lexes successfully").token)
+        .collect();
+
+    let mut predicates = Vec::new();
+    let mut idx = 0;
+    while idx < tokens.len() {
+        if let Token::Key(key) = tokens[idx] {
+            let predicate = match (tokens.get(idx + 1), tokens.get(idx + 2)) {
+                (Some(Token::Equals), Some(Token::Value(value))) => {
+                    idx += 2;
+                    value_predicate(key, value)
+                }
+                (Some(Token::OpenParen), Some(Token::Value(version))) if key == "version" => {
+                    idx += 3;
+                    SpecPredicate::RustcVersion(version)
+                }
+                _ => match key {
+                    "unix" | "windows" => SpecPredicate::Target {
+                        key: "target_family",
+                        value: key,
+                    },
+                    _ => SpecPredicate::Flag(key),
+                },
+            };
+            predicates.push(predicate);
+        }
+        idx += 1;
+    }
+    predicates
+}
+
+fn value_predicate<'a>(key: &'a str, value: &'a str) -> SpecPredicate<'a> {
+    match key {
+        "target_feature" => SpecPredicate::TargetFeature(value),
+        "feature" => SpecPredicate::Feature(value),
+        // Other keys are rejected while parsing, so this is a target_ key.
+        _ => SpecPredicate::Target { key, value },
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Typed values for the properties of a platform, as returned by `Platform::target_arch` and
+//! friends.
+
+use cfg_expr::targets;
+use std::fmt;
+
+macro_rules! property_enum {
+    (
+        $(#[$outer:meta])*
+        pub enum $name:ident: $cfg:ident {
+            $($variant:ident = $value:ident,)+
+        }
+    ) => {
+        $(#[$outer])*
+        #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+        #[non_exhaustive]
+        pub enum $name {
+            $(
+                #[doc = concat!("`", stringify!($value), "`.")]
+                $variant,
+            )+
+        }
+
+        impl $name {
+            /// Returns the value as it's written in `cfg()` expressions.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => stringify!($value),)+
+                }
+            }
+
+            pub(crate) fn new(value: targets::$cfg) -> Self {
+                match value {
+                    $(targets::$cfg::$value => $name::$variant,)+
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
+}
+
+property_enum! {
+    /// The architecture of a platform, as matched by `cfg(target_arch = "...")`.
+    pub enum TargetArch: Arch {
+        Aarch64 = aarch64,
+        Arm = arm,
+        Hexagon = hexagon,
+        Mips = mips,
+        Mips64 = mips64,
+        Msp430 = msp430,
+        Nvptx64 = nvptx64,
+        Powerpc = powerpc,
+        Powerpc64 = powerpc64,
+        Riscv32 = riscv32,
+        Riscv64 = riscv64,
+        S390x = s390x,
+        Sparc = sparc,
+        Sparc64 = sparc64,
+        Wasm32 = wasm32,
+        X86 = x86,
+        X86_64 = x86_64,
+    }
+}
+
+property_enum! {
+    /// The operating system of a platform, as matched by `cfg(target_os = "...")`.
+    pub enum TargetOs: Os {
+        Android = android,
+        Cloudabi = cloudabi,
+        Cuda = cuda,
+        Dragonfly = dragonfly,
+        Emscripten = emscripten,
+        Freebsd = freebsd,
+        Fuchsia = fuchsia,
+        Haiku = haiku,
+        Hermit = hermit,
+        Ios = ios,
+        L4re = l4re,
+        Linux = linux,
+        Macos = macos,
+        Netbsd = netbsd,
+        Openbsd = openbsd,
+        Redox = redox,
+        Solaris = solaris,
+        Uefi = uefi,
+        Unknown = unknown,
+        Vxworks = vxworks,
+        Wasi = wasi,
+        Windows = windows,
+    }
+}
+
+property_enum! {
+    /// The environment of a platform, as matched by `cfg(target_env = "...")`.
+    pub enum TargetEnv: Env {
+        Eabihf = eabihf,
+        Gnu = gnu,
+        Gnueabihf = gnueabihf,
+        Msvc = msvc,
+        Musl = musl,
+        Relibc = relibc,
+        Sgx = sgx,
+        Uclibc = uclibc,
+    }
+}
+
+property_enum! {
+    /// The vendor of a platform, as matched by `cfg(target_vendor = "...")`.
+    pub enum TargetVendor: Vendor {
+        Apple = apple,
+        Fortanix = fortanix,
+        Nvidia = nvidia,
+        Pc = pc,
+        Rumprun = rumprun,
+        Sun = sun,
+        Unknown = unknown,
+        Uwp = uwp,
+        Wrs = wrs,
+    }
+}
+
+property_enum! {
+    /// The family of a platform, as matched by `cfg(target_family = "...")`, `cfg(unix)` and
+    /// `cfg(windows)`.
+    ///
+    /// `wasm` is a family as well, but platforms can be in it alongside `unix`: use
+    /// `Platform::is_wasm` to check for it.
+    pub enum TargetFamily: Family {
+        Unix = unix,
+        Windows = windows,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Platform, TargetFeatures};
+
+    #[test]
+    fn test_names() {
+        // The names match cfg-expr's, which is what specs are parsed with.
+        for target_info in targets::ALL {
+            let arch = TargetArch::new(target_info.arch);
+            assert_eq!(arch.as_str(), format!("{:?}", target_info.arch));
+            if let Some(os) = target_info.os {
+                assert_eq!(TargetOs::new(os).to_string(), format!("{:?}", os));
+            }
+            if let Some(env) = target_info.env {
+                assert_eq!(TargetEnv::new(env).as_str(), format!("{:?}", env));
+            }
+            if let Some(vendor) = target_info.vendor {
+                assert_eq!(TargetVendor::new(vendor).as_str(), format!("{:?}", vendor));
+            }
+        }
+        assert_eq!(TargetFamily::new(targets::Family::unix).as_str(), "unix");
+    }
+
+    #[test]
+    fn test_platform_properties() {
+        let platform = Platform::new("x86_64-pc-windows-msvc", TargetFeatures::Unknown).unwrap();
+        assert_eq!(platform.target_vendor(), Some(TargetVendor::Pc));
+        assert_eq!(platform.target_family(), Some(TargetFamily::Windows));
+
+        // Guessed platforms report the properties that are known.
+        let platform =
+            Platform::new_heuristic("riscv64gc-unknown-freebsd", TargetFeatures::Unknown);
+        assert_eq!(platform.target_os(), Some(TargetOs::Freebsd));
+        assert_eq!(platform.target_family(), Some(TargetFamily::Unix));
+        assert_eq!(platform.target_arch(), Some(TargetArch::Riscv64));
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::alias::builtin_target;
+use crate::custom::CustomTarget;
+use crate::Platform;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+static REGISTRY: RwLock<BTreeMap<Box<str>, Arc<CustomTarget>>> = RwLock::new(BTreeMap::new());
+
+/// Registers a custom platform, so that its triple is known everywhere in this process.
+///
+/// The platform must have been created by `Platform::from_json`, `Platform::builder` or
+/// `Platform::from_rustc_cfg_output`. Once it's registered, `TargetSpec`s can be parsed from
+/// its triple, and `Platform::new` creates a platform with its properties. This includes the
+/// specs and platforms that other libraries, like `guppy`, create from strings.
+///
+/// Registering a triple again replaces the previous registration. Platforms and specs created
+/// before the triple was registered aren't affected. The platform's target features and flags
+/// aren't registered.
+///
+/// Returns an error if the platform isn't custom, or if its triple is builtin.
+///
+/// ## Examples
+///
+/// ```
+/// use target_spec::{register_custom_target, Platform, TargetFeatures, TargetSpec};
+///
+/// assert!("armv7-mycorp-none".parse::<TargetSpec>().is_err());
+///
+/// let platform = Platform::builder("armv7-mycorp-none")
+///     .arch("arm")
+///     .pointer_width(32)
+///     .vendor("")
+///     .build()
+///     .unwrap();
+/// register_custom_target(&platform).unwrap();
+///
+/// let spec: TargetSpec = "armv7-mycorp-none".parse().unwrap();
+/// let platform = Platform::new("armv7-mycorp-none", TargetFeatures::Unknown).unwrap();
+/// assert_eq!(spec.eval(&platform), Some(true));
+///
+/// let spec: TargetSpec = "cfg(target_arch = \"arm\")".parse().unwrap();
+/// assert_eq!(spec.eval(&platform), Some(true));
+/// ```
+pub fn register_custom_target(platform: &Platform<'_>) -> Result<(), RegisterTargetError> {
+    let triple = platform.triple();
+    if builtin_target(triple).is_some() {
+        return Err(RegisterTargetError::BuiltinTriple(triple.to_string()));
+    }
+    let custom = platform
+        .custom_target_arc()
+        .ok_or_else(|| RegisterTargetError::NotCustom(triple.to_string()))?;
+    REGISTRY
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .insert(triple.into(), custom);
+    Ok(())
+}
+
+/// Returns the triples registered with `register_custom_target`, in sorted order.
+pub fn registered_custom_targets() -> Vec<String> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .keys()
+        .map(|triple| triple.to_string())
+        .collect()
+}
+
+/// Returns the custom target registered with this triple, if any.
+pub(crate) fn lookup(triple: &str) -> Option<Arc<CustomTarget>> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .get(triple)
+        .cloned()
+}
+
+/// An error returned by `register_custom_target`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum RegisterTargetError {
+    /// The platform's triple is builtin, so it can't be registered.
+    BuiltinTriple(String),
+    /// The platform isn't custom: it's builtin, or its properties were guessed.
+    NotCustom(String),
+}
+
+impl fmt::Display for RegisterTargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegisterTargetError::BuiltinTriple(triple) => {
+                write!(f, "cannot register builtin triple: {}", triple)
+            }
+            RegisterTargetError::NotCustom(triple) => {
+                write!(f, "cannot register platform that isn't custom: {}", triple)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegisterTargetError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TargetFeatures, TargetSpec};
+
+    #[test]
+    fn test_register() {
+        // Tests share the registry, so use triples that no other test registers.
+        let triple = "riscv32-registrytest-none";
+        assert!(triple.parse::<TargetSpec>().is_err());
+        assert!(Platform::new(triple, TargetFeatures::Unknown).is_none());
+
+        let platform = Platform::builder(triple)
+            .arch("riscv32")
+            .pointer_width(32)
+            .build()
+            .unwrap();
+        register_custom_target(&platform).unwrap();
+        assert!(registered_custom_targets().contains(&triple.to_string()));
+
+        let spec: TargetSpec = triple.parse().unwrap();
+        let platform = Platform::new(triple, TargetFeatures::none()).unwrap();
+        assert!(platform.is_custom());
+        assert_eq!(spec.eval(&platform), Some(true));
+        let heuristic = Platform::new_heuristic(triple, TargetFeatures::none());
+        assert!(!heuristic.is_heuristic());
+
+        let linux = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown).unwrap();
+        assert_eq!(
+            register_custom_target(&linux),
+            Err(RegisterTargetError::BuiltinTriple(
+                "x86_64-unknown-linux-gnu".to_string()
+            ))
+        );
+        let guessed = Platform::new_heuristic("riscv64gc-unknown-freebsd", TargetFeatures::Unknown);
+        assert_eq!(
+            register_custom_target(&guessed),
+            Err(RegisterTargetError::NotCustom(
+                "riscv64gc-unknown-freebsd".to_string()
+            ))
+        );
+    }
+}
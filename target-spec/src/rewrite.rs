@@ -0,0 +1,252 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Support for `cfg()` keys that the version of `cfg-expr` used here predates.
+//!
+//! `cfg-expr` rejects unknown keys starting with `target_`, so before parsing, such keys are
+//! rewritten to names that it treats as generic key-value predicates. The evaluator then matches
+//! on the rewritten names.
+//!
+//! `cfg-expr` only knows about the `unix` and `windows` families, so `target_family = "wasm"` is
+//! rewritten the same way. Other families are left alone.
+//!
+//! `version("1.50")` predicates are rewritten to key-value predicates in the same way, since
+//! `cfg-expr` doesn't support them at all.
+
+use cfg_expr::expr::lexer::{Lexer, Token};
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// The key `target_abi` predicates are rewritten to.
+pub(crate) const ABI_KEY: &str = "__target_spec_target_abi";
+
+/// The key `target_has_atomic` predicates are rewritten to.
+pub(crate) const HAS_ATOMIC_KEY: &str = "__target_spec_target_has_atomic";
+
+/// The key `target_family = "wasm"` predicates are rewritten to.
+pub(crate) const WASM_FAMILY_KEY: &str = "__target_spec_target_family";
+
+/// The key `version("...")` predicates are rewritten to.
+pub(crate) const VERSION_KEY: &str = "__target_spec_rustc_version";
+
+/// What `version(` is rewritten to. The closing parenthesis is removed.
+const VERSION_REPLACEMENT: &str = "__target_spec_rustc_version = ";
+
+/// Keys that are rewritten, and what they're rewritten to.
+static REWRITTEN_KEYS: &[(&str, &str)] = &[
+    ("target_abi", ABI_KEY),
+    ("target_has_atomic", HAS_ATOMIC_KEY),
+];
+
+/// Returns true if this key-value predicate key is one produced by `rewrite_keys`.
+pub(crate) fn is_rewritten_key(key: &str) -> bool {
+    key == VERSION_KEY
+        || key == WASM_FAMILY_KEY
+        || REWRITTEN_KEYS
+            .iter()
+            .any(|(_, rewritten)| *rewritten == key)
+}
+
+/// A `cfg()` expression with its keys rewritten, along with the replacements that were made.
+pub(crate) struct RewrittenCfg<'a> {
+    text: Cow<'a, str>,
+    // The spans of the original keys, relative to the lexer's input, and what they were replaced
+    // with.
+    replacements: Vec<(Range<usize>, &'static str)>,
+}
+
+impl<'a> RewrittenCfg<'a> {
+    fn unchanged(input: &'a str) -> Self {
+        Self {
+            text: Cow::Borrowed(input),
+            replacements: Vec::new(),
+        }
+    }
+
+    /// Returns the rewritten expression.
+    pub(crate) fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Maps a span reported by `cfg-expr` for the rewritten expression back to a span in the
+    /// original expression.
+    ///
+    /// Like the lexer, both spans are relative to the part of the expression inside `cfg()`, if
+    /// present. Spans that start or end within a rewritten key are widened to cover the original
+    /// key.
+    pub(crate) fn original_span(&self, span: Range<usize>) -> Range<usize> {
+        self.original_pos(span.start, false)..self.original_pos(span.end, true)
+    }
+
+    fn original_pos(&self, pos: usize, is_end: bool) -> usize {
+        // The number of bytes the rewritten expression is ahead of the original by, so far.
+        let mut delta = 0;
+        for (span, key) in &self.replacements {
+            let start = span.start + delta;
+            if pos < start {
+                break;
+            }
+            if pos < start + key.len() {
+                return if is_end { span.end } else { span.start };
+            }
+            delta = delta + key.len() - span.len();
+        }
+        pos - delta
+    }
+}
+
+/// Rewrites `key = "..."` predicates in this `cfg()` expression, for each key in
+/// `REWRITTEN_KEYS`, as well as `target_family = "wasm"` and `version("...")` predicates.
+///
+/// The input is returned unchanged if it has no such predicates, or if it can't be lexed: in that
+/// case parsing it reports the error.
+pub(crate) fn rewrite_keys(input: &str) -> RewrittenCfg<'_> {
+    // The lexer strips the surrounding cfg() if present, and reports spans relative to the rest.
+    let (prefix, inner, suffix) = if input.starts_with("cfg(") && input.ends_with(')') {
+        ("cfg(", &input[4..input.len() - 1], ")")
+    } else {
+        ("", input, "")
+    };
+
+    let tokens: Result<Vec<_>, _> = Lexer::new(input).collect();
+    let tokens = match tokens {
+        Ok(tokens) => tokens,
+        Err(_) => return RewrittenCfg::unchanged(input),
+    };
+    let mut replacements = Vec::new();
+    for (idx, window) in tokens.windows(2).enumerate() {
+        match (&window[0].token, &window[1].token) {
+            (Token::Key(key), Token::Equals) => {
+                if let Some((_, rewritten)) =
+                    REWRITTEN_KEYS.iter().find(|(original, _)| original == key)
+                {
+                    replacements.push((window[0].span.clone(), *rewritten));
+                } else if *key == "target_family" {
+                    if let Some(Token::Value("wasm")) = tokens.get(idx + 2).map(|t| &t.token) {
+                        replacements.push((window[0].span.clone(), WASM_FAMILY_KEY));
+                    }
+                }
+            }
+            (Token::Key("version"), Token::OpenParen) => {
+                // Only rewrite version("..."), so that other uses are reported by the parser.
+                if let [value, close] = &tokens[idx + 2..(idx + 4).min(tokens.len())] {
+                    if let (Token::Value(_), Token::CloseParen) = (&value.token, &close.token) {
+                        let open = window[0].span.start..window[1].span.end;
+                        replacements.push((open, VERSION_REPLACEMENT));
+                        replacements.push((close.span.clone(), ""));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if replacements.is_empty() {
+        return RewrittenCfg::unchanged(input);
+    }
+
+    let mut rewritten = String::with_capacity(input.len() + replacements.len() * 16);
+    rewritten.push_str(prefix);
+    let mut last = 0;
+    for (span, key) in &replacements {
+        rewritten.push_str(&inner[last..span.start]);
+        rewritten.push_str(key);
+        last = span.end;
+    }
+    rewritten.push_str(&inner[last..]);
+    rewritten.push_str(suffix);
+    RewrittenCfg {
+        text: Cow::Owned(rewritten),
+        replacements,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite() {
+        assert_eq!(
+            rewrite_keys("cfg(all(target_abi = \"eabihf\", target_abi=\"\"))").as_str(),
+            format!("cfg(all({} = \"eabihf\", {}=\"\"))", ABI_KEY, ABI_KEY),
+        );
+        assert_eq!(
+            rewrite_keys("cfg(any(target_has_atomic = \"64\", target_abi = \"x32\"))").as_str(),
+            format!(
+                "cfg(any({} = \"64\", {} = \"x32\"))",
+                HAS_ATOMIC_KEY, ABI_KEY
+            ),
+        );
+        // Values and flags named like rewritten keys aren't rewritten.
+        for input in &[
+            "cfg(foo = \"target_abi\")",
+            "cfg(target_abi)",
+            "cfg(target_has_atomic)",
+            "cfg(unix)",
+        ] {
+            assert!(
+                matches!(rewrite_keys(input).text, Cow::Borrowed(_)),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_rewrite_wasm_family() {
+        assert_eq!(
+            rewrite_keys("cfg(all(target_family = \"wasm\", target_family = \"unix\"))").as_str(),
+            format!(
+                "cfg(all({} = \"wasm\", target_family = \"unix\"))",
+                WASM_FAMILY_KEY
+            ),
+        );
+        assert!(matches!(
+            rewrite_keys("cfg(target_family = \"windows\")").text,
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_rewrite_version() {
+        let rewritten = rewrite_keys("cfg(any(version(\"1.50\"), unix))");
+        assert_eq!(
+            rewritten.as_str(),
+            format!("cfg(any({} = \"1.50\", unix))", VERSION_KEY)
+        );
+        // The spans below are relative to the part inside cfg().
+        let unix_start = 4 + VERSION_REPLACEMENT.len() + 8;
+        assert_eq!(rewritten.original_span(unix_start..unix_start + 4), 21..25);
+        assert_eq!(rewritten.original_span(6..10), 4..12, "within version(");
+
+        for input in &["cfg(version)", "cfg(version())", "cfg(version(unix))"] {
+            assert!(
+                matches!(rewrite_keys(input).text, Cow::Borrowed(_)),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_original_span() {
+        // The spans below are relative to the part inside cfg().
+        let rewritten = rewrite_keys("cfg(any(target_abi = \"x32\", unix, target_abi = \"\"))");
+        let first = 4..4 + ABI_KEY.len();
+        let unix = first.end + 10..first.end + 14;
+        assert_eq!(&rewritten.as_str()[4 + unix.start..4 + unix.end], "unix");
+
+        // Spans before and after rewritten keys are shifted back.
+        assert_eq!(rewritten.original_span(0..3), 0..3);
+        assert_eq!(rewritten.original_span(unix), 24..28);
+        // Spans within rewritten keys cover the original key.
+        assert_eq!(
+            rewritten.original_span(first.start + 2..first.end - 2),
+            4..14
+        );
+        assert_eq!(rewritten.original_span(first), 4..14);
+
+        let unchanged = rewrite_keys("cfg(unix)");
+        assert_eq!(unchanged.original_span(0..4), 0..4);
+    }
+}
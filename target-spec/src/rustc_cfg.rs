@@ -0,0 +1,288 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::atomic::AtomicWidths;
+use crate::custom::CustomTarget;
+use cfg_expr::targets::TargetInfo;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// The parsed output of `rustc --print cfg`.
+#[derive(Debug)]
+pub(crate) struct RustcCfg<'a> {
+    pub(crate) target: CustomTarget,
+    pub(crate) target_features: Vec<&'a str>,
+    /// Names without values, like `unix` or `debug_assertions`.
+    pub(crate) flags: Vec<&'a str>,
+}
+
+/// Parses the output of `rustc --print cfg` for this triple.
+pub(crate) fn rustc_cfg<'a>(triple: &str, output: &'a str) -> Result<RustcCfg<'a>, RustcCfgError> {
+    let mut values: BTreeMap<&str, Vec<&'a str>> = BTreeMap::new();
+    let mut flags = vec![];
+    for line in output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        match line.find('=') {
+            Some(idx) => {
+                let value = &line[idx + 1..];
+                if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+                    return Err(RustcCfgError::InvalidLine(line.to_string()));
+                }
+                values
+                    .entry(&line[..idx])
+                    .or_default()
+                    .push(&value[1..value.len() - 1]);
+            }
+            None => flags.push(line),
+        }
+    }
+
+    let single = |key: &'static str| -> Option<&str> {
+        values
+            .get(key)
+            .and_then(|values| values.first())
+            .copied()
+            .filter(|value| !value.is_empty())
+    };
+    let required = |key: &'static str| single(key).ok_or(RustcCfgError::MissingKey(key));
+
+    // Targets can have several families, e.g. unix and wasm. cfg-expr only knows about unix and
+    // windows, so pick the first of those.
+    let family = values.get("target_family").and_then(|families| {
+        families
+            .iter()
+            .find(|family| **family == "unix" || **family == "windows")
+            .copied()
+    });
+
+    // Besides widths in bits, target_has_atomic can be "ptr", which AtomicWidths derives from the
+    // pointer width.
+    let widths: Vec<u8> = values
+        .get("target_has_atomic")
+        .into_iter()
+        .flatten()
+        .filter_map(|width| width.parse().ok())
+        .collect();
+    let atomic_widths = match (widths.iter().min(), widths.iter().max()) {
+        (Some(min), Some(max)) => AtomicWidths {
+            min: *min,
+            max: *max,
+        },
+        _ => AtomicWidths { min: 8, max: 0 },
+    };
+
+    let target_info = TargetInfo {
+        arch: parse_value("target_arch", required("target_arch")?)?,
+        // Bare-metal targets have an os of "none".
+        os: single("target_os")
+            .filter(|os| *os != "none")
+            .map(|os| parse_value("target_os", os))
+            .transpose()?,
+        env: single("target_env")
+            .map(|env| parse_value("target_env", env))
+            .transpose()?,
+        vendor: single("target_vendor")
+            .map(|vendor| parse_value("target_vendor", vendor))
+            .transpose()?,
+        family: family
+            .map(|family| parse_value("target_family", family))
+            .transpose()?,
+        pointer_width: parse_value("target_pointer_width", required("target_pointer_width")?)?,
+        endian: parse_value("target_endian", required("target_endian")?)?,
+        triple: "",
+    };
+
+    Ok(RustcCfg {
+        target: CustomTarget {
+            triple: triple.into(),
+            target_info,
+            abi: single("target_abi").unwrap_or_default().to_string(),
+            atomic_widths,
+        },
+        target_features: values.remove("target_feature").unwrap_or_default(),
+        flags,
+    })
+}
+
+/// Returns the flags set by `cargo:rustc-cfg` directives in this build script output.
+///
+/// Both the `cargo:` and `cargo::` prefixes are accepted. Key-value cfgs like `foo="bar"` are
+/// checked, but not returned.
+pub(crate) fn build_script_flags(output: &str) -> Result<Vec<&str>, RustcCfgError> {
+    let mut flags = vec![];
+    for line in output.lines().map(str::trim) {
+        let cfg = match line
+            .strip_prefix("cargo::rustc-cfg=")
+            .or_else(|| line.strip_prefix("cargo:rustc-cfg="))
+        {
+            Some(cfg) => cfg,
+            None => continue,
+        };
+        let (name, value) = match cfg.find('=') {
+            Some(idx) => (&cfg[..idx], Some(&cfg[idx + 1..])),
+            None => (cfg, None),
+        };
+        let valid_name = !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        let valid_value = match value {
+            Some(value) => value.len() >= 2 && value.starts_with('"') && value.ends_with('"'),
+            None => true,
+        };
+        if !valid_name || !valid_value {
+            return Err(RustcCfgError::InvalidLine(line.to_string()));
+        }
+        if value.is_none() {
+            flags.push(name);
+        }
+    }
+    Ok(flags)
+}
+
+/// An error that occurred while parsing the output of `rustc --print cfg`.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum RustcCfgError {
+    /// A line wasn't a name or a `key="value"` pair.
+    ///
+    /// For build script output, this is a `cargo:rustc-cfg` directive that wasn't.
+    InvalidLine(String),
+    /// A key that's required to evaluate `cfg()` expressions was missing.
+    MissingKey(&'static str),
+    /// The output had a value that isn't known to `target-spec`.
+    UnknownValue {
+        /// The key in the output.
+        key: &'static str,
+        /// The unknown value.
+        value: String,
+    },
+}
+
+impl fmt::Display for RustcCfgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RustcCfgError::InvalidLine(line) => write!(f, "invalid rustc cfg line: {}", line),
+            RustcCfgError::MissingKey(key) => write!(f, "rustc cfg output is missing {}", key),
+            RustcCfgError::UnknownValue { key, value } => {
+                write!(f, "rustc cfg output has unknown {}: {}", key, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RustcCfgError {}
+
+fn parse_value<T: FromStr>(key: &'static str, value: &str) -> Result<T, RustcCfgError> {
+    value.parse().map_err(|_| RustcCfgError::UnknownValue {
+        key,
+        value: value.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfg_expr::targets::{Arch, Endian, Env, Family, Os, Vendor};
+
+    static X86_64_LINUX: &str = r#"debug_assertions
+panic="unwind"
+target_abi=""
+target_arch="x86_64"
+target_endian="little"
+target_env="gnu"
+target_family="unix"
+target_feature="fxsr"
+target_feature="sse"
+target_feature="sse2"
+target_has_atomic="16"
+target_has_atomic="32"
+target_has_atomic="64"
+target_has_atomic="8"
+target_has_atomic="ptr"
+target_os="linux"
+target_pointer_width="64"
+target_vendor="unknown"
+unix
+"#;
+
+    #[test]
+    fn test_parse() {
+        let cfg = rustc_cfg("x86_64-unknown-linux-gnu", X86_64_LINUX).unwrap();
+        assert_eq!(&*cfg.target.triple, "x86_64-unknown-linux-gnu");
+        let target_info = &cfg.target.target_info;
+        assert_eq!(target_info.arch, Arch::x86_64);
+        assert_eq!(target_info.os, Some(Os::linux));
+        assert_eq!(target_info.env, Some(Env::gnu));
+        assert_eq!(target_info.vendor, Some(Vendor::unknown));
+        assert_eq!(target_info.family, Some(Family::unix));
+        assert_eq!(target_info.pointer_width, 64);
+        assert_eq!(target_info.endian, Endian::little);
+        assert_eq!(cfg.target.abi, "");
+        assert_eq!(cfg.target.atomic_widths, AtomicWidths { min: 8, max: 64 });
+        assert_eq!(cfg.target_features, vec!["fxsr", "sse", "sse2"]);
+        assert_eq!(cfg.flags, vec!["debug_assertions", "unix"]);
+    }
+
+    #[test]
+    fn test_bare_metal() {
+        let output = "target_arch=\"arm\"\ntarget_endian=\"little\"\ntarget_os=\"none\"\n\
+                      target_abi=\"eabi\"\ntarget_pointer_width=\"32\"\ntarget_vendor=\"unknown\"\n";
+        let cfg = rustc_cfg("thumbv6m-none-eabi", output).unwrap();
+        assert_eq!(cfg.target.target_info.os, None);
+        assert_eq!(cfg.target.target_info.family, None);
+        assert_eq!(cfg.target.abi, "eabi");
+        assert_eq!(cfg.target.atomic_widths, AtomicWidths { min: 8, max: 0 });
+    }
+
+    #[test]
+    fn test_build_script_flags() {
+        let output = "cargo:rerun-if-changed=build.rs\n\
+                      cargo:rustc-cfg=has_foo\n\
+                      cargo:rustc-cfg=bar=\"baz\"\n\
+                      warning: not a directive\n\
+                      cargo::rustc-cfg=nightly\n";
+        assert_eq!(
+            build_script_flags(output).unwrap(),
+            vec!["has_foo", "nightly"]
+        );
+
+        for line in &[
+            "cargo:rustc-cfg=",
+            "cargo:rustc-cfg=foo bar",
+            "cargo:rustc-cfg=bar=baz",
+            "cargo:rustc-cfg=1foo",
+        ] {
+            assert_eq!(
+                build_script_flags(line).unwrap_err(),
+                RustcCfgError::InvalidLine(line.to_string()),
+            );
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            rustc_cfg("foo", "target_arch=x86_64").unwrap_err(),
+            RustcCfgError::InvalidLine("target_arch=x86_64".to_string()),
+        );
+        assert_eq!(
+            rustc_cfg("foo", "target_arch=\"x86_64\"\ntarget_endian=\"little\"").unwrap_err(),
+            RustcCfgError::MissingKey("target_pointer_width"),
+        );
+        assert_eq!(
+            rustc_cfg(
+                "foo",
+                "target_arch=\"z80\"\ntarget_endian=\"little\"\ntarget_pointer_width=\"16\""
+            )
+            .unwrap_err(),
+            RustcCfgError::UnknownValue {
+                key: "target_arch",
+                value: "z80".to_string()
+            },
+        );
+    }
+}
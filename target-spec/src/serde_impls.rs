@@ -0,0 +1,417 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! `Serialize` and `Deserialize` implementations, enabled with the `serde` feature.
+//!
+//! * `TargetSpec` is represented as the string it was parsed from.
+//! * `TargetFeatures` is represented as `"unknown"`, `"all"` or a sorted list of features.
+//! * `Platform` is represented as a map with `triple`, `target-features` and `flags` keys. Custom
+//!   platforms also have a `custom` key, with the parts of the custom target spec that
+//!   `target-spec` reads. Platforms created with `Platform::new_heuristic` for triples that
+//!   aren't builtin have `heuristic` set to `true`. Platforms with a rustc version have a
+//!   `rustc-version` key.
+//! * `RustcVersion` is represented as a string, such as `"1.50.0"`.
+//! * `PlatformSummary` is represented as a map with `triple`, `target-features`, `flags` and
+//!   `heuristic` keys, like `Platform` but without `custom`. `TargetFeaturesSummary` is
+//!   represented like `TargetFeatures`.
+//!
+//! `Platform` and `TargetFeatures` borrow strings from the input, so they can only be deserialized
+//! from formats that support borrowing, such as JSON through `serde_json::from_str`. Use
+//! `PlatformSummary` for other formats.
+
+use crate::custom::{custom_target_from_spec, CustomTargetSpec};
+use crate::{
+    ParseError, Platform, PlatformSummary, RustcVersion, TargetFeatures, TargetFeaturesSummary,
+    TargetSpec,
+};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeSet;
+use std::fmt;
+
+impl Serialize for TargetSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for TargetSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        match input.parse() {
+            Ok(spec) => Ok(spec),
+            // Specs created with TargetSpec::custom serialize to their triple.
+            Err(ParseError::UnknownTriple(_)) => Ok(TargetSpec::from_custom_triple(input)),
+            Err(err) => Err(de::Error::custom(err)),
+        }
+    }
+}
+
+impl Serialize for RustcVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for RustcVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        input.parse().map_err(de::Error::custom)
+    }
+}
+
+impl<'a> Serialize for TargetFeatures<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TargetFeatures::Unknown => serializer.serialize_str("unknown"),
+            TargetFeatures::All => serializer.serialize_str("all"),
+            TargetFeatures::Features(features) => {
+                let mut features: Vec<_> = features.iter().collect();
+                features.sort_unstable();
+                let mut seq = serializer.serialize_seq(Some(features.len()))?;
+                for feature in features {
+                    seq.serialize_element(feature)?;
+                }
+                seq.end()
+            }
+        }
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for TargetFeatures<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TargetFeaturesVisitor)
+    }
+}
+
+struct TargetFeaturesVisitor;
+
+impl<'de> Visitor<'de> for TargetFeaturesVisitor {
+    type Value = TargetFeatures<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"unknown\", \"all\" or a list of target features")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        match value {
+            "unknown" => Ok(TargetFeatures::Unknown),
+            "all" => Ok(TargetFeatures::All),
+            _ => Err(E::invalid_value(de::Unexpected::Str(value), &self)),
+        }
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut features = vec![];
+        while let Some(feature) = seq.next_element::<&'de str>()? {
+            features.push(feature);
+        }
+        Ok(TargetFeatures::features(&features))
+    }
+}
+
+impl<'a> Serialize for Platform<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut flags: Vec<_> = self.flags().collect();
+        flags.sort_unstable();
+        PlatformRepr {
+            triple: self.triple(),
+            target_features: self.target_features().clone(),
+            flags,
+            custom: self.custom_target().map(|custom| custom.to_spec()),
+            heuristic: self.is_heuristic(),
+            rustc_version: self.rustc_version(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for Platform<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = PlatformRepr::deserialize(deserializer)?;
+        let triple = repr.triple;
+        let mut platform = match repr.custom {
+            Some(custom) => {
+                let custom = custom_target_from_spec(triple, custom).map_err(de::Error::custom)?;
+                Platform::from_custom_target(custom, repr.target_features)
+            }
+            None if repr.heuristic => Platform::new_heuristic(triple, repr.target_features),
+            None => Platform::new(triple, repr.target_features)
+                .ok_or_else(|| de::Error::custom(format_args!("unknown triple: {}", triple)))?,
+        };
+        platform.add_flags(&repr.flags);
+        platform.set_rustc_version(repr.rustc_version);
+        Ok(platform)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct PlatformRepr<'a> {
+    triple: &'a str,
+    #[serde(borrow, default = "unknown_features")]
+    target_features: TargetFeatures<'a>,
+    #[serde(borrow, default, skip_serializing_if = "Vec::is_empty")]
+    flags: Vec<&'a str>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    custom: Option<CustomTargetSpec>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    heuristic: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rustc_version: Option<RustcVersion>,
+}
+
+impl Serialize for PlatformSummary {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PlatformSummaryRepr {
+            triple: self.triple.clone(),
+            target_features: self.target_features.clone(),
+            flags: self.flags.clone(),
+            heuristic: self.heuristic,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PlatformSummary {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = PlatformSummaryRepr::deserialize(deserializer)?;
+        Ok(PlatformSummary {
+            triple: repr.triple,
+            target_features: repr.target_features,
+            flags: repr.flags,
+            heuristic: repr.heuristic,
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct PlatformSummaryRepr {
+    triple: String,
+    #[serde(default = "unknown_features_summary")]
+    target_features: TargetFeaturesSummary,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    flags: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    heuristic: bool,
+}
+
+impl Serialize for TargetFeaturesSummary {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_target_features().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TargetFeaturesSummary {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TargetFeaturesSummaryVisitor)
+    }
+}
+
+struct TargetFeaturesSummaryVisitor;
+
+impl<'de> Visitor<'de> for TargetFeaturesSummaryVisitor {
+    type Value = TargetFeaturesSummary;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        TargetFeaturesVisitor.expecting(f)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        match value {
+            "unknown" => Ok(TargetFeaturesSummary::Unknown),
+            "all" => Ok(TargetFeaturesSummary::All),
+            _ => Err(E::invalid_value(de::Unexpected::Str(value), &self)),
+        }
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut features = BTreeSet::new();
+        while let Some(feature) = seq.next_element::<String>()? {
+            features.insert(feature);
+        }
+        Ok(TargetFeaturesSummary::Features(features))
+    }
+}
+
+fn unknown_features_summary() -> TargetFeaturesSummary {
+    TargetFeaturesSummary::Unknown
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+fn unknown_features<'a>() -> TargetFeatures<'a> {
+    TargetFeatures::Unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thumbv7em(target_features: TargetFeatures<'_>) -> Platform<'_> {
+        Platform::builder("thumbv7em-custom")
+            .arch("arm")
+            .pointer_width(32)
+            .os("none")
+            .vendor("unknown")
+            .max_atomic_width(32)
+            .target_features(target_features)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_target_spec() {
+        for input in &[
+            "x86_64-unknown-linux-gnu",
+            "cfg(any(unix, target_feature = \"sse2\"))",
+            "thumbv7em-custom",
+        ] {
+            let json = serde_json::to_string(input).unwrap();
+            let spec: TargetSpec = serde_json::from_str(&json).unwrap();
+            assert_eq!(spec.as_str(), *input);
+            assert_eq!(serde_json::to_string(&spec).unwrap(), json);
+        }
+
+        let custom: TargetSpec = serde_json::from_str("\"thumbv7em-custom\"").unwrap();
+        let platform = thumbv7em(TargetFeatures::Unknown);
+        assert_eq!(custom.eval(&platform), Some(true));
+
+        serde_json::from_str::<TargetSpec>("\"cfg(not(unix)\"")
+            .expect_err("invalid cfg() expressions are rejected");
+    }
+
+    #[test]
+    fn test_target_features() {
+        let features = TargetFeatures::features(&["sse2", "avx"]);
+        let json = serde_json::to_string(&features).unwrap();
+        assert_eq!(json, r#"["avx","sse2"]"#);
+        let features: TargetFeatures<'_> = serde_json::from_str(&json).unwrap();
+        assert_eq!(features.matches("avx"), Some(true));
+        assert_eq!(features.matches("fma"), Some(false));
+
+        for keyword in &["unknown", "all"] {
+            let json = format!("\"{}\"", keyword);
+            let features: TargetFeatures<'_> = serde_json::from_str(&json).unwrap();
+            assert_eq!(serde_json::to_string(&features).unwrap(), json);
+        }
+        serde_json::from_str::<TargetFeatures<'_>>("\"some\"")
+            .expect_err("only unknown and all are accepted as strings");
+    }
+
+    #[test]
+    fn test_platform() {
+        let mut platform = Platform::new(
+            "x86_64-unknown-linux-gnu",
+            TargetFeatures::features(&["sse2"]),
+        )
+        .unwrap();
+        platform.add_flags(&["tokio_unstable", "cargo_web"]);
+        let json = serde_json::to_string(&platform).unwrap();
+        assert_eq!(
+            json,
+            r#"{"triple":"x86_64-unknown-linux-gnu","target-features":["sse2"],"flags":["cargo_web","tokio_unstable"]}"#
+        );
+        let roundtrip: Platform<'_> = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&roundtrip).unwrap(), json);
+        assert!(!roundtrip.is_custom());
+        assert!(roundtrip.has_flag("cargo_web"));
+
+        platform.set_rustc_version(Some(RustcVersion::new(1, 50, 0)));
+        let json = serde_json::to_string(&platform).unwrap();
+        assert!(
+            json.ends_with(r#","rustc-version":"1.50.0"}"#),
+            "json: {}",
+            json
+        );
+        let roundtrip: Platform<'_> = serde_json::from_str(&json).unwrap();
+        assert_eq!(roundtrip.rustc_version(), Some(RustcVersion::new(1, 50, 0)));
+
+        // Target features and flags are optional.
+        let platform: Platform<'_> =
+            serde_json::from_str(r#"{"triple":"x86_64-pc-windows-msvc"}"#).unwrap();
+        assert_eq!(platform.target_features().matches("sse2"), None);
+
+        serde_json::from_str::<Platform<'_>>(r#"{"triple":"z80-unknown-unknown"}"#)
+            .expect_err("unknown triples are rejected");
+    }
+
+    #[test]
+    fn test_platform_summary() {
+        let mut platform = Platform::new(
+            "x86_64-unknown-linux-gnu",
+            TargetFeatures::features(&["sse2", "avx"]),
+        )
+        .unwrap();
+        platform.add_flags(&["tokio_unstable", "cargo_web"]);
+        let summary = PlatformSummary::new(&platform);
+
+        let json = serde_json::to_string(&summary).unwrap();
+        assert_eq!(
+            json,
+            r#"{"triple":"x86_64-unknown-linux-gnu","target-features":["avx","sse2"],"flags":["cargo_web","tokio_unstable"]}"#
+        );
+        let roundtrip: PlatformSummary = serde_json::from_str(&json).unwrap();
+        assert_eq!(roundtrip, summary);
+
+        let toml = toml::to_string(&summary).unwrap();
+        assert_eq!(
+            toml,
+            "triple = \"x86_64-unknown-linux-gnu\"\n\
+             target-features = [\"avx\", \"sse2\"]\n\
+             flags = [\"cargo_web\", \"tokio_unstable\"]\n"
+        );
+        let roundtrip: PlatformSummary = toml::from_str(&toml).unwrap();
+        assert_eq!(roundtrip, summary);
+
+        let summary: PlatformSummary = toml::from_str(
+            "triple = \"riscv64gc-unknown-freebsd\"\ntarget-features = \"all\"\nheuristic = true\n",
+        )
+        .unwrap();
+        let platform = summary.to_platform().unwrap();
+        assert!(platform.is_heuristic());
+        assert_eq!(platform.target_features().matches("sse2"), Some(true));
+
+        // Target features default to unknown.
+        let summary: PlatformSummary =
+            toml::from_str("triple = \"x86_64-pc-windows-msvc\"").unwrap();
+        assert_eq!(summary.target_features, TargetFeaturesSummary::Unknown);
+    }
+
+    #[test]
+    fn test_heuristic_platform() {
+        let platform = Platform::new_heuristic("riscv64gc-unknown-freebsd", TargetFeatures::none());
+        let json = serde_json::to_string(&platform).unwrap();
+        assert_eq!(
+            json,
+            r#"{"triple":"riscv64gc-unknown-freebsd","target-features":[],"heuristic":true}"#
+        );
+        let roundtrip: Platform<'_> = serde_json::from_str(&json).unwrap();
+        assert!(roundtrip.is_heuristic());
+        assert_eq!(serde_json::to_string(&roundtrip).unwrap(), json);
+    }
+
+    #[test]
+    fn test_custom_platform() {
+        let platform = thumbv7em(TargetFeatures::none());
+        let json = serde_json::to_string(&platform).unwrap();
+        let roundtrip: Platform<'_> = serde_json::from_str(&json).unwrap();
+        assert!(roundtrip.is_custom());
+        assert_eq!(roundtrip.triple(), "thumbv7em-custom");
+        assert_eq!(serde_json::to_string(&roundtrip).unwrap(), json);
+
+        for (spec, expected) in &[
+            ("cfg(target_arch = \"arm\")", Some(true)),
+            ("cfg(target_os = \"\")", Some(true)),
+            ("cfg(target_has_atomic = \"64\")", Some(false)),
+            ("cfg(target_feature = \"neon\")", Some(false)),
+        ] {
+            let spec: TargetSpec = spec.parse().unwrap();
+            assert_eq!(spec.eval(&roundtrip), *expected, "{}", spec);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Simplification of `cfg()` expressions into a normal form.
+//!
+//! The expression is rebuilt as a tree from its tokens, and simplified bottom-up:
+//! * double negations are removed,
+//! * nested `all` and `any` operators are flattened into their parent,
+//! * operands are sorted and deduplicated,
+//! * `all` and `any` operators with a single operand are replaced with that operand, and
+//! * operators known to always be true or false, like `all(x, not(x))`, are replaced with `all()`
+//!   or `any()` respectively.
+//!
+//! Equivalent expressions that only differ in these ways simplify to the same text.
+
+use cfg_expr::expr::lexer::{Lexer, Token};
+use std::fmt;
+
+/// Simplifies this `cfg()` expression, which must have been successfully parsed, returning the
+/// simplified expression including its surrounding `cfg()`.
+pub(crate) fn simplify_cfg(input: &str) -> String {
+    // The input was parsed successfully, so it lexes without errors.
+    let tokens: Vec<_> = Lexer::new(input)
+        .map(|token| token.expect("parsed spec lexes successfully").token)
+        .collect();
+    let mut pos = 0;
+    let node = Node::parse(&tokens, &mut pos).simplify();
+    format!("cfg({})", node)
+}
+
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Node {
+    Pred(String, Option<String>),
+    Version(String),
+    All(Vec<Node>),
+    Any(Vec<Node>),
+    Not(Box<Node>),
+}
+
+impl Node {
+    /// Parses the expression starting at `pos`, advancing `pos` past it.
+    fn parse(tokens: &[Token<'_>], pos: &mut usize) -> Self {
+        let token = &tokens[*pos];
+        *pos += 1;
+        match token {
+            Token::Key(key) => match tokens.get(*pos) {
+                Some(Token::Equals) => {
+                    let value = match &tokens[*pos + 1] {
+                        Token::Value(value) => value.to_string(),
+                        other => unreachable!("parsed spec has a value after =, found {:?}", other),
+                    };
+                    *pos += 2;
+                    Node::Pred(key.to_string(), Some(value))
+                }
+                Some(Token::OpenParen) if *key == "version" => {
+                    let value = match &tokens[*pos + 1] {
+                        Token::Value(value) => value.to_string(),
+                        other => unreachable!("parsed version() has a value, found {:?}", other),
+                    };
+                    *pos += 3;
+                    Node::Version(value)
+                }
+                _ => Node::Pred(key.to_string(), None),
+            },
+            Token::All => Node::All(Self::parse_operands(tokens, pos)),
+            Token::Any => Node::Any(Self::parse_operands(tokens, pos)),
+            Token::Not => {
+                let mut operands = Self::parse_operands(tokens, pos);
+                assert_eq!(operands.len(), 1, "parsed not() has a single operand");
+                Node::Not(Box::new(operands.remove(0)))
+            }
+            other => unreachable!("parsed spec has a predicate or operator, found {:?}", other),
+        }
+    }
+
+    /// Parses a parenthesized, comma-separated list of operands.
+    fn parse_operands(tokens: &[Token<'_>], pos: &mut usize) -> Vec<Self> {
+        assert_eq!(
+            tokens[*pos],
+            Token::OpenParen,
+            "operators are followed by ("
+        );
+        *pos += 1;
+        let mut operands = Vec::new();
+        loop {
+            match tokens[*pos] {
+                Token::CloseParen => {
+                    *pos += 1;
+                    return operands;
+                }
+                Token::Comma => *pos += 1,
+                _ => operands.push(Self::parse(tokens, pos)),
+            }
+        }
+    }
+
+    fn always_true() -> Self {
+        Node::All(Vec::new())
+    }
+
+    fn always_false() -> Self {
+        Node::Any(Vec::new())
+    }
+
+    fn simplify(self) -> Self {
+        match self {
+            // Cargo always evaluates feature predicates to false.
+            Node::Pred(key, Some(_)) if key == "feature" => Self::always_false(),
+            Node::Pred(..) | Node::Version(_) => self,
+            Node::Not(operand) => match operand.simplify() {
+                Node::Not(inner) => *inner,
+                Node::All(operands) if operands.is_empty() => Self::always_false(),
+                Node::Any(operands) if operands.is_empty() => Self::always_true(),
+                other => Node::Not(Box::new(other)),
+            },
+            Node::All(operands) => Self::simplify_operands(operands, true),
+            Node::Any(operands) => Self::simplify_operands(operands, false),
+        }
+    }
+
+    /// Simplifies the operands of an `all` operator if `is_all` is true, or of an `any` operator
+    /// otherwise.
+    fn simplify_operands(operands: Vec<Node>, is_all: bool) -> Self {
+        // For `all`, an operand that's always false makes the whole operator false, and operands
+        // that are always true can be dropped. The reverse holds for `any`.
+        let (identity, absorbing) = if is_all {
+            (Self::always_true(), Self::always_false())
+        } else {
+            (Self::always_false(), Self::always_true())
+        };
+
+        let mut flattened = Vec::with_capacity(operands.len());
+        for operand in operands {
+            match operand.simplify() {
+                operand if operand == absorbing => return absorbing,
+                Node::All(inner) if is_all => flattened.extend(inner),
+                Node::Any(inner) if !is_all => flattened.extend(inner),
+                operand => flattened.push(operand),
+            }
+        }
+        flattened.sort();
+        flattened.dedup();
+
+        // Some operand and its negation: any(x, not(x)) is always true, and all(x, not(x)) is
+        // always false.
+        let complementary = flattened.iter().any(|operand| match operand {
+            Node::Not(inner) => flattened.binary_search(inner).is_ok(),
+            _ => false,
+        });
+        if complementary {
+            return absorbing;
+        }
+
+        match flattened.len() {
+            0 => identity,
+            1 => flattened.remove(0),
+            _ if is_all => Node::All(flattened),
+            _ => Node::Any(flattened),
+        }
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, operands) = match self {
+            Node::Pred(key, None) => return f.write_str(key),
+            Node::Pred(key, Some(value)) => return write!(f, "{} = \"{}\"", key, value),
+            Node::Version(version) => return write!(f, "version(\"{}\")", version),
+            Node::Not(operand) => return write!(f, "not({})", operand),
+            Node::All(operands) => ("all", operands),
+            Node::Any(operands) => ("any", operands),
+        };
+        write!(f, "{}(", name)?;
+        for (idx, operand) in operands.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", operand)?;
+        }
+        f.write_str(")")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Platform, TargetFeatures, TargetSpec};
+    use cfg_expr::targets::ALL;
+
+    fn simplify(input: &str) -> String {
+        input.parse::<TargetSpec>().unwrap().simplify().to_string()
+    }
+
+    #[test]
+    fn test_simplify() {
+        for (input, expected) in &[
+            ("cfg(unix)", "cfg(unix)"),
+            ("cfg(not(not(unix)))", "cfg(unix)"),
+            ("cfg(not(not(not(unix))))", "cfg(not(unix))"),
+            ("cfg(any(unix, unix))", "cfg(unix)"),
+            ("cfg(all(unix))", "cfg(unix)"),
+            (
+                "cfg(any(windows, unix, windows,))",
+                "cfg(any(unix, windows))",
+            ),
+            ("cfg(all(unix, not(unix)))", "cfg(any())"),
+            ("cfg(any(unix, not(unix)))", "cfg(all())"),
+            (
+                "cfg(all(unix, all(target_arch = \"x86_64\", unix)))",
+                "cfg(all(target_arch = \"x86_64\", unix))",
+            ),
+            ("cfg(any(all(), target_os = \"linux\"))", "cfg(all())"),
+            ("cfg(all(any(), target_os = \"linux\"))", "cfg(any())"),
+            ("cfg(not(any()))", "cfg(all())"),
+            (
+                "cfg(all(unix, not(any(windows, windows))))",
+                "cfg(all(unix, not(windows)))",
+            ),
+            ("cfg(any(feature = \"foo\", unix))", "cfg(unix)"),
+            ("cfg(not(not(version(\"1.50\"))))", "cfg(version(\"1.50\"))"),
+            // Contradictions that depend on what keys mean are found as well.
+            ("cfg(all(unix, windows))", "cfg(any())"),
+            (
+                "cfg(all(target_os = \"linux\", target_os = \"macos\"))",
+                "cfg(any())",
+            ),
+            (
+                "cfg(any(target_endian = \"little\", target_endian = \"big\"))",
+                "cfg(all())",
+            ),
+            // Rewritten keys are written out as they were originally.
+            (
+                "cfg(all(target_abi = \"eabihf\", target_abi = \"eabihf\"))",
+                "cfg(target_abi = \"eabihf\")",
+            ),
+            // Triples are returned as-is.
+            ("x86_64-unknown-linux-gnu", "x86_64-unknown-linux-gnu"),
+        ] {
+            assert_eq!(simplify(input), *expected, "simplifying {}", input);
+        }
+    }
+
+    #[test]
+    fn test_simplify_equivalent() {
+        for input in &[
+            "cfg(all(unix, not(not(target_arch = \"x86_64\"))))",
+            "cfg(any(all(windows, target_env = \"msvc\"), not(unix), unix))",
+            "cfg(not(all(target_os = \"linux\", any(target_pointer_width = \"32\", windows))))",
+            "cfg(all(not(unix), any(unix, windows, target_os = \"wasi\")))",
+        ] {
+            let spec: TargetSpec = input.parse().unwrap();
+            let simplified = spec.simplify();
+            for target_info in ALL {
+                let platform = Platform::new(target_info.triple, TargetFeatures::Unknown).unwrap();
+                assert_eq!(
+                    spec.eval(&platform),
+                    simplified.eval(&platform),
+                    "{} and {} on {}",
+                    spec,
+                    simplified,
+                    target_info.triple
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{ParseError, Platform, TargetFeatures};
+use std::collections::BTreeSet;
+
+/// An owned description of a platform, which can be stored in configuration files and turned
+/// back into a `Platform`.
+///
+/// Unlike `Platform`, a summary doesn't borrow from anything, so with the `serde` feature it can
+/// be deserialized from any format, including TOML. Target features and flags are stored in
+/// sorted order, so serializing a summary always produces the same output.
+///
+/// Only the triple is stored, not the properties of the platform. Custom platforms can be
+/// recreated from their summaries if their triple was registered with `register_custom_target`.
+///
+/// With the `serde` feature, a summary is represented as a map with `triple`, `target-features`
+/// and `flags` keys, as well as a `heuristic` key set to `true` for platforms created with
+/// `Platform::new_heuristic` for triples that aren't builtin. Target features are represented as
+/// with `TargetFeatures`.
+///
+/// ## Examples
+///
+/// ```
+/// use target_spec::{Platform, PlatformSummary, TargetFeatures};
+///
+/// let mut platform = Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::features(&["sse2"]))
+///     .unwrap();
+/// platform.add_flags(&["tokio_unstable"]);
+///
+/// let summary = PlatformSummary::new(&platform);
+/// let roundtrip = summary.to_platform().unwrap();
+/// assert_eq!(roundtrip.triple(), "x86_64-unknown-linux-gnu");
+/// assert_eq!(roundtrip.target_features().matches("sse2"), Some(true));
+/// assert!(roundtrip.has_flag("tokio_unstable"));
+/// assert_eq!(PlatformSummary::new(&roundtrip), summary);
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PlatformSummary {
+    /// The target triple.
+    pub triple: String,
+    /// The target features.
+    pub target_features: TargetFeaturesSummary,
+    /// The flags set on the platform with `Platform::add_flags`.
+    pub flags: BTreeSet<String>,
+    /// True if the platform's properties are guessed from its triple, as with
+    /// `Platform::new_heuristic`.
+    pub heuristic: bool,
+}
+
+impl PlatformSummary {
+    /// Creates a new summary of this platform.
+    pub fn new(platform: &Platform<'_>) -> Self {
+        Self {
+            triple: platform.triple().to_string(),
+            target_features: TargetFeaturesSummary::new(platform.target_features()),
+            flags: platform.flags().map(|flag| flag.to_string()).collect(),
+            heuristic: platform.is_heuristic(),
+        }
+    }
+
+    /// Creates the platform this summary describes.
+    ///
+    /// The platform borrows its target features and flags from this summary.
+    ///
+    /// Returns `ParseError::UnknownTriple` if the triple isn't known to `target-spec`, which
+    /// happens for custom platforms that aren't registered.
+    pub fn to_platform(&self) -> Result<Platform<'_>, ParseError> {
+        let target_features = self.target_features.to_target_features();
+        let mut platform = if self.heuristic {
+            Platform::new_heuristic(&self.triple, target_features)
+        } else {
+            Platform::new(&self.triple, target_features)
+                .ok_or_else(|| ParseError::UnknownTriple(self.triple.clone()))?
+        };
+        let flags: Vec<&str> = self.flags.iter().map(|flag| flag.as_str()).collect();
+        platform.add_flags(&flags);
+        Ok(platform)
+    }
+}
+
+/// An owned version of `TargetFeatures`, as part of a `PlatformSummary`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum TargetFeaturesSummary {
+    /// The target features are unknown.
+    Unknown,
+    /// Only match the specified features.
+    Features(BTreeSet<String>),
+    /// Match all features.
+    All,
+}
+
+impl TargetFeaturesSummary {
+    /// Creates a new summary of these target features.
+    pub fn new(target_features: &TargetFeatures<'_>) -> Self {
+        match target_features {
+            TargetFeatures::Unknown => TargetFeaturesSummary::Unknown,
+            TargetFeatures::Features(features) => TargetFeaturesSummary::Features(
+                features.iter().map(|feature| feature.to_string()).collect(),
+            ),
+            TargetFeatures::All => TargetFeaturesSummary::All,
+        }
+    }
+
+    /// Returns the target features this summary describes, borrowing from this summary.
+    pub fn to_target_features(&self) -> TargetFeatures<'_> {
+        match self {
+            TargetFeaturesSummary::Unknown => TargetFeatures::Unknown,
+            TargetFeaturesSummary::Features(features) => {
+                TargetFeatures::Features(features.iter().map(|feature| feature.as_str()).collect())
+            }
+            TargetFeaturesSummary::All => TargetFeatures::All,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_platform() {
+        let platform = Platform::new_heuristic("riscv64gc-unknown-freebsd", TargetFeatures::All);
+        let summary = PlatformSummary::new(&platform);
+        assert!(summary.heuristic);
+        assert_eq!(summary.target_features, TargetFeaturesSummary::All);
+        let roundtrip = summary.to_platform().unwrap();
+        assert!(roundtrip.is_heuristic());
+        assert_eq!(PlatformSummary::new(&roundtrip), summary);
+
+        let platform = Platform::builder("thumbv7em-summarytest")
+            .arch("arm")
+            .pointer_width(32)
+            .build()
+            .unwrap();
+        assert_eq!(
+            PlatformSummary::new(&platform).to_platform().unwrap_err(),
+            ParseError::UnknownTriple("thumbv7em-summarytest".to_string())
+        );
+    }
+}
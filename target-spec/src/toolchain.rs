@@ -0,0 +1,131 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::CustomTargetError;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::io;
+use std::process::{Command, ExitStatus};
+
+/// Returns the target spec JSON that an installed toolchain has for this triple, as printed by
+/// `rustc --print target-spec-json -Z unstable-options --target <triple>`.
+///
+/// If `toolchain` is specified, it's passed to rustc as `+<toolchain>`, which requires rustc to be
+/// the `rustup` proxy. rustc is taken from the `RUSTC` environment variable if it's set, as with
+/// Cargo. `-Z unstable-options` is only accepted by nightly toolchains.
+///
+/// This lets targets that are newer than `target-spec`'s builtin list be used, as long as their
+/// properties are known to `target-spec`. Use `Platform::from_toolchain` to create a platform
+/// from the result.
+pub fn target_spec_json(toolchain: Option<&str>, triple: &str) -> Result<String, ToolchainError> {
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| OsString::from("rustc"));
+    run(&rustc, toolchain, triple)
+}
+
+fn run(rustc: &OsStr, toolchain: Option<&str>, triple: &str) -> Result<String, ToolchainError> {
+    let output = command(rustc, toolchain, triple)
+        .output()
+        .map_err(ToolchainError::Exec)?;
+    if !output.status.success() {
+        return Err(ToolchainError::Failed {
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|err| ToolchainError::InvalidSpec(CustomTargetError::InvalidJson(err.to_string())))
+}
+
+fn command(rustc: &OsStr, toolchain: Option<&str>, triple: &str) -> Command {
+    let mut command = Command::new(rustc);
+    if let Some(toolchain) = toolchain {
+        command.arg(format!("+{}", toolchain));
+    }
+    command.args([
+        "--print",
+        "target-spec-json",
+        "-Z",
+        "unstable-options",
+        "--target",
+        triple,
+    ]);
+    command
+}
+
+/// An error that occurred while getting a target spec from an installed toolchain.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ToolchainError {
+    /// rustc couldn't be run.
+    Exec(io::Error),
+    /// rustc exited unsuccessfully. This happens if the toolchain isn't installed or isn't
+    /// nightly, or if it doesn't know about the triple.
+    Failed {
+        /// The exit status of rustc.
+        status: ExitStatus,
+        /// What rustc printed to standard error.
+        stderr: String,
+    },
+    /// The target spec printed by rustc couldn't be parsed.
+    InvalidSpec(CustomTargetError),
+}
+
+impl fmt::Display for ToolchainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolchainError::Exec(err) => write!(f, "failed to run rustc: {}", err),
+            ToolchainError::Failed { status, stderr } => {
+                write!(f, "rustc failed ({}):\n{}", status, stderr)
+            }
+            ToolchainError::InvalidSpec(err) => {
+                write!(f, "invalid target spec from rustc: {}", err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ToolchainError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ToolchainError::Exec(err) => Some(err),
+            ToolchainError::Failed { .. } => None,
+            ToolchainError::InvalidSpec(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        let command = command(
+            OsStr::new("rustc"),
+            Some("nightly"),
+            "x86_64-unknown-linux-gnu",
+        );
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            args,
+            vec![
+                "+nightly",
+                "--print",
+                "target-spec-json",
+                "-Z",
+                "unstable-options",
+                "--target",
+                "x86_64-unknown-linux-gnu"
+            ]
+        );
+
+        let err = run(
+            OsStr::new("/nonexistent/rustc"),
+            None,
+            "x86_64-unknown-linux-gnu",
+        )
+        .unwrap_err();
+        assert!(matches!(err, ToolchainError::Exec(_)), "{}", err);
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A rustc version, used to evaluate `cfg(version("1.50"))` predicates.
+///
+/// Set on a platform with `Platform::set_rustc_version`. A predicate matches if the platform's
+/// version is at least the version it mentions. As with rustc, pre-release suffixes like
+/// `-nightly` and `-beta.3` are ignored, so nightly compilers match the version they're a
+/// nightly of.
+///
+/// ## Examples
+///
+/// ```
+/// use target_spec::RustcVersion;
+///
+/// let version: RustcVersion = "1.50.0-nightly".parse().unwrap();
+/// assert_eq!(version, RustcVersion::new(1, 50, 0));
+/// assert_eq!(version.to_string(), "1.50.0");
+/// assert!(version > "1.49.2".parse().unwrap());
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RustcVersion {
+    major: u16,
+    minor: u16,
+    patch: u16,
+}
+
+impl RustcVersion {
+    /// Creates a new `RustcVersion`.
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parses the version in a `version()` predicate, which is either `major.minor` or
+    /// `major.minor.patch`, with no suffix.
+    pub(crate) fn parse_predicate(value: &str) -> Option<Self> {
+        let mut parts = value.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        let patch = match parts.next() {
+            Some(patch) => patch.parse().ok()?,
+            None => 0,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self::new(major, minor, patch))
+    }
+
+    /// Returns the major version.
+    pub fn major(&self) -> u16 {
+        self.major
+    }
+
+    /// Returns the minor version.
+    pub fn minor(&self) -> u16 {
+        self.minor
+    }
+
+    /// Returns the patch version.
+    pub fn patch(&self) -> u16 {
+        self.patch
+    }
+}
+
+impl FromStr for RustcVersion {
+    type Err = InvalidRustcVersion;
+
+    /// Parses a version such as `1.50.0`, as printed by `rustc --version`, ignoring any
+    /// pre-release suffix. The patch version may be left out.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let version = match input.find('-') {
+            Some(idx) => &input[..idx],
+            None => input,
+        };
+        Self::parse_predicate(version).ok_or(InvalidRustcVersion { _priv: () })
+    }
+}
+
+impl fmt::Display for RustcVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// An error returned while parsing a `RustcVersion`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvalidRustcVersion {
+    _priv: (),
+}
+
+impl fmt::Display for InvalidRustcVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid rustc version (expected major.minor or major.minor.patch)")
+    }
+}
+
+impl std::error::Error for InvalidRustcVersion {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        for (input, expected) in &[
+            ("1.50", Some(RustcVersion::new(1, 50, 0))),
+            ("1.50.2", Some(RustcVersion::new(1, 50, 2))),
+            ("1.50.2-beta.3", Some(RustcVersion::new(1, 50, 2))),
+            ("1", None),
+            ("1.50.2.1", None),
+            ("1.x", None),
+            ("", None),
+        ] {
+            assert_eq!(input.parse().ok(), *expected, "parsing {}", input);
+        }
+
+        assert_eq!(RustcVersion::parse_predicate("1.50-nightly"), None);
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright (c) The cargo-guppy Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Warnings about predicates that parse correctly but look like mistakes.
+
+use crate::abi::builtin_abis;
+use crate::atomic::HAS_ATOMIC_VALUES;
+use crate::SpecPredicate;
+use std::fmt;
+
+/// The `target_pointer_width` values of builtin targets.
+static POINTER_WIDTHS: &[&str] = &["16", "32", "64"];
+
+/// A predicate in a spec that's probably a mistake, even though the spec parsed.
+///
+/// Returned by `TargetSpec::warnings`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum SpecWarning<'a> {
+    /// A `target_abi`, `target_has_atomic` or `target_pointer_width` predicate has a value that
+    /// no builtin target has, so it only matches custom platforms, if any.
+    ///
+    /// Values of other keys like `target_os` are checked while parsing instead, and
+    /// `CfgParseError::suggestion` suggests a replacement for them.
+    UnknownValue {
+        /// The key, for example `target_abi`.
+        key: &'a str,
+        /// The unknown value.
+        value: &'a str,
+        /// The closest known value, if any is close enough to be a likely misspelling.
+        suggestion: Option<&'static str>,
+    },
+}
+
+impl<'a> fmt::Display for SpecWarning<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecWarning::UnknownValue {
+                key,
+                value,
+                suggestion,
+            } => {
+                write!(
+                    f,
+                    "{} = \"{}\" doesn't match any builtin target",
+                    key, value
+                )?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, " (did you mean \"{}\"?)", suggestion)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns warnings for these predicates, in the order they're written.
+pub(crate) fn spec_warnings<'a>(
+    predicates: impl IntoIterator<Item = SpecPredicate<'a>>,
+) -> Vec<SpecWarning<'a>> {
+    predicates
+        .into_iter()
+        .filter_map(|predicate| match predicate {
+            SpecPredicate::Target { key, value } => {
+                let known: Vec<&'static str> = match key {
+                    "target_abi" => builtin_abis().collect(),
+                    "target_has_atomic" => HAS_ATOMIC_VALUES.to_vec(),
+                    "target_pointer_width" => POINTER_WIDTHS.to_vec(),
+                    _ => return None,
+                };
+                // An empty ABI matches targets without one.
+                if known.contains(&value) || (key == "target_abi" && value.is_empty()) {
+                    return None;
+                }
+                Some(SpecWarning::UnknownValue {
+                    key,
+                    value,
+                    suggestion: suggest(value, known),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the candidate closest to `value`, if it's close enough to be a likely misspelling.
+///
+/// Ties are broken in favor of the candidate that sorts first, so that suggestions don't depend on
+/// the order of the candidates.
+pub(crate) fn suggest(
+    value: &str,
+    candidates: impl IntoIterator<Item = &'static str>,
+) -> Option<&'static str> {
+    let value = value.to_ascii_lowercase();
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(&value, candidate), candidate))
+        .filter(|(distance, candidate)| {
+            // Allow one edit for every three characters, and always at least one.
+            let max_distance = (value.len().max(candidate.len()) / 3).max(1);
+            *distance <= max_distance
+        })
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Returns the Levenshtein distance between these strings, counting characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + (a_char != *b_char) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TargetSpec;
+
+    #[test]
+    fn test_warnings() {
+        let spec: TargetSpec = "cfg(any(target_abi = \"eabhf\", target_has_atomic = \"pt\", \
+                                target_pointer_width = \"65\", target_abi = \"\", unix))"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            spec.warnings(),
+            vec![
+                SpecWarning::UnknownValue {
+                    key: "target_abi",
+                    value: "eabhf",
+                    suggestion: Some("eabihf"),
+                },
+                SpecWarning::UnknownValue {
+                    key: "target_has_atomic",
+                    value: "pt",
+                    suggestion: Some("ptr"),
+                },
+                SpecWarning::UnknownValue {
+                    key: "target_pointer_width",
+                    value: "65",
+                    suggestion: Some("64"),
+                },
+            ]
+        );
+        assert_eq!(
+            spec.warnings()[0].to_string(),
+            "target_abi = \"eabhf\" doesn't match any builtin target (did you mean \"eabihf\"?)"
+        );
+
+        let spec: TargetSpec = "cfg(target_abi = \"llvm\")".parse().unwrap();
+        assert_eq!(
+            spec.warnings(),
+            vec![SpecWarning::UnknownValue {
+                key: "target_abi",
+                value: "llvm",
+                suggestion: None,
+            }]
+        );
+        let spec: TargetSpec = "x86_64-unknown-linux-gnu".parse().unwrap();
+        assert!(spec.warnings().is_empty());
+
+        for target_info in cfg_expr::targets::ALL {
+            let width = target_info.pointer_width.to_string();
+            assert!(
+                POINTER_WIDTHS.contains(&width.as_str()),
+                "{}",
+                target_info.triple
+            );
+        }
+    }
+
+    #[test]
+    fn test_suggest() {
+        let oses = ["linux", "macos", "windows", "ios"];
+        assert_eq!(suggest("linx", oses.iter().copied()), Some("linux"));
+        assert_eq!(suggest("Linux", oses.iter().copied()), Some("linux"));
+        assert_eq!(suggest("macs", oses.iter().copied()), Some("macos"));
+        assert_eq!(suggest("mac", oses.iter().copied()), None);
+        assert_eq!(suggest("solaris", oses.iter().copied()), None);
+        assert_eq!(edit_distance("x86-64", "x86_64"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
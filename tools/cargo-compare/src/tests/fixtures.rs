@@ -46,6 +46,8 @@ impl Fixture {
         }
         let metadata_opts = CargoMetadataOptions {
             manifest_path: Some(workspace_dir.join("Cargo.toml")),
+            offline: false,
+            registry_mirror: None,
         };
         let graph = metadata_opts
             .make_command()
@@ -103,7 +105,7 @@ impl Fixture {
                     pf,
                     include_dev,
                     v2,
-                    target_platform: target_platform.map(|s| s.to_string()),
+                    target_platform,
                     metadata_opts: metadata_opts.clone(),
                 },
             )